@@ -0,0 +1,28 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use broot::{
+    file_sizes::{self, FileSize},
+    fixtures::{Fixture, FixtureSpec},
+    task_sync::Dam,
+};
+
+// run with `cargo bench --features fixtures --bench dir_sizes`
+fn dir_size_benchmark(c: &mut Criterion) {
+    let fixture = Fixture::build(&FixtureSpec {
+        depth: 4,
+        fan_out: 4,
+        files_per_dir: 20,
+        ..FixtureSpec::default()
+    })
+    .unwrap();
+    let task = format!("FileSize::from_dir({} files)", fixture.nb_files);
+    c.bench_function(&task, |b| {
+        b.iter(|| {
+            file_sizes::clear_cache();
+            black_box(FileSize::from_dir(fixture.root(), &Dam::unlimited()));
+        });
+    });
+}
+
+criterion_group!(benches, dir_size_benchmark);
+criterion_main!(benches);
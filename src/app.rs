@@ -11,70 +11,131 @@
 use {
     crate::{
         app_context::AppContext,
+        app_stack::StateStack,
         app_state::{AppState, AppStateCmdResult},
         browser_states::BrowserState,
         command_parsing::parse_command_sequence,
-        commands::Command,
+        commands::{Action, Command},
+        deep_dates,
         errors::ProgramError,
+        exec_queue,
         external::Launchable,
         file_sizes,
+        fs_watch::FsWatcher,
         git_status_computer,
-        io::WriteCleanup,
-        screens::Screen,
+        input_history::InputHistory,
+        line_counts,
+        nested_launch::ControlSocket,
+        outcome_channel::Outcome,
+        path_display,
+        queue_states::QueueState,
+        remote,
+        screens::{self, Screen},
         skin::Skin,
+        start_states::StartState,
         status::Status,
-        task_sync::Dam,
-    },
-    crossterm::{
-        self, cursor,
-        event::{DisableMouseCapture, EnableMouseCapture},
-        terminal::{EnterAlternateScreen, LeaveAlternateScreen},
-        QueueableCommand,
+        task_sync::{Dam, DamWake},
+        tree_export::{self, OutFormat},
+        usage_stats,
+        verb_store::PrefixSearchResult,
     },
+    crossbeam::channel,
     minimad::Composite,
-    std::io::Write,
+    notify::RecursiveMode,
+    std::{
+        collections::HashSet,
+        error::Error,
+        fs::OpenOptions,
+        io::{self, Write},
+        iter,
+        path::PathBuf,
+        time::{Duration, Instant},
+    },
     termimad::EventSource,
 };
 
-/// Helper function for type inference: queue a Command but return Result<()>
-#[inline]
-fn just_queue(
-    mut writer: impl Write,
-    command: impl crossterm::Command,
-) -> crossterm::Result<()> {
-    writer.queue(command).map(move |_| ())
-}
+/// the minimal duration between two redraws while tasks progress,
+/// that is at most 25 frames per second
+const MIN_FRAME_INTERVAL: Duration = Duration::from_millis(40);
 
 pub struct App {
-    states: Vec<Box<dyn AppState>>, // stack: the last one is current
+    states: StateStack,
     quitting: bool,
     launch_at_end: Option<Launchable>, // what must be launched after end
+    title: Option<String>,             // last title given to the terminal
+    watcher: Option<FsWatcher>,        // only when the current tree is in watch mode
+    last_selection: Option<PathBuf>,   // the last selection told to the outcome channel
+    last_root: Option<PathBuf>,        // the last root written to the --outroots file
+    watch_paused: bool,                // true when the watch mode was paused for inactivity
+    input_history: InputHistory,       // the validated patterns and verb invocations
+    input_before_event: Command,       // the input before the last user event
 }
 
 impl App {
     pub fn new() -> App {
         App {
-            states: Vec::new(),
+            states: StateStack::default(),
             quitting: false,
             launch_at_end: None,
+            title: None,
+            watcher: None,
+            last_selection: None,
+            last_root: None,
+            watch_paused: false,
+            input_history: InputHistory::default(),
+            input_before_event: Command::new(),
         }
     }
 
     pub fn push(&mut self, new_state: Box<dyn AppState>) {
-        self.states.push(new_state);
+        self.states.push(new_state, None);
+    }
+
+    /// remove the current state, and return the command to use in
+    /// the one below: the input typed before leaving it, if any, or
+    /// the one given by its refresh
+    fn pop(&mut self, screen: &mut Screen, con: &AppContext) -> Command {
+        let kept_input = self.states.pop();
+        let cmd = self.mut_state().refresh(screen, con);
+        match kept_input {
+            Some(kept_input) => {
+                debug!("restoring the input {:?}", &kept_input.raw);
+                kept_input
+            }
+            None => cmd,
+        }
     }
 
     fn mut_state(&mut self) -> &mut dyn AppState {
-        self.states
-            .last_mut()
-            .expect("No path has been pushed")
-            .as_mut()
+        self.states.current_mut()
     }
     fn state(&self) -> &dyn AppState {
-        self.states
-            .last()
-            .expect("No path has been pushed")
-            .as_ref()
+        self.states.current()
+    }
+
+    /// write the status of the current state, followed, when several
+    /// states are stacked, by their breadcrumb
+    fn write_status(
+        &self,
+        w: &mut impl Write,
+        cmd: &Command,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        screen.set_breadcrumb(self.states.breadcrumb());
+        write_state_status(self.state(), w, cmd, screen, con)
+    }
+
+    /// write the flags of the current state, the input field getting
+    /// back its default width, which the state may reduce
+    fn write_flags(
+        &self,
+        w: &mut impl Write,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        screen.set_flags_width(screens::FLAGS_AREA_WIDTH);
+        self.state().write_flags(w, screen, con)
     }
 
     /// execute all the pending tasks until there's none remaining or
@@ -87,15 +148,227 @@ impl App {
         con: &AppContext,
         dam: &mut Dam,
     ) -> Result<(), ProgramError> {
-        let state = self.mut_state();
-        while state.has_pending_task() & !dam.has_event() {
-            state.do_pending_task(screen, dam);
-            state.display(w, screen, con)?;
-            state.write_status(w, cmd, &screen, con)?;
+        let mut last_draw: Option<Instant> = None;
+        let mut undrawn_progress = false;
+        while self.state().has_pending_task() & !dam.has_event() {
+            self.mut_state().do_pending_task(screen, con, dam);
+            // the screen updates are coalesced, as drawing after every
+            // small step would slow down long computations
+            if last_draw.map_or(true, |instant| instant.elapsed() >= MIN_FRAME_INTERVAL) {
+                self.mut_state().display(w, screen, con)?;
+                self.write_status(w, cmd, screen, con)?;
+                last_draw = Some(Instant::now());
+                undrawn_progress = false;
+            } else {
+                undrawn_progress = true;
+            }
+        }
+        if undrawn_progress {
+            self.mut_state().display(w, screen, con)?;
+            self.write_status(w, cmd, screen, con)?;
         }
         Ok(())
     }
 
+    /// make the watcher follow the directories of the current tree,
+    /// when it's in watch mode and the watch isn't paused, or the
+    /// whole directory where a file is awaited
+    fn update_watcher(&mut self) {
+        let state = self.state();
+        let mut mode = RecursiveMode::NonRecursive;
+        let dirs: HashSet<PathBuf> = match (state.awaited_file(), state.exported_tree()) {
+            (Some(awaited), _) => {
+                mode = RecursiveMode::Recursive;
+                iter::once(awaited.dir.clone()).collect()
+            }
+            (None, Some(tree)) if tree.options.watch && !self.watch_paused => tree
+                .lines
+                .iter()
+                .filter(|line| line.is_dir() && !line.is_remote()) // no notification from servers
+                .map(|line| line.path.clone())
+                .collect(),
+            _ => HashSet::new(),
+        };
+        if dirs.is_empty() {
+            self.watcher = None;
+            return;
+        }
+        if self.watcher.is_none() {
+            match FsWatcher::new() {
+                Ok(watcher) => {
+                    self.watcher = Some(watcher);
+                }
+                Err(e) => {
+                    warn!("failed to start watching: {:?}", e);
+                    return;
+                }
+            }
+        }
+        if let Some(watcher) = &mut self.watcher {
+            watcher.watch(dirs, mode);
+        }
+    }
+
+    /// refresh the current state after a change in the watched
+    /// directories. The command is kept, so that the pattern or
+    /// the verb being typed isn't lost
+    fn refresh_on_fs_change(
+        &mut self,
+        w: &mut impl Write,
+        cmd: &Command,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        let (changes, created): (Vec<Option<PathBuf>>, Vec<PathBuf>) = match &self.watcher {
+            Some(watcher) => (watcher.gather_changes(), watcher.created.try_iter().collect()),
+            None => (Vec::new(), Vec::new()),
+        };
+        forget_changes(&changes);
+        // while a file is awaited, the other changes are ignored
+        if let Some(awaited) = self.state().awaited_file() {
+            if !created.iter().any(|path| awaited.matches(path)) {
+                return Ok(());
+            }
+        }
+        debug!("refreshing after a change in the watched directories");
+        self.mut_state().refresh(screen, con);
+        let found = if created.is_empty() {
+            None
+        } else {
+            self.mut_state().on_created_files(&created, screen, con)
+        };
+        self.mut_state().display(w, screen, con)?;
+        match found {
+            Some(text) => {
+                // the bell tells the user, who may be in another window
+                write!(w, "\x07")?;
+                Status::from_message(Composite::from_inline(&text)).display(w, screen)?;
+            }
+            None => self.write_status(w, cmd, screen, con)?,
+        }
+        self.write_flags(w, screen, con)?;
+        screen.display_input(w, cmd, con)?;
+        Ok(())
+    }
+
+    /// open a tree on the path sent by a broot launched from this
+    /// one, the path being selected when it's a file
+    fn open_sent_path(
+        &mut self,
+        w: &mut impl Write,
+        path: PathBuf,
+        cmd: &Command,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        let (root, selection) = if path.is_dir() {
+            (path, None)
+        } else {
+            match path.parent() {
+                Some(parent) => (parent.to_path_buf(), Some(path.clone())),
+                None => {
+                    return Ok(());
+                }
+            }
+        };
+        let tree_options = con.launch_args.tree_options.clone();
+        match BrowserState::new(root, tree_options, screen, con, &Dam::unlimited()) {
+            Ok(Some(mut state)) => {
+                if let Some(selection) = selection {
+                    if state.tree.try_select_path(&selection) {
+                        state.tree.make_selection_visible(BrowserState::page_height(screen));
+                    }
+                }
+                self.push(Box::new(state));
+            }
+            Ok(None) => {
+                return Ok(());
+            }
+            Err(e) => {
+                let text = format!("can't open the path sent by the nested broot: {}", e);
+                Status::from_error(Composite::from_inline(&text)).display(w, screen)?;
+                return Ok(());
+            }
+        }
+        self.mut_state().display(w, screen, con)?;
+        self.write_status(w, cmd, screen, con)?;
+        self.write_flags(w, screen, con)?;
+        screen.display_input(w, cmd, con)?;
+        self.update_title(w, con)?;
+        self.send_selection_change(con);
+        Ok(())
+    }
+
+    /// set the title of the terminal to the root of the current
+    /// tree, if it changed and the configuration asks for it
+    fn update_title(&mut self, w: &mut impl Write, con: &AppContext) -> Result<(), ProgramError> {
+        if !con.launch_args.set_terminal_title {
+            return Ok(());
+        }
+        let title = match self.state().exported_tree() {
+            Some(tree) => format!("broot {}", tree.root().to_string_lossy()),
+            None => "broot".to_string(),
+        };
+        if self.title.as_ref() != Some(&title) {
+            screens::write_title(w, &title)?;
+            self.title = Some(title);
+        }
+        Ok(())
+    }
+
+    /// return the path selected in the current state, if it has a tree
+    fn selected_path(&self) -> Option<PathBuf> {
+        self.state()
+            .exported_tree()
+            .map(|tree| tree.selected_line().path.clone())
+    }
+
+    /// tell the outcome channel when the selection changed
+    fn send_selection_change(&mut self, con: &AppContext) {
+        if con.outcome_channel.is_none() {
+            return;
+        }
+        let selection = self.selected_path();
+        if selection != self.last_selection {
+            if let Some(path) = &selection {
+                con.send_outcome(Outcome::Selection(path));
+            }
+            self.last_selection = selection;
+        }
+    }
+
+    /// append the root to the file given with `--outroots` when it
+    /// changed, so that the shell can record all the visited directories
+    fn export_visited_root(&mut self, con: &AppContext) {
+        let export_path = match &con.launch_args.roots_export_path {
+            Some(export_path) => export_path,
+            None => return,
+        };
+        let root = match self.state().exported_tree() {
+            Some(tree) if !tree.lines[0].is_remote() && !tree.lines[0].in_archive => {
+                tree.root().clone()
+            }
+            _ => return,
+        };
+        if self.last_root.as_ref() == Some(&root) {
+            return;
+        }
+        let written = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(export_path)
+            .and_then(|mut f| writeln!(f, "{}", root.to_string_lossy()));
+        if let Err(e) = written {
+            warn!("failed to export the root {:?}: {:?}", root, e);
+        }
+        self.last_root = Some(root);
+    }
+
+    /// tell the outcome channel the application quits, with
+    /// the final selection
+    fn send_quit(&self, con: &AppContext) {
+        con.send_outcome(Outcome::Quit(self.selected_path().as_deref()));
+    }
 
     /// apply a command, and returns a command, which may be the same (modified or not)
     ///  or a new one.
@@ -108,8 +381,38 @@ impl App {
         con: &AppContext,
     ) -> Result<Command, ProgramError> {
         debug!("action: {:?}", &cmd.action);
+        // the verb and the selection it applies to, for the outcome channel
+        let verb_name = match &cmd.action {
+            Action::VerbInvocate(invocation) => match con.verb_store.search(&invocation.name) {
+                PrefixSearchResult::Match(verb) => Some(verb.invocation.name.clone()),
+                _ => None,
+            },
+            Action::VerbIndex(idx) => Some(con.verb_store.verbs[*idx].invocation.name.clone()),
+            _ => None,
+        };
+        if let Some(name) = verb_name.as_ref().filter(|name| !name.is_empty()) {
+            usage_stats::count_verb(name);
+        }
+        if let Action::OpenSelection | Action::AltOpenSelection | Action::VerbInvocate(_) =
+            &cmd.action
+        {
+            if let Some(mode) = cmd.pattern_mode() {
+                usage_stats::count_pattern(mode);
+            }
+        }
+        let verb_selection = self.selected_path();
+        let kept_input = self.input_before_event.kept_input(&cmd.action);
         let mut error: Option<String> = None;
-        match self.mut_state().apply(&mut cmd, screen, con)? {
+        let mut message: Option<String> = None;
+        let result = match cmd.action.key() {
+            // a state is only given the keys it declares
+            Some(key) if !self.state().help().declares_key(key) => {
+                debug!("the {} doesn't declare the {:?} key", self.state().help().name, key);
+                AppStateCmdResult::Keep
+            }
+            _ => self.mut_state().apply(&mut cmd, screen, con)?,
+        };
+        match result {
             AppStateCmdResult::Quit => {
                 debug!("cmd result quit");
                 self.quitting = true;
@@ -119,7 +422,12 @@ impl App {
                 self.quitting = true;
             }
             AppStateCmdResult::NewState(boxed_state, new_cmd) => {
-                self.push(boxed_state);
+                // what was typed is restored when coming back
+                self.states.push(boxed_state, kept_input);
+                cmd = new_cmd;
+            }
+            AppStateCmdResult::ReplaceState(boxed_state, new_cmd) => {
+                self.states.replace(boxed_state);
                 cmd = new_cmd;
             }
             AppStateCmdResult::RefreshState { clear_cache } => {
@@ -128,13 +436,17 @@ impl App {
                 }
                 cmd = self.mut_state().refresh(screen, con);
             }
+            AppStateCmdResult::RefreshWithMessage(txt) => {
+                clear_caches();
+                cmd = self.mut_state().refresh(screen, con);
+                message = Some(txt);
+            }
             AppStateCmdResult::PopState => {
                 if self.states.len() == 1 {
                     debug!("quitting on last pop state");
                     self.quitting = true;
                 } else {
-                    self.states.pop();
-                    cmd = self.mut_state().refresh(screen, con);
+                    cmd = self.pop(screen, con);
                 }
             }
             AppStateCmdResult::PopStateAndReapply => {
@@ -150,16 +462,75 @@ impl App {
             AppStateCmdResult::DisplayError(txt) => {
                 error = Some(txt);
             }
+            AppStateCmdResult::DisplayFailure(mut err) => {
+                if let (ProgramError::FileOp { source }, Some(name)) = (&mut err, &verb_name) {
+                    source.verb.get_or_insert_with(|| name.clone());
+                }
+                log_failure(&err);
+                error = Some(err.to_string());
+            }
             _ => {}
         }
+        if let (Some(name), Some(path), None) = (&verb_name, &verb_selection, &error) {
+            con.send_outcome(Outcome::Verb { name, path });
+        }
+        if !self.quitting {
+            self.send_selection_change(con);
+            self.export_visited_root(con);
+        }
+        // the messages are written with the breadcrumb of the new stack
+        screen.set_breadcrumb(self.states.breadcrumb());
         self.mut_state().display(w, screen, con)?;
-        match error {
-            Some(text) => Status::from_error(Composite::from_inline(&text)).display(w, screen)?,
-            None => self.state().write_status(w, &cmd, screen, con)?,
+        match (error, message) {
+            (Some(text), _) => {
+                Status::from_error(Composite::from_inline(&text)).display(w, screen)?
+            }
+            (None, Some(text)) => {
+                Status::from_message(Composite::from_inline(&text)).display(w, screen)?
+            }
+            (None, None) => self.write_status(w, &cmd, screen, con)?,
         }
         screen.input_field.set_content(&cmd.raw);
-        screen.input_field.display_on(w)?;
-        self.state().write_flags(w, screen, con)?;
+        self.write_flags(w, screen, con)?;
+        screen.display_input(w, &cmd, con)?;
+        Ok(cmd)
+    }
+
+    /// apply the commands given with --cmd, as if they were typed.
+    ///
+    /// Each command is applied only once the state produced by the
+    /// previous one has settled (searches and other pending tasks are
+    /// done): they're not interrupted by user events, so that a key hit
+    /// during a long search doesn't make the next command apply to an
+    /// incomplete tree. The tasks of the last command are left to the
+    /// caller.
+    ///
+    /// The pattern given with `--pattern` is typed before the commands.
+    fn apply_launch_commands(
+        &mut self,
+        w: &mut impl Write,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<Command, ProgramError> {
+        let mut cmd = Command::new();
+        let mut arg_cmds = Vec::new();
+        if let Some(pattern) = &con.launch_args.pattern {
+            arg_cmds.push(Command::from_raw(pattern.to_string(), false));
+        }
+        if let Some(unparsed_commands) = &con.launch_args.commands {
+            arg_cmds.extend(parse_command_sequence(unparsed_commands, con)?);
+        }
+        let mut dam = Dam::unlimited();
+        for (idx, arg_cmd) in arg_cmds.into_iter().enumerate() {
+            if idx > 0 {
+                self.do_pending_tasks(w, &cmd, screen, con, &mut dam)?;
+            }
+            debug!("applying launch command {:?}", &arg_cmd.raw);
+            cmd = self.apply_command(w, arg_cmd, screen, con)?;
+            if self.quitting {
+                break;
+            }
+        }
         Ok(cmd)
     }
 
@@ -170,93 +541,151 @@ impl App {
         con: &AppContext,
         skin: Skin,
     ) -> Result<Option<Launchable>, ProgramError> {
-        // Ensure the buffer is flushed before we return
-        let writer = WriteCleanup::new(writer, |w| w.flush());
-
-        // Push some terminal state changes, ensuring they're reverted when we
-        // end the program.
-        let writer = WriteCleanup::build(
-            writer,
-            |w| just_queue(w, EnterAlternateScreen),
-            |w| just_queue(w, LeaveAlternateScreen),
-        )?;
-        let writer = WriteCleanup::build(
-            writer,
-            |w| just_queue(w, cursor::Hide),
-            |w| just_queue(w, cursor::Show),
-        )?;
+        // the terminal state changes are reverted when the writer is dropped
+        let mut writer = screens::terminal_writer(writer, con)?;
 
         debug!("we're on screen");
         let mut screen = Screen::new(con, skin)?;
 
-        let mut writer = WriteCleanup::build(
-            writer,
-            |w| just_queue(w, EnableMouseCapture),
-            |w| just_queue(w, DisableMouseCapture),
-        )?;
-
         // we listen for events in a separate thread so that we can go on listening
         // when a long search is running, and interrupt it if needed
         let event_source = EventSource::new()?;
         let rx_events = event_source.receiver();
         let mut dam = Dam::from(rx_events);
+        // the broots launched from the verbs may send us the path
+        // they were launched on (see the nested_launch conf entry)
+        let control_socket = match ControlSocket::bind() {
+            Ok(control_socket) => Some(control_socket),
+            Err(e) => {
+                info!("no control socket: {}", e);
+                None
+            }
+        };
+        let no_control = channel::never();
+        let sent_paths = control_socket.as_ref().map_or(&no_control, |s| &s.paths);
+        self.input_history = InputHistory::new(con.launch_args.persist_input_history);
+        usage_stats::init(con.launch_args.usage_stats);
+        path_display::init(con.launch_args.path_display);
 
-        self.push(Box::new(
-            BrowserState::new(
-                con.launch_args.root.clone(),
-                con.launch_args.tree_options.clone(),
-                &screen,
-                &Dam::unlimited(),
-            )?
-            .expect("Failed to create BrowserState"),
-        ));
-
-        let mut cmd = Command::new();
+        if con.launch_args.start_screen {
+            self.push(Box::new(StartState::new(con)));
+        } else {
+            self.push(Box::new(initial_browser_state(&screen, con)?));
+            if con.launch_args.persist_queue
+                && exec_queue::persist_for(&con.launch_args.root) > 0
+                && con.launch_args.commands.is_none()
+            {
+                // the commands queued in this root during a previous
+                // session are proposed before the tree
+                self.push(Box::new(QueueState::restored()));
+            }
+        }
 
         // if some commands were passed to the application
         //  we execute them before even starting listening for events
-        if let Some(unparsed_commands) = &con.launch_args.commands {
-            for arg_cmd in parse_command_sequence(unparsed_commands, con)? {
-                cmd = self.apply_command(&mut writer, arg_cmd, &mut screen, con)?;
-                self.do_pending_tasks(
-                    &mut writer,
-                    &cmd,
-                    &mut screen,
-                    con,
-                    &mut dam,
-                )?;
-                if self.quitting {
-                    return Ok(self.launch_at_end.take());
-                }
-            }
+        let mut cmd = self.apply_launch_commands(&mut writer, &mut screen, con)?;
+        if self.quitting {
+            self.send_quit(con);
+            return Ok(self.launch_at_end.take());
         }
 
-        let state = self.mut_state();
-        state.display(&mut writer, &screen, con)?;
-        state.write_status(&mut writer, &cmd, &screen, con)?;
-        state.write_flags(&mut writer, &mut screen, con)?;
+        self.mut_state().display(&mut writer, &screen, con)?;
+        self.write_status(&mut writer, &cmd, &mut screen, con)?;
+        self.write_flags(&mut writer, &mut screen, con)?;
 
-        screen.input_field.display_on(&mut writer)?;
+        screen.display_input(&mut writer, &cmd, con)?;
+        self.update_title(&mut writer, con)?;
+        self.send_selection_change(con);
+        self.export_visited_root(con);
+        let no_fs_change = channel::never();
+        let mut last_event = Instant::now();
         loop {
             if !self.quitting {
                 self.do_pending_tasks(&mut writer, &cmd, &mut screen, con, &mut dam)?;
             }
-            let event = match dam.next_event() {
-                Some(event) => event,
-                None => {
+            self.update_watcher();
+            let fs_changes = self.watcher.as_ref().map_or(&no_fs_change, |w| &w.changes);
+            // the watch is paused when the user seems away, unless
+            // a file is awaited
+            let awaiting = self.state().awaited_file().is_some();
+            let idle_timeout = match (&self.watcher, con.launch_args.watch_idle_pause) {
+                (Some(_), Some(pause)) if !awaiting => {
+                    Some(pause.checked_sub(last_event.elapsed()).unwrap_or_default())
+                }
+                _ => None,
+            };
+            let event = match dam.next_wake(fs_changes, sent_paths, idle_timeout) {
+                DamWake::Event(event) => event,
+                DamWake::Control(path) => {
+                    self.open_sent_path(&mut writer, path, &cmd, &mut screen, con)?;
+                    continue;
+                }
+                DamWake::Other(()) => {
+                    self.refresh_on_fs_change(&mut writer, &cmd, &mut screen, con)?;
+                    continue;
+                }
+                DamWake::Timeout => {
+                    debug!("pausing the watch after {:?} without event", last_event.elapsed());
+                    self.watch_paused = true;
+                    continue;
+                }
+                DamWake::Dead => {
                     // this is how we quit the application,
                     // when the input thread is properly closed
                     break;
                 }
             };
-            cmd.add_event(&event, &mut screen.input_field, con, self.state());
+            last_event = Instant::now();
+            if self.watch_paused {
+                // changes may have been missed while the watch was paused
+                self.watch_paused = false;
+                clear_caches();
+                self.refresh_on_fs_change(&mut writer, &cmd, &mut screen, con)?;
+            }
+            self.input_before_event = cmd.clone();
+            // the state is borrowed from its field, apart from the history
+            let state = self.states.current();
+            cmd.add_event(&event, &mut screen.input_field, &mut self.input_history, con, state);
             debug!("command after add_event: {:?}", &cmd);
             cmd = self.apply_command(&mut writer, cmd, &mut screen, con)?;
+            self.update_title(&mut writer, con)?;
             event_source.unblock(self.quitting);
         }
 
+        self.send_quit(con);
+        if let Err(e) = self.input_history.save() {
+            warn!("failed to save the input history: {:?}", e);
+        }
+        if let Err(e) = usage_stats::save() {
+            warn!("failed to save the usage stats: {:?}", e);
+        }
         Ok(self.launch_at_end.take())
     }
+
+    /// run the launch commands without interface nor event listening,
+    /// then return either what those commands asked to launch or a
+    /// printer of the resulting tree in the required format
+    pub fn run_headless(
+        mut self,
+        con: &AppContext,
+        skin: Skin,
+        out_format: OutFormat,
+    ) -> Result<Option<Launchable>, ProgramError> {
+        // nothing is drawn: all the display goes to the sink
+        let mut writer = io::sink();
+        let mut screen = Screen::new(con, skin)?;
+        let mut dam = Dam::unlimited();
+        self.push(Box::new(initial_browser_state(&screen, con)?));
+        let cmd = self.apply_launch_commands(&mut writer, &mut screen, con)?;
+        if self.quitting {
+            return Ok(self.launch_at_end.take());
+        }
+        self.do_pending_tasks(&mut writer, &cmd, &mut screen, con, &mut dam)?;
+        match self.state().exported_tree() {
+            Some(tree) => Ok(Some(Launchable::printer(tree_export::export(tree, out_format)))),
+            None => Err(ProgramError::NothingToExport),
+        }
+    }
 }
 
 impl Drop for App {
@@ -265,10 +694,97 @@ impl Drop for App {
     }
 }
 
-/// clear the file sizes and git stats cache.
+/// build the state of the root given at launch, selecting the file
+/// broot was launched on, if any
+fn initial_browser_state(screen: &Screen, con: &AppContext) -> Result<BrowserState, ProgramError> {
+    let mut state = BrowserState::new(
+        con.launch_args.root.clone(),
+        con.launch_args.tree_options.clone(),
+        screen,
+        con,
+        &Dam::unlimited(),
+    )?
+    .expect("Failed to create BrowserState");
+    if let Some(path) = &con.launch_args.selection {
+        if state.tree.try_select_path(path) {
+            state.tree.make_selection_visible(BrowserState::page_height(screen));
+        } else {
+            info!("launch file {:?} isn't in the initial tree", path);
+        }
+    }
+    Ok(state)
+}
+
+/// write the status of the state or, when the arguments of a verb
+/// are being asked, the one of the prompt
+fn write_state_status(
+    state: &dyn AppState,
+    w: &mut impl Write,
+    cmd: &Command,
+    screen: &Screen,
+    con: &AppContext,
+) -> Result<(), ProgramError> {
+    let prompt = match &cmd.prompt {
+        Some(prompt) => prompt,
+        None => {
+            return state.write_status(w, cmd, screen, con);
+        }
+    };
+    let verb = &con.verb_store.verbs[prompt.verb_index];
+    let arg = prompt.current_arg();
+    let label = arg.label.as_ref().unwrap_or(&arg.name);
+    let mut text = format!("**{}** needs *{}*", &verb.invocation.name, label);
+    if prompt.args.len() > 1 {
+        text.push_str(&format!(
+            " (argument {} of {})",
+            prompt.values.len() + 1,
+            prompt.args.len(),
+        ));
+    }
+    text.push_str(": type it then *enter*, or *esc* to cancel");
+    Status::from_message(Composite::from_inline(&text)).display(w, screen)
+}
+
+/// log the error displayed to the user with the chain of its
+/// sources, which tell more than the status line
+fn log_failure(err: &ProgramError) {
+    warn!("failure: {}", err);
+    let mut source = err.source();
+    while let Some(err) = source {
+        warn!("   caused by: {:?}", err);
+        source = err.source();
+    }
+}
+
+/// clear the file sizes, line counts and git stats cache.
 /// This should be done on Refresh actions and after any external
 /// command.
-fn clear_caches() {
+pub fn clear_caches() {
     file_sizes::clear_cache();
+    deep_dates::clear_cache();
+    line_counts::clear_cache();
     git_status_computer::clear_cache();
+    remote::clear_cache();
+}
+
+/// forget what's cached about the changed paths and the directories
+/// containing them, or everything when the changes are unknown
+fn forget_changes(changes: &[Option<PathBuf>]) {
+    for change in changes {
+        match change {
+            Some(path) => {
+                file_sizes::forget_path(path);
+                deep_dates::forget_path(path);
+                line_counts::forget_path(path);
+            }
+            None => {
+                clear_caches();
+                return;
+            }
+        }
+    }
+    if !changes.is_empty() {
+        // the statuses are computed for the whole repository
+        git_status_computer::clear_cache();
+    }
 }
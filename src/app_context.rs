@@ -1,29 +1,76 @@
-use crate::{
-    cli::AppLaunchArgs,
-    conf::Conf,
-    verb_store::VerbStore,
+use {
+    crate::{
+        cli::AppLaunchArgs,
+        conf::Conf,
+        conf_doc::ConfSetting,
+        icons::Icons,
+        organize::OrganizeRule,
+        outcome_channel::{Outcome, OutcomeChannel},
+        profiles::Profile,
+        special_paths::SpecialPath,
+        trash::TrashCommand,
+        verb_store::VerbStore,
+    },
+    std::collections::HashMap,
+    termimad::CompoundStyle,
 };
 
 /// The immutable container that can be passed around
 /// to provide the configuration things
 pub struct AppContext {
     pub config_path: String,
+    pub project_conf_path: Option<String>, // the project_conf of the launch args, for display
     pub launch_args: AppLaunchArgs,
     pub verb_store: VerbStore,
+    pub special_paths: Vec<SpecialPath>, // compiled from the special-paths conf section
+    pub trash_commands: Vec<TrashCommand>, // compiled from the trash-commands conf section
+    pub organize_rules: Vec<OrganizeRule>, // from the organize-rules conf section
+    pub profiles: Vec<Profile>, // from the profiles conf section
+    pub skin_entries: HashMap<String, CompoundStyle>, // the [skin] conf section, applied over presets
+    pub icons: Icons, // written before names when the show_icons tree option is set
+    pub outcome_channel: Option<OutcomeChannel>, // only when launched with --listen
+    pub conf_settings: Vec<ConfSetting>, // the keys set by the configuration files
 }
 
 impl AppContext {
     pub fn from(
         launch_args: AppLaunchArgs,
-        verb_store: VerbStore
+        verb_store: VerbStore,
+        special_paths: Vec<SpecialPath>,
+        trash_commands: Vec<TrashCommand>,
+        organize_rules: Vec<OrganizeRule>,
+        profiles: Vec<Profile>,
+        skin_entries: HashMap<String, CompoundStyle>,
+        icons: Icons,
+        outcome_channel: Option<OutcomeChannel>,
+        conf_settings: Vec<ConfSetting>,
     ) -> Self {
         let config_path = Conf::default_location()
             .to_string_lossy()
             .to_string();
+        let project_conf_path = launch_args
+            .project_conf
+            .as_ref()
+            .map(|path| path.to_string_lossy().to_string());
         Self {
             config_path,
+            project_conf_path,
             launch_args,
             verb_store,
+            special_paths,
+            trash_commands,
+            organize_rules,
+            profiles,
+            skin_entries,
+            icons,
+            outcome_channel,
+            conf_settings,
+        }
+    }
+    /// tell the clients of the outcome channel, if there's one
+    pub fn send_outcome(&self, outcome: Outcome<'_>) {
+        if let Some(outcome_channel) = &self.outcome_channel {
+            outcome_channel.send(outcome);
         }
     }
 }
@@ -0,0 +1,129 @@
+//! the stack of the application states.
+//!
+//! The current state is the top one. Leaving it (with *esc* or
+//! `:back`, once it has nothing to clear or interrupt) displays
+//! again the state below, with the input which was typed in it.
+//! The names of the stacked states make the breadcrumb displayed
+//! on the status line (e.g. `tree › git log › help`).
+
+use crate::{app_state::AppState, commands::Command};
+
+/// the separator of the states in the breadcrumb
+pub const BREADCRUMB_SEPARATOR: &str = " › ";
+
+/// a state and what's needed to come back to it
+struct StackedState {
+    state: Box<dyn AppState>,
+    kept_input: Option<Command>, // the input to restore when coming back
+}
+
+#[derive(Default)]
+pub struct StateStack {
+    entries: Vec<StackedState>, // the last one is current
+}
+
+impl StateStack {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// push a state above the current one. The input typed in the
+    /// current one, if given, is restored when the new state is left
+    pub fn push(&mut self, state: Box<dyn AppState>, kept_input: Option<Command>) {
+        if let Some(current) = self.entries.last_mut() {
+            current.kept_input = kept_input;
+        }
+        self.entries.push(StackedState {
+            state,
+            kept_input: None,
+        });
+    }
+
+    /// replace the current state, the new one being left to the
+    /// state below it
+    pub fn replace(&mut self, state: Box<dyn AppState>) {
+        self.entries.pop();
+        self.entries.push(StackedState {
+            state,
+            kept_input: None,
+        });
+    }
+
+    /// remove the current state, and return the input kept for the
+    /// one below, which becomes current
+    pub fn pop(&mut self) -> Option<Command> {
+        self.entries.pop();
+        self.entries
+            .last_mut()
+            .and_then(|entry| entry.kept_input.take())
+    }
+
+    pub fn current(&self) -> &dyn AppState {
+        self.entries
+            .last()
+            .expect("No path has been pushed")
+            .state
+            .as_ref()
+    }
+
+    pub fn current_mut(&mut self) -> &mut dyn AppState {
+        self.entries
+            .last_mut()
+            .expect("No path has been pushed")
+            .state
+            .as_mut()
+    }
+
+    /// the names of the stacked states, the current one last,
+    /// or nothing when there's only one kind of state
+    pub fn breadcrumb(&self) -> Option<String> {
+        breadcrumb_of(
+            self.entries
+                .iter()
+                .map(|entry| short_name(entry.state.help().name))
+                .collect(),
+        )
+    }
+}
+
+/// join the names of the states, the consecutive states of the same
+/// kind (like the trees of the successive focuses) being named once
+fn breadcrumb_of(mut names: Vec<&str>) -> Option<String> {
+    names.dedup();
+    if names.len() < 2 {
+        None
+    } else {
+        Some(names.join(BREADCRUMB_SEPARATOR))
+    }
+}
+
+/// the name of a screen, as displayed in the breadcrumb
+fn short_name(name: &str) -> &str {
+    name.trim_end_matches(" screen")
+}
+
+#[cfg(test)]
+mod app_stack_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_short_name() {
+        assert_eq!(short_name("git log screen"), "git log");
+        assert_eq!(short_name("tree"), "tree");
+        assert_eq!(short_name("rename preview"), "rename preview");
+    }
+
+    #[test]
+    fn check_breadcrumb() {
+        assert_eq!(breadcrumb_of(vec!["tree", "tree", "tree"]), None);
+        assert_eq!(
+            breadcrumb_of(vec!["tree", "tree", "git log", "tree", "tree", "help"]),
+            Some("tree › git log › tree › help".to_string()),
+        );
+    }
+}
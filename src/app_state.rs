@@ -3,12 +3,15 @@ use {
         app_context::AppContext,
         browser_states::BrowserState,
         commands::Command,
-        errors::{ProgramError, TreeBuildError},
+        errors::{FileOpError, ProgramError, TreeBuildError},
         external::Launchable,
+        flat_tree::Tree,
+        fs_watch::AwaitedFile,
+        help_content::ScreenHelp,
         screens::Screen,
         task_sync::Dam,
     },
-    std::io::Write,
+    std::{io::Write, path::PathBuf},
 };
 
 /// Result of applying a command to a state
@@ -17,10 +20,13 @@ pub enum AppStateCmdResult {
     Keep,
     Launch(Box<Launchable>),
     DisplayError(String),
+    DisplayFailure(ProgramError), // an error whose sources are logged
     NewState(Box<dyn AppState>, Command),
+    ReplaceState(Box<dyn AppState>, Command), // the current state is replaced by the new one
     PopStateAndReapply, // the state asks the command be executed on a previous state
     PopState,
     RefreshState { clear_cache: bool },
+    RefreshWithMessage(String), // the files changed, the message tells how
 }
 
 impl AppStateCmdResult {
@@ -39,6 +45,12 @@ impl AppStateCmdResult {
     }
 }
 
+impl From<FileOpError> for AppStateCmdResult {
+    fn from(source: FileOpError) -> Self {
+        AppStateCmdResult::DisplayFailure(ProgramError::FileOp { source })
+    }
+}
+
 impl From<Launchable> for AppStateCmdResult {
     fn from(launchable: Launchable) -> Self {
         AppStateCmdResult::Launch(Box::new(launchable))
@@ -48,6 +60,10 @@ impl From<Launchable> for AppStateCmdResult {
 /// a whole application state, stackable to allow reverting
 ///  to a previous one
 pub trait AppState {
+    /// the help of the state, which gives its name in the breadcrumb
+    /// of the stacked states, and declares the keys it's given
+    fn help(&self) -> &'static ScreenHelp;
+
     fn apply(
         &mut self,
         cmd: &mut Command,
@@ -59,7 +75,7 @@ pub trait AppState {
 
     fn refresh(&mut self, screen: &Screen, con: &AppContext) -> Command;
 
-    fn do_pending_task(&mut self, screen: &mut Screen, dam: &mut Dam);
+    fn do_pending_task(&mut self, screen: &mut Screen, con: &AppContext, dam: &mut Dam);
 
     fn has_pending_task(&self) -> bool;
 
@@ -84,4 +100,26 @@ pub trait AppState {
         screen: &Screen,
         con: &AppContext,
     ) -> Result<(), ProgramError>;
+
+    /// the tree which would be exported when broot runs without
+    /// interface, if the state has one
+    fn exported_tree(&self) -> Option<&Tree> {
+        None
+    }
+
+    /// the file the state waits for, if any (see `:await`)
+    fn awaited_file(&self) -> Option<&AwaitedFile> {
+        None
+    }
+
+    /// called when files were created in the watched directories.
+    /// Return a message when the state found what it was waiting for.
+    fn on_created_files(
+        &mut self,
+        _paths: &[PathBuf],
+        _screen: &Screen,
+        _con: &AppContext,
+    ) -> Option<String> {
+        None
+    }
 }
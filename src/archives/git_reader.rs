@@ -0,0 +1,124 @@
+//! reading of the tree of a directory, as it was at a given commit,
+//! from the objects of its git repository
+
+use {
+    super::{extraction_target, write_entry, ArchivedEntry},
+    git2::{ObjectType, Oid, Repository, Tree, TreeWalkMode, TreeWalkResult},
+    std::{
+        io,
+        path::{Path, PathBuf},
+        time::{Duration, UNIX_EPOCH},
+    },
+};
+
+fn io_error(e: git2::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e)
+}
+
+/// return the repository of the directory, and the path
+/// of the directory in the repository
+fn open_repo(dir: &Path) -> io::Result<(Repository, PathBuf)> {
+    let repo = Repository::discover(dir).map_err(io_error)?;
+    let workdir = repo
+        .workdir()
+        .and_then(|workdir| workdir.canonicalize().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no working directory"))?;
+    let dir = dir.canonicalize()?;
+    let rel = match dir.strip_prefix(&workdir) {
+        Ok(rel) => rel.to_path_buf(),
+        Err(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{:?} isn't in the working directory of its repository", dir),
+            ));
+        }
+    };
+    Ok((repo, rel))
+}
+
+/// return the tree of the directory at the commit
+fn tree_at<'r>(repo: &'r Repository, rel: &Path, commit: Oid) -> io::Result<Tree<'r>> {
+    let tree = repo.find_commit(commit).and_then(|c| c.tree()).map_err(io_error)?;
+    if rel.as_os_str().is_empty() {
+        return Ok(tree);
+    }
+    tree.get_path(rel)
+        .and_then(|entry| entry.to_object(repo))
+        .and_then(|object| object.peel_to_tree())
+        .map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no directory {:?} at this commit", rel),
+            )
+        })
+}
+
+/// return the commit a reference (a branch, a tag, a commit hash,
+/// `HEAD~3`, etc.) points to
+pub fn resolve(dir: &Path, reference: &str) -> io::Result<Oid> {
+    let (repo, _) = open_repo(dir)?;
+    let object = repo.revparse_single(reference).map_err(io_error)?;
+    let commit = object.peel_to_commit().map_err(io_error)?;
+    Ok(commit.id())
+}
+
+/// list the files and directories of the directory at the commit.
+/// All entries get the date of the commit.
+pub fn read_entries(dir: &Path, commit: Oid) -> io::Result<Vec<ArchivedEntry>> {
+    let (repo, rel) = open_repo(dir)?;
+    let seconds = repo.find_commit(commit).map_err(io_error)?.time().seconds();
+    let modified = Some(UNIX_EPOCH + Duration::from_secs(seconds.max(0) as u64));
+    let tree = tree_at(&repo, &rel, commit)?;
+    let odb = repo.odb().map_err(io_error)?;
+    let mut entries = Vec::new();
+    tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+        let name = match entry.name() {
+            Some(name) => name,
+            None => {
+                return TreeWalkResult::Skip;
+            }
+        };
+        let path = Path::new(root).join(name);
+        match entry.kind() {
+            Some(ObjectType::Tree) => entries.push(ArchivedEntry {
+                path,
+                is_dir: true,
+                size: 0,
+                modified,
+            }),
+            Some(ObjectType::Blob) => entries.push(ArchivedEntry {
+                path,
+                is_dir: false,
+                size: odb.read_header(entry.id()).map_or(0, |(size, _)| size as u64),
+                modified,
+            }),
+            _ => {} // submodules aren't listed
+        }
+        TreeWalkResult::Ok
+    })
+    .map_err(io_error)?;
+    Ok(entries)
+}
+
+/// write the `inner` entry of the directory at the commit, and its
+/// descendants, to `dest`. Return the number of written entries.
+pub fn extract(dir: &Path, commit: Oid, inner: &Path, dest: &Path) -> io::Result<usize> {
+    let (repo, rel) = open_repo(dir)?;
+    let tree = tree_at(&repo, &rel, commit)?;
+    let mut blobs = Vec::new();
+    tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+        if let (Some(name), Some(ObjectType::Blob)) = (entry.name(), entry.kind()) {
+            let path = Path::new(root).join(name);
+            if let Some(target) = extraction_target(&path, inner, dest) {
+                blobs.push((entry.id(), target));
+            }
+        }
+        TreeWalkResult::Ok
+    })
+    .map_err(io_error)?;
+    for (id, target) in &blobs {
+        let blob = repo.find_blob(*id).map_err(io_error)?;
+        write_entry(&mut blob.content(), false, target)?;
+    }
+    Ok(blobs.len())
+}
@@ -0,0 +1,537 @@
+//! reading of archives (zip and tar files), so that their content
+//! can be browsed as a tree, and extraction of their entries.
+//!
+//! The paths of entries are "virtual": they're made of the path of the
+//! archive file followed by the path of the entry in the archive, for
+//! example `/home/dys/dl/site.zip/img/logo.png`.
+//!
+//! A directory of a git repository can also be browsed as it was at
+//! a given commit, like an archive whose path is the one of the
+//! directory followed by `@` and the short hash of the commit, for
+//! example `/home/dys/dev/broot/src@1a2b3c4d5e/app.rs`.
+
+mod git_reader;
+mod tar_reader;
+mod zip_reader;
+
+use {
+    crate::previews,
+    flate2::read::GzDecoder,
+    git2::Oid,
+    std::{
+        collections::HashMap,
+        fs::{self, OpenOptions},
+        io::{self, Read},
+        path::{Component, Path, PathBuf},
+        sync::{Arc, Mutex},
+        time::SystemTime,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+    Git(Oid), // a directory of a repository, at a commit
+}
+
+/// the number of hex digits of the commit hash in the path of a snapshot
+const SHORT_HASH_LEN: usize = 10;
+
+/// the suffixes of the names of the supported archives
+static SUFFIXES: &[(&str, ArchiveKind)] = &[
+    (".tar.gz", ArchiveKind::TarGz),
+    (".tgz", ArchiveKind::TarGz),
+    (".tar", ArchiveKind::Tar),
+    (".zip", ArchiveKind::Zip),
+    (".jar", ArchiveKind::Zip),
+];
+
+/// split the name of an archive file into its stem and
+/// the kind of archive, or return None if it's not an archive
+fn split_name(name: &str) -> Option<(&str, ArchiveKind)> {
+    SUFFIXES.iter().find_map(|(suffix, kind)| {
+        let stem_len = name.len().checked_sub(suffix.len())?;
+        match name.get(stem_len..) {
+            Some(end) if stem_len > 0 && end.eq_ignore_ascii_case(suffix) => {
+                Some((&name[..stem_len], *kind))
+            }
+            _ => None,
+        }
+    })
+}
+
+fn name_of(path: &Path) -> Option<&str> {
+    path.file_name().and_then(|name| name.to_str())
+}
+
+/// tell whether the file is an archive broot can browse
+/// (only its name is checked)
+pub fn is_archive(path: &Path) -> bool {
+    name_of(path).and_then(split_name).is_some()
+}
+
+/// split the name of a snapshot, like `src@1a2b3c4d5e`, into the
+/// name of the directory and the commit hash
+fn split_snapshot_name(name: &str) -> Option<(&str, &str)> {
+    let idx = name.rfind('@')?;
+    let (stem, hash) = (&name[..idx], &name[idx + 1..]);
+    if stem.is_empty() || hash.len() < 7 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        None
+    } else {
+        Some((stem, hash))
+    }
+}
+
+/// return the directory on disk of the snapshot at the given path,
+/// and the commit hash, or None if it's not the root of a snapshot
+fn snapshot_source(path: &Path) -> Option<(PathBuf, &str)> {
+    let (stem, hash) = name_of(path).and_then(split_snapshot_name)?;
+    let dir = path.with_file_name(stem);
+    if dir.is_dir() {
+        Some((dir, hash))
+    } else {
+        None
+    }
+}
+
+/// return the path of the archive when the given path is an
+/// archive or a path in an archive, or the root of the snapshot
+/// when it's a path in a snapshot
+pub fn archive_containing(path: &Path) -> Option<PathBuf> {
+    let mut child: Option<&Path> = None;
+    for ancestor in path.ancestors() {
+        if let Ok(md) = fs::metadata(ancestor) {
+            // the first ancestor found on disk is either the
+            // archive, the parent of a snapshot, or a normal
+            // file or directory
+            if let Some(child) = child.filter(|_| md.is_dir()) {
+                if snapshot_source(child).is_some() {
+                    return Some(child.to_path_buf());
+                }
+            }
+            return if md.is_file() && is_archive(ancestor) {
+                Some(ancestor.to_path_buf())
+            } else {
+                None
+            };
+        }
+        child = Some(ancestor);
+    }
+    None
+}
+
+/// return the virtual path of the directory as it was at the commit
+/// the reference points to. The directory may itself be in a snapshot,
+/// so that one can go from a commit to another one.
+pub fn snapshot_path(dir: &Path, reference: &str) -> io::Result<PathBuf> {
+    let dir = match archive_containing(dir).and_then(|root| {
+        let (source, _) = snapshot_source(&root)?;
+        dir.strip_prefix(&root).ok().map(|inner| source.join(inner))
+    }) {
+        Some(dir) => dir,
+        None => dir.to_path_buf(),
+    };
+    let name = match name_of(&dir) {
+        Some(name) => name,
+        None => {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid directory name"));
+        }
+    };
+    let commit = git_reader::resolve(&dir, reference)?.to_string();
+    Ok(dir.with_file_name(format!("{}@{}", name, &commit[..SHORT_HASH_LEN])))
+}
+
+/// make a relative path from the path of an entry, or return None
+/// when it's empty or could point outside of the extraction directory
+fn clean_path(raw: &str) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for component in Path::new(raw).components() {
+        match component {
+            Component::Normal(c) => path.push(c),
+            Component::CurDir => {}
+            _ => {
+                return None;
+            }
+        }
+    }
+    if path.as_os_str().is_empty() {
+        None
+    } else {
+        Some(path)
+    }
+}
+
+/// return where an entry must be written when extracting the `inner`
+/// entry (a file or a directory) to `dest`, or None if the entry
+/// isn't `inner` or one of its descendants
+fn extraction_target(entry_path: &Path, inner: &Path, dest: &Path) -> Option<PathBuf> {
+    let rel = entry_path.strip_prefix(inner).ok()?;
+    if rel.as_os_str().is_empty() {
+        Some(dest.to_path_buf())
+    } else {
+        Some(dest.join(rel))
+    }
+}
+
+/// write an extracted entry, creating the missing directories.
+/// An existing file is never written through.
+fn write_entry(reader: &mut impl Read, is_dir: bool, target: &Path) -> io::Result<()> {
+    if is_dir {
+        return fs::create_dir_all(target);
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().write(true).create_new(true).open(target)?;
+    io::copy(reader, &mut file)?;
+    Ok(())
+}
+
+/// a file or a directory found in an archive
+#[derive(Debug, Clone)]
+pub struct ArchivedEntry {
+    pub path: PathBuf, // in the archive
+    pub is_dir: bool,
+    pub size: u64, // for a directory, the sum of the sizes of its files
+    pub modified: Option<SystemTime>,
+}
+
+impl ArchivedEntry {
+    fn implicit_dir(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            is_dir: true,
+            size: 0,
+            modified: None,
+        }
+    }
+    pub fn name(&self) -> &str {
+        name_of(&self.path).unwrap_or("")
+    }
+}
+
+/// the index of the content of an archive
+pub struct Archive {
+    pub path: PathBuf,   // the virtual path of the root
+    pub source: PathBuf, // the archive file, or the directory of a snapshot
+    kind: ArchiveKind,
+    modified: Option<SystemTime>, // of the archive file, to check the index is up to date
+    entries: HashMap<PathBuf, ArchivedEntry>,
+    children: HashMap<PathBuf, Vec<PathBuf>>, // the root of the archive being the empty path
+    size: u64,
+}
+
+lazy_static! {
+    // the last opened archive, kept as it's usually browsed for a while
+    static ref LAST_ARCHIVE: Mutex<Option<Arc<Archive>>> = Mutex::new(None);
+}
+
+/// read the index of the archive, or take it from cache if it was
+/// just read and the file wasn't modified since
+pub fn open(path: &Path) -> io::Result<Arc<Archive>> {
+    let snapshot = snapshot_source(path);
+    // the content of a commit never changes
+    let modified = match snapshot {
+        Some(_) => None,
+        None => fs::metadata(path)?.modified().ok(),
+    };
+    let mut last = LAST_ARCHIVE.lock().unwrap();
+    if let Some(archive) = &*last {
+        if archive.path == path && archive.modified == modified {
+            return Ok(Arc::clone(archive));
+        }
+    }
+    let (source, kind) = match snapshot {
+        Some((dir, hash)) => {
+            let commit = git_reader::resolve(&dir, hash)?;
+            (dir, ArchiveKind::Git(commit))
+        }
+        None => match name_of(path).and_then(split_name) {
+            Some((_, kind)) => (path.to_path_buf(), kind),
+            None => {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "not a supported archive"));
+            }
+        },
+    };
+    let entries = time!(
+        Debug,
+        "read archive",
+        path,
+        match kind {
+            ArchiveKind::Zip => zip_reader::read_entries(path)?,
+            ArchiveKind::Git(commit) => git_reader::read_entries(&source, commit)?,
+            _ => tar_reader::read_entries(tar_stream(path, kind)?)?,
+        }
+    );
+    let archive = Arc::new(Archive::from_entries(
+        path.to_path_buf(),
+        source,
+        kind,
+        modified,
+        entries,
+    ));
+    *last = Some(Arc::clone(&archive));
+    Ok(archive)
+}
+
+fn tar_stream(path: &Path, kind: ArchiveKind) -> io::Result<Box<dyn Read>> {
+    let file = fs::File::open(path)?;
+    Ok(if kind == ArchiveKind::TarGz {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    })
+}
+
+impl Archive {
+    /// build the index from the entries read in the archive
+    fn from_entries(
+        path: PathBuf,
+        source: PathBuf,
+        kind: ArchiveKind,
+        modified: Option<SystemTime>,
+        list: Vec<ArchivedEntry>,
+    ) -> Self {
+        let mut entries: HashMap<PathBuf, ArchivedEntry> = HashMap::new();
+        for mut entry in list {
+            // the parent directories aren't always listed, and
+            // sometimes listed without being flagged as directories
+            for dir in entry.path.ancestors().skip(1) {
+                if dir.as_os_str().is_empty() {
+                    break;
+                }
+                match entries.get_mut(dir) {
+                    Some(parent) => parent.is_dir = true,
+                    None => {
+                        entries.insert(dir.to_path_buf(), ArchivedEntry::implicit_dir(dir));
+                    }
+                }
+            }
+            if entries.get(&entry.path).map_or(false, |known| known.is_dir) {
+                entry.is_dir = true;
+            }
+            entries.insert(entry.path.clone(), entry);
+        }
+        for entry in entries.values_mut().filter(|entry| entry.is_dir) {
+            entry.size = 0; // computed from the files below
+        }
+        let files: Vec<(PathBuf, u64)> = entries
+            .values()
+            .filter(|entry| !entry.is_dir)
+            .map(|entry| (entry.path.clone(), entry.size))
+            .collect();
+        let mut size = 0;
+        for (path, file_size) in files {
+            size += file_size;
+            for dir in path.ancestors().skip(1) {
+                if let Some(dir) = entries.get_mut(dir) {
+                    dir.size += file_size;
+                }
+            }
+        }
+        let mut children: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+        children.insert(PathBuf::new(), Vec::new());
+        for (path, entry) in &entries {
+            if entry.is_dir {
+                children.entry(path.clone()).or_default();
+            }
+            let parent = path.parent().unwrap_or_else(|| Path::new(""));
+            children.entry(parent.to_path_buf()).or_default().push(path.clone());
+        }
+        Self {
+            path,
+            source,
+            kind,
+            modified,
+            entries,
+            children,
+            size,
+        }
+    }
+
+    /// return the path in the archive of a virtual path
+    fn inner_path<'p>(&self, path: &'p Path) -> io::Result<&'p Path> {
+        path.strip_prefix(&self.path).map_err(|_| {
+            io::Error::new(io::ErrorKind::NotFound, format!("{:?} isn't in the archive", path))
+        })
+    }
+
+    /// tell whether the virtual path is a directory (the archive itself
+    /// being one), or return None if there's no such entry
+    pub fn is_dir(&self, path: &Path) -> Option<bool> {
+        let inner = self.inner_path(path).ok()?;
+        if inner.as_os_str().is_empty() {
+            Some(true)
+        } else {
+            self.entries.get(inner).map(|entry| entry.is_dir)
+        }
+    }
+
+    /// return the size of the file or the summed size of
+    /// the files of the directory
+    pub fn size_of(&self, path: &Path) -> Option<u64> {
+        let inner = self.inner_path(path).ok()?;
+        if inner.as_os_str().is_empty() {
+            Some(self.size)
+        } else {
+            self.entries.get(inner).map(|entry| entry.size)
+        }
+    }
+
+    /// return the entries of a directory of the archive
+    pub fn children(&self, path: &Path) -> io::Result<Vec<ArchivedEntry>> {
+        let inner = self.inner_path(path)?;
+        match self.children.get(inner) {
+            Some(paths) => Ok(paths.iter().map(|p| self.entries[p].clone()).collect()),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no directory {:?} in the archive", inner),
+            )),
+        }
+    }
+
+    /// extract the file or directory at the virtual path into the
+    /// destination directory (the whole archive being extracted in a
+    /// directory named after it). Return the path of what was written.
+    pub fn extract(&self, path: &Path, dest_dir: &Path) -> io::Result<PathBuf> {
+        let inner = self.inner_path(path)?;
+        let name = if inner.as_os_str().is_empty() {
+            match self.kind {
+                ArchiveKind::Git(_) => name_of(&self.path),
+                _ => name_of(&self.path).and_then(split_name).map(|(stem, _)| stem),
+            }
+        } else {
+            name_of(inner)
+        };
+        let dest = match name {
+            Some(name) => dest_dir.join(name),
+            None => {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid name"));
+            }
+        };
+        if dest.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{:?} already exists", dest),
+            ));
+        }
+        let count = match self.kind {
+            ArchiveKind::Zip => zip_reader::extract(&self.path, inner, &dest)?,
+            ArchiveKind::Git(commit) => git_reader::extract(&self.source, commit, inner, &dest)?,
+            _ => tar_reader::extract(tar_stream(&self.path, self.kind)?, inner, &dest)?,
+        };
+        debug!("{} entries extracted to {:?}", count, &dest);
+        if !dest.exists() {
+            // the directory had no listed entry, only descendants
+            fs::create_dir_all(&dest)?;
+        }
+        Ok(dest)
+    }
+
+    /// write a temporary copy of the file at the virtual path, so
+    /// that it can be opened, and return its path
+    pub fn preview(&self, path: &Path) -> io::Result<PathBuf> {
+        let dir = match name_of(&self.path) {
+            Some(name) => previews::dir(name)?,
+            None => {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid name"));
+            }
+        };
+        if let Some(name) = name_of(path) {
+            let previous = dir.join(name);
+            if fs::symlink_metadata(&previous).is_ok() {
+                fs::remove_file(previous)?;
+            }
+        }
+        self.extract(path, &dir)
+    }
+}
+
+#[cfg(test)]
+mod archives_tests {
+
+    use super::*;
+
+    fn entry(path: &str, is_dir: bool, size: u64) -> ArchivedEntry {
+        ArchivedEntry {
+            path: PathBuf::from(path),
+            is_dir,
+            size,
+            modified: None,
+        }
+    }
+
+    #[test]
+    fn check_split_name() {
+        assert_eq!(split_name("site.zip"), Some(("site", ArchiveKind::Zip)));
+        assert_eq!(split_name("broot-0.13.tar.GZ"), Some(("broot-0.13", ArchiveKind::TarGz)));
+        assert_eq!(split_name("src.tar"), Some(("src", ArchiveKind::Tar)));
+        assert_eq!(split_name(".zip"), None);
+        assert_eq!(split_name("notes.txt"), None);
+    }
+
+    #[test]
+    fn check_split_snapshot_name() {
+        assert_eq!(split_snapshot_name("src@1a2b3c4d5e"), Some(("src", "1a2b3c4d5e")));
+        assert_eq!(split_snapshot_name("me@home@ABCDEF0"), Some(("me@home", "ABCDEF0")));
+        assert_eq!(split_snapshot_name("src@main"), None);
+        assert_eq!(split_snapshot_name("src@1a2b"), None);
+        assert_eq!(split_snapshot_name("@1a2b3c4d5e"), None);
+        assert_eq!(split_snapshot_name("src"), None);
+    }
+
+    #[test]
+    fn check_clean_path() {
+        assert_eq!(clean_path("./img/logo.png"), Some(PathBuf::from("img/logo.png")));
+        assert_eq!(clean_path("img/"), Some(PathBuf::from("img")));
+        assert_eq!(clean_path("../etc/passwd"), None);
+        assert_eq!(clean_path("/etc/passwd"), None);
+        assert_eq!(clean_path("."), None);
+    }
+
+    #[test]
+    fn check_index() {
+        let archive = Archive::from_entries(
+            PathBuf::from("/dl/site.zip"),
+            PathBuf::from("/dl/site.zip"),
+            ArchiveKind::Zip,
+            None,
+            vec![
+                entry("index.html", false, 100),
+                entry("img/", false, 0), // some archivers forget the flag
+                entry("img/logo.png", false, 20),
+                entry("js/lib/app.js", false, 3),
+            ],
+        );
+        assert_eq!(archive.size_of(Path::new("/dl/site.zip")), Some(123));
+        assert_eq!(archive.size_of(Path::new("/dl/site.zip/js")), Some(3));
+        assert_eq!(archive.is_dir(Path::new("/dl/site.zip/js/lib")), Some(true));
+        assert_eq!(archive.is_dir(Path::new("/dl/site.zip/index.html")), Some(false));
+        assert_eq!(archive.is_dir(Path::new("/dl/site.zip/nothing")), None);
+        let mut names: Vec<String> = archive
+            .children(Path::new("/dl/site.zip"))
+            .unwrap()
+            .iter()
+            .map(|e| e.name().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["img", "index.html", "js"]);
+        assert!(archive.children(Path::new("/dl/site.zip/index.html")).is_err());
+    }
+
+    #[test]
+    fn check_extraction_target() {
+        let dest = Path::new("/dl/img");
+        assert_eq!(
+            extraction_target(Path::new("img/logo.png"), Path::new("img"), dest),
+            Some(PathBuf::from("/dl/img/logo.png")),
+        );
+        assert_eq!(
+            extraction_target(Path::new("img"), Path::new("img"), dest),
+            Some(PathBuf::from("/dl/img")),
+        );
+        assert_eq!(extraction_target(Path::new("js/app.js"), Path::new("img"), dest), None);
+    }
+}
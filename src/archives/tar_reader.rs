@@ -0,0 +1,62 @@
+use {
+    super::{clean_path, extraction_target, write_entry, ArchivedEntry},
+    std::{
+        io::{self, Read},
+        path::{Path, PathBuf},
+        time::{Duration, UNIX_EPOCH},
+    },
+    tar::{Archive, Entry, EntryType},
+};
+
+/// return the path of the entry and whether it's a directory, or
+/// None for the entries which aren't listed (links, devices, etc.)
+fn path_of<R: Read>(entry: &Entry<R>) -> io::Result<Option<(PathBuf, bool)>> {
+    let is_dir = match entry.header().entry_type() {
+        EntryType::Directory => true,
+        EntryType::Regular | EntryType::Continuous => false,
+        _ => {
+            return Ok(None);
+        }
+    };
+    let path = entry.path()?;
+    Ok(path.to_str().and_then(clean_path).map(|path| (path, is_dir)))
+}
+
+pub fn read_entries(stream: impl Read) -> io::Result<Vec<ArchivedEntry>> {
+    let mut archive = Archive::new(stream);
+    let mut entries = Vec::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if let Some((path, is_dir)) = path_of(&entry)? {
+            entries.push(ArchivedEntry {
+                path,
+                is_dir,
+                size: entry.size(),
+                modified: entry
+                    .header()
+                    .mtime()
+                    .ok()
+                    .map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+            });
+        }
+    }
+    Ok(entries)
+}
+
+/// extract the `inner` entry of the archive and its descendants
+/// to `dest`. Return the number of written entries.
+pub fn extract(stream: impl Read, inner: &Path, dest: &Path) -> io::Result<usize> {
+    let mut archive = Archive::new(stream);
+    let mut count = 0;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let target = path_of(&entry)?.and_then(|(entry_path, is_dir)| {
+            extraction_target(&entry_path, inner, dest).map(|target| (target, is_dir))
+        });
+        if let Some((target, is_dir)) = target {
+            write_entry(&mut entry, is_dir, &target)?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
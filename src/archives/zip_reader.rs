@@ -0,0 +1,58 @@
+use {
+    super::{clean_path, extraction_target, write_entry, ArchivedEntry},
+    chrono::{Local, TimeZone},
+    std::{fs, io, path::Path, time::SystemTime},
+    zip::{result::ZipError, DateTime, ZipArchive},
+};
+
+fn io_error(e: ZipError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// convert the date of a zip entry, which is in local time
+fn system_time(d: DateTime) -> Option<SystemTime> {
+    Local
+        .ymd_opt(d.year().into(), d.month().into(), d.day().into())
+        .single()?
+        .and_hms_opt(d.hour().into(), d.minute().into(), d.second().into())
+        .map(SystemTime::from)
+}
+
+fn open(path: &Path) -> io::Result<ZipArchive<fs::File>> {
+    ZipArchive::new(fs::File::open(path)?).map_err(io_error)
+}
+
+pub fn read_entries(path: &Path) -> io::Result<Vec<ArchivedEntry>> {
+    let mut zip = open(path)?;
+    let mut entries = Vec::new();
+    for i in 0..zip.len() {
+        let file = zip.by_index(i).map_err(io_error)?;
+        if let Some(path) = clean_path(file.name()) {
+            entries.push(ArchivedEntry {
+                path,
+                is_dir: file.is_dir(),
+                size: file.size(),
+                modified: system_time(file.last_modified()),
+            });
+        }
+    }
+    Ok(entries)
+}
+
+/// extract the `inner` entry of the archive and its descendants
+/// to `dest`. Return the number of written entries.
+pub fn extract(path: &Path, inner: &Path, dest: &Path) -> io::Result<usize> {
+    let mut zip = open(path)?;
+    let mut count = 0;
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i).map_err(io_error)?;
+        let target = clean_path(file.name())
+            .and_then(|entry_path| extraction_target(&entry_path, inner, dest));
+        if let Some(target) = target {
+            let is_dir = file.is_dir();
+            write_entry(&mut file, is_dir, &target)?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
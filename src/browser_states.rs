@@ -2,28 +2,50 @@ use {
     crate::{
         app_context::AppContext,
         app_state::{AppState, AppStateCmdResult},
+        archives, browser_verbs, bulk_rename,
         commands::{Action, Command},
+        confirm_states::{self, ConfirmState},
+        copy_states::CopyState,
         displayable_tree::DisplayableTree,
-        errors::{ProgramError, TreeBuildError},
+        errors::{FileOp, FileOpError, ProgramError, RegexError, TreeBuildError},
+        exec_queue::{self, QueuedExec},
         external::Launchable,
+        file_copy::FileCopy,
+        file_metadata::Preserve,
         flat_tree::{LineType, Tree},
+        fs_watch::AwaitedFile,
         git_status_computer,
+        help_content::ScreenHelp,
         help_states::HelpState,
+        notes,
+        organize,
+        path_completion,
         patterns::Pattern,
-        screens::{self, Screen},
+        portable_names,
+        profiles,
+        rename_states::RenameState,
+        resource_limits,
+        roots,
+        screens::Screen,
+        shell_output_states::ShellOutputState,
+        size_report,
+        special_paths,
         status::Status,
+        tags::{self, TagColor},
         task_sync::Dam,
         tree_build::TreeBuilder,
         tree_options::TreeOptions,
+        verb_invocation::VerbInvocation,
         verb_store::PrefixSearchResult,
-        verbs::VerbExecutor,
+        verbs::{self, Verb, VerbExecutor},
     },
     minimad::Composite,
     open,
+    regex::Regex,
     std::{
-        fs::OpenOptions,
-        io::Write,
-        path::PathBuf,
+        fs::{self, OpenOptions},
+        io::{self, Write},
+        path::{Path, PathBuf},
     },
 };
 
@@ -34,44 +56,232 @@ pub struct BrowserState {
     pub filtered_tree: Option<Tree>,
     pub pending_pattern: Pattern, // a pattern (or not) which has not yet be applied
     pub total_search_required: bool, // whether the pending pattern should be in total search mode
+    pub completions: Vec<String>, // the candidates of the last verb argument completion
+    pub awaited: Option<AwaitedFile>, // the file to select when it appears (see `:await`)
 }
 
 impl BrowserState {
+    /// build a state whose tree has the path as root, with the profile
+    /// and sort required by the special paths when entering it
     pub fn new(
         path: PathBuf,
         mut options: TreeOptions,
         screen: &Screen,
+        con: &AppContext,
+        dam: &Dam,
+    ) -> Result<Option<BrowserState>, TreeBuildError> {
+        let profile = special_paths::profile(&con.special_paths, &path)
+            .and_then(|name| profiles::find(&con.profiles, name));
+        if options.special_profile.as_deref() != profile.map(|p| p.name.as_str()) {
+            if options.special_profile.is_some() {
+                // leaving the directories of the previous profile
+                let roots = std::mem::take(&mut options.roots);
+                let pattern = options.pattern.take();
+                options = con.launch_args.tree_options.clone();
+                options.roots = roots;
+                options.pattern = pattern;
+            }
+            if let Some(profile) = profile {
+                profile.apply(&mut options);
+                options.special_profile = Some(profile.name.clone());
+            }
+        }
+        match special_paths::sort(&con.special_paths, &path) {
+            Some(sort) => {
+                options.sort = sort;
+                options.special_sort = true;
+            }
+            None if options.special_sort => {
+                // leaving the directory for which the sort was set
+                options.sort = con.launch_args.tree_options.sort;
+                options.special_sort = false;
+            }
+            None => {}
+        }
+        BrowserState::new_keeping_options(path, options, screen, con, dam)
+    }
+
+    fn new_keeping_options(
+        path: PathBuf,
+        mut options: TreeOptions,
+        screen: &Screen,
+        con: &AppContext,
         dam: &Dam,
     ) -> Result<Option<BrowserState>, TreeBuildError> {
         let pending_pattern = options.pattern.take();
-        let builder = TreeBuilder::from(path, options, BrowserState::page_height(screen) as usize)?;
+        let builder = TreeBuilder::from(
+            path,
+            options,
+            BrowserState::page_height(screen) as usize,
+            con,
+        )?;
         Ok(builder.build(false, dam).map(move |tree| BrowserState {
             tree,
             filtered_tree: None,
             pending_pattern,
             total_search_required: false,
+            completions: Vec::new(),
+            awaited: None,
         }))
     }
 
     pub fn with_new_options(
         &self,
         screen: &Screen,
+        con: &AppContext,
         change_options: &dyn Fn(&mut TreeOptions),
     ) -> AppStateCmdResult {
         let tree = self.displayed_tree();
         let mut options = tree.options.clone();
         change_options(&mut options);
+        if options.sort != tree.options.sort {
+            options.special_sort = false;
+        }
         AppStateCmdResult::from_optional_state(
-            BrowserState::new(
+            BrowserState::new_keeping_options(
                 tree.root().clone(),
                 options,
                 screen,
+                con,
                 &Dam::unlimited(),
             ),
             Command::from_pattern(&tree.options.pattern),
         )
     }
 
+    /// build a state displaying the given directory in addition
+    /// to the already displayed root(s)
+    pub fn add_root(
+        &self,
+        path_arg: &Option<String>,
+        screen: &Screen,
+        con: &AppContext,
+    ) -> AppStateCmdResult {
+        let tree = self.displayed_tree();
+        let path_arg = match path_arg {
+            Some(path_arg) => path_arg,
+            None => {
+                return AppStateCmdResult::DisplayError("a path is needed".to_string());
+            }
+        };
+        let line = tree.selected_line();
+        let directory = if line.is_dir() {
+            line.target()
+        } else {
+            line.path.parent().unwrap_or(&line.path).to_path_buf()
+        };
+        let path = match verbs::path_from_directory(&directory, path_arg).canonicalize() {
+            Ok(path) if path.is_dir() => path,
+            _ => {
+                return AppStateCmdResult::DisplayError(format!("not a directory: {:?}", path_arg));
+            }
+        };
+        let mut roots = if tree.options.roots.is_empty() {
+            vec![tree.root().clone()]
+        } else {
+            tree.options.roots.clone()
+        };
+        if roots.iter().any(|r| path.starts_with(r)) {
+            return AppStateCmdResult::DisplayError(format!("{:?} is already displayed", path));
+        }
+        roots.retain(|r| !r.starts_with(&path));
+        roots.push(path);
+        let mut options = tree.options.clone();
+        let root = roots::common_ancestor(&roots);
+        options.roots = roots;
+        AppStateCmdResult::from_optional_state(
+            BrowserState::new(root, options, screen, con, &Dam::unlimited()),
+            Command::from_pattern(&tree.options.pattern),
+        )
+    }
+
+    /// complete the last argument of the verb being typed, assuming
+    /// it's a path. When there are several candidates, the input is
+    /// completed up to their common part and they're kept to be
+    /// displayed in the status.
+    pub fn complete_verb_arg(
+        &mut self,
+        cmd: &mut Command,
+        invocation: &VerbInvocation,
+        con: &AppContext,
+    ) -> AppStateCmdResult {
+        let verb = match con.verb_store.search(&invocation.name) {
+            PrefixSearchResult::Match(verb) => verb,
+            _ => {
+                return AppStateCmdResult::Keep;
+            }
+        };
+        let token = if cmd.raw.ends_with(char::is_whitespace) {
+            ""
+        } else if invocation.args.is_some() {
+            cmd.raw.split_whitespace().last().unwrap_or("")
+        } else {
+            return AppStateCmdResult::Keep; // the verb name is still being typed
+        };
+        let line = self.displayed_tree().selected_line();
+        let directory = if line.is_dir() && !verb.execution.contains(":path-from-parent}") {
+            line.path.clone()
+        } else {
+            line.path.parent().unwrap_or(&line.path).to_path_buf()
+        };
+        let mut completions = path_completion::complete_path(&directory, token);
+        let completed = match completions.len() {
+            0 => {
+                return AppStateCmdResult::DisplayError("no possible completion".to_string());
+            }
+            1 => completions.remove(0),
+            _ => path_completion::common_prefix(&completions),
+        };
+        let raw = format!("{}{}", &cmd.raw[..cmd.raw.len() - token.len()], completed);
+        cmd.set_raw(raw);
+        self.completions = completions;
+        AppStateCmdResult::Keep
+    }
+
+    /// stage the execution of an external verb on the selection,
+    /// to be run later with the other queued ones
+    pub fn queue_verb(
+        &self,
+        invocation_arg: &Option<String>,
+        con: &AppContext,
+    ) -> AppStateCmdResult {
+        let invocation = match invocation_arg {
+            Some(invocation_arg) => VerbInvocation::from(invocation_arg),
+            None => {
+                return AppStateCmdResult::DisplayError("a verb is needed".to_string());
+            }
+        };
+        let verb = match con.verb_store.search(&invocation.name) {
+            PrefixSearchResult::Match(verb) => verb,
+            _ => {
+                return AppStateCmdResult::verb_not_found(&invocation.name);
+            }
+        };
+        if verb.execution.starts_with(':') || verb.from_shell {
+            return AppStateCmdResult::DisplayError(format!(
+                "{} can't be queued: only external commands executed by broot can",
+                &invocation.name,
+            ));
+        }
+        if let Some(err) = verb.match_error(&invocation) {
+            return AppStateCmdResult::DisplayError(err);
+        }
+        let path = self.displayed_tree().selected_line().path.clone();
+        let capture = self.selection_capture();
+        exec_queue::push(QueuedExec {
+            description: verb.shell_exec_string(&path, capture.as_deref(), &invocation.args),
+            exec_token: verb.exec_token(&path, capture.as_deref(), &invocation.args),
+        });
+        AppStateCmdResult::Keep
+    }
+
+    /// return the part of the name of the selected line captured by
+    /// the first group of the pattern, when it's a regex with groups
+    pub fn selection_capture(&self) -> Option<String> {
+        let tree = self.displayed_tree();
+        tree.options.pattern.capture(&tree.selected_line().name)
+    }
+
     pub fn page_height(screen: &Screen) -> i32 {
         i32::from(screen.height) - 2
     }
@@ -91,11 +301,39 @@ impl BrowserState {
     pub fn open_selection_stay_in_broot(
         &mut self,
         screen: &mut Screen,
-        _con: &AppContext,
+        con: &AppContext,
     ) -> Result<AppStateCmdResult, ProgramError> {
         let tree = self.displayed_tree();
         let line = tree.selected_line();
+        if line.in_archive && line.is_file() {
+            // a temporary copy of the file is opened
+            let preview = archives::archive_containing(&line.path)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "archive not found"))
+                .and_then(|p| archives::open(&p))
+                .and_then(|archive| archive.preview(&line.path))
+                .and_then(|copy| open::that(&copy));
+            return Ok(match preview {
+                Ok(exit_status) => {
+                    info!("open returned with exit_status {:?}", exit_status);
+                    AppStateCmdResult::Keep
+                }
+                Err(e) => AppStateCmdResult::DisplayError(e.to_string()),
+            });
+        }
         match &line.line_type {
+            LineType::File if archives::is_archive(&line.path) => {
+                // the archive is browsed as a directory
+                Ok(AppStateCmdResult::from_optional_state(
+                    BrowserState::new(
+                        line.path.clone(),
+                        tree.options.without_pattern(),
+                        screen,
+                        con,
+                        &Dam::unlimited(),
+                    ),
+                    Command::new(),
+                ))
+            }
             LineType::File => match open::that(&line.path) {
                 Ok(exit_status) => {
                     info!("open returned with exit_status {:?}", exit_status);
@@ -114,7 +352,7 @@ impl BrowserState {
                 }
                 let dam = Dam::unlimited();
                 Ok(AppStateCmdResult::from_optional_state(
-                    BrowserState::new(target, tree.options.without_pattern(), screen, &dam),
+                    BrowserState::new(target, tree.options.without_pattern(), screen, con, &dam),
                     Command::new(),
                 ))
             }
@@ -136,6 +374,11 @@ impl BrowserState {
     ) -> Result<AppStateCmdResult, ProgramError> {
         let tree = self.displayed_tree();
         let line = tree.selected_line();
+        if line.in_archive {
+            return Ok(AppStateCmdResult::DisplayError(
+                "use :extract to get the entries out of the archive".to_string(),
+            ));
+        }
         match &line.line_type {
             LineType::File => make_opener(line.path.clone(), line.is_exe(), con),
             LineType::Dir | LineType::SymLinkToDir(_) => {
@@ -143,6 +386,7 @@ impl BrowserState {
                     let cd_idx = con.verb_store.index_of("cd");
                     con.verb_store.verbs[cd_idx].to_cmd_result(
                         &line.target(),
+                        None,
                         &None,
                         screen,
                         con,
@@ -166,9 +410,307 @@ impl BrowserState {
         }
     }
 
+    /// extract the selected entry of an archive in the directory
+    /// of the archive, then display this directory
+    pub fn extract_selection(&self, screen: &Screen, con: &AppContext) -> AppStateCmdResult {
+        let tree = self.displayed_tree();
+        let line = tree.selected_line();
+        if !line.in_archive {
+            return AppStateCmdResult::DisplayError(
+                "only the entries of archives can be extracted".to_string(),
+            );
+        }
+        let archive = match archives::archive_containing(&line.path).map(|p| archives::open(&p)) {
+            Some(Ok(archive)) => archive,
+            Some(Err(e)) => {
+                return AppStateCmdResult::DisplayError(e.to_string());
+            }
+            None => {
+                return AppStateCmdResult::DisplayError("archive not found".to_string());
+            }
+        };
+        let dir = match archive.path.parent() {
+            Some(dir) => dir,
+            None => {
+                return AppStateCmdResult::DisplayError("no parent found".to_string());
+            }
+        };
+        match archive.extract(&line.path, dir) {
+            Ok(extracted) => {
+                info!("extracted {:?}", extracted);
+                AppStateCmdResult::from_optional_state(
+                    BrowserState::new(
+                        dir.to_path_buf(),
+                        tree.options.without_pattern(),
+                        screen,
+                        con,
+                        &Dam::unlimited(),
+                    ),
+                    Command::new(),
+                )
+            }
+            Err(e) => AppStateCmdResult::DisplayError(format!("extraction failed: {}", e)),
+        }
+    }
+
+    /// display the selected directory (or the parent of the selected
+    /// file) as it was at the commit the reference points to
+    pub fn open_snapshot(
+        &self,
+        reference: &Option<String>,
+        screen: &Screen,
+        con: &AppContext,
+    ) -> AppStateCmdResult {
+        let reference = match reference {
+            Some(reference) => reference,
+            None => {
+                return AppStateCmdResult::DisplayError(
+                    "a reference (branch, tag, commit) is needed".to_string(),
+                );
+            }
+        };
+        let tree = self.displayed_tree();
+        let line = tree.selected_line();
+        if line.is_remote() {
+            return AppStateCmdResult::DisplayError(
+                "remote directories can't be seen at a commit".to_string(),
+            );
+        }
+        let dir = if line.is_dir() {
+            line.target()
+        } else {
+            match line.path.parent() {
+                Some(dir) => dir.to_path_buf(),
+                None => {
+                    return AppStateCmdResult::DisplayError("no parent found".to_string());
+                }
+            }
+        };
+        match archives::snapshot_path(&dir, reference) {
+            Ok(path) => AppStateCmdResult::from_optional_state(
+                BrowserState::new(
+                    path,
+                    tree.options.without_pattern(),
+                    screen,
+                    con,
+                    &Dam::unlimited(),
+                ),
+                Command::new(),
+            ),
+            Err(e) => {
+                AppStateCmdResult::DisplayError(format!("no snapshot at {}: {}", reference, e))
+            }
+        }
+    }
+
+    /// write a report of the known sizes of the root's directories,
+    /// at a path relative to the selected directory
+    pub fn write_size_report(&self, path_arg: &Option<String>) -> AppStateCmdResult {
+        let path_arg = match path_arg {
+            Some(path_arg) => path_arg,
+            None => {
+                return AppStateCmdResult::DisplayError("a path is needed".to_string());
+            }
+        };
+        let tree = self.displayed_tree();
+        let line = tree.selected_line();
+        let directory = if line.is_dir() {
+            line.target()
+        } else {
+            line.path.parent().unwrap_or(&line.path).to_path_buf()
+        };
+        let report_path = verbs::path_from_directory(&directory, path_arg);
+        match size_report::write(tree.root(), &report_path) {
+            Ok(count) => {
+                info!("{} sizes written in {:?}", count, report_path);
+                AppStateCmdResult::RefreshState { clear_cache: false }
+            }
+            Err(e) => AppStateCmdResult::DisplayError(format!("no report written: {}", e)),
+        }
+    }
+
+    /// apply a change (of mode, owner...) to the selected file, then
+    /// refresh the tree so that it's visible
+    pub fn change_selection(
+        &self,
+        change: impl FnOnce(&Path) -> io::Result<()>,
+    ) -> AppStateCmdResult {
+        let line = self.displayed_tree().selected_line();
+        if line.in_archive || line.is_remote() {
+            return AppStateCmdResult::DisplayError(
+                "only the files of the local file system can be changed".to_string(),
+            );
+        }
+        match change(&line.path) {
+            Ok(()) => AppStateCmdResult::RefreshState { clear_cache: false },
+            Err(e) => AppStateCmdResult::DisplayError(e.to_string()),
+        }
+    }
+
+    /// open the preview of the renames, by a regex, of the names of
+    /// the visible files and directories
+    pub fn rename_regex(&self, verb: &Verb, args: &Option<String>) -> AppStateCmdResult {
+        let captures = args
+            .as_ref()
+            .and_then(|args| verb.args_parser.as_ref()?.captures(args));
+        let captures = match captures {
+            Some(captures) => captures,
+            None => {
+                return AppStateCmdResult::DisplayError(
+                    "a regex and a replacement are needed".to_string(),
+                );
+            }
+        };
+        let regex = match Regex::new(&captures["pattern"]) {
+            Ok(regex) => regex,
+            Err(e) => {
+                return AppStateCmdResult::DisplayError(RegexError::from(e).to_string());
+            }
+        };
+        let tree = self.displayed_tree();
+        let paths: Vec<PathBuf> = tree
+            .lines
+            .iter()
+            .skip(1) // the root isn't renamed
+            .filter(|line| line.is_selectable() && !line.in_archive && !line.is_remote())
+            .map(|line| line.path.clone())
+            .collect();
+        let renames = bulk_rename::plan(&paths, &regex, &captures["replacement"]);
+        let state = RenameState::new(tree.root(), renames);
+        AppStateCmdResult::NewState(Box::new(state), Command::new())
+    }
+
+    /// start the copy of the selection to the path given as argument,
+    /// relative to the parent of the selection (the copy is put in
+    /// the destination if it's a directory)
+    pub fn copy_selection(&self, args: &Option<String>, con: &AppContext) -> AppStateCmdResult {
+        let line = self.displayed_tree().selected_line();
+        if line.in_archive || line.is_remote() {
+            return AppStateCmdResult::DisplayError("only local files can be copied".to_string());
+        }
+        // the destination may be preceded with flags telling which metadata to keep
+        let args = args.as_deref().unwrap_or("");
+        let (preserve, input) = match Preserve::from_args(args, con.launch_args.copy_preserve) {
+            Ok((_, "")) => {
+                return AppStateCmdResult::DisplayError("a destination is needed".to_string());
+            }
+            Ok(parsed) => parsed,
+            Err(e) => {
+                return AppStateCmdResult::DisplayError(e);
+            }
+        };
+        let source = line.path.clone();
+        let parent = source.parent().unwrap_or(&source);
+        let mut dest = verbs::path_from_directory(parent, input);
+        if dest.is_dir() {
+            if let Some(name) = source.file_name() {
+                dest = dest.join(name);
+            }
+        }
+        let problems = portable_names::check_copy(&source, &dest);
+        if !problems.is_empty() {
+            return AppStateCmdResult::DisplayError(portable_names::describe(&problems));
+        }
+        match FileCopy::new(source.clone(), dest.clone(), con.launch_args.max_copy_rate, preserve) {
+            Ok(copy) => AppStateCmdResult::NewState(Box::new(CopyState::new(copy)), Command::new()),
+            Err(e) => FileOpError::new(FileOp::Copy, &source, e).with_dest(&dest).into(),
+        }
+    }
+
+    /// open the preview of the renames making the names of the selected
+    /// file or directory, and of its descendants, valid on all the usual
+    /// file systems
+    pub fn sanitize_names(&self) -> AppStateCmdResult {
+        let tree = self.displayed_tree();
+        let line = tree.selected_line();
+        if line.in_archive || line.is_remote() {
+            return AppStateCmdResult::DisplayError("only local files can be renamed".to_string());
+        }
+        let mut paths = portable_names::tree_paths(&line.path);
+        if &line.path == tree.root() {
+            paths.remove(0); // the root isn't renamed
+        }
+        let renames = bulk_rename::plan_sanitization(&paths);
+        if renames.is_empty() {
+            return AppStateCmdResult::DisplayError("all the names are valid".to_string());
+        }
+        let state = RenameState::new(tree.root(), renames);
+        AppStateCmdResult::NewState(Box::new(state), Command::new())
+    }
+
+    /// preview the moves of the files of the selected directory (or of
+    /// the parent of the selected file) to the folders of the rules
+    pub fn organize(&self, con: &AppContext) -> AppStateCmdResult {
+        let tree = self.displayed_tree();
+        let line = tree.selected_line();
+        if line.in_archive || line.is_remote() {
+            return AppStateCmdResult::DisplayError("only local files can be moved".to_string());
+        }
+        let dir = if line.is_dir() {
+            &line.path
+        } else {
+            line.path.parent().unwrap_or(&line.path)
+        };
+        let rules = if con.organize_rules.is_empty() {
+            organize::default_rules()
+        } else {
+            con.organize_rules.clone()
+        };
+        let renames = match organize::plan(dir, &rules) {
+            Ok(renames) => renames,
+            Err(e) => {
+                return AppStateCmdResult::DisplayError(e.to_string());
+            }
+        };
+        if renames.is_empty() {
+            return AppStateCmdResult::DisplayError("nothing to organize".to_string());
+        }
+        let state = RenameState::new(tree.root(), renames);
+        AppStateCmdResult::NewState(Box::new(state), Command::new())
+    }
+
+    /// attach the note to the selected entry or, when there's
+    /// none, remove its note
+    pub fn annotate_selection(&self, note: Option<&str>) -> AppStateCmdResult {
+        let line = self.displayed_tree().selected_line();
+        if line.in_archive || line.is_remote() {
+            return AppStateCmdResult::DisplayError(
+                "only local files can be annotated".to_string(),
+            );
+        }
+        let changed = match note {
+            Some(note) => notes::set(&line.path, note).map(|()| true),
+            None => notes::remove(&line.path),
+        };
+        match changed {
+            Ok(true) => AppStateCmdResult::Keep,
+            Ok(false) => AppStateCmdResult::DisplayError("the selection has no note".to_string()),
+            Err(e) => AppStateCmdResult::DisplayError(format!("notes can't be saved: {}", e)),
+        }
+    }
+
+    /// toggle the tag of the selected entry or, when there's
+    /// no color, remove all its tags
+    pub fn tag_selection(&self, color: Option<TagColor>) -> AppStateCmdResult {
+        let line = self.displayed_tree().selected_line();
+        if line.in_archive || line.is_remote() {
+            return AppStateCmdResult::DisplayError("only local files can be tagged".to_string());
+        }
+        let changed = match color {
+            Some(color) => tags::toggle(&line.path, color).map(|_| true),
+            None => tags::clear(&line.path),
+        };
+        match changed {
+            Ok(true) => AppStateCmdResult::Keep,
+            Ok(false) => AppStateCmdResult::DisplayError("the selection has no tag".to_string()),
+            Err(e) => AppStateCmdResult::DisplayError(format!("tags can't be saved: {}", e)),
+        }
+    }
+
     pub fn go_to_parent(
         &mut self,
         screen: &mut Screen,
+        con: &AppContext,
     ) -> AppStateCmdResult {
         match &self.displayed_tree().selected_line().path.parent() {
             Some(path) => AppStateCmdResult::from_optional_state(
@@ -176,6 +718,7 @@ impl BrowserState {
                     path.to_path_buf(),
                     self.displayed_tree().options.without_pattern(),
                     screen,
+                    con,
                     &Dam::unlimited(),
                 ),
                 Command::new(),
@@ -184,16 +727,119 @@ impl BrowserState {
         }
     }
 
+    /// when the selection was removed by another program, the trees are
+    /// refreshed, which selects the nearest remaining line, instead of
+    /// applying the verb, which would fail or hit another path
+    fn removed_selection(&self, verb: &Verb) -> Option<AppStateCmdResult> {
+        let tree = self.displayed_tree();
+        if tree.selection == 0 || !verb.uses_selection() {
+            return None;
+        }
+        let path = &tree.selected_line().path;
+        if fs::symlink_metadata(path).is_ok() {
+            return None;
+        }
+        Some(AppStateCmdResult::RefreshWithMessage(format!(
+            "{:?} doesn't exist anymore, the nearest file is now selected",
+            path.file_name().unwrap_or_else(|| path.as_os_str()),
+        )))
+    }
+
+    /// return the state asking to confirm the verb, when it's destructive
+    /// and applies to a protected path not yet confirmed
+    fn confirmation(
+        &self,
+        verb: &Verb,
+        action: &Action,
+        confirmed_path: Option<&Path>,
+        con: &AppContext,
+    ) -> Option<AppStateCmdResult> {
+        if !verb.is_destructive() {
+            return None;
+        }
+        let tree = self.displayed_tree();
+        let source = if verb.uses_selection() {
+            tree.selected_line().path.clone()
+        } else {
+            tree.root().clone()
+        };
+        // the destination of a move, eg `:mv {newpath}`, is changed too
+        let args = match action {
+            Action::VerbInvocate(invocation) => &invocation.args,
+            _ => &verb.invocation.args,
+        };
+        let capture = self.selection_capture();
+        let destination = verb.destination(&source, capture.as_deref(), args);
+        let mut paths = vec![source];
+        paths.extend(destination);
+        // the paths are confirmed in order
+        if let Some(idx) = confirmed_path.and_then(|c| paths.iter().position(|p| p == c)) {
+            paths.drain(..=idx);
+        }
+        let path = paths
+            .iter()
+            .find(|path| confirm_states::is_protected(path, &con.launch_args.protected_paths))?;
+        Some(AppStateCmdResult::NewState(
+            Box::new(ConfirmState::new(action.clone(), &verb.invocation.name, path)),
+            Command::new(),
+        ))
+    }
+
+    /// describe the selection in plain words, as a screen reader
+    /// can't guess it from the tree
+    fn selection_announcement(&self) -> String {
+        let tree = self.displayed_tree();
+        let line = tree.selected_line();
+        let count = tree.lines.len() - 1;
+        if tree.selection == 0 {
+            return format!("root {}, {} lines", line.path.to_string_lossy(), count);
+        }
+        let kind = match &line.line_type {
+            LineType::Dir => "directory",
+            LineType::SymLinkToDir(_) => "link to directory",
+            LineType::SymLinkToFile(_) => "link to file",
+            LineType::File | LineType::Pruning => "file",
+        };
+        let path = line.path.strip_prefix(tree.root()).unwrap_or(&line.path);
+        let mut announcement = format!(
+            "{} {}, line {} of {}",
+            kind,
+            path.to_string_lossy(),
+            tree.selection,
+            count,
+        );
+        let tags = tags::get(&line.path);
+        if !tags.is_empty() {
+            let names: Vec<&str> = tags.iter().map(|color| color.name()).collect();
+            announcement.push_str(&format!(", tagged {}", names.join(" and ")));
+        }
+        if let Some(note) = notes::get(&line.path) {
+            announcement.push_str(&format!(", note: {}", note));
+        }
+        announcement
+    }
+
     fn normal_status_message(&self, has_pattern: bool) -> Composite<'static> {
         let tree = self.displayed_tree();
         if tree.selection == 0 {
-            if has_pattern {
+            if has_pattern && !tree.total_search {
+                mad_inline!("Hit *esc* to remove the filter, *ctrl*-*s* to search all children, '?' for help")
+            } else if has_pattern {
                 mad_inline!("Hit *esc* to remove the filter, *enter* to go up, '?' for help")
             } else {
                 mad_inline!("Hit *esc* to go back, *enter* to go up, *?* for help, or a few letters to search")
             }
         } else {
             let line = &tree.lines[tree.selection];
+            if cfg!(unix) && line.has_error && line.line_type == LineType::Dir {
+                return mad_inline!("This directory can't be read, *:sudo_browse* would open it as root");
+            }
+            if line.in_archive && line.is_file() {
+                return mad_inline!("Hit *enter* to open a copy, or a space then *extract*");
+            }
+            if line.is_file() && archives::is_archive(&line.path) {
+                return mad_inline!("Hit *enter* to browse the archive, or a space then a verb");
+            }
             if has_pattern {
                 if line.is_dir() {
                     mad_inline!("Hit *enter* to focus, *alt*-*enter* to cd, *esc* to clear filter, or a space then a verb")
@@ -235,9 +881,17 @@ fn make_opener(
 }
 
 impl AppState for BrowserState {
+    fn help(&self) -> &'static ScreenHelp {
+        &browser_verbs::HELP
+    }
+
     fn has_pending_task(&self) -> bool {
         self.pending_pattern.is_some()
+            || self.displayed_tree().has_file_missing_size()
+            || self.displayed_tree().line_git_statuses_pending
             || self.displayed_tree().has_dir_missing_size()
+            || self.displayed_tree().has_dir_missing_deep_date()
+            || self.displayed_tree().has_file_missing_line_count()
             || self.displayed_tree().is_missing_git_status_computation()
     }
 
@@ -248,20 +902,60 @@ impl AppState for BrowserState {
         screen: &Screen,
         con: &AppContext,
     ) -> Result<(), ProgramError> {
-        let task = if self.pending_pattern.is_some() {
+        let task = if self.total_search_required {
+            Some("searching all children")
+        } else if self.pending_pattern.is_some() {
             Some("searching")
-        } else if self.displayed_tree().has_dir_missing_size() {
+        } else if self.displayed_tree().has_file_missing_size()
+            || self.displayed_tree().has_dir_missing_size()
+        {
             Some("computing sizes")
+        } else if self.displayed_tree().line_git_statuses_pending {
+            Some("reading git statuses")
+        } else if self.displayed_tree().has_dir_missing_deep_date() {
+            Some("computing deep dates")
+        } else if self.displayed_tree().has_file_missing_line_count() {
+            Some("counting lines")
         } else {
             None
         };
+        if let Some(warning) = resource_limits::take_warning() {
+            return Status::new(task, Composite::from_inline(&warning), true)
+                .display(&mut w, screen);
+        }
         match &cmd.action {
-            Action::FuzzyPatternEdit(s) if !s.is_empty() => {
+            Action::FuzzyPatternEdit(s) if !s.is_empty() && !con.launch_args.accessible => {
                 Status::new(task, self.normal_status_message(true), false).display(&mut w, screen)
             }
-            Action::RegexEdit(s, _) if !s.is_empty() => {
+            Action::RegexEdit(s, _) if !s.is_empty() && !con.launch_args.accessible => {
                 Status::new(task, self.normal_status_message(true), false).display(&mut w, screen)
             }
+            Action::ShellEdit(shell_command) => Status::new(
+                task,
+                if shell_command.trim().is_empty() {
+                    mad_inline!("Type a command for the shell, `{file}` being the selection")
+                } else {
+                    mad_inline!("Hit *enter* to run the command in the directory of the selection")
+                },
+                false,
+            )
+            .display(&mut w, screen),
+            Action::VerbEdit(_) if !self.completions.is_empty() => Status::new(
+                task,
+                Composite::from_inline(&format!(
+                    "Possible completions: {}",
+                    self.completions
+                        .iter()
+                        .map(|c| {
+                            let name = c.trim_end_matches('/');
+                            format!("*{}*", name.rsplit('/').next().unwrap_or(name))
+                        })
+                        .collect::<Vec<String>>()
+                        .join(", "),
+                )),
+                false,
+            )
+            .display(&mut w, screen),
             Action::VerbEdit(invocation) => {
                 if invocation.name.is_empty() {
                     Status::new(
@@ -282,7 +976,15 @@ impl AppState for BrowserState {
                         .display(&mut w, screen),
                         PrefixSearchResult::Match(verb) => {
                             let line = self.displayed_tree().selected_line();
-                            verb.write_status(&mut w, task, line.path.clone(), invocation, screen)
+                            let capture = self.selection_capture();
+                            verb.write_status(
+                                &mut w,
+                                task,
+                                line.path.clone(),
+                                capture.as_deref(),
+                                invocation,
+                                screen,
+                            )
                         }
                         PrefixSearchResult::TooManyMatches(completions) => Status::new(
                             task,
@@ -300,9 +1002,50 @@ impl AppState for BrowserState {
                     }
                 }
             }
+            _ if self.awaited.is_some() => {
+                let glob = self.awaited.as_ref().map_or("", |awaited| awaited.glob.as_str());
+                Status::new(
+                    Some("waiting"),
+                    Composite::from_inline(&format!(
+                        "Waiting for a file matching `{}` to appear, *esc* to stop",
+                        glob,
+                    )),
+                    false,
+                )
+                .display(&mut w, screen)
+            }
+            _ if !self.displayed_tree().removed.is_empty() => {
+                let removed = &self.displayed_tree().removed;
+                let names: Vec<String> = removed
+                    .iter()
+                    .filter_map(|path| path.file_name())
+                    .map(|name| format!("*{}*", name.to_string_lossy()))
+                    .collect();
+                Status::new(
+                    task,
+                    Composite::from_inline(&format!(
+                        "Removed since the last refresh: {}",
+                        names.join(", "),
+                    )),
+                    false,
+                )
+                .display(&mut w, screen)
+            }
+            _ if con.launch_args.accessible => {
+                let announcement = self.selection_announcement();
+                Status::new(task, mad_inline!("$0", &announcement), false).display(&mut w, screen)
+            }
             _ => {
-                Status::new(task, self.normal_status_message(false), false)
-                    .display(&mut w, screen)
+                let tree = self.displayed_tree();
+                let note = Some(tree.selected_line())
+                    .filter(|_| tree.selection > 0)
+                    .and_then(|line| notes::get(&line.path));
+                match note {
+                    Some(note) => Status::new(task, mad_inline!("*Note:* $0", &note), false)
+                        .display(&mut w, screen),
+                    None => Status::new(task, self.normal_status_message(false), false)
+                        .display(&mut w, screen),
+                }
             }
         }
     }
@@ -320,10 +1063,31 @@ impl AppState for BrowserState {
         con: &AppContext,
     ) -> Result<AppStateCmdResult, ProgramError> {
         self.pending_pattern = Pattern::None;
+        self.completions.clear();
+        let confirmed_path = cmd.confirmed_path.take();
+        if !matches!(cmd.action, Action::Resize(..)) {
+            // the changes of the last refresh have been seen
+            self.tree.clear_changes();
+            if let Some(tree) = &mut self.filtered_tree {
+                tree.clear_changes();
+            }
+        }
         let page_height = BrowserState::page_height(screen);
         match &cmd.action {
+            Action::Back if self.awaited.is_some() => {
+                self.awaited = None;
+                Ok(AppStateCmdResult::Keep)
+            }
             Action::Back => {
-                if self.filtered_tree.is_some() {
+                if self.displayed_tree().has_dir_missing_size() {
+                    // the computation of sizes is stopped, the remaining
+                    // directories being left unsized
+                    self.displayed_tree_mut().sizes_interrupted = true;
+                    Ok(AppStateCmdResult::Keep)
+                } else if self.displayed_tree().has_dir_missing_deep_date() {
+                    self.displayed_tree_mut().deep_dates_interrupted = true;
+                    Ok(AppStateCmdResult::Keep)
+                } else if self.filtered_tree.is_some() {
                     self.filtered_tree = None;
                     cmd.raw.clear();
                     Ok(AppStateCmdResult::Keep)
@@ -339,13 +1103,18 @@ impl AppState for BrowserState {
                 self.displayed_tree_mut().move_selection(*dy, page_height);
                 Ok(AppStateCmdResult::Keep)
             }
+            Action::Scroll(dy) => {
+                self.displayed_tree_mut().try_scroll(*dy, page_height);
+                Ok(AppStateCmdResult::Keep)
+            }
             Action::Click(_, y) => {
                 let y = *y as i32;
                 self.displayed_tree_mut().try_select_y(y);
                 Ok(AppStateCmdResult::Keep)
             }
             Action::DoubleClick(_, y) => {
-                if self.displayed_tree().selection == *y as usize {
+                let tree = self.displayed_tree();
+                if tree.selection as i32 == *y as i32 + tree.scroll {
                     self.open_selection_stay_in_broot(screen, con)
                 } else {
                     // A double click always come after a simple click at
@@ -357,6 +1126,14 @@ impl AppState for BrowserState {
             Action::OpenSelection => self.open_selection_stay_in_broot(screen, con),
             Action::AltOpenSelection => self.open_selection_quit_broot(screen, con),
             Action::FuzzyPatternEdit(pat) => {
+                if let Some(color) = TagColor::from_pattern(pat) {
+                    // `tag:red` is the short form of `:filter_by_tag red`
+                    self.filtered_tree = None;
+                    self.pending_pattern = Pattern::None;
+                    return Ok(self.with_new_options(screen, con, &|o| {
+                        o.tag_filter = Some(color);
+                    }));
+                }
                 match pat.len() {
                     0 => {
                         self.filtered_tree = None;
@@ -368,7 +1145,7 @@ impl AppState for BrowserState {
                 Ok(AppStateCmdResult::Keep)
             }
             Action::Help => Ok(AppStateCmdResult::NewState(
-                Box::new(HelpState::new(screen, con)),
+                Box::new(HelpState::new(screen, con, &browser_verbs::HELP)),
                 Command::new(),
             )),
             Action::Next => {
@@ -399,34 +1176,73 @@ impl AppState for BrowserState {
             }
             Action::VerbIndex(index) => {
                 let verb = &con.verb_store.verbs[*index];
+                if let Some(refresh) = self.removed_selection(verb) {
+                    return Ok(refresh);
+                }
+                if let Some(confirmation) =
+                    self.confirmation(verb, &cmd.action, confirmed_path.as_deref(), con)
+                {
+                    return Ok(confirmation);
+                }
                 self.execute_verb(verb, &verb.invocation, screen, con)
             }
             Action::VerbInvocate(invocation) => match con.verb_store.search(&invocation.name) {
                 PrefixSearchResult::Match(verb) => {
+                    if let Some(refresh) = self.removed_selection(verb) {
+                        return Ok(refresh);
+                    }
+                    if let Some(confirmation) =
+                        self.confirmation(verb, &cmd.action, confirmed_path.as_deref(), con)
+                    {
+                        return Ok(confirmation);
+                    }
                     self.execute_verb(verb, &invocation, screen, con)
                 }
                 _ => Ok(AppStateCmdResult::verb_not_found(&invocation.name)),
             },
+            Action::VerbComplete(invocation) => {
+                let invocation = invocation.clone();
+                Ok(self.complete_verb_arg(cmd, &invocation, con))
+            }
+            Action::ShellExec(shell_command) => {
+                let line = self.displayed_tree().selected_line();
+                let capture = self.selection_capture();
+                let shell_command =
+                    verbs::shell_command_string(shell_command, &line.path, capture.as_deref());
+                let directory = if line.is_dir() {
+                    line.path.as_path()
+                } else {
+                    line.path.parent().unwrap_or(&line.path)
+                };
+                cmd.raw.clear();
+                Ok(AppStateCmdResult::NewState(
+                    Box::new(ShellOutputState::new(shell_command, directory)),
+                    Command::new(),
+                ))
+            }
             _ => Ok(AppStateCmdResult::Keep),
         }
     }
 
     /// do some work, totally or partially, if there's some to do.
     /// Stop as soon as the dam asks for interruption
-    fn do_pending_task(&mut self, screen: &mut Screen, dam: &mut Dam) {
+    fn do_pending_task(&mut self, screen: &mut Screen, con: &AppContext, dam: &mut Dam) {
         if self.pending_pattern.is_some() {
             let pattern_str = self.pending_pattern.to_string();
             let mut options = self.tree.options.clone();
             options.pattern = self.pending_pattern.take();
             let root = self.tree.root().clone();
             let len = self.tree.lines.len() as u16;
-            let builder = match TreeBuilder::from(root, options, len as usize) {
+            let mut builder = match TreeBuilder::from(root, options, len as usize, con) {
                 Ok(builder) => builder,
                 Err(e) => {
                     warn!("Error while preparing tree builder: {:?}", e);
                     return;
                 }
             };
+            // when the pattern was extended, the previous search
+            // tells which directories may contain matches
+            builder.reuse_walk(self.filtered_tree.as_ref().and_then(|tree| tree.walk.clone()));
             let mut filtered_tree = time!(
                 Info,
                 "tree filtering",
@@ -440,12 +1256,20 @@ impl AppState for BrowserState {
                 self.filtered_tree = filtered_tree;
             }
 
+        } else if self.displayed_tree().has_file_missing_size() {
+            self.displayed_tree_mut().fetch_file_sizes();
+        } else if self.displayed_tree().line_git_statuses_pending {
+            self.displayed_tree_mut().fetch_line_git_statuses();
         } else if self.displayed_tree().is_missing_git_status_computation() {
             let root_path = self.displayed_tree().root();
             let git_status = git_status_computer::get_tree_status(root_path, dam);
             self.displayed_tree_mut().git_status = git_status;
-        } else {
+        } else if self.displayed_tree().has_dir_missing_size() {
             self.displayed_tree_mut().fetch_some_missing_dir_size(dam);
+        } else if self.displayed_tree().has_dir_missing_deep_date() {
+            self.displayed_tree_mut().fetch_some_missing_deep_date(dam);
+        } else {
+            self.displayed_tree_mut().fetch_some_missing_line_count();
         }
     }
 
@@ -453,7 +1277,7 @@ impl AppState for BrowserState {
         &mut self,
         mut w: &mut dyn Write,
         screen: &Screen,
-        _con: &AppContext,
+        con: &AppContext,
     ) -> Result<(), ProgramError> {
         screen.goto(&mut w, 0, 0)?;
         let dp = DisplayableTree {
@@ -466,20 +1290,22 @@ impl AppState for BrowserState {
                 height: screen.height - 2,
             },
             in_app: true,
+            icons: Some(&con.icons),
+            accessible: con.launch_args.accessible,
         };
         dp.write_on(&mut w)
     }
 
-    fn refresh(&mut self, screen: &Screen, _con: &AppContext) -> Command {
+    fn refresh(&mut self, screen: &Screen, con: &AppContext) -> Command {
         let page_height = BrowserState::page_height(screen) as usize;
         // refresh the base tree
-        if let Err(e) = self.tree.refresh(page_height) {
+        if let Err(e) = self.tree.refresh(page_height, con) {
             warn!("refreshing base tree failed : {:?}", e);
         }
         // refresh the filtered tree, if any
         Command::from_pattern(match self.filtered_tree {
             Some(ref mut tree) => {
-                if let Err(e) = tree.refresh(page_height) {
+                if let Err(e) = tree.refresh(page_height, con) {
                     warn!("refreshing filtered tree failed : {:?}", e);
                 }
                 &tree.options.pattern
@@ -496,18 +1322,125 @@ impl AppState for BrowserState {
         _con: &AppContext,
     ) -> Result<(), ProgramError> {
         let tree = self.displayed_tree();
-        let total_char_size = screens::FLAGS_AREA_WIDTH;
+        let sort = tree.options.sort;
+        let date_filter = tree.options.date_filter.to_string();
+        let ext_filter = tree.options.ext_filter.to_string();
+        let note_filter = tree.options.note_filter.as_ref();
+        let tag_filter = tree.options.tag_filter.map(TagColor::name);
+        let max_depth = tree.options.max_depth.map(|d| d.to_string());
+        let max_children = tree.options.max_children.map(|c| c.to_string());
+        let queue_len = exec_queue::len();
+        let mut total_char_size = 11; // " h:y   gi:y"
+        if queue_len > 0 {
+            total_char_size += 5 + queue_len.to_string().len() as u16; // " q:xxx  "
+        }
+        if sort.is_some() {
+            total_char_size += 5 + sort.name().len() as u16; // " s:xxx  "
+        }
+        if !date_filter.is_empty() {
+            total_char_size += 5 + date_filter.len() as u16; // " m:xxx  "
+        }
+        if !ext_filter.is_empty() {
+            total_char_size += 5 + ext_filter.len() as u16; // " e:xxx  "
+        }
+        if let Some(note_filter) = note_filter {
+            total_char_size += 6 + note_filter.chars().count() as u16; // " nt:xxx  "
+        }
+        if let Some(tag_filter) = tag_filter {
+            total_char_size += 5 + tag_filter.len() as u16; // " t:xxx  "
+        }
+        if let Some(max_depth) = &max_depth {
+            total_char_size += 5 + max_depth.len() as u16; // " d:xxx  "
+        }
+        if let Some(max_children) = &max_children {
+            total_char_size += 5 + max_children.len() as u16; // " n:xxx  "
+        }
+        screen.set_flags_width(total_char_size);
         screen.goto_clear(
             &mut w,
-            screen.width - total_char_size - 1,
+            screen.width - total_char_size,
             screen.height - 1,
         )?;
         let h_value = if tree.options.show_hidden { 'y' } else { 'n' };
         let gi_value = if tree.options.respect_git_ignore { 'y' } else { 'n' };
+        if sort.is_some() {
+            screen.skin.flag_label.queue_str(&mut w, " s:")?;
+            screen.skin.flag_value.queue_str(&mut w, sort.name())?;
+            screen.skin.flag_label.queue_str(&mut w, "  ")?;
+        }
+        if queue_len > 0 {
+            screen.skin.flag_label.queue_str(&mut w, " q:")?;
+            screen.skin.flag_value.queue(&mut w, queue_len)?;
+            screen.skin.flag_label.queue_str(&mut w, "  ")?;
+        }
+        if !date_filter.is_empty() {
+            screen.skin.flag_label.queue_str(&mut w, " m:")?;
+            screen.skin.flag_value.queue_str(&mut w, &date_filter)?;
+            screen.skin.flag_label.queue_str(&mut w, "  ")?;
+        }
+        if !ext_filter.is_empty() {
+            screen.skin.flag_label.queue_str(&mut w, " e:")?;
+            screen.skin.flag_value.queue_str(&mut w, &ext_filter)?;
+            screen.skin.flag_label.queue_str(&mut w, "  ")?;
+        }
+        if let Some(note_filter) = note_filter {
+            screen.skin.flag_label.queue_str(&mut w, " nt:")?;
+            screen.skin.flag_value.queue_str(&mut w, note_filter)?;
+            screen.skin.flag_label.queue_str(&mut w, "  ")?;
+        }
+        if let Some(tag_filter) = tag_filter {
+            screen.skin.flag_label.queue_str(&mut w, " t:")?;
+            screen.skin.flag_value.queue_str(&mut w, tag_filter)?;
+            screen.skin.flag_label.queue_str(&mut w, "  ")?;
+        }
+        if let Some(max_depth) = &max_depth {
+            screen.skin.flag_label.queue_str(&mut w, " d:")?;
+            screen.skin.flag_value.queue_str(&mut w, max_depth)?;
+            screen.skin.flag_label.queue_str(&mut w, "  ")?;
+        }
+        if let Some(max_children) = &max_children {
+            screen.skin.flag_label.queue_str(&mut w, " n:")?;
+            screen.skin.flag_value.queue_str(&mut w, max_children)?;
+            screen.skin.flag_label.queue_str(&mut w, "  ")?;
+        }
         screen.skin.flag_label.queue_str(&mut w, " h:")?;
         screen.skin.flag_value.queue(&mut w, h_value)?;
         screen.skin.flag_label.queue_str(&mut w, "   gi:")?;
         screen.skin.flag_value.queue(&mut w, gi_value)?;
         Ok(())
     }
+
+    fn exported_tree(&self) -> Option<&Tree> {
+        Some(self.displayed_tree())
+    }
+
+    fn awaited_file(&self) -> Option<&AwaitedFile> {
+        self.awaited.as_ref()
+    }
+
+    /// select the awaited file when it appears, the tree being
+    /// rebuilt on its directory if it's not displayed
+    fn on_created_files(
+        &mut self,
+        paths: &[PathBuf],
+        screen: &Screen,
+        con: &AppContext,
+    ) -> Option<String> {
+        let awaited = self.awaited.as_ref()?;
+        let path = paths.iter().find(|path| awaited.matches(path) && path.is_file())?.clone();
+        let message = format!("*{}* appeared", path.to_string_lossy());
+        self.awaited = None;
+        self.filtered_tree = None;
+        if !self.tree.try_select_path(&path) {
+            let dir = path.parent()?.to_path_buf();
+            let options = self.tree.options.without_pattern();
+            let dam = Dam::unlimited();
+            if let Ok(Some(state)) = BrowserState::new(dir, options, screen, con, &dam) {
+                *self = state;
+                self.tree.try_select_path(&path);
+            }
+        }
+        self.tree.make_selection_visible(BrowserState::page_height(screen));
+        Some(message)
+    }
 }
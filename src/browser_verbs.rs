@@ -3,27 +3,95 @@ use {
         app_context::AppContext,
         app_state::AppStateCmdResult,
         browser_states::BrowserState,
+        code_stats_states::CodeStatsState,
         commands::Command,
-        errors::ProgramError,
+        conf_doc_states::ConfDocState,
+        date_filter::DateFilter,
+        date_source::DateSource,
+        errors::{FileOp, FileOpError, ProgramError},
+        exec_queue,
+        ext_filter::ExtFilter,
         external,
+        file_sizes,
+        filesystems_states::FilesystemState,
         flat_tree::Tree,
+        fs_watch::AwaitedFile,
+        git_ignore_states::GitIgnoreState,
+        git_log_states::GitLogState,
+        help_content::{ScreenHelp, ScreenKey},
         help_states::HelpState,
+        op_journal::{self, Operation},
+        outcome_channel::Outcome,
+        palette_states::PaletteState,
+        profiles,
+        queue_states::QueueState,
         screens::Screen,
+        skin::Skin,
+        skin_presets,
+        sort::Sort,
+        tags::TagColor,
         task_sync::Dam,
+        trash,
+        trash_states::TrashState,
         tree_options::TreeOptions,
+        usage_states::UsageState,
         verb_invocation::VerbInvocation,
         verbs::{Verb, VerbExecutor},
     },
     directories::UserDirs,
-    std::path::PathBuf,
+    std::{path::PathBuf, str::FromStr},
 };
 
-fn focus_path(path: PathBuf, screen: &mut Screen, tree: &Tree) -> AppStateCmdResult {
+/// the help of the tree, which lists all the verbs
+pub static HELP: ScreenHelp = ScreenHelp {
+    name: "tree",
+    description: "",
+    keys: &[
+        ScreenKey::new("esc", "get back to the previous state, or clear the pattern"),
+        ScreenKey::new("↑ ↓", "move the selection"),
+        ScreenKey::new("enter", "open the selected file, or focus the selected directory"),
+        ScreenKey::new("alt-enter", "leave broot and `cd` to the selected directory"),
+        ScreenKey::new("tab, shift-tab", "select the next or previous match"),
+        ScreenKey::new("← →", "focus the parent, or the selection, when the input is empty"),
+        ScreenKey::new("space or :", "start a verb"),
+        ScreenKey::new("ctrl-p", "search all the verbs, the bookmarks and the recent roots"),
+        ScreenKey::new("?", "display this help"),
+    ],
+    builtins: None,
+    external_verbs: true,
+};
+
+/// parse the argument of a verb setting a limit, 0 meaning
+/// there's no limit
+fn parse_limit<T>(arg: &Option<String>) -> Result<Option<T>, String>
+where
+    T: FromStr + Default + PartialEq,
+{
+    let arg = arg.as_ref().map_or("", |s| s.trim());
+    match arg.parse::<T>() {
+        Ok(limit) if limit == T::default() => Ok(None),
+        Ok(limit) => Ok(Some(limit)),
+        Err(_) => Err(format!("{:?} isn't a valid limit", arg)),
+    }
+}
+
+fn unknown_tag_color(name: &str) -> String {
+    let colors: Vec<&str> = TagColor::ALL.iter().map(|color| color.name()).collect();
+    format!("{:?} isn't a tag color (expected one of {})", name, colors.join(", "))
+}
+
+fn focus_path(
+    path: PathBuf,
+    screen: &mut Screen,
+    tree: &Tree,
+    con: &AppContext,
+) -> AppStateCmdResult {
     AppStateCmdResult::from_optional_state(
         BrowserState::new(
             path,
             tree.options.clone(),
             screen,
+            con,
             &Dam::unlimited(),
         ),
         Command::from_pattern(&tree.options.pattern),
@@ -43,30 +111,156 @@ impl VerbExecutor for BrowserState {
         }
         let page_height = BrowserState::page_height(screen);
         Ok(match verb.execution.as_ref() {
+            ":add_root" => self.add_root(&invocation.args, screen, con),
+            ":at" => self.open_snapshot(&invocation.args, screen, con),
+            ":await" => {
+                let glob = invocation.args.as_ref().map_or("", String::as_str);
+                let root = self.tree.root();
+                if self.tree.lines[0].in_archive || self.tree.lines[0].is_remote() {
+                    AppStateCmdResult::DisplayError(
+                        "only local directories can be watched".to_string(),
+                    )
+                } else {
+                    match AwaitedFile::new(root.to_path_buf(), glob) {
+                        Ok(awaited) => {
+                            self.filtered_tree = None;
+                            self.awaited = Some(awaited);
+                            AppStateCmdResult::Keep
+                        }
+                        Err(e) => AppStateCmdResult::DisplayError(format!("invalid glob: {}", e)),
+                    }
+                }
+            }
             ":back" => AppStateCmdResult::PopState,
+            ":clear_queue" => {
+                exec_queue::clear();
+                AppStateCmdResult::Keep
+            }
+            ":config_doc" => {
+                AppStateCmdResult::NewState(Box::new(ConfDocState::new(con)), Command::new())
+            }
+            ":code_stats" => AppStateCmdResult::NewState(
+                Box::new(CodeStatsState::new(
+                    self.displayed_tree().selected_line().target(),
+                    self.displayed_tree().options.without_pattern(),
+                )),
+                Command::new(),
+            ),
+            ":execute_queue" => {
+                if exec_queue::len() == 0 {
+                    AppStateCmdResult::DisplayError(
+                        "the queue is empty: use :queue to stage commands".to_string(),
+                    )
+                } else {
+                    AppStateCmdResult::NewState(Box::new(QueueState::new()), Command::new())
+                }
+            }
+            ":extract" => self.extract_selection(screen, con),
+            ":file_log" => {
+                let tree = self.displayed_tree();
+                let line = tree.selected_line();
+                if line.in_archive || line.is_remote() || !line.is_file() {
+                    AppStateCmdResult::DisplayError("select a file of a git repository".to_string())
+                } else {
+                    match GitLogState::for_file(line.path.clone(), tree.options.without_pattern()) {
+                        Ok(state) => AppStateCmdResult::NewState(Box::new(state), Command::new()),
+                        Err(e) => AppStateCmdResult::DisplayError(format!("no git log: {}", e)),
+                    }
+                }
+            }
+            ":filter_by_date" | ":older_than" => {
+                let args = invocation.args.as_ref().map_or("", String::as_str);
+                let filter = if verb.execution == ":older_than" {
+                    format!("older_than:{}", args.trim())
+                } else {
+                    args.to_string()
+                };
+                match DateFilter::from_str(&filter) {
+                    Ok(date_filter) => self.with_new_options(screen, con, &|o| {
+                        o.date_filter = date_filter;
+                        o.show_dates = true;
+                    }),
+                    Err(e) => AppStateCmdResult::DisplayError(e.to_string()),
+                }
+            }
+            ":filter_by_note" => {
+                let filter = invocation.args.as_deref().unwrap_or("").trim().to_string();
+                self.with_new_options(screen, con, &|o| o.note_filter = Some(filter.clone()))
+            }
+            ":filter_by_tag" => {
+                let name = invocation.args.as_deref().unwrap_or("");
+                match TagColor::from_name(name) {
+                    Some(color) => self.with_new_options(screen, con, &|o| {
+                        o.tag_filter = Some(color);
+                    }),
+                    None => AppStateCmdResult::DisplayError(unknown_tag_color(name)),
+                }
+            }
+            ":ext" => {
+                let list = invocation.args.as_ref().map_or("", String::as_str);
+                let ext_filter = ExtFilter::from_str(list);
+                self.with_new_options(screen, con, &|o| {
+                    // giving the current list again removes the filter
+                    o.ext_filter = if o.ext_filter == ext_filter {
+                        ExtFilter::default()
+                    } else {
+                        ext_filter.clone()
+                    };
+                })
+            }
+            ":cp" => self.copy_selection(&invocation.args, con),
+            ":drives" | ":filesystems" => {
+                match FilesystemState::new(self.displayed_tree().options.without_pattern()) {
+                    Ok(state) => AppStateCmdResult::NewState(Box::new(state), Command::new()),
+                    Err(e) => AppStateCmdResult::DisplayError(e.to_string()),
+                }
+            }
             ":focus" => {
                 let tree = self.displayed_tree_mut();
                 let line = &tree.selected_line();
                 let mut path = line.target();
-                if !path.is_dir() {
+                let is_dir = if line.in_archive || line.is_remote() {
+                    line.is_dir()
+                } else {
+                    path.is_dir()
+                };
+                if !is_dir {
                     path = path.parent().unwrap().to_path_buf();
                 }
-                focus_path(path, screen, tree)
+                focus_path(path, screen, tree, con)
             }
-            ":focus_root" => focus_path(PathBuf::from("/"), screen, self.displayed_tree()),
+            ":focus_root" => focus_path(PathBuf::from("/"), screen, self.displayed_tree(), con),
             ":up_tree" => match self.displayed_tree().root().parent() {
-                Some(path) => focus_path(path.to_path_buf(), screen, self.displayed_tree()),
+                Some(path) => focus_path(path.to_path_buf(), screen, self.displayed_tree(), con),
                 None => AppStateCmdResult::DisplayError("no parent found".to_string()),
             },
             ":focus_user_home" => match UserDirs::new() {
-                Some(ud) => focus_path(ud.home_dir().to_path_buf(), screen, self.displayed_tree()),
+                Some(ud) => focus_path(ud.home_dir().to_path_buf(), screen, self.displayed_tree(), con),
                 None => AppStateCmdResult::DisplayError("no user home directory found".to_string()),
             },
-            ":help" => {
-                AppStateCmdResult::NewState(Box::new(HelpState::new(screen, con)), Command::new())
+            ":gitignore_add" => {
+                let line = self.displayed_tree().selected_line();
+                if line.in_archive || line.is_remote() {
+                    AppStateCmdResult::DisplayError(
+                        "only local files can be ignored".to_string(),
+                    )
+                } else {
+                    match GitIgnoreState::new(&line.path, line.is_dir()) {
+                        Ok(state) => AppStateCmdResult::NewState(Box::new(state), Command::new()),
+                        Err(e) => AppStateCmdResult::DisplayError(e.to_string()),
+                    }
+                }
             }
+            ":help" => AppStateCmdResult::NewState(
+                Box::new(HelpState::new(screen, con, &HELP)),
+                Command::new(),
+            ),
             ":open_stay" => self.open_selection_stay_in_broot(screen, con)?,
             ":open_leave" => self.open_selection_quit_broot(screen, con)?,
+            ":open_trash" => match TrashState::new() {
+                Ok(state) => AppStateCmdResult::NewState(Box::new(state), Command::new()),
+                Err(e) => AppStateCmdResult::DisplayError(e.to_string()),
+            },
             ":line_down" => {
                 self.displayed_tree_mut().move_selection(1, page_height);
                 AppStateCmdResult::Keep
@@ -75,6 +269,18 @@ impl VerbExecutor for BrowserState {
                 self.displayed_tree_mut().move_selection(-1, page_height);
                 AppStateCmdResult::Keep
             }
+            ":log" => {
+                let tree = self.displayed_tree();
+                let line = tree.selected_line();
+                if line.in_archive || line.is_remote() {
+                    AppStateCmdResult::DisplayError("no git log for this path".to_string())
+                } else {
+                    match GitLogState::new(line.path.clone(), tree.options.without_pattern()) {
+                        Ok(state) => AppStateCmdResult::NewState(Box::new(state), Command::new()),
+                        Err(e) => AppStateCmdResult::DisplayError(format!("no git log: {}", e)),
+                    }
+                }
+            }
             ":page_down" => {
                 let tree = self.displayed_tree_mut();
                 if page_height < tree.lines.len() as i32 {
@@ -89,15 +295,45 @@ impl VerbExecutor for BrowserState {
                 }
                 AppStateCmdResult::Keep
             }
-            ":parent" => self.go_to_parent(screen),
+            ":palette" => {
+                AppStateCmdResult::NewState(Box::new(PaletteState::new(con)), Command::new())
+            }
+            ":parent" => self.go_to_parent(screen, con),
             ":print_path" => {
                 external::print_path(&self.displayed_tree().selected_line().target(), con)?
             }
+            ":send_path" => match &con.outcome_channel {
+                Some(outcome_channel) => {
+                    let path = self.displayed_tree().selected_line().target();
+                    outcome_channel.send(Outcome::Path(&path));
+                    AppStateCmdResult::Keep
+                }
+                None => AppStateCmdResult::DisplayError(
+                    "broot must be launched with --listen to send paths".to_string(),
+                ),
+            },
             ":print_relative_path" => {
                 external::print_relative_path(&self.displayed_tree().selected_line().target(), con)?
             }
             ":print_tree" => external::print_tree(&self.displayed_tree(), screen, con)?,
             ":refresh" => AppStateCmdResult::RefreshState { clear_cache: true },
+            ":refresh_sizes" => {
+                file_sizes::forget_dir_sizes(self.displayed_tree().root());
+                AppStateCmdResult::RefreshState { clear_cache: false }
+            }
+            ":rename_regex" => self.rename_regex(verb, &invocation.args),
+            ":sanitize_names" => self.sanitize_names(),
+            ":organize" => self.organize(con),
+            #[cfg(unix)]
+            ":chmod" => {
+                let mode = invocation.args.as_deref().unwrap_or("");
+                self.change_selection(|path| crate::permissions::chmod(path, mode))
+            }
+            #[cfg(unix)]
+            ":chown" => {
+                let owner = invocation.args.as_deref().unwrap_or("");
+                self.change_selection(|path| crate::permissions::chown(path, owner))
+            }
             ":select_first" => {
                 self.displayed_tree_mut().try_select_first();
                 AppStateCmdResult::Keep
@@ -106,23 +342,130 @@ impl VerbExecutor for BrowserState {
                 self.displayed_tree_mut().try_select_last();
                 AppStateCmdResult::Keep
             }
-            ":toggle_dates" => self.with_new_options(screen, &|o| o.show_dates ^= true),
+            ":no_date_filter" => {
+                self.with_new_options(screen, con, &|o| o.date_filter = DateFilter::default())
+            }
+            ":no_ext_filter" => {
+                self.with_new_options(screen, con, &|o| o.ext_filter = ExtFilter::default())
+            }
+            ":no_note_filter" => self.with_new_options(screen, con, &|o| o.note_filter = None),
+            ":no_sort" => self.with_new_options(screen, con, &|o| o.sort = Sort::None),
+            ":no_tag_filter" => self.with_new_options(screen, con, &|o| o.tag_filter = None),
+            ":note" => self.annotate_selection(invocation.args.as_deref()),
+            ":remove_note" => self.annotate_selection(None),
+            ":set_max_children" => match parse_limit(&invocation.args) {
+                Ok(max_children) => {
+                    self.with_new_options(screen, con, &|o| o.max_children = max_children)
+                }
+                Err(e) => AppStateCmdResult::DisplayError(e),
+            },
+            ":set_max_depth" => match parse_limit(&invocation.args) {
+                Ok(max_depth) => self.with_new_options(screen, con, &|o| o.max_depth = max_depth),
+                Err(e) => AppStateCmdResult::DisplayError(e),
+            },
+            ":set_skin" => {
+                let name = invocation.args.as_ref().map_or("", String::as_str);
+                match skin_presets::entries(name, &con.skin_entries) {
+                    Some(entries) => {
+                        screen.set_skin(Skin::create(entries));
+                        AppStateCmdResult::Keep
+                    }
+                    None => AppStateCmdResult::DisplayError(format!(
+                        "unknown skin {:?}, available ones are {}",
+                        name,
+                        skin_presets::names(),
+                    )),
+                }
+            }
+            ":toggle_theme_mode" => {
+                let mode = screen.theme_mode.toggled();
+                let name = con.launch_args.skin_preset_for(mode);
+                match skin_presets::entries(name, &con.skin_entries) {
+                    Some(entries) => {
+                        screen.set_skin(Skin::create(entries));
+                        screen.theme_mode = mode;
+                        AppStateCmdResult::Keep
+                    }
+                    None => AppStateCmdResult::DisplayError(format!(
+                        "unknown skin {:?}, available ones are {}",
+                        name,
+                        skin_presets::names(),
+                    )),
+                }
+            }
+            ":profile" => {
+                let name = invocation.args.as_deref().unwrap_or("");
+                match profiles::find(&con.profiles, name) {
+                    Some(profile) => self.with_new_options(screen, con, &|o| {
+                        profile.apply(o);
+                        o.special_profile = None;
+                    }),
+                    None => AppStateCmdResult::DisplayError(format!(
+                        "unknown profile {:?}, available ones are {}",
+                        name,
+                        profiles::names(&con.profiles),
+                    )),
+                }
+            }
+            ":size_report" => self.write_size_report(&invocation.args),
+            ":sort_by_count" => self.with_new_options(screen, con, &|o| {
+                o.sort = o.sort.toggled(Sort::Count);
+            }),
+            ":sort_by_date" => self.with_new_options(screen, con, &|o| {
+                o.sort = o.sort.toggled(Sort::Date);
+                if o.sort == Sort::Date {
+                    o.show_dates = true;
+                }
+            }),
+            ":sort_by_size" => self.with_new_options(screen, con, &|o| {
+                o.sort = o.sort.toggled(Sort::Size);
+                if o.sort == Sort::Size {
+                    o.show_sizes = true;
+                }
+            }),
+            ":toggle_dates" => self.with_new_options(screen, con, &|o| o.show_dates ^= true),
+            ":toggle_deep_dates" => self.with_new_options(screen, con, &|o| {
+                if o.date_source == DateSource::Deep {
+                    o.date_source = DateSource::default();
+                } else {
+                    o.date_source = DateSource::Deep;
+                    o.show_dates = true;
+                }
+            }),
             ":toggle_files" => {
-                self.with_new_options(screen, &|o: &mut TreeOptions| o.only_folders ^= true)
+                self.with_new_options(screen, con, &|o: &mut TreeOptions| o.only_folders ^= true)
+            }
+            ":toggle_freshness" => {
+                self.with_new_options(screen, con, &|o| o.show_freshness ^= true)
             }
-            ":toggle_hidden" => self.with_new_options(screen, &|o| o.show_hidden ^= true),
+            ":toggle_hidden" => self.with_new_options(screen, con, &|o| o.show_hidden ^= true),
             ":toggle_git_ignore" => {
-                self.with_new_options(screen, &|o| o.respect_git_ignore ^= true)
+                self.with_new_options(screen, con, &|o| o.respect_git_ignore ^= true)
             }
+            ":toggle_git_dir_stats" => self.with_new_options(screen, con, &|o| {
+                o.show_git_dir_stats ^= true;
+                if o.show_git_dir_stats {
+                    o.show_git_file_info = true;
+                }
+            }),
             ":toggle_git_file_info" => {
-                self.with_new_options(screen, &|o| o.show_git_file_info ^= true)
+                self.with_new_options(screen, con, &|o| o.show_git_file_info ^= true)
             }
             ":toggle_git_status" => {
-                self.with_new_options(screen, &|o| o.filter_by_git_status ^= true)
+                self.with_new_options(screen, con, &|o| o.filter_by_git_status ^= true)
+            }
+            ":toggle_icons" => self.with_new_options(screen, con, &|o| o.show_icons ^= true),
+            ":toggle_ignore_audit" => {
+                self.with_new_options(screen, con, &|o| o.show_ignore_audit ^= true)
+            }
+            ":toggle_line_counts" => {
+                self.with_new_options(screen, con, &|o| o.show_line_counts ^= true)
             }
-            ":toggle_perm" => self.with_new_options(screen, &|o| o.show_permissions ^= true),
-            ":toggle_sizes" => self.with_new_options(screen, &|o| o.show_sizes ^= true),
-            ":toggle_trim_root" => self.with_new_options(screen, &|o| o.trim_root ^= true),
+            ":toggle_links" => self.with_new_options(screen, con, &|o| o.follow_links ^= true),
+            ":toggle_perm" => self.with_new_options(screen, con, &|o| o.show_permissions ^= true),
+            ":toggle_sizes" => self.with_new_options(screen, con, &|o| o.show_sizes ^= true),
+            ":toggle_trim_root" => self.with_new_options(screen, con, &|o| o.trim_root ^= true),
+            ":toggle_watch" => self.with_new_options(screen, con, &|o| o.watch ^= true),
             ":total_search" => {
                 if let Some(tree) = &self.filtered_tree {
                     if tree.total_search {
@@ -140,9 +483,51 @@ impl VerbExecutor for BrowserState {
                     )
                 }
             }
+            ":kill_task" => AppStateCmdResult::DisplayError(
+                "there's no running task: the shell commands are killed when their output is left"
+                    .to_string(),
+            ),
+            ":purge" | ":restore" => AppStateCmdResult::DisplayError(
+                "this verb applies to the items listed with :open_trash".to_string(),
+            ),
+            ":trash" => {
+                let tree = self.displayed_tree();
+                if tree.selection == 0 {
+                    AppStateCmdResult::DisplayError("the root can't be trashed".to_string())
+                } else {
+                    let path = &tree.selected_line().path;
+                    match trash::trash_with_fallback(path, &con.trash_commands) {
+                        Ok(true) => {
+                            op_journal::record(Operation::Trash(path.clone()));
+                            AppStateCmdResult::RefreshState { clear_cache: true }
+                        }
+                        // the trash of the command isn't known to broot
+                        Ok(false) => AppStateCmdResult::RefreshWithMessage(format!(
+                            "{} moved to the trash by a trash command: this can't be undone",
+                            path.to_string_lossy(),
+                        )),
+                        Err(e) => FileOpError::new(FileOp::Trash, path, e).into(),
+                    }
+                }
+            }
+            ":queue" => self.queue_verb(&invocation.args, con),
             ":quit" => AppStateCmdResult::Quit,
+            ":undo" => match op_journal::undo_last(&con.trash_commands) {
+                Ok(report) => AppStateCmdResult::RefreshWithMessage(report),
+                Err(e) => AppStateCmdResult::DisplayFailure(e),
+            },
+            ":untag" => self.tag_selection(None),
+            ":usage" => AppStateCmdResult::NewState(Box::new(UsageState::new(con)), Command::new()),
+            execution if execution.starts_with(":tag_") => {
+                let name = &execution[":tag_".len()..];
+                match TagColor::from_name(name) {
+                    Some(color) => self.tag_selection(Some(color)),
+                    None => AppStateCmdResult::DisplayError(unknown_tag_color(name)),
+                }
+            }
             _ => verb.to_cmd_result(
                 &self.displayed_tree().selected_line().path.clone(),
+                self.selection_capture().as_deref(),
                 &invocation.args,
                 screen,
                 con,
@@ -0,0 +1,244 @@
+//! renaming of several files at once, by applying a regular
+//! expression to their names, as done by `:rename_regex`.
+//!
+//! The renames are first planned, so that they can be previewed
+//! with their conflicts, then applied.
+//!
+//! A rename changing only the case of a name is supported on the
+//! case-insensitive file systems (usual on macOS and Windows), where
+//! the new path already "exists" as it's the old file.
+
+use {
+    crate::{
+        errors::{FileOp, FileOpError},
+        portable_names,
+    },
+    regex::Regex,
+    std::{
+        collections::HashSet,
+        fmt, fs, io,
+        path::{self, Path, PathBuf},
+        process,
+    },
+};
+
+#[derive(Debug)]
+pub enum RenameStatus {
+    Planned,
+    Conflict(&'static str), // the rename won't be done
+    Done,
+    Failed(FileOpError),
+}
+
+impl RenameStatus {
+    pub fn is_planned(&self) -> bool {
+        matches!(self, RenameStatus::Planned)
+    }
+    pub fn is_done(&self) -> bool {
+        matches!(self, RenameStatus::Done)
+    }
+}
+
+#[derive(Debug)]
+pub struct Rename {
+    pub old_path: PathBuf,
+    pub new_path: PathBuf,
+    pub status: RenameStatus,
+}
+
+impl Rename {
+    pub fn old_name(&self) -> String {
+        file_name(&self.old_path)
+    }
+    pub fn new_name(&self) -> String {
+        file_name(&self.new_path)
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().to_string())
+}
+
+impl fmt::Display for RenameStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenameStatus::Planned => Ok(()),
+            RenameStatus::Conflict(reason) => write!(f, "skipped: {}", reason),
+            RenameStatus::Done => write!(f, "done"),
+            RenameStatus::Failed(e) => write!(f, "failed: {}", e),
+        }
+    }
+}
+
+/// plan the renames of the paths whose names are matched by the regex,
+/// `replacement` being able to refer to the groups, as in `$1` or `${name}`.
+///
+/// The paths are expected in tree order, parents before children.
+pub fn plan(paths: &[PathBuf], regex: &Regex, replacement: &str) -> Vec<Rename> {
+    plan_with(paths, |name| {
+        if regex.is_match(name) {
+            Some(regex.replace_all(name, replacement).to_string())
+        } else {
+            None
+        }
+    })
+}
+
+/// plan the renames giving to the paths whose names couldn't be written
+/// on a Windows file system (eg `a:b`) a name valid everywhere (`a_b`).
+///
+/// The paths are expected in tree order, parents before children.
+pub fn plan_sanitization(paths: &[PathBuf]) -> Vec<Rename> {
+    plan_with(paths, |name| Some(portable_names::sanitize(name)))
+}
+
+/// plan the renames of the paths, with the new names given by
+/// `rename`, which returns None for names which aren't renamed
+fn plan_with<F>(paths: &[PathBuf], rename: F) -> Vec<Rename>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut renames = Vec::new();
+    let mut new_paths = HashSet::new();
+    for old_path in paths {
+        let name = match old_path.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name,
+            None => continue,
+        };
+        let new_name = match rename(name) {
+            Some(new_name) if new_name != name => new_name,
+            _ => continue,
+        };
+        let new_path = old_path.with_file_name(&new_name);
+        let status = if new_name.is_empty() || new_name == "." || new_name == ".." {
+            RenameStatus::Conflict("invalid name")
+        } else if new_name.contains(path::MAIN_SEPARATOR) {
+            RenameStatus::Conflict("the name can't contain a separator")
+        } else if !new_paths.insert(new_path.clone()) {
+            RenameStatus::Conflict("another file gets the same name")
+        } else if new_path.exists() && !is_case_change(old_path, &new_path) {
+            RenameStatus::Conflict("the new name is already used")
+        } else {
+            RenameStatus::Planned
+        };
+        renames.push(Rename {
+            old_path: old_path.clone(),
+            new_path,
+            status,
+        });
+    }
+    renames
+}
+
+/// tell whether the new name differs from the old one only by the case
+fn differs_only_by_case(old_name: &str, new_name: &str) -> bool {
+    old_name != new_name && old_name.to_lowercase() == new_name.to_lowercase()
+}
+
+/// tell whether the rename only changes the case of the name of a file, which
+/// is the case when no entry has exactly the new name in the directory (the
+/// new path "existing" because the file system is case-insensitive)
+fn is_case_change(old_path: &Path, new_path: &Path) -> bool {
+    if !differs_only_by_case(&file_name(old_path), &file_name(new_path)) {
+        return false;
+    }
+    let (dir, new_name) = match (new_path.parent(), new_path.file_name()) {
+        (Some(dir), Some(new_name)) => (dir, new_name),
+        _ => return false,
+    };
+    match fs::read_dir(dir) {
+        Ok(entries) => !entries
+            .filter_map(Result::ok)
+            .any(|entry| entry.file_name() == new_name),
+        Err(_) => false,
+    }
+}
+
+/// tell whether the new path is taken by another file than the old one,
+/// which a rename would replace
+pub fn is_taken(old_path: &Path, new_path: &Path) -> bool {
+    fs::symlink_metadata(new_path).is_ok() && !is_case_change(old_path, new_path)
+}
+
+/// rename the file, going through a temporary name when only the case
+/// changes, as some case-insensitive file systems would ignore it
+pub fn rename_file(old_path: &Path, new_path: &Path) -> io::Result<()> {
+    if !differs_only_by_case(&file_name(old_path), &file_name(new_path)) {
+        return fs::rename(old_path, new_path);
+    }
+    let tmp_name = format!("{}.broot-rename-{}", file_name(old_path), process::id());
+    let tmp_path = old_path.with_file_name(tmp_name);
+    fs::rename(old_path, &tmp_path)?;
+    fs::rename(&tmp_path, new_path).map_err(|e| {
+        // we try to restore the old name so that the file isn't lost
+        let _ = fs::rename(&tmp_path, old_path);
+        e
+    })
+}
+
+/// create the directory of the new path when it's missing, as
+/// when the files are moved to new folders by `:organize`
+fn create_parent(new_path: &Path) -> io::Result<()> {
+    match new_path.parent() {
+        Some(dir) if !dir.exists() => fs::create_dir_all(dir),
+        _ => Ok(()),
+    }
+}
+
+/// do the planned renames, children before their parents so that
+/// their paths stay valid, and return the number of failures
+pub fn apply(renames: &mut [Rename]) -> usize {
+    let mut failures = 0;
+    for rename in renames.iter_mut().rev() {
+        if !rename.status.is_planned() {
+            continue;
+        }
+        let renamed = create_parent(&rename.new_path)
+            .and_then(|()| rename_file(&rename.old_path, &rename.new_path));
+        rename.status = match renamed {
+            Ok(()) => RenameStatus::Done,
+            Err(e) => {
+                failures += 1;
+                let e = FileOpError::new(FileOp::Rename, &rename.old_path, e)
+                    .with_dest(&rename.new_path);
+                RenameStatus::Failed(e)
+            }
+        };
+    }
+    failures
+}
+
+#[cfg(test)]
+mod bulk_rename_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_plan() {
+        let paths: Vec<PathBuf> = ["IMG_1.jpg", "IMG_2.JPG", "img_1.jpg", "notes.txt"]
+            .iter()
+            .map(|name| PathBuf::from("/nonexistent").join(name))
+            .collect();
+        let regex = Regex::new(r"(?i)^img_(\d+)\.jpg$").unwrap();
+        let renames = plan(&paths, &regex, "photo-$1.jpg");
+        assert_eq!(renames.len(), 3);
+        assert_eq!(renames[0].new_name(), "photo-1.jpg");
+        assert!(renames[0].status.is_planned());
+        assert_eq!(renames[1].new_name(), "photo-2.jpg");
+        assert_eq!(renames[2].old_name(), "img_1.jpg");
+        assert!(matches!(renames[2].status, RenameStatus::Conflict(_)));
+        let regex = Regex::new(r"\.txt$").unwrap();
+        let renames = plan(&paths, &regex, "");
+        assert_eq!(renames[0].new_name(), "notes");
+        assert!(plan(&paths, &Regex::new("notes").unwrap(), "notes").is_empty());
+    }
+
+    #[test]
+    fn check_case_changes() {
+        assert!(differs_only_by_case("IMG_1.JPG", "img_1.jpg"));
+        assert!(differs_only_by_case("Été.txt", "été.txt"));
+        assert!(!differs_only_by_case("img_1.jpg", "img_1.jpg"));
+        assert!(!differs_only_by_case("IMG_1.JPG", "img_2.jpg"));
+    }
+}
@@ -11,7 +11,11 @@ pub fn clap_app() -> clap::App<'static, 'static> {
         .version(env!("CARGO_PKG_VERSION"))
         .author("dystroy <denys.seguret@gmail.com>")
         .about("Balanced tree view + fuzzy search + BFS + customizable launcher")
-        .arg(clap::Arg::with_name("root").help("sets the root directory"))
+        .arg(
+            clap::Arg::with_name("root")
+                .multiple(true)
+                .help("sets the root directory or the file to select (several ones may be given)"),
+        )
 
         // tree flags
 
@@ -27,6 +31,34 @@ pub fn clap_app() -> clap::App<'static, 'static> {
                 .long("no-dates")
                 .help("Don't show last modified date"),
         )
+        .arg(
+            clap::Arg::with_name("date-source")
+                .long("date-source")
+                .takes_value(true)
+                .value_name("date")
+                .possible_values(&["modified", "changed", "accessed", "created", "deep"])
+                .help("Which date to display and sort by"),
+        )
+        .arg(
+            clap::Arg::with_name("freshness")
+                .long("freshness")
+                .help("Color the names according to how recently they were modified"),
+        )
+        .arg(
+            clap::Arg::with_name("icons")
+                .long("icons")
+                .help("Write an icon before the names (needs a Nerd Font)"),
+        )
+        .arg(
+            clap::Arg::with_name("no-icons")
+                .long("no-icons")
+                .help("Don't write icons before the names"),
+        )
+        .arg(
+            clap::Arg::with_name("line-counts")
+                .long("line-counts")
+                .help("Show the number of lines of text files"),
+        )
         .arg(
             clap::Arg::with_name("only-folders")
                 .short("f")
@@ -51,6 +83,16 @@ pub fn clap_app() -> clap::App<'static, 'static> {
                 .long("no-show-git-info")
                 .help("Don't show git statuses on files"),
         )
+        .arg(
+            clap::Arg::with_name("git-dir-stats")
+                .long("git-dir-stats")
+                .help("Show on directories how many of their files have each git status"),
+        )
+        .arg(
+            clap::Arg::with_name("git-status")
+                .long("git-status")
+                .help("Only show files having an interesting git status, including hidden ones"),
+        )
         .arg(
             clap::Arg::with_name("hidden")
                 .short("h")
@@ -75,6 +117,16 @@ pub fn clap_app() -> clap::App<'static, 'static> {
                 .long("no-show-gitignored")
                 .help("Don't show gitignored files"),
         )
+        .arg(
+            clap::Arg::with_name("follow-links")
+                .long("follow-links")
+                .help("Search in symlinked directories too"),
+        )
+        .arg(
+            clap::Arg::with_name("no-follow-links")
+                .long("no-follow-links")
+                .help("Don't search in symlinked directories"),
+        )
         .arg(
             clap::Arg::with_name("permissions")
                 .short("p")
@@ -99,6 +151,49 @@ pub fn clap_app() -> clap::App<'static, 'static> {
                 .long("no-sizes")
                 .help("Don't show sizes"),
         )
+        .arg(
+            clap::Arg::with_name("modified")
+                .long("modified")
+                .takes_value(true)
+                .value_name("filter")
+                .help("Only show files whose last modification date matches the filter (eg '>2020-03-01' or 'older_than:180d')"),
+        )
+        .arg(
+            clap::Arg::with_name("sort-by")
+                .long("sort-by")
+                .takes_value(true)
+                .value_name("sort")
+                .possible_values(&["none", "count", "date", "size"])
+                .help("Sort the first level by count, date or size (only the first level is then displayed)"),
+        )
+        .arg(
+            clap::Arg::with_name("max-depth")
+                .long("max-depth")
+                .takes_value(true)
+                .value_name("depth")
+                .help("Don't display the directories deeper than this (0 for no limit)"),
+        )
+        .arg(
+            clap::Arg::with_name("min-depth")
+                .long("min-depth")
+                .takes_value(true)
+                .value_name("depth")
+                .help("Only display the entries at least this deep, and their ancestors"),
+        )
+        .arg(
+            clap::Arg::with_name("prune")
+                .long("prune")
+                .takes_value(true)
+                .value_name("globs")
+                .help("Semicolon separated globs of directories never entered, eg \".git;node_modules\""),
+        )
+        .arg(
+            clap::Arg::with_name("max-children")
+                .long("max-children")
+                .takes_value(true)
+                .value_name("count")
+                .help("List at most this number of children per directory (0 for no limit)"),
+        )
         .arg(
             clap::Arg::with_name("trim-root")
                 .short("t")
@@ -111,6 +206,11 @@ pub fn clap_app() -> clap::App<'static, 'static> {
                 .long("no-trim-root")
                 .help("Don't trim the root level, show a scrollbar"),
         )
+        .arg(
+            clap::Arg::with_name("watch")
+                .long("watch")
+                .help("Refresh the tree when files are created, removed or renamed"),
+        )
 
         // other options
 
@@ -120,6 +220,12 @@ pub fn clap_app() -> clap::App<'static, 'static> {
                 .takes_value(true)
                 .help("Where to write the produced cmd (if any)"),
         )
+        .arg(
+            clap::Arg::with_name("roots-export-path")
+                .long("outroots")
+                .takes_value(true)
+                .help("Where to append the roots visited during the session"),
+        )
         .arg(
             clap::Arg::with_name("commands")
                 .short("c")
@@ -127,6 +233,12 @@ pub fn clap_app() -> clap::App<'static, 'static> {
                 .takes_value(true)
                 .help("Semicolon separated commands to execute (experimental)"),
         )
+        .arg(
+            clap::Arg::with_name("pattern")
+                .long("pattern")
+                .takes_value(true)
+                .help("Search to apply at start, before the commands"),
+        )
         .arg(
             clap::Arg::with_name("conf")
                 .long("conf")
@@ -140,6 +252,35 @@ pub fn clap_app() -> clap::App<'static, 'static> {
                 .takes_value(true)
                 .help("Where to write the produced path (if any)"),
         )
+        .arg(
+            clap::Arg::with_name("listen")
+                .long("listen")
+                .takes_value(true)
+                .value_name("socket")
+                .help("Write the selection changes, executed verbs and final selection, as JSON lines, to the clients of this unix socket"),
+        )
+        .arg(
+            clap::Arg::with_name("out-format")
+                .long("out-format")
+                .takes_value(true)
+                .value_name("format")
+                .possible_values(&["json", "paths"])
+                .help("Don't open the interface but print the tree, after the --cmd commands, in that format"),
+        )
+        .arg(
+            clap::Arg::with_name("print-verbs")
+                .long("print-verbs")
+                .help("Don't open the interface but print the verbs, as defined by the configuration"),
+        )
+        .arg(
+            clap::Arg::with_name("verbs-format")
+                .long("format")
+                .takes_value(true)
+                .value_name("format")
+                .possible_values(&["json", "md", "tsv"])
+                .requires("print-verbs")
+                .help("The format of the verbs printed with --print-verbs (default: md)"),
+        )
         .arg(
             clap::Arg::with_name("height")
                 .long("height")
@@ -151,6 +292,16 @@ pub fn clap_app() -> clap::App<'static, 'static> {
                 .long("install")
                 .help("Install or reinstall the br shell function"),
         )
+        .arg(
+            clap::Arg::with_name("accessible")
+                .long("accessible")
+                .help("Draw without symbols and tell the selection in the status, for screen readers"),
+        )
+        .arg(
+            clap::Arg::with_name("no-alt-screen")
+                .long("no-alt-screen")
+                .help("Draw on the normal screen, so that the tree stays in the terminal on exit"),
+        )
         .arg(
             clap::Arg::with_name("no-style")
                 .long("no-style")
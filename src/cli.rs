@@ -9,24 +9,47 @@ use {
     crate::{
         app::App,
         app_context::AppContext,
-        conf::Conf,
+        collation,
+        conf::{self, Conf},
+        date_filter::DateFilter,
         errors::{ProgramError, TreeBuildError},
         external::Launchable,
+        file_metadata::Preserve,
+        file_sizes,
+        icons::Icons,
+        nested_launch::{self, NestedLaunch},
+        outcome_channel::OutcomeChannel,
+        path_display::PathDisplay,
+        recent_roots,
+        remote,
+        roots,
         shell_install::{
             ShellInstall,
             ShellInstallState,
         },
         skin,
+        skin_presets,
+        special_paths,
+        theme_mode::{self, ThemeMode},
+        sort::Sort,
+        tree_export::OutFormat,
         tree_options::TreeOptions,
+        verb_export::{self, VerbFormat},
         verb_store::VerbStore,
     },
     std::{
         env,
+        ffi::{OsStr, OsString},
         io,
         path::{Path, PathBuf},
+        time::Duration,
     },
 };
 
+/// seconds without user event after which the watch mode
+/// is paused, when the configuration doesn't tell
+const DEFAULT_WATCH_IDLE_PAUSE: i64 = 300;
+
 // launch arguments related to installation
 // (not used by the application after the first step)
 struct InstallLaunchArgs {
@@ -56,12 +79,46 @@ impl InstallLaunchArgs {
 /// life of the program
 pub struct AppLaunchArgs {
     pub root: PathBuf,                    // what should be the initial root
+    pub selection: Option<PathBuf>,       // the file broot was launched on, selected in the root
     pub file_export_path: Option<String>, // where to write the produced path (if required with --out)
     pub cmd_export_path: Option<String>, // where to write the produced command (if required with --outcmd)
+    pub roots_export_path: Option<String>, // where to append the visited roots (with --outroots)
     pub tree_options: TreeOptions,       // initial tree options
+    pub pattern: Option<String>,         // the search to start with, typed as in the input
     pub commands: Option<String>,        // commands passed as cli argument, still unparsed
     pub height: Option<u16>,             // an optional height to replace the screen's one
     pub no_style: bool,                  // whether to remove all styles (including colors)
+    pub start_screen: bool,              // whether to start on the list of bookmarks and recent roots
+    pub bookmarks: Vec<PathBuf>,         // the roots to propose first on the start screen
+    pub alt_screen: bool,                // whether to draw on the alternate screen
+    pub accessible: bool,                // whether the display is made for screen readers
+    pub set_terminal_title: bool,        // whether the terminal title shows the current root
+    pub mouse_capture: bool,             // whether mouse events are handled by broot
+    pub input_hints: bool,               // whether the input shows a placeholder and completions
+    pub path_display: PathDisplay,       // how the root and the paths of the status are written
+    pub theme_mode: ThemeMode,           // whether the terminal has a light background, at launch
+    pub skin_preset: String,             // the skin preset used on dark backgrounds
+    pub light_skin_preset: String,       // the skin preset used on light backgrounds
+    pub dot_pattern_shows_hidden: bool,  // whether a pattern starting with a dot finds hidden files
+    pub watch_idle_pause: Option<Duration>, // inactivity after which the watch mode is paused
+    pub persist_input_history: bool, // whether the input history is kept between sessions
+    pub persist_queue: bool, // whether the queued commands are kept between sessions
+    pub usage_stats: bool, // whether the uses of the verbs and patterns are counted
+    pub max_copy_rate: Option<u64>, // in bytes per second, for the copies done by :cp
+    pub copy_preserve: Preserve, // the metadata kept by the copies done by :cp
+    pub protected_paths: Vec<PathBuf>, // where destructive verbs must be confirmed
+    pub project_conf: Option<PathBuf>, // the .broot file of the project, if any
+    pub project_conf_trusted: bool, // whether the verbs of the project_conf were added
+}
+
+impl AppLaunchArgs {
+    /// return the name of the skin preset to use in this theme mode
+    pub fn skin_preset_for(&self, mode: ThemeMode) -> &str {
+        match mode {
+            ThemeMode::Dark => &self.skin_preset,
+            ThemeMode::Light => &self.light_skin_preset,
+        }
+    }
 }
 
 #[cfg(not(windows))]
@@ -78,10 +135,30 @@ fn canonicalize_root(root: &Path) -> io::Result<PathBuf> {
     })
 }
 
-fn get_root_path(cli_args: &ArgMatches<'_>) -> Result<PathBuf, ProgramError> {
-    let mut root = cli_args
-        .value_of("root")
-        .map_or(env::current_dir()?, PathBuf::from);
+/// return the canonicalized roots passed as arguments, or the current
+/// directory if there's none
+fn get_root_paths(cli_args: &ArgMatches<'_>) -> Result<Vec<PathBuf>, ProgramError> {
+    match cli_args.values_of("root") {
+        Some(values) => values.map(|v| check_root_path(PathBuf::from(v))).collect(),
+        None => Ok(vec![check_root_path(env::current_dir()?)?]),
+    }
+}
+
+/// return the name of the file given as only root argument, as
+/// broot opens its parent and selects it
+fn get_file_arg_name(cli_args: &ArgMatches<'_>) -> Option<OsString> {
+    let mut values = cli_args.values_of("root")?;
+    let path = PathBuf::from(values.next()?);
+    if values.next().is_some() || remote::is_remote(&path) || !path.is_file() {
+        return None;
+    }
+    path.file_name().map(OsStr::to_os_string)
+}
+
+fn check_root_path(mut root: PathBuf) -> Result<PathBuf, ProgramError> {
+    if remote::is_remote(&root) {
+        return Ok(root); // it will be checked when connecting
+    }
     if !root.exists() {
         Err(TreeBuildError::FileNotFound {
             path: format!("{:?}", &root),
@@ -132,8 +209,8 @@ pub fn run() -> Result<Option<Launchable>, ProgramError> {
         .map(|s| s.split(';').map(PathBuf::from).collect());
 
     // if we don't run on a specific config file, we check the
-    // configuration
-    if specific_conf.is_none() {
+    // configuration (unless we're just asked to print the verbs)
+    if specific_conf.is_none() && !cli_matches.is_present("print-verbs") {
         let mut shell_install = ShellInstall::new(install_args.install);
         shell_install.check()?;
         if shell_install.should_quit {
@@ -143,7 +220,7 @@ pub fn run() -> Result<Option<Launchable>, ProgramError> {
 
     // read the configuration file(s): either the standard one
     // or the ones required by the launch args
-    let config = match &specific_conf {
+    let mut config = match &specific_conf {
         Some(conf_paths) => {
             let mut conf = Conf::default();
             for path in conf_paths {
@@ -160,6 +237,33 @@ pub fn run() -> Result<Option<Launchable>, ProgramError> {
     // found in the config file(s) (if any) then overriden
     // by the cli args
     let mut tree_options = TreeOptions::default();
+    tree_options.follow_links = config.search_symlinked_dirs;
+    tree_options.prune = special_paths::prune_paths(&config.prune);
+    collation::init(config.locale_collation);
+    if let Some(tie_breaker) = config.tie_breaker {
+        tree_options.tie_breaker = tie_breaker;
+    }
+    if let Some(cols_order) = &config.cols_order {
+        tree_options.cols_order = cols_order.clone();
+    }
+    if let Some(cols_priority) = &config.cols_priority {
+        tree_options.cols_priority = cols_priority.clone();
+    }
+    if let Some(show_icons) = config.show_icons {
+        tree_options.show_icons = show_icons;
+    }
+    if let Some(align_size_units) = config.align_size_units {
+        tree_options.align_size_units = align_size_units;
+    }
+    if let Some(date_source) = config.date_source {
+        tree_options.date_source = date_source;
+    }
+    if let Some(sort) = config.default_sort {
+        tree_options.sort = sort;
+        if sort == Sort::Size {
+            tree_options.show_sizes = true;
+        }
+    }
     if !config.default_flags.is_empty() {
         debug!("Applying default flags {:?} from conf", &config.default_flags);
         let clap_app = crate::clap::clap_app()
@@ -170,33 +274,199 @@ pub fn run() -> Result<Option<Launchable>, ProgramError> {
         debug!("modified tree options: {:?}", &tree_options);
     }
     tree_options.apply(&cli_matches);
+    if let Some(date_filter) = cli_matches.value_of("modified") {
+        tree_options.date_filter = DateFilter::from_str(date_filter)?;
+    }
+
+    // when several roots are given, we display their common ancestor
+    // but only the branches leading to them
+    let mut root_paths = get_root_paths(&cli_matches)?;
+    roots::dedupe(&mut root_paths);
+    let root = if root_paths.len() > 1 {
+        tree_options.roots = root_paths.clone();
+        roots::common_ancestor(&root_paths)
+    } else {
+        root_paths.remove(0)
+    };
+    let selection = get_file_arg_name(&cli_matches).map(|name| root.join(name));
+
+    // the verbs and special paths of the project containing the
+    // root, if it has a .broot file in a trusted directory, are
+    // added to the user's ones
+    let project_conf = if remote::is_remote(&root) {
+        None
+    } else {
+        conf::find_project_conf(&root)
+    };
+    let project_conf_trusted = match &project_conf {
+        Some(path) if config.is_trusted_project(path) => {
+            info!("reading project configuration {:?}", path);
+            if let Err(e) = config.read_project_file(path) {
+                eprintln!("Failed to read project configuration in {:?}.", path);
+                return Err(e.into());
+            }
+            true
+        }
+        Some(path) => {
+            info!("not reading the untrusted project configuration {:?}", path);
+            false
+        }
+        None => false,
+    };
 
     // verb store is completed from the config file(s)
     let mut verb_store = VerbStore::new();
     verb_store.init(&config);
+    if cli_matches.is_present("print-verbs") {
+        let format = cli_matches
+            .value_of("verbs-format")
+            .and_then(VerbFormat::from_name)
+            .unwrap_or(VerbFormat::Md);
+        return Ok(Some(Launchable::printer(verb_export::export(&verb_store.verbs, format))));
+    }
 
     // reading the other arguments
     let file_export_path = cli_matches.value_of("file-export-path").map(str::to_string);
     let cmd_export_path = cli_matches.value_of("cmd-export-path").map(str::to_string);
+    let roots_export_path = cli_matches.value_of("roots-export-path").map(str::to_string);
+    let pattern = cli_matches.value_of("pattern").map(str::to_string);
     let commands = cli_matches.value_of("commands").map(str::to_string);
     let no_style = cli_matches.is_present("no-style");
     let height = cli_matches.value_of("height").and_then(|s| s.parse().ok());
+    let out_format = cli_matches.value_of("out-format").and_then(OutFormat::from_name);
+    let alt_screen = !cli_matches.is_present("no-alt-screen") && config.alt_screen.unwrap_or(true);
 
-    let root = get_root_path(&cli_matches)?;
+    // a broot launched from a verb of another broot may hand the
+    // path to the outer one instead of starting a second interface
+    if out_format.is_none() && commands.is_none() && !cli_matches.is_present("listen") {
+        if let Some(socket_path) = nested_launch::outer_socket() {
+            let path = selection.as_ref().unwrap_or(&root);
+            let send = match config.nested_launch {
+                NestedLaunch::Ask => nested_launch::ask_send(path)?,
+                NestedLaunch::Send => true,
+                NestedLaunch::Start => false,
+            };
+            if send {
+                match nested_launch::send_path(&socket_path, path) {
+                    Ok(()) => {
+                        return Ok(None);
+                    }
+                    Err(e) => {
+                        warn!("failed to send {:?} to the outer broot: {}", path, e);
+                    }
+                }
+            }
+        }
+    }
+    let watch_idle_pause = match config.watch_idle_pause.unwrap_or(DEFAULT_WATCH_IDLE_PAUSE) {
+        seconds if seconds > 0 => Some(Duration::from_secs(seconds as u64)),
+        _ => None,
+    };
+    let outcome_channel = match cli_matches.value_of("listen") {
+        Some(socket_path) => Some(OutcomeChannel::bind(PathBuf::from(socket_path))?),
+        None => None,
+    };
+
+    // the start screen replaces the tree when no path is given, and
+    // it lists the roots broot was launched on
+    let start_screen = config.start_screen
+        && cli_matches.value_of("root").is_none()
+        && out_format.is_none();
+    if config.start_screen && !start_screen {
+        if let Err(e) = recent_roots::remember(&root) {
+            warn!("failed to remember {:?} as recent root: {:?}", &root, e);
+        }
+    }
+
+    // the background is only detected when there's a screen
+    let theme_mode = config
+        .theme_mode
+        .or_else(|| if out_format.is_none() && !no_style { theme_mode::detect() } else { None })
+        .unwrap_or(ThemeMode::Dark);
+
+    // a skin_preset set alone is used on all backgrounds
+    let light_skin_preset = config
+        .light_skin_preset
+        .clone()
+        .or_else(|| config.skin_preset.clone())
+        .unwrap_or_else(|| "light".to_string());
 
     let launch_args = AppLaunchArgs {
         root,
+        selection,
         file_export_path,
         cmd_export_path,
+        roots_export_path,
         tree_options,
+        pattern,
         commands,
         height,
         no_style,
+        start_screen,
+        bookmarks: config.bookmarks,
+        alt_screen,
+        accessible: cli_matches.is_present("accessible") || config.accessible,
+        set_terminal_title: config.set_terminal_title,
+        mouse_capture: config.mouse_capture.unwrap_or(true),
+        input_hints: config.input_hints.unwrap_or(true),
+        path_display: PathDisplay {
+            abbreviate_home: config.abbreviate_home.unwrap_or(true),
+            shorten: config.shorten_paths.unwrap_or(false),
+        },
+        dot_pattern_shows_hidden: config.dot_pattern_shows_hidden.unwrap_or(true),
+        watch_idle_pause,
+        persist_input_history: config.persist_input_history,
+        persist_queue: config.persist_queue,
+        usage_stats: config.usage_stats,
+        max_copy_rate: config
+            .max_copy_rate
+            .filter(|&rate| rate > 0)
+            .map(|rate| rate as u64 * 1_000_000),
+        copy_preserve: config.copy_preserve.unwrap_or_default(),
+        protected_paths: config.protected_paths.unwrap_or_else(|| {
+            vec![PathBuf::from("/"), PathBuf::from("/etc"), PathBuf::from("/usr")]
+        }),
+        project_conf,
+        project_conf_trusted,
+        theme_mode,
+        skin_preset: config.skin_preset.unwrap_or_else(|| "default".to_string()),
+        light_skin_preset,
     };
 
-    let context = AppContext::from(launch_args, verb_store);
-    let skin = skin::Skin::create(config.skin);
-    App::new().run(crate::io::writer(), &context, skin)
+    let skin_preset = launch_args.skin_preset_for(theme_mode);
+    let skin_entries = match skin_presets::entries(skin_preset, &config.skin) {
+        Some(entries) => entries,
+        None => {
+            eprintln!(
+                "Unknown skin preset {:?} (available ones are {})",
+                skin_preset,
+                skin_presets::names(),
+            );
+            config.skin.clone()
+        }
+    };
+    let skin = skin::Skin::create(skin_entries);
+    let icons = Icons::new(&config.icons);
+    let context = AppContext::from(
+        launch_args,
+        verb_store,
+        config.special_paths,
+        config.trash_commands,
+        config.organize_rules,
+        config.profiles,
+        config.skin,
+        icons,
+        outcome_channel,
+        config.settings,
+    );
+    let launchable = match out_format {
+        Some(out_format) => App::new().run_headless(&context, skin, out_format),
+        None => App::new().run(crate::io::writer(), &context, skin),
+    };
+    if let Err(e) = file_sizes::save_persisted_sizes() {
+        warn!("failed to save the sizes of directories: {:?}", e);
+    }
+    launchable
 }
 
 /// wait for user input, return `true` if she
@@ -0,0 +1,335 @@
+//! count the lines of code, comments and blanks of source files,
+//! for the languages recognized by their extension.
+//!
+//! This is a light counter: strings aren't parsed so a comment
+//! marker in a string literal may be taken for a real one.
+
+use {
+    std::{
+        cmp::Reverse,
+        fs,
+        ops::AddAssign,
+        path::Path,
+    },
+};
+
+/// how comments are written in a language
+pub struct Language {
+    pub name: &'static str,
+    extensions: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+}
+
+const C_BLOCK: Option<(&str, &str)> = Some(("/*", "*/"));
+
+pub static LANGUAGES: &[Language] = &[
+    Language {
+        name: "C",
+        extensions: &["c", "h"],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "C++",
+        extensions: &["cpp", "cc", "cxx", "hpp", "hh", "hxx"],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "C#",
+        extensions: &["cs"],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "CSS",
+        extensions: &["css", "scss"],
+        line_comments: &[],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "Go",
+        extensions: &["go"],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "Haskell",
+        extensions: &["hs"],
+        line_comments: &["--"],
+        block_comment: Some(("{-", "-}")),
+    },
+    Language {
+        name: "HTML",
+        extensions: &["html", "htm", "xml"],
+        line_comments: &[],
+        block_comment: Some(("<!--", "-->")),
+    },
+    Language {
+        name: "Java",
+        extensions: &["java"],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "JavaScript",
+        extensions: &["js", "mjs", "jsx"],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "JSON",
+        extensions: &["json"],
+        line_comments: &[],
+        block_comment: None,
+    },
+    Language {
+        name: "Kotlin",
+        extensions: &["kt", "kts"],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "Lua",
+        extensions: &["lua"],
+        line_comments: &["--"],
+        block_comment: Some(("--[[", "]]")),
+    },
+    Language {
+        name: "Markdown",
+        extensions: &["md"],
+        line_comments: &[],
+        block_comment: None,
+    },
+    Language {
+        name: "Python",
+        extensions: &["py"],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+    Language {
+        name: "Ruby",
+        extensions: &["rb"],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+    Language {
+        name: "Rust",
+        extensions: &["rs"],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "Shell",
+        extensions: &["sh", "bash", "zsh", "fish"],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+    Language {
+        name: "SQL",
+        extensions: &["sql"],
+        line_comments: &["--"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "Swift",
+        extensions: &["swift"],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "TOML",
+        extensions: &["toml"],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+    Language {
+        name: "TypeScript",
+        extensions: &["ts", "tsx"],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "YAML",
+        extensions: &["yml", "yaml"],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+];
+
+/// return the language of the file, based on its extension
+pub fn language_of(path: &Path) -> Option<&'static Language> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|lang| lang.extensions.contains(&ext.as_str()))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LineStats {
+    pub code: usize,
+    pub comments: usize,
+    pub blanks: usize,
+}
+
+impl LineStats {
+    pub fn lines(&self) -> usize {
+        self.code + self.comments + self.blanks
+    }
+}
+
+impl AddAssign for LineStats {
+    fn add_assign(&mut self, other: Self) {
+        self.code += other.code;
+        self.comments += other.comments;
+        self.blanks += other.blanks;
+    }
+}
+
+impl Language {
+    /// count the code, comment and blank lines of a text.
+    ///
+    /// A line with both code and a comment counts as code.
+    pub fn count(&self, text: &str) -> LineStats {
+        let mut stats = LineStats::default();
+        let mut in_block = false;
+        for line in text.lines() {
+            let line = line.trim();
+            if in_block {
+                stats.comments += 1;
+                if let Some((_, end)) = self.block_comment {
+                    in_block = !line.contains(end);
+                }
+            } else if line.is_empty() {
+                stats.blanks += 1;
+            } else if self.line_comments.iter().any(|lc| line.starts_with(lc))
+                && !self.block_comment.map_or(false, |(start, _)| line.starts_with(start))
+            {
+                stats.comments += 1;
+            } else {
+                match self.block_comment {
+                    Some((start, end)) if line.starts_with(start) => {
+                        stats.comments += 1;
+                        in_block = !line[start.len()..].contains(end);
+                    }
+                    Some((start, end)) => {
+                        stats.code += 1;
+                        if let Some(idx) = line.find(start) {
+                            in_block = !line[idx + start.len()..].contains(end);
+                        }
+                    }
+                    None => {
+                        stats.code += 1;
+                    }
+                }
+            }
+        }
+        stats
+    }
+}
+
+/// return the language and line stats of a source file, or None if
+/// it's not in a recognized language or not readable as text
+pub fn file_stats(path: &Path) -> Option<(&'static Language, LineStats)> {
+    let language = language_of(path)?;
+    let text = fs::read_to_string(path).ok()?;
+    Some((language, language.count(&text)))
+}
+
+/// the stats of the files of one language
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LanguageStats {
+    pub files: usize,
+    pub lines: LineStats,
+}
+
+/// the stats of a set of files, by language
+#[derive(Debug, Clone, Default)]
+pub struct CodeStats {
+    pub languages: Vec<(&'static str, LanguageStats)>, // sorted by decreasing code lines
+}
+
+impl CodeStats {
+    pub fn add(&mut self, language: &Language, lines: LineStats) {
+        let idx = match self
+            .languages
+            .iter()
+            .position(|(name, _)| *name == language.name)
+        {
+            Some(idx) => idx,
+            None => {
+                self.languages.push((language.name, LanguageStats::default()));
+                self.languages.len() - 1
+            }
+        };
+        let stats = &mut self.languages[idx].1;
+        stats.files += 1;
+        stats.lines += lines;
+        self.languages
+            .sort_by_key(|(_, stats)| Reverse(stats.lines.code));
+    }
+    pub fn total(&self) -> LanguageStats {
+        let mut total = LanguageStats::default();
+        for (_, stats) in &self.languages {
+            total.files += stats.files;
+            total.lines += stats.lines;
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod code_stats_tests {
+
+    use {
+        super::*,
+        std::path::Path,
+    };
+
+    #[test]
+    fn check_language_of() {
+        assert_eq!(language_of(Path::new("src/main.rs")).unwrap().name, "Rust");
+        assert_eq!(language_of(Path::new("a/b.Py")).unwrap().name, "Python");
+        assert!(language_of(Path::new("README")).is_none());
+        assert!(language_of(Path::new("image.png")).is_none());
+    }
+
+    #[test]
+    fn check_rust_count() {
+        let rust = language_of(Path::new("a.rs")).unwrap();
+        let text = r#"
+//! a module
+
+/* a block
+   comment */
+fn main() { // with a comment
+    let a = 2; /* starting
+    a block */
+    /* one line block */
+}
+"#;
+        assert_eq!(
+            rust.count(text),
+            LineStats {
+                code: 3,
+                comments: 5,
+                blanks: 2,
+            },
+        );
+    }
+
+    #[test]
+    fn check_lua_count() {
+        let lua = language_of(Path::new("a.lua")).unwrap();
+        let text = "--[[ a\nblock ]]\n-- a line\nprint(1)\n";
+        assert_eq!(
+            lua.count(text),
+            LineStats {
+                code: 1,
+                comments: 3,
+                blanks: 0,
+            },
+        );
+    }
+}
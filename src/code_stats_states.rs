@@ -0,0 +1,337 @@
+use {
+    crate::{
+        app_context::AppContext,
+        app_state::{AppState, AppStateCmdResult},
+        code_stats::{self, CodeStats},
+        code_stats_verbs,
+        commands::{Action, Command},
+        errors::ProgramError,
+        git_ignore::{GitIgnoreChain, GitIgnorer},
+        help_content::ScreenHelp,
+        help_states::HelpState,
+        screens::Screen,
+        special_paths::{self, SpecialHandling},
+        status::Status,
+        task_sync::Dam,
+        tree_options::TreeOptions,
+        verb_store::PrefixSearchResult,
+        verbs::VerbExecutor,
+    },
+    crossterm::{
+        terminal::{Clear, ClearType},
+        QueueableCommand,
+    },
+    minimad::{Composite, TextTemplate},
+    std::{
+        fs,
+        io::Write,
+        path::{Path, PathBuf},
+        time::{Duration, Instant},
+    },
+    termimad::{Area, FmtText, TextView},
+};
+
+static MD: &str = r#"
+# Code stats of ${root}
+|:-|:-:|:-:|:-:|:-:|:-:
+|**language**|**files**|**code**|**comments**|**blanks**|**lines**
+|:-|-:|-:|-:|-:|-:
+${language-rows
+|${language}|${files}|${code}|${comments}|${blanks}|${lines}
+}
+|-
+|**total**|${files}|${code}|${comments}|${blanks}|${lines}
+|-
+"#;
+
+/// how long a pending task may run before the screen is updated
+const TASK_DURATION: Duration = Duration::from_millis(100);
+
+/// an application state displaying the lines of code, comments and
+/// blanks of a file or of all the source files of a directory, by
+/// language.
+///
+/// The files are read in pending tasks so that the stats grow
+/// on screen while the directory is explored.
+pub struct CodeStatsState {
+    root: PathBuf,
+    tree_options: TreeOptions, // hidden and gitignored files are skipped according to them
+    git_ignorer: GitIgnorer,
+    dirs: Vec<(PathBuf, GitIgnoreChain)>, // directories still to be read
+    files: Vec<PathBuf>,                  // source files still to be counted
+    stats: CodeStats,
+    pub scroll: i32,
+    pub area: Area,
+    dirty: bool, // background must be cleared
+}
+
+impl CodeStatsState {
+    pub fn new(root: PathBuf, tree_options: TreeOptions) -> CodeStatsState {
+        let mut state = CodeStatsState {
+            root,
+            tree_options,
+            git_ignorer: GitIgnorer::new(),
+            dirs: Vec::new(),
+            files: Vec::new(),
+            stats: CodeStats::default(),
+            scroll: 0,
+            area: Area::uninitialized(), // will be fixed at drawing time
+            dirty: true,
+        };
+        state.start_computation();
+        state
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// forget the stats and start again reading the root
+    fn start_computation(&mut self) {
+        self.stats = CodeStats::default();
+        self.dirs.clear();
+        self.files.clear();
+        if self.root.is_dir() {
+            let chain = self.git_ignorer.root_chain(&self.root);
+            self.dirs.push((self.root.clone(), chain));
+        } else {
+            self.files.push(self.root.clone());
+        }
+    }
+
+    /// push the children of the directory which must be counted
+    fn read_dir(&mut self, dir: &Path, chain: &GitIgnoreChain, con: &AppContext) {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                debug!("can't read {:?}: {:?}", dir, e);
+                return;
+            }
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(_) => continue,
+            };
+            if file_type.is_symlink() {
+                continue; // links aren't followed, as they could make loops
+            }
+            let special_handling = special_paths::handling(&con.special_paths, &path);
+            if special_handling == SpecialHandling::Hide {
+                continue;
+            }
+            if special_handling != SpecialHandling::Show {
+                if !self.tree_options.show_hidden && name.starts_with('.') {
+                    continue;
+                }
+                if self.tree_options.respect_git_ignore
+                    && !self.git_ignorer.accepts(chain, &path, &name, file_type.is_dir())
+                {
+                    continue;
+                }
+            }
+            if file_type.is_dir() {
+                if special_handling != SpecialHandling::NoEnter {
+                    let chain = self.git_ignorer.deeper_chain(chain, &path);
+                    self.dirs.push((path, chain));
+                }
+            } else if code_stats::language_of(&path).is_some() {
+                self.files.push(path);
+            }
+        }
+    }
+}
+
+impl AppState for CodeStatsState {
+    fn help(&self) -> &'static ScreenHelp {
+        &code_stats_verbs::HELP
+    }
+
+    fn has_pending_task(&self) -> bool {
+        !self.dirs.is_empty() || !self.files.is_empty()
+    }
+
+    fn can_execute(&self, _verb_index: usize, _con: &AppContext) -> bool {
+        true
+    }
+
+    fn apply(
+        &mut self,
+        cmd: &mut Command,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<AppStateCmdResult, ProgramError> {
+        Ok(match &cmd.action {
+            Action::Back => AppStateCmdResult::PopState,
+            Action::Help => AppStateCmdResult::NewState(
+                Box::new(HelpState::new(screen, con, &code_stats_verbs::HELP)),
+                Command::new(),
+            ),
+            Action::MoveSelection(dy) | Action::Scroll(dy) => {
+                self.scroll += *dy;
+                AppStateCmdResult::Keep
+            }
+            Action::Resize(w, h) => {
+                screen.set_terminal_size(*w, *h, con);
+                self.dirty = true;
+                AppStateCmdResult::Keep
+            }
+            Action::VerbIndex(index) => {
+                let verb = &con.verb_store.verbs[*index];
+                self.execute_verb(verb, &verb.invocation, screen, con)?
+            }
+            Action::VerbInvocate(invocation) => match con.verb_store.search(&invocation.name) {
+                PrefixSearchResult::Match(verb) => {
+                    self.execute_verb(verb, &invocation, screen, con)?
+                }
+                _ => AppStateCmdResult::verb_not_found(&invocation.name),
+            },
+            _ => AppStateCmdResult::Keep,
+        })
+    }
+
+    /// count the lines again, as files may have changed
+    fn refresh(&mut self, _screen: &Screen, _con: &AppContext) -> Command {
+        self.dirty = true;
+        self.start_computation();
+        Command::new()
+    }
+
+    /// read directories and count files until the dam asks for
+    /// interruption or it's time to show the progress
+    fn do_pending_task(&mut self, _screen: &mut Screen, con: &AppContext, dam: &mut Dam) {
+        let start = Instant::now();
+        while start.elapsed() < TASK_DURATION && !dam.has_event() {
+            if let Some(file) = self.files.pop() {
+                if let Some((language, lines)) = code_stats::file_stats(&file) {
+                    self.stats.add(language, lines);
+                }
+            } else if let Some((dir, chain)) = self.dirs.pop() {
+                self.read_dir(&dir, &chain, con);
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn display(
+        &mut self,
+        mut w: &mut dyn Write,
+        screen: &Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        if self.dirty {
+            screen.skin.default.queue_bg(&mut w)?;
+            screen.clear(&mut w)?;
+            self.area = Area::new(0, 0, screen.width, screen.height - 2);
+            self.area.pad_for_max_width(110);
+            self.dirty = false;
+        }
+        lazy_static! {
+            static ref TEMPLATE: TextTemplate<'static> = TextTemplate::from(MD);
+        }
+        let root = self.root.to_string_lossy();
+        let rows: Vec<Vec<String>> = self
+            .stats
+            .languages
+            .iter()
+            .map(|(name, stats)| {
+                vec![
+                    name.to_string(),
+                    stats.files.to_string(),
+                    stats.lines.code.to_string(),
+                    stats.lines.comments.to_string(),
+                    stats.lines.blanks.to_string(),
+                    stats.lines.lines().to_string(),
+                ]
+            })
+            .collect();
+        let total = self.stats.total();
+        let total_row = vec![
+            total.files.to_string(),
+            total.lines.code.to_string(),
+            total.lines.comments.to_string(),
+            total.lines.blanks.to_string(),
+            total.lines.lines().to_string(),
+        ];
+        let mut expander = TEMPLATE.expander();
+        expander
+            .set("root", &root)
+            .set("files", &total_row[0])
+            .set("code", &total_row[1])
+            .set("comments", &total_row[2])
+            .set("blanks", &total_row[3])
+            .set("lines", &total_row[4]);
+        for row in &rows {
+            expander
+                .sub("language-rows")
+                .set("language", &row[0])
+                .set("files", &row[1])
+                .set("code", &row[2])
+                .set("comments", &row[3])
+                .set("blanks", &row[4])
+                .set("lines", &row[5]);
+        }
+        let text = expander.expand();
+        let fmt_text = FmtText::from_text(
+            &screen.help_skin,
+            text,
+            Some((self.area.width - 1) as usize),
+        );
+        let mut text_view = TextView::from(&self.area, &fmt_text);
+        self.scroll = text_view.set_scroll(self.scroll);
+        Ok(text_view.write_on(&mut w)?)
+    }
+
+    fn write_status(
+        &self,
+        mut w: &mut dyn Write,
+        cmd: &Command,
+        screen: &Screen,
+        con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        match &cmd.action {
+            Action::VerbEdit(invocation) if !invocation.name.is_empty() => {
+                match con.verb_store.search(&invocation.name) {
+                    PrefixSearchResult::Match(verb) => verb.write_status(
+                        &mut w,
+                        None,
+                        self.root.clone(),
+                        None,
+                        invocation,
+                        screen,
+                    ),
+                    _ => Status::from_error(mad_inline!("No matching verb"))
+                        .display(&mut w, screen),
+                }
+            }
+            _ => {
+                let task = if self.has_pending_task() {
+                    Some("counting lines")
+                } else {
+                    None
+                };
+                let hint = format!(
+                    "{}, or a space to start a verb",
+                    code_stats_verbs::HELP.keys_hint(),
+                );
+                Status::new(task, Composite::from_inline(&hint), false).display(&mut w, screen)
+            }
+        }
+    }
+
+    /// there's no meaningful flags here
+    fn write_flags(
+        &self,
+        mut w: &mut dyn Write,
+        screen: &mut Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        screen.skin.default.queue_bg(&mut w)?;
+        w.queue(Clear(ClearType::UntilNewLine))?;
+        Ok(())
+    }
+}
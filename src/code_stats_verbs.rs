@@ -0,0 +1,80 @@
+//! this modules defines the execution of verbs on the code stats screen
+
+use crate::{
+    app_context::AppContext,
+    app_state::AppStateCmdResult,
+    code_stats_states::CodeStatsState,
+    commands::Command,
+    errors::ProgramError,
+    external,
+    help_content::{ScreenHelp, ScreenKey},
+    help_states::HelpState,
+    screens::Screen,
+    verb_invocation::VerbInvocation,
+    verbs::{Verb, VerbExecutor},
+};
+
+/// the help of the code statistics screen
+pub static HELP: ScreenHelp = ScreenHelp {
+    name: "code statistics screen",
+    description: "This screen tells the number of files and lines of code by language.",
+    keys: &[
+        ScreenKey::hinted("esc", "get back to the tree", "to get back to the tree"),
+        ScreenKey::new("↑ ↓", "scroll"),
+        ScreenKey::new("?", "display this help"),
+    ],
+    builtins: Some(&[
+        ":back", ":code_stats", ":refresh", ":help", ":line_down", ":line_up", ":page_down",
+        ":page_up", ":print_path", ":print_relative_path", ":quit", ":focus_user_home",
+        ":focus_root", ":toggle_*",
+    ]),
+    external_verbs: true,
+};
+
+impl VerbExecutor for CodeStatsState {
+    fn execute_verb(
+        &mut self,
+        verb: &Verb,
+        invocation: &VerbInvocation,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<AppStateCmdResult, ProgramError> {
+        if let Some(err) = verb.match_error(invocation) {
+            return Ok(AppStateCmdResult::DisplayError(err));
+        }
+        if let Some(err) = HELP.unlisted_error(verb) {
+            return Ok(AppStateCmdResult::DisplayError(err));
+        }
+        Ok(match verb.execution.as_ref() {
+            ":back" => AppStateCmdResult::PopState,
+            ":code_stats" | ":refresh" => AppStateCmdResult::RefreshState { clear_cache: false },
+            ":help" => AppStateCmdResult::NewState(
+                Box::new(HelpState::new(screen, con, &HELP)),
+                Command::new(),
+            ),
+            ":line_down" => {
+                self.scroll += 1;
+                AppStateCmdResult::Keep
+            }
+            ":line_up" => {
+                self.scroll -= 1;
+                AppStateCmdResult::Keep
+            }
+            ":page_down" => {
+                self.scroll += self.area.height as i32;
+                AppStateCmdResult::Keep
+            }
+            ":page_up" => {
+                self.scroll -= self.area.height as i32;
+                AppStateCmdResult::Keep
+            }
+            ":print_path" => external::print_path(self.root(), con)?,
+            ":print_relative_path" => external::print_relative_path(self.root(), con)?,
+            ":quit" => AppStateCmdResult::Quit,
+            ":focus_user_home" | ":focus_root" => AppStateCmdResult::PopStateAndReapply,
+            _ if verb.execution.starts_with(":toggle") => AppStateCmdResult::PopStateAndReapply,
+            _ if verb.execution.starts_with(':') => AppStateCmdResult::Keep,
+            _ => verb.to_cmd_result(self.root(), None, &invocation.args, screen, con)?,
+        })
+    }
+}
@@ -0,0 +1,81 @@
+//! the comparison of the names of files, which decides their order in
+//! the tree.
+//!
+//! By default the lowercased names are compared character by character,
+//! which puts the accented letters after `z`. With `locale_collation`
+//! set in the configuration, the collation of the locale (`LC_COLLATE`)
+//! is used instead, which is slower. It's only available on unix.
+
+use std::{
+    cmp::Ordering,
+    sync::atomic::{AtomicBool, Ordering as AtomicOrdering},
+};
+
+static LOCALE_COLLATION: AtomicBool = AtomicBool::new(false);
+
+/// set whether the names are compared with the collation of the
+/// locale. Must be called before other threads are started.
+pub fn init(locale_collation: bool) {
+    if locale_collation && !set_collation_locale() {
+        warn!("the collation of the locale can't be used, names are sorted by characters");
+        return;
+    }
+    LOCALE_COLLATION.store(locale_collation, AtomicOrdering::Relaxed);
+}
+
+/// load the collation of the locale given by the environment, which
+/// is useless when it's the C one, as it compares the bytes
+#[cfg(unix)]
+fn set_collation_locale() -> bool {
+    let empty = b"\0".as_ptr() as *const libc::c_char; // the locale of the environment
+    let locale = unsafe { libc::setlocale(libc::LC_COLLATE, empty) };
+    if locale.is_null() {
+        return false;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(locale) }.to_string_lossy();
+    debug!("collation locale: {:?}", name);
+    !(name == "C" || name == "POSIX" || name.starts_with("C."))
+}
+
+#[cfg(not(unix))]
+fn set_collation_locale() -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn locale_compare(a: &str, b: &str) -> Option<Ordering> {
+    let a = std::ffi::CString::new(a).ok()?;
+    let b = std::ffi::CString::new(b).ok()?;
+    Some(unsafe { libc::strcoll(a.as_ptr(), b.as_ptr()) }.cmp(&0))
+}
+
+#[cfg(not(unix))]
+fn locale_compare(_a: &str, _b: &str) -> Option<Ordering> {
+    None
+}
+
+/// compare two names, the ones which are equal for the collation
+/// being ordered by their characters so that the order is total
+pub fn compare_names(a: &str, b: &str) -> Ordering {
+    let ordering = if LOCALE_COLLATION.load(AtomicOrdering::Relaxed) {
+        locale_compare(a, b)
+    } else {
+        None
+    };
+    ordering
+        .unwrap_or_else(|| a.to_lowercase().cmp(&b.to_lowercase()))
+        .then_with(|| a.cmp(b))
+}
+
+#[cfg(test)]
+mod collation_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_default_order() {
+        let mut names = vec!["b", "été", "A", "a", "B", "zoo"];
+        names.sort_by(|a, b| compare_names(a, b));
+        assert_eq!(names, vec!["A", "a", "B", "b", "zoo", "été"]);
+    }
+}
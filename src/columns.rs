@@ -0,0 +1,210 @@
+//! the columns of the tree lines: the optional ones (git status, size,
+//! date, etc.), the tree branches and the name.
+//!
+//! Their order is given by `cols_order` in configuration, a column which
+//! isn't there never being displayed.
+//! When the terminal isn't wide enough for all the enabled columns
+//! and the names, the least important columns are hidden rather than
+//! the names truncated.
+//!
+//! The tree renderer writes the cells through the `Column` trait, each
+//! col having its implementation (see `displayable_tree::builtin_column`).
+
+use {
+    crate::{
+        displayable_tree::DisplayableTree,
+        errors::ProgramError,
+        flat_tree::Tree,
+    },
+    std::io::Write,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Col {
+    Git,
+    Branch, // the branches of the tree, before the name
+    Size,
+    Date,
+    Count,
+    Lines,
+    Permission,
+    Name, // always displayed, and always the last one
+}
+
+/// the default order of the columns in the tree lines
+pub const DEFAULT_COLS_ORDER: &[Col] = &[
+    Col::Git,
+    Col::Branch,
+    Col::Size,
+    Col::Permission,
+    Col::Date,
+    Col::Count,
+    Col::Lines,
+    Col::Name,
+];
+
+/// the default order of importance of the columns, the most important
+/// first: when there's not enough room, the last ones are hidden first
+pub const DEFAULT_COLS_PRIORITY: &[Col] = &[
+    Col::Git,
+    Col::Size,
+    Col::Date,
+    Col::Count,
+    Col::Lines,
+    Col::Permission,
+];
+
+/// the width the names are granted before any column is hidden
+/// (a column is never hidden for names longer than that)
+pub const NAME_MIN_WIDTH: usize = 40;
+
+/// a column of the tree lines
+pub trait Column {
+    /// tell whether the column is shown with the options of the
+    /// tree, provided there's enough room
+    fn is_enabled(&self, tree: &Tree) -> bool;
+    /// the width of the cells, 0 when it depends on the line
+    fn width_hint(&self) -> usize;
+    /// write the cell of the line at `line_index` in the tree
+    fn write_cell(
+        &self,
+        dt: &DisplayableTree<'_, '_>,
+        f: &mut dyn Write,
+        line_index: usize,
+        selected: bool,
+    ) -> Result<(), ProgramError>;
+}
+
+impl Col {
+    pub fn from_name(name: &str) -> Option<Col> {
+        match name.to_lowercase().as_ref() {
+            "git" => Some(Col::Git),
+            "branch" => Some(Col::Branch),
+            "size" => Some(Col::Size),
+            "date" => Some(Col::Date),
+            "count" => Some(Col::Count),
+            "lines" => Some(Col::Lines),
+            "perm" | "permission" => Some(Col::Permission),
+            "name" => Some(Col::Name),
+            _ => None,
+        }
+    }
+    /// tell whether the column may be hidden when there's not enough
+    /// room or when it's not enabled
+    pub fn is_optional(self) -> bool {
+        match self {
+            Col::Branch | Col::Name => false,
+            _ => true,
+        }
+    }
+}
+
+/// build a valid order from the one given in configuration: duplicates
+/// are removed and the name is moved (or added) at the end
+pub fn complete_order(cols: &[Col]) -> Vec<Col> {
+    let mut order: Vec<Col> = Vec::new();
+    for col in cols {
+        if *col != Col::Name && !order.contains(col) {
+            order.push(*col);
+        }
+    }
+    order.push(Col::Name);
+    order
+}
+
+/// build a complete priority list from the one given in configuration:
+/// the optional columns which aren't mentioned come after, in default order
+pub fn complete_priority(cols: &[Col]) -> Vec<Col> {
+    let mut priority: Vec<Col> = Vec::new();
+    for col in cols.iter().chain(DEFAULT_COLS_PRIORITY) {
+        if col.is_optional() && !priority.contains(col) {
+            priority.push(*col);
+        }
+    }
+    priority
+}
+
+/// return the enabled columns which fit in the available width
+/// (assuming names need `names_width`), hiding the least important
+/// ones first
+pub fn visible_cols<E, W>(
+    priority: &[Col],
+    is_enabled: E,
+    width_of: W,
+    available_width: usize,
+    names_width: usize,
+) -> Vec<Col>
+where
+    E: Fn(Col) -> bool,
+    W: Fn(Col) -> usize,
+{
+    let names_width = names_width.min(NAME_MIN_WIDTH);
+    let mut cols: Vec<Col> = priority.iter().cloned().filter(|&c| is_enabled(c)).collect();
+    while !cols.is_empty() {
+        let cols_width: usize = cols.iter().map(|&c| width_of(c)).sum();
+        if cols_width + names_width <= available_width {
+            break;
+        }
+        cols.pop();
+    }
+    cols
+}
+
+#[cfg(test)]
+mod columns_tests {
+
+    use super::*;
+
+    fn width_of(col: Col) -> usize {
+        match col {
+            Col::Git => 1,
+            Col::Size => 17,
+            Col::Date => 17,
+            Col::Count => 6,
+            Col::Lines => 7,
+            Col::Permission => 25,
+            Col::Branch | Col::Name => 0,
+        }
+    }
+
+    #[test]
+    fn check_visible_cols() {
+        let all = |_| true;
+        let priority = DEFAULT_COLS_PRIORITY;
+        assert_eq!(visible_cols(priority, all, width_of, 200, 30).len(), 6);
+        assert_eq!(
+            visible_cols(priority, all, width_of, 70, 30),
+            vec![Col::Git, Col::Size, Col::Date],
+        );
+        // long names don't hide more columns than names of NAME_MIN_WIDTH
+        assert_eq!(
+            visible_cols(priority, all, width_of, 70, 300),
+            vec![Col::Git, Col::Size],
+        );
+        assert_eq!(
+            visible_cols(priority, |c| c != Col::Size, width_of, 70, 30),
+            vec![Col::Git, Col::Date, Col::Count, Col::Lines],
+        );
+        assert!(visible_cols(priority, all, width_of, 20, 30).is_empty());
+    }
+
+    #[test]
+    fn check_order_completion() {
+        assert_eq!(
+            complete_order(&[Col::Name, Col::Size, Col::Branch, Col::Size]),
+            vec![Col::Size, Col::Branch, Col::Name],
+        );
+        assert_eq!(
+            complete_order(&[Col::Git, Col::Date]),
+            vec![Col::Git, Col::Date, Col::Name],
+        );
+    }
+
+    #[test]
+    fn check_priority_completion() {
+        assert_eq!(
+            complete_priority(&[Col::Date, Col::Name, Col::Permission]),
+            vec![Col::Date, Col::Permission, Col::Git, Col::Size, Col::Count, Col::Lines],
+        );
+    }
+}
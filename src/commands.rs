@@ -4,18 +4,58 @@
 
 use {
     crate::{
-        app_context::AppContext, app_state::AppState, keys, patterns::Pattern,
-        verb_invocation::VerbInvocation,
+        app_context::AppContext, app_state::AppState, input_history::InputHistory, keys,
+        patterns::Pattern, verb_invocation::VerbInvocation,
+        verb_store::{PrefixSearchResult, VerbStore}, verbs::VerbArg,
     },
     regex::Regex,
+    std::path::PathBuf,
     termimad::{Event, InputField},
 };
 
+/// number of lines scrolled by a notch of the mouse wheel
+const WHEEL_LINES: i32 = 3;
+
 #[derive(Debug, Clone)]
 pub struct Command {
     pub raw: String,     // what's visible in the input
     parts: CommandParts, // the parsed parts of the visible input
     pub action: Action, // what's required, based on the last key (which may be not visible, like esc)
+    pub prompt: Option<ArgPrompt>, // when the arguments of a verb are being asked
+    pub confirmed_path: Option<PathBuf>, // the protected path the verb was confirmed for
+}
+
+/// the arguments of a verb triggered by its key, which are asked
+/// one after the other in the input
+#[derive(Debug, Clone)]
+pub struct ArgPrompt {
+    pub verb_index: usize,
+    pub args: Vec<VerbArg>,
+    pub values: Vec<String>, // the arguments already given
+}
+
+impl ArgPrompt {
+    /// build a prompt when the verb has arguments and can be invoked
+    /// with them (i.e. it has a name)
+    fn new(verb_index: usize, con: &AppContext) -> Option<ArgPrompt> {
+        let verb = &con.verb_store.verbs[verb_index];
+        let args = verb.prompt_args();
+        if args.is_empty() || verb.invocation.name.is_empty() {
+            return None;
+        }
+        Some(ArgPrompt {
+            verb_index,
+            args,
+            values: Vec::new(),
+        })
+    }
+    /// the argument currently asked
+    pub fn current_arg(&self) -> &VerbArg {
+        &self.args[self.values.len()]
+    }
+    fn current_default(&self) -> String {
+        self.current_arg().default.clone().unwrap_or_default()
+    }
 }
 
 /// An intermediate parsed representation of the raw string
@@ -24,17 +64,22 @@ pub struct CommandParts {
     pattern: Option<String>,     // either a fuzzy pattern or the core of a regex
     regex_flags: Option<String>, // may be Some("") if user asked for a regex but specified no flag
     verb_invocation: Option<VerbInvocation>, // may be empty if user typed the separator but no char after
+    shell_command: Option<String>, // a command for the shell, typed after a `!`
 }
 
 #[derive(Debug, Clone)]
 pub enum Action {
     Unparsed,
     MoveSelection(i32),           // up (neg) or down (positive) in the list
+    Scroll(i32),                  // scroll the view up (neg) or down, usually with the mouse wheel
     OpenSelection,                // open the selected line
     AltOpenSelection,             // alternate open the selected line
     VerbEdit(VerbInvocation),     // verb invocation, unfinished
     VerbInvocate(VerbInvocation), // verb invocation, after the user hit enter
+    VerbComplete(VerbInvocation), // completion of the verb argument, asked with tab
     VerbIndex(usize),             // verb call, withtout specific argument (using a trigger key)
+    ShellEdit(String),            // shell command, unfinished
+    ShellExec(String),            // shell command, after the user hit enter
     FuzzyPatternEdit(String),     // a pattern being edited
     RegexEdit(String, String),    // a regex being edited (core & flags)
     Back,                         // back to last app state, or clear pattern
@@ -52,10 +97,15 @@ impl CommandParts {
             pattern: None,
             regex_flags: None,
             verb_invocation: None,
+            shell_command: None,
         }
     }
     fn from(raw: &str) -> Self {
         let mut cp = CommandParts::new();
+        if raw.starts_with('!') {
+            cp.shell_command = Some(raw[1..].to_string());
+            return cp;
+        }
         let c = regex!(
             r"(?x)
                 ^
@@ -114,7 +164,13 @@ impl Default for CommandParts {
 
 impl Action {
     fn from(cp: &CommandParts, finished: bool) -> Action {
-        if let Some(verb_invocation) = &cp.verb_invocation {
+        if let Some(shell_command) = &cp.shell_command {
+            if finished {
+                Action::ShellExec(shell_command.clone())
+            } else {
+                Action::ShellEdit(shell_command.clone())
+            }
+        } else if let Some(verb_invocation) = &cp.verb_invocation {
             if finished {
                 Action::VerbInvocate(verb_invocation.clone())
             } else {
@@ -133,6 +189,19 @@ impl Action {
             Action::FuzzyPatternEdit(String::from(""))
         }
     }
+    /// the name, as declared in the help of the screens, of the key
+    /// producing the action, when the screen gives it its meaning
+    pub fn key(&self) -> Option<&'static str> {
+        match self {
+            Action::Back => Some("esc"),
+            Action::OpenSelection => Some("enter"),
+            Action::AltOpenSelection => Some("alt-enter"),
+            Action::Next => Some("tab"),
+            Action::Previous => Some("shift-tab"),
+            Action::Help => Some("?"),
+            _ => None,
+        }
+    }
 }
 
 impl Command {
@@ -141,6 +210,8 @@ impl Command {
             raw: String::new(),
             parts: CommandParts::new(),
             action: Action::Unparsed,
+            prompt: None,
+            confirmed_path: None,
         }
     }
 
@@ -150,7 +221,13 @@ impl Command {
     pub fn from_raw(raw: String, finished: bool) -> Self {
         let parts = CommandParts::from(&raw);
         let action = Action::from(&parts, finished);
-        Self { raw, action, parts }
+        Self {
+            raw,
+            action,
+            parts,
+            prompt: None,
+            confirmed_path: None,
+        }
     }
 
     /// build a non executed command from a pattern
@@ -165,6 +242,60 @@ impl Command {
         )
     }
 
+    /// replace the raw input (for example with a completed one).
+    /// The action is rebuilt as if the new input had been typed
+    pub fn set_raw(&mut self, raw: String) {
+        self.parts = CommandParts::from(&raw);
+        self.action = Action::from(&self.parts, false);
+        self.raw = raw;
+    }
+
+    /// return the input to restore when coming back to the state this
+    /// input was typed in, after the action made broot leave it: the
+    /// pattern and a pending verb invocation are kept, but neither an
+    /// executed verb invocation nor a shell command
+    pub fn kept_input(&self, action: &Action) -> Option<Command> {
+        if self.prompt.is_some() || self.parts.shell_command.is_some() {
+            return None;
+        }
+        let raw = match action {
+            Action::VerbInvocate(_) => CommandParts::split(&self.raw).0,
+            _ => Some(self.raw.clone()),
+        };
+        raw.filter(|raw| !raw.is_empty())
+            .map(|raw| Command::from_raw(raw, false))
+    }
+
+    /// return the mode of the pattern of the input, if there's one
+    pub fn pattern_mode(&self) -> Option<&'static str> {
+        match (&self.parts.pattern, &self.parts.regex_flags) {
+            (Some(pattern), _) if pattern.is_empty() => None,
+            (Some(_), Some(_)) => Some("regex"),
+            (Some(_), None) => Some("fuzzy"),
+            (None, _) => None,
+        }
+    }
+
+    /// return the chars which would complete the name of the verb
+    /// being typed, if they can be guessed
+    pub fn verb_name_completion(&self, verb_store: &VerbStore) -> Option<String> {
+        let invocation = self.parts.verb_invocation.as_ref()?;
+        if invocation.is_empty()
+            || invocation.args.is_some()
+            || self.raw.ends_with(char::is_whitespace)
+        {
+            return None;
+        }
+        verb_store.name_completion(&invocation.name)
+    }
+
+    /// replace the input with an entry of the history, the user
+    /// still being free to edit it
+    fn recall(&mut self, raw: String, input_field: &mut InputField) {
+        input_field.set_content(&raw);
+        self.set_raw(raw);
+    }
+
     /// set the action and clears the other parts :
     ///  the command is now just the action.
     /// This isn't used when the parts must be kept,
@@ -175,17 +306,96 @@ impl Command {
         self.action = action;
     }
 
+    /// start asking the arguments of a verb, the default value of
+    /// the first one being proposed in the input
+    fn start_prompt(&mut self, prompt: ArgPrompt, input_field: &mut InputField) {
+        self.set_action(Action::Unparsed);
+        self.raw = prompt.current_default();
+        input_field.set_content(&self.raw);
+        self.prompt = Some(prompt);
+    }
+
+    /// build the prompt to start when the typed verb declares prompts
+    /// and no argument was given
+    fn prompt_for_invocation(&self, con: &AppContext) -> Option<ArgPrompt> {
+        let invocation = self.parts.verb_invocation.as_ref()?;
+        if invocation.args.is_some() {
+            return None;
+        }
+        match con.verb_store.search(&invocation.name) {
+            PrefixSearchResult::Match(verb) if !verb.prompts.is_empty() => {
+                ArgPrompt::new(con.verb_store.index_of(&verb.invocation.name), con)
+            }
+            _ => None,
+        }
+    }
+
+    /// apply a key event while the arguments of a verb are asked: the typed
+    /// value doesn't filter the tree, enter goes to the next argument, or
+    /// makes the verb invocation when it was the last one, and esc cancels
+    fn add_prompt_event(
+        &mut self,
+        mut prompt: ArgPrompt,
+        event: &Event,
+        input_field: &mut InputField,
+        history: &mut InputHistory,
+        con: &AppContext,
+    ) {
+        match event {
+            Event::Key(key) if *key == keys::ESC => {
+                input_field.set_content("");
+                self.set_action(Action::Unparsed);
+            }
+            Event::Key(key) if *key == keys::ENTER => {
+                prompt.values.push(self.raw.clone());
+                if prompt.values.len() < prompt.args.len() {
+                    self.start_prompt(prompt, input_field);
+                } else {
+                    let verb = &con.verb_store.verbs[prompt.verb_index];
+                    let args = verb.fill_args(&prompt.args, &prompt.values);
+                    let raw = format!(":{} {}", verb.invocation.name, args);
+                    history.push(&raw);
+                    input_field.set_content(&raw);
+                    *self = Command::from_raw(raw, true);
+                }
+            }
+            _ => {
+                if input_field.apply_event(event) {
+                    self.raw = input_field.get_content();
+                }
+                self.prompt = Some(prompt);
+            }
+        }
+    }
+
     /// apply an event to modify the command.
     /// The command isn't applied to the state
     pub fn add_event(
         &mut self,
         event: &Event,
         input_field: &mut InputField,
+        history: &mut InputHistory,
         con: &AppContext,
         state: &dyn AppState,
     ) {
         debug!("add_event {:?}", event);
         self.action = Action::Unparsed;
+        if let Some(prompt) = self.prompt.take() {
+            match event {
+                Event::Key(_) => {
+                    self.add_prompt_event(prompt, event, input_field, history, con);
+                    return;
+                }
+                Event::Resize(..) | Event::Wheel(_) => {
+                    self.prompt = Some(prompt);
+                }
+                _ => {
+                    // a click cancels the prompt
+                    input_field.set_content("");
+                    self.raw.clear();
+                }
+            }
+        }
         match event {
             Event::Click(x, y, ..) => {
                 if !input_field.apply_event(&event) {
@@ -196,10 +406,37 @@ impl Command {
                 self.action = Action::DoubleClick(*x, *y);
             }
             Event::Key(key) => {
+                // the up and down arrows browse the history of the inputs, like
+                // in a shell, when a verb is typed or the history already browsed.
+                // Otherwise they move the selection
+                if *key == keys::UP
+                    && (history.is_browsing()
+                        || self.parts.verb_invocation.is_some()
+                        || self.parts.shell_command.is_some())
+                {
+                    if let Some(raw) = history.previous(&self.raw) {
+                        self.recall(raw, input_field);
+                    }
+                    return;
+                }
+                if *key == keys::DOWN && history.is_browsing() {
+                    if let Some(raw) = history.next() {
+                        self.recall(raw, input_field);
+                    }
+                    return;
+                }
+                history.stop_browsing();
+
                 // we first handle the cases that MUST absolutely
                 // not be overriden by configuration
 
                 if *key == keys::ENTER && self.parts.verb_invocation.is_some() {
+                    // a verb declaring prompts, called without arguments, asks them
+                    if let Some(prompt) = self.prompt_for_invocation(con) {
+                        self.start_prompt(prompt, input_field);
+                        return;
+                    }
+                    history.push(&self.raw);
                     self.action = Action::from(&self.parts, true);
                     return;
                 }
@@ -222,6 +459,11 @@ impl Command {
                 // we now check if the key is the trigger key of one of the verbs
                 if let Some(index) = con.verb_store.index_of_key(*key) {
                     if state.can_execute(index, con) {
+                        // the arguments of the verb, if any, are asked first
+                        if let Some(prompt) = ArgPrompt::new(index, con) {
+                            self.start_prompt(prompt, input_field);
+                            return;
+                        }
                         self.set_action(Action::VerbIndex(index));
                         return;
                     } else {
@@ -230,6 +472,7 @@ impl Command {
                 }
 
                 if *key == keys::ENTER {
+                    history.push(&self.raw);
                     self.action = Action::from(&self.parts, true);
                     return;
                 }
@@ -240,6 +483,19 @@ impl Command {
                 }
 
                 if *key == keys::TAB {
+                    // the name of the verb is completed before its arguments
+                    if let Some(completion) = self.verb_name_completion(&con.verb_store) {
+                        let raw = format!("{}{}", self.raw, completion);
+                        input_field.set_content(&raw);
+                        self.set_raw(raw);
+                        return;
+                    }
+                    if let Some(invocation) = &self.parts.verb_invocation {
+                        if !invocation.name.is_empty() {
+                            self.action = Action::VerbComplete(invocation.clone());
+                            return;
+                        }
+                    }
                     self.set_action(Action::Next);
                     return;
                 }
@@ -277,7 +533,7 @@ impl Command {
                 self.action = Action::Resize(*w, *h);
             }
             Event::Wheel(lines_count) => {
-                self.action = Action::MoveSelection(*lines_count);
+                self.action = Action::Scroll(*lines_count * WHEEL_LINES);
             }
             _ => {}
         }
@@ -289,3 +545,23 @@ impl Default for Command {
         Command::new()
     }
 }
+
+#[cfg(test)]
+mod commands_tests {
+
+    use super::*;
+
+    fn kept_raw(raw: &str, action: &Action) -> Option<String> {
+        Command::from_raw(raw.to_string(), false).kept_input(action).map(|cmd| cmd.raw)
+    }
+
+    #[test]
+    fn check_kept_input() {
+        assert_eq!(kept_raw("mai", &Action::Help), Some("mai".to_string()));
+        assert_eq!(kept_raw("mai:cp ", &Action::Help), Some("mai:cp ".to_string()));
+        let executed = Action::VerbInvocate(VerbInvocation::from("help"));
+        assert_eq!(kept_raw("mai:help", &executed), Some("mai".to_string()));
+        assert_eq!(kept_raw(":help", &executed), None);
+        assert_eq!(kept_raw("!ls", &Action::ShellExec("ls".to_string())), None);
+    }
+}
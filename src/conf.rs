@@ -3,18 +3,33 @@
 
 use {
     crate::{
+        columns::{self, Col},
+        conf_doc::{self, ConfSetting},
+        date_source::DateSource,
         errors::ConfError,
+        file_metadata::Preserve,
         keys,
+        nested_launch::NestedLaunch,
+        organize::OrganizeRule,
+        profiles::Profile,
         skin_conf,
+        sort::Sort,
+        special_paths::{SpecialHandling, SpecialPath},
+        theme_mode::ThemeMode,
+        tie_breaker::TieBreaker,
+        trash::TrashCommand,
         verb_conf::VerbConf,
+        verbs::{self, VerbArg},
     },
-    crossterm::style::Attribute,
+    crossterm::{event::KeyEvent, style::Attribute},
     directories::ProjectDirs,
+    regex::{Captures, Regex},
     std::{
         collections::HashMap,
-        fs, io,
+        env, fs, io,
         path::{Path, PathBuf},
         result::Result,
+        time::Duration,
     },
     termimad::CompoundStyle,
     toml::{self, Value},
@@ -23,8 +38,47 @@ use {
 #[derive(Default)]
 pub struct Conf {
     pub default_flags: String, // the flags to apply before cli ones
+    pub default_sort: Option<Sort>,
+    pub date_source: Option<DateSource>,
+    pub start_screen: bool, // whether to show the start screen when no path is given
+    pub nested_launch: NestedLaunch, // what a broot launched from another broot does
+    pub alt_screen: Option<bool>, // whether to draw on the alternate screen of the terminal
+    pub set_terminal_title: bool, // whether to show the current root in the terminal title
+    pub mouse_capture: Option<bool>, // whether broot handles the mouse events
+    pub input_hints: Option<bool>, // whether the placeholder and verb completions are shown
+    pub abbreviate_home: Option<bool>, // whether the home directory is written `~`
+    pub shorten_paths: Option<bool>, // whether directories of too long paths are shortened
+    pub accessible: bool, // whether the display is made for screen readers
+    pub bookmarks: Vec<PathBuf>,
+    pub cols_order: Option<Vec<Col>>,
+    pub cols_priority: Option<Vec<Col>>,
+    pub align_size_units: Option<bool>,
+    pub dot_pattern_shows_hidden: Option<bool>, // whether a pattern starting with a dot finds hidden files
+    pub search_symlinked_dirs: bool, // whether searches enter symlinked directories
+    pub prune: Vec<String>, // globs of the directories which are shown but never entered
+    pub locale_collation: bool, // whether names are sorted with the collation of the locale
+    pub tie_breaker: Option<TieBreaker>, // which of the best matches of a search is selected
+    pub watch_idle_pause: Option<i64>, // seconds without user event before the watch is paused
+    pub persist_input_history: bool, // whether the input history is kept between sessions
+    pub persist_queue: bool, // whether the queued commands are kept between sessions
+    pub usage_stats: bool, // whether the uses of the verbs and patterns are counted
+    pub max_copy_rate: Option<i64>, // in MB per second, for the copies done by :cp
+    pub copy_preserve: Option<Preserve>, // the metadata kept by the copies done by :cp
+    pub protected_paths: Option<Vec<PathBuf>>, // where destructive verbs must be confirmed
+    pub trusted_projects: Vec<PathBuf>, // the directories whose .broot file is read
+    pub special_paths: Vec<SpecialPath>,
+    pub trash_commands: Vec<TrashCommand>, // used when the native trash fails
+    pub organize_rules: Vec<OrganizeRule>, // the folders :organize moves the files to
+    pub profiles: Vec<Profile>, // named flags and sorts, from the [profiles] section
     pub verbs: Vec<VerbConf>,
+    pub keys: Vec<(KeyEvent, String)>, // keys bound to verb names in the [keys] section
+    pub show_icons: Option<bool>, // whether an icon is written before the names
+    pub icons: HashMap<String, String>, // glyphs by extension, from the [icons] section
+    pub skin_preset: Option<String>, // name of the skin on which the [skin] entries are applied
+    pub light_skin_preset: Option<String>, // the preset used instead on light backgrounds
+    pub theme_mode: Option<ThemeMode>, // None when the background must be detected
     pub skin: HashMap<String, CompoundStyle>,
+    pub settings: Vec<ConfSetting>, // the keys set by the files, in reading order
 }
 
 fn string_field(value: &Value, field_name: &str) -> Option<String> {
@@ -37,6 +91,14 @@ fn string_field(value: &Value, field_name: &str) -> Option<String> {
     }
     None
 }
+fn integer_field(value: &Value, field_name: &str) -> Option<i64> {
+    if let Value::Table(tbl) = value {
+        if let Some(Value::Integer(i)) = tbl.get(field_name) {
+            return Some(*i);
+        }
+    }
+    None
+}
 fn bool_field(value: &Value, field_name: &str) -> Option<bool> {
     if let Value::Table(tbl) = value {
         if let Some(Value::Boolean(b)) = tbl.get(field_name) {
@@ -46,6 +108,75 @@ fn bool_field(value: &Value, field_name: &str) -> Option<bool> {
     None
 }
 
+/// read the arguments a verb asks for, in order, when not given
+/// (eg `prompts = [{ arg = "newname", label = "new name" }]`)
+fn verb_prompts(verb_value: &Value) -> Vec<VerbArg> {
+    let mut prompts = Vec::new();
+    if let Some(Value::Array(prompts_value)) = verb_value.get("prompts") {
+        for prompt_value in prompts_value {
+            match string_field(prompt_value, "arg") {
+                Some(name) => prompts.push(VerbArg {
+                    name,
+                    label: string_field(prompt_value, "label"),
+                    default: string_field(prompt_value, "default"),
+                }),
+                None => eprintln!("Invalid verb prompt in configuration: missing arg"),
+            }
+        }
+    }
+    prompts
+}
+
+type SpecialPathValue = (Option<SpecialHandling>, Option<Sort>, Option<String>);
+
+/// read the value of a special path, either a handling (eg `"hide"`) or
+/// a table with a handling, a sort and/or a profile (eg `{ sort = "date" }`)
+fn special_path_value(value: &Value) -> Option<SpecialPathValue> {
+    match value {
+        Value::String(handling) => Some((Some(SpecialHandling::from_name(handling)?), None, None)),
+        Value::Table(tbl) => {
+            let handling = match tbl.get("handling") {
+                Some(handling) => Some(SpecialHandling::from_name(handling.as_str()?)?),
+                None => None,
+            };
+            let sort = match tbl.get("sort") {
+                Some(sort) => Some(Sort::from_name(sort.as_str()?)?),
+                None => None,
+            };
+            let profile = match tbl.get("profile") {
+                Some(profile) => Some(profile.as_str()?.to_string()),
+                None => None,
+            };
+            if handling.is_none() && sort.is_none() && profile.is_none() {
+                None
+            } else {
+                Some((handling, sort, profile))
+            }
+        }
+        _ => None,
+    }
+}
+
+/// replace the `$VAR` and `${VAR}` parts of a configuration value with
+/// the values given by `lookup`. Unknown variables are left as they are
+/// (the ones of a verb execution may still be resolved when it's run),
+/// and `$$` is a literal `$`.
+fn expand_vars(value: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    regex!(r"\$(?:\$|\{(\w+)\}|(\w+))")
+        .replace_all(value, |c: &Captures<'_>| {
+            match c.get(1).or_else(|| c.get(2)) {
+                Some(name) => lookup(name.as_str()).unwrap_or_else(|| c[0].to_string()),
+                None => "$".to_string(), // escaped dollar
+            }
+        })
+        .to_string()
+}
+
+/// expand the environment variables of a configuration value
+fn expand_env_vars(value: &str) -> String {
+    expand_vars(value, |name| env::var(name).ok())
+}
+
 /// return the instance of ProjectDirs holding broot's specific paths
 pub fn app_dirs() -> ProjectDirs {
     ProjectDirs::from("org", "dystroy", "broot")
@@ -57,14 +188,36 @@ pub fn dir() -> PathBuf {
     app_dirs().config_dir().to_path_buf()
 }
 
+/// the name of the configuration file a project may have at its root
+pub const PROJECT_CONF_NAME: &str = ".broot";
+
+/// return the nearest project configuration file, in the
+/// directory or in one of its ancestors. It's only read when
+/// its directory is trusted (see `Conf::is_trusted_project`).
+pub fn find_project_conf(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(PROJECT_CONF_NAME))
+        .find(|path| path.is_file())
+}
+
 impl Conf {
 
     pub fn default_location() -> PathBuf {
         dir().join("conf.toml")
     }
 
-    /// read the configuration file from the default OS specific location.
-    /// Create it if it doesn't exist
+    /// the location of the configuration file an administrator may
+    /// write for all the users of the machine
+    pub fn system_location() -> Option<PathBuf> {
+        if cfg!(unix) {
+            Some(PathBuf::from("/etc/broot/conf.toml"))
+        } else {
+            None
+        }
+    }
+
+    /// read the configuration file from the default OS specific location,
+    /// over the system one if there's one. Create it if it doesn't exist
     pub fn from_default_location() -> Result<Conf, ConfError> {
         let conf_filepath = Conf::default_location();
         if !conf_filepath.exists() {
@@ -78,7 +231,14 @@ impl Conf {
             println!("You should have a look at it.");
         }
         let mut conf = Conf::default();
-        match conf.read_file(&conf_filepath) {
+        if let Some(system_filepath) = Conf::system_location().filter(|path| path.exists()) {
+            if let Err(e) = conf.read_file(&system_filepath) {
+                println!("Failed to read the system configuration in {:?}.", &system_filepath);
+                println!("Please ask the administrator of this machine to fix this file.");
+                return Err(e);
+            }
+        }
+        match conf.read_file_over(&conf_filepath) {
             Err(e) => {
                 println!("Failed to read configuration in {:?}.", &conf_filepath);
                 println!("Please delete or fix this file.");
@@ -99,12 +259,354 @@ impl Conf {
     /// stderr is supposed to be a valid solution for displaying errors
     /// (i.e. this function is called before or after the terminal alternation)
     pub fn read_file(&mut self, filepath: &Path) -> Result<(), ConfError> {
+        self.read_file_in(filepath, Path::new("/"))
+    }
+
+    /// read a configuration file over the ones already read: its
+    /// settings replace theirs, and its verbs, key bindings, special
+    /// paths, trash commands and organize rules come first so that they take precedence.
+    /// The keys it binds are removed from the previous verbs.
+    fn read_file_over(&mut self, filepath: &Path) -> Result<(), ConfError> {
+        let mut verbs = std::mem::take(&mut self.verbs);
+        let keys = std::mem::take(&mut self.keys);
+        let mut special_paths = std::mem::take(&mut self.special_paths);
+        let mut trash_commands = std::mem::take(&mut self.trash_commands);
+        let mut organize_rules = std::mem::take(&mut self.organize_rules);
+        self.read_file(filepath)?;
+        let is_rebound = |key: KeyEvent| {
+            self.verbs.iter().any(|v| v.key == Some(key))
+                || self.keys.iter().any(|(k, _)| *k == key)
+        };
+        for verb in &mut verbs {
+            if verb.key.map_or(false, is_rebound) {
+                verb.key = None;
+            }
+        }
+        let keys: Vec<(KeyEvent, String)> = keys
+            .into_iter()
+            .filter(|(key, _)| !is_rebound(*key))
+            .collect();
+        self.verbs.append(&mut verbs);
+        self.keys.extend(keys);
+        self.special_paths.append(&mut special_paths);
+        self.trash_commands.append(&mut trash_commands);
+        self.organize_rules.append(&mut organize_rules);
+        Ok(())
+    }
+
+    /// tell whether the project configuration file may be read, that
+    /// is whether its directory is in the `trusted_projects` of the
+    /// user. A `.broot` file of a cloned repository could otherwise
+    /// add any command to the verbs.
+    pub fn is_trusted_project(&self, filepath: &Path) -> bool {
+        let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let project_dir = match filepath.parent() {
+            Some(dir) => canonical(dir),
+            None => {
+                return false;
+            }
+        };
+        self.trusted_projects.iter().any(|dir| canonical(dir) == project_dir)
+    }
+
+    /// read the verbs and special paths of a project configuration
+    /// file (a `.broot` file) and put them before the ones already
+    /// read. The verbs can't change the existing names, shortcuts
+    /// and keys (see `VerbStore::init`), and the other settings of
+    /// the file are ignored, as they're the user's choice.
+    pub fn read_project_file(&mut self, filepath: &Path) -> Result<(), ConfError> {
+        let mut project_conf = Conf::default();
+        let project_dir = filepath.parent().unwrap_or_else(|| Path::new("/"));
+        project_conf.read_file_in(filepath, project_dir)?;
+        for verb in &mut project_conf.verbs {
+            verb.from_project = true;
+        }
+        project_conf.verbs.append(&mut self.verbs);
+        self.verbs = project_conf.verbs;
+        project_conf.special_paths.append(&mut self.special_paths);
+        self.special_paths = project_conf.special_paths;
+        project_conf
+            .settings
+            .retain(|setting| setting.key == "verbs" || setting.key == "special-paths");
+        self.settings.append(&mut project_conf.settings);
+        Ok(())
+    }
+
+    /// read a configuration file, the globs of special paths being
+    /// relative to `dir`
+    fn read_file_in(&mut self, filepath: &Path, dir: &Path) -> Result<(), ConfError> {
         let data = fs::read_to_string(filepath)?;
         let root: Value = data.parse::<Value>()?;
+        if let Value::Table(root_tbl) = &root {
+            for (key, value) in root_tbl {
+                if conf_doc::is_known_key(key) {
+                    self.settings.push(ConfSetting::new(key, value, filepath));
+                } else {
+                    eprintln!("Unknown key {:?} in configuration {:?}", key, filepath);
+                }
+            }
+        }
         // reading default flags
         if let Some(s) = string_field(&root, "default_flags") {
             self.default_flags.push_str(&s);
         }
+        // reading default sort
+        if let Some(s) = string_field(&root, "default_sort") {
+            match Sort::from_name(&s) {
+                Some(sort) => {
+                    self.default_sort = Some(sort);
+                }
+                None => {
+                    eprintln!("Invalid default_sort in configuration: {:?}", s);
+                }
+            }
+        }
+        // reading the date to display and sort by
+        if let Some(s) = string_field(&root, "date_source") {
+            match DateSource::from_name(&s) {
+                Some(date_source) => {
+                    self.date_source = Some(date_source);
+                }
+                None => {
+                    eprintln!("Invalid date_source in configuration: {:?}", s);
+                }
+            }
+        }
+        // reading the start screen settings
+        if let Some(b) = bool_field(&root, "start_screen") {
+            self.start_screen = b;
+        }
+        if let Some(s) = string_field(&root, "nested_launch") {
+            match NestedLaunch::from_name(&s) {
+                Some(nested_launch) => {
+                    self.nested_launch = nested_launch;
+                }
+                None => {
+                    eprintln!("Invalid nested_launch in configuration: {:?}", s);
+                }
+            }
+        }
+        if let Some(b) = bool_field(&root, "alt_screen") {
+            self.alt_screen = Some(b);
+        }
+        if let Some(b) = bool_field(&root, "set_terminal_title") {
+            self.set_terminal_title = b;
+        }
+        if let Some(b) = bool_field(&root, "mouse_capture") {
+            self.mouse_capture = Some(b);
+        }
+        if let Some(b) = bool_field(&root, "input_hints") {
+            self.input_hints = Some(b);
+        }
+        if let Some(b) = bool_field(&root, "abbreviate_home") {
+            self.abbreviate_home = Some(b);
+        }
+        if let Some(b) = bool_field(&root, "shorten_paths") {
+            self.shorten_paths = Some(b);
+        }
+        if let Some(b) = bool_field(&root, "accessible") {
+            self.accessible = b;
+        }
+        if let Some(Value::Array(bookmarks)) = &root.get("bookmarks") {
+            for bookmark in bookmarks.iter().filter_map(Value::as_str) {
+                let bookmark = expand_env_vars(bookmark);
+                self.bookmarks.push(verbs::path_from_directory(Path::new("/"), &bookmark));
+            }
+        }
+        // reading the order of the columns
+        if let Some(cols) = string_field(&root, "cols_order") {
+            let mut order = Vec::new();
+            for name in cols.split_whitespace() {
+                match Col::from_name(name) {
+                    Some(col) => order.push(col),
+                    None => eprintln!("Invalid column in cols_order: {:?}", name),
+                }
+            }
+            self.cols_order = Some(columns::complete_order(&order));
+        }
+        // reading the priority of the columns
+        if let Some(Value::Array(cols)) = &root.get("cols_priority") {
+            let mut priority = Vec::new();
+            for name in cols.iter().filter_map(Value::as_str) {
+                match Col::from_name(name) {
+                    Some(col) => priority.push(col),
+                    None => eprintln!("Invalid column in cols_priority: {:?}", name),
+                }
+            }
+            self.cols_priority = Some(columns::complete_priority(&priority));
+        }
+        if let Some(b) = bool_field(&root, "align_size_units") {
+            self.align_size_units = Some(b);
+        }
+        if let Some(b) = bool_field(&root, "dot_pattern_shows_hidden") {
+            self.dot_pattern_shows_hidden = Some(b);
+        }
+        if let Some(b) = bool_field(&root, "search_symlinked_dirs") {
+            self.search_symlinked_dirs = b;
+        }
+        if let Some(b) = bool_field(&root, "locale_collation") {
+            self.locale_collation = b;
+        }
+        if let Some(s) = string_field(&root, "tie_breaker") {
+            match TieBreaker::from_name(&s) {
+                Some(tie_breaker) => {
+                    self.tie_breaker = Some(tie_breaker);
+                }
+                None => {
+                    eprintln!("Invalid tie_breaker in configuration: {:?}", s);
+                }
+            }
+        }
+        if let Some(seconds) = integer_field(&root, "watch_idle_pause") {
+            self.watch_idle_pause = Some(seconds);
+        }
+        if let Some(b) = bool_field(&root, "persist_input_history") {
+            self.persist_input_history = b;
+        }
+        if let Some(b) = bool_field(&root, "persist_queue") {
+            self.persist_queue = b;
+        }
+        if let Some(b) = bool_field(&root, "usage_stats") {
+            self.usage_stats = b;
+        }
+        if let Some(Value::Array(prune)) = &root.get("prune") {
+            self.prune = prune.iter().filter_map(Value::as_str).map(str::to_string).collect();
+        }
+        if let Some(Value::Array(protected_paths)) = &root.get("protected_paths") {
+            self.protected_paths = Some(
+                protected_paths
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(|path| verbs::path_from_directory(Path::new("/"), &expand_env_vars(path)))
+                    .collect(),
+            );
+        }
+        if let Some(Value::Array(trusted_projects)) = &root.get("trusted_projects") {
+            self.trusted_projects = trusted_projects
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|path| verbs::path_from_directory(Path::new("/"), &expand_env_vars(path)))
+                .collect();
+        }
+        if let Some(rate) = integer_field(&root, "max_copy_rate") {
+            self.max_copy_rate = Some(rate);
+        }
+        if let Some(s) = string_field(&root, "copy_preserve") {
+            match Preserve::from_names(&s) {
+                Some(preserve) => {
+                    self.copy_preserve = Some(preserve);
+                }
+                None => {
+                    eprintln!("Invalid copy_preserve in configuration: {:?}", s);
+                }
+            }
+        }
+        if let Some(b) = bool_field(&root, "show_icons") {
+            self.show_icons = Some(b);
+        }
+        if let Some(name) = string_field(&root, "skin_preset") {
+            self.skin_preset = Some(name);
+        }
+        if let Some(name) = string_field(&root, "light_skin_preset") {
+            self.light_skin_preset = Some(name);
+        }
+        if let Some(s) = string_field(&root, "theme_mode") {
+            match ThemeMode::from_name(&s) {
+                Some(mode) => {
+                    self.theme_mode = Some(mode);
+                }
+                None if s == "auto" => {
+                    self.theme_mode = None;
+                }
+                None => {
+                    eprintln!("Invalid theme_mode in configuration: {:?}", s);
+                }
+            }
+        }
+        // reading the paths needing a special handling
+        if let Some(Value::Table(special_paths)) = &root.get("special-paths") {
+            for (glob, value) in special_paths {
+                let (handling, sort, profile) = match special_path_value(value) {
+                    Some(settings) => settings,
+                    None => {
+                        eprintln!("Invalid special path handling for {:?}: {}", glob, value);
+                        continue;
+                    }
+                };
+                // globs without separator apply to file names, other ones
+                // to whole paths, and may start with ~
+                let glob = expand_env_vars(glob);
+                let glob = if glob.contains('/') {
+                    verbs::path_from_directory(dir, &glob)
+                        .to_string_lossy()
+                        .to_string()
+                } else {
+                    glob.to_string()
+                };
+                match SpecialPath::new(&glob, handling, sort, profile) {
+                    Ok(special_path) => self.special_paths.push(special_path),
+                    Err(e) => eprintln!("Invalid special path {:?}: {}", glob, e),
+                }
+            }
+        }
+        // reading the commands replacing the native trash where it fails
+        if let Some(Value::Table(trash_commands)) = &root.get("trash-commands") {
+            for (glob, execution) in trash_commands {
+                let execution = match execution.as_str() {
+                    Some(execution) => execution,
+                    None => {
+                        eprintln!("Invalid trash command for {:?}: {}", glob, execution);
+                        continue;
+                    }
+                };
+                let glob = verbs::path_from_directory(Path::new("/"), &expand_env_vars(glob));
+                match TrashCommand::new(&glob.to_string_lossy(), &execution) {
+                    Ok(trash_command) => self.trash_commands.push(trash_command),
+                    Err(e) => eprintln!("Invalid trash command glob {:?}: {}", glob, e),
+                }
+            }
+        }
+        // reading the profiles, a profile replacing the one of the
+        // same name read in a previous file
+        if let Some(Value::Table(profiles)) = &root.get("profiles") {
+            for (name, profile_value) in profiles {
+                let flags = string_field(profile_value, "flags").unwrap_or_default();
+                let sort = match string_field(profile_value, "sort") {
+                    Some(s) => match Sort::from_name(&s) {
+                        Some(sort) => Some(sort),
+                        None => {
+                            eprintln!("Invalid sort in profile {:?}: {:?}", name, s);
+                            continue;
+                        }
+                    },
+                    None => None,
+                };
+                match Profile::new(name, &flags, sort) {
+                    Ok(profile) => {
+                        self.profiles.retain(|p| p.name != profile.name);
+                        self.profiles.push(profile);
+                    }
+                    Err(e) => eprintln!("Invalid flags in profile {:?}: {}", name, e),
+                }
+            }
+        }
+        // reading the rules telling :organize where to move the files
+        if let Some(Value::Array(organize_rules)) = &root.get("organize-rules") {
+            for rule_value in organize_rules {
+                let glob = string_field(rule_value, "glob").unwrap_or_else(|| "*".to_string());
+                let folder = match string_field(rule_value, "folder") {
+                    Some(folder) => folder,
+                    None => {
+                        eprintln!("Missing folder in organize rule {}", rule_value);
+                        continue;
+                    }
+                };
+                match OrganizeRule::new(&glob, &folder) {
+                    Ok(rule) => self.organize_rules.push(rule),
+                    Err(e) => eprintln!("Invalid organize rule glob {:?}: {}", glob, e),
+                }
+            }
+        }
         // reading verbs
         if let Some(Value::Array(verbs_value)) = &root.get("verbs") {
             for verb_value in verbs_value.iter() {
@@ -119,6 +621,11 @@ impl Conf {
                             key: keys::key_event_desc(key),
                         });
                     }
+                    if self.verbs.iter().any(|v| v.key == Some(key)) {
+                        return Err(ConfError::ConflictingKey {
+                            key: keys::key_event_desc(key),
+                        });
+                    }
                 }
                 let execution = match string_field(verb_value, "execution") {
                     Some(s) => s,
@@ -146,9 +653,55 @@ impl Conf {
                     from_shell,
                     leave_broot,
                     confirm: bool_field(verb_value, "confirm"),
+                    timeout: integer_field(verb_value, "timeout")
+                        .filter(|&seconds| seconds > 0)
+                        .map(|seconds| Duration::from_secs(seconds as u64)),
+                    from_project: false,
+                    prompts: verb_prompts(verb_value),
                 });
             }
         }
+        // reading the keys bound to verbs
+        if let Some(Value::Table(keys_tbl)) = &root.get("keys") {
+            for (raw_key, verb_name) in keys_tbl.iter() {
+                let key = keys::parse_key(raw_key)?;
+                if keys::is_reserved(key) {
+                    return Err(ConfError::ReservedKey {
+                        key: keys::key_event_desc(key),
+                    });
+                }
+                if self.keys.iter().any(|(k, _)| *k == key)
+                    || self.verbs.iter().any(|v| v.key == Some(key))
+                {
+                    return Err(ConfError::ConflictingKey {
+                        key: keys::key_event_desc(key),
+                    });
+                }
+                match verb_name.as_str() {
+                    Some(verb_name) => {
+                        self.keys.push((key, verb_name.to_string()));
+                    }
+                    None => {
+                        eprintln!("Invalid [keys] entry in configuration");
+                        eprintln!("The value of {:?} must be the name of a verb", raw_key);
+                    }
+                }
+            }
+        }
+        // reading the icons
+        if let Some(Value::Table(icons_tbl)) = &root.get("icons") {
+            for (k, v) in icons_tbl.iter() {
+                match v.as_str() {
+                    Some(glyph) => {
+                        self.icons.insert(k.to_string(), glyph.to_string());
+                    }
+                    None => {
+                        eprintln!("Invalid [icons] entry in configuration");
+                        eprintln!("The value of {:?} must be a string", k);
+                    }
+                }
+            }
+        }
         // reading the skin
         if let Some(Value::Table(entries_tbl)) = &root.get("skin") {
             for (k, v) in entries_tbl.iter() {
@@ -191,6 +744,296 @@ const DEFAULT_CONF_FILE: &str = r#"
 #
 default_flags = ""
 
+###############################################################
+# Default sort
+# The first level of the tree can be sorted by "count", "date"
+# or "size" (only this level is then displayed).
+#
+# default_sort = "date"
+
+###############################################################
+# Date source
+# The date displayed in the date column and used to sort by date
+# may be "modified" (the default), "changed" (which includes
+# changes of metadata, like permissions), "accessed", "created"
+# (not available on all file systems) or "deep" (the most recent
+# modification found in a directory and its descendants, which is
+# computed in background).
+#
+# date_source = "created"
+
+###############################################################
+# Columns order
+# The columns of the tree lines, in display order. A column
+# which isn't in this list is never displayed. The name is
+# always the last column.
+# Available columns: git, branch, size, perm, date, count,
+# lines and name.
+#
+# cols_order = "git branch size perm date count lines name"
+
+###############################################################
+# Columns priority
+# When the terminal is too narrow for all the displayed columns,
+# the last ones of this list are hidden first, so that names
+# don't have to be truncated.
+#
+# cols_priority = ["git", "size", "date", "count", "lines", "perm"]
+
+###############################################################
+# Size units
+# Sizes are right aligned with their units (K, M, G...) in their
+# own column. Set this to false to have the units just after
+# the numbers.
+#
+# align_size_units = false
+
+###############################################################
+# Searching dotfiles
+# When the pattern starts with a dot, hidden files are searched
+# too, even when they're not displayed (h:n), as you're probably
+# looking for one of them. Set this to false to prevent it.
+#
+# dot_pattern_shows_hidden = false
+
+###############################################################
+# Searching in symlinked directories
+# Symbolic links to directories aren't followed by default. Set
+# this to true to have searches enter them (a directory reached
+# twice through links is only read once). This can also be
+# changed with --follow-links or the :toggle_links verb.
+#
+# search_symlinked_dirs = true
+
+###############################################################
+# Sorting names with the collation of the locale
+# Names are sorted by their lowercased characters, which puts
+# the accented letters after z. Set this to true to sort them
+# as your locale (LC_COLLATE) does, which is a little slower.
+#
+# locale_collation = true
+
+###############################################################
+# Best match of a search
+# When several lines have the best score, the least deep one is
+# selected. You may prefer the one with the shortest name
+# ("shorter_name") or the most recently modified one
+# ("more_recent"). Lines still equal are taken in the tree order.
+#
+# tie_breaker = "shorter_name"
+
+###############################################################
+# Pausing the watch mode
+# In watch mode, the watching of the directories is paused after
+# this number of seconds without key or mouse event, so that
+# a forgotten broot doesn't wake up on every file change. It's
+# resumed, with a refresh, on the next event. 0 means never.
+#
+# watch_idle_pause = 300
+
+###############################################################
+# Input history
+# The patterns and verb invocations you validated with enter are
+# recalled with the up and down arrows when typing a verb. Set
+# this to true to keep them between sessions.
+#
+# persist_input_history = true
+
+###############################################################
+# Persisted queue
+# The commands staged with :queue are lost when broot quits. Set
+# this to true to save them as they're queued, and to be offered
+# to execute them when broot is launched again on the same
+# directory.
+#
+# persist_queue = true
+
+###############################################################
+# Usage statistics
+# Set this to true to count, on this computer only, the uses of
+# the verbs and of the pattern modes, which :usage displays. It
+# helps finding the verbs you never use and the ones which deserve
+# a better key. Nothing is ever sent anywhere.
+#
+# usage_stats = true
+
+###############################################################
+# Copies
+# The copies done with :cp use all the bandwidth they can. You
+# may limit their rate, in megabytes per second, for example
+# to keep a network share usable by others.
+#
+# max_copy_rate = 20
+#
+# Only the permissions of the copied files are kept by default.
+# List the metadata to keep among mtime (the dates), permissions,
+# xattrs (the extended attributes), ownership (the owner and group,
+# when allowed), or set "all" to copy like `cp -a`. A copy may
+# also be given its own list, eg `:cp --preserve=mtime dest` or
+# `:cp -a dest`.
+#
+# copy_preserve = "mtime,permissions"
+
+###############################################################
+# Protected paths
+# A destructive verb (rm, mv, trash, chmod, chown, organize,
+# sanitize_names, rename_regex, and your verbs declared with
+# `confirm = true`) applied to one of those paths or to a path
+# inside them, or moving a file into them, asks you to type the
+# name of the path first. `/` only protects itself and its direct
+# children. Set an empty array to never ask.
+#
+# protected_paths = ["/", "/etc", "/usr"]
+
+###############################################################
+# Trusted projects
+# The verbs of the .broot file of a project are only added when
+# the directory of this file is listed here, as a cloned
+# repository could otherwise give you any command.
+#
+# trusted_projects = ["~/dev/my-project"]
+
+###############################################################
+# Icons
+# An icon depending on the type of the file can be written before
+# each name. The default glyphs need a "Nerd Font" in your terminal.
+# Icons of extensions can be added or changed in the [icons]
+# section below.
+#
+# show_icons = true
+
+###############################################################
+# Skin preset
+# broot ships with a few skins: "default", "light" (for white
+# backgrounds) and "solarized-dark". The entries of the [skin]
+# section below are applied over the chosen one.
+# You can also try them with :set_skin while broot is running.
+# skin_preset is used on dark backgrounds and light_skin_preset
+# on light ones (skin_preset set alone being used on both), broot
+# detecting the background of the terminal unless theme_mode is
+# set to "dark" or "light" instead of "auto".
+# :toggle_theme_mode switches between them while broot is running.
+#
+# skin_preset = "solarized-dark"
+# light_skin_preset = "light"
+# theme_mode = "dark"
+
+###############################################################
+# Terminal
+# broot draws on the alternate screen of the terminal, which is
+# restored on exit. With alt_screen = false, broot draws on the
+# normal screen so that the last tree stays in the terminal (this
+# can also be asked with the --no-alt-screen launch argument).
+# With set_terminal_title = true, the title of the terminal is
+# the current root.
+# broot captures the mouse: a click selects a line, a double click
+# opens it and the wheel scrolls. Set mouse_capture = false to keep
+# the native selection of text of your terminal instead.
+# The empty input shows a dimmed placeholder, and the end of the
+# name of the verb being typed is suggested, tab completing it.
+# Set input_hints = false to hide them.
+# The home directory is written `~` in the root line and the status,
+# unless abbreviate_home = false. With shorten_paths = true, the
+# directories of a root path too long for the terminal are reduced
+# to their first letter (eg `~/d/b/src`).
+# With accessible = true (or the --accessible launch argument), the
+# tree is drawn without symbols nor scrollbar, and the status tells
+# the path of the selection, for screen readers.
+#
+# alt_screen = false
+# set_terminal_title = true
+# mouse_capture = false
+# input_hints = false
+# abbreviate_home = false
+# shorten_paths = true
+# accessible = true
+
+###############################################################
+# Start screen
+# When start_screen is true and broot is launched without path,
+# it starts on a screen listing the bookmarks and the directories
+# it was recently launched on.
+# Bookmarks must be absolute paths or start with `~`.
+#
+# start_screen = true
+# bookmarks = [
+#     "~/dev",
+# ]
+
+###############################################################
+# Pruned directories
+# Directories matching those globs are shown but never entered,
+# neither to display the tree nor to search, which makes searches
+# in code trees much faster. Unlike "no-enter" special paths, they
+# can also be given at launch with --prune.
+#
+# prune = [".git", "node_modules", "target"]
+
+###############################################################
+# Nested launches
+# When broot is launched from a verb of a running broot (for
+# example in a shell opened by a verb), it asks whether the path
+# should rather be opened in the running broot. Set this to "send"
+# to always do it without asking, or to "start" to always start a
+# second broot.
+#
+# nested_launch = "send"
+
+###############################################################
+# Special paths
+# Some paths may be handled specially:
+#  - "hide" : never shown
+#  - "no-enter" : shown, but their content is never listed
+#  - "show" : shown even when hidden files or gitignored
+#    ones aren't
+# A glob without slash applies to file names, other ones apply
+# to whole paths and must be absolute or start with `~` or `**`.
+# A directory may also be given a sort (name, count, date or size),
+# or a profile, applied when it becomes the root of the tree.
+#
+# [special-paths]
+# "node_modules" = "hide"
+# "~/.config/broot/backups" = "no-enter"
+# ".gitignore" = "show"
+# "~/Downloads" = { sort = "date" }
+# "~/dev/*" = { profile = "code" }
+
+###############################################################
+# Profiles
+# Named sets of flags (as in default_flags) and sort, applied
+# with `:profile name` or when entering a special path naming
+# them.
+#
+# [profiles]
+# code = { flags = "gIH" }
+# cleanup = { flags = "s", sort = "size" }
+
+###############################################################
+# Trash commands
+# Where the native trash fails (for example on some network
+# mounts), :trash may run a command instead. The first command
+# whose glob matches the whole path of the trashed file is used.
+#
+# [trash-commands]
+# "/mnt/nas/**" = "gio trash {file}"
+
+###############################################################
+# Organize rules
+# :organize moves the files of a directory to subfolders. Each
+# file goes to the folder of the first rule whose glob matches its
+# name, the files matched by no rule staying in place. A folder
+# may contain {ext}, {year}, {month} and {day}, the date being the
+# one of the last modification. Without rule, the files are moved
+# to a folder per extension.
+#
+# [[organize-rules]]
+# glob = "*.jpg"
+# folder = "photos/{year}-{month}"
+#
+# [[organize-rules]]
+# glob = "*"
+# folder = "{ext}"
+
 ###############################################################
 # Verbs and shortcuts
 
@@ -201,6 +1044,8 @@ default_flags = ""
 #  pattern.
 # Example:
 #  execution = "/usr/bin/nvim {file}"
+# On Windows, where $EDITOR is rarely set, you may use
+#  execution = "notepad {file}"
 #
 [[verbs]]
 invocation = "edit"
@@ -231,6 +1076,19 @@ name = "view"
 invocation = "view"
 execution = "$PAGER {file}"
 
+# A verb may ask its arguments, one after the other, when it's
+# called without them. Here the new name is asked before the
+# destination, whose default is proposed:
+#
+# [[verbs]]
+# invocation = "dup {destination=../} {newname}"
+# key = "ctrl-y"
+# execution = "/bin/cp -r {file} {directory}/{destination}/{newname}"
+# prompts = [
+#     { arg = "newname", label = "new name" },
+#     { arg = "destination", label = "destination directory" },
+# ]
+
 # If you uncomment the two next shortcuts, the left
 # and right arrow keys will be used to go to the parent
 # directory or to open a selected one:
@@ -268,6 +1126,27 @@ execution = "$PAGER {file}"
 # key = "ctrl-g"
 # execution = ":toggle_git_status"
 
+###############################################################
+# Keys
+# Keys can also be bound to verbs, built-in or not, by their
+# name. A key given here is taken from the verb it was previously
+# triggering, if any.
+#
+# [keys]
+# "ctrl-j" = "line_down"
+# "ctrl-k" = "line_up"
+# "ctrl-x" = "quit"
+
+###############################################################
+# Icons of extensions
+# The ":dir", ":file", ":link" and ":exe" keys give the icons
+# used when no extension matches.
+#
+# [icons]
+# rs = ""
+# log = "L"
+# ":dir" = ""
+
 ###############################################################
 # Skin
 # If you want to change the colors of broot,
@@ -284,6 +1163,7 @@ execution = "$PAGER {file}"
 # directory = "rgb(255, 152, 0) none bold"
 # exe = "rgb(17, 164, 181) none"
 # link = "Magenta none"
+# broken_link = "Red none italic"
 # pruning = "rgb(89, 73, 101) none Italic"
 # perm__ = "gray(5) None"
 # perm_r = "ansi(94) None"
@@ -291,14 +1171,21 @@ execution = "$PAGER {file}"
 # perm_x = "ansi(65) None"
 # owner = "gray(12) none"
 # group = "gray(12) none"
+# freshness_hour = "ansi(196) none"
+# freshness_day = "ansi(208) none"
+# freshness_week = "ansi(214) none"
+# freshness_month = "ansi(179) none"
 # selected_line = "none gray(3)"
 # char_match = "yellow none"
+# char_match_selected = "yellow none"
 # file_error = "Red none"
 # flag_label = "gray(16) none"
 # flag_value = "rgb(255, 152, 0) none bold"
 # input = "White none"
+# input_hint = "gray(10) none italic"
 # status_error = "Red gray(2)"
 # status_job = "ansi(220) gray(5)"
+# status_breadcrumb = "gray(13) gray(3)"
 # status_normal = "gray(20) gray(3)"
 # status_italic = "rgb(255, 152, 0) None"
 # status_bold = "rgb(255, 152, 0) None bold"
@@ -318,3 +1205,45 @@ execution = "$PAGER {file}"
 
 "#;
 
+
+#[cfg(test)]
+mod conf_tests {
+
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "HOME" => Some("/home/me".to_string()),
+            "EDITOR" => Some("nvim".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn check_vars_expansion() {
+        assert_eq!(expand_vars("$HOME/dev", lookup), "/home/me/dev");
+        assert_eq!(expand_vars("${HOME}_old/dev", lookup), "/home/me_old/dev");
+        assert_eq!(expand_vars("$EDITOR {file}", lookup), "nvim {file}");
+        // unknown variables are kept for the execution
+        assert_eq!(expand_vars("$PAGER {file}", lookup), "$PAGER {file}");
+        assert_eq!(expand_vars("echo $$HOME costs 5$", lookup), "echo $HOME costs 5$");
+    }
+
+    #[test]
+    fn check_special_path_values() {
+        let value = |toml: &str| {
+            let root = format!("v = {}", toml).parse::<Value>().unwrap();
+            special_path_value(&root["v"])
+        };
+        assert_eq!(value(r#""hide""#), Some((Some(SpecialHandling::Hide), None, None)));
+        assert_eq!(value(r#"{ sort = "date" }"#), Some((None, Some(Sort::Date), None)));
+        assert_eq!(
+            value(r#"{ handling = "show", sort = "name" }"#),
+            Some((Some(SpecialHandling::Show), Some(Sort::None), None)),
+        );
+        assert_eq!(value(r#"{ profile = "code" }"#), Some((None, None, Some("code".to_string()))));
+        assert_eq!(value(r#"{ sort = "color" }"#), None);
+        assert_eq!(value(r#"{ }"#), None);
+        assert_eq!(value("3"), None);
+    }
+}
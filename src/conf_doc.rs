@@ -0,0 +1,191 @@
+//! the description of the keys broot recognizes in its configuration
+//! files, and of the values they were given.
+//!
+//! Reading a file warns about the keys which aren't in `CONF_KEYS`, so
+//! that this list can't silently miss one.
+
+use {
+    minimad::{Text, TextTemplate},
+    std::path::{Path, PathBuf},
+    toml::Value,
+};
+
+/// a key of the configuration files
+pub struct ConfKey {
+    pub name: &'static str,
+    pub kind: &'static str,    // the type of the value, as written in the file
+    pub default: &'static str, // the value used when the key isn't set
+}
+
+/// all the keys broot reads at the root of its configuration files
+pub static CONF_KEYS: &[ConfKey] = &[
+    ConfKey { name: "abbreviate_home", kind: "boolean", default: "true" },
+    ConfKey { name: "accessible", kind: "boolean", default: "false" },
+    ConfKey { name: "alt_screen", kind: "boolean", default: "true" },
+    ConfKey { name: "align_size_units", kind: "boolean", default: "false" },
+    ConfKey { name: "bookmarks", kind: "array of paths", default: "none" },
+    ConfKey { name: "cols_order", kind: "string of column names", default: "built-in order" },
+    ConfKey { name: "cols_priority", kind: "array of column names", default: "built-in order" },
+    ConfKey { name: "copy_preserve", kind: "string", default: "permissions" },
+    ConfKey { name: "date_source", kind: "string", default: "modified" },
+    ConfKey { name: "default_flags", kind: "string", default: "none" },
+    ConfKey { name: "default_sort", kind: "string", default: "none" },
+    ConfKey { name: "dot_pattern_shows_hidden", kind: "boolean", default: "true" },
+    ConfKey { name: "icons", kind: "table of glyphs by extension", default: "built-in icons" },
+    ConfKey { name: "input_hints", kind: "boolean", default: "true" },
+    ConfKey { name: "keys", kind: "table of verb names by key", default: "none" },
+    ConfKey { name: "light_skin_preset", kind: "string", default: "skin_preset, or light" },
+    ConfKey { name: "locale_collation", kind: "boolean", default: "false" },
+    ConfKey { name: "max_copy_rate", kind: "integer (MB/s)", default: "unlimited" },
+    ConfKey { name: "mouse_capture", kind: "boolean", default: "true" },
+    ConfKey { name: "nested_launch", kind: "ask, send or start", default: "ask" },
+    ConfKey { name: "organize-rules", kind: "array of tables", default: "folders by extension" },
+    ConfKey { name: "persist_input_history", kind: "boolean", default: "false" },
+    ConfKey { name: "persist_queue", kind: "boolean", default: "false" },
+    ConfKey { name: "profiles", kind: "table of flags and sorts by name", default: "none" },
+    ConfKey { name: "protected_paths", kind: "array of paths", default: "/, /etc and /usr" },
+    ConfKey { name: "prune", kind: "array of globs", default: "none" },
+    ConfKey { name: "search_symlinked_dirs", kind: "boolean", default: "false" },
+    ConfKey { name: "set_terminal_title", kind: "boolean", default: "false" },
+    ConfKey { name: "shorten_paths", kind: "boolean", default: "false" },
+    ConfKey { name: "show_icons", kind: "boolean", default: "false" },
+    ConfKey { name: "skin", kind: "table of styles", default: "the preset's one" },
+    ConfKey { name: "skin_preset", kind: "string", default: "default" },
+    ConfKey { name: "special-paths", kind: "table of handlings/sorts by glob", default: "none" },
+    ConfKey { name: "start_screen", kind: "boolean", default: "false" },
+    ConfKey { name: "theme_mode", kind: "auto, dark or light", default: "auto" },
+    ConfKey { name: "tie_breaker", kind: "string", default: "shallower" },
+    ConfKey { name: "trash-commands", kind: "table of commands by glob", default: "none" },
+    ConfKey { name: "trusted_projects", kind: "array of paths", default: "none" },
+    ConfKey { name: "usage_stats", kind: "boolean", default: "false" },
+    ConfKey { name: "verbs", kind: "array of verb tables", default: "none" },
+    ConfKey { name: "watch_idle_pause", kind: "integer (seconds)", default: "300" },
+];
+
+/// tell whether broot reads this key at the root of a configuration file
+pub fn is_known_key(name: &str) -> bool {
+    CONF_KEYS.iter().any(|key| key.name == name)
+}
+
+/// a key which was set in a configuration file
+#[derive(Debug, Clone)]
+pub struct ConfSetting {
+    pub key: String,
+    pub value: String, // a short description of the value
+    pub file: PathBuf,
+}
+
+impl ConfSetting {
+    pub fn new(key: &str, value: &Value, file: &Path) -> ConfSetting {
+        ConfSetting {
+            key: key.to_string(),
+            value: summarize(value),
+            file: file.to_path_buf(),
+        }
+    }
+}
+
+fn entries(count: usize) -> String {
+    format!("{} entr{}", count, if count == 1 { "y" } else { "ies" })
+}
+
+/// describe a value in a way which fits a table cell: the tables
+/// and arrays of tables are just counted
+fn summarize(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.to_string(),
+        Value::Table(tbl) => entries(tbl.len()),
+        Value::Array(arr) if arr.iter().any(Value::is_table) => entries(arr.len()),
+        Value::Array(arr) => arr
+            .iter()
+            .map(summarize)
+            .collect::<Vec<String>>()
+            .join(" "),
+        _ => value.to_string(),
+    }
+}
+
+static MD: &str = r#"
+# Configuration keys
+The keys broot reads at the root of its configuration files, with the value given in the last file setting it (the system one, then yours, then the project's one for the verbs and special paths).
+|:-|:-|:-|:-|:-
+|**key**|**type**|**default**|**value**|**set in**
+|:-|:-|:-|:-|:-
+${key-rows
+|${key}|${kind}|${default}|${value}|${files}
+}
+|-
+"#;
+
+/// a key with its effective value
+pub struct KeyRow {
+    key: &'static ConfKey,
+    value: String, // the one given in the last file setting it
+    files: String, // the files setting it
+}
+
+/// build the rows of all the keys, from the settings read in the
+/// configuration files (in order of precedence)
+pub fn key_rows(settings: &[ConfSetting]) -> Vec<KeyRow> {
+    CONF_KEYS
+        .iter()
+        .map(|key| {
+            let key_settings: Vec<&ConfSetting> =
+                settings.iter().filter(|s| s.key == key.name).collect();
+            let value = key_settings
+                .last()
+                .map_or_else(|| "-".to_string(), |setting| setting.value.to_string());
+            let files = key_settings
+                .iter()
+                .map(|setting| setting.file.to_string_lossy().to_string())
+                .collect::<Vec<String>>()
+                .join(", ");
+            KeyRow { key, value, files }
+        })
+        .collect()
+}
+
+/// build the markdown text describing the keys and their values
+pub fn build_text(rows: &[KeyRow]) -> Text<'_> {
+    lazy_static! {
+        static ref TEMPLATE: TextTemplate<'static> = TextTemplate::from(MD);
+    }
+    let mut expander = TEMPLATE.expander();
+    for row in rows {
+        expander
+            .sub("key-rows")
+            .set("key", row.key.name)
+            .set("kind", row.key.kind)
+            .set("default", row.key.default)
+            .set("value", &row.value)
+            .set("files", &row.files);
+    }
+    expander.expand()
+}
+
+#[cfg(test)]
+mod conf_doc_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_summaries() {
+        let root: Value = r#"
+            bookmarks = ["~/dev", "/tmp"]
+            show_icons = true
+            [[verbs]]
+            invocation = "edit"
+            [[verbs]]
+            invocation = "view"
+            [skin]
+            default = "gray(22) none"
+        "#
+        .parse()
+        .unwrap();
+        let summary = |key: &str| summarize(&root[key]);
+        assert_eq!(summary("bookmarks"), "~/dev /tmp");
+        assert_eq!(summary("show_icons"), "true");
+        assert_eq!(summary("verbs"), "2 entries");
+        assert_eq!(summary("skin"), "1 entry");
+    }
+}
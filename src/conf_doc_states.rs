@@ -0,0 +1,200 @@
+use {
+    crate::{
+        app_context::AppContext,
+        app_state::{AppState, AppStateCmdResult},
+        commands::{Action, Command},
+        conf_doc::{self, KeyRow},
+        errors::ProgramError,
+        help_content::{ScreenHelp, ScreenKey},
+        help_states::HelpState,
+        screens::Screen,
+        status::Status,
+        task_sync::Dam,
+        verb_store::PrefixSearchResult,
+    },
+    crossterm::{
+        terminal::{Clear, ClearType},
+        QueueableCommand,
+    },
+    minimad::Composite,
+    std::io::Write,
+    termimad::{Area, FmtText, TextView},
+};
+
+/// the help of the configuration keys screen
+pub static HELP: ScreenHelp = ScreenHelp {
+    name: "configuration keys screen",
+    description: "This screen lists the keys of the configuration files, with their types, \
+        their defaults, and the values given in your files.",
+    keys: &[
+        ScreenKey::hinted("esc", "get back to the previous screen", "to get back"),
+        ScreenKey::new("↑ ↓", "scroll"),
+        ScreenKey::hinted("?", "display this help", "for the help"),
+    ],
+    builtins: Some(&[
+        ":back", ":config_doc", ":help", ":line_down", ":line_up", ":page_down", ":page_up",
+        ":quit",
+    ]),
+    external_verbs: false,
+};
+
+/// an application state describing the keys of the configuration
+/// and the values the configuration files give them
+pub struct ConfDocState {
+    rows: Vec<KeyRow>,
+    scroll: i32,
+    area: Area,
+    dirty: bool, // background must be cleared
+}
+
+impl ConfDocState {
+    pub fn new(con: &AppContext) -> ConfDocState {
+        ConfDocState {
+            rows: conf_doc::key_rows(&con.conf_settings),
+            scroll: 0,
+            area: Area::uninitialized(), // will be fixed at drawing time
+            dirty: true,
+        }
+    }
+}
+
+impl AppState for ConfDocState {
+    fn help(&self) -> &'static ScreenHelp {
+        &HELP
+    }
+
+    fn has_pending_task(&self) -> bool {
+        false
+    }
+
+    fn can_execute(&self, _verb_index: usize, _con: &AppContext) -> bool {
+        true
+    }
+
+    fn apply(
+        &mut self,
+        cmd: &mut Command,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<AppStateCmdResult, ProgramError> {
+        let verb_execution = match &cmd.action {
+            Action::VerbIndex(index) => Some(con.verb_store.verbs[*index].execution.as_str()),
+            Action::VerbInvocate(invocation) => match con.verb_store.search(&invocation.name) {
+                PrefixSearchResult::Match(verb) => Some(verb.execution.as_str()),
+                _ => {
+                    return Ok(AppStateCmdResult::verb_not_found(&invocation.name));
+                }
+            },
+            _ => None,
+        };
+        let page_height = self.area.height as i32;
+        Ok(match (&cmd.action, verb_execution) {
+            (Action::Back, _) | (_, Some(":back")) => AppStateCmdResult::PopState,
+            (Action::Help, _) | (_, Some(":help")) => AppStateCmdResult::NewState(
+                Box::new(HelpState::new(screen, con, &HELP)),
+                Command::new(),
+            ),
+            (_, Some(":config_doc")) => AppStateCmdResult::Keep,
+            (_, Some(":line_down")) => {
+                self.scroll += 1;
+                AppStateCmdResult::Keep
+            }
+            (_, Some(":line_up")) => {
+                self.scroll -= 1;
+                AppStateCmdResult::Keep
+            }
+            (_, Some(":page_down")) => {
+                self.scroll += page_height;
+                AppStateCmdResult::Keep
+            }
+            (_, Some(":page_up")) => {
+                self.scroll -= page_height;
+                AppStateCmdResult::Keep
+            }
+            (_, Some(":quit")) => AppStateCmdResult::Quit,
+            // the other verbs apply to the previous screen
+            (_, Some(_)) => AppStateCmdResult::PopStateAndReapply,
+            (Action::MoveSelection(dy), _) | (Action::Scroll(dy), _) => {
+                self.scroll += *dy;
+                AppStateCmdResult::Keep
+            }
+            (Action::Resize(w, h), _) => {
+                screen.set_terminal_size(*w, *h, con);
+                self.dirty = true;
+                AppStateCmdResult::Keep
+            }
+            _ => AppStateCmdResult::Keep,
+        })
+    }
+
+    fn refresh(&mut self, _screen: &Screen, con: &AppContext) -> Command {
+        self.rows = conf_doc::key_rows(&con.conf_settings);
+        self.dirty = true;
+        Command::new()
+    }
+
+    fn do_pending_task(&mut self, _screen: &mut Screen, _con: &AppContext, _dam: &mut Dam) {
+        unreachable!();
+    }
+
+    fn display(
+        &mut self,
+        mut w: &mut dyn Write,
+        screen: &Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        if self.dirty {
+            screen.skin.default.queue_bg(&mut w)?;
+            screen.clear(&mut w)?;
+            self.area = Area::new(0, 0, screen.width, screen.height - 2);
+            self.area.pad_for_max_width(140);
+            self.dirty = false;
+        }
+        let text = conf_doc::build_text(&self.rows);
+        let fmt_text = FmtText::from_text(
+            &screen.help_skin,
+            text,
+            Some((self.area.width - 1) as usize),
+        );
+        let mut text_view = TextView::from(&self.area, &fmt_text);
+        self.scroll = text_view.set_scroll(self.scroll);
+        Ok(text_view.write_on(&mut w)?)
+    }
+
+    fn write_status(
+        &self,
+        mut w: &mut dyn Write,
+        cmd: &Command,
+        screen: &Screen,
+        con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        match &cmd.action {
+            Action::VerbEdit(invocation) if !invocation.name.is_empty() => {
+                match con.verb_store.search(&invocation.name) {
+                    PrefixSearchResult::Match(_) => Status::from_message(mad_inline!(
+                        "Hit *enter* to execute the verb"
+                    ))
+                    .display(&mut w, screen),
+                    _ => Status::from_error(mad_inline!("No matching verb"))
+                        .display(&mut w, screen),
+                }
+            }
+            _ => {
+                let hint = format!("{}, or a space to start a verb", HELP.keys_hint());
+                Status::from_message(Composite::from_inline(&hint)).display(&mut w, screen)
+            }
+        }
+    }
+
+    /// there's no meaningful flags here
+    fn write_flags(
+        &self,
+        mut w: &mut dyn Write,
+        screen: &mut Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        screen.skin.default.queue_bg(&mut w)?;
+        w.queue(Clear(ClearType::UntilNewLine))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,238 @@
+use {
+    crate::{
+        app_context::AppContext,
+        app_state::{AppState, AppStateCmdResult},
+        commands::{Action, Command},
+        errors::ProgramError,
+        help_content::{ScreenHelp, ScreenKey},
+        screens::Screen,
+        status::Status,
+        task_sync::Dam,
+    },
+    crossterm::{
+        terminal::{Clear, ClearType},
+        QueueableCommand,
+    },
+    std::{
+        io::Write,
+        path::{Path, PathBuf},
+    },
+};
+
+/// return the path with the links of its parent resolved, so that eg
+/// `/bin/ls` is seen as `/usr/bin/ls` when `/bin` is a link to `/usr/bin`.
+/// The path itself isn't resolved, as a verb applied to a link changes
+/// the link.
+fn real_path(path: &Path) -> PathBuf {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => match parent.canonicalize() {
+            Ok(parent) => parent.join(name),
+            Err(_) => path.to_path_buf(),
+        },
+        _ => path.to_path_buf(),
+    }
+}
+
+/// tell whether the path is the protected one or is inside it.
+/// As all paths are inside `/`, a root only protects itself and
+/// its direct children.
+fn is_inside(path: &Path, protected: &Path) -> bool {
+    if protected.parent().is_none() {
+        path == protected || path.parent() == Some(protected)
+    } else {
+        path.starts_with(protected)
+    }
+}
+
+/// tell whether a destructive verb applied to the path must be confirmed,
+/// that is whether the path is one of the protected paths or is inside one
+/// of them, the links being resolved
+pub fn is_protected(path: &Path, protected_paths: &[PathBuf]) -> bool {
+    let real = real_path(path);
+    protected_paths.iter().any(|protected| {
+        let real_protected = protected.canonicalize().unwrap_or_else(|_| protected.clone());
+        [path, real.as_path()]
+            .iter()
+            .any(|path| is_inside(path, protected) || is_inside(path, &real_protected))
+    })
+}
+
+/// the help of the confirmation screen
+pub static HELP: ScreenHelp = ScreenHelp {
+    name: "confirmation screen",
+    description: "This screen asks, before a verb changing a protected path is executed, \
+        to type the name of the path.",
+    keys: &[
+        ScreenKey::new("esc", "get back to the tree without executing the verb"),
+        ScreenKey::new("enter", "execute the verb, when the typed name matches"),
+    ],
+    builtins: Some(&[]),
+    external_verbs: false,
+};
+
+/// an application state asking, before a destructive verb is applied
+/// to a protected path, to type the name of the path. The verb call is
+/// done on the previous state once the typed name matches.
+pub struct ConfirmState {
+    action: Action, // the verb call, done again once confirmed
+    verb_name: String,
+    path: PathBuf,
+    expected: String, // what must be typed to confirm
+}
+
+impl ConfirmState {
+    pub fn new(action: Action, verb_name: &str, path: &Path) -> ConfirmState {
+        let expected = path
+            .file_name()
+            .unwrap_or_else(|| path.as_os_str())
+            .to_string_lossy()
+            .to_string();
+        ConfirmState {
+            action,
+            verb_name: verb_name.to_string(),
+            path: path.to_path_buf(),
+            expected,
+        }
+    }
+}
+
+impl AppState for ConfirmState {
+    fn help(&self) -> &'static ScreenHelp {
+        &HELP
+    }
+
+    fn has_pending_task(&self) -> bool {
+        false
+    }
+
+    fn can_execute(&self, _verb_index: usize, _con: &AppContext) -> bool {
+        false
+    }
+
+    fn apply(
+        &mut self,
+        cmd: &mut Command,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<AppStateCmdResult, ProgramError> {
+        Ok(match &cmd.action {
+            Action::Back => AppStateCmdResult::PopState,
+            // the typed name may contain a space, which makes it look like a verb
+            Action::OpenSelection | Action::VerbInvocate(_) => {
+                if cmd.raw.trim() == self.expected {
+                    cmd.raw.clear();
+                    cmd.action = self.action.clone();
+                    cmd.confirmed_path = Some(self.path.clone());
+                    AppStateCmdResult::PopStateAndReapply
+                } else {
+                    AppStateCmdResult::DisplayError(format!(
+                        "type {:?} to confirm, or hit esc to cancel",
+                        self.expected,
+                    ))
+                }
+            }
+            Action::Resize(w, h) => {
+                screen.set_terminal_size(*w, *h, con);
+                AppStateCmdResult::Keep
+            }
+            _ => AppStateCmdResult::Keep,
+        })
+    }
+
+    fn refresh(&mut self, _screen: &Screen, _con: &AppContext) -> Command {
+        Command::new()
+    }
+
+    fn do_pending_task(&mut self, _screen: &mut Screen, _con: &AppContext, _dam: &mut Dam) {
+        unreachable!();
+    }
+
+    fn display(
+        &mut self,
+        mut w: &mut dyn Write,
+        screen: &Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        screen.goto_clear(&mut w, 0, 0)?;
+        screen.skin.default.queue_bg(&mut w)?;
+        screen.skin.help_headers.queue_str(&mut w, "protected path")?;
+        screen.clear_line(&mut w)?;
+        screen.goto_clear(&mut w, 0, 1)?;
+        screen.goto(&mut w, 0, 2)?;
+        screen.skin.default.queue(&mut w, format!(":{} would apply to ", self.verb_name))?;
+        screen.skin.file_error.queue(&mut w, self.path.to_string_lossy().to_string())?;
+        w.queue(Clear(ClearType::UntilNewLine))?;
+        screen.goto(&mut w, 0, 3)?;
+        screen.skin.tree.queue_str(
+            &mut w,
+            "which is protected (see protected_paths in the configuration)",
+        )?;
+        w.queue(Clear(ClearType::UntilNewLine))?;
+        for y in 4..screen.height.max(3) - 2 {
+            screen.goto(&mut w, 0, y)?;
+            w.queue(Clear(ClearType::UntilNewLine))?;
+        }
+        Ok(())
+    }
+
+    fn write_status(
+        &self,
+        mut w: &mut dyn Write,
+        _cmd: &Command,
+        screen: &Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        Status::from_message(mad_inline!(
+            "Type *$0* then hit *enter* to confirm, *esc* to cancel",
+            &self.expected,
+        ))
+        .display(&mut w, screen)
+    }
+
+    /// there's no meaningful flags here
+    fn write_flags(
+        &self,
+        mut w: &mut dyn Write,
+        screen: &mut Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        screen.skin.default.queue_bg(&mut w)?;
+        w.queue(Clear(ClearType::UntilNewLine))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod confirm_states_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_protection() {
+        let protected_paths = vec![PathBuf::from("/"), PathBuf::from("/etc")];
+        let check = |path: &str, expected: bool| {
+            assert_eq!(is_protected(Path::new(path), &protected_paths), expected);
+        };
+        check("/", true);
+        check("/home", true);
+        check("/home/me", false);
+        check("/etc", true);
+        check("/etc/ssh/sshd_config", true);
+        check("/etcetera/file", false);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn check_protection_through_links() {
+        let dir = std::env::temp_dir().join(format!("broot-protection-{}", std::process::id()));
+        let real = dir.join("real");
+        std::fs::create_dir_all(&real).unwrap();
+        std::os::unix::fs::symlink(&real, dir.join("link")).unwrap();
+        let protected_paths = vec![real.clone()];
+        assert!(is_protected(&dir.join("link/file"), &protected_paths));
+        assert!(!is_protected(&dir.join("link"), &protected_paths));
+        let protected_paths = vec![dir.join("link")];
+        assert!(is_protected(&real.join("file"), &protected_paths));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,393 @@
+use {
+    crate::{
+        app_context::AppContext,
+        app_state::{AppState, AppStateCmdResult},
+        commands::{Action, Command},
+        copy_verbs,
+        errors::{FileOp, FileOpError, ProgramError},
+        file_copy::{ConflictChoice, FileCopy},
+        file_sizes::FileSize,
+        help_content::ScreenHelp,
+        help_states::HelpState,
+        op_journal::{self, Operation},
+        screens::Screen,
+        status::Status,
+        task_sync::Dam,
+        verb_store::PrefixSearchResult,
+        verbs::VerbExecutor,
+    },
+    crossterm::{
+        terminal::{Clear, ClearType},
+        QueueableCommand,
+    },
+    chrono::{DateTime, Local},
+    std::{fs, io::Write, path::Path, time::Duration},
+    termimad::ProgressBar,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+enum CopyStatus {
+    Running,
+    Cancelled,
+    Failed(String),
+    Done,
+}
+
+/// an application state showing the progress of a copy done by
+/// broot (see `:cp`), which goes on as long as the state is displayed.
+///
+/// When a file already exists at the destination, the copy waits
+/// for the user to choose what to do, for this file or for all
+/// the conflicts.
+pub struct CopyState {
+    copy: FileCopy,
+    status: CopyStatus,
+    selected_choice: usize, // index in ConflictChoice::ALL
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}h{:02}m", secs / 3600, secs % 3600 / 60)
+    } else if secs >= 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// describe the size and date of a file or directory, so that the
+/// user can compare the source with what exists at the destination
+fn describe_file(path: &Path) -> String {
+    match fs::symlink_metadata(path) {
+        Ok(md) => {
+            let kind = if md.is_dir() {
+                "directory".to_string()
+            } else {
+                FileSize::new(md.len(), false).to_string()
+            };
+            match md.modified() {
+                Ok(date) => {
+                    let date: DateTime<Local> = date.into();
+                    format!("{}, modified {}", kind, date.format("%Y/%m/%d %R"))
+                }
+                Err(_) => kind,
+            }
+        }
+        Err(e) => e.to_string(),
+    }
+}
+
+impl CopyState {
+    pub fn new(copy: FileCopy) -> CopyState {
+        CopyState {
+            copy,
+            status: CopyStatus::Running,
+            selected_choice: 0,
+        }
+    }
+
+    fn has_conflict(&self) -> bool {
+        self.status == CopyStatus::Running && self.copy.conflict().is_some()
+    }
+
+    fn move_choice(&mut self, dy: i32) {
+        let count = ConflictChoice::ALL.len() as i32;
+        self.selected_choice = (self.selected_choice as i32 + dy).rem_euclid(count) as usize;
+    }
+
+    /// apply the selected choice to the conflict, and to the
+    /// following ones too when `for_all` is true
+    fn resolve(&mut self, for_all: bool) -> AppStateCmdResult {
+        let choice = ConflictChoice::ALL[self.selected_choice];
+        match self.copy.resolve(choice, for_all) {
+            Ok(()) => AppStateCmdResult::Keep,
+            // the conflict is still there, waiting for another choice
+            Err(e) => AppStateCmdResult::DisplayError(e.to_string()),
+        }
+    }
+
+    /// stop the copy, which may be resumed later (the file being
+    /// copied is resumed from where it was)
+    pub fn cancel(&mut self) {
+        if self.status == CopyStatus::Running {
+            self.copy.interrupt();
+            self.status = CopyStatus::Cancelled;
+        }
+    }
+
+    /// go on with a cancelled or failed copy
+    pub fn resume(&mut self) {
+        match self.status {
+            CopyStatus::Cancelled | CopyStatus::Failed(_) => {
+                self.copy.resume();
+                self.status = CopyStatus::Running;
+            }
+            _ => {}
+        }
+    }
+
+    /// cancel the copy if it runs, or leave the state
+    pub fn back(&mut self) -> AppStateCmdResult {
+        if self.status == CopyStatus::Running {
+            self.cancel();
+            AppStateCmdResult::Keep
+        } else {
+            AppStateCmdResult::PopState
+        }
+    }
+
+    /// resume the copy if it was stopped, or leave the state once done
+    pub fn validate(&mut self) -> AppStateCmdResult {
+        match self.status {
+            CopyStatus::Done => AppStateCmdResult::PopState,
+            _ => {
+                self.resume();
+                AppStateCmdResult::Keep
+            }
+        }
+    }
+}
+
+impl AppState for CopyState {
+    fn help(&self) -> &'static ScreenHelp {
+        &copy_verbs::HELP
+    }
+
+    fn has_pending_task(&self) -> bool {
+        self.status == CopyStatus::Running && self.copy.conflict().is_none()
+    }
+
+    fn can_execute(&self, _verb_index: usize, _con: &AppContext) -> bool {
+        true
+    }
+
+    fn apply(
+        &mut self,
+        cmd: &mut Command,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<AppStateCmdResult, ProgramError> {
+        Ok(match &cmd.action {
+            Action::Back => self.back(),
+            Action::Help => {
+                AppStateCmdResult::NewState(
+                    Box::new(HelpState::new(screen, con, &copy_verbs::HELP)),
+                    Command::new(),
+                )
+            }
+            Action::MoveSelection(dy) if self.has_conflict() => {
+                self.move_choice(*dy);
+                AppStateCmdResult::Keep
+            }
+            Action::OpenSelection if self.has_conflict() => self.resolve(false),
+            Action::AltOpenSelection if self.has_conflict() => self.resolve(true),
+            Action::OpenSelection => self.validate(),
+            Action::Resize(w, h) => {
+                screen.set_terminal_size(*w, *h, con);
+                AppStateCmdResult::Keep
+            }
+            Action::VerbIndex(index) => {
+                let verb = &con.verb_store.verbs[*index];
+                self.execute_verb(verb, &verb.invocation, screen, con)?
+            }
+            Action::VerbInvocate(invocation) => match con.verb_store.search(&invocation.name) {
+                PrefixSearchResult::Match(verb) => {
+                    self.execute_verb(verb, &invocation, screen, con)?
+                }
+                _ => AppStateCmdResult::verb_not_found(&invocation.name),
+            },
+            _ => AppStateCmdResult::Keep,
+        })
+    }
+
+    fn refresh(&mut self, _screen: &Screen, _con: &AppContext) -> Command {
+        Command::new()
+    }
+
+    /// copy a little more
+    fn do_pending_task(&mut self, _screen: &mut Screen, _con: &AppContext, _dam: &mut Dam) {
+        if let Err(e) = self.copy.step() {
+            let e = FileOpError::new(FileOp::Copy, &self.copy.source, e).with_dest(&self.copy.dest);
+            warn!("copy failed: {:?}", e);
+            self.copy.interrupt();
+            self.status = CopyStatus::Failed(e.to_string());
+        } else if self.copy.is_done() {
+            self.status = CopyStatus::Done;
+            // removing a destination which existed before would lose data
+            if !self.copy.reused_dest {
+                op_journal::record(Operation::Copy(self.copy.dest.clone()));
+            }
+        }
+    }
+
+    fn display(
+        &mut self,
+        mut w: &mut dyn Write,
+        screen: &Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        let copy = &self.copy;
+        screen.goto_clear(&mut w, 0, 0)?;
+        screen.skin.default.queue_bg(&mut w)?;
+        screen.skin.help_headers.queue(
+            &mut w,
+            format!(
+                "copy of {} to {}",
+                copy.source.to_string_lossy(),
+                copy.dest.to_string_lossy(),
+            ),
+        )?;
+        screen.clear_line(&mut w)?;
+        screen.goto(&mut w, 0, 2)?;
+        let share = copy.done_share();
+        screen.skin.count.queue(
+            &mut w,
+            format!("{:>4.0}% {:<30} ", 100.0 * share, ProgressBar::new(share, 30)),
+        )?;
+        screen.skin.file.queue(
+            &mut w,
+            format!(
+                "{} of {}",
+                FileSize::new(copy.done_bytes, false),
+                FileSize::new(copy.total_bytes, false),
+            ),
+        )?;
+        if copy.resumed_bytes > 0 {
+            screen.skin.pruning.queue(
+                &mut w,
+                format!(" ({} resumed)", FileSize::new(copy.resumed_bytes, false)),
+            )?;
+        }
+        w.queue(Clear(ClearType::UntilNewLine))?;
+        screen.goto(&mut w, 0, 3)?;
+        if self.status == CopyStatus::Running {
+            screen.skin.tree.queue(
+                &mut w,
+                format!("{}/s", FileSize::new(copy.rate(), false)),
+            )?;
+            if let Some(remaining) = copy.remaining_time() {
+                screen
+                    .skin
+                    .tree
+                    .queue(&mut w, format!(", {} remaining", format_duration(remaining)))?;
+            }
+        }
+        w.queue(Clear(ClearType::UntilNewLine))?;
+        screen.goto(&mut w, 0, 4)?;
+        if let Some(path) = copy.current_file() {
+            screen.skin.file.queue(&mut w, path.to_string_lossy())?;
+        }
+        w.queue(Clear(ClearType::UntilNewLine))?;
+        let mut y = 5;
+        let conflict = copy.conflict().filter(|_| self.status == CopyStatus::Running);
+        if let Some((source, dest)) = conflict {
+            screen.goto(&mut w, 0, 4)?;
+            screen.skin.file_error.queue(
+                &mut w,
+                format!("{} already exists", dest.to_string_lossy()),
+            )?;
+            w.queue(Clear(ClearType::UntilNewLine))?;
+            screen.goto(&mut w, 0, 5)?;
+            screen
+                .skin
+                .tree
+                .queue(&mut w, format!("  source:   {}", describe_file(source)))?;
+            w.queue(Clear(ClearType::UntilNewLine))?;
+            screen.goto(&mut w, 0, 6)?;
+            screen
+                .skin
+                .tree
+                .queue(&mut w, format!("  existing: {}", describe_file(dest)))?;
+            w.queue(Clear(ClearType::UntilNewLine))?;
+            screen.goto_clear(&mut w, 0, 7)?;
+            y = 8;
+            for (idx, choice) in ConflictChoice::ALL.iter().enumerate() {
+                screen.goto(&mut w, 0, y)?;
+                if idx == self.selected_choice {
+                    screen
+                        .skin
+                        .selected_line
+                        .queue(&mut w, format!("> {}", choice.description()))?;
+                } else {
+                    screen
+                        .skin
+                        .default
+                        .queue(&mut w, format!("  {}", choice.description()))?;
+                }
+                w.queue(Clear(ClearType::UntilNewLine))?;
+                y += 1;
+            }
+        }
+        for y in y..screen.height.max(7) - 2 {
+            screen.goto(&mut w, 0, y)?;
+            w.queue(Clear(ClearType::UntilNewLine))?;
+        }
+        Ok(())
+    }
+
+    fn write_status(
+        &self,
+        mut w: &mut dyn Write,
+        cmd: &Command,
+        screen: &Screen,
+        con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        match (&cmd.action, &self.status) {
+            (Action::VerbEdit(invocation), _) if !invocation.name.is_empty() => {
+                match con.verb_store.search(&invocation.name) {
+                    PrefixSearchResult::Match(verb) => Status::from_message(mad_inline!(
+                        "Hit *enter* to **$0**",
+                        &verb.invocation.name,
+                    ))
+                    .display(&mut w, screen),
+                    _ => Status::from_error(mad_inline!("No matching verb"))
+                        .display(&mut w, screen),
+                }
+            }
+            (_, CopyStatus::Running) if self.copy.conflict().is_some() => Status::new(
+                Some("conflict"),
+                mad_inline!("*enter*: this file, *alt-enter*: all the conflicts, *esc*: cancel"),
+                false,
+            )
+            .display(&mut w, screen),
+            (_, CopyStatus::Running) => {
+                Status::new(Some("copying"), mad_inline!("Hit *esc* to cancel"), false)
+                    .display(&mut w, screen)
+            }
+            (_, CopyStatus::Cancelled) => Status::from_message(mad_inline!(
+                "Copy cancelled. Hit *enter* to resume it, *esc* to get back to the tree"
+            ))
+            .display(&mut w, screen),
+            (_, CopyStatus::Failed(e)) => Status::from_error(mad_inline!(
+                "Copy failed: $0. Hit *enter* to retry, *esc* to get back to the tree",
+                e,
+            ))
+            .display(&mut w, screen),
+            (_, CopyStatus::Done) if self.copy.skipped_count > 0 => {
+                let skipped_count = self.copy.skipped_count.to_string();
+                Status::from_message(mad_inline!(
+                    "Copy done, $0 files skipped. Hit *esc* to get back to the tree",
+                    &skipped_count,
+                ))
+                .display(&mut w, screen)
+            }
+            (_, CopyStatus::Done) => {
+                Status::from_message(mad_inline!("Copy done. Hit *esc* to get back to the tree"))
+                    .display(&mut w, screen)
+            }
+        }
+    }
+
+    /// there's no meaningful flags here
+    fn write_flags(
+        &self,
+        mut w: &mut dyn Write,
+        screen: &mut Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        screen.skin.default.queue_bg(&mut w)?;
+        w.queue(Clear(ClearType::UntilNewLine))?;
+        Ok(())
+    }
+}
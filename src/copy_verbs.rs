@@ -0,0 +1,59 @@
+//! this modules defines the execution of verbs on the copy screen
+
+use crate::{
+    app_context::AppContext,
+    app_state::AppStateCmdResult,
+    commands::Command,
+    copy_states::CopyState,
+    errors::ProgramError,
+    help_content::{ScreenHelp, ScreenKey},
+    help_states::HelpState,
+    screens::Screen,
+    verb_invocation::VerbInvocation,
+    verbs::{Verb, VerbExecutor},
+};
+
+/// the help of the copy screen
+pub static HELP: ScreenHelp = ScreenHelp {
+    name: "copy screen",
+    description: "This screen shows the progress of a `:cp`, and asks what to do when \
+        something already exists at the destination.",
+    keys: &[
+        ScreenKey::new("esc", "cancel the copy, or get back to the tree once it's done"),
+        ScreenKey::new("enter", "resume a cancelled copy, or apply the choice to this conflict"),
+        ScreenKey::new("alt-enter", "apply the choice to all the conflicts"),
+        ScreenKey::new("↑ ↓", "select what to do with a conflict"),
+        ScreenKey::new("?", "display this help"),
+    ],
+    builtins: Some(&[":back", ":help", ":quit"]),
+    external_verbs: false,
+};
+
+impl VerbExecutor for CopyState {
+    fn execute_verb(
+        &mut self,
+        verb: &Verb,
+        invocation: &VerbInvocation,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<AppStateCmdResult, ProgramError> {
+        if let Some(err) = verb.match_error(invocation) {
+            return Ok(AppStateCmdResult::DisplayError(err));
+        }
+        if let Some(err) = HELP.unlisted_error(verb) {
+            return Ok(AppStateCmdResult::DisplayError(err));
+        }
+        Ok(match verb.execution.as_ref() {
+            ":back" => self.back(),
+            ":help" => AppStateCmdResult::NewState(
+                Box::new(HelpState::new(screen, con, &HELP)),
+                Command::new(),
+            ),
+            ":quit" => AppStateCmdResult::Quit,
+            _ if verb.execution.starts_with(':') => AppStateCmdResult::Keep,
+            _ => AppStateCmdResult::DisplayError(
+                "hit esc to cancel the copy, or to get back once it's done".to_string(),
+            ),
+        })
+    }
+}
@@ -0,0 +1,150 @@
+//! a filter on the last modification date of files.
+//!
+//! A filter is made of space separated parts, each one
+//! being a date with an optional comparison operator:
+//! - `>2020-03-01` : modified after the first of March 2020
+//! - `<2020-03-01` : modified before the first of March 2020
+//! - `2020-03-01` : modified this very day
+//! - `older_than:180d` : not modified in the last 180 days
+//! - `newer_than:2w` : modified in the last two weeks
+//!
+//! Ages are given in days (`d`), weeks (`w`), months of 30 days (`m`)
+//! or years of 365 days (`y`), and are counted from today.
+//!
+//! Dates are understood in the local timezone.
+
+use {
+    crate::errors::ProgramError,
+    chrono::{offset::Local, DateTime, Duration, NaiveDate},
+    std::{fmt, time::SystemTime},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DateFilter {
+    pub after: Option<NaiveDate>,  // excluded
+    pub before: Option<NaiveDate>, // excluded
+}
+
+/// parse an age like `180d` or `2w` into the date it goes back to
+fn parse_age(age: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let unit = age.chars().last()?;
+    let count = i64::from(age[..age.len() - unit.len_utf8()].parse::<u32>().ok()?);
+    let days = match unit {
+        'd' => count,
+        'w' => count * 7,
+        'm' => count * 30,
+        'y' => count * 365,
+        _ => {
+            return None;
+        }
+    };
+    today.checked_sub_signed(Duration::days(days))
+}
+
+impl DateFilter {
+    pub fn from_str(s: &str) -> Result<DateFilter, ProgramError> {
+        DateFilter::from_str_at(s, Local::today().naive_local())
+    }
+    /// parse the filter, the ages being counted from `today`
+    fn from_str_at(s: &str, today: NaiveDate) -> Result<DateFilter, ProgramError> {
+        let invalid = || ProgramError::InvalidDateFilter {
+            raw: s.to_string(),
+        };
+        let mut filter = DateFilter::default();
+        for part in s.split_whitespace() {
+            if let Some(age) = part.strip_prefix("older_than:") {
+                filter.before = Some(parse_age(age, today).ok_or_else(invalid)?);
+                continue;
+            }
+            if let Some(age) = part.strip_prefix("newer_than:") {
+                filter.after = Some(parse_age(age, today).ok_or_else(invalid)?);
+                continue;
+            }
+            let (op, date) = match part.chars().next() {
+                Some(c) if c == '<' || c == '>' => (Some(c), &part[1..]),
+                _ => (None, part),
+            };
+            let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| invalid())?;
+            match op {
+                Some('>') => {
+                    filter.after = Some(date);
+                }
+                Some(_) => {
+                    filter.before = Some(date);
+                }
+                None => {
+                    filter.after = date.pred_opt();
+                    filter.before = date.succ_opt();
+                }
+            }
+        }
+        Ok(filter)
+    }
+    pub fn is_some(&self) -> bool {
+        self.after.is_some() || self.before.is_some()
+    }
+    pub fn accepts(&self, modified: SystemTime) -> bool {
+        let date = DateTime::<Local>::from(modified).date().naive_local();
+        if let Some(after) = self.after {
+            if date <= after {
+                return false;
+            }
+        }
+        if let Some(before) = self.before {
+            if date >= before {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl fmt::Display for DateFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.after, self.before) {
+            (Some(after), Some(before)) => write!(f, ">{} <{}", after, before),
+            (Some(after), None) => write!(f, ">{}", after),
+            (None, Some(before)) => write!(f, "<{}", before),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod date_filter_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_date_filter_parsing() {
+        let d = |y, m, d| Some(NaiveDate::from_ymd(y, m, d));
+        let filter = DateFilter::from_str(">2020-03-01").unwrap();
+        assert_eq!(filter.after, d(2020, 3, 1));
+        assert_eq!(filter.before, None);
+        let filter = DateFilter::from_str(">2020-03-01 <2020-04-15").unwrap();
+        assert_eq!(filter.after, d(2020, 3, 1));
+        assert_eq!(filter.before, d(2020, 4, 15));
+        let filter = DateFilter::from_str("2020-03-01").unwrap();
+        assert_eq!(filter.after, d(2020, 2, 29));
+        assert_eq!(filter.before, d(2020, 3, 2));
+        assert!(DateFilter::from_str("").map(|f| !f.is_some()).unwrap());
+        assert!(DateFilter::from_str(">2020-13-01").is_err());
+        assert!(DateFilter::from_str("yesterday").is_err());
+    }
+
+    #[test]
+    fn check_age_parsing() {
+        let d = |y, m, d| Some(NaiveDate::from_ymd(y, m, d));
+        let today = NaiveDate::from_ymd(2020, 6, 30);
+        let filter = DateFilter::from_str_at("older_than:180d", today).unwrap();
+        assert_eq!(filter.after, None);
+        assert_eq!(filter.before, d(2020, 1, 2));
+        let filter = DateFilter::from_str_at("newer_than:2w", today).unwrap();
+        assert_eq!(filter.after, d(2020, 6, 16));
+        let filter = DateFilter::from_str_at("older_than:1y newer_than:2y", today).unwrap();
+        assert_eq!(filter.before, d(2019, 7, 1));
+        assert_eq!(filter.after, d(2018, 7, 1));
+        assert!(DateFilter::from_str_at("older_than:3", today).is_err());
+        assert!(DateFilter::from_str_at("older_than:d", today).is_err());
+    }
+}
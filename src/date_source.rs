@@ -0,0 +1,73 @@
+//! the timestamp used for the date column and the date sort.
+//!
+//! "recently modified" and "recently created" aren't the same
+//! question, so the user may choose the date to consider.
+
+use std::{fs::Metadata, time::SystemTime};
+
+#[cfg(unix)]
+use std::{os::unix::fs::MetadataExt, time::Duration};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateSource {
+    Modified, // last modification of the content (mtime)
+    Changed,  // last change of the content or of the metadata (ctime)
+    Accessed, // last access (atime)
+    Created,  // creation (birth time), not available on all file systems
+    Deep,     // most recent modification in the directory and its descendants
+}
+
+impl DateSource {
+    /// parse a date source, as given in configuration or in launch arguments
+    pub fn from_name(name: &str) -> Option<DateSource> {
+        match name.to_lowercase().as_ref() {
+            "modified" | "mtime" => Some(DateSource::Modified),
+            "changed" | "ctime" => Some(DateSource::Changed),
+            "accessed" | "atime" => Some(DateSource::Accessed),
+            "created" | "birth" => Some(DateSource::Created),
+            "deep" | "deep_mtime" => Some(DateSource::Deep),
+            _ => None,
+        }
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            DateSource::Modified => "modified",
+            DateSource::Changed => "changed",
+            DateSource::Accessed => "accessed",
+            DateSource::Created => "created",
+            DateSource::Deep => "deep",
+        }
+    }
+    /// return the date of the file, if the system provides it.
+    ///
+    /// The deep date of a directory must be computed (see the
+    /// deep_dates module), the one given here being the one of a file.
+    pub fn date(self, metadata: &Metadata) -> Option<SystemTime> {
+        match self {
+            DateSource::Modified | DateSource::Deep => metadata.modified().ok(),
+            DateSource::Changed => changed(metadata),
+            DateSource::Accessed => metadata.accessed().ok(),
+            DateSource::Created => metadata.created().ok(),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn changed(metadata: &Metadata) -> Option<SystemTime> {
+    if metadata.ctime() < 0 {
+        return None;
+    }
+    Some(SystemTime::UNIX_EPOCH + Duration::new(metadata.ctime() as u64, metadata.ctime_nsec() as u32))
+}
+
+/// there's no ctime outside unix, the last modification is the closest date
+#[cfg(not(unix))]
+fn changed(metadata: &Metadata) -> Option<SystemTime> {
+    metadata.modified().ok()
+}
+
+impl Default for DateSource {
+    fn default() -> Self {
+        DateSource::Modified
+    }
+}
@@ -0,0 +1,87 @@
+//! compute the deep date of directories, which is the most recent
+//! modification date found in the directory and its descendants.
+//!
+//! The modification date of a directory only changes when entries
+//! are added, removed or renamed in it: editing a file deep in a
+//! project doesn't change the date of the project's directory, while
+//! it changes its deep date.
+//!
+//! Symbolic links aren't followed. The computed dates are cached,
+//! for the directory and for all its descendants.
+
+use {
+    crate::task_sync::{ComputationResult, Dam},
+    std::{
+        collections::HashMap,
+        fs,
+        path::{Path, PathBuf},
+        sync::Mutex,
+        time::SystemTime,
+    },
+};
+
+lazy_static! {
+    static ref DEEP_DATE_CACHE_MUTEX: Mutex<HashMap<PathBuf, SystemTime>> =
+        Mutex::new(HashMap::new());
+}
+
+pub fn clear_cache() {
+    let mut cache = DEEP_DATE_CACHE_MUTEX.lock().unwrap();
+    cache.clear();
+}
+
+/// forget the dates of the changed path, of its descendants and
+/// of the directories containing it
+pub fn forget_path(changed: &Path) {
+    let mut cache = DEEP_DATE_CACHE_MUTEX.lock().unwrap();
+    cache.retain(|path, _| !changed.starts_with(path) && !path.starts_with(changed));
+}
+
+/// compute the deep date of a directory, filling the cache, or
+/// return None if the computation was interrupted by the dam
+fn compute(
+    path: &Path,
+    own_date: Option<SystemTime>,
+    dam: &Dam,
+    cache: &mut HashMap<PathBuf, SystemTime>,
+) -> Option<Option<SystemTime>> {
+    if let Some(date) = cache.get(path) {
+        return Some(Some(*date));
+    }
+    let mut deep_date = own_date;
+    if let Ok(entries) = fs::read_dir(path) {
+        for entry in entries.flatten() {
+            if dam.has_event() {
+                return None;
+            }
+            let md = match entry.metadata() {
+                Ok(md) => md,
+                Err(_) => continue,
+            };
+            let date = if md.is_dir() {
+                compute(&entry.path(), md.modified().ok(), dam, cache)?
+            } else {
+                md.modified().ok()
+            };
+            deep_date = deep_date.max(date);
+        }
+    }
+    if let Some(date) = deep_date {
+        cache.insert(path.to_path_buf(), date);
+    }
+    Some(deep_date)
+}
+
+/// return the deep date of the directory, computing it if needed.
+///
+/// NotComputed is returned if the computation was interrupted, and
+/// None if no date could be read.
+pub fn deep_date(path: &Path, dam: &Dam) -> ComputationResult<SystemTime> {
+    let mut cache = DEEP_DATE_CACHE_MUTEX.lock().unwrap();
+    let own_date = fs::symlink_metadata(path).and_then(|md| md.modified()).ok();
+    match time!(Debug, "deep date", path, compute(path, own_date, dam, &mut cache)) {
+        Some(Some(date)) => ComputationResult::Done(date),
+        Some(None) => ComputationResult::None,
+        None => ComputationResult::NotComputed,
+    }
+}
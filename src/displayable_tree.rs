@@ -1,12 +1,20 @@
 use {
     crate::{
+        columns::{self, Col, Column},
         errors::ProgramError,
         file_sizes::FileSize,
-        flat_tree::{LineType, Tree, TreeLine},
+        flat_tree::{LineChange, LineType, Tree, TreeLine},
+        git_ignore::{IgnoreAudit, IgnoringRule},
         task_sync::ComputationResult,
+        git_status::DirGitStats,
         git_status_display::GitStatusDisplay,
+        path_display,
+        icons::Icons,
+        notes,
         patterns::Pattern,
         skin::Skin,
+        sort::Sort,
+        tags::{self, TagColor},
     },
     chrono::{offset::Local, DateTime},
     crossterm::{
@@ -23,11 +31,7 @@ use {
 };
 
 #[cfg(unix)]
-use {
-    crate::permissions,
-    std::os::unix::fs::MetadataExt,
-    umask::*,
-};
+use umask::*;
 
 /// declare a style named `$dst` which is usually a reference to the `$src`
 /// skin but, in case `selected` is true, is a clone with background changed
@@ -47,6 +51,9 @@ macro_rules! cond_bg {
     };
 }
 
+/// a piece of horizontal line, sliced to fill the permission
+/// column of the lines which have none
+static HORIZONTAL_BRANCH: &str = "────────────────────────────────────────";
 
 /// A tree wrapper which can be used either
 /// - to write on the screen in the application,
@@ -62,6 +69,8 @@ pub struct DisplayableTree<'s, 't> {
     pub skin: &'s Skin,
     pub area: termimad::Area,
     pub in_app: bool, // if true we show the selection and scrollbar
+    pub icons: Option<&'s Icons>, // only used when the show_icons option is set
+    pub accessible: bool, // if true, no symbol is drawn, only words
 }
 
 impl<'s, 't> DisplayableTree<'s, 't> {
@@ -77,6 +86,17 @@ impl<'s, 't> DisplayableTree<'s, 't> {
                 height: tree.lines.len() as u16,
             },
             in_app: false,
+            icons: None,
+            accessible: false,
+        }
+    }
+
+    /// return the icons to write before the names, if any
+    fn icons(&self) -> Option<&Icons> {
+        if self.tree.options.show_icons {
+            self.icons
+        } else {
+            None
         }
     }
 
@@ -90,11 +110,45 @@ impl<'s, 't> DisplayableTree<'s, 't> {
                     &self.skin.file
                 }
             }
+            LineType::SymLinkToFile(_) | LineType::SymLinkToDir(_) if line.has_error => {
+                &self.skin.broken_link
+            }
             LineType::SymLinkToFile(_) | LineType::SymLinkToDir(_) => &self.skin.link,
             LineType::Pruning => &self.skin.pruning,
         }
     }
 
+    /// return the style giving the color of the names of lines modified
+    /// recently, or None for the ones older than a month
+    fn freshness_style(&self, line: &TreeLine) -> Option<&CompoundStyle> {
+        let date = line.date(self.tree.options.date_source)?;
+        let age = SystemTime::now().duration_since(date).unwrap_or_default();
+        let hours = age.as_secs() / 3600;
+        if hours < 1 {
+            Some(&self.skin.freshness_hour)
+        } else if hours < 24 {
+            Some(&self.skin.freshness_day)
+        } else if hours < 24 * 7 {
+            Some(&self.skin.freshness_week)
+        } else if hours < 24 * 30 {
+            Some(&self.skin.freshness_month)
+        } else {
+            None
+        }
+    }
+
+    fn tag_style(&self, color: TagColor) -> &CompoundStyle {
+        match color {
+            TagColor::Red => &self.skin.tag_red,
+            TagColor::Orange => &self.skin.tag_orange,
+            TagColor::Yellow => &self.skin.tag_yellow,
+            TagColor::Green => &self.skin.tag_green,
+            TagColor::Blue => &self.skin.tag_blue,
+            TagColor::Purple => &self.skin.tag_purple,
+            TagColor::Gray => &self.skin.tag_gray,
+        }
+    }
+
     fn write_line_size(
         &self,
         f: &mut impl Write,
@@ -106,11 +160,48 @@ impl<'s, 't> DisplayableTree<'s, 't> {
             let pb = ProgressBar::new(s.part_of(total_size), 10);
             cond_bg!(size_style, self, selected, self.name_style(&line));
             cond_bg!(sparse_style, self, selected, self.skin.sparse);
-            size_style.queue(f, format!("{:>5}", s.to_string()))?;
+            let (value, unit) = s.value_and_unit();
+            if self.tree.options.align_size_units {
+                size_style.queue(f, format_args!("{:>4}{:<1}", value, unit))?;
+            } else {
+                // units are at most one char long
+                size_style.queue(f, format_args!("{:>w$}{}", value, unit, w = 5 - unit.len()))?;
+            }
             sparse_style.queue(f, if s.sparse { 's' } else { ' ' })?;
-            size_style.queue(f, format!("{:<10} ", pb))
+            size_style.queue(f, format_args!("{:<10} ", pb))
+        } else if self.tree.sizes_interrupted && line.line_type == LineType::Dir {
+            // the size computation was stopped before this directory
+            cond_bg!(size_style, self, selected, self.name_style(&line));
+            size_style.queue_str(f, "    ?            ")
         } else {
-            self.skin.tree.queue_str(f, "──────────────── ")
+            self.write_horizontal_branch(f, 16)?;
+            self.skin.tree.queue_str(f, " ")
+        }
+    }
+
+    fn write_line_count(
+        &self,
+        f: &mut impl Write,
+        line: &TreeLine,
+        selected: bool,
+    ) -> Result<(), termimad::Error> {
+        cond_bg!(count_style, self, selected, self.skin.count);
+        match line.children_count {
+            Some(count) => count_style.queue(f, format_args!("{:>5} ", count)),
+            None => count_style.queue_str(f, "      "),
+        }
+    }
+
+    fn write_line_line_count(
+        &self,
+        f: &mut impl Write,
+        line: &TreeLine,
+        selected: bool,
+    ) -> Result<(), termimad::Error> {
+        cond_bg!(count_style, self, selected, self.skin.count);
+        match line.line_count {
+            ComputationResult::Done(count) => count_style.queue(f, format_args!("{:>6} ", count)),
+            _ => count_style.queue_str(f, "       "),
         }
     }
 
@@ -122,18 +213,93 @@ impl<'s, 't> DisplayableTree<'s, 't> {
         if !line.is_selectable() {
             self.skin.tree.queue(f, ' ')
         } else {
-            match line.git_status.map(|s| s.status) {
-                Some(Status::CURRENT) => self.skin.git_status_current.queue(f, ' '),
-                Some(Status::WT_NEW) => self.skin.git_status_new.queue(f, 'N'),
-                Some(Status::CONFLICTED) => self.skin.git_status_conflicted.queue(f, 'C'),
-                Some(Status::WT_MODIFIED) => self.skin.git_status_modified.queue(f, 'M'),
-                Some(Status::IGNORED) => self.skin.git_status_ignored.queue(f, 'I'),
+            // statuses are bit sets (and directories get the union of the
+            // statuses of their descendants) so we show the most urgent one
+            match line.git_status {
+                Some(gs) if gs.status.is_empty() => self.skin.git_status_current.queue(f, ' '),
+                Some(gs) if gs.status.contains(Status::CONFLICTED) => {
+                    self.skin.git_status_conflicted.queue(f, 'C')
+                }
+                Some(gs) if gs.status.contains(Status::WT_MODIFIED) => {
+                    self.skin.git_status_modified.queue(f, 'M')
+                }
+                Some(gs) if gs.status.contains(Status::WT_NEW) => {
+                    self.skin.git_status_new.queue(f, 'N')
+                }
+                Some(gs) if gs.is_staged() => self.skin.git_status_staged.queue(f, 'S'),
+                Some(gs) if gs.status.contains(Status::IGNORED) => {
+                    self.skin.git_status_ignored.queue(f, 'I')
+                }
+                // a placeholder until the statuses are read
+                None if self.tree.line_git_statuses_pending => self.skin.tree.queue(f, '·'),
                 None => self.skin.tree.queue(f, ' '),
                 _ => self.skin.git_status_other.queue_str(f, "?"),
             }
         }
     }
 
+    /// write the counts of descendants by git status, eg " 3M 1N"
+    fn write_git_dir_stats(
+        &self,
+        f: &mut impl Write,
+        dir_stats: DirGitStats,
+        selected: bool,
+    ) -> Result<(), termimad::Error> {
+        cond_bg!(conflicted_style, self, selected, self.skin.git_status_conflicted);
+        cond_bg!(modified_style, self, selected, self.skin.git_status_modified);
+        cond_bg!(new_style, self, selected, self.skin.git_status_new);
+        cond_bg!(staged_style, self, selected, self.skin.git_status_staged);
+        if dir_stats.conflicted > 0 {
+            conflicted_style.queue(f, format_args!(" {}C", dir_stats.conflicted))?;
+        }
+        if dir_stats.modified > 0 {
+            modified_style.queue(f, format_args!(" {}M", dir_stats.modified))?;
+        }
+        if dir_stats.new > 0 {
+            new_style.queue(f, format_args!(" {}N", dir_stats.new))?;
+        }
+        if dir_stats.staged > 0 {
+            staged_style.queue(f, format_args!(" {}S", dir_stats.staged))?;
+        }
+        Ok(())
+    }
+
+    /// tell whether git tracks the entry, or which rule makes it ignore it
+    fn write_ignore_audit(
+        &self,
+        f: &mut impl Write,
+        audit: &IgnoreAudit,
+        selected: bool,
+    ) -> Result<(), termimad::Error> {
+        match audit {
+            IgnoreAudit::Tracked(None) => {
+                cond_bg!(tracked_style, self, selected, self.skin.pruning);
+                tracked_style.queue_str(f, "  tracked")
+            }
+            IgnoreAudit::Tracked(Some(rule)) => {
+                cond_bg!(tracked_style, self, selected, self.skin.pruning);
+                tracked_style.queue(
+                    f,
+                    format!("  tracked, although ignored by {}", self.rule_origin(rule)),
+                )
+            }
+            IgnoreAudit::Untracked => {
+                cond_bg!(untracked_style, self, selected, self.skin.git_status_new);
+                untracked_style.queue_str(f, "  untracked")
+            }
+            IgnoreAudit::Ignored(rule) => {
+                cond_bg!(ignored_style, self, selected, self.skin.git_status_ignored);
+                ignored_style.queue(f, format!("  ignored by {}", self.rule_origin(rule)))
+            }
+        }
+    }
+
+    /// the pattern of an ignore rule, with the file and line defining it
+    fn rule_origin(&self, rule: &IgnoringRule) -> String {
+        let file = rule.file.strip_prefix(self.tree.root()).unwrap_or(&rule.file);
+        format!("{} ({}:{})", rule.pattern, file.to_string_lossy(), rule.line)
+    }
+
     fn write_date(
         &self,
         f: &mut impl Write,
@@ -142,7 +308,7 @@ impl<'s, 't> DisplayableTree<'s, 't> {
     ) -> Result<(), termimad::Error> {
         let date_time: DateTime<Local> = system_time.into();
         cond_bg!(date_style, self, selected, self.skin.dates);
-        date_style.queue(f, date_time.format("%Y/%m/%d %R ").to_string())
+        date_style.queue(f, format_args!("{}", date_time.format("%Y/%m/%d %R ")))
     }
 
     #[cfg(unix)]
@@ -208,6 +374,80 @@ impl<'s, 't> DisplayableTree<'s, 't> {
         Ok(())
     }
 
+    /// write a horizontal line of the given width (in chars), in one
+    /// styled segment, where a column has no value
+    fn write_horizontal_branch(
+        &self,
+        f: &mut impl Write,
+        width: usize,
+    ) -> Result<(), termimad::Error> {
+        if self.accessible {
+            return self.skin.tree.queue(f, format!("{:w$}", "", w = width));
+        }
+        // all the chars of HORIZONTAL_BRANCH are 3 bytes long
+        let mut width = width;
+        while width > 0 {
+            let part_width = width.min(HORIZONTAL_BRANCH.len() / 3);
+            self.skin.tree.queue_str(f, &HORIZONTAL_BRANCH[..3 * part_width])?;
+            width -= part_width;
+        }
+        Ok(())
+    }
+
+    /// write the branches of the tree before the line
+    fn write_branches(
+        &self,
+        f: &mut impl Write,
+        line_index: usize,
+        line: &TreeLine,
+    ) -> Result<(), termimad::Error> {
+        if self.accessible {
+            // the depth is told by the indentation only
+            return self.skin.tree.queue(f, format!("{:w$}", "", w = 3 * line.depth as usize));
+        }
+        for depth in 0..line.depth {
+            self.skin.tree.queue_str(
+                f,
+                if line.left_branchs[depth as usize] {
+                    if self.tree.has_branch(line_index + 1, depth as usize) {
+                        if depth == line.depth - 1 {
+                            "├──"
+                        } else {
+                            "│  "
+                        }
+                    } else {
+                        "└──"
+                    }
+                } else {
+                    "   "
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn write_line_permissions(
+        &self,
+        f: &mut impl Write,
+        line: &TreeLine,
+        user_group_max_lengths: (usize, usize),
+        selected: bool,
+    ) -> Result<(), termimad::Error> {
+        if line.is_selectable() {
+            self.write_mode(f, line.mode(), selected)?;
+            let (owner, group) = line.owner_names();
+            cond_bg!(owner_style, self, selected, self.skin.owner);
+            owner_style.queue(f, format_args!(" {:w$}", &owner, w = user_group_max_lengths.0))?;
+            cond_bg!(group_style, self, selected, self.skin.group);
+            group_style.queue(f, format_args!(" {:w$} ", &group, w = user_group_max_lengths.1))?;
+        } else {
+            let length = 9 + 1 + user_group_max_lengths.0 + 1 + user_group_max_lengths.1 + 1;
+            self.write_horizontal_branch(f, length)?;
+        }
+        Ok(())
+    }
+
     fn write_line_name(
         &self,
         f: &mut impl Write,
@@ -224,17 +464,50 @@ impl<'s, 't> DisplayableTree<'s, 't> {
                     &self.skin.file
                 }
             }
+            LineType::SymLinkToFile(_) | LineType::SymLinkToDir(_) if line.has_error => {
+                &self.skin.broken_link
+            }
             LineType::SymLinkToFile(_) | LineType::SymLinkToDir(_) => &self.skin.link,
             LineType::Pruning => &self.skin.pruning,
         };
+        let freshness_fg = if self.tree.options.show_freshness && line.is_selectable() {
+            self.freshness_style(line).and_then(|fs| fs.get_fg())
+        } else {
+            None
+        };
+        let mut fresh_style;
+        let style = match freshness_fg {
+            Some(fg) => {
+                fresh_style = style.clone();
+                fresh_style.set_fg(fg);
+                &fresh_style
+            }
+            _ => style,
+        };
         cond_bg!(style, self, selected, style);
-        cond_bg!(char_match_style, self, selected, self.skin.char_match);
+        let char_match = if selected {
+            &self.skin.char_match_selected
+        } else {
+            &self.skin.char_match
+        };
+        cond_bg!(char_match_style, self, selected, char_match);
+        if let Some(icons) = self.icons() {
+            match icons.icon_of(line) {
+                Some(icon) => style.queue(f, format_args!("{} ", icon))?,
+                None => style.queue_str(f, "  ")?,
+            }
+        }
         pattern.style(&line.name, &style, &char_match_style).write_on(f)?;
         match &line.line_type {
             LineType::Dir => {
                 if line.unlisted > 0 {
                     style.queue_str(f, " …")?;
                 }
+                if self.tree.options.show_git_dir_stats {
+                    if let Some(dir_stats) = line.git_status.and_then(|gs| gs.dir_stats) {
+                        self.write_git_dir_stats(f, dir_stats, selected)?;
+                    }
+                }
             }
             LineType::SymLinkToFile(target) | LineType::SymLinkToDir(target) => {
                 style.queue_str(f, " -> ")?;
@@ -252,6 +525,42 @@ impl<'s, 't> DisplayableTree<'s, 't> {
             }
             _ => {}
         }
+        if line.is_loop {
+            cond_bg!(loop_style, self, selected, self.skin.file_error);
+            loop_style.queue_str(f, " (loop)")?;
+        } else if line.has_error && line.line_type == LineType::Dir {
+            cond_bg!(error_style, self, selected, self.skin.file_error);
+            error_style.queue_str(f, " (unreadable)")?;
+        }
+        match line.change {
+            Some(LineChange::Added) => {
+                cond_bg!(added_style, self, selected, self.skin.git_status_new);
+                added_style.queue_str(f, " (new)")?;
+            }
+            Some(LineChange::Resized(delta)) => {
+                cond_bg!(resized_style, self, selected, self.skin.git_status_modified);
+                let sign = if delta < 0 { '-' } else { '+' };
+                let delta = FileSize::new(delta.abs() as u64, false);
+                resized_style.queue(f, format!(" ({}{})", sign, delta))?;
+            }
+            None => {}
+        }
+        if notes::has_note(&line.path) {
+            cond_bg!(note_style, self, selected, self.skin.note);
+            note_style.queue_str(f, if self.accessible { " (note)" } else { " ✎" })?;
+        }
+        for (idx, color) in tags::get(&line.path).into_iter().enumerate() {
+            let tag_style = self.tag_style(color);
+            cond_bg!(tag_style, self, selected, *tag_style);
+            if self.accessible {
+                tag_style.queue(f, format!(" ({})", color.name()))?;
+            } else {
+                tag_style.queue_str(f, if idx == 0 { " ●" } else { "●" })?;
+            }
+        }
+        if let Some(audit) = &line.ignore_audit {
+            self.write_ignore_audit(f, audit, selected)?;
+        }
         Ok(())
     }
 
@@ -261,11 +570,45 @@ impl<'s, 't> DisplayableTree<'s, 't> {
         selected: bool,
     ) -> Result<(), ProgramError> {
         cond_bg!(style, self, selected, self.skin.directory);
-        let title = self.tree.lines[0].path.to_string_lossy();
-        style.queue_str(f, &title)?;
+        let mut title_len = 0;
+        // the home is abbreviated and the path shortened only in app
+        let root_path = &self.tree.lines[0].path;
+        let parts = if self.in_app {
+            path_display::parts(root_path, self.area.width as usize)
+        } else {
+            path_display::full_parts(root_path)
+        };
+        // the path is written ancestor by ancestor, so that
+        // the known sizes can be inserted
+        cond_bg!(size_style, self, selected, self.skin.count);
+        for part in parts {
+            style.queue_str(f, &part.text)?;
+            title_len += part.text.chars().count();
+            if !self.tree.options.show_sizes {
+                continue;
+            }
+            let known = self.tree.ancestor_sizes.iter().find(|(path, _)| path == part.ancestor);
+            if let Some((_, size)) = known {
+                let size = format!("({})", size);
+                size_style.queue_str(f, &size)?;
+                title_len += size.chars().count();
+            }
+        }
+        if self.tree.options.show_sizes {
+            if let Some(size) = self.tree.lines[0].size {
+                let size = format!("  {}", size);
+                size_style.queue_str(f, &size)?;
+                title_len += size.chars().count();
+            }
+        }
+        if self.tree.nb_unreadable > 0 {
+            cond_bg!(error_style, self, selected, self.skin.file_error);
+            let unreadable = format!("  {} unreadable", self.tree.nb_unreadable);
+            error_style.queue_str(f, &unreadable)?;
+            title_len += unreadable.chars().count();
+        }
         if self.in_app {
             self.extend_line(f, selected)?;
-            let title_len = title.chars().count();
             if title_len < self.area.width as usize {
                 if let ComputationResult::Done(git_status) = &self.tree.git_status {
                     let git_status_display = GitStatusDisplay::from(
@@ -300,10 +643,27 @@ impl<'s, 't> DisplayableTree<'s, 't> {
     /// write the whole tree on the given `impl Write`
     pub fn write_on(&self, f: &mut impl Write) -> Result<(), ProgramError> {
         let tree = self.tree;
-        #[cfg(unix)]
-        let user_group_max_lengths = user_group_max_lengths(&tree);
-        let total_size = tree.total_size();
-        let scrollbar = if self.in_app {
+        let columns: Vec<(Col, Box<dyn Column>)> = tree
+            .options
+            .cols_order
+            .iter()
+            .map(|&col| (col, builtin_column(col, tree)))
+            .collect();
+        let column = |col: Col| columns.iter().find(|(c, _)| *c == col).map(|(_, column)| column);
+        let icon_width = if self.icons().is_some() { 2 } else { 0 };
+        let names_width = tree.lines[1..]
+            .iter()
+            .map(|line| 3 * line.depth as usize + icon_width + line.name.chars().count() + 2)
+            .max()
+            .unwrap_or(0);
+        let cols = columns::visible_cols(
+            &tree.options.cols_priority,
+            |col| column(col).map_or(false, |column| column.is_enabled(tree)),
+            |col| column(col).map_or(0, |column| column.width_hint()),
+            (self.area.width as usize).saturating_sub(1), // we keep a column for the scrollbar
+            names_width,
+        );
+        let scrollbar = if self.in_app && !self.accessible {
             self.area.scrollbar(tree.scroll, tree.lines.len() as i32 - 1)
         } else {
             None
@@ -321,59 +681,13 @@ impl<'s, 't> DisplayableTree<'s, 't> {
             }
             let mut selected = false;
             if line_index < tree.lines.len() {
-                let line = &tree.lines[line_index];
                 selected = self.in_app && line_index == tree.selection;
-                if !tree.git_status.is_none() {
-                    self.write_line_git_status(f, line)?;
-                }
-                for depth in 0..line.depth {
-                    self.skin.tree.queue_str(
-                        f,
-                        if line.left_branchs[depth as usize] {
-                            if self.tree.has_branch(line_index + 1, depth as usize) {
-                                if depth == line.depth - 1 {
-                                    "├──"
-                                } else {
-                                    "│  "
-                                }
-                            } else {
-                                "└──"
-                            }
-                        } else {
-                            "   "
-                        },
-                    )?;
-                }
-                if tree.options.show_sizes {
-                    self.write_line_size(f, line, total_size, selected)?;
-                }
-                #[cfg(unix)]
-                {
-                    if tree.options.show_permissions {
-                        if line.is_selectable() {
-                            self.write_mode(f, line.mode(), selected)?;
-                            let owner = permissions::user_name(line.metadata.uid());
-                            cond_bg!(owner_style, self, selected, self.skin.owner);
-                            owner_style.queue(f, format!(" {:w$}", &owner, w = user_group_max_lengths.0,))?;
-                            let group = permissions::group_name(line.metadata.gid());
-                            cond_bg!(group_style, self, selected, self.skin.group);
-                            group_style.queue(f, format!(" {:w$} ", &group, w = user_group_max_lengths.1,))?;
-                        } else {
-                            let length = 9 + 1 +user_group_max_lengths.0 + 1 + user_group_max_lengths.1 + 1;
-                            for _ in 0..length {
-                                self.skin.tree.queue_str(f, "─")?;
-                            }
-                        }
-                    }
-                }
-                if tree.options.show_dates {
-                    if let Some(date) = line.modified() {
-                        self.write_date(f, date, selected)?;
-                    } else {
-                        self.skin.tree.queue_str(f, "─────────────────")?;
+                for (col, column) in &columns {
+                    if col.is_optional() && !cols.contains(col) {
+                        continue;
                     }
+                    column.write_cell(self, &mut *f, line_index, selected)?;
                 }
-                self.write_line_name(f, line, &tree.options.pattern, selected)?;
             }
             self.extend_line(f, selected)?;
             f.queue(SetBackgroundColor(Color::Reset))?;
@@ -400,13 +714,223 @@ fn user_group_max_lengths(tree: &Tree) -> (usize, usize) {
     let mut max_group_len = 0;
     if tree.options.show_permissions {
         for i in 1..tree.lines.len() {
-            let line = &tree.lines[i];
-            let user = permissions::user_name(line.metadata.uid());
+            let (user, group) = tree.lines[i].owner_names();
             max_user_len = max_user_len.max(user.len());
-            let group = permissions::group_name(line.metadata.gid());
             max_group_len = max_group_len.max(group.len());
         }
     }
     (max_user_len, max_group_len)
 }
 
+
+/// build the column writing the cells of a built-in col
+pub fn builtin_column(col: Col, tree: &Tree) -> Box<dyn Column> {
+    match col {
+        Col::Git => Box::new(GitColumn),
+        Col::Branch => Box::new(BranchColumn),
+        Col::Size => Box::new(SizeColumn {
+            total_size: tree.total_size(),
+        }),
+        Col::Date => Box::new(DateColumn),
+        Col::Count => Box::new(CountColumn),
+        Col::Lines => Box::new(LinesColumn),
+        Col::Permission => Box::new(PermissionColumn::new(tree)),
+        Col::Name => Box::new(NameColumn),
+    }
+}
+
+struct GitColumn;
+impl Column for GitColumn {
+    fn is_enabled(&self, tree: &Tree) -> bool {
+        !tree.git_status.is_none()
+    }
+    fn width_hint(&self) -> usize {
+        1
+    }
+    fn write_cell(
+        &self,
+        dt: &DisplayableTree<'_, '_>,
+        mut f: &mut dyn Write,
+        line_index: usize,
+        _selected: bool,
+    ) -> Result<(), ProgramError> {
+        dt.write_line_git_status(&mut f, &dt.tree.lines[line_index])?;
+        Ok(())
+    }
+}
+
+struct BranchColumn;
+impl Column for BranchColumn {
+    fn is_enabled(&self, _tree: &Tree) -> bool {
+        true
+    }
+    fn width_hint(&self) -> usize {
+        0
+    }
+    fn write_cell(
+        &self,
+        dt: &DisplayableTree<'_, '_>,
+        mut f: &mut dyn Write,
+        line_index: usize,
+        _selected: bool,
+    ) -> Result<(), ProgramError> {
+        dt.write_branches(&mut f, line_index, &dt.tree.lines[line_index])?;
+        Ok(())
+    }
+}
+
+struct SizeColumn {
+    total_size: FileSize, // the sizes bars are relative to it
+}
+impl Column for SizeColumn {
+    fn is_enabled(&self, tree: &Tree) -> bool {
+        tree.options.show_sizes
+    }
+    fn width_hint(&self) -> usize {
+        17
+    }
+    fn write_cell(
+        &self,
+        dt: &DisplayableTree<'_, '_>,
+        mut f: &mut dyn Write,
+        line_index: usize,
+        selected: bool,
+    ) -> Result<(), ProgramError> {
+        let line = &dt.tree.lines[line_index];
+        dt.write_line_size(&mut f, line, self.total_size, selected)?;
+        Ok(())
+    }
+}
+
+struct DateColumn;
+impl Column for DateColumn {
+    fn is_enabled(&self, tree: &Tree) -> bool {
+        tree.options.show_dates
+    }
+    fn width_hint(&self) -> usize {
+        17
+    }
+    fn write_cell(
+        &self,
+        dt: &DisplayableTree<'_, '_>,
+        mut f: &mut dyn Write,
+        line_index: usize,
+        selected: bool,
+    ) -> Result<(), ProgramError> {
+        let line = &dt.tree.lines[line_index];
+        if let Some(date) = line.date(dt.tree.options.date_source) {
+            dt.write_date(&mut f, date, selected)?;
+        } else {
+            dt.write_horizontal_branch(&mut f, 17)?;
+        }
+        Ok(())
+    }
+}
+
+struct CountColumn;
+impl Column for CountColumn {
+    fn is_enabled(&self, tree: &Tree) -> bool {
+        tree.options.sort == Sort::Count
+    }
+    fn width_hint(&self) -> usize {
+        6
+    }
+    fn write_cell(
+        &self,
+        dt: &DisplayableTree<'_, '_>,
+        mut f: &mut dyn Write,
+        line_index: usize,
+        selected: bool,
+    ) -> Result<(), ProgramError> {
+        dt.write_line_count(&mut f, &dt.tree.lines[line_index], selected)?;
+        Ok(())
+    }
+}
+
+struct LinesColumn;
+impl Column for LinesColumn {
+    fn is_enabled(&self, tree: &Tree) -> bool {
+        tree.options.show_line_counts
+    }
+    fn width_hint(&self) -> usize {
+        7
+    }
+    fn write_cell(
+        &self,
+        dt: &DisplayableTree<'_, '_>,
+        mut f: &mut dyn Write,
+        line_index: usize,
+        selected: bool,
+    ) -> Result<(), ProgramError> {
+        dt.write_line_line_count(&mut f, &dt.tree.lines[line_index], selected)?;
+        Ok(())
+    }
+}
+
+struct PermissionColumn {
+    #[cfg(unix)]
+    user_group_max_lengths: (usize, usize),
+}
+impl PermissionColumn {
+    fn new(_tree: &Tree) -> Self {
+        Self {
+            #[cfg(unix)]
+            user_group_max_lengths: user_group_max_lengths(_tree),
+        }
+    }
+}
+impl Column for PermissionColumn {
+    fn is_enabled(&self, tree: &Tree) -> bool {
+        cfg!(unix) && tree.options.show_permissions
+    }
+    #[cfg(unix)]
+    fn width_hint(&self) -> usize {
+        9 + 1 + self.user_group_max_lengths.0 + 1 + self.user_group_max_lengths.1 + 1
+    }
+    #[cfg(not(unix))]
+    fn width_hint(&self) -> usize {
+        0
+    }
+    #[cfg(unix)]
+    fn write_cell(
+        &self,
+        dt: &DisplayableTree<'_, '_>,
+        mut f: &mut dyn Write,
+        line_index: usize,
+        selected: bool,
+    ) -> Result<(), ProgramError> {
+        let line = &dt.tree.lines[line_index];
+        dt.write_line_permissions(&mut f, line, self.user_group_max_lengths, selected)?;
+        Ok(())
+    }
+    #[cfg(not(unix))]
+    fn write_cell(
+        &self,
+        _dt: &DisplayableTree<'_, '_>,
+        _f: &mut dyn Write,
+        _line_index: usize,
+        _selected: bool,
+    ) -> Result<(), ProgramError> {
+        Ok(())
+    }
+}
+
+struct NameColumn;
+impl Column for NameColumn {
+    fn is_enabled(&self, _tree: &Tree) -> bool {
+        true
+    }
+    fn width_hint(&self) -> usize {
+        0
+    }
+    fn write_cell(
+        &self,
+        dt: &DisplayableTree<'_, '_>,
+        mut f: &mut dyn Write,
+        line_index: usize,
+        selected: bool,
+    ) -> Result<(), ProgramError> {
+        let line = &dt.tree.lines[line_index];
+        dt.write_line_name(&mut f, line, &dt.tree.options.pattern, selected)
+    }
+}
@@ -1,9 +1,13 @@
 //! Definitions of custom errors used in broot
 
 use {
+    crate::file_locks,
     custom_error::custom_error,
     regex,
-    std::io,
+    std::{
+        error, fmt, io,
+        path::{Path, PathBuf},
+    },
 };
 
 custom_error! {pub ProgramError
@@ -17,13 +21,122 @@ custom_error! {pub ProgramError
     UnmatchingVerbArgs {name: String} = "No matching argument found for verb {:?}",
     TreeBuild {source: TreeBuildError} = "{}",
     LaunchError {program: String, source: io::Error} = "Unable to launch {program}: {source}",
+    LaunchTimeout {program: String, seconds: u64} = "{program} was killed after {seconds}s",
+    ProgramFailed {program: String, status: String} = "{program} failed ({status})",
+    FileOp {source: FileOpError} = "{source}",
+    NothingToUndo = "Nothing to undo",
     UnknowShell {shell: String} = "Unknown shell: {shell}",
+    InvalidDateFilter {raw: String} = "Invalid date filter: {raw:?} (expected something like >2020-03-01)",
+    NothingToExport = "The final state has no tree to export",
     InternalError {details: String} = "Internal error: {details}", // should not happen
 }
 
+/// an operation on files done by broot itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileOp {
+    Copy,
+    Rename,
+    Trash,
+    Restore,
+    Purge,
+    Remove,
+}
+
+impl FileOp {
+    fn participle(self) -> &'static str {
+        match self {
+            Self::Copy => "copying",
+            Self::Rename => "renaming",
+            Self::Trash => "trashing",
+            Self::Restore => "restoring",
+            Self::Purge => "purging",
+            Self::Remove => "removing",
+        }
+    }
+}
+
+/// the failure of an operation on files, keeping the io error, whose
+/// kind can be checked, the paths involved and, when known, the verb
+/// which was executed and the processes using the file
+#[derive(Debug)]
+pub struct FileOpError {
+    pub op: FileOp,
+    pub path: PathBuf,
+    pub dest: Option<PathBuf>,
+    pub verb: Option<String>,
+    pub lockers: Option<String>, // eg "it's used by vim (pid 1234)"
+    pub source: io::Error,
+}
+
+impl FileOpError {
+    pub fn new(op: FileOp, path: &Path, source: io::Error) -> Self {
+        // the processes are looked for now, as they may close the file later
+        let lockers = if file_locks::is_lock_error(&source) {
+            file_locks::describe_lockers(path)
+        } else {
+            None
+        };
+        Self {
+            op,
+            path: path.to_path_buf(),
+            dest: None,
+            verb: None,
+            lockers,
+            source,
+        }
+    }
+    pub fn with_dest(mut self, dest: &Path) -> Self {
+        self.dest = Some(dest.to_path_buf());
+        self
+    }
+    pub fn kind(&self) -> io::ErrorKind {
+        self.source.kind()
+    }
+}
+
+/// describe the io error without the code of the os error, which
+/// means nothing to most users (it's still in the logs)
+fn describe_cause(err: &io::Error) -> String {
+    let description = err.to_string();
+    match description.find(" (os error ") {
+        Some(idx) => description[..idx].to_string(),
+        None => description,
+    }
+}
+
+impl fmt::Display for FileOpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            describe_cause(&self.source),
+            self.op.participle(),
+            self.path.to_string_lossy(),
+        )?;
+        if let Some(dest) = &self.dest {
+            write!(f, " to {}", dest.to_string_lossy())?;
+        }
+        if let Some(lockers) = &self.lockers {
+            write!(f, " - {}", lockers)?;
+        }
+        if let Some(verb) = &self.verb {
+            write!(f, " (verb: {})", verb)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for FileOpError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
 custom_error! {pub TreeBuildError
     NotADirectory { path: String } = "Not a directory: {}",
     FileNotFound { path: String } = "File not found: {}",
+    UnreadableArchive { path: String, details: String } = "Unreadable archive {path}: {details}",
+    UnreachableRemote { path: String, details: String } = "Unreachable remote {path}: {details}",
 }
 
 custom_error! {pub ConfError
@@ -33,6 +146,8 @@ custom_error! {pub ConfError
     InvalidVerbInvocation {invocation: String}      = "invalid verb invocation: {}",
     InvalidKey {raw: String}                        = "not a valid key: {}",
     ReservedKey {key: String}                       = "reserved key: {}",
+    ConflictingKey {key: String}                    = "key bound to several verbs: {}",
+    InvalidVerbPrompt {arg: String}                 = "prompted arg not in the invocation: {}",
 }
 
 // error which can be raised when parsing a regex the
@@ -51,3 +166,23 @@ custom_error! {pub InvalidSkinError
     InvalidStyle {style: String}   = "Invalid skin style : {}",
 }
 
+
+#[cfg(test)]
+mod errors_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_file_op_error_display() {
+        let mut err = FileOpError::new(
+            FileOp::Rename,
+            Path::new("/x"),
+            io::Error::new(io::ErrorKind::PermissionDenied, "Permission denied (os error 13)"),
+        )
+        .with_dest(Path::new("/y"));
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(err.to_string(), "Permission denied renaming /x to /y");
+        err.verb = Some("rn".to_string());
+        assert_eq!(err.to_string(), "Permission denied renaming /x to /y (verb: rn)");
+    }
+}
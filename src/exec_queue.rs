@@ -0,0 +1,249 @@
+//! a queue of external verb executions, staged with `:queue`
+//! on different selections then run together with `:execute_queue`.
+//!
+//! The queue is global so that it survives the navigation between
+//! states.
+//!
+//! When `persist_queue` is set, the queue is written in broot's cache
+//! on every change, so that the commands staged during a long session
+//! aren't lost on a crash or an unwanted quit. The queues of the
+//! different roots are kept apart.
+
+use {
+    crate::{conf, external},
+    std::{
+        fs, io,
+        path::{Path, PathBuf},
+        process,
+        sync::Mutex,
+    },
+    toml::{value::Table, Value},
+};
+
+/// an external command, ready to be launched
+#[derive(Debug, Clone)]
+pub struct QueuedExec {
+    pub description: String, // the command as displayed to the user
+    pub exec_token: Vec<String>,
+}
+
+impl QueuedExec {
+    /// run the command and wait for its end. Return the error
+    /// to display when it couldn't be launched or when it failed.
+    ///
+    /// The terminal being in broot's raw mode and alternate screen,
+    /// the command gets no input and its output is captured: the first
+    /// line of its error output is given when it fails.
+    pub fn run(&self) -> Result<(), String> {
+        let mut parts = external::resolve_env_variables(self.exec_token.clone()).into_iter();
+        let exe = parts.next().ok_or_else(|| "empty command".to_string())?;
+        let output = process::Command::new(&exe)
+            .args(parts)
+            .stdin(process::Stdio::null())
+            .output()
+            .map_err(|e| format!("unable to launch {}: {}", exe, e))?;
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        match stderr.lines().map(str::trim).find(|line| !line.is_empty()) {
+            Some(line) => Err(format!("{} failed ({}): {}", exe, output.status, line)),
+            None => Err(format!("{} failed ({})", exe, output.status)),
+        }
+    }
+}
+
+lazy_static! {
+    static ref QUEUE_MUTEX: Mutex<Vec<QueuedExec>> = Mutex::new(Vec::new());
+    // the root the queue is persisted for, when it's persisted
+    static ref PERSISTED_ROOT: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+/// a queue as persisted, with the root it was built in
+type PersistedQueue = (PathBuf, Vec<QueuedExec>);
+
+fn persisted_file_path() -> PathBuf {
+    conf::app_dirs().cache_dir().join("queues.toml")
+}
+
+fn queued_exec_to_toml(qe: &QueuedExec) -> Value {
+    let mut table = Table::new();
+    table.insert("description".to_string(), Value::String(qe.description.clone()));
+    let tokens = qe.exec_token.iter().cloned().map(Value::String).collect();
+    table.insert("exec_token".to_string(), Value::Array(tokens));
+    Value::Table(table)
+}
+
+fn queued_exec_from_toml(value: &Value) -> Option<QueuedExec> {
+    let description = value.get("description")?.as_str()?.to_string();
+    let exec_token = value
+        .get("exec_token")?
+        .as_array()?
+        .iter()
+        .map(|token| token.as_str().map(str::to_string))
+        .collect::<Option<Vec<String>>>()?;
+    Some(QueuedExec {
+        description,
+        exec_token,
+    })
+}
+
+fn queues_to_toml(queues: &[PersistedQueue]) -> String {
+    let queues = queues
+        .iter()
+        .map(|(root, queue)| {
+            let mut table = Table::new();
+            table.insert("root".to_string(), Value::String(root.to_string_lossy().to_string()));
+            let commands = queue.iter().map(queued_exec_to_toml).collect();
+            table.insert("commands".to_string(), Value::Array(commands));
+            Value::Table(table)
+        })
+        .collect();
+    let mut table = Table::new();
+    table.insert("queues".to_string(), Value::Array(queues));
+    Value::Table(table).to_string()
+}
+
+/// parse the persisted queues, the invalid ones being skipped
+fn queues_from_toml(content: &str) -> Vec<PersistedQueue> {
+    let root: Value = match content.parse() {
+        Ok(root) => root,
+        Err(e) => {
+            warn!("invalid persisted queues: {:?}", e);
+            return Vec::new();
+        }
+    };
+    let queues = match root.get("queues").and_then(Value::as_array) {
+        Some(queues) => queues,
+        None => {
+            return Vec::new();
+        }
+    };
+    queues
+        .iter()
+        .filter_map(|queue| {
+            let root = PathBuf::from(queue.get("root")?.as_str()?);
+            let commands = queue
+                .get("commands")?
+                .as_array()?
+                .iter()
+                .filter_map(queued_exec_from_toml)
+                .collect();
+            Some((root, commands))
+        })
+        .collect()
+}
+
+fn read_persisted() -> Vec<PersistedQueue> {
+    fs::read_to_string(persisted_file_path())
+        .map(|content| queues_from_toml(&content))
+        .unwrap_or_default()
+}
+
+fn write_persisted(queues: &[PersistedQueue]) -> io::Result<()> {
+    let file_path = persisted_file_path();
+    if queues.is_empty() {
+        return match fs::remove_file(file_path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    if let Some(dir) = file_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(file_path, queues_to_toml(queues))
+}
+
+/// write the queue as the one of the persisted root, if any,
+/// the queues of the other roots being kept
+fn save(queue: &[QueuedExec]) {
+    let root = match &*PERSISTED_ROOT.lock().unwrap() {
+        Some(root) => root.clone(),
+        None => {
+            return;
+        }
+    };
+    let mut queues = read_persisted();
+    queues.retain(|(r, _)| r != &root);
+    if !queue.is_empty() {
+        queues.push((root, queue.to_vec()));
+    }
+    if let Err(e) = write_persisted(&queues) {
+        warn!("failed to persist the queue: {:?}", e);
+    }
+}
+
+/// persist the queue, from now on, as the one of this root, after
+/// having restored the commands queued in it during a previous
+/// session. Return the number of queued commands.
+pub fn persist_for(root: &Path) -> usize {
+    let restored = read_persisted()
+        .into_iter()
+        .find(|(r, _)| r == root)
+        .map(|(_, queue)| queue)
+        .unwrap_or_default();
+    *PERSISTED_ROOT.lock().unwrap() = Some(root.to_path_buf());
+    let mut queue = QUEUE_MUTEX.lock().unwrap();
+    queue.extend(restored);
+    queue.len()
+}
+
+pub fn push(queued_exec: QueuedExec) {
+    let mut queue = QUEUE_MUTEX.lock().unwrap();
+    queue.push(queued_exec);
+    save(&queue);
+}
+
+pub fn len() -> usize {
+    QUEUE_MUTEX.lock().unwrap().len()
+}
+
+pub fn descriptions() -> Vec<String> {
+    QUEUE_MUTEX
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|qe| qe.description.clone())
+        .collect()
+}
+
+/// empty the queue and return its content
+pub fn take() -> Vec<QueuedExec> {
+    let mut queue = QUEUE_MUTEX.lock().unwrap();
+    let taken = queue.drain(..).collect();
+    save(&queue);
+    taken
+}
+
+pub fn clear() {
+    let mut queue = QUEUE_MUTEX.lock().unwrap();
+    queue.clear();
+    save(&queue);
+}
+
+#[cfg(test)]
+mod exec_queue_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_persisted_queues() {
+        let queues = vec![
+            (
+                PathBuf::from("/home/dys/dev"),
+                vec![QueuedExec {
+                    description: "mv a \"b c\"".to_string(),
+                    exec_token: vec!["mv".to_string(), "a".to_string(), "b c".to_string()],
+                }],
+            ),
+            (PathBuf::from("/tmp"), Vec::new()),
+        ];
+        let parsed = queues_from_toml(&queues_to_toml(&queues));
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].0, queues[0].0);
+        assert_eq!(parsed[0].1[0].description, queues[0].1[0].description);
+        assert_eq!(parsed[0].1[0].exec_token, queues[0].1[0].exec_token);
+        assert!(parsed[1].1.is_empty());
+        assert!(queues_from_toml("queues = 3").is_empty());
+    }
+}
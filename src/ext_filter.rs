@@ -0,0 +1,61 @@
+//! a filter on the extensions of files, independent from the pattern.
+//!
+//! It's given as a list of extensions separated by commas or spaces,
+//! eg `rs,toml`, a leading dot being optional.
+//! Extensions are compared in a case insensitive way.
+
+use std::fmt;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExtFilter {
+    extensions: Vec<String>, // lowercase, without dot
+}
+
+impl ExtFilter {
+    pub fn from_str(s: &str) -> ExtFilter {
+        let extensions = s
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .map(|ext| ext.trim_start_matches('.').to_lowercase())
+            .filter(|ext| !ext.is_empty())
+            .collect();
+        ExtFilter { extensions }
+    }
+    pub fn is_some(&self) -> bool {
+        !self.extensions.is_empty()
+    }
+    /// tell whether the file name has one of the extensions
+    pub fn accepts(&self, name: &str) -> bool {
+        match name.rfind('.') {
+            Some(idx) if idx > 0 => {
+                let ext = name[idx + 1..].to_lowercase();
+                self.extensions.iter().any(|e| *e == ext)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for ExtFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.extensions.join(","))
+    }
+}
+
+#[cfg(test)]
+mod ext_filter_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_ext_filter() {
+        let filter = ExtFilter::from_str("rs, .TOML");
+        assert_eq!(filter.to_string(), "rs,toml");
+        assert!(filter.accepts("main.rs"));
+        assert!(filter.accepts("Cargo.toml"));
+        assert!(filter.accepts("README.RS"));
+        assert!(!filter.accepts("rs"));
+        assert!(!filter.accepts(".rs"));
+        assert!(!filter.accepts("main.rs.bak"));
+        assert!(!ExtFilter::from_str(" , ").is_some());
+    }
+}
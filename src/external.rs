@@ -3,7 +3,9 @@ use std::{
     fs::OpenOptions,
     io::{self, Write},
     path::{Path, PathBuf},
-    process::Command,
+    process::{Child, Command, ExitStatus},
+    thread,
+    time::{Duration, Instant},
 };
 
 use open;
@@ -47,7 +49,7 @@ pub enum Launchable {
 
 /// If a part starts by a '$', replace it by the environment variable of the same name.
 /// This part is splitted too (because of https://github.com/Canop/broot/issues/114)
-fn resolve_env_variables(parts: Vec<String>) -> Vec<String> {
+pub fn resolve_env_variables(parts: Vec<String>) -> Vec<String> {
     let mut resolved = Vec::new();
     for part in parts.into_iter() {
         if part.starts_with('$') {
@@ -108,7 +110,7 @@ impl Launchable {
                 dp.write_on(&mut std::io::stdout())
             }
             Launchable::Program { exe, args } => {
-                Command::new(&exe)
+                let status = Command::new(&exe)
                     .args(args.iter())
                     .spawn()
                     .and_then(|mut p| p.wait())
@@ -116,7 +118,7 @@ impl Launchable {
                         program: exe.clone(),
                         source,
                     })?;
-                Ok(())
+                check_status(exe, status)
             }
             Launchable::SystemOpen { path } => {
                 open::that(&path)?;
@@ -124,21 +126,134 @@ impl Launchable {
             }
         }
     }
+
+    /// execute the launchable, killing the program if it's still
+    /// running after the timeout, with the processes it started
+    pub fn execute_within(&self, timeout: Duration) -> Result<(), ProgramError> {
+        match self {
+            Launchable::Program { exe, args } => {
+                let launch_error = |source| ProgramError::LaunchError {
+                    program: exe.clone(),
+                    source,
+                };
+                let mut command = Command::new(&exe);
+                command.args(args.iter());
+                let mut child = spawn_in_own_group(&mut command).map_err(launch_error)?;
+                let deadline = Instant::now() + timeout;
+                loop {
+                    if let Some(status) = child.try_wait().map_err(launch_error)? {
+                        return check_status(exe, status);
+                    }
+                    if Instant::now() > deadline {
+                        warn!("killing {:?}, still running after {:?}", exe, timeout);
+                        let _ = kill_group(&mut child); // it may have ended in the meantime
+                        child.wait().map_err(launch_error)?;
+                        return Err(ProgramError::LaunchTimeout {
+                            program: exe.clone(),
+                            seconds: timeout.as_secs(),
+                        });
+                    }
+                    thread::sleep(Duration::from_millis(20));
+                }
+            }
+            _ => self.execute(),
+        }
+    }
+}
+
+/// return an error when the program didn't succeed
+fn check_status(exe: &str, status: ExitStatus) -> Result<(), ProgramError> {
+    if status.success() {
+        Ok(())
+    } else {
+        Err(ProgramError::ProgramFailed {
+            program: exe.to_string(),
+            status: status.to_string(),
+        })
+    }
+}
+
+/// start the program in a new process group, so that the processes
+/// it starts can be killed with it
+#[cfg(unix)]
+pub fn spawn_in_own_group(command: &mut Command) -> io::Result<Child> {
+    use std::os::unix::process::CommandExt;
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setpgid(0, 0) == 0 {
+                Ok(())
+            } else {
+                Err(io::Error::last_os_error())
+            }
+        });
+    }
+    command.spawn()
+}
+
+#[cfg(not(unix))]
+pub fn spawn_in_own_group(command: &mut Command) -> io::Result<Child> {
+    command.spawn()
+}
+
+/// kill the program and the processes of its group
+#[cfg(unix)]
+pub fn kill_group(child: &mut Child) -> io::Result<()> {
+    // a negative pid designates the process group
+    if unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(unix))]
+pub fn kill_group(child: &mut Child) -> io::Result<()> {
+    child.kill()
 }
 
 // from a path, build a string usable in a shell command, wrapping
 //  it in quotes if necessary (and then escaping internal quotes).
 // Don't do unnecessary transformation, so that the produced string
 //  is prettier on screen.
+#[cfg(not(windows))]
 pub fn escape_for_shell(path: &Path) -> String {
     let path = path.to_string_lossy();
     if regex!(r"^[\w/.-]*$").is_match(&path) {
         path.to_string()
     } else {
-        format!("'{}'", &path.replace('\'', r"'\''"))
+        quote(&path)
     }
 }
 
+// On Windows, the path is wrapped in double quotes, which both cmd
+//  and PowerShell understand (single quotes aren't quotes for cmd),
+//  and which can't be found in a path so don't have to be escaped.
+// The backslashes and the drive colon don't need quotes.
+#[cfg(windows)]
+pub fn escape_for_shell(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    if regex!(r"^[\w\\/.:-]*$").is_match(&path) {
+        path.to_string()
+    } else {
+        quote(&path)
+    }
+}
+
+/// quote the path for the shell, even when it's not necessary
+pub fn quote_for_shell(path: &Path) -> String {
+    quote(&path.to_string_lossy())
+}
+
+#[cfg(not(windows))]
+fn quote(path: &str) -> String {
+    format!("'{}'", path.replace('\'', r"'\''"))
+}
+
+#[cfg(windows)]
+fn quote(path: &str) -> String {
+    format!("\"{}\"", path)
+}
+
 pub fn print_path(path: &Path, con: &AppContext) -> io::Result<AppStateCmdResult> {
     let path = path.to_string_lossy().to_string();
     Ok(
@@ -0,0 +1,634 @@
+//! the copy of a file, or of a directory with its content, done by
+//! broot in small steps so that its progress can be displayed and it
+//! can be cancelled, as done by `:cp`.
+//!
+//! The content of a file is written in a part file next to its
+//! destination, which is renamed when complete. When the copy of
+//! a file is started again, after a cancellation or in another
+//! session, it resumes from the part file (unless the source was
+//! modified since).
+//!
+//! When a file already exists at the destination, the copy stops
+//! until the conflict is settled, unless a choice was made for all
+//! the conflicts. A directory which already exists is merged, its
+//! content being checked file by file.
+//!
+//! When the destination is an existing directory, it's taken as a
+//! previous copy to complete: it's merged without asking, and the
+//! files found already copied aren't copied again.
+//!
+//! The metadata of the copied files and directories are kept as
+//! required by a `Preserve`, the ones of a directory being set once
+//! its content is copied.
+
+use {
+    crate::file_metadata::{self, Preserve},
+    std::{
+        cmp,
+        fs::{self, File, OpenOptions},
+        io::{self, Read, Seek, SeekFrom, Write},
+        path::{Path, PathBuf},
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+/// the suffix of the names of the incompletely copied files
+const PART_SUFFIX: &str = ".broot-part";
+
+/// the max number of bytes copied in a step
+const STEP_SIZE: usize = 1024 * 1024;
+
+/// the max time a throttled step waits
+const MAX_THROTTLE_WAIT: Duration = Duration::from_millis(50);
+
+/// what to do when something to copy already exists at the destination
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictChoice {
+    Overwrite, // a directory is merged
+    Skip,
+    KeepBoth, // the copy is renamed with a number
+    OverwriteIfNewer, // overwrite when the source was modified after the existing file
+}
+
+impl ConflictChoice {
+    pub const ALL: [ConflictChoice; 4] = [
+        ConflictChoice::Overwrite,
+        ConflictChoice::Skip,
+        ConflictChoice::KeepBoth,
+        ConflictChoice::OverwriteIfNewer,
+    ];
+    pub fn description(self) -> &'static str {
+        match self {
+            ConflictChoice::Overwrite => "overwrite (merge for a directory)",
+            ConflictChoice::Skip => "skip",
+            ConflictChoice::KeepBoth => "keep both, renaming the copy with a number",
+            ConflictChoice::OverwriteIfNewer => "overwrite if the source is newer",
+        }
+    }
+}
+
+/// something to create at the destination. The `merge` and `replace`
+/// flags tell whether it may replace an existing file (the conflict
+/// being settled)
+#[derive(Debug, Clone)]
+enum Entry {
+    Dir { source: PathBuf, dest: PathBuf, merge: bool },
+    DirMetadata { source: PathBuf, dest: PathBuf }, // after the content of the dir
+    File { source: PathBuf, dest: PathBuf, replace: bool },
+    Link { source: PathBuf, target: PathBuf, dest: PathBuf, replace: bool },
+}
+
+impl Entry {
+    fn source(&self) -> &Path {
+        match self {
+            Entry::Dir { source, .. } => source,
+            Entry::DirMetadata { source, .. } => source,
+            Entry::File { source, .. } => source,
+            Entry::Link { source, .. } => source,
+        }
+    }
+    fn is_dir(&self) -> bool {
+        match self {
+            Entry::Dir { .. } => true,
+            _ => false,
+        }
+    }
+    fn dest(&self) -> &Path {
+        match self {
+            Entry::Dir { dest, .. } => dest,
+            Entry::DirMetadata { dest, .. } => dest,
+            Entry::File { dest, .. } => dest,
+            Entry::Link { dest, .. } => dest,
+        }
+    }
+    fn set_dest(&mut self, new_dest: PathBuf) {
+        match self {
+            Entry::Dir { dest, .. } => *dest = new_dest,
+            Entry::DirMetadata { dest, .. } => *dest = new_dest,
+            Entry::File { dest, .. } => *dest = new_dest,
+            Entry::Link { dest, .. } => *dest = new_dest,
+        }
+    }
+    /// tell whether the entry's creation would hit an existing file
+    /// which the user didn't accept to replace
+    fn is_conflicting(&self) -> bool {
+        match self {
+            Entry::Dir { merge: false, dest, .. } => fs::symlink_metadata(dest).is_ok(),
+            Entry::File { replace: false, dest, .. } => fs::symlink_metadata(dest).is_ok(),
+            Entry::Link { replace: false, dest, .. } => fs::symlink_metadata(dest).is_ok(),
+            _ => false,
+        }
+    }
+    /// accept the replacement of the existing file, or the merge
+    /// with the existing directory
+    fn accept_existing(&mut self) -> io::Result<()> {
+        let dest_is_dir = fs::symlink_metadata(self.dest()).map_or(false, |md| md.is_dir());
+        match self {
+            Entry::Dir { merge, dest, .. } => {
+                if !dest_is_dir {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        format!("{:?} can't be replaced with a directory", dest),
+                    ));
+                }
+                *merge = true;
+            }
+            Entry::File { replace, dest, .. } | Entry::Link { replace, dest, .. } => {
+                if dest_is_dir {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        format!("the directory {:?} can't be replaced with a file", dest),
+                    ));
+                }
+                *replace = true;
+            }
+            Entry::DirMetadata { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+/// the file whose content is being copied
+struct Transfer {
+    source_path: PathBuf,
+    source: File,
+    part: File,
+    part_path: PathBuf,
+    dest: PathBuf,
+    replace: bool,
+    resumed: u64, // the bytes found in the part file
+    copied: u64,  // including the resumed ones
+}
+
+pub struct FileCopy {
+    pub source: PathBuf,
+    pub dest: PathBuf,
+    entries: Vec<Entry>, // the ones still to create, the next one last
+    transfer: Option<Transfer>,
+    conflict: Option<Entry>, // waiting for the user's choice
+    conflict_policy: Option<ConflictChoice>, // the choice made for all the conflicts
+    pub skipped_count: usize, // the files not copied because of a conflict
+    pub reused_dest: bool, // whether the destination existed before and was kept
+    buffer: Vec<u8>,
+    pub total_bytes: u64,
+    pub done_bytes: u64,
+    pub resumed_bytes: u64, // the bytes which were found already copied
+    max_rate: Option<u64>,  // in bytes per second
+    preserve: Preserve,
+    run_start: Instant,     // when the copy was last started or resumed
+    run_bytes: u64,         // the bytes copied since run_start
+}
+
+/// build the name of the n-th copy kept beside an existing file,
+/// eg `notes (2).txt` for `notes.txt`
+fn numbered_name(name: &str, n: usize, keep_extension: bool) -> String {
+    match name.rfind('.') {
+        // the dot starting the name of a hidden file isn't an extension's one
+        Some(idx) if idx > 0 && keep_extension => {
+            format!("{} ({}){}", &name[..idx], n, &name[idx..])
+        }
+        _ => format!("{} ({})", name, n),
+    }
+}
+
+/// return the first path, numbered after the given one, at which
+/// nothing exists
+fn free_path(path: &Path, is_dir: bool) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    (1..)
+        .map(|n| path.with_file_name(numbered_name(&name, n, !is_dir)))
+        .find(|path| fs::symlink_metadata(path).is_err())
+        .unwrap() // there's always a free name
+}
+
+/// tell whether the source was modified after the destination
+fn is_newer(source: &Path, dest: &Path) -> bool {
+    let modified = |path| fs::symlink_metadata(path).and_then(|md| md.modified());
+    match (modified(source), modified(dest)) {
+        (Ok(source_date), Ok(dest_date)) => source_date > dest_date,
+        _ => false,
+    }
+}
+
+fn part_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(PART_SUFFIX);
+    dest.with_file_name(name)
+}
+
+/// tell whether the destination file looks like a complete copy
+/// of the source: same size, and not older
+fn is_copied(source: &Path, dest: &Path) -> bool {
+    match (fs::symlink_metadata(source), fs::symlink_metadata(dest)) {
+        (Ok(source_md), Ok(dest_md)) => {
+            dest_md.is_file() && dest_md.len() == source_md.len() && !is_newer(source, dest)
+        }
+        _ => false,
+    }
+}
+
+/// tell whether the part file was written after the last
+/// modification of the source, and so may be resumed
+fn is_resumable(part: &fs::Metadata, source: &fs::Metadata) -> bool {
+    if part.len() > source.len() {
+        return false;
+    }
+    match (part.modified(), source.modified()) {
+        (Ok(part_date), Ok(source_date)) => part_date >= source_date,
+        _ => false,
+    }
+}
+
+#[cfg(unix)]
+fn make_link(_source: &Path, target: &Path, dest: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, dest)
+}
+
+#[cfg(windows)]
+fn make_link(source: &Path, target: &Path, dest: &Path) -> io::Result<()> {
+    if fs::metadata(source).map_or(false, |md| md.is_dir()) {
+        std::os::windows::fs::symlink_dir(target, dest)
+    } else {
+        std::os::windows::fs::symlink_file(target, dest)
+    }
+}
+
+/// list the entries to create for the copy of source, parents
+/// before their children, and return the size of the files
+fn plan(source: &Path, dest: &Path, entries: &mut Vec<Entry>) -> io::Result<u64> {
+    let md = fs::symlink_metadata(source)?;
+    let file_type = md.file_type();
+    if file_type.is_symlink() {
+        entries.push(Entry::Link {
+            source: source.to_path_buf(),
+            target: fs::read_link(source)?,
+            dest: dest.to_path_buf(),
+            replace: false,
+        });
+        Ok(0)
+    } else if file_type.is_dir() {
+        entries.push(Entry::Dir {
+            source: source.to_path_buf(),
+            dest: dest.to_path_buf(),
+            merge: false,
+        });
+        let mut size = 0;
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            size += plan(&entry.path(), &dest.join(entry.file_name()), entries)?;
+        }
+        entries.push(Entry::DirMetadata {
+            source: source.to_path_buf(),
+            dest: dest.to_path_buf(),
+        });
+        Ok(size)
+    } else if file_type.is_file() {
+        entries.push(Entry::File {
+            source: source.to_path_buf(),
+            dest: dest.to_path_buf(),
+            replace: false,
+        });
+        Ok(md.len())
+    } else {
+        warn!("not copying special file {:?}", source);
+        Ok(0)
+    }
+}
+
+impl FileCopy {
+    /// prepare the copy of source to dest. An existing destination
+    /// directory is completed, while an existing file is subject to
+    /// the settlement of a conflict.
+    /// `max_rate`, in bytes per second, throttles the copy.
+    pub fn new(
+        source: PathBuf,
+        dest: PathBuf,
+        max_rate: Option<u64>,
+        preserve: Preserve,
+    ) -> io::Result<FileCopy> {
+        if dest.starts_with(&source) {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "a directory can't be copied into itself",
+            ));
+        }
+        let mut entries = Vec::new();
+        let total_bytes = plan(&source, &dest, &mut entries)?;
+        let mut done_bytes = 0;
+        let is_dir = |path: &Path| fs::symlink_metadata(path).map_or(false, |md| md.is_dir());
+        let reused_dest = is_dir(&source) && is_dir(&dest);
+        if reused_dest {
+            // a copy started before, maybe in another session, is completed
+            for entry in &mut entries {
+                if let Entry::Dir { merge, .. } = entry {
+                    *merge = true;
+                }
+            }
+            entries.retain(|entry| match entry {
+                Entry::File { source, dest, .. } if is_copied(source, dest) => {
+                    done_bytes += fs::metadata(source).map_or(0, |md| md.len());
+                    false
+                }
+                _ => true,
+            });
+        }
+        entries.reverse();
+        Ok(FileCopy {
+            source,
+            dest,
+            entries,
+            transfer: None,
+            conflict: None,
+            conflict_policy: None,
+            skipped_count: 0,
+            reused_dest,
+            buffer: vec![0; STEP_SIZE],
+            total_bytes,
+            done_bytes,
+            resumed_bytes: done_bytes,
+            max_rate,
+            preserve,
+            run_start: Instant::now(),
+            run_bytes: 0,
+        })
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.transfer.is_none() && self.entries.is_empty() && self.conflict.is_none()
+    }
+
+    /// the source and destination of the copy which waits for
+    /// the user to settle the conflict, if any
+    pub fn conflict(&self) -> Option<(&Path, &Path)> {
+        self.conflict.as_ref().map(|entry| (entry.source(), entry.dest()))
+    }
+
+    /// settle the pending conflict, and the following ones too
+    /// when `for_all` is true
+    pub fn resolve(&mut self, choice: ConflictChoice, for_all: bool) -> io::Result<()> {
+        if for_all {
+            self.conflict_policy = Some(choice);
+        }
+        match self.conflict.take() {
+            Some(entry) => self.settle(entry, choice),
+            None => Ok(()),
+        }
+    }
+
+    fn settle(&mut self, mut entry: Entry, choice: ConflictChoice) -> io::Result<()> {
+        let choice = match choice {
+            // the files of a merged directory are compared one by one
+            ConflictChoice::OverwriteIfNewer
+                if entry.is_dir() || is_newer(entry.source(), entry.dest()) =>
+            {
+                ConflictChoice::Overwrite
+            }
+            ConflictChoice::OverwriteIfNewer => ConflictChoice::Skip,
+            choice => choice,
+        };
+        match choice {
+            ConflictChoice::Overwrite => {
+                if let Err(e) = entry.accept_existing() {
+                    // the user will have to make another choice
+                    self.conflict = Some(entry);
+                    self.conflict_policy = None;
+                    return Err(e);
+                }
+                if entry.dest() == self.dest {
+                    self.reused_dest = true;
+                }
+                self.entries.push(entry);
+            }
+            ConflictChoice::Skip => {
+                if entry.dest() == self.dest {
+                    self.reused_dest = true;
+                }
+                self.skip(entry);
+            }
+            _ => {
+                // the entry, and the content of a directory, go to a free path
+                let old_dest = entry.dest().to_path_buf();
+                let new_dest = free_path(&old_dest, entry.is_dir());
+                for other in &mut self.entries {
+                    if let Ok(relative) = other.dest().strip_prefix(&old_dest) {
+                        let other_dest = new_dest.join(relative);
+                        other.set_dest(other_dest);
+                    }
+                }
+                if old_dest == self.dest {
+                    self.dest = new_dest.clone();
+                }
+                entry.set_dest(new_dest);
+                self.entries.push(entry);
+            }
+        }
+        Ok(())
+    }
+
+    /// drop the entry and, for a directory, its content
+    fn skip(&mut self, entry: Entry) {
+        let dest = entry.dest().to_path_buf();
+        let (mut skipped, kept): (Vec<Entry>, Vec<Entry>) = self
+            .entries
+            .drain(..)
+            .partition(|other| other.dest().starts_with(&dest));
+        self.entries = kept;
+        skipped.push(entry);
+        for entry in skipped {
+            match entry {
+                Entry::File { source, .. } => {
+                    self.done_bytes += fs::metadata(&source).map_or(0, |md| md.len());
+                    self.skipped_count += 1;
+                }
+                Entry::Link { .. } => {
+                    self.skipped_count += 1;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// the destination of the file being copied, if any
+    pub fn current_file(&self) -> Option<&Path> {
+        self.transfer.as_ref().map(|transfer| transfer.dest.as_path())
+    }
+
+    /// the part of the bytes which are copied, between 0 and 1
+    pub fn done_share(&self) -> f32 {
+        if self.total_bytes == 0 {
+            1.0
+        } else {
+            (self.done_bytes as f64 / self.total_bytes as f64).min(1.0) as f32
+        }
+    }
+
+    /// the rate of the copy since it was last started, in bytes per second
+    pub fn rate(&self) -> u64 {
+        let elapsed = self.run_start.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            (self.run_bytes as f64 / elapsed) as u64
+        } else {
+            0
+        }
+    }
+
+    /// the estimated duration of the rest of the copy, if the rate is known
+    pub fn remaining_time(&self) -> Option<Duration> {
+        match self.rate() {
+            0 => None,
+            rate => {
+                let remaining = self.total_bytes.saturating_sub(self.done_bytes);
+                Some(Duration::from_secs(remaining / rate))
+            }
+        }
+    }
+
+    fn start_transfer(
+        &mut self,
+        source_path: PathBuf,
+        dest: PathBuf,
+        replace: bool,
+    ) -> io::Result<()> {
+        let part_path = part_path(&dest);
+        let mut source = File::open(&source_path)?;
+        let source_md = source.metadata()?;
+        let resumable = fs::metadata(&part_path)
+            .ok()
+            .filter(|part_md| is_resumable(part_md, &source_md));
+        let (part, copied) = match resumable {
+            Some(part_md) => {
+                source.seek(SeekFrom::Start(part_md.len()))?;
+                let part = OpenOptions::new().append(true).open(&part_path)?;
+                (part, part_md.len())
+            }
+            None => (File::create(&part_path)?, 0),
+        };
+        self.done_bytes += copied;
+        self.resumed_bytes += copied;
+        self.transfer = Some(Transfer {
+            source_path,
+            source,
+            part,
+            part_path,
+            dest,
+            replace,
+            resumed: copied,
+            copied,
+        });
+        Ok(())
+    }
+
+    /// wait a little if the copy goes faster than the max rate,
+    /// and return whether it did
+    fn throttle(&self) -> bool {
+        if let Some(max_rate) = self.max_rate {
+            let expected = Duration::from_secs_f64(self.run_bytes as f64 / max_rate as f64);
+            let elapsed = self.run_start.elapsed();
+            if expected > elapsed {
+                thread::sleep(cmp::min(expected - elapsed, MAX_THROTTLE_WAIT));
+                return true;
+            }
+        }
+        false
+    }
+
+    /// create a directory or a link, or start the copy of a file
+    fn create(&mut self, entry: Entry) -> io::Result<()> {
+        match entry {
+            Entry::Dir { dest, .. } => fs::create_dir_all(&dest)?,
+            Entry::DirMetadata { source, dest } => {
+                file_metadata::copy_metadata(&source, &dest, self.preserve)?;
+            }
+            Entry::Link { source, target, dest, replace } => {
+                if replace && fs::symlink_metadata(&dest).is_ok() {
+                    fs::remove_file(&dest)?;
+                }
+                make_link(&source, &target, &dest)?;
+                file_metadata::copy_metadata(&source, &dest, self.preserve)?;
+            }
+            Entry::File { source, dest, replace } => self.start_transfer(source, dest, replace)?,
+        }
+        Ok(())
+    }
+
+    /// do a small part of the copy: create a directory or a link,
+    /// or copy a part of a file
+    pub fn step(&mut self) -> io::Result<()> {
+        if self.throttle() {
+            return Ok(());
+        }
+        let transfer = match self.transfer.as_mut() {
+            Some(transfer) => transfer,
+            None => {
+                if self.conflict.is_some() {
+                    return Ok(()); // waiting for the user's choice
+                }
+                match self.entries.pop() {
+                    Some(entry) if entry.is_conflicting() => match self.conflict_policy {
+                        Some(choice) => self.settle(entry, choice)?,
+                        None => self.conflict = Some(entry),
+                    },
+                    Some(entry) => {
+                        if let Err(e) = self.create(entry.clone()) {
+                            // kept so that it's created on resume
+                            self.entries.push(entry);
+                            return Err(e);
+                        }
+                    }
+                    None => {}
+                }
+                return Ok(());
+            }
+        };
+        let len = transfer.source.read(&mut self.buffer)?;
+        if len > 0 {
+            transfer.part.write_all(&self.buffer[..len])?;
+            transfer.copied += len as u64;
+            self.done_bytes += len as u64;
+            self.run_bytes += len as u64;
+            return Ok(());
+        }
+        // the file is completely copied
+        transfer.part.flush()?;
+        file_metadata::copy_metadata(&transfer.source_path, &transfer.part_path, self.preserve)?;
+        fs::rename(&transfer.part_path, &transfer.dest)?;
+        self.transfer = None;
+        Ok(())
+    }
+
+    /// stop the copy of the current file, keeping its part file so
+    /// that the copy can be resumed
+    pub fn interrupt(&mut self) {
+        if let Some(transfer) = self.transfer.take() {
+            self.done_bytes -= transfer.copied;
+            self.resumed_bytes -= transfer.resumed;
+            self.entries.push(Entry::File {
+                source: transfer.source_path,
+                dest: transfer.dest,
+                replace: transfer.replace,
+            });
+        }
+    }
+
+    /// prepare the copy to go on after an interruption
+    pub fn resume(&mut self) {
+        self.run_start = Instant::now();
+        self.run_bytes = 0;
+    }
+}
+
+#[cfg(test)]
+mod file_copy_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_numbered_names() {
+        assert_eq!(numbered_name("notes.txt", 2, true), "notes (2).txt");
+        assert_eq!(numbered_name("archive.tar.gz", 1, true), "archive.tar (1).gz");
+        assert_eq!(numbered_name(".bashrc", 1, true), ".bashrc (1)");
+        assert_eq!(numbered_name("photos.2020", 3, false), "photos.2020 (3)");
+        assert_eq!(numbered_name("README", 1, true), "README (1)");
+    }
+}
@@ -0,0 +1,103 @@
+use {
+    super::LockingProcess,
+    std::{io, mem, os::windows::ffi::OsStrExt, path::Path, ptr},
+    winapi::{
+        shared::{
+            minwindef::{DWORD, UINT},
+            winerror::{
+                ERROR_LOCK_VIOLATION, ERROR_MORE_DATA, ERROR_SHARING_VIOLATION, ERROR_SUCCESS,
+            },
+        },
+        um::restartmanager::{
+            RmEndSession, RmGetList, RmRegisterResources, RmStartSession, CCH_RM_SESSION_KEY,
+            RM_PROCESS_INFO,
+        },
+    },
+};
+
+/// a Restart Manager session, ended when dropped
+struct Session {
+    handle: DWORD,
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        unsafe {
+            RmEndSession(self.handle);
+        }
+    }
+}
+
+fn check(code: DWORD) -> io::Result<()> {
+    if code == ERROR_SUCCESS {
+        Ok(())
+    } else {
+        Err(io::Error::from_raw_os_error(code as i32))
+    }
+}
+
+/// tell whether the error comes from another process using the file
+pub fn is_lock_error(err: &io::Error) -> bool {
+    match err.raw_os_error() {
+        Some(code) => {
+            let code = code as DWORD;
+            code == ERROR_SHARING_VIOLATION || code == ERROR_LOCK_VIOLATION
+        }
+        None => false,
+    }
+}
+
+/// return the processes which have the file open
+pub fn locking_processes(path: &Path) -> io::Result<Vec<LockingProcess>> {
+    let mut handle: DWORD = 0;
+    let mut session_key = [0u16; CCH_RM_SESSION_KEY + 1];
+    check(unsafe { RmStartSession(&mut handle, 0, session_key.as_mut_ptr()) })?;
+    let session = Session { handle };
+    let wide_path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut file_names = [wide_path.as_ptr()];
+    check(unsafe {
+        RmRegisterResources(
+            session.handle,
+            1,
+            file_names.as_mut_ptr(),
+            0,
+            ptr::null_mut(),
+            0,
+            ptr::null_mut(),
+        )
+    })?;
+    let mut infos: Vec<RM_PROCESS_INFO> = Vec::new();
+    loop {
+        let mut needed: UINT = 0;
+        let mut count = infos.len() as UINT;
+        let mut reboot_reasons: DWORD = 0;
+        let code = unsafe {
+            RmGetList(
+                session.handle,
+                &mut needed,
+                &mut count,
+                infos.as_mut_ptr(),
+                &mut reboot_reasons,
+            )
+        };
+        if code == ERROR_MORE_DATA {
+            // the list changed or was bigger than our buffer
+            infos = vec![unsafe { mem::zeroed() }; needed as usize];
+            continue;
+        }
+        check(code)?;
+        infos.truncate(count as usize);
+        break;
+    }
+    Ok(infos
+        .iter()
+        .map(|info| {
+            let app_name = &info.strAppName;
+            let len = app_name.iter().position(|&c| c == 0).unwrap_or(app_name.len());
+            LockingProcess {
+                pid: info.Process.dwProcessId,
+                name: String::from_utf16_lossy(&app_name[..len]),
+            }
+        })
+        .collect())
+}
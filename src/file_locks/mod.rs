@@ -0,0 +1,50 @@
+//! detection of the processes using a file, so that an operation
+//! which failed because the file is in use can tell which program
+//! to close before retrying.
+//!
+//! It's only done on Windows, where an open file usually can't be
+//! renamed nor removed, with the Restart Manager.
+
+#[cfg(windows)]
+mod file_locks_windows;
+
+#[cfg(windows)]
+pub use file_locks_windows::{is_lock_error, locking_processes};
+
+use std::{io, path::Path};
+
+/// a process which has a file open
+#[derive(Debug, Clone)]
+pub struct LockingProcess {
+    pub pid: u32,
+    pub name: String,
+}
+
+#[cfg(not(windows))]
+pub fn is_lock_error(_err: &io::Error) -> bool {
+    false
+}
+
+#[cfg(not(windows))]
+pub fn locking_processes(_path: &Path) -> io::Result<Vec<LockingProcess>> {
+    Ok(Vec::new())
+}
+
+/// tell which processes use the file, as in "it's used by vim (pid 1234)",
+/// or nothing when there's none
+pub fn describe_lockers(path: &Path) -> Option<String> {
+    match locking_processes(path) {
+        Ok(processes) if !processes.is_empty() => {
+            let processes: Vec<String> = processes
+                .iter()
+                .map(|p| format!("{} (pid {})", p.name, p.pid))
+                .collect();
+            Some(format!("it's used by {}", processes.join(", ")))
+        }
+        Ok(_) => None,
+        Err(e) => {
+            warn!("failed to find the processes using {:?}: {:?}", path, e);
+            None
+        }
+    }
+}
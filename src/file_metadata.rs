@@ -0,0 +1,321 @@
+//! the metadata which a copy done by broot keeps, besides the content:
+//! the dates, the permissions, the extended attributes and the owner,
+//! as `cp -a` does.
+//!
+//! Only the owner's change is restricted: when the user isn't allowed
+//! to give the copy the owner of the source, only its group is kept,
+//! if possible, and the copy goes on.
+
+use std::{fs, io, path::Path};
+
+/// which metadata a copy keeps
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Preserve {
+    pub mtime: bool, // the dates of last modification and access
+    pub permissions: bool,
+    pub xattrs: bool, // the extended attributes (only on linux)
+    pub ownership: bool, // the owner and group (only on unix)
+}
+
+impl Default for Preserve {
+    /// only the permissions are kept by default, as done by `cp`
+    fn default() -> Self {
+        Self {
+            mtime: false,
+            permissions: true,
+            xattrs: false,
+            ownership: false,
+        }
+    }
+}
+
+impl Preserve {
+    pub fn all() -> Self {
+        Self {
+            mtime: true,
+            permissions: true,
+            xattrs: true,
+            ownership: true,
+        }
+    }
+    pub fn none() -> Self {
+        Self {
+            mtime: false,
+            permissions: false,
+            xattrs: false,
+            ownership: false,
+        }
+    }
+    /// parse a comma separated list of the kept metadata, eg
+    /// `mtime,permissions`, or `all`, or `none`
+    pub fn from_names(names: &str) -> Option<Self> {
+        let mut preserve = Self::none();
+        for name in names.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            match name.to_lowercase().as_ref() {
+                "all" => preserve = Self::all(),
+                "none" => preserve = Self::none(),
+                "mtime" | "timestamps" => preserve.mtime = true,
+                "permissions" | "mode" => preserve.permissions = true,
+                "xattrs" | "xattr" => preserve.xattrs = true,
+                "ownership" => preserve.ownership = true,
+                _ => return None,
+            }
+        }
+        Some(preserve)
+    }
+    /// read the flags starting the arguments of a copy (`-a` to keep
+    /// all the metadata, `--preserve=list` or `--no-preserve`) and
+    /// return the metadata to keep with the rest of the arguments
+    pub fn from_args(args: &str, default: Preserve) -> Result<(Self, &str), String> {
+        let mut preserve = default;
+        let mut rest = args.trim_start();
+        while rest.starts_with('-') {
+            let flag_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let flag = &rest[..flag_end];
+            preserve = match flag {
+                "-a" => Self::all(),
+                "--no-preserve" => Self::none(),
+                _ if flag.starts_with("--preserve=") => Self::from_names(&flag[11..])
+                    .ok_or_else(|| format!("invalid metadata list: {:?}", &flag[11..]))?,
+                _ => return Err(format!("unknown flag: {:?}", flag)),
+            };
+            rest = rest[flag_end..].trim_start();
+        }
+        Ok((preserve, rest))
+    }
+}
+
+#[cfg(unix)]
+fn c_path(path: &Path) -> io::Result<std::ffi::CString> {
+    use std::os::unix::ffi::OsStrExt;
+    std::ffi::CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "nul byte in path"))
+}
+
+/// give dest the owner and group of the source, or only its group
+/// when the user isn't allowed to change the owner
+#[cfg(unix)]
+fn copy_ownership(md: &fs::Metadata, dest: &Path) -> io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+    let c_dest = c_path(dest)?;
+    if unsafe { libc::lchown(c_dest.as_ptr(), md.uid(), md.gid()) } == 0 {
+        return Ok(());
+    }
+    let e = io::Error::last_os_error();
+    if e.raw_os_error() != Some(libc::EPERM) {
+        return Err(e);
+    }
+    // -1 as uid means the owner isn't changed
+    if unsafe { libc::lchown(c_dest.as_ptr(), u32::MAX as libc::uid_t, md.gid()) } != 0 {
+        debug!("not allowed to keep the owner of {:?}", dest);
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn copy_ownership(_md: &fs::Metadata, _dest: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// tell whether the error means the attribute can't be set at the
+/// destination, in which case it's skipped
+#[cfg(target_os = "linux")]
+fn is_unsupported_xattr(e: &io::Error) -> bool {
+    match e.raw_os_error() {
+        Some(code) => code == libc::ENOTSUP || code == libc::EPERM || code == libc::EACCES,
+        None => false,
+    }
+}
+
+/// read a list or a value of extended attributes, growing the buffer
+/// as long as it's too small
+#[cfg(target_os = "linux")]
+fn read_xattr_buffer<F>(read: F) -> io::Result<Vec<u8>>
+where
+    F: Fn(*mut libc::c_void, usize) -> libc::ssize_t,
+{
+    let mut buffer = vec![0u8; 256];
+    loop {
+        let len = read(buffer.as_mut_ptr() as *mut libc::c_void, buffer.len());
+        if len >= 0 {
+            buffer.truncate(len as usize);
+            return Ok(buffer);
+        }
+        let e = io::Error::last_os_error();
+        if e.raw_os_error() != Some(libc::ERANGE) {
+            return Err(e);
+        }
+        let len = buffer.len() * 2;
+        buffer.resize(len, 0);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn copy_xattrs(source: &Path, dest: &Path) -> io::Result<()> {
+    let c_source = c_path(source)?;
+    let c_dest = c_path(dest)?;
+    let names = read_xattr_buffer(|buf, size| unsafe {
+        libc::llistxattr(c_source.as_ptr(), buf as *mut libc::c_char, size)
+    })?;
+    // the names are separated by nul bytes
+    for name in names.split(|&b| b == 0).filter(|name| !name.is_empty()) {
+        let c_name = std::ffi::CString::new(name)?;
+        let value = read_xattr_buffer(|buf, size| unsafe {
+            libc::lgetxattr(c_source.as_ptr(), c_name.as_ptr(), buf, size)
+        })?;
+        let res = unsafe {
+            libc::lsetxattr(
+                c_dest.as_ptr(),
+                c_name.as_ptr(),
+                value.as_ptr() as *const libc::c_void,
+                value.len(),
+                0,
+            )
+        };
+        if res != 0 {
+            let e = io::Error::last_os_error();
+            if !is_unsupported_xattr(&e) {
+                return Err(e);
+            }
+            debug!("extended attribute {:?} not kept on {:?}: {}", c_name, dest, e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn copy_xattrs(_source: &Path, _dest: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_times(md: &fs::Metadata, dest: &Path) -> io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+    let c_dest = c_path(dest)?;
+    let times = [
+        libc::timespec {
+            tv_sec: md.atime() as libc::time_t,
+            tv_nsec: md.atime_nsec() as _,
+        },
+        libc::timespec {
+            tv_sec: md.mtime() as libc::time_t,
+            tv_nsec: md.mtime_nsec() as _,
+        },
+    ];
+    let res = unsafe {
+        libc::utimensat(
+            libc::AT_FDCWD,
+            c_dest.as_ptr(),
+            times.as_ptr(),
+            libc::AT_SYMLINK_NOFOLLOW,
+        )
+    };
+    if res == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(windows)]
+fn copy_times(md: &fs::Metadata, dest: &Path) -> io::Result<()> {
+    use {
+        std::os::windows::{
+            fs::{MetadataExt, OpenOptionsExt},
+            io::AsRawHandle,
+        },
+        winapi::{
+            shared::minwindef::FILETIME,
+            um::{
+                fileapi::SetFileTime, winbase::FILE_FLAG_BACKUP_SEMANTICS,
+                winnt::FILE_WRITE_ATTRIBUTES,
+            },
+        },
+    };
+    let to_filetime = |time: u64| FILETIME {
+        dwLowDateTime: time as u32,
+        dwHighDateTime: (time >> 32) as u32,
+    };
+    let access_time = to_filetime(md.last_access_time());
+    let write_time = to_filetime(md.last_write_time());
+    // the backup semantics flag is needed to open directories
+    let file = fs::OpenOptions::new()
+        .access_mode(FILE_WRITE_ATTRIBUTES)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(dest)?;
+    let res = unsafe {
+        SetFileTime(file.as_raw_handle() as _, std::ptr::null(), &access_time, &write_time)
+    };
+    if res != 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// give dest the metadata of source which must be preserved.
+///
+/// The metadata of a symbolic link are its own, and not the ones
+/// of its target. Its permissions aren't changed.
+pub fn copy_metadata(source: &Path, dest: &Path, preserve: Preserve) -> io::Result<()> {
+    let md = fs::symlink_metadata(source)?;
+    let is_link = md.file_type().is_symlink();
+    // the owner is changed first as it may clear the setuid and setgid bits
+    if preserve.ownership {
+        copy_ownership(&md, dest)?;
+    }
+    // the attributes are set before the permissions, which may forbid it
+    if preserve.xattrs {
+        copy_xattrs(source, dest)?;
+    }
+    if preserve.permissions && !is_link {
+        fs::set_permissions(dest, md.permissions())?;
+    }
+    // the dates are set last, as setting the other metadata may change them
+    if preserve.mtime && (cfg!(unix) || !is_link) {
+        copy_times(&md, dest)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod file_metadata_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_preserve_parsing() {
+        assert_eq!(Preserve::from_names("all"), Some(Preserve::all()));
+        assert_eq!(Preserve::from_names(""), Some(Preserve::none()));
+        assert_eq!(
+            Preserve::from_names("mtime, Permissions"),
+            Some(Preserve {
+                mtime: true,
+                permissions: true,
+                xattrs: false,
+                ownership: false,
+            }),
+        );
+        assert_eq!(
+            Preserve::from_names("all,none,ownership").map(|p| (p.ownership, p.mtime)),
+            Some((true, false)),
+        );
+        assert_eq!(Preserve::from_names("mtime,colors"), None);
+    }
+
+    #[test]
+    fn check_preserve_flags() {
+        let default = Preserve::default();
+        assert_eq!(Preserve::from_args("../backup", default), Ok((default, "../backup")));
+        assert_eq!(
+            Preserve::from_args("-a ../backup", default),
+            Ok((Preserve::all(), "../backup")),
+        );
+        assert_eq!(
+            Preserve::from_args("--no-preserve  --preserve=mtime b", default),
+            Ok((Preserve::from_names("mtime").unwrap(), "b")),
+        );
+        assert!(Preserve::from_args("--preserve=colors b", default).is_err());
+        assert!(Preserve::from_args("-r b", default).is_err());
+    }
+}
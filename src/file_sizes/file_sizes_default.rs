@@ -1,7 +1,7 @@
 //! size computation for non linux
 
 use {
-    crate::task_sync::Dam,
+    crate::{resource_limits, task_sync::Dam},
     crossbeam::{channel::unbounded, sync::WaitGroup},
     std::{
         fs,
@@ -30,7 +30,7 @@ pub fn compute_dir_size(path: &Path, dam: &Dam) -> Option<u64> {
 
     let wg = WaitGroup::new();
     let period = Duration::from_micros(50);
-    for _ in 0..8 {
+    for thread_idx in 0..resource_limits::parallelism() {
         let size = Arc::clone(&size);
         let busy = Arc::clone(&busy);
         let wg = wg.clone();
@@ -40,8 +40,11 @@ pub fn compute_dir_size(path: &Path, dam: &Dam) -> Option<u64> {
             loop {
                 let o = dirs_receiver.recv_timeout(period);
                 if let Ok(Some(open_dir)) = o {
-                    if let Ok(entries) = fs::read_dir(&open_dir) {
+                    if let Ok(entries) = resource_limits::read_dir(&open_dir) {
                         for e in entries.flatten() {
+                            if observer.has_event() {
+                                break; // a huge directory mustn't delay the interruption
+                            }
                             if let Ok(md) = e.metadata() {
                                 if md.is_dir() {
                                     busy.fetch_add(1, Ordering::Relaxed);
@@ -59,6 +62,9 @@ pub fn compute_dir_size(path: &Path, dam: &Dam) -> Option<u64> {
                 if observer.has_event() {
                     break;
                 }
+                if thread_idx >= resource_limits::parallelism() {
+                    break; // file descriptors are scarce, the other threads go on
+                }
             }
             drop(wg);
         });
@@ -1,5 +1,8 @@
 use {
-    crate::task_sync::Dam,
+    crate::{
+        resource_limits,
+        task_sync::Dam,
+    },
     crossbeam::{
         channel::unbounded,
         sync::WaitGroup,
@@ -22,6 +25,13 @@ use {
 pub fn compute_dir_size(path: &Path, dam: &Dam) -> Option<u64> {
     debug!("compute size of dir {:?} --------------- ", path);
     let inodes = Arc::new(Mutex::new(HashSet::<u64>::default())); // to avoid counting twice an inode
+    // (device, inode) of the directories already queued, so that a loop (made
+    // for example by a bind mount) doesn't keep the computation running forever
+    let mut dirs = HashSet::<(u64, u64)>::default();
+    if let Ok(md) = fs::metadata(path) {
+        dirs.insert((md.dev(), md.ino()));
+    }
+    let dirs = Arc::new(Mutex::new(dirs));
     // the computation is done on blocks of 512 bytes
     // see https://doc.rust-lang.org/std/os/unix/fs/trait.MetadataExt.html#tymethod.blocks
     let blocks = Arc::new(AtomicU64::new(0));
@@ -37,21 +47,28 @@ pub fn compute_dir_size(path: &Path, dam: &Dam) -> Option<u64> {
 
     let wg = WaitGroup::new();
     let period = Duration::from_micros(50);
-    for _ in 0..8 {
+    for thread_idx in 0..resource_limits::parallelism() {
         let blocks = Arc::clone(&blocks);
         let busy = Arc::clone(&busy);
         let wg = wg.clone();
         let (dirs_sender, dirs_receiver) = (dirs_sender.clone(), dirs_receiver.clone());
         let inodes = inodes.clone();
+        let dirs = dirs.clone();
         let observer = dam.observer();
         thread::spawn(move|| {
             loop {
                 let o = dirs_receiver.recv_timeout(period);
                 if let Ok(Some(open_dir)) = o {
-                    if let Ok(entries) = fs::read_dir(&open_dir) {
+                    if let Ok(entries) = resource_limits::read_dir(&open_dir) {
                         for e in entries.flatten() {
+                            if observer.has_event() {
+                                break; // a huge directory mustn't delay the interruption
+                            }
                             if let Ok(md) = e.metadata() {
                                 if md.is_dir() {
+                                    if !dirs.lock().unwrap().insert((md.dev(), md.ino())) {
+                                        continue; // already counted
+                                    }
                                     busy.fetch_add(1, Ordering::Relaxed);
                                     dirs_sender.send(Some(e.path())).unwrap();
                                 } else if md.nlink() > 1 {
@@ -73,6 +90,9 @@ pub fn compute_dir_size(path: &Path, dam: &Dam) -> Option<u64> {
                 if observer.has_event() {
                     break;
                 }
+                if thread_idx >= resource_limits::parallelism() {
+                    break; // file descriptors are scarce, the other threads go on
+                }
             }
             drop(wg);
         });
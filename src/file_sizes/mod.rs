@@ -3,12 +3,15 @@
 ///  same directories again and again.
 /// Hard links are checked to avoid counting
 ///  twice an inode.
+/// The computed sizes are also persisted so that they
+///  may be displayed, before being checked, in the next
+///  sessions.
 ///
 use {
     crate::task_sync::Dam,
     std::{
         collections::HashMap,
-        fmt,
+        fmt, io,
         ops::AddAssign,
         path::{Path, PathBuf},
         sync::Mutex,
@@ -26,6 +29,38 @@ pub fn clear_cache() {
     size_cache.clear();
 }
 
+/// forget the sizes of the changed path, of its descendants and
+/// of the directories containing it
+pub fn forget_path(changed: &Path) {
+    let mut size_cache = SIZE_CACHE_MUTEX.lock().unwrap();
+    size_cache.retain(|path, _| !changed.starts_with(path) && !path.starts_with(changed));
+}
+
+/// forget all sizes of the directory and its descendants, including
+/// the ones persisted from previous sessions
+pub fn forget_dir_sizes(root: &Path) {
+    let mut size_cache = SIZE_CACHE_MUTEX.lock().unwrap();
+    size_cache.retain(|path, _| !path.starts_with(root));
+    persisted_cache::forget(root);
+}
+
+/// return the already known sizes of the directory and of its
+/// descendants, the ones computed in this session having priority
+pub fn known_dir_sizes(root: &Path) -> Vec<(PathBuf, FileSize)> {
+    let mut sizes: HashMap<PathBuf, u64> = persisted_cache::get_under(root).into_iter().collect();
+    let size_cache = SIZE_CACHE_MUTEX.lock().unwrap();
+    for (path, size) in size_cache.iter().filter(|(path, _)| path.starts_with(root)) {
+        sizes.insert(path.clone(), *size);
+    }
+    sizes.into_iter().map(|(path, size)| (path, FileSize::new(size, false))).collect()
+}
+
+/// write the sizes computed in this session, so that they're
+/// available in the next ones
+pub fn save_persisted_sizes() -> io::Result<()> {
+    persisted_cache::save()
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct FileSize {
     real_size: u64, // bytes, the space it takes on disk
@@ -54,12 +89,44 @@ impl FileSize {
         }
         if let Some(s) = time!(Debug, "size sum", path, compute_dir_size(path, dam)) {
             size_cache.insert(PathBuf::from(path), s);
+            persisted_cache::set(path, s);
             Some(FileSize::new(s, false))
         } else {
             None
         }
     }
 
+    /// Return the size of the directory if it was already computed, in
+    ///  this session or in a previous one, without computing it
+    pub fn from_known(path: &Path) -> Option<Self> {
+        let size_cache = SIZE_CACHE_MUTEX.lock().unwrap();
+        size_cache
+            .get(path)
+            .copied()
+            .or_else(|| persisted_cache::get(path))
+            .map(|s| Self::new(s, false))
+    }
+
+    /// Return the size the directory had in a previous session, if
+    ///  it wasn't modified since. Changes deeper in the directory
+    ///  aren't detected, so this size must be checked with from_dir.
+    pub fn from_persisted_cache(path: &Path) -> Option<Self> {
+        persisted_cache::get(path).map(|s| Self::new(s, false))
+    }
+
+    /// return the size expressed in the biggest unit keeping it
+    /// below 5000, and the name of that unit (empty for bytes)
+    pub fn value_and_unit(&self) -> (u64, &'static str) {
+        let mut v = self.real_size;
+        let mut i = 0;
+        while v >= 5000 && i < SIZE_NAMES.len() - 1 {
+            //v >>= 10;
+            v /= 1000;
+            i += 1;
+        }
+        (v, SIZE_NAMES[i])
+    }
+
     pub fn part_of(self, total: Self) -> f32 {
         if total.real_size == 0 {
             0.0
@@ -72,14 +139,8 @@ impl FileSize {
 impl fmt::Display for FileSize {
     /// format a number of bytes as a string, for example 247K
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut v = self.real_size;
-        let mut i = 0;
-        while v >= 5000 && i < SIZE_NAMES.len() - 1 {
-            //v >>= 10;
-            v /= 1000;
-            i += 1;
-        }
-        write!(f, "{}{}", v, &SIZE_NAMES[i])
+        let (v, unit) = self.value_and_unit();
+        write!(f, "{}{}", v, unit)
     }
 }
 
@@ -98,6 +159,8 @@ impl Into<u64> for FileSize {
     }
 }
 
+mod persisted_cache;
+
 // ---------------- OS dependent implementations
 
 #[cfg(unix)]
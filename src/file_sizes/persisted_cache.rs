@@ -0,0 +1,339 @@
+//! the sizes of directories, kept in a file between sessions so
+//! that they can be displayed before being computed again.
+//!
+//! An entry is only used while the modification date of the directory
+//! is the same. As this date doesn't change when something deeper is
+//! modified, the sizes found here must still be checked.
+//!
+//! The file is shared by the instances of broot running at the same
+//! time (eg in several tabs of a terminal): it's written during the
+//! session, under a lock, merged with what the other instances wrote,
+//! and read again when a size is missing and the file changed.
+
+use {
+    crate::conf,
+    std::{
+        collections::HashMap,
+        fs, io,
+        path::{Path, PathBuf},
+        process,
+        sync::Mutex,
+        thread,
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// the maximal number of directories in the file
+const MAX_ENTRIES: usize = 10_000;
+
+/// the minimal delay between two writings of the file during a session
+const SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// the minimal delay between two checks of the file, for the sizes
+/// computed by the other instances
+const RELOAD_INTERVAL: Duration = Duration::from_secs(2);
+
+/// how long an instance waits for the lock before giving up the writing
+const LOCK_WAIT: Duration = Duration::from_secs(2);
+
+/// the age after which a lock is assumed to be left by a crashed instance
+const STALE_LOCK_AGE: Duration = Duration::from_secs(10);
+
+struct Entry {
+    mtime: u64,  // seconds since the epoch
+    size: u64,
+    used: bool, // whether it was read or written in this session
+}
+
+struct SizeCache {
+    entries: HashMap<PathBuf, Entry>,
+    changed: bool,
+    forgotten: Vec<PathBuf>, // roots whose sizes must not come back from the file
+    file_mtime: Option<SystemTime>, // of the file, when last read or written
+    last_check: Instant,
+    last_save: Instant,
+}
+
+lazy_static! {
+    // the file is only read when a size is first needed
+    static ref CACHE: Mutex<Option<SizeCache>> = Mutex::new(None);
+}
+
+fn file_path() -> PathBuf {
+    conf::app_dirs().cache_dir().join("dir_sizes")
+}
+
+fn lock_path() -> PathBuf {
+    conf::app_dirs().cache_dir().join("dir_sizes.lock")
+}
+
+fn file_mtime() -> Option<SystemTime> {
+    fs::metadata(file_path()).ok()?.modified().ok()
+}
+
+fn mtime_of(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    modified.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs())
+}
+
+/// parse the content of the file, made of lines
+/// like `size mtime path`
+fn parse(content: &str) -> HashMap<PathBuf, Entry> {
+    let mut entries = HashMap::new();
+    for line in content.lines() {
+        let mut parts = line.splitn(3, ' ');
+        let size = parts.next().and_then(|s| s.parse().ok());
+        let mtime = parts.next().and_then(|s| s.parse().ok());
+        if let (Some(size), Some(mtime), Some(path)) = (size, mtime, parts.next()) {
+            entries.insert(PathBuf::from(path), Entry { mtime, size, used: false });
+        }
+    }
+    entries
+}
+
+fn read_file() -> HashMap<PathBuf, Entry> {
+    fs::read_to_string(file_path())
+        .map(|content| parse(&content))
+        .unwrap_or_default()
+}
+
+/// a lock on the file, held while it's written, so that the instances
+/// writing at the same time don't lose the sizes of the other ones
+struct FileLock {
+    path: PathBuf,
+}
+
+impl FileLock {
+    fn acquire(path: PathBuf) -> io::Result<Self> {
+        let start = Instant::now();
+        loop {
+            match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => {
+                    return Ok(Self { path });
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    if is_stale(&path) {
+                        warn!("removing the stale lock {:?}", &path);
+                        let _ = fs::remove_file(&path);
+                    } else if start.elapsed() > LOCK_WAIT {
+                        return Err(e);
+                    } else {
+                        thread::sleep(Duration::from_millis(20));
+                    }
+                }
+                Err(e) => {
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn is_stale(lock_path: &Path) -> bool {
+    fs::metadata(lock_path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .map_or(false, |age| age > STALE_LOCK_AGE)
+}
+
+impl SizeCache {
+    fn load() -> Self {
+        let now = Instant::now();
+        Self {
+            file_mtime: file_mtime(),
+            entries: read_file(),
+            changed: false,
+            forgotten: Vec::new(),
+            last_check: now,
+            last_save: now,
+        }
+    }
+
+    /// return the size of the directory if it wasn't modified since
+    /// it was computed
+    fn find(&mut self, path: &Path, mtime: u64) -> Option<u64> {
+        let entry = self.entries.get_mut(path)?;
+        if entry.mtime == mtime {
+            entry.used = true;
+            Some(entry.size)
+        } else {
+            None
+        }
+    }
+
+    /// take the entries read from the file, unless the ones already
+    /// known are as recent, or they were forgotten in this session
+    fn merge(&mut self, entries: HashMap<PathBuf, Entry>) {
+        for (path, entry) in entries {
+            if self.forgotten.iter().any(|root| path.starts_with(root)) {
+                continue;
+            }
+            match self.entries.get(&path) {
+                Some(known) if known.mtime >= entry.mtime => {}
+                _ => {
+                    self.entries.insert(path, entry);
+                }
+            }
+        }
+    }
+
+    /// read the file again if another instance wrote it since
+    fn reload_if_changed(&mut self) {
+        if self.last_check.elapsed() < RELOAD_INTERVAL {
+            return;
+        }
+        self.last_check = Instant::now();
+        let file_mtime = file_mtime();
+        if file_mtime != self.file_mtime {
+            debug!("reading the sizes written by another instance");
+            self.file_mtime = file_mtime;
+            self.merge(read_file());
+        }
+    }
+
+    /// write the file, with the entries the other instances
+    /// wrote since it was read. The entries used in this session
+    /// are kept first when there are too many of them.
+    fn write(&mut self) -> io::Result<()> {
+        let file_path = file_path();
+        if let Some(dir) = file_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let _lock = FileLock::acquire(lock_path())?;
+        self.merge(read_file());
+        let mut entries: Vec<(&PathBuf, &Entry)> = self.entries.iter().collect();
+        entries.sort_by_key(|(_, entry)| !entry.used);
+        let mut content = String::new();
+        for (path, entry) in entries.iter().take(MAX_ENTRIES) {
+            if let Some(path) = path.to_str() {
+                if !path.contains('\n') {
+                    content.push_str(&format!("{} {} {}\n", entry.size, entry.mtime, path));
+                }
+            }
+        }
+        // the file is replaced at once, so that it's never read half written
+        let tmp_path = file_path.with_file_name(format!("dir_sizes.{}.tmp", process::id()));
+        fs::write(&tmp_path, content)?;
+        fs::rename(&tmp_path, &file_path)?;
+        self.file_mtime = file_mtime();
+        self.changed = false;
+        self.forgotten.clear();
+        self.last_save = Instant::now();
+        Ok(())
+    }
+}
+
+fn with_cache<R>(f: impl FnOnce(&mut SizeCache) -> R) -> R {
+    let mut cache = CACHE.lock().unwrap();
+    f(cache.get_or_insert_with(SizeCache::load))
+}
+
+/// return the size found for the directory in a previous session,
+/// or by another instance, if it wasn't modified since
+pub fn get(path: &Path) -> Option<u64> {
+    let mtime = mtime_of(path)?;
+    with_cache(|cache| {
+        cache.find(path, mtime).or_else(|| {
+            cache.reload_if_changed();
+            cache.find(path, mtime)
+        })
+    })
+}
+
+/// return the sizes of the directory and of its descendants,
+/// for the ones which weren't modified since
+pub fn get_under(root: &Path) -> Vec<(PathBuf, u64)> {
+    let candidates: Vec<(PathBuf, u64, u64)> = with_cache(|cache| {
+        cache.reload_if_changed();
+        cache
+            .entries
+            .iter()
+            .filter(|(path, _)| path.starts_with(root))
+            .map(|(path, entry)| (path.clone(), entry.mtime, entry.size))
+            .collect()
+    });
+    candidates
+        .into_iter()
+        .filter(|(path, mtime, _)| mtime_of(path) == Some(*mtime))
+        .map(|(path, _, size)| (path, size))
+        .collect()
+}
+
+/// remember the size of the directory, writing the file from
+/// time to time so that the other instances can use the sizes
+pub fn set(path: &Path, size: u64) {
+    let mtime = match mtime_of(path) {
+        Some(mtime) => mtime,
+        None => {
+            return;
+        }
+    };
+    with_cache(|cache| {
+        cache.entries.insert(path.to_path_buf(), Entry { mtime, size, used: true });
+        cache.changed = true;
+        if cache.last_save.elapsed() > SAVE_INTERVAL {
+            if let Err(e) = cache.write() {
+                warn!("failed to write the sizes: {:?}", e);
+                cache.last_save = Instant::now(); // not retried immediately
+            }
+        }
+    });
+}
+
+/// forget the sizes of the directory and of its descendants
+pub fn forget(root: &Path) {
+    with_cache(|cache| {
+        cache.entries.retain(|path, _| !path.starts_with(root));
+        cache.forgotten.push(root.to_path_buf());
+        cache.changed = true;
+    });
+}
+
+/// write the sizes in the file, if they changed since it was last written
+pub fn save() -> io::Result<()> {
+    let mut cache = CACHE.lock().unwrap();
+    match &mut *cache {
+        Some(cache) if cache.changed => cache.write(),
+        _ => Ok(()), // nothing new to save
+    }
+}
+
+#[cfg(test)]
+mod persisted_cache_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_parse() {
+        let entries = parse("4096 1586000000 /home/dys/dev\n12 34 /tmp/with space\nbad line\n");
+        assert_eq!(entries.len(), 2);
+        let entry = &entries[Path::new("/tmp/with space")];
+        assert_eq!(entry.size, 12);
+        assert_eq!(entry.mtime, 34);
+    }
+
+    #[test]
+    fn check_merge() {
+        let mut cache = SizeCache {
+            entries: parse("10 100 /a\n20 200 /b\n"),
+            changed: false,
+            forgotten: vec![PathBuf::from("/c")],
+            file_mtime: None,
+            last_check: Instant::now(),
+            last_save: Instant::now(),
+        };
+        // as written by another instance
+        cache.merge(parse("11 101 /a\n19 199 /b\n30 300 /c/d\n40 400 /e\n"));
+        assert_eq!(cache.find(Path::new("/a"), 101), Some(11));
+        assert_eq!(cache.find(Path::new("/b"), 200), Some(20));
+        assert_eq!(cache.find(Path::new("/c/d"), 300), None);
+        assert_eq!(cache.find(Path::new("/e"), 400), Some(40));
+    }
+}
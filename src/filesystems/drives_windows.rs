@@ -0,0 +1,107 @@
+use {
+    super::{Mount, MountStats},
+    std::{ffi::OsStr, io, mem, os::windows::ffi::OsStrExt, path::PathBuf, ptr},
+    winapi::{
+        shared::minwindef::{DWORD, MAX_PATH, UINT},
+        um::{
+            fileapi::{GetDiskFreeSpaceExW, GetDriveTypeW, GetLogicalDrives, GetVolumeInformationW},
+            winbase::{
+                DRIVE_CDROM, DRIVE_FIXED, DRIVE_NO_ROOT_DIR, DRIVE_RAMDISK, DRIVE_REMOTE,
+                DRIVE_REMOVABLE,
+            },
+            winnt::ULARGE_INTEGER,
+        },
+    },
+};
+
+/// read the drives which can be browsed, in the order of their letters
+/// (a drive without medium, like an empty card reader, isn't listed)
+pub fn read_mounts() -> io::Result<Vec<Mount>> {
+    let mask = unsafe { GetLogicalDrives() };
+    if mask == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok((0..26u8)
+        .filter(|i| mask & (1 << i) != 0)
+        .filter_map(|i| read_drive((b'A' + i) as char))
+        .collect())
+}
+
+fn to_wide(s: &str) -> Vec<u16> {
+    OsStr::new(s).encode_wide().chain(Some(0)).collect()
+}
+
+fn from_wide(buffer: &[u16]) -> String {
+    let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    String::from_utf16_lossy(&buffer[..len])
+}
+
+fn read_drive(letter: char) -> Option<Mount> {
+    let root = format!("{}:\\", letter);
+    let wide_root = to_wide(&root);
+    let drive_type = unsafe { GetDriveTypeW(wide_root.as_ptr()) };
+    if drive_type == DRIVE_NO_ROOT_DIR {
+        return None;
+    }
+    let stats = read_stats(&wide_root)?;
+    let mut label = [0u16; MAX_PATH + 1];
+    let mut fs_name = [0u16; MAX_PATH + 1];
+    let ok = unsafe {
+        GetVolumeInformationW(
+            wide_root.as_ptr(),
+            label.as_mut_ptr(),
+            label.len() as DWORD,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            fs_name.as_mut_ptr(),
+            fs_name.len() as DWORD,
+        )
+    };
+    let (label, fs_type) = if ok != 0 {
+        (from_wide(&label), from_wide(&fs_name))
+    } else {
+        (String::new(), String::new())
+    };
+    // the device column shows the label of the volume, or its kind
+    let device = if label.is_empty() {
+        drive_type_name(drive_type).to_string()
+    } else {
+        label
+    };
+    Some(Mount {
+        device,
+        mount_point: PathBuf::from(root),
+        fs_type,
+        stats,
+    })
+}
+
+fn drive_type_name(drive_type: UINT) -> &'static str {
+    match drive_type {
+        DRIVE_REMOVABLE => "removable disk",
+        DRIVE_FIXED => "local disk",
+        DRIVE_REMOTE => "network drive",
+        DRIVE_CDROM => "optical drive",
+        DRIVE_RAMDISK => "ram disk",
+        _ => "drive",
+    }
+}
+
+fn read_stats(wide_root: &[u16]) -> Option<MountStats> {
+    unsafe {
+        let mut available: ULARGE_INTEGER = mem::zeroed();
+        let mut size: ULARGE_INTEGER = mem::zeroed();
+        let mut free: ULARGE_INTEGER = mem::zeroed();
+        if GetDiskFreeSpaceExW(wide_root.as_ptr(), &mut available, &mut size, &mut free) == 0 {
+            return None;
+        }
+        let size = *size.QuadPart();
+        let free = *free.QuadPart();
+        Some(MountStats {
+            size,
+            used: size - free,
+            available: *available.QuadPart(),
+        })
+    }
+}
@@ -0,0 +1,61 @@
+//! the list of the mounted file systems, with their usage.
+//!
+//! Only Linux and Windows are currently supported: the mounts are
+//! read in /proc/mounts on Linux, and are the drives on Windows.
+
+#[cfg(target_os = "linux")]
+mod mounts_linux;
+
+#[cfg(windows)]
+mod drives_windows;
+
+#[cfg(target_os = "linux")]
+pub use mounts_linux::read_mounts;
+
+#[cfg(windows)]
+pub use drives_windows::read_mounts;
+
+use std::path::PathBuf;
+
+/// the space of a file system, in bytes
+#[derive(Debug, Clone, Copy)]
+pub struct MountStats {
+    pub size: u64,
+    pub used: u64,
+    pub available: u64, // for unprivileged users, may be less than size - used
+}
+
+impl MountStats {
+    /// the part of the space which is used, between 0 and 1
+    pub fn use_share(&self) -> f32 {
+        let usable = self.used + self.available;
+        if usable == 0 {
+            0.0
+        } else {
+            self.used as f32 / usable as f32
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Mount {
+    pub device: String,
+    pub mount_point: PathBuf,
+    pub fs_type: String,
+    pub stats: MountStats,
+}
+
+#[cfg(any(target_os = "linux", windows))]
+pub fn supported() -> bool {
+    true
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+pub fn supported() -> bool {
+    false
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+pub fn read_mounts() -> std::io::Result<Vec<Mount>> {
+    Ok(Vec::new())
+}
@@ -0,0 +1,110 @@
+use {
+    super::{Mount, MountStats},
+    std::{
+        ffi::CString,
+        fs, io, mem,
+        os::unix::ffi::OsStrExt,
+        path::{Path, PathBuf},
+    },
+};
+
+/// read the mounted file systems having a size, in the order
+/// of /proc/mounts (a mount point hidden by a later mount is
+/// only listed once)
+pub fn read_mounts() -> io::Result<Vec<Mount>> {
+    let content = fs::read_to_string("/proc/mounts")?;
+    let mut mounts: Vec<Mount> = Vec::new();
+    for line in content.lines() {
+        let (device, mount_point, fs_type) = match parse_mount_line(line) {
+            Some(parts) => parts,
+            None => {
+                warn!("unexpected line in /proc/mounts: {:?}", line);
+                continue;
+            }
+        };
+        // pseudo file systems (proc, sysfs, cgroup, etc.) have no size
+        let stats = match read_stats(&mount_point) {
+            Some(stats) if stats.size > 0 => stats,
+            _ => continue,
+        };
+        let mount = Mount {
+            device,
+            mount_point,
+            fs_type,
+            stats,
+        };
+        match mounts.iter_mut().find(|m| m.mount_point == mount.mount_point) {
+            Some(previous) => *previous = mount,
+            None => mounts.push(mount),
+        }
+    }
+    Ok(mounts)
+}
+
+/// parse the device, mount point and type of a line of /proc/mounts
+fn parse_mount_line(line: &str) -> Option<(String, PathBuf, String)> {
+    let mut tokens = line.split_whitespace();
+    let device = unescape(tokens.next()?);
+    let mount_point = PathBuf::from(unescape(tokens.next()?));
+    let fs_type = tokens.next()?.to_string();
+    Some((device, mount_point, fs_type))
+}
+
+/// replace the octal escapes of /proc/mounts (eg "\040" for a space)
+fn unescape(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 3 < bytes.len() {
+            let digits = &bytes[i + 1..i + 4];
+            if digits.iter().all(|d| (b'0'..=b'7').contains(d)) {
+                let c = digits.iter().fold(0u32, |c, d| c * 8 + u32::from(d - b'0'));
+                unescaped.push(c as u8);
+                i += 4;
+                continue;
+            }
+        }
+        unescaped.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&unescaped).to_string()
+}
+
+fn read_stats(mount_point: &Path) -> Option<MountStats> {
+    let c_path = CString::new(mount_point.as_os_str().as_bytes()).ok()?;
+    unsafe {
+        let mut statvfs: libc::statvfs = mem::zeroed();
+        if libc::statvfs(c_path.as_ptr(), &mut statvfs) != 0 {
+            return None;
+        }
+        let fragment_size = statvfs.f_frsize as u64;
+        let size = statvfs.f_blocks as u64 * fragment_size;
+        let free = statvfs.f_bfree as u64 * fragment_size;
+        let available = statvfs.f_bavail as u64 * fragment_size;
+        Some(MountStats {
+            size,
+            used: size - free,
+            available,
+        })
+    }
+}
+
+#[cfg(test)]
+mod mounts_linux_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_mount_line_parsing() {
+        assert_eq!(
+            parse_mount_line("/dev/sda2 / ext4 rw,relatime 0 0"),
+            Some(("/dev/sda2".to_string(), PathBuf::from("/"), "ext4".to_string())),
+        );
+        assert_eq!(
+            parse_mount_line("/dev/sdb1 /media/my\\040disk vfat rw 0 0"),
+            Some(("/dev/sdb1".to_string(), PathBuf::from("/media/my disk"), "vfat".to_string())),
+        );
+        assert_eq!(parse_mount_line("garbage"), None);
+    }
+}
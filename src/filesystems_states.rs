@@ -0,0 +1,299 @@
+use {
+    crate::{
+        app_context::AppContext,
+        app_state::{AppState, AppStateCmdResult},
+        browser_states::BrowserState,
+        commands::{Action, Command},
+        errors::ProgramError,
+        file_sizes::FileSize,
+        filesystems::{self, Mount},
+        filesystems_verbs,
+        help_content::ScreenHelp,
+        help_states::HelpState,
+        screens::Screen,
+        status::Status,
+        task_sync::Dam,
+        tree_options::TreeOptions,
+        verb_store::PrefixSearchResult,
+        verbs::VerbExecutor,
+    },
+    crossterm::{
+        terminal::{Clear, ClearType},
+        QueueableCommand,
+    },
+    minimad::Composite,
+    std::{
+        io::Write,
+        path::{Path, PathBuf},
+    },
+    termimad::ProgressBar,
+};
+
+/// an application state listing the mounted file systems, so
+/// that the user may see their usage and browse one of them
+pub struct FilesystemState {
+    mounts: Vec<Mount>,
+    tree_options: TreeOptions, // the options of the tree opened on a mount point
+    pub selection: usize,
+    pub scroll: usize,
+}
+
+impl FilesystemState {
+    pub fn new(tree_options: TreeOptions) -> Result<FilesystemState, ProgramError> {
+        let mounts = filesystems::read_mounts()?;
+        Ok(FilesystemState {
+            mounts,
+            tree_options,
+            selection: 0,
+            scroll: 0,
+        })
+    }
+
+    /// return the number of lines available for the mounts
+    pub fn page_height(screen: &Screen) -> usize {
+        (screen.height as usize).max(3) - 3
+    }
+
+    pub fn mounts_count(&self) -> usize {
+        self.mounts.len()
+    }
+
+    pub fn selected_path(&self) -> Option<&Path> {
+        self.mounts
+            .get(self.selection)
+            .map(|mount| mount.mount_point.as_path())
+    }
+
+    pub fn move_selection(&mut self, dy: i32, page_height: usize) {
+        let len = self.mounts.len() as i32;
+        if len == 0 {
+            return;
+        }
+        let selection = (self.selection as i32 + dy).max(0).min(len - 1) as usize;
+        self.selection = selection;
+        if selection < self.scroll {
+            self.scroll = selection;
+        } else if selection >= self.scroll + page_height {
+            self.scroll = selection + 1 - page_height;
+        }
+    }
+
+    /// open a browser state on the mount point of the selected
+    /// file system
+    pub fn open_selection(&self, screen: &Screen, con: &AppContext) -> AppStateCmdResult {
+        match self.selected_path() {
+            Some(path) => AppStateCmdResult::from_optional_state(
+                BrowserState::new(
+                    path.to_path_buf(),
+                    self.tree_options.clone(),
+                    screen,
+                    con,
+                    &Dam::unlimited(),
+                ),
+                Command::new(),
+            ),
+            None => AppStateCmdResult::DisplayError("no file system selected".to_string()),
+        }
+    }
+}
+
+impl AppState for FilesystemState {
+    fn help(&self) -> &'static ScreenHelp {
+        &filesystems_verbs::HELP
+    }
+
+    fn has_pending_task(&self) -> bool {
+        false
+    }
+
+    fn can_execute(&self, _verb_index: usize, _con: &AppContext) -> bool {
+        true
+    }
+
+    fn apply(
+        &mut self,
+        cmd: &mut Command,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<AppStateCmdResult, ProgramError> {
+        let page_height = FilesystemState::page_height(screen);
+        Ok(match &cmd.action {
+            Action::Back => AppStateCmdResult::PopState,
+            Action::Click(_, y) => {
+                let y = *y as usize;
+                if y > 0 && y <= page_height && self.scroll + y - 1 < self.mounts.len() {
+                    self.selection = self.scroll + y - 1;
+                }
+                AppStateCmdResult::Keep
+            }
+            Action::DoubleClick(_, y) => {
+                if *y > 0 && self.selection + 1 == self.scroll + *y as usize {
+                    self.open_selection(screen, con)
+                } else {
+                    AppStateCmdResult::Keep
+                }
+            }
+            Action::Help => {
+                AppStateCmdResult::NewState(
+                    Box::new(HelpState::new(screen, con, &filesystems_verbs::HELP)),
+                    Command::new(),
+                )
+            }
+            Action::MoveSelection(dy) | Action::Scroll(dy) => {
+                self.move_selection(*dy, page_height);
+                AppStateCmdResult::Keep
+            }
+            Action::OpenSelection => self.open_selection(screen, con),
+            Action::Resize(w, h) => {
+                screen.set_terminal_size(*w, *h, con);
+                AppStateCmdResult::Keep
+            }
+            Action::VerbIndex(index) => {
+                let verb = &con.verb_store.verbs[*index];
+                self.execute_verb(verb, &verb.invocation, screen, con)?
+            }
+            Action::VerbInvocate(invocation) => match con.verb_store.search(&invocation.name) {
+                PrefixSearchResult::Match(verb) => {
+                    self.execute_verb(verb, &invocation, screen, con)?
+                }
+                _ => AppStateCmdResult::verb_not_found(&invocation.name),
+            },
+            _ => AppStateCmdResult::Keep,
+        })
+    }
+
+    /// read the mounts again, as their usage may have changed
+    fn refresh(&mut self, screen: &Screen, _con: &AppContext) -> Command {
+        match filesystems::read_mounts() {
+            Ok(mounts) => {
+                self.mounts = mounts;
+                let page_height = FilesystemState::page_height(screen);
+                self.move_selection(0, page_height);
+            }
+            Err(e) => {
+                warn!("failed to read the mounts: {:?}", e);
+            }
+        }
+        Command::new()
+    }
+
+    fn do_pending_task(&mut self, _screen: &mut Screen, _con: &AppContext, _dam: &mut Dam) {
+        unreachable!();
+    }
+
+    fn display(
+        &mut self,
+        mut w: &mut dyn Write,
+        screen: &Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        let page_height = FilesystemState::page_height(screen);
+        let device_width = self
+            .mounts
+            .iter()
+            .map(|m| m.device.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(6)
+            .min(30);
+        let type_width = self
+            .mounts
+            .iter()
+            .map(|m| m.fs_type.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(4);
+        screen.goto_clear(&mut w, 0, 0)?;
+        screen.skin.default.queue_bg(&mut w)?;
+        screen.skin.help_headers.queue(
+            &mut w,
+            format!(
+                "{:dw$} {:tw$}  size  used avail  use%            mount point",
+                "device",
+                "type",
+                dw = device_width,
+                tw = type_width,
+            ),
+        )?;
+        screen.clear_line(&mut w)?;
+        for y in 0..page_height {
+            screen.goto(&mut w, 0, y as u16 + 1)?;
+            let idx = self.scroll + y;
+            let selected = idx == self.selection;
+            if let Some(mount) = self.mounts.get(idx) {
+                cond_bg!(device_style, screen, selected, screen.skin.file);
+                cond_bg!(type_style, screen, selected, screen.skin.tree);
+                cond_bg!(size_style, screen, selected, screen.skin.file);
+                cond_bg!(share_style, screen, selected, screen.skin.count);
+                cond_bg!(mount_point_style, screen, selected, screen.skin.directory);
+                let device: String = mount.device.chars().take(device_width).collect();
+                device_style.queue(&mut w, format!("{:dw$} ", device, dw = device_width))?;
+                type_style.queue(&mut w, format!("{:tw$} ", mount.fs_type, tw = type_width))?;
+                let stats = mount.stats;
+                size_style.queue(
+                    &mut w,
+                    format!(
+                        "{:>5} {:>5} {:>5} ",
+                        FileSize::new(stats.size, false).to_string(),
+                        FileSize::new(stats.used, false).to_string(),
+                        FileSize::new(stats.available, false).to_string(),
+                    ),
+                )?;
+                let share = stats.use_share();
+                share_style.queue(&mut w, format!("{:>4.0}% ", 100.0 * share))?;
+                share_style.queue(&mut w, format!("{:<10} ", ProgressBar::new(share, 10)))?;
+                mount_point_style.queue(&mut w, mount.mount_point.to_string_lossy())?;
+            } else if idx == 0 && self.mounts.is_empty() {
+                screen.skin.tree.queue_str(&mut w, "No mounted file system found")?;
+            }
+            if selected && idx < self.mounts.len() {
+                screen.skin.selected_line.queue_bg(&mut w)?;
+            } else {
+                screen.skin.default.queue_bg(&mut w)?;
+            }
+            w.queue(Clear(ClearType::UntilNewLine))?;
+        }
+        Ok(())
+    }
+
+    fn write_status(
+        &self,
+        mut w: &mut dyn Write,
+        cmd: &Command,
+        screen: &Screen,
+        con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        match &cmd.action {
+            Action::VerbEdit(invocation) if !invocation.name.is_empty() => {
+                match con.verb_store.search(&invocation.name) {
+                    PrefixSearchResult::Match(verb) => verb.write_status(
+                        &mut w,
+                        None,
+                        self.selected_path().map_or_else(PathBuf::new, Path::to_path_buf),
+                        None,
+                        invocation,
+                        screen,
+                    ),
+                    _ => Status::from_error(mad_inline!("No matching verb"))
+                        .display(&mut w, screen),
+                }
+            }
+            _ => {
+                let hint = filesystems_verbs::HELP.keys_hint();
+                Status::from_message(Composite::from_inline(&hint)).display(&mut w, screen)
+            }
+        }
+    }
+
+    /// there's no meaningful flags here
+    fn write_flags(
+        &self,
+        mut w: &mut dyn Write,
+        screen: &mut Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        screen.skin.default.queue_bg(&mut w)?;
+        w.queue(Clear(ClearType::UntilNewLine))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+//! this modules defines the execution of verbs on the file systems screen
+
+use crate::{
+    app_context::AppContext,
+    app_state::AppStateCmdResult,
+    commands::Command,
+    errors::ProgramError,
+    external,
+    filesystems_states::FilesystemState,
+    help_content::{ScreenHelp, ScreenKey},
+    help_states::HelpState,
+    screens::Screen,
+    verb_invocation::VerbInvocation,
+    verbs::{Verb, VerbExecutor},
+};
+
+/// the help of the file systems screen
+pub static HELP: ScreenHelp = ScreenHelp {
+    name: "file systems screen",
+    description: "This screen lists the mounted file systems, with their usage.",
+    keys: &[
+        ScreenKey::hinted("esc", "get back to the tree, or clear the filter", "to get back"),
+        ScreenKey::new("↑ ↓", "move the selection"),
+        ScreenKey::hinted(
+            "enter",
+            "browse the selected file system",
+            "to browse the selected file system",
+        ),
+        ScreenKey::new("?", "display this help"),
+    ],
+    builtins: Some(&[
+        ":back", ":filesystems", ":focus", ":open_stay", ":help", ":line_down", ":line_up",
+        ":page_down", ":page_up", ":print_path", ":quit", ":refresh", ":select_first",
+        ":select_last",
+    ]),
+    external_verbs: true,
+};
+
+impl VerbExecutor for FilesystemState {
+    fn execute_verb(
+        &mut self,
+        verb: &Verb,
+        invocation: &VerbInvocation,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<AppStateCmdResult, ProgramError> {
+        if let Some(err) = verb.match_error(invocation) {
+            return Ok(AppStateCmdResult::DisplayError(err));
+        }
+        if let Some(err) = HELP.unlisted_error(verb) {
+            return Ok(AppStateCmdResult::DisplayError(err));
+        }
+        let page_height = FilesystemState::page_height(screen);
+        let selected_path = match self.selected_path() {
+            Some(path) => path.to_path_buf(),
+            None => {
+                return Ok(match verb.execution.as_ref() {
+                    ":back" => AppStateCmdResult::PopState,
+                    ":help" => AppStateCmdResult::NewState(
+                        Box::new(HelpState::new(screen, con, &HELP)),
+                        Command::new(),
+                    ),
+                    ":quit" => AppStateCmdResult::Quit,
+                    _ => AppStateCmdResult::DisplayError("no file system selected".to_string()),
+                });
+            }
+        };
+        Ok(match verb.execution.as_ref() {
+            ":back" => AppStateCmdResult::PopState,
+            ":filesystems" => AppStateCmdResult::RefreshState { clear_cache: false },
+            ":focus" | ":open_stay" => self.open_selection(screen, con),
+            ":help" => AppStateCmdResult::NewState(
+                Box::new(HelpState::new(screen, con, &HELP)),
+                Command::new(),
+            ),
+            ":line_down" => {
+                self.move_selection(1, page_height);
+                AppStateCmdResult::Keep
+            }
+            ":line_up" => {
+                self.move_selection(-1, page_height);
+                AppStateCmdResult::Keep
+            }
+            ":page_down" => {
+                self.move_selection(page_height as i32, page_height);
+                AppStateCmdResult::Keep
+            }
+            ":page_up" => {
+                self.move_selection(-(page_height as i32), page_height);
+                AppStateCmdResult::Keep
+            }
+            ":print_path" => external::print_path(&selected_path, con)?,
+            ":quit" => AppStateCmdResult::Quit,
+            ":refresh" => AppStateCmdResult::RefreshState { clear_cache: false },
+            ":select_first" => {
+                self.move_selection(-(self.selection as i32), page_height);
+                AppStateCmdResult::Keep
+            }
+            ":select_last" => {
+                let len = self.mounts_count() as i32;
+                self.move_selection(len, page_height);
+                AppStateCmdResult::Keep
+            }
+            _ if verb.execution.starts_with(':') => AppStateCmdResult::Keep,
+            _ => verb.to_cmd_result(&selected_path, None, &invocation.args, screen, con)?,
+        })
+    }
+}
@@ -0,0 +1,238 @@
+//! deterministic synthetic file trees, built in a temporary directory,
+//! for the benchmarks and tests of broot and of the programs embedding
+//! it. Only available with the `fixtures` feature.
+//!
+//! The same spec, with the same seed, always gives the same tree:
+//!
+//! ```no_run
+//! use broot::fixtures::{Fixture, FixtureSpec};
+//!
+//! let fixture = Fixture::build(&FixtureSpec {
+//!     depth: 3,
+//!     fan_out: 4,
+//!     ..FixtureSpec::default()
+//! }).unwrap();
+//! println!("{} files in {:?}", fixture.nb_files, fixture.root());
+//! // the directory is removed when the fixture is dropped
+//! ```
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// the parts the names are made of
+const SYLLABLES: &[&str] = &[
+    "ba", "ko", "ri", "tem", "lu", "sa", "no", "vi", "pe", "dra", "mo", "chi",
+];
+
+/// the extension of the files ignored when the tree has gitignore files
+const IGNORED_EXTENSION: &str = "log";
+
+static NEXT_FIXTURE_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// the description of a synthetic tree
+#[derive(Debug, Clone)]
+pub struct FixtureSpec {
+    pub seed: u64,
+    pub depth: usize,         // number of levels of directories under the root
+    pub fan_out: usize,       // number of subdirectories of a directory
+    pub files_per_dir: usize, // number of files of a directory
+    pub name_len: (usize, usize), // min and max numbers of syllables of a name
+    pub extensions: Vec<String>, // the extensions of the files, picked at random
+    pub hidden_ratio: f64,    // part of the names starting with a dot
+    pub max_file_size: u64,   // in bytes, the sizes being picked at random below
+    pub gitignore: bool,      // whether the tree is a git repository with gitignore files
+}
+
+impl Default for FixtureSpec {
+    fn default() -> Self {
+        FixtureSpec {
+            seed: 1,
+            depth: 2,
+            fan_out: 3,
+            files_per_dir: 5,
+            name_len: (1, 4),
+            extensions: vec!["rs".to_string(), "md".to_string(), "txt".to_string()],
+            hidden_ratio: 0.1,
+            max_file_size: 4096,
+            gitignore: false,
+        }
+    }
+}
+
+/// a xorshift generator: not good for anything serious, but
+/// small, fast, and giving the same numbers on all platforms
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng((seed ^ 0x9E37_79B9_7F4A_7C15) | 1) // the state must not be 0
+    }
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+    /// a number in `[min, max]`
+    fn between(&mut self, min: u64, max: u64) -> u64 {
+        if max <= min {
+            min
+        } else {
+            min + self.next() % (max - min + 1)
+        }
+    }
+    /// true with the given probability
+    fn chance(&mut self, ratio: f64) -> bool {
+        (self.next() % 1_000_000) as f64 / 1_000_000.0 < ratio
+    }
+}
+
+/// a synthetic tree, removed on drop
+#[derive(Debug)]
+pub struct Fixture {
+    root: PathBuf,
+    pub nb_dirs: usize, // not counting the root
+    pub nb_files: usize,
+    pub total_size: u64, // the sum of the sizes of the files
+}
+
+impl Fixture {
+    /// build the tree in a new temporary directory
+    pub fn build(spec: &FixtureSpec) -> io::Result<Fixture> {
+        let root = std::env::temp_dir().join(format!(
+            "broot-fixture-{}-{}",
+            process::id(),
+            NEXT_FIXTURE_ID.fetch_add(1, Ordering::Relaxed),
+        ));
+        fs::create_dir_all(&root)?;
+        let mut fixture = Fixture {
+            root,
+            nb_dirs: 0,
+            nb_files: 0,
+            total_size: 0,
+        };
+        if spec.gitignore {
+            fs::create_dir(fixture.root.join(".git"))?;
+        }
+        let mut rng = Rng::new(spec.seed);
+        let root = fixture.root.clone();
+        // on error the fixture is dropped, which removes the partial tree
+        fixture.fill(&root, spec, spec.depth, &mut rng)?;
+        Ok(fixture)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn fill(
+        &mut self,
+        dir: &Path,
+        spec: &FixtureSpec,
+        depth: usize,
+        rng: &mut Rng,
+    ) -> io::Result<()> {
+        if spec.gitignore {
+            fs::write(dir.join(".gitignore"), format!("*.{}\n", IGNORED_EXTENSION))?;
+        }
+        for idx in 0..spec.files_per_dir {
+            let extension = if spec.gitignore && rng.chance(0.25) {
+                IGNORED_EXTENSION
+            } else if spec.extensions.is_empty() {
+                ""
+            } else {
+                &spec.extensions[rng.between(0, spec.extensions.len() as u64 - 1) as usize]
+            };
+            let mut name = random_name(spec, idx, rng);
+            if !extension.is_empty() {
+                name.push('.');
+                name.push_str(extension);
+            }
+            let size = rng.between(0, spec.max_file_size);
+            let mut file = fs::File::create(dir.join(name))?;
+            file.write_all(&vec![b'b'; size as usize])?;
+            self.nb_files += 1;
+            self.total_size += size;
+        }
+        if depth > 0 {
+            for idx in 0..spec.fan_out {
+                let sub_dir = dir.join(random_name(spec, idx, rng));
+                fs::create_dir(&sub_dir)?;
+                self.nb_dirs += 1;
+                self.fill(&sub_dir, spec, depth - 1, rng)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_dir_all(&self.root) {
+            warn!("failed to remove the fixture {:?}: {:?}", &self.root, e);
+        }
+    }
+}
+
+/// build a name, made unique in its directory by the index
+fn random_name(spec: &FixtureSpec, idx: usize, rng: &mut Rng) -> String {
+    let mut name = String::new();
+    if rng.chance(spec.hidden_ratio) {
+        name.push('.');
+    }
+    let nb_syllables = rng.between(spec.name_len.0 as u64, spec.name_len.1 as u64).max(1);
+    for _ in 0..nb_syllables {
+        name.push_str(SYLLABLES[rng.between(0, SYLLABLES.len() as u64 - 1) as usize]);
+    }
+    name.push_str(&format!("_{}", idx));
+    name
+}
+
+#[cfg(test)]
+mod fixtures_tests {
+
+    use super::*;
+
+    /// the paths and sizes of the files of the tree, relative to its root
+    fn listing(fixture: &Fixture) -> Vec<(PathBuf, u64)> {
+        let mut listing = Vec::new();
+        let mut dirs = vec![fixture.root().to_path_buf()];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(&dir).unwrap() {
+                let entry = entry.unwrap();
+                let path = entry.path();
+                let len = entry.metadata().unwrap().len();
+                if path.is_dir() {
+                    dirs.push(path.clone());
+                }
+                listing.push((path.strip_prefix(fixture.root()).unwrap().to_path_buf(), len));
+            }
+        }
+        listing.sort();
+        listing
+    }
+
+    #[test]
+    fn check_determinism() {
+        let spec = FixtureSpec {
+            gitignore: true,
+            ..FixtureSpec::default()
+        };
+        let a = Fixture::build(&spec).unwrap();
+        let b = Fixture::build(&spec).unwrap();
+        assert_ne!(a.root(), b.root());
+        assert_eq!(a.nb_dirs, 3 + 9);
+        assert_eq!(a.nb_files, 5 * (1 + 3 + 9));
+        assert_eq!(listing(&a), listing(&b));
+        let root = a.root().to_path_buf();
+        drop(a);
+        assert!(!root.exists());
+    }
+}
@@ -2,31 +2,47 @@
 //!  no link from a child to its parent or from a parent to its children.
 use {
     crate::{
+        app_context::AppContext,
+        collation,
+        date_source::DateSource,
+        deep_dates,
         errors,
         file_sizes::FileSize,
+        git_ignore::IgnoreAudit,
         git_status::{
             LineGitStatus,
+            LineStatusComputer,
             TreeGitStatus,
         },
+        line_counts,
+        notes,
+        remote::RemoteEntry,
         task_sync::{
             ComputationResult,
         },
         selection_type::SelectionType,
+        sort::Sort,
+        tags,
         task_sync::Dam,
-        tree_build::TreeBuilder,
+        tree_build::{TreeBuilder, WalkCache},
         tree_options::TreeOptions,
     },
+    git2::Repository,
     std::{
         cmp::{self, Ord, Ordering, PartialOrd},
+        collections::{HashMap, HashSet},
         fs,
+        iter,
         mem,
         path::{Path, PathBuf},
+        sync::Arc,
         time::SystemTime,
     },
 };
 
 #[cfg(unix)]
 use {
+    crate::permissions,
     std::os::unix::fs::MetadataExt,
     umask::Mode,
 };
@@ -43,6 +59,21 @@ pub enum LineType {
     Pruning,               // a "xxx unlisted" line
 }
 
+/// how a line changed since the previous refresh of the tree
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineChange {
+    Added,
+    Resized(i64), // the difference of size, in bytes
+}
+
+/// the metadata of the file of a line, which comes either
+/// from the local file system or from a remote server
+#[derive(Debug, Clone)]
+pub enum LineMetadata {
+    Local(fs::Metadata),
+    Remote(RemoteEntry),
+}
+
 /// a line in the representation of the file hierarchy
 #[derive(Debug, Clone)]
 pub struct TreeLine {
@@ -52,12 +83,20 @@ pub struct TreeLine {
     pub path: PathBuf,
     pub line_type: LineType,
     pub has_error: bool,
+    pub is_loop: bool, // a directory which isn't entered as it's one of its ancestors
+    pub in_archive: bool, // the path is virtual, made of the archive path and the path in it
     pub nb_kept_children: usize,
     pub unlisted: usize, // number of not listed children (Dir) or brothers (Pruning)
+    pub children_count: Option<usize>, // number of listable children, only for directories
     pub score: i32,      // 0 if there's no pattern
     pub size: Option<FileSize>, // None when not measured
-    pub metadata: fs::Metadata,
+    pub size_is_stale: bool, // true when the size comes from a previous session and wasn't checked
+    pub deep_date: ComputationResult<SystemTime>, // only computed for directories
+    pub line_count: ComputationResult<usize>, // only computed for text files
+    pub metadata: LineMetadata,
     pub git_status: Option<LineGitStatus>,
+    pub ignore_audit: Option<IgnoreAudit>, // only when auditing the ignore rules
+    pub change: Option<LineChange>, // since the previous refresh
 }
 
 #[derive(Debug, Clone)]
@@ -67,8 +106,16 @@ pub struct Tree {
     pub options: TreeOptions,
     pub scroll: i32, // the number of lines at the top hidden because of scrolling
     pub nb_gitignored: u32, // number of times a gitignore pattern excluded a file
+    pub nb_unreadable: u32, // number of directories which couldn't be read
+    pub sizes_interrupted: bool, // true when the user stopped the computation of dir sizes
+    pub deep_dates_interrupted: bool, // true when the user stopped the computation of deep dates
+    pub ancestor_sizes: Vec<(PathBuf, FileSize)>, // the already known sizes of the root's ancestors
     pub total_search: bool, // whether the search was made on all children
     pub git_status: ComputationResult<TreeGitStatus>,
+    pub walk: Option<Arc<WalkCache>>, // the directories read by the search, for a longer pattern
+    pub build_time: SystemTime, // when the lines were read
+    pub removed: Vec<PathBuf>, // the lines which disappeared at the last refresh
+    pub line_git_statuses_pending: bool, // true until the git statuses of the lines are read
 }
 
 impl TreeLine {
@@ -93,14 +140,53 @@ impl TreeLine {
     }
     pub fn is_of(&self, selection_type: SelectionType) -> bool {
         match selection_type {
-            SelectionType::Any => true,
+            SelectionType::Any | SelectionType::None => true,
             SelectionType::File => self.is_file(),
             SelectionType::Directory => self.is_dir(),
         }
     }
+    /// tell whether the file is on a remote server
+    pub fn is_remote(&self) -> bool {
+        match &self.metadata {
+            LineMetadata::Remote(_) => true,
+            _ => false,
+        }
+    }
     #[cfg(unix)]
     pub fn mode(&self) -> Mode {
-        Mode::from(self.metadata.mode())
+        match &self.metadata {
+            LineMetadata::Local(metadata) => Mode::from(metadata.mode()),
+            LineMetadata::Remote(entry) => Mode::from(entry.mode),
+        }
+    }
+    /// return the names of the owner and of the group. The ids of
+    /// a remote server can't be resolved, so they're displayed as is
+    #[cfg(unix)]
+    pub fn owner_names(&self) -> (String, String) {
+        match &self.metadata {
+            LineMetadata::Local(metadata) => (
+                permissions::user_name(metadata.uid()),
+                permissions::group_name(metadata.gid()),
+            ),
+            LineMetadata::Remote(entry) => {
+                let id = |id: Option<u32>| id.map_or_else(|| "?".to_string(), |id| id.to_string());
+                (id(entry.uid), id(entry.gid))
+            }
+        }
+    }
+    /// the device and inode of the file, which are kept when it's renamed
+    #[cfg(unix)]
+    pub fn identity(&self) -> Option<(u64, u64)> {
+        match &self.metadata {
+            LineMetadata::Local(metadata) if !self.in_archive => {
+                Some((metadata.dev(), metadata.ino()))
+            }
+            _ => None,
+        }
+    }
+    #[cfg(not(unix))]
+    pub fn identity(&self) -> Option<(u64, u64)> {
+        None
     }
     pub fn is_exe(&self) -> bool {
         #[cfg(unix)]
@@ -127,11 +213,39 @@ impl TreeLine {
             _ => self.path.clone(),
         }
     }
+    /// return the size of a file, as read when building the tree
+    pub fn file_len(&self) -> Option<u64> {
+        match (&self.line_type, &self.metadata) {
+            (LineType::File, LineMetadata::Local(metadata)) => Some(metadata.len()),
+            (LineType::File, LineMetadata::Remote(entry)) => Some(entry.size),
+            _ => None,
+        }
+    }
     /// return the last modification date, if it makes sense
     pub fn modified(&self) -> Option<SystemTime> {
         match &self.line_type {
             LineType::Pruning => None,
-            _ => self.metadata.modified().ok(),
+            _ => match &self.metadata {
+                LineMetadata::Local(metadata) => metadata.modified().ok(),
+                LineMetadata::Remote(entry) => entry.modified,
+            },
+        }
+    }
+    /// return the date to display or sort with, if it makes sense
+    pub fn date(&self, date_source: DateSource) -> Option<SystemTime> {
+        match &self.line_type {
+            LineType::Pruning => None,
+            // a SFTP server only gives the modification date
+            LineType::Dir if date_source == DateSource::Deep && !self.is_remote() => {
+                match self.deep_date {
+                    ComputationResult::Done(date) => Some(date),
+                    _ => None,
+                }
+            }
+            _ => match &self.metadata {
+                LineMetadata::Local(metadata) => date_source.date(metadata),
+                LineMetadata::Remote(entry) => entry.modified,
+            },
         }
     }
 }
@@ -145,7 +259,8 @@ impl Eq for TreeLine {}
 
 impl Ord for TreeLine {
     // paths are sorted in a complete ignore case way
-    // (A<a<B<b)
+    // (A<a<B<b), or with the collation of the locale
+    // (see the collation module)
     fn cmp(&self, other: &TreeLine) -> Ordering {
         let mut sci = self.path.components();
         let mut oci = other.path.components();
@@ -156,11 +271,7 @@ impl Ord for TreeLine {
                         Some(oc) => {
                             let scs = sc.as_os_str().to_string_lossy();
                             let ocs = oc.as_os_str().to_string_lossy();
-                            let lower_ordering = scs.to_lowercase().cmp(&ocs.to_lowercase());
-                            if lower_ordering != Ordering::Equal {
-                                return lower_ordering;
-                            }
-                            let ordering = scs.cmp(&ocs);
+                            let ordering = collation::compare_names(&scs, &ocs);
                             if ordering != Ordering::Equal {
                                 return ordering;
                             }
@@ -189,35 +300,145 @@ impl PartialOrd for TreeLine {
 }
 
 impl Tree {
-    pub fn refresh(&mut self, page_height: usize) -> Result<(), errors::TreeBuildError> {
+    pub fn refresh(
+        &mut self,
+        page_height: usize,
+        con: &AppContext,
+    ) -> Result<(), errors::TreeBuildError> {
         let builder = TreeBuilder::from(
             	self.root().to_path_buf(),
             	self.options.clone(),
             	page_height,
+            	con,
             )?;
         let mut tree = builder.build(
             	false, // on refresh we always do a non total search
                 &Dam::unlimited(),
             ).unwrap(); // should not fail
-        // we save the old selection to try restore it
-        let selected_path = self.selected_line().path.to_path_buf();
+        let renamed = tree.mark_changes(&self.lines, self.build_time);
+        for (old_path, new_path) in &renamed {
+            if let Err(e) = tags::move_path(old_path, new_path) {
+                warn!("moving the tags of {:?} failed: {:?}", old_path, e);
+            }
+            if let Err(e) = notes::move_path(old_path, new_path) {
+                warn!("moving the note of {:?} failed: {:?}", old_path, e);
+            }
+        }
+        // we save the old selection to try restore it, under its new
+        // name if it was renamed
+        let mut selected_path = self.selected_line().path.to_path_buf();
+        if let Some((_, new_path)) = renamed.iter().find(|(old, _)| *old == selected_path) {
+            selected_path = new_path.clone();
+        }
+        let previous_selection = self.selection;
         mem::swap(&mut self.lines, &mut tree.lines);
+        self.walk = tree.walk; // the old one may list removed files
+        self.build_time = tree.build_time;
+        self.removed = tree.removed;
+        // the git statuses are read again, after the display
+        self.line_git_statuses_pending = tree.line_git_statuses_pending;
+        self.git_status = tree.git_status;
         self.scroll = 0;
-        if !self.try_select_path(&selected_path) {
-            if self.selection >= self.lines.len() {
-                self.selection = 0;
-            }
+        if !self.try_select_path(&selected_path)
+            && !self.try_select_nearest(&tree.lines, previous_selection)
+            && self.selection >= self.lines.len()
+        {
+            self.selection = 0;
         }
         self.make_selection_visible(page_height as i32);
         Ok(())
     }
 
+    /// compare the lines with the ones of the previous snapshot of the
+    /// tree, to show what changed. A line which wasn't displayed is only
+    /// considered as added when its directory changed since the snapshot,
+    /// as it may just have been unlisted before.
+    ///
+    /// A line whose path disappeared while the same file (same device and
+    /// inode) appeared elsewhere isn't a removal but a rename: the pairs
+    /// of old and new paths are returned.
+    fn mark_changes(
+        &mut self,
+        previous: &[TreeLine],
+        previous_time: SystemTime,
+    ) -> Vec<(PathBuf, PathBuf)> {
+        let previous_lines: HashMap<&Path, &TreeLine> = previous
+            .iter()
+            .filter(|line| line.is_selectable())
+            .map(|line| (line.path.as_path(), line))
+            .collect();
+        let changed_dirs: HashSet<PathBuf> = self
+            .lines
+            .iter()
+            .filter(|line| line.is_dir() && line.modified().map_or(false, |d| d > previous_time))
+            .map(|line| line.path.clone())
+            .collect();
+        let gone: Vec<&TreeLine> = {
+            let paths: HashSet<&Path> =
+                self.lines.iter().map(|line| line.path.as_path()).collect();
+            previous
+                .iter()
+                .filter(|line| line.is_selectable() && !line.in_archive && !line.is_remote())
+                .filter(|line| !paths.contains(line.path.as_path()))
+                .filter(|line| fs::symlink_metadata(&line.path).is_err())
+                .collect()
+        };
+        let gone_by_identity: HashMap<(u64, u64), &TreeLine> = gone
+            .iter()
+            .filter_map(|line| line.identity().map(|identity| (identity, *line)))
+            .collect();
+        let mut renamed = Vec::new();
+        for line in self.lines.iter_mut().skip(1) {
+            if !line.is_selectable() {
+                continue;
+            }
+            line.change = match previous_lines.get(line.path.as_path()) {
+                Some(old) => match (old.file_len(), line.file_len()) {
+                    (Some(old_len), Some(len)) if old_len != len => {
+                        Some(LineChange::Resized(len as i64 - old_len as i64))
+                    }
+                    _ => None,
+                },
+                None => match line.identity().and_then(|id| gone_by_identity.get(&id)) {
+                    Some(old) => {
+                        renamed.push((old.path.clone(), line.path.clone()));
+                        None
+                    }
+                    None => line
+                        .path
+                        .parent()
+                        .filter(|dir| changed_dirs.contains(*dir))
+                        .map(|_| LineChange::Added),
+                },
+            };
+        }
+        self.removed = gone
+            .iter()
+            .filter(|line| !renamed.iter().any(|(old, _)| *old == line.path))
+            .map(|line| line.path.clone())
+            .collect();
+        renamed
+    }
+
+    /// forget the changes of the last refresh, once they were seen
+    pub fn clear_changes(&mut self) {
+        for line in self.lines.iter_mut() {
+            line.change = None;
+        }
+        self.removed.clear();
+    }
+
     /// do what must be done after line additions or removals:
     /// - sort the lines
     /// - compute left branchs
     pub fn after_lines_changed(&mut self) {
         // we sort the lines (this is mandatory to avoid crashes)
         self.lines[1..].sort();
+        // when there's a sort mode, there's only one level, which
+        // we can order as we want
+        if self.options.sort.is_some() {
+            self.sort_siblings();
+        }
 
         let mut best_index = 0; // index of the line with the best score
         for i in 1..self.lines.len() {
@@ -315,7 +536,10 @@ impl Tree {
         }
     }
     pub fn try_scroll(&mut self, dy: i32, page_height: i32) {
-        self.scroll = (self.scroll + dy).max(0).min(self.lines.len() as i32 - 5);
+        // a tree fitting the page isn't scrolled
+        if page_height < self.lines.len() as i32 {
+            self.scroll = (self.scroll + dy).min(self.lines.len() as i32 - 5).max(0);
+        }
         self.select_visible_line(page_height);
     }
     /// try to select a line (works if y+scroll falls on a selectable line)
@@ -370,8 +594,10 @@ impl Tree {
                 continue;
             }
             if line.score == best_score {
-                // in case of equal scores, we prefer the shortest path
-                if self.lines[idx].depth >= self.lines[self.selection].depth {
+                // in case of equal scores, the tie breaker of the options
+                // decides, the first line in the tree winning if still equal
+                let selected = &self.lines[self.selection];
+                if self.options.tie_breaker.compare(line, selected) != Ordering::Less {
                     continue;
                 }
             }
@@ -392,6 +618,33 @@ impl Tree {
         }
         false
     }
+    /// when the selected line disappeared, select the nearest one still
+    /// there: a following sibling, else a preceding one, else the closest
+    /// ancestor. The previous lines are the ones before the refresh.
+    fn try_select_nearest(&mut self, previous: &[TreeLine], previous_selection: usize) -> bool {
+        let removed = match previous.get(previous_selection) {
+            Some(line) => line,
+            None => return false,
+        };
+        let is_sibling = |line: &TreeLine| line.path.parent() == removed.path.parent();
+        for line in previous[previous_selection + 1..].iter() {
+            if line.depth < removed.depth {
+                break;
+            }
+            if is_sibling(line) && self.try_select_path(&line.path) {
+                return true;
+            }
+        }
+        for line in previous[..previous_selection].iter().rev() {
+            if line.depth < removed.depth {
+                break;
+            }
+            if is_sibling(line) && self.try_select_path(&line.path) {
+                return true;
+            }
+        }
+        removed.path.ancestors().skip(1).any(|ancestor| self.try_select_path(ancestor))
+    }
     pub fn try_select_first(&mut self) -> bool {
         for idx in 0..self.lines.len() {
             let line = &self.lines[idx];
@@ -450,8 +703,29 @@ impl Tree {
 
     pub fn has_dir_missing_size(&self) -> bool {
         self.options.show_sizes
+            && !self.sizes_interrupted
+            && self.lines.iter().any(|line|
+                line.line_type == LineType::Dir
+                    && !line.is_remote()
+                    && (line.size.is_none() || line.size_is_stale)
+            )
+    }
+
+    pub fn has_dir_missing_deep_date(&self) -> bool {
+        self.options.date_source == DateSource::Deep
+            && !self.deep_dates_interrupted
+            && self.lines.iter().any(|line|
+                line.line_type == LineType::Dir
+                    && !line.is_remote()
+                    && !line.in_archive
+                    && line.deep_date.is_not_computed()
+            )
+    }
+
+    pub fn has_file_missing_line_count(&self) -> bool {
+        self.options.show_line_counts
             && self.lines.iter().skip(1).any(|line|
-                line.line_type == LineType::Dir && line.size.is_none()
+                line.is_file() && !line.is_remote() && line.line_count.is_not_computed()
             )
     }
 
@@ -459,41 +733,175 @@ impl Tree {
         self.git_status.is_not_computed()
     }
 
+    pub fn has_file_missing_size(&self) -> bool {
+        self.options.show_sizes
+            && self.lines.iter().skip(1).any(|line| line.is_file() && line.size.is_none())
+    }
+
+    /// read the git statuses of the lines, which is deferred to after
+    /// the first display of the tree
+    pub fn fetch_line_git_statuses(&mut self) {
+        let computer = time!(
+            Debug,
+            "init line_status_computer",
+            Repository::discover(self.root()).ok().map(LineStatusComputer::from),
+        );
+        if let Some(computer) = computer {
+            for line in self.lines.iter_mut() {
+                line.git_status = computer.line_status(&line.path);
+            }
+        }
+        self.line_git_statuses_pending = false;
+    }
+
     pub fn fetch_file_sizes(&mut self) {
         for i in 1..self.lines.len() {
-            if self.lines[i].is_file() {
+            // the sizes of files in archives are already known
+            if self.lines[i].is_file() && self.lines[i].size.is_none() {
                 self.lines[i].size = Some(FileSize::from_file(&self.lines[i].path));
             }
         }
-        self.sort_siblings_by_size();
+        self.sort_siblings();
+    }
+
+    /// take the sizes of the root's ancestors which are already known,
+    /// from this session or a previous one, without computing any
+    pub fn fetch_ancestor_sizes(&mut self) {
+        self.ancestor_sizes = self.lines[0]
+            .path
+            .ancestors()
+            .skip(1)
+            .filter_map(|path| FileSize::from_known(path).map(|size| (path.to_path_buf(), size)))
+            .collect();
     }
 
     /// compute the size of one directory
     ///
+    /// The sizes persisted from previous sessions are all taken at first
+    ///  call, then computed again after the missing ones.
+    /// The root is measured after the other directories, whose content
+    ///  is then usually in the system's cache.
+    ///
     /// To compute the size of all of them, this should be called until
     ///  has_dir_missing_size returns false
     pub fn fetch_some_missing_dir_size(&mut self, dam: &Dam) {
-        for i in 1..self.lines.len() {
+        let mut found_persisted = false;
+        for line in self.lines.iter_mut() {
+            if line.size.is_none() && line.line_type == LineType::Dir {
+                if let Some(size) = FileSize::from_persisted_cache(&line.path) {
+                    line.size = Some(size);
+                    line.size_is_stale = true;
+                    found_persisted = true;
+                }
+            }
+        }
+        if found_persisted {
+            self.sort_siblings();
+            return;
+        }
+        let len = self.lines.len();
+        let root_last = move || (1..len).chain(iter::once(0));
+        for i in root_last() {
             if self.lines[i].size.is_none() && self.lines[i].line_type == LineType::Dir {
                 self.lines[i].size = FileSize::from_dir(&self.lines[i].path, dam);
-                self.sort_siblings_by_size();
+                self.sort_siblings();
+                return;
+            }
+        }
+        for i in root_last() {
+            if self.lines[i].size_is_stale {
+                if let Some(size) = FileSize::from_dir(&self.lines[i].path, dam) {
+                    self.lines[i].size = Some(size);
+                    self.lines[i].size_is_stale = false;
+                    self.sort_siblings();
+                }
+                return;
+            }
+        }
+    }
+
+    /// compute the deep date of one directory, the root being the last
+    ///
+    /// To compute the deep dates of all of them, this should be called
+    ///  until has_dir_missing_deep_date returns false
+    pub fn fetch_some_missing_deep_date(&mut self, dam: &Dam) {
+        let len = self.lines.len();
+        for i in (1..len).chain(iter::once(0)) {
+            let line = &self.lines[i];
+            if line.line_type == LineType::Dir
+                && !line.is_remote()
+                && !line.in_archive
+                && line.deep_date.is_not_computed()
+            {
+                let deep_date = deep_dates::deep_date(&line.path, dam);
+                self.lines[i].deep_date = deep_date;
+                if self.lines[i].deep_date.is_done() {
+                    self.sort_siblings();
+                }
                 return;
             }
         }
     }
-    /// Sort files according to their size
+
+    /// count the lines of one file
+    ///
+    /// To count the lines of all of them, this should be called until
+    ///  has_file_missing_line_count returns false
+    pub fn fetch_some_missing_line_count(&mut self) {
+        for i in 1..self.lines.len() {
+            if self.lines[i].is_file() && self.lines[i].line_count.is_not_computed() {
+                self.lines[i].line_count = match line_counts::line_count(&self.lines[i].path) {
+                    Some(count) => ComputationResult::Done(count),
+                    None => ComputationResult::None,
+                };
+                return;
+            }
+        }
+    }
+
+    /// Sort files according to the sort mode or, when there's none
+    /// and sizes are displayed, according to their size
     ///
     /// Warning: must not be called if there's more than one level displayed!
     /// (a better sort should be devised but it's unsure whether it would be
     /// readable enough)
-    fn sort_siblings_by_size(&mut self) {
+    fn sort_siblings(&mut self) {
+        let sort = match self.options.sort {
+            Sort::None if self.options.show_sizes => Sort::Size,
+            sort => sort,
+        };
         // we'll try to keep the same path selected
         let selected_path = self.selected_line().path.to_path_buf();
-        self.lines[1..].sort_by(|a, b| {
-            let asize = a.size.map_or(0, |s| s.into());
-            let bsize = b.size.map_or(0, |s| s.into());
-            bsize.cmp(&asize)
-        });
+        match sort {
+            Sort::None => {
+                return;
+            }
+            Sort::Count => {
+                // the builder may have stopped before listing the
+                // content of the directories, which is then counted here
+                for line in &mut self.lines[1..] {
+                    if line.children_count.is_none()
+                        && line.line_type == LineType::Dir
+                        && !line.in_archive
+                        && !line.is_remote()
+                    {
+                        line.children_count = fs::read_dir(&line.path).ok().map(Iterator::count);
+                    }
+                }
+                self.lines[1..].sort_by(|a, b| b.children_count.cmp(&a.children_count));
+            }
+            Sort::Date => {
+                let date_source = self.options.date_source;
+                self.lines[1..].sort_by(|a, b| b.date(date_source).cmp(&a.date(date_source)));
+            }
+            Sort::Size => {
+                self.lines[1..].sort_by(|a, b| {
+                    let asize = a.size.map_or(0, |s| s.into());
+                    let bsize = b.size.map_or(0, |s| s.into());
+                    bsize.cmp(&asize)
+                });
+            }
+        }
         self.try_select_path(&selected_path);
     }
 
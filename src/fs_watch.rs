@@ -0,0 +1,163 @@
+//! watching of the displayed directories, so that the tree can be
+//! refreshed when files are created, removed or renamed.
+//!
+//! When a file is awaited (see `:await`), the whole directory is
+//! watched, so that the file is found wherever it appears.
+
+use {
+    crossbeam::channel::{bounded, unbounded, Receiver},
+    notify::{self, DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher},
+    std::{
+        collections::HashSet,
+        path::{Path, PathBuf},
+        sync::mpsc,
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+/// how long the notifications are gathered before a refresh is asked,
+/// so that a big operation (eg a copy) doesn't trigger many refreshes
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(300);
+
+/// how long without change ends a burst of changes
+const QUIET_DELAY: Duration = Duration::from_millis(100);
+
+/// the longest a refresh is deferred by a burst of changes,
+/// so that continuous changes still refresh the tree
+const MAX_GATHERING_DURATION: Duration = Duration::from_secs(2);
+
+/// a file awaited in a directory, described by a glob which, when
+/// it has no separator, is matched against the name of the file, and
+/// otherwise against its path relative to the directory
+#[derive(Debug, Clone)]
+pub struct AwaitedFile {
+    pub dir: PathBuf,
+    pub glob: String,
+    pattern: glob::Pattern,
+}
+
+impl AwaitedFile {
+    pub fn new(dir: PathBuf, glob: &str) -> Result<Self, glob::PatternError> {
+        Ok(Self {
+            dir,
+            glob: glob.to_string(),
+            pattern: glob::Pattern::new(glob)?,
+        })
+    }
+    pub fn matches(&self, path: &Path) -> bool {
+        if self.glob.contains('/') {
+            path.strip_prefix(&self.dir)
+                .map_or(false, |rel| self.pattern.matches_path(rel))
+        } else {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, |name| self.pattern.matches(name))
+        }
+    }
+}
+
+/// A watcher of directories, each one being watched without its
+/// descendants, as only the displayed directories matter, unless
+/// a file is awaited
+pub struct FsWatcher {
+    watcher: RecommendedWatcher,
+    watched: HashSet<PathBuf>,
+    mode: RecursiveMode,
+    pub changes: Receiver<()>, // receives a message when something changed
+    pub created: Receiver<PathBuf>, // the files created or renamed since the last change
+    changed: Receiver<Option<PathBuf>>, // the changed paths, None when they're unknown
+}
+
+impl FsWatcher {
+    pub fn new() -> notify::Result<Self> {
+        let (notify_sender, notify_receiver) = mpsc::channel();
+        let watcher = notify::watcher(notify_sender, DEBOUNCE_DELAY)?;
+        let (changes_sender, changes) = bounded(1);
+        let (created_sender, created) = unbounded();
+        let (changed_sender, changed) = unbounded();
+        thread::spawn(move || {
+            // the loop ends when the watcher is dropped
+            for event in notify_receiver {
+                match event {
+                    DebouncedEvent::Create(path) => {
+                        let _ = changed_sender.send(Some(path.clone()));
+                        let _ = created_sender.send(path);
+                    }
+                    DebouncedEvent::Rename(old_path, path) => {
+                        let _ = changed_sender.send(Some(old_path));
+                        let _ = changed_sender.send(Some(path.clone()));
+                        let _ = created_sender.send(path);
+                    }
+                    DebouncedEvent::Remove(path) => {
+                        let _ = changed_sender.send(Some(path));
+                    }
+                    DebouncedEvent::Rescan => {
+                        let _ = changed_sender.send(None);
+                    }
+                    _ => {
+                        continue;
+                    }
+                }
+                // if there's already a message in the channel,
+                // one refresh will be enough
+                let _ = changes_sender.try_send(());
+            }
+        });
+        Ok(Self {
+            watcher,
+            watched: HashSet::new(),
+            mode: RecursiveMode::NonRecursive,
+            changes,
+            created,
+            changed,
+        })
+    }
+
+    /// wait for the end of the burst of changes, so that it makes
+    /// only one refresh, then return the changed paths (None standing
+    /// for unknown changes)
+    pub fn gather_changes(&self) -> Vec<Option<PathBuf>> {
+        let start = Instant::now();
+        while start.elapsed() < MAX_GATHERING_DURATION
+            && self.changes.recv_timeout(QUIET_DELAY).is_ok()
+        {}
+        self.changed.try_iter().collect()
+    }
+
+    /// change the watched directories
+    pub fn watch(&mut self, dirs: HashSet<PathBuf>, mode: RecursiveMode) {
+        if mode != self.mode {
+            // the directories must be watched again in the other mode
+            self.watch(HashSet::new(), self.mode);
+            self.mode = mode;
+        }
+        for path in self.watched.difference(&dirs) {
+            if let Err(e) = self.watcher.unwatch(path) {
+                debug!("failed to unwatch {:?}: {:?}", path, e);
+            }
+        }
+        for path in dirs.difference(&self.watched) {
+            if let Err(e) = self.watcher.watch(path, mode) {
+                debug!("failed to watch {:?}: {:?}", path, e);
+            }
+        }
+        self.watched = dirs;
+    }
+}
+
+#[cfg(test)]
+mod fs_watch_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_awaited_file() {
+        let awaited = AwaitedFile::new(PathBuf::from("/dev/app"), "*.deb").unwrap();
+        assert!(awaited.matches(Path::new("/dev/app/target/debian/app_1.0.deb")));
+        assert!(!awaited.matches(Path::new("/dev/app/target/app.rpm")));
+        let awaited = AwaitedFile::new(PathBuf::from("/dev/app"), "target/*/app").unwrap();
+        assert!(awaited.matches(Path::new("/dev/app/target/release/app")));
+        assert!(!awaited.matches(Path::new("/dev/app/src/app")));
+    }
+}
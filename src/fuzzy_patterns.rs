@@ -1,6 +1,8 @@
 //! a simple fuzzy pattern matcher for filename filtering / sorting.
 //! It's not meant for file contents but for small strings (less than 1000 chars)
 //!  such as file names.
+//! As in fzf, a pattern starting with `^` must match at the start of the
+//!  name and a pattern ending with `$` must match at its end.
 
 use std::fmt::{self, Write};
 
@@ -20,13 +22,21 @@ pub struct FuzzyPattern {
     lc_bytes: Box<[u8]>,
     lc_chars: Box<[char]>, // lowercase characters
     max_nb_holes: usize,
+    anchored_start: bool, // whether the match must start with the candidate
+    anchored_end: bool,   // whether the match must end with the candidate
 }
 
 impl fmt::Display for FuzzyPattern {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.anchored_start {
+            f.write_char('^')?;
+        }
         for &c in self.lc_chars.iter() {
             f.write_char(c)?
         }
+        if self.anchored_end {
+            f.write_char('$')?;
+        }
         Ok(())
     }
 }
@@ -46,7 +56,16 @@ impl FuzzyPattern {
 
     /// build a pattern which will later be usable for fuzzy search.
     /// A pattern should be reused
-    pub fn from(pat: &str) -> FuzzyPattern {
+    pub fn from(mut pat: &str) -> FuzzyPattern {
+        // anchors are only anchors when there's something to anchor
+        let anchored_start = pat.len() > 1 && pat.starts_with('^');
+        if anchored_start {
+            pat = &pat[1..];
+        }
+        let anchored_end = pat.len() > 1 && pat.ends_with('$');
+        if anchored_end {
+            pat = &pat[..pat.len() - 1];
+        }
         let lc_bytes = pat.to_lowercase().as_bytes().to_vec();
         let lc_bytes = lc_bytes.into_boxed_slice();
         let lc_chars: Vec<char> = pat.chars().map(|c| c.to_ascii_lowercase()).collect();
@@ -66,9 +85,42 @@ impl FuzzyPattern {
             lc_bytes,
             lc_chars,
             max_nb_holes,
+            anchored_start,
+            anchored_end,
         }
     }
 
+    pub fn starts_with_dot(&self) -> bool {
+        self.lc_chars.first() == Some(&'.')
+    }
+
+    /// tell whether all candidates matched by the other pattern
+    /// may be matched by this one, which is the case when it was
+    /// made by typing more characters at the end of this one
+    pub fn is_extended_by(&self, other: &FuzzyPattern) -> bool {
+        !self.anchored_end
+            && (other.anchored_start || !self.anchored_start)
+            && other.lc_chars.starts_with(&self.lc_chars)
+    }
+
+    /// tell whether the candidate contains the characters of the
+    /// pattern, in order, holes not being counted.
+    ///
+    /// It's cheaper than a real match and it's true for all candidates
+    /// matched by this pattern or by a pattern extending it.
+    pub fn may_match(&self, candidate: &str) -> bool {
+        let mut cand_chars = candidate.chars().map(|c| c.to_ascii_lowercase());
+        let mut pattern_chars = self.lc_chars.iter();
+        if self.anchored_start {
+            if let Some(&first) = pattern_chars.next() {
+                if cand_chars.next() != Some(first) {
+                    return false;
+                }
+            }
+        }
+        pattern_chars.all(|&pc| cand_chars.any(|cc| cc == pc))
+    }
+
     /// look for a match starting at a given character
     fn match_starting_at_index(
         &self,
@@ -78,21 +130,35 @@ impl FuzzyPattern {
         if cand_chars[start_idx] != self.lc_chars[0] {
             return MatchSearchResult::None;
         }
+        if self.anchored_end && self.lc_chars.len() == 1 && start_idx + 1 != cand_chars.len() {
+            return MatchSearchResult::None;
+        }
         let mut pos: Vec<usize> = vec![]; // positions of matching chars in candidate
         pos.push(start_idx);
         let mut d = 1;
         let mut nb_holes = 0;
+        let last_pat_idx = self.lc_chars.len() - 1;
         for pat_idx in 1..self.lc_chars.len() {
             let hole_start = d;
-            loop {
-                let cand_idx = start_idx + d;
-                if cand_idx == cand_chars.len() {
+            if self.anchored_end && pat_idx == last_pat_idx {
+                // the last char of the pattern must be the last one of the candidate
+                let cand_idx = cand_chars.len() - 1;
+                if cand_idx < start_idx + d || cand_chars[cand_idx] != self.lc_chars[pat_idx] {
                     return MatchSearchResult::None;
                 }
-                d += 1;
-                if cand_chars[cand_idx] == self.lc_chars[pat_idx] {
-                    pos.push(cand_idx);
-                    break;
+                pos.push(cand_idx);
+                d = cand_idx - start_idx + 1;
+            } else {
+                loop {
+                    let cand_idx = start_idx + d;
+                    if cand_idx == cand_chars.len() {
+                        return MatchSearchResult::None;
+                    }
+                    d += 1;
+                    if cand_chars[cand_idx] == self.lc_chars[pat_idx] {
+                        pos.push(cand_idx);
+                        break;
+                    }
                 }
             }
             if hole_start + 1 != d {
@@ -138,7 +204,11 @@ impl FuzzyPattern {
         }
         let mut best_score = 0;
         let mut best_match: Option<Match> = None;
-        let n = cand_chars.len() - self.lc_chars.len();
+        let n = if self.anchored_start {
+            0
+        } else {
+            cand_chars.len() - self.lc_chars.len()
+        };
         for start_idx in 0..=n {
             match self.match_starting_at_index(&cand_chars, start_idx) {
                 MatchSearchResult::Perfect(m) => {
@@ -168,18 +238,33 @@ impl FuzzyPattern {
         if cand[start_idx].to_ascii_lowercase() != self.lc_bytes[0] {
             return ScoreSearchResult::None;
         }
+        if self.anchored_end && self.lc_bytes.len() == 1 && start_idx + 1 != cand.len() {
+            return ScoreSearchResult::None;
+        }
         let mut d = 1;
         let mut nb_holes = 0;
+        let last_pat_idx = self.lc_bytes.len() - 1;
         for pat_idx in 1..self.lc_bytes.len() {
             let hole_start = d;
-            loop {
-                let cand_idx = start_idx + d;
-                if cand_idx == cand.len() {
+            if self.anchored_end && pat_idx == last_pat_idx {
+                // the last byte of the pattern must be the last one of the candidate
+                let cand_idx = cand.len() - 1;
+                if cand_idx < start_idx + d
+                    || cand[cand_idx].to_ascii_lowercase() != self.lc_bytes[pat_idx]
+                {
                     return ScoreSearchResult::None;
                 }
-                d += 1;
-                if cand[cand_idx].to_ascii_lowercase() == self.lc_bytes[pat_idx] {
-                    break;
+                d = cand_idx - start_idx + 1;
+            } else {
+                loop {
+                    let cand_idx = start_idx + d;
+                    if cand_idx == cand.len() {
+                        return ScoreSearchResult::None;
+                    }
+                    d += 1;
+                    if cand[cand_idx].to_ascii_lowercase() == self.lc_bytes[pat_idx] {
+                        break;
+                    }
                 }
             }
             if hole_start + 1 != d {
@@ -228,7 +313,11 @@ impl FuzzyPattern {
             return None;
         }
         let mut best_score = 0;
-        let n = candidate.len() - self.lc_bytes.len();
+        let n = if self.anchored_start {
+            0
+        } else {
+            candidate.len() - self.lc_bytes.len()
+        };
         for start_idx in 0..=n {
             match self.score_starting_at(candidate.as_bytes(), start_idx) {
                 ScoreSearchResult::Perfect(s) => {
@@ -320,6 +409,57 @@ mod fuzzy_pattern_tests {
         }
     }
 
+    #[test]
+    fn check_anchors() {
+        let check = |pattern: &str, name: &str, matching: bool| {
+            let fp = FuzzyPattern::from(pattern);
+            assert_eq!(fp.find(name).is_some(), matching, "{:?} on {:?}", pattern, name);
+            assert_eq!(fp.score_of(name), fp.find(name).map(|m| m.score));
+        };
+        check("^br", "broot.rs", true);
+        check("^br", "a_broot.rs", false);
+        check("rs$", "broot.rs", true);
+        check("rs$", "rs.toml", false);
+        check("s$", "broot.rs", true);
+        check("s$", "broot.rst", false);
+        check("^bt$", "broot", true);
+        check("^bt$", "broots", false);
+        check("^", "a^b", true);
+        check("$", "a$b", true);
+        let fp = FuzzyPattern::from("ab$");
+        assert_eq!(fp.find("ab_ab").unwrap().pos, vec![3, 4]);
+        assert_eq!(fp.find("axxb").unwrap().pos, vec![0, 3]);
+        assert_eq!(fp.to_string(), "ab$");
+    }
+
+    /// check that the candidates matched by an extended pattern are
+    /// all possibly matched by the shorter one
+    #[test]
+    fn check_extensions() {
+        let names = ["a_b_c_d_ef", "abcdef", "xabcdef", "fedcba", "ab", "a.b.c.d.e.f.g"];
+        let patterns = ["a", "ab", "abc", "abcd", "abcde", "abcdef", "^a", "^ab", "^abcdef"];
+        for short in patterns.iter().map(|p| FuzzyPattern::from(p)) {
+            for long in patterns.iter().map(|p| FuzzyPattern::from(p)) {
+                if !short.is_extended_by(&long) {
+                    continue;
+                }
+                for name in &names {
+                    if long.find(name).is_some() {
+                        assert!(short.may_match(name), "{} on {:?}", short, name);
+                    }
+                }
+            }
+        }
+        assert!(FuzzyPattern::from("ab").is_extended_by(&FuzzyPattern::from("^abc")));
+        assert!(!FuzzyPattern::from("^ab").is_extended_by(&FuzzyPattern::from("abc")));
+        assert!(!FuzzyPattern::from("ab$").is_extended_by(&FuzzyPattern::from("ab$c")));
+        assert!(!FuzzyPattern::from("^").is_extended_by(&FuzzyPattern::from("^a")));
+        // a candidate may be rejected by a pattern but matched by a longer one
+        assert!(FuzzyPattern::from("abcde").find("a_b_c_d_ef").is_none());
+        assert!(FuzzyPattern::from("abcdef").find("a_b_c_d_ef").is_some());
+        assert!(!FuzzyPattern::from("^ab").may_match("xab"));
+    }
+
     #[test]
     fn check_orderings() {
         check_ordering_for(
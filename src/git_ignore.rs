@@ -11,7 +11,8 @@ use {
     std::{
         fs::File,
         io::{BufRead, BufReader, Result},
-        path::Path,
+        path::{Path, PathBuf},
+        sync::Arc,
     },
 };
 
@@ -19,6 +20,23 @@ pub fn is_repo(root: &Path) -> bool {
     root.join(".git").exists()
 }
 
+/// where a rule of a gitignore file comes from, so that
+/// the user can be told why a file is ignored
+#[derive(Debug)]
+pub struct IgnoringRule {
+    pub pattern: String, // as written in the file
+    pub file: PathBuf,
+    pub line: usize, // starting at 1
+}
+
+/// what git does with an entry, as shown when auditing the ignore rules
+#[derive(Debug, Clone)]
+pub enum IgnoreAudit {
+    Tracked(Option<Arc<IgnoringRule>>), // with the ignore rule git doesn't apply, if any
+    Untracked,
+    Ignored(Arc<IgnoringRule>),
+}
+
 /// a simple rule of a gitignore file
 #[derive(Clone)]
 struct GitIgnoreRule {
@@ -27,10 +45,12 @@ struct GitIgnoreRule {
     filename: bool,  // does this rule apply to just the filename
     pattern: glob::Pattern,
     pattern_options: glob::MatchOptions,
+    source: Arc<IgnoringRule>,
 }
 
 impl GitIgnoreRule {
-    fn from(line: &str, dir: &Path) -> Option<GitIgnoreRule> {
+    fn from(line: &str, file: &Path, line_number: usize) -> Option<GitIgnoreRule> {
+        let dir = file.parent().unwrap_or_else(|| Path::new("/"));
         if line.starts_with('#') {
             return None; // comment line
         }
@@ -62,6 +82,11 @@ impl GitIgnoreRule {
                         directory: c.get(3).is_some(),
                         filename: !has_separator,
                         pattern_options,
+                        source: Arc::new(IgnoringRule {
+                            pattern: line.trim().to_string(),
+                            file: file.to_path_buf(),
+                            line: line_number,
+                        }),
                     });
                 }
             }
@@ -78,10 +103,9 @@ pub struct GitIgnoreFile {
 impl GitIgnoreFile {
     pub fn new(path: &Path) -> Result<GitIgnoreFile> {
         let f = File::open(path)?;
-        let parent = path.parent().unwrap();
         let mut rules: Vec<GitIgnoreRule> = Vec::new();
-        for line in BufReader::new(f).lines() {
-            if let Some(rule) = GitIgnoreRule::from(&line?, &parent) {
+        for (idx, line) in BufReader::new(f).lines().enumerate() {
+            if let Some(rule) = GitIgnoreRule::from(&line?, path, idx + 1) {
                 rules.push(rule);
             }
         }
@@ -204,6 +228,16 @@ impl GitIgnorer {
         filename: &str,
         directory: bool,
     ) -> bool {
+        self.ignoring_rule(chain, path, filename, directory).is_none()
+    }
+    /// return the rule making git ignore the path, if any
+    pub fn ignoring_rule(
+        &self,
+        chain: &GitIgnoreChain,
+        path: &Path,
+        filename: &str,
+        directory: bool,
+    ) -> Option<Arc<IgnoringRule>> {
         // we start with deeper files: deeper rules have a bigger priority
         for id in chain.file_ids.iter().rev() {
             let file = &self.files[*id];
@@ -217,11 +251,15 @@ impl GitIgnorer {
                     rule.pattern.matches_path_with(path, rule.pattern_options)
                 };
                 if ok {
-                    return rule.ok;
+                    return if rule.ok {
+                        None
+                    } else {
+                        Some(Arc::clone(&rule.source))
+                    };
                 }
             }
         }
-        true
+        None
     }
 }
 
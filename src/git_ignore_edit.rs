@@ -0,0 +1,138 @@
+//! addition of patterns to the .gitignore files, so that a file,
+//! all the files with the same name, or all the files with the
+//! same extension, stop being tracked and displayed.
+
+use {
+    crate::git_ignore::is_repo,
+    std::{
+        fs,
+        io::{self, Write},
+        path::{Path, PathBuf},
+    },
+};
+
+/// a pattern which can be added to ignore the selection
+#[derive(Debug, Clone, PartialEq)]
+pub struct IgnoreChoice {
+    pub description: String,
+    pub pattern: String,
+}
+
+/// return the .gitignore file a pattern for the path should go to:
+/// the nearest one among the ancestors, or the one at the root of
+/// the repository if there's none. Return None out of a repository.
+pub fn ignore_file_for(path: &Path) -> Option<PathBuf> {
+    for dir in path.ancestors().skip(1) {
+        let file = dir.join(".gitignore");
+        if file.exists() || is_repo(dir) {
+            return Some(file);
+        }
+    }
+    None
+}
+
+/// escape the characters which would be understood as globs
+fn escape(name: &str) -> String {
+    let mut escaped = String::new();
+    for c in name.chars() {
+        if let '*' | '?' | '[' | '\\' = c {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// escape the first character of a pattern when it would make
+/// the line a comment or a negation
+fn escape_start(pattern: String) -> String {
+    if pattern.starts_with('#') || pattern.starts_with('!') {
+        format!("\\{}", pattern)
+    } else {
+        pattern
+    }
+}
+
+/// build the patterns which would ignore the path: by name, by
+/// extension (for a file having one), or by path relative to the
+/// directory of the .gitignore file
+pub fn choices(path: &Path, is_dir: bool, ignore_dir: &Path) -> Vec<IgnoreChoice> {
+    let mut choices = Vec::new();
+    let suffix = if is_dir { "/" } else { "" };
+    let kind = if is_dir { "directory" } else { "file" };
+    if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+        choices.push(IgnoreChoice {
+            description: format!("every {} named {}", kind, name),
+            pattern: escape_start(format!("{}{}", escape(name), suffix)),
+        });
+    }
+    if !is_dir {
+        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+            choices.push(IgnoreChoice {
+                description: format!("every file with the .{} extension", ext),
+                pattern: format!("*.{}", escape(ext)),
+            });
+        }
+    }
+    if let Ok(rel) = path.strip_prefix(ignore_dir) {
+        let parts: Vec<String> = rel
+            .components()
+            .map(|c| escape(&c.as_os_str().to_string_lossy()))
+            .collect();
+        if !parts.is_empty() {
+            choices.push(IgnoreChoice {
+                description: format!("only this {}", kind),
+                pattern: format!("/{}{}", parts.join("/"), suffix),
+            });
+        }
+    }
+    choices
+}
+
+/// append the pattern to the ignore file, creating it if needed.
+/// Return false when the pattern was already there.
+pub fn add_pattern(file: &Path, pattern: &str) -> io::Result<bool> {
+    let content = match fs::read_to_string(file) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(e);
+        }
+    };
+    if content.lines().any(|line| line.trim_end() == pattern) {
+        return Ok(false);
+    }
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(file)?;
+    if !content.is_empty() && !content.ends_with('\n') {
+        writeln!(file)?;
+    }
+    writeln!(file, "{}", pattern)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod git_ignore_edit_tests {
+
+    use super::*;
+
+    fn patterns(path: &str, is_dir: bool) -> Vec<String> {
+        choices(Path::new(path), is_dir, Path::new("/dev/broot"))
+            .into_iter()
+            .map(|choice| choice.pattern)
+            .collect()
+    }
+
+    #[test]
+    fn check_choices() {
+        assert_eq!(
+            patterns("/dev/broot/src/main.rs", false),
+            vec!["main.rs", "*.rs", "/src/main.rs"],
+        );
+        assert_eq!(patterns("/dev/broot/target", true), vec!["target/", "/target/"]);
+        assert_eq!(patterns("/dev/broot/Makefile", false), vec!["Makefile", "/Makefile"]);
+        assert_eq!(
+            patterns("/dev/broot/#notes[1]", false),
+            vec!["\\#notes\\[1]", "/#notes\\[1]"],
+        );
+    }
+}
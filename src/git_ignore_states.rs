@@ -0,0 +1,207 @@
+use {
+    crate::{
+        app_context::AppContext,
+        app_state::{AppState, AppStateCmdResult},
+        commands::{Action, Command},
+        errors::ProgramError,
+        git_ignore_edit::{self, IgnoreChoice},
+        help_content::{ScreenHelp, ScreenKey},
+        help_states::HelpState,
+        screens::Screen,
+        status::Status,
+        task_sync::Dam,
+        verb_store::PrefixSearchResult,
+    },
+    crossterm::{
+        terminal::{Clear, ClearType},
+        QueueableCommand,
+    },
+    minimad::Composite,
+    std::{
+        io::{self, Write},
+        path::{Path, PathBuf},
+    },
+};
+
+/// the help of the ignore pattern menu
+pub static HELP: ScreenHelp = ScreenHelp {
+    name: "gitignore menu",
+    description: "This screen proposes the patterns which would make git ignore the selection, \
+        the chosen one being added to the nearest `.gitignore` file.",
+    keys: &[
+        ScreenKey::hinted("esc", "get back to the tree without changing anything", "to cancel"),
+        ScreenKey::new("↑ ↓", "select a pattern"),
+        ScreenKey::hinted(
+            "enter",
+            "add the selected pattern to the .gitignore file",
+            "to add the selected pattern",
+        ),
+        ScreenKey::new("?", "display this help"),
+    ],
+    builtins: Some(&[":back", ":help", ":quit"]),
+    external_verbs: false,
+};
+
+/// an application state letting the user choose how the selected
+/// file or directory is ignored: by name, extension, or path
+pub struct GitIgnoreState {
+    ignore_file: PathBuf,
+    choices: Vec<IgnoreChoice>,
+    selection: usize,
+}
+
+impl GitIgnoreState {
+    pub fn new(path: &Path, is_dir: bool) -> io::Result<GitIgnoreState> {
+        let ignore_file = git_ignore_edit::ignore_file_for(path).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "not in a git repository")
+        })?;
+        let ignore_dir = ignore_file.parent().unwrap_or_else(|| Path::new(""));
+        let choices = git_ignore_edit::choices(path, is_dir, ignore_dir);
+        if choices.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "nothing to ignore"));
+        }
+        Ok(GitIgnoreState {
+            ignore_file,
+            choices,
+            selection: 0,
+        })
+    }
+
+    fn move_selection(&mut self, dy: i32) {
+        let count = self.choices.len() as i32;
+        self.selection = (self.selection as i32 + dy).rem_euclid(count) as usize;
+    }
+
+    /// add the selected pattern, then get back to the
+    /// tree, which is refreshed
+    fn add_selected(&self) -> AppStateCmdResult {
+        let pattern = &self.choices[self.selection].pattern;
+        match git_ignore_edit::add_pattern(&self.ignore_file, pattern) {
+            Ok(true) => AppStateCmdResult::PopState,
+            Ok(false) => AppStateCmdResult::DisplayError(format!(
+                "{} is already in {}",
+                pattern,
+                self.ignore_file.to_string_lossy(),
+            )),
+            Err(e) => AppStateCmdResult::DisplayError(e.to_string()),
+        }
+    }
+}
+
+impl AppState for GitIgnoreState {
+    fn help(&self) -> &'static ScreenHelp {
+        &HELP
+    }
+
+    fn has_pending_task(&self) -> bool {
+        false
+    }
+
+    fn can_execute(&self, _verb_index: usize, _con: &AppContext) -> bool {
+        true
+    }
+
+    fn apply(
+        &mut self,
+        cmd: &mut Command,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<AppStateCmdResult, ProgramError> {
+        let verb_execution = match &cmd.action {
+            Action::VerbIndex(index) => Some(con.verb_store.verbs[*index].execution.as_str()),
+            Action::VerbInvocate(invocation) => match con.verb_store.search(&invocation.name) {
+                PrefixSearchResult::Match(verb) => Some(verb.execution.as_str()),
+                _ => {
+                    return Ok(AppStateCmdResult::verb_not_found(&invocation.name));
+                }
+            },
+            _ => None,
+        };
+        Ok(match (&cmd.action, verb_execution) {
+            (Action::Back, _) | (_, Some(":back")) => AppStateCmdResult::PopState,
+            (Action::Help, _) | (_, Some(":help")) => AppStateCmdResult::NewState(
+                Box::new(HelpState::new(screen, con, &HELP)),
+                Command::new(),
+            ),
+            (_, Some(":quit")) => AppStateCmdResult::Quit,
+            (_, Some(_)) => AppStateCmdResult::DisplayError(
+                "hit enter to add the pattern, or esc to cancel".to_string(),
+            ),
+            (Action::MoveSelection(dy), _) => {
+                self.move_selection(*dy);
+                AppStateCmdResult::Keep
+            }
+            (Action::OpenSelection, _) => self.add_selected(),
+            (Action::Resize(w, h), _) => {
+                screen.set_terminal_size(*w, *h, con);
+                AppStateCmdResult::Keep
+            }
+            _ => AppStateCmdResult::Keep,
+        })
+    }
+
+    fn refresh(&mut self, _screen: &Screen, _con: &AppContext) -> Command {
+        Command::new()
+    }
+
+    fn do_pending_task(&mut self, _screen: &mut Screen, _con: &AppContext, _dam: &mut Dam) {
+        unreachable!();
+    }
+
+    fn display(
+        &mut self,
+        mut w: &mut dyn Write,
+        screen: &Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        screen.goto_clear(&mut w, 0, 0)?;
+        screen.skin.default.queue_bg(&mut w)?;
+        screen.skin.help_headers.queue(
+            &mut w,
+            format!("add to {}:", self.ignore_file.to_string_lossy()),
+        )?;
+        screen.clear_line(&mut w)?;
+        screen.goto_clear(&mut w, 0, 1)?;
+        let mut y = 2;
+        for (idx, choice) in self.choices.iter().enumerate() {
+            screen.goto(&mut w, 0, y)?;
+            let (style, mark) = if idx == self.selection {
+                (&screen.skin.selected_line, ">")
+            } else {
+                (&screen.skin.default, " ")
+            };
+            style.queue(&mut w, format!("{} {:<40} ", mark, choice.pattern))?;
+            screen.skin.tree.queue(&mut w, &choice.description)?;
+            w.queue(Clear(ClearType::UntilNewLine))?;
+            y += 1;
+        }
+        for y in y..screen.height.max(3) - 2 {
+            screen.goto(&mut w, 0, y)?;
+            w.queue(Clear(ClearType::UntilNewLine))?;
+        }
+        Ok(())
+    }
+
+    fn write_status(
+        &self,
+        mut w: &mut dyn Write,
+        _cmd: &Command,
+        screen: &Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        let hint = HELP.keys_hint();
+        Status::from_message(Composite::from_inline(&hint)).display(&mut w, screen)
+    }
+
+    /// there's no meaningful flags here
+    fn write_flags(
+        &self,
+        mut w: &mut dyn Write,
+        screen: &mut Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        screen.skin.default.queue_bg(&mut w)?;
+        w.queue(Clear(ClearType::UntilNewLine))?;
+        Ok(())
+    }
+}
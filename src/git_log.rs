@@ -0,0 +1,134 @@
+//! reading of the recent commits of the git repository which
+//! touched a directory or a file, with the files they changed in it,
+//! and of the versions of a file at those commits
+
+use {
+    crate::previews,
+    git2::{DiffOptions, ObjectType, Oid, Repository, Sort},
+    std::{
+        io::{self, Write},
+        path::{Path, PathBuf},
+    },
+};
+
+/// the maximal number of commits listed
+pub const MAX_COMMITS: usize = 200;
+
+/// the maximal number of commits read before giving up finding
+/// more commits touching the directory, so that a directory rarely
+/// modified in a long history doesn't make the log too slow
+const MAX_SCANNED_COMMITS: usize = 5_000;
+
+/// a commit which touched the directory
+#[derive(Debug, Clone)]
+pub struct LogCommit {
+    pub id: Oid,
+    pub hash: String, // abbreviated
+    pub summary: String,
+    pub author: String,
+    pub seconds: i64, // date of the commit, in seconds since the epoch
+    pub files: Vec<PathBuf>, // the files changed in the directory, as absolute paths
+}
+
+fn io_error(e: git2::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e)
+}
+
+/// return the repository of the directory or file, its working
+/// directory, and the path of the directory or file in it
+fn open_repo(path: &Path) -> io::Result<(Repository, PathBuf, PathBuf)> {
+    let path = path.canonicalize()?;
+    // the repository is searched from the directory of a file
+    let start = if path.is_dir() {
+        &path
+    } else {
+        path.parent().unwrap_or(&path)
+    };
+    let repo = Repository::discover(start).map_err(io_error)?;
+    let workdir = repo
+        .workdir()
+        .and_then(|workdir| workdir.canonicalize().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no working directory"))?;
+    let rel = match path.strip_prefix(&workdir) {
+        Ok(rel) => rel.to_path_buf(),
+        Err(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{:?} isn't in the working directory of its repository", path),
+            ));
+        }
+    };
+    Ok((repo, workdir, rel))
+}
+
+/// return the recent commits, the most recent first, of the
+/// current branch which changed the file or files in the directory
+pub fn read_log(path: &Path) -> io::Result<Vec<LogCommit>> {
+    let (repo, workdir, rel) = open_repo(path)?;
+    let mut revwalk = repo.revwalk().map_err(io_error)?;
+    revwalk.push_head().map_err(io_error)?;
+    revwalk.set_sorting(Sort::TIME);
+    let mut commits = Vec::new();
+    for oid in revwalk.take(MAX_SCANNED_COMMITS) {
+        let commit = repo.find_commit(oid.map_err(io_error)?).map_err(io_error)?;
+        let tree = commit.tree().map_err(io_error)?;
+        // a merge is compared to its first parent
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree().map_err(io_error)?),
+            Err(_) => None, // the first commit
+        };
+        let mut options = DiffOptions::new();
+        if !rel.as_os_str().is_empty() {
+            options.pathspec(&rel);
+        }
+        let diff = repo
+            .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut options))
+            .map_err(io_error)?;
+        let files: Vec<PathBuf> = diff
+            .deltas()
+            .filter_map(|delta| delta.new_file().path().or_else(|| delta.old_file().path()))
+            .map(|path| workdir.join(path))
+            .collect();
+        if files.is_empty() {
+            continue;
+        }
+        let hash = commit.id().to_string();
+        commits.push(LogCommit {
+            id: commit.id(),
+            hash: hash[..hash.len().min(10)].to_string(),
+            summary: commit.summary().unwrap_or("").to_string(),
+            author: commit.author().name().unwrap_or("").to_string(),
+            seconds: commit.time().seconds(),
+            files,
+        });
+        if commits.len() == MAX_COMMITS {
+            break;
+        }
+    }
+    Ok(commits)
+}
+
+/// write a temporary copy of the file as it was at the commit, so
+/// that it can be opened, and return its path
+pub fn write_version(path: &Path, commit: Oid) -> io::Result<PathBuf> {
+    let (repo, _, rel) = open_repo(path)?;
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid name"))?;
+    let tree = repo.find_commit(commit).and_then(|c| c.tree()).map_err(io_error)?;
+    let blob = tree
+        .get_path(&rel)
+        .ok()
+        .filter(|entry| entry.kind() == Some(ObjectType::Blob))
+        .and_then(|entry| repo.find_blob(entry.id()).ok())
+        .ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "the file doesn't exist at this commit")
+        })?;
+    // the directory is named after the commit, so that the copy keeps
+    // the name, and thus the extension, of the file
+    let hash = commit.to_string();
+    let dir = previews::dir(&format!("git-{}", &hash[..hash.len().min(10)]))?;
+    let copy = dir.join(name);
+    previews::create_file(&copy)?.write_all(blob.content())?;
+    Ok(copy)
+}
@@ -0,0 +1,424 @@
+use {
+    crate::{
+        app_context::AppContext,
+        app_state::{AppState, AppStateCmdResult},
+        browser_states::BrowserState,
+        commands::{Action, Command},
+        errors::ProgramError,
+        git_log::{self, LogCommit},
+        git_log_verbs,
+        help_content::ScreenHelp,
+        help_states::HelpState,
+        screens::Screen,
+        status::Status,
+        task_sync::Dam,
+        tree_options::TreeOptions,
+        verb_store::PrefixSearchResult,
+        verbs::VerbExecutor,
+    },
+    chrono::{Local, TimeZone},
+    crossterm::{
+        terminal::{Clear, ClearType},
+        QueueableCommand,
+    },
+    std::{
+        io::{self, Write},
+        path::{Path, PathBuf},
+    },
+};
+
+/// a line of the log: a commit, or one of the files it changed
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Row {
+    Commit(usize),
+    File(usize, usize), // index of the commit, index of the file in the commit
+}
+
+/// an application state listing the recent commits which changed
+/// the selected directory or file. The files changed by a commit
+/// are listed below it when it's opened, and may be focused in
+/// the tree.
+/// In the log of a file (`:file_log`), opening a commit opens
+/// instead a copy of the file as it was at this commit.
+pub struct GitLogState {
+    path: PathBuf,
+    tree_options: TreeOptions, // the options of the tree opened on a file
+    file_log: bool,
+    commits: Vec<LogCommit>,
+    opened: Option<usize>, // the commit whose files are listed
+    pub selection: usize, // index in the rows
+    pub scroll: usize,
+}
+
+impl GitLogState {
+    pub fn new(path: PathBuf, tree_options: TreeOptions) -> io::Result<GitLogState> {
+        // the paths of the changed files are canonical
+        let path = path.canonicalize()?;
+        let commits = git_log::read_log(&path)?;
+        Ok(GitLogState {
+            path,
+            tree_options,
+            file_log: false,
+            commits,
+            opened: None,
+            selection: 0,
+            scroll: 0,
+        })
+    }
+
+    /// build the log of a file, whose commits open the versions of the file
+    pub fn for_file(path: PathBuf, tree_options: TreeOptions) -> io::Result<GitLogState> {
+        if path.is_dir() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "not a file"));
+        }
+        let mut state = GitLogState::new(path, tree_options)?;
+        state.file_log = true;
+        Ok(state)
+    }
+
+    pub fn is_file_log(&self) -> bool {
+        self.file_log
+    }
+
+    /// return the number of lines available for the rows
+    pub fn page_height(screen: &Screen) -> usize {
+        (screen.height as usize).max(3) - 3
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        for (commit_idx, commit) in self.commits.iter().enumerate() {
+            rows.push(Row::Commit(commit_idx));
+            if self.opened == Some(commit_idx) {
+                for file_idx in 0..commit.files.len() {
+                    rows.push(Row::File(commit_idx, file_idx));
+                }
+            }
+        }
+        rows
+    }
+
+    pub fn rows_count(&self) -> usize {
+        self.commits.len() + self.opened.map_or(0, |idx| self.commits[idx].files.len())
+    }
+
+    fn selected_row(&self) -> Option<Row> {
+        self.rows().get(self.selection).copied()
+    }
+
+    /// the selected file or, when a commit is selected, the path
+    /// the log was opened on
+    pub fn selected_path(&self) -> &Path {
+        match self.selected_row() {
+            Some(Row::File(commit_idx, file_idx)) => &self.commits[commit_idx].files[file_idx],
+            _ => &self.path,
+        }
+    }
+
+    pub fn move_selection(&mut self, dy: i32, page_height: usize) {
+        let len = self.rows_count() as i32;
+        if len == 0 {
+            return;
+        }
+        let selection = (self.selection as i32 + dy).max(0).min(len - 1) as usize;
+        self.selection = selection;
+        if selection < self.scroll {
+            self.scroll = selection;
+        } else if selection >= self.scroll + page_height {
+            self.scroll = selection + 1 - page_height;
+        }
+    }
+
+    /// list the files of the selected commit (or hide them), or
+    /// focus the selected file in a tree. In a file log, open the
+    /// version of the file at the selected commit.
+    pub fn open_selection(&mut self, screen: &Screen, con: &AppContext) -> AppStateCmdResult {
+        match self.selected_row() {
+            Some(Row::Commit(commit_idx)) if self.file_log => {
+                let version = git_log::write_version(&self.path, self.commits[commit_idx].id)
+                    .and_then(|copy| open::that(&copy));
+                match version {
+                    Ok(exit_status) => {
+                        info!("open returned with exit_status {:?}", exit_status);
+                        AppStateCmdResult::Keep
+                    }
+                    Err(e) => AppStateCmdResult::DisplayError(e.to_string()),
+                }
+            }
+            Some(Row::Commit(commit_idx)) => {
+                self.opened = if self.opened == Some(commit_idx) {
+                    None
+                } else {
+                    Some(commit_idx)
+                };
+                // the commits above aren't opened, so the selected one is at its index
+                self.selection = commit_idx;
+                self.move_selection(0, GitLogState::page_height(screen));
+                AppStateCmdResult::Keep
+            }
+            Some(Row::File(commit_idx, file_idx)) => {
+                let path = &self.commits[commit_idx].files[file_idx];
+                self.focus_file(path, screen, con)
+            }
+            None => AppStateCmdResult::DisplayError("no commit selected".to_string()),
+        }
+    }
+
+    /// open a tree on the directory of the file, with the file selected
+    fn focus_file(&self, path: &Path, screen: &Screen, con: &AppContext) -> AppStateCmdResult {
+        if !path.exists() {
+            return AppStateCmdResult::DisplayError(format!(
+                "{} isn't in the working tree anymore",
+                path.to_string_lossy(),
+            ));
+        }
+        let dir = match path.parent() {
+            Some(dir) => dir.to_path_buf(),
+            None => {
+                return AppStateCmdResult::DisplayError("no parent found".to_string());
+            }
+        };
+        let dam = Dam::unlimited();
+        match BrowserState::new(dir, self.tree_options.clone(), screen, con, &dam) {
+            Ok(Some(mut state)) => {
+                state.tree.try_select_path(path);
+                state.tree.make_selection_visible(BrowserState::page_height(screen));
+                AppStateCmdResult::NewState(Box::new(state), Command::new())
+            }
+            Ok(None) => AppStateCmdResult::Keep,
+            Err(e) => AppStateCmdResult::DisplayError(e.to_string()),
+        }
+    }
+
+    /// read the log again, as there may be new commits
+    pub fn reload(&mut self, screen: &Screen) -> AppStateCmdResult {
+        match git_log::read_log(&self.path) {
+            Ok(commits) => {
+                self.commits = commits;
+                self.opened = None;
+                self.move_selection(0, GitLogState::page_height(screen));
+                AppStateCmdResult::Keep
+            }
+            Err(e) => AppStateCmdResult::DisplayError(format!("no git log: {}", e)),
+        }
+    }
+}
+
+impl AppState for GitLogState {
+    fn help(&self) -> &'static ScreenHelp {
+        git_log_verbs::help(self)
+    }
+
+    fn has_pending_task(&self) -> bool {
+        false
+    }
+
+    fn can_execute(&self, _verb_index: usize, _con: &AppContext) -> bool {
+        true
+    }
+
+    fn apply(
+        &mut self,
+        cmd: &mut Command,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<AppStateCmdResult, ProgramError> {
+        let page_height = GitLogState::page_height(screen);
+        Ok(match &cmd.action {
+            Action::Back => AppStateCmdResult::PopState,
+            Action::Click(_, y) => {
+                let y = *y as usize;
+                if y > 0 && y <= page_height && self.scroll + y - 1 < self.rows_count() {
+                    self.selection = self.scroll + y - 1;
+                }
+                AppStateCmdResult::Keep
+            }
+            Action::DoubleClick(_, y) => {
+                if *y > 0 && self.selection + 1 == self.scroll + *y as usize {
+                    self.open_selection(screen, con)
+                } else {
+                    AppStateCmdResult::Keep
+                }
+            }
+            Action::Help => AppStateCmdResult::NewState(
+                Box::new(HelpState::new(screen, con, git_log_verbs::help(self))),
+                Command::new(),
+            ),
+            Action::MoveSelection(dy) | Action::Scroll(dy) => {
+                self.move_selection(*dy, page_height);
+                AppStateCmdResult::Keep
+            }
+            Action::OpenSelection => self.open_selection(screen, con),
+            Action::Resize(w, h) => {
+                screen.set_terminal_size(*w, *h, con);
+                AppStateCmdResult::Keep
+            }
+            Action::VerbIndex(index) => {
+                let verb = &con.verb_store.verbs[*index];
+                self.execute_verb(verb, &verb.invocation, screen, con)?
+            }
+            Action::VerbInvocate(invocation) => match con.verb_store.search(&invocation.name) {
+                PrefixSearchResult::Match(verb) => {
+                    self.execute_verb(verb, &invocation, screen, con)?
+                }
+                _ => AppStateCmdResult::verb_not_found(&invocation.name),
+            },
+            _ => AppStateCmdResult::Keep,
+        })
+    }
+
+    /// the log isn't read again when coming back from a tree, so that
+    /// the opened commit stays opened (the :refresh verb reads it again)
+    fn refresh(&mut self, _screen: &Screen, _con: &AppContext) -> Command {
+        Command::new()
+    }
+
+    fn do_pending_task(&mut self, _screen: &mut Screen, _con: &AppContext, _dam: &mut Dam) {
+        unreachable!();
+    }
+
+    fn display(
+        &mut self,
+        mut w: &mut dyn Write,
+        screen: &Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        let page_height = GitLogState::page_height(screen);
+        let width = screen.width as usize;
+        let author_width = self
+            .commits
+            .iter()
+            .map(|c| c.author.chars().count())
+            .max()
+            .unwrap_or(0)
+            .min(20);
+        screen.goto_clear(&mut w, 0, 0)?;
+        screen.skin.default.queue_bg(&mut w)?;
+        screen.skin.help_headers.queue(
+            &mut w,
+            format!(
+                "{} {}",
+                if self.file_log { "History of" } else { "Commits changing" },
+                self.path.to_string_lossy(),
+            ),
+        )?;
+        screen.clear_line(&mut w)?;
+        let rows = self.rows();
+        for y in 0..page_height {
+            screen.goto(&mut w, 0, y as u16 + 1)?;
+            let idx = self.scroll + y;
+            let selected = idx == self.selection;
+            match rows.get(idx) {
+                Some(Row::Commit(commit_idx)) => {
+                    let commit = &self.commits[*commit_idx];
+                    cond_bg!(tree_style, screen, selected, screen.skin.tree);
+                    cond_bg!(hash_style, screen, selected, screen.skin.count);
+                    cond_bg!(date_style, screen, selected, screen.skin.dates);
+                    cond_bg!(author_style, screen, selected, screen.skin.owner);
+                    cond_bg!(summary_style, screen, selected, screen.skin.file);
+                    let marker = if self.file_log {
+                        "  " // a commit of a file log has no file to list
+                    } else if self.opened == Some(*commit_idx) {
+                        "▾ "
+                    } else {
+                        "▸ "
+                    };
+                    tree_style.queue_str(&mut w, marker)?;
+                    hash_style.queue(&mut w, format!("{} ", commit.hash))?;
+                    let date = Local.timestamp(commit.seconds, 0);
+                    date_style.queue(&mut w, format!("{} ", date.format("%Y/%m/%d %R")))?;
+                    let author: String = commit.author.chars().take(author_width).collect();
+                    author_style.queue(&mut w, format!("{:aw$} ", author, aw = author_width))?;
+                    let used = 2 + commit.hash.len() + 17 + author_width + 1;
+                    let summary: String =
+                        commit.summary.chars().take(width.saturating_sub(used)).collect();
+                    summary_style.queue(&mut w, summary)?;
+                }
+                Some(Row::File(commit_idx, file_idx)) => {
+                    let path = &self.commits[*commit_idx].files[*file_idx];
+                    // the log of a file lists this file, which is named
+                    let name = match path.strip_prefix(&self.path) {
+                        Ok(rel) if !rel.as_os_str().is_empty() => rel.as_os_str(),
+                        _ => path.file_name().unwrap_or_else(|| path.as_os_str()),
+                    };
+                    let name = name.to_string_lossy().to_string();
+                    let name: String = name.chars().take(width.saturating_sub(5)).collect();
+                    cond_bg!(tree_style, screen, selected, screen.skin.tree);
+                    cond_bg!(file_style, screen, selected, screen.skin.file);
+                    cond_bg!(missing_style, screen, selected, screen.skin.file_error);
+                    tree_style.queue_str(&mut w, "    ")?;
+                    if path.exists() {
+                        file_style.queue(&mut w, name)?;
+                    } else {
+                        missing_style.queue(&mut w, name)?;
+                    }
+                }
+                None if idx == 0 => {
+                    screen.skin.tree.queue_str(&mut w, "No commit changed this path")?;
+                }
+                None => {}
+            }
+            if selected && idx < rows.len() {
+                screen.skin.selected_line.queue_bg(&mut w)?;
+            } else {
+                screen.skin.default.queue_bg(&mut w)?;
+            }
+            w.queue(Clear(ClearType::UntilNewLine))?;
+        }
+        Ok(())
+    }
+
+    fn write_status(
+        &self,
+        mut w: &mut dyn Write,
+        cmd: &Command,
+        screen: &Screen,
+        con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        match &cmd.action {
+            Action::VerbEdit(invocation) if !invocation.name.is_empty() => {
+                match con.verb_store.search(&invocation.name) {
+                    PrefixSearchResult::Match(verb) => verb.write_status(
+                        &mut w,
+                        None,
+                        self.selected_path().to_path_buf(),
+                        None,
+                        invocation,
+                        screen,
+                    ),
+                    _ => Status::from_error(mad_inline!("No matching verb"))
+                        .display(&mut w, screen),
+                }
+            }
+            _ => match self.selected_row() {
+                Some(Row::Commit(_)) if self.file_log => Status::from_message(mad_inline!(
+                    "Hit *enter* to open the file as it was at the selected commit"
+                ))
+                .display(&mut w, screen),
+                Some(Row::File(..)) => Status::from_message(mad_inline!(
+                    "Hit *enter* to focus the selected file in the tree, *esc* to get back"
+                ))
+                .display(&mut w, screen),
+                _ => Status::from_message(mad_inline!(
+                    "Hit *enter* to list the files of the selected commit, *esc* to get back"
+                ))
+                .display(&mut w, screen),
+            },
+        }
+    }
+
+    /// the flags area tells which commits are displayed
+    fn write_flags(
+        &self,
+        mut w: &mut dyn Write,
+        screen: &mut Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        screen.write_scroll_position(
+            &mut w,
+            self.scroll,
+            GitLogState::page_height(screen),
+            self.rows_count(),
+        )
+    }
+}
@@ -0,0 +1,122 @@
+//! this modules defines the execution of verbs on the git log screen
+
+use crate::{
+    app_context::AppContext,
+    app_state::AppStateCmdResult,
+    commands::Command,
+    errors::ProgramError,
+    external,
+    git_log_states::GitLogState,
+    help_content::{ScreenHelp, ScreenKey},
+    help_states::HelpState,
+    screens::Screen,
+    verb_invocation::VerbInvocation,
+    verbs::{Verb, VerbExecutor},
+};
+
+/// the help of the git log screen
+pub static HELP: ScreenHelp = ScreenHelp {
+    name: "git log screen",
+    description: "This screen lists the recent commits which changed the selection. \
+        Open a commit to list the files it changed, which can be focused in the tree.",
+    keys: &[
+        ScreenKey::new("esc", "get back to the tree"),
+        ScreenKey::new("↑ ↓", "move the selection"),
+        ScreenKey::new(
+            "enter",
+            "list the files of the selected commit, or focus the selected file",
+        ),
+        ScreenKey::new("?", "display this help"),
+    ],
+    builtins: Some(&[
+        ":back", ":file_log", ":focus", ":help", ":line_down", ":line_up", ":log", ":open_stay",
+        ":page_down", ":page_up", ":print_path", ":quit", ":refresh", ":select_first",
+        ":select_last",
+    ]),
+    external_verbs: true,
+};
+
+/// the help of the log of a file
+pub static FILE_LOG_HELP: ScreenHelp = ScreenHelp {
+    name: "file history screen",
+    description: "This screen lists the recent commits which changed the selected file. \
+        Open a commit to see the file as it was at this commit.",
+    keys: &[
+        ScreenKey::new("esc", "get back to the tree"),
+        ScreenKey::new("↑ ↓", "move the selection"),
+        ScreenKey::new("enter", "open a copy of the file as it was at the selected commit"),
+        ScreenKey::new("?", "display this help"),
+    ],
+    builtins: Some(&[
+        ":back", ":file_log", ":focus", ":help", ":line_down", ":line_up", ":log", ":open_stay",
+        ":page_down", ":page_up", ":print_path", ":quit", ":refresh", ":select_first",
+        ":select_last",
+    ]),
+    external_verbs: true,
+};
+
+/// return the help of the log, which depends on its kind
+pub fn help(state: &GitLogState) -> &'static ScreenHelp {
+    if state.is_file_log() {
+        &FILE_LOG_HELP
+    } else {
+        &HELP
+    }
+}
+
+impl VerbExecutor for GitLogState {
+    fn execute_verb(
+        &mut self,
+        verb: &Verb,
+        invocation: &VerbInvocation,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<AppStateCmdResult, ProgramError> {
+        if let Some(err) = verb.match_error(invocation) {
+            return Ok(AppStateCmdResult::DisplayError(err));
+        }
+        if let Some(err) = help(self).unlisted_error(verb) {
+            return Ok(AppStateCmdResult::DisplayError(err));
+        }
+        let page_height = GitLogState::page_height(screen);
+        let selected_path = self.selected_path().to_path_buf();
+        Ok(match verb.execution.as_ref() {
+            ":back" => AppStateCmdResult::PopState,
+            ":focus" | ":open_stay" => self.open_selection(screen, con),
+            ":help" => AppStateCmdResult::NewState(
+                Box::new(HelpState::new(screen, con, help(self))),
+                Command::new(),
+            ),
+            ":line_down" => {
+                self.move_selection(1, page_height);
+                AppStateCmdResult::Keep
+            }
+            ":line_up" => {
+                self.move_selection(-1, page_height);
+                AppStateCmdResult::Keep
+            }
+            ":file_log" | ":log" | ":refresh" => self.reload(screen),
+            ":page_down" => {
+                self.move_selection(page_height as i32, page_height);
+                AppStateCmdResult::Keep
+            }
+            ":page_up" => {
+                self.move_selection(-(page_height as i32), page_height);
+                AppStateCmdResult::Keep
+            }
+            ":print_path" => external::print_path(&selected_path, con)?,
+            ":quit" => AppStateCmdResult::Quit,
+            ":select_first" => {
+                self.move_selection(-(self.selection as i32), page_height);
+                AppStateCmdResult::Keep
+            }
+            ":select_last" => {
+                let len = self.rows_count() as i32;
+                self.move_selection(len, page_height);
+                AppStateCmdResult::Keep
+            }
+            _ if verb.execution.starts_with(':') => AppStateCmdResult::Keep,
+            _ => verb.to_cmd_result(&selected_path, None, &invocation.args, screen, con)?,
+        })
+    }
+}
@@ -6,7 +6,7 @@ use {
         Status,
     },
     std::{
-        collections::HashMap,
+        collections::{HashMap, HashSet},
         path::{
             Path,
             PathBuf,
@@ -14,41 +14,99 @@ use {
     },
 };
 
+const STAGED: Status = Status::from_bits_truncate(
+    Status::INDEX_NEW.bits()
+        | Status::INDEX_MODIFIED.bits()
+        | Status::INDEX_DELETED.bits()
+        | Status::INDEX_RENAMED.bits()
+        | Status::INDEX_TYPECHANGE.bits()
+);
+
 const INTERESTING: Status = Status::from_bits_truncate(
-    Status::WT_NEW.bits() | Status::CONFLICTED.bits() | Status::WT_MODIFIED.bits()
+    Status::WT_NEW.bits()
+        | Status::CONFLICTED.bits()
+        | Status::WT_MODIFIED.bits()
+        | STAGED.bits()
 );
 
-// if I add nothing, I'll remove this useless struct
-// and only use git2.Status
+/// the number of descendants of a directory having each
+/// interesting status (a file may be counted in several ones,
+/// for example when it's both staged and modified)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DirGitStats {
+    pub modified: usize,
+    pub new: usize,
+    pub staged: usize,
+    pub conflicted: usize,
+}
+
+impl DirGitStats {
+    pub fn add(&mut self, status: Status) {
+        if status.contains(Status::WT_MODIFIED) {
+            self.modified += 1;
+        }
+        if status.contains(Status::WT_NEW) {
+            self.new += 1;
+        }
+        if status.intersects(STAGED) {
+            self.staged += 1;
+        }
+        if status.contains(Status::CONFLICTED) {
+            self.conflicted += 1;
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct LineGitStatus {
     pub status: Status,
+    pub dir_stats: Option<DirGitStats>, // only for directories
 }
 
 impl LineGitStatus {
     pub fn from(repo: &Repository, relative_path: &Path) -> Option<LineGitStatus> {
         repo
             .status_file(&relative_path).ok()
-            .map(|status| LineGitStatus { status })
+            .map(|status| LineGitStatus { status, dir_stats: None })
     }
     pub fn is_interesting(&self) -> bool {
         self.status.intersects(INTERESTING)
     }
+    /// tell whether some changes were added to the index
+    pub fn is_staged(&self) -> bool {
+        self.status.intersects(STAGED)
+    }
 }
 
 pub struct LineStatusComputer {
     interesting_statuses: HashMap<PathBuf, Status>,
+    dir_statuses: HashMap<PathBuf, (Status, DirGitStats)>, // union and counts of the statuses of descendants
 }
 impl LineStatusComputer {
     pub fn from(repo: Repository) -> Self {
         let repo_path = repo.path().parent().unwrap().to_path_buf();
         let mut interesting_statuses = HashMap::new();
+        let mut dir_statuses: HashMap<PathBuf, (Status, DirGitStats)> = HashMap::new();
         if let Ok(statuses) = &repo.statuses(None) {
             for entry in statuses.iter() {
                 let status = entry.status();
                 if status.intersects(INTERESTING) {
                     if let Some(path) = entry.path() {
                         let path = repo_path.join(path);
+                        // we aggregate the status on all the ancestors which
+                        // are inside the repository
+                        let mut ancestor = path.parent();
+                        while let Some(dir) = ancestor {
+                            if !dir.starts_with(&repo_path) {
+                                break;
+                            }
+                            let (dir_status, dir_stats) = dir_statuses
+                                .entry(dir.to_path_buf())
+                                .or_insert_with(|| (Status::empty(), DirGitStats::default()));
+                            dir_status.insert(status);
+                            dir_stats.add(status);
+                            ancestor = dir.parent();
+                        }
                         interesting_statuses.insert(path, status);
                     }
                 }
@@ -58,16 +116,50 @@ impl LineStatusComputer {
         }
         Self {
             interesting_statuses,
+            dir_statuses,
         }
     }
+    /// return the status of a file, or the aggregated status of
+    /// its descendants for a directory
     pub fn line_status(&self, path: &Path) -> Option<LineGitStatus> {
-        self.interesting_statuses.get(path).map(|&status| LineGitStatus { status })
+        if let Some(&status) = self.interesting_statuses.get(path) {
+            return Some(LineGitStatus { status, dir_stats: None });
+        }
+        self.dir_statuses
+            .get(path)
+            .map(|&(status, dir_stats)| LineGitStatus { status, dir_stats: Some(dir_stats) })
     }
     pub fn is_interesting(&self, path: &Path) -> bool {
         self.interesting_statuses.contains_key(path)
     }
 }
 
+/// the files of the index of a repository, and their ancestors
+/// in the repository, that is the paths git tracks
+pub struct TrackedPaths {
+    paths: HashSet<PathBuf>,
+}
+impl TrackedPaths {
+    pub fn from(repo: &Repository) -> Option<Self> {
+        let workdir = repo.workdir()?;
+        let index = repo.index().ok()?;
+        let mut paths = HashSet::new();
+        for entry in index.iter() {
+            let path = workdir.join(String::from_utf8_lossy(&entry.path).as_ref());
+            let mut ancestor = Some(path.as_path());
+            while let Some(path) = ancestor {
+                if path == workdir || !paths.insert(path.to_path_buf()) {
+                    break;
+                }
+                ancestor = path.parent();
+            }
+        }
+        Some(Self { paths })
+    }
+    pub fn contains(&self, path: &Path) -> bool {
+        self.paths.contains(path)
+    }
+}
 
 ///
 #[derive(Debug, Clone)]
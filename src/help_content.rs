@@ -1,6 +1,8 @@
 use {
     crate::{
         app_context::AppContext,
+        patterns::Pattern,
+        verbs::Verb,
     },
     minimad::{
         Text,
@@ -8,6 +10,93 @@ use {
     },
 };
 
+/// a key of a screen. The keys are declared once, in the help of the
+/// screen, which lists them: the application gives the state only the
+/// keys it declares, and the status hints at the ones having a hint
+pub struct ScreenKey {
+    pub key: &'static str,          // e.g. "alt-enter", or "tab, shift-tab" for several keys
+    pub action: &'static str,       // what the key does, in the help (markdown)
+    pub hint: Option<&'static str>, // what the key does, in the status (e.g. "to get back")
+}
+
+impl ScreenKey {
+    pub const fn new(key: &'static str, action: &'static str) -> Self {
+        Self {
+            key,
+            action,
+            hint: None,
+        }
+    }
+    /// a key which is also hinted at in the status of the screen
+    pub const fn hinted(key: &'static str, action: &'static str, hint: &'static str) -> Self {
+        Self {
+            key,
+            action,
+            hint: Some(hint),
+        }
+    }
+}
+
+/// what the help tells about the screen it's opened from
+pub struct ScreenHelp {
+    pub name: &'static str,
+    pub description: &'static str, // markdown
+    pub keys: &'static [ScreenKey],
+    pub builtins: Option<&'static [&'static str]>, // the executions handled (a `*` ending a
+                                                   // prefix), None for all
+    pub external_verbs: bool, // whether the verbs launching programs apply
+}
+
+impl ScreenHelp {
+    /// tell whether the verb does something on the screen, and
+    /// must be listed in its help
+    pub fn lists(&self, verb: &Verb) -> bool {
+        if verb.execution.starts_with(':') {
+            match self.builtins {
+                Some(builtins) => builtins.iter().any(|&builtin| {
+                    if builtin.ends_with('*') {
+                        verb.execution.starts_with(&builtin[..builtin.len() - 1])
+                    } else {
+                        builtin == verb.execution
+                    }
+                }),
+                None => true,
+            }
+        } else {
+            self.external_verbs
+        }
+    }
+    /// return the error to display when the verb is a built-in which
+    /// isn't listed in the help. The verb executors check it before
+    /// matching the execution, so that the help lists exactly the
+    /// built-ins they execute
+    pub fn unlisted_error(&self, verb: &Verb) -> Option<String> {
+        if verb.execution.starts_with(':') && !self.lists(verb) {
+            Some(format!("{} isn't available on the {}", verb.execution, self.name))
+        } else {
+            None
+        }
+    }
+    /// tell whether the screen declares the key, named as in the
+    /// help (see `Action::key`)
+    pub fn declares_key(&self, key: &str) -> bool {
+        self.keys
+            .iter()
+            .any(|screen_key| screen_key.key.split(", ").any(|name| name == key))
+    }
+    /// the status telling what the hinted keys do, e.g.
+    /// "Hit *enter* to browse the selection, *esc* to get back"
+    pub fn keys_hint(&self) -> String {
+        let hints: Vec<String> = self
+            .keys
+            .iter()
+            .filter_map(|k| k.hint.map(|hint| format!("*{}* {}", k.key, hint)))
+            .collect();
+        format!("Hit {}", hints.join(", "))
+    }
+}
+
+/// the complete help, opened from the tree
 static MD: &str = r#"
 
 # broot ${version}
@@ -16,26 +105,41 @@ static MD: &str = r#"
 It's best used when launched as **br**.
 See **https://dystroy.org/broot** for a complete guide.
 
-The *esc* key gets you back to the previous state.
 Typing some letters searches the tree and selects the most relevant file.
 To use a regular expression, use a slash at start or end eg `/j(ava|s)$`.
-The *↑* and *↓* arrow keys can be used to change selection.
 The mouse can be used to select (on click) or open (on double-click).
 
+## Keys
+
+|:-:|:-
+|**key**|**action**
+|-:|:-
+${key-rows
+|${key}|${action}
+}
+|-:
+
 ## Verbs
 
 To execute a verb, type a space or `:` then start of its name or shortcut.
+Typing letters on this page filters the table, then *enter* puts the selected verb in the input.
 |:-:|:-:|:-:|:-:
 |**name**|**shortcut**|**key**|**description**
 |-:|:-:|:-:|:-
 ${verb-rows
-|${name}|${shortcut}|${key}|${description}`${execution}`
+|${mark}${name}|${shortcut}|${key}|${origin}${description}`${execution}`
 }
 |-:
 
 ## Configuration
 
 Verbs and skin can be configured in **${config-path}**.
+${project-conf
+The verbs marked *project* come from **${path}**.
+}
+${untrusted-project-conf
+The verbs of **${path}** weren't added, as its directory isn't in your `trusted_projects`.
+}
 
 ## Launch Arguments
 
@@ -44,35 +148,204 @@ Some options can be set on launch:
 * `-f` or `--only-folders` : only show folders
 * `-s` or `--sizes` : display sizes
 * `-d` or `--dates` : display last modified dates
+* `--date-source` : display and sort by the `modified` (default), `changed`, `accessed` or `created` date
+* `--freshness` : color the names of the files modified in the last month according to their age
+* `--icons` : write an icon before the names (needs a Nerd Font)
+* `--line-counts` : display the number of lines of text files
+* `-g` or `--show-git-info` : display git statuses of files and directories
+* `--git-status` : only show files having a git status (as `:toggle_git_status` does)
+* `--modified` : only show files modified in a date range, eg `--modified ">2020-03-01 <2020-04-01"`
+* `--watch` : refresh the tree when files are created, removed or renamed
+* `--max-depth` and `--max-children` : limit the depth of the tree and the number of children listed per directory
+* `--sort-by` : sort by `count`, `date` or `size` (only the first level is then displayed)
+* `--no-alt-screen` : draw on the normal screen, so that the tree stays in the terminal on exit
+* `--listen` : write the selection changes, executed verbs and final selection to the clients of a unix socket, eg `broot --listen /tmp/broot.sock`
+* `--out-format` : don't open the interface, apply the `--cmd` commands then print the tree as `json` or `paths`, eg `broot --cmd "rs" --out-format paths`
+Several directories may be given: they're then displayed together (more can be added with `:add_root`).
  (for the complete list, run `broot --help`)
 
+## Start Screen
+
+When `start_screen = true` is set in the configuration and broot is launched without path, it starts on a list of the bookmarks (also defined in the configuration) and of the directories it was recently launched on. Type to filter this list, then hit *enter* to browse the selected root.
+
+## Completion
+
+While typing a verb argument which is a path (eg `:mv ../som`), hit *tab* to complete it. When there are several possibilities, the input is completed up to their common part and they're listed in the status line.
+
+## Input History
+
+While typing a verb, hit *↑* and *↓* to recall the patterns and verbs you previously validated with *enter*.
+
+## Queued Commands
+
+Use `:queue` followed by a verb (eg `:queue mv ../archive`) to stage a command on the current selection without executing it. Stage as many as needed on different selections, then `:execute_queue` lists them and runs them all when you hit *enter*, telling which ones failed. They're run without input and their output isn't displayed, so interactive programs can't be queued.
+With `persist_queue = true` in the configuration, the queue is saved as it changes and proposed again when broot is launched on the same directory.
+
+## Git Statuses
+
+When git info is displayed, a letter precedes each line:
+* `M` : modified
+* `N` : new (untracked)
+* `S` : staged (changes were added to the index)
+* `C` : conflicted
+A directory shows the most urgent status among its descendants. With `:toggle_git_dir_stats` (or the `--git-dir-stats` launch argument) it also tells how many of them have each status, eg `3M 1N`.
+
 ## Flags
 
 Flags are displayed at bottom right:
+* `s:count`, `s:date`, `s:size` : the current sort mode, when there's one
+* `m:>2020-03-01` : the filter on modification dates, when there's one
+* `d:3` : the max depth of the displayed directories, set with `:set_max_depth`
+* `n:50` : the max number of children listed per directory, set with `:set_max_children`
+* `q:3` : the number of commands staged with `:queue`
 * `h:y` or `h:n` : whether hidden files are shown
 * `gi:a`, `gi:y`, `gi:n` : whether gitignore is on `auto`, `yes` or `no`
  When gitignore is auto, .gitignore rules are respected if the displayed root is a git repository or in one.
 "#;
 
-/// build the markdown which will be displayed in the help page
-pub fn build_text(con: &AppContext) -> Text<'_> {
+/// the help of a screen other than the tree: only its keys and verbs
+static SCREEN_MD: &str = r#"
+
+# broot ${version} : ${screen}
+
+${description}
+
+## Keys
+
+|:-:|:-
+|**key**|**action**
+|-:|:-
+${key-rows
+|${key}|${action}
+}
+|-:
+
+## Verbs
+
+To execute a verb, type a space or `:` then start of its name or shortcut.
+Typing letters on this page filters the table, then *enter* puts the selected verb in the input.
+|:-:|:-:|:-:|:-:
+|**name**|**shortcut**|**key**|**description**
+|-:|:-:|:-:|:-
+${verb-rows
+|${mark}${name}|${shortcut}|${key}|${origin}${description}`${execution}`
+}
+|-:
+
+The complete help, with all the verbs, is displayed when hitting *?* on the tree.
+"#;
+
+/// the help page when the user typed a pattern: only the matching verbs
+static FILTERED_MD: &str = r#"# Verbs matching `${pattern}`
+|:-:|:-:|:-:|:-:
+|**name**|**shortcut**|**key**|**description**
+|-:|:-:|:-:|:-
+${verb-rows
+|${mark}${name}|${shortcut}|${key}|${origin}${description}`${execution}`
+}
+|-:
+"#;
+
+/// the number of lines above the first verb row of the filtered page
+pub const FILTERED_HEADER_HEIGHT: usize = 4;
+
+/// the verbs displayed when the user typed a pattern
+pub struct VerbFilter<'s> {
+    pub pattern: &'s str,
+    pub verbs: &'s [usize], // indexes in the verb store, best matches first
+    pub selection: usize,   // index in verbs
+}
+
+/// return the indexes of the verbs listed in the help of the screen
+/// whose name, shortcut, key or execution is matched by the pattern,
+/// the best matches first
+pub fn matching_verbs(
+    con: &AppContext,
+    screen_help: &ScreenHelp,
+    pattern: &Pattern,
+) -> Vec<usize> {
+    let mut scored: Vec<(i32, usize)> = con
+        .verb_store
+        .verbs
+        .iter()
+        .enumerate()
+        .filter(|(_, verb)| screen_help.lists(verb))
+        .filter_map(|(idx, verb)| {
+            let shortcut = verb.shortcut.as_deref().unwrap_or("");
+            [&verb.invocation.name[..], shortcut, &verb.key_desc[..], &verb.execution[..]]
+                .iter()
+                .filter(|s| !s.is_empty())
+                .filter_map(|s| pattern.score_of(s))
+                .max()
+                .map(|score| (score, idx))
+        })
+        .collect();
+    // the sort is stable so the verbs stay in alphabetical order on equal scores
+    scored.sort_by(|a, b| b.0.cmp(&a.0));
+    scored.into_iter().map(|(_, idx)| idx).collect()
+}
+
+/// build the markdown which will be displayed in the help page of
+/// the screen, which is reduced to the matching verbs when there's
+/// a filter
+pub fn build_text<'s>(
+    con: &'s AppContext,
+    screen_help: &'s ScreenHelp,
+    filter: Option<VerbFilter<'s>>,
+) -> Text<'s> {
     lazy_static! {
         // this doesn't really matter, only half a ms is spared
         static ref TEMPLATE: TextTemplate<'static> = TextTemplate::from(MD);
+        static ref SCREEN_TEMPLATE: TextTemplate<'static> = TextTemplate::from(SCREEN_MD);
+        static ref FILTERED_TEMPLATE: TextTemplate<'static> = TextTemplate::from(FILTERED_MD);
     }
-    let mut expander = TEMPLATE.expander();
+    let mut expander = match (&filter, screen_help.builtins) {
+        (Some(_), _) => FILTERED_TEMPLATE.expander(),
+        (None, None) => TEMPLATE.expander(),
+        (None, Some(_)) => SCREEN_TEMPLATE.expander(),
+    };
     expander
         .set("version", env!("CARGO_PKG_VERSION"))
-        .set("config-path", &con.config_path);
-    for verb in &con.verb_store.verbs {
+        .set("config-path", &con.config_path)
+        .set("screen", screen_help.name)
+        .set_md("description", screen_help.description);
+    for key in screen_help.keys {
+        expander.sub("key-rows").set("key", key.key).set_md("action", key.action);
+    }
+    if let Some(path) = &con.project_conf_path {
+        let name = if con.launch_args.project_conf_trusted {
+            "project-conf"
+        } else {
+            "untrusted-project-conf"
+        };
+        expander.sub(name).set("path", path);
+    }
+    let rows: Vec<(&Verb, bool)> = match &filter {
+        Some(filter) => {
+            expander.set("pattern", filter.pattern);
+            filter
+                .verbs
+                .iter()
+                .enumerate()
+                .map(|(i, &idx)| (&con.verb_store.verbs[idx], i == filter.selection))
+                .collect()
+        }
+        None => con
+            .verb_store
+            .verbs
+            .iter()
+            .filter(|verb| screen_help.lists(verb))
+            .map(|verb| (verb, false))
+            .collect(),
+    };
+    for (verb, selected) in rows {
         let sub = expander
             .sub("verb-rows")
             .set("name", &verb.invocation.name)
-            .set(
-                "shortcut",
-                if let Some(sk) = &verb.shortcut { &sk } else { "" }, // TODO use as_deref when it's available
-            )
+            .set("shortcut", verb.shortcut.as_deref().unwrap_or(""))
             .set("key", &verb.key_desc);
+        sub.set_md("mark", if selected { "**➜** " } else { "" });
+        sub.set_md("origin", if verb.from_project { "*project* " } else { "" });
         if let Some(description) = &verb.description {
             sub.set_md("description", &description);
             sub.set("execution", "");
@@ -83,3 +356,29 @@ pub fn build_text(con: &AppContext) -> Text<'_> {
     }
     expander.expand()
 }
+
+#[cfg(test)]
+mod help_content_tests {
+
+    use super::*;
+
+    static HELP: ScreenHelp = ScreenHelp {
+        name: "test screen",
+        description: "",
+        keys: &[
+            ScreenKey::hinted("esc", "get back to the tree", "to get back"),
+            ScreenKey::new("tab, shift-tab", "select the next or previous match"),
+            ScreenKey::hinted("enter", "browse the selection", "to browse it"),
+        ],
+        builtins: Some(&[]),
+        external_verbs: false,
+    };
+
+    #[test]
+    fn check_keys() {
+        assert!(HELP.declares_key("esc"));
+        assert!(HELP.declares_key("shift-tab"));
+        assert!(!HELP.declares_key("alt-enter"));
+        assert_eq!(HELP.keys_hint(), "Hit *esc* to get back, *enter* to browse it");
+    }
+}
@@ -5,17 +5,15 @@ use {
         commands::{Action, Command},
         conf::Conf,
         errors::ProgramError,
-        help_content,
+        help_content::{self, ScreenHelp, VerbFilter},
+        help_verbs,
+        patterns::Pattern,
         screens::Screen,
         status::Status,
         task_sync::Dam,
         verb_store::PrefixSearchResult,
         verbs::VerbExecutor,
     },
-    crossterm::{
-        terminal::{Clear, ClearType},
-        QueueableCommand,
-    },
     minimad::Composite,
     std::io::Write,
     termimad::{Area, FmtText, TextView},
@@ -25,21 +23,106 @@ use {
 pub struct HelpState {
     pub scroll: i32, // scroll position
     pub area: Area,
+    content_height: usize, // number of lines of the formatted help, known once displayed
+    screen_help: &'static ScreenHelp, // the help of the screen it was opened from
     dirty: bool, // background must be cleared
+    pattern: Pattern, // when some, only the matching verbs are displayed
+    pattern_str: String, // the pattern, as typed
+    matching_verbs: Vec<usize>, // indexes in the verb store, best matches first
+    selection: usize, // index in matching_verbs
 }
 
 impl HelpState {
-    pub fn new(_screen: &Screen, _con: &AppContext) -> HelpState {
+    pub fn new(
+        _screen: &Screen,
+        _con: &AppContext,
+        screen_help: &'static ScreenHelp,
+    ) -> HelpState {
         let area = Area::uninitialized(); // will be fixed at drawing time
         HelpState {
             area,
             scroll: 0,
+            content_height: 0,
+            screen_help,
             dirty: true,
+            pattern: Pattern::None,
+            pattern_str: String::new(),
+            matching_verbs: Vec::new(),
+            selection: 0,
+        }
+    }
+
+    /// filter the verbs table with the pattern, or display the
+    /// whole help when there's none
+    fn set_pattern(&mut self, pattern: Pattern, con: &AppContext) {
+        self.matching_verbs = if pattern.is_some() {
+            help_content::matching_verbs(con, self.screen_help, &pattern)
+        } else {
+            Vec::new()
+        };
+        self.pattern_str = Command::from_pattern(&pattern).raw;
+        self.pattern = pattern;
+        self.selection = 0;
+        self.scroll = 0;
+        self.dirty = true;
+    }
+
+    /// move the selection among the matching verbs, scrolling
+    /// so that it stays visible
+    fn move_selection(&mut self, dy: i32) {
+        let len = self.matching_verbs.len() as i32;
+        if len == 0 {
+            return;
+        }
+        self.selection = (self.selection as i32 + dy).max(0).min(len - 1) as usize;
+        let y = (help_content::FILTERED_HEADER_HEIGHT + self.selection) as i32;
+        let height = self.area.height as i32;
+        if y < self.scroll {
+            self.scroll = y;
+        } else if y >= self.scroll + height {
+            self.scroll = y + 1 - height;
+        }
+    }
+
+    /// put the selected verb in the input, so that the user
+    /// may complete and execute it
+    fn prefill_selected_verb(&self, cmd: &mut Command, con: &AppContext) -> AppStateCmdResult {
+        let verb = match self.matching_verbs.get(self.selection) {
+            Some(&idx) => &con.verb_store.verbs[idx],
+            None => {
+                return AppStateCmdResult::DisplayError("no verb selected".to_string());
+            }
+        };
+        if verb.invocation.name.is_empty() {
+            return AppStateCmdResult::DisplayError(
+                "this verb is only triggered by its key".to_string(),
+            );
+        }
+        cmd.set_raw(if verb.invocation.args.is_some() {
+            format!(":{} ", verb.invocation.name)
+        } else {
+            format!(":{}", verb.invocation.name)
+        });
+        AppStateCmdResult::Keep
+    }
+
+    /// open the help of the help screen, unless it's the current one
+    pub fn open_own_help(&self, screen: &Screen, con: &AppContext) -> AppStateCmdResult {
+        if std::ptr::eq(self.screen_help, &help_verbs::HELP) {
+            AppStateCmdResult::Keep
+        } else {
+            AppStateCmdResult::NewState(
+                Box::new(HelpState::new(screen, con, &help_verbs::HELP)),
+                Command::new(),
+            )
         }
     }
 }
 
 impl AppState for HelpState {
+    fn help(&self) -> &'static ScreenHelp {
+        &help_verbs::HELP
+    }
 
     fn has_pending_task(&self) -> bool {
         false
@@ -60,11 +143,45 @@ impl AppState for HelpState {
         con: &AppContext,
     ) -> Result<AppStateCmdResult, ProgramError> {
         Ok(match &cmd.action {
-            Action::Back => AppStateCmdResult::PopState,
-            Action::MoveSelection(dy) => {
+            Action::Back => {
+                if self.pattern.is_some() {
+                    self.set_pattern(Pattern::None, con);
+                    cmd.raw.clear();
+                    AppStateCmdResult::Keep
+                } else {
+                    AppStateCmdResult::PopState
+                }
+            }
+            Action::FuzzyPatternEdit(pat) => {
+                self.set_pattern(
+                    if pat.is_empty() {
+                        Pattern::None
+                    } else {
+                        Pattern::fuzzy(pat)
+                    },
+                    con,
+                );
+                AppStateCmdResult::Keep
+            }
+            Action::RegexEdit(pat, flags) => match Pattern::regex(pat, flags) {
+                Ok(pattern) => {
+                    self.set_pattern(pattern, con);
+                    AppStateCmdResult::Keep
+                }
+                Err(e) => AppStateCmdResult::DisplayError(e.to_string()),
+            },
+            Action::MoveSelection(dy) if self.pattern.is_some() => {
+                self.move_selection(*dy);
+                AppStateCmdResult::Keep
+            }
+            Action::MoveSelection(dy) | Action::Scroll(dy) => {
                 self.scroll += *dy;
                 AppStateCmdResult::Keep
             }
+            Action::OpenSelection if self.pattern.is_some() => {
+                self.prefill_selected_verb(cmd, con)
+            }
+            Action::Help => self.open_own_help(screen, con),
             Action::Resize(w, h) => {
                 screen.set_terminal_size(*w, *h, con);
                 self.dirty = true;
@@ -88,7 +205,7 @@ impl AppState for HelpState {
         Command::new()
     }
 
-    fn do_pending_task(&mut self, _screen: &mut Screen, _dam: &mut Dam) {
+    fn do_pending_task(&mut self, _screen: &mut Screen, _con: &AppContext, _dam: &mut Dam) {
         unreachable!();
     }
 
@@ -105,12 +222,22 @@ impl AppState for HelpState {
             self.area.pad_for_max_width(110);
             self.dirty = false;
         }
-        let text = help_content::build_text(con);
+        let filter = if self.pattern.is_some() {
+            Some(VerbFilter {
+                pattern: &self.pattern_str,
+                verbs: &self.matching_verbs,
+                selection: self.selection,
+            })
+        } else {
+            None
+        };
+        let text = help_content::build_text(con, self.screen_help, filter);
         let fmt_text = FmtText::from_text(
             &screen.help_skin,
             text,
             Some((self.area.width - 1) as usize),
         );
+        self.content_height = fmt_text.lines.len();
         let mut text_view = TextView::from(&self.area, &fmt_text);
         self.scroll = text_view.set_scroll(self.scroll);
         Ok(text_view.write_on(&mut w)?)
@@ -140,6 +267,7 @@ impl AppState for HelpState {
                             &mut w,
                             None,
                             Conf::default_location(),
+                            None,
                             invocation,
                             screen,
                         ),
@@ -157,22 +285,34 @@ impl AppState for HelpState {
                     }
                 }
             }
-            _ => Status::from_message(mad_inline!(
-                "Hit *esc* to get back to the tree, or a space to start a verb"
+            _ if self.pattern.is_some() && self.matching_verbs.is_empty() => {
+                Status::from_error(mad_inline!("No verb matches the pattern"))
+                    .display(&mut w, screen)
+            }
+            _ if self.pattern.is_some() => Status::from_message(mad_inline!(
+                "Hit *enter* to put the selected verb in the input, *esc* to see the whole help"
             ))
             .display(&mut w, screen),
+            _ => Status::from_message(Composite::from_inline(&format!(
+                "Hit *esc* to get back to the {}, type to filter the verbs, or a space for a verb",
+                self.screen_help.name,
+            )))
+            .display(&mut w, screen),
         }
     }
 
-    /// there's no meaningful flags here
+    /// the flags area tells which part of a long help is displayed
     fn write_flags(
         &self,
         mut w: &mut dyn Write,
         screen: &mut Screen,
         _con: &AppContext,
     ) -> Result<(), ProgramError> {
-        screen.skin.default.queue_bg(&mut w)?;
-        w.queue(Clear(ClearType::UntilNewLine))?;
-        Ok(())
+        screen.write_scroll_position(
+            &mut w,
+            self.scroll.max(0) as usize,
+            self.area.height as usize,
+            self.content_height,
+        )
     }
 }
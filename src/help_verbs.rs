@@ -6,8 +6,10 @@ use crate::{
     browser_states::BrowserState,
     commands::Command,
     conf::{self, Conf},
+    conf_doc_states::ConfDocState,
     errors::ProgramError,
     external::{self, Launchable},
+    help_content::{ScreenHelp, ScreenKey},
     help_states::HelpState,
     screens::Screen,
     task_sync::Dam,
@@ -16,6 +18,25 @@ use crate::{
     verbs::{Verb, VerbExecutor},
 };
 
+/// the help of the help screen
+pub static HELP: ScreenHelp = ScreenHelp {
+    name: "help screen",
+    description: "The help screen lists the keys and verbs of the screen it was opened from. \
+        Its verbs apply to the configuration file.",
+    keys: &[
+        ScreenKey::new("esc", "get back to the previous screen, or display the whole help again"),
+        ScreenKey::new("↑ ↓", "scroll, or move the selection among the filtered verbs"),
+        ScreenKey::new("enter", "put the selected verb in the input"),
+        ScreenKey::new("?", "display this help"),
+    ],
+    builtins: Some(&[
+        ":back", ":config_doc", ":focus", ":parent", ":help", ":line_down", ":line_up",
+        ":open_stay", ":open_leave", ":page_down", ":page_up", ":print_path",
+        ":print_relative_path", ":quit", ":focus_user_home", ":focus_root", ":toggle_*",
+    ]),
+    external_verbs: true,
+};
+
 impl VerbExecutor for HelpState {
     fn execute_verb(
         &mut self,
@@ -27,6 +48,9 @@ impl VerbExecutor for HelpState {
         if let Some(err) = verb.match_error(invocation) {
             return Ok(AppStateCmdResult::DisplayError(err));
         }
+        if let Some(err) = HELP.unlisted_error(verb) {
+            return Ok(AppStateCmdResult::DisplayError(err));
+        }
         Ok(match verb.execution.as_ref() {
             ":back" => AppStateCmdResult::PopState,
             ":focus" | ":parent" => AppStateCmdResult::from_optional_state(
@@ -34,11 +58,15 @@ impl VerbExecutor for HelpState {
                     conf::dir(),
                     TreeOptions::default(),
                     screen,
+                    con,
                     &Dam::unlimited(),
                 ),
                 Command::new(),
             ),
-            ":help" => AppStateCmdResult::Keep,
+            ":config_doc" => {
+                AppStateCmdResult::NewState(Box::new(ConfDocState::new(con)), Command::new())
+            }
+            ":help" => self.open_own_help(screen, con),
             ":line_down" => {
                 self.scroll += 1;
                 AppStateCmdResult::Keep
@@ -73,6 +101,7 @@ impl VerbExecutor for HelpState {
             _ if verb.execution.starts_with(':') => AppStateCmdResult::Keep,
             _ => verb.to_cmd_result(
                 &Conf::default_location(),
+                None,
                 &invocation.args,
                 screen,
                 con,
@@ -0,0 +1,175 @@
+//! icons written before the names of files, chosen according to
+//! their kind and extension.
+//!
+//! The default glyphs are the ones of the Nerd Fonts, so they're only
+//! rendered correctly when the terminal uses such a font.
+
+use {
+    crate::flat_tree::{LineType, TreeLine},
+    std::collections::HashMap,
+};
+
+/// glyphs of the kinds of files, used when there's no icon for the extension
+const DIR: &str = "\u{f115}";
+const FILE: &str = "\u{f15b}";
+const LINK: &str = "\u{f0c1}";
+const EXE: &str = "\u{f489}";
+
+/// the default glyphs, by extension
+static EXTENSION_ICONS: &[(&str, &str)] = &[
+    ("7z", "\u{f410}"),
+    ("bash", "\u{f489}"),
+    ("bmp", "\u{f1c5}"),
+    ("c", "\u{e61e}"),
+    ("cc", "\u{e61d}"),
+    ("conf", "\u{e615}"),
+    ("cpp", "\u{e61d}"),
+    ("cs", "\u{f81a}"),
+    ("css", "\u{e749}"),
+    ("csv", "\u{f1c3}"),
+    ("doc", "\u{f1c2}"),
+    ("docx", "\u{f1c2}"),
+    ("fish", "\u{f489}"),
+    ("gif", "\u{f1c5}"),
+    ("go", "\u{e626}"),
+    ("gz", "\u{f410}"),
+    ("h", "\u{f0fd}"),
+    ("hpp", "\u{f0fd}"),
+    ("hs", "\u{e777}"),
+    ("htm", "\u{e736}"),
+    ("html", "\u{e736}"),
+    ("ini", "\u{e615}"),
+    ("java", "\u{e738}"),
+    ("jpeg", "\u{f1c5}"),
+    ("jpg", "\u{f1c5}"),
+    ("js", "\u{e74e}"),
+    ("json", "\u{e60b}"),
+    ("jsx", "\u{e7ba}"),
+    ("kt", "\u{e634}"),
+    ("lock", "\u{f023}"),
+    ("log", "\u{f18d}"),
+    ("lua", "\u{e620}"),
+    ("md", "\u{e609}"),
+    ("mp3", "\u{f001}"),
+    ("mp4", "\u{f03d}"),
+    ("pdf", "\u{f1c1}"),
+    ("php", "\u{e73d}"),
+    ("png", "\u{f1c5}"),
+    ("ppt", "\u{f1c4}"),
+    ("py", "\u{e73c}"),
+    ("rb", "\u{e21e}"),
+    ("rs", "\u{e7a8}"),
+    ("scss", "\u{e749}"),
+    ("sh", "\u{f489}"),
+    ("sql", "\u{f1c0}"),
+    ("svg", "\u{f1c5}"),
+    ("swift", "\u{e755}"),
+    ("tar", "\u{f410}"),
+    ("toml", "\u{e615}"),
+    ("ts", "\u{e628}"),
+    ("tsx", "\u{e7ba}"),
+    ("txt", "\u{f15c}"),
+    ("vim", "\u{e62b}"),
+    ("wav", "\u{f001}"),
+    ("xls", "\u{f1c3}"),
+    ("xlsx", "\u{f1c3}"),
+    ("xml", "\u{e619}"),
+    ("xz", "\u{f410}"),
+    ("yaml", "\u{e615}"),
+    ("yml", "\u{e615}"),
+    ("zip", "\u{f410}"),
+    ("zsh", "\u{f489}"),
+];
+
+/// the glyphs to use, built from the default ones and the ones
+/// of the [icons] section of the configuration
+#[derive(Debug, Clone)]
+pub struct Icons {
+    dir: String,
+    file: String,
+    link: String,
+    exe: String,
+    extensions: HashMap<String, String>, // keys are lowercase
+}
+
+impl Icons {
+    /// build the icons, the ones of the configuration replacing the
+    /// default ones. Configuration keys are extensions, apart the
+    /// special ":dir", ":file", ":link" and ":exe" ones.
+    pub fn new(conf_icons: &HashMap<String, String>) -> Icons {
+        let mut icons = Icons {
+            dir: DIR.to_string(),
+            file: FILE.to_string(),
+            link: LINK.to_string(),
+            exe: EXE.to_string(),
+            extensions: EXTENSION_ICONS
+                .iter()
+                .map(|(ext, glyph)| (ext.to_string(), glyph.to_string()))
+                .collect(),
+        };
+        for (key, glyph) in conf_icons {
+            let glyph = glyph.clone();
+            match key.as_str() {
+                ":dir" => icons.dir = glyph,
+                ":file" => icons.file = glyph,
+                ":link" => icons.link = glyph,
+                ":exe" => icons.exe = glyph,
+                ext => {
+                    icons.extensions.insert(ext.to_lowercase(), glyph);
+                }
+            }
+        }
+        icons
+    }
+
+    /// return the glyph of a file which isn't a directory nor a link
+    fn file_icon(&self, name: &str, exe: bool) -> &str {
+        let ext = match name.rfind('.') {
+            Some(idx) if idx > 0 => Some(name[idx + 1..].to_lowercase()),
+            _ => None,
+        };
+        if let Some(glyph) = ext.and_then(|ext| self.extensions.get(&ext)) {
+            glyph
+        } else if exe {
+            &self.exe
+        } else {
+            &self.file
+        }
+    }
+
+    /// return the glyph to display before the name of the line,
+    /// or None if the line isn't a file (ie a pruning line)
+    pub fn icon_of(&self, line: &TreeLine) -> Option<&str> {
+        match &line.line_type {
+            LineType::Dir => Some(&self.dir),
+            LineType::File => Some(self.file_icon(&line.name, line.is_exe())),
+            LineType::SymLinkToFile(_) | LineType::SymLinkToDir(_) => Some(&self.link),
+            LineType::Pruning => None,
+        }
+    }
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Icons::new(&HashMap::new())
+    }
+}
+
+#[cfg(test)]
+mod icons_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_file_icons() {
+        let mut conf_icons = HashMap::new();
+        conf_icons.insert("RS".to_string(), "R".to_string());
+        conf_icons.insert(":exe".to_string(), "X".to_string());
+        let icons = Icons::new(&conf_icons);
+        assert_eq!(icons.file_icon("main.rs", false), "R");
+        assert_eq!(icons.file_icon("Cargo.TOML", false), "\u{e615}");
+        assert_eq!(icons.file_icon("build", true), "X");
+        assert_eq!(icons.file_icon("README", false), FILE);
+        assert_eq!(icons.file_icon(".bashrc", false), FILE);
+    }
+}
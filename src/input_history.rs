@@ -0,0 +1,144 @@
+//! the history of the inputs (patterns and verb invocations) which
+//! were validated with enter, recalled with the up and down arrows
+//! like in a shell.
+//!
+//! The history may be persisted between sessions, in broot's cache.
+
+use {
+    crate::conf,
+    std::{fs, io, path::PathBuf},
+};
+
+/// the maximal number of entries, older ones being forgotten
+const MAX_ENTRIES: usize = 500;
+
+/// the position in the history while the user browses it
+#[derive(Debug)]
+struct Browsing {
+    idx: usize,    // index of the recalled entry
+    typed: String, // what was in the input before browsing
+}
+
+#[derive(Debug, Default)]
+pub struct InputHistory {
+    entries: Vec<String>, // the oldest first
+    browsing: Option<Browsing>,
+    persisted: bool,
+}
+
+fn file_path() -> PathBuf {
+    conf::app_dirs().cache_dir().join("input_history")
+}
+
+impl InputHistory {
+    /// create the history, with the entries of the previous
+    /// sessions when it's persisted
+    pub fn new(persisted: bool) -> Self {
+        let entries = if persisted {
+            fs::read_to_string(file_path())
+                .map(|content| content.lines().map(str::to_string).collect())
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        Self {
+            entries,
+            browsing: None,
+            persisted,
+        }
+    }
+    /// remember a validated input, unless it's the same as the last one
+    pub fn push(&mut self, raw: &str) {
+        self.browsing = None;
+        // the leading space of a verb invocation matters, so the input isn't trimmed
+        if raw.trim().is_empty() || raw.contains('\n') {
+            return;
+        }
+        if self.entries.last().map_or(false, |e| e == raw) {
+            return;
+        }
+        self.entries.push(raw.to_string());
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+    }
+    pub fn is_browsing(&self) -> bool {
+        self.browsing.is_some()
+    }
+    /// end the browsing, the input being kept as it is
+    pub fn stop_browsing(&mut self) {
+        self.browsing = None;
+    }
+    /// return the entry before the one currently recalled, or the
+    /// last one if the browsing starts, `current` being the input
+    /// to give back when the browsing goes past the last entry
+    pub fn previous(&mut self, current: &str) -> Option<String> {
+        let idx = match &self.browsing {
+            Some(browsing) if browsing.idx > 0 => browsing.idx - 1,
+            Some(_) => {
+                return None; // we're already on the oldest entry
+            }
+            None if self.entries.is_empty() => {
+                return None;
+            }
+            None => self.entries.len() - 1,
+        };
+        let typed = self
+            .browsing
+            .take()
+            .map_or_else(|| current.to_string(), |browsing| browsing.typed);
+        self.browsing = Some(Browsing { idx, typed });
+        Some(self.entries[idx].clone())
+    }
+    /// return the entry after the one currently recalled or, after the
+    /// last one, the input which was there before browsing
+    pub fn next(&mut self) -> Option<String> {
+        let browsing = self.browsing.take()?;
+        if browsing.idx + 1 < self.entries.len() {
+            let entry = self.entries[browsing.idx + 1].clone();
+            self.browsing = Some(Browsing {
+                idx: browsing.idx + 1,
+                typed: browsing.typed,
+            });
+            Some(entry)
+        } else {
+            Some(browsing.typed)
+        }
+    }
+    /// write the history in broot's cache, if it's persisted
+    pub fn save(&self) -> io::Result<()> {
+        if !self.persisted {
+            return Ok(());
+        }
+        let file_path = file_path();
+        if let Some(dir) = file_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut content = self.entries.join("\n");
+        content.push('\n');
+        fs::write(file_path, content)
+    }
+}
+
+#[cfg(test)]
+mod input_history_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_browsing() {
+        let mut history = InputHistory::default();
+        assert_eq!(history.previous("typ"), None);
+        history.push("src");
+        history.push(":rm");
+        history.push(":rm");
+        history.push("");
+        assert_eq!(history.previous("typ"), Some(":rm".to_string()));
+        assert_eq!(history.previous(":rm"), Some("src".to_string()));
+        assert_eq!(history.previous("src"), None);
+        assert_eq!(history.next(), Some(":rm".to_string()));
+        assert_eq!(history.next(), Some("typ".to_string()));
+        assert!(!history.is_browsing());
+        assert_eq!(history.next(), None);
+    }
+}
@@ -18,44 +18,115 @@ pub mod displayable_tree;
 
 pub mod app;
 pub mod app_context;
+pub mod app_stack;
 pub mod app_state;
+pub mod archives;
 pub mod browser_states;
 pub mod browser_verbs;
+pub mod bulk_rename;
 pub mod clap;
 pub mod cli;
+pub mod code_stats;
+pub mod code_stats_states;
+pub mod code_stats_verbs;
+pub mod collation;
+pub mod columns;
 pub mod command_parsing;
 pub mod commands;
 pub mod conf;
+pub mod conf_doc;
+pub mod conf_doc_states;
+pub mod confirm_states;
+pub mod copy_states;
+pub mod copy_verbs;
+pub mod date_filter;
+pub mod date_source;
+pub mod deep_dates;
 pub mod errors;
+pub mod exec_queue;
+pub mod ext_filter;
 pub mod external;
+pub mod file_copy;
+pub mod file_locks;
+pub mod file_metadata;
 pub mod file_sizes;
+pub mod filesystems;
+pub mod filesystems_states;
+pub mod filesystems_verbs;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
 pub mod flat_tree;
+pub mod fs_watch;
 pub mod fuzzy_patterns;
 pub mod git;
 pub mod git_ignore;
+pub mod git_ignore_edit;
+pub mod git_ignore_states;
+pub mod git_log;
+pub mod git_log_states;
+pub mod git_log_verbs;
 pub mod git_status;
 pub mod git_status_computer;
 pub mod git_status_display;
 pub mod help_content;
 pub mod help_states;
 pub mod help_verbs;
+pub mod icons;
+pub mod input_history;
 pub mod io;
 pub mod keys;
+pub mod line_counts;
 pub mod mad_skin;
 pub mod matched_string;
+pub mod nested_launch;
+pub mod notes;
+pub mod op_journal;
+pub mod organize;
+pub mod outcome_channel;
+pub mod palette_states;
+pub mod path_completion;
+pub mod path_display;
+pub mod path_store;
 pub mod patterns;
+pub mod portable_names;
 pub mod permissions;
+pub mod previews;
+pub mod profiles;
+pub mod queue_states;
+pub mod recent_roots;
 pub mod regex_patterns;
+pub mod remote;
+pub mod rename_states;
+pub mod rename_verbs;
+pub mod resource_limits;
+pub mod roots;
 pub mod screens;
 pub mod selection_type;
 pub mod shell_install;
+pub mod shell_output_states;
+pub mod size_report;
 pub mod skin;
 pub mod skin_conf;
+pub mod skin_presets;
+pub mod sort;
+pub mod special_paths;
+pub mod start_states;
+pub mod start_verbs;
 pub mod status;
+pub mod tags;
 pub mod task_sync;
+pub mod theme_mode;
+pub mod tie_breaker;
+pub mod trash;
+pub mod trash_states;
+pub mod trash_verbs;
 pub mod tree_build;
+pub mod tree_export;
 pub mod tree_options;
+pub mod usage_states;
+pub mod usage_stats;
 pub mod verb_conf;
+pub mod verb_export;
 pub mod verb_invocation;
 pub mod verb_store;
 pub mod verbs;
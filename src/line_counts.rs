@@ -0,0 +1,93 @@
+//! count the lines of text files.
+//!
+//! Counts are computed only when displayed, and cached as
+//! reading a file is costly. Files containing a NUL byte are
+//! considered binary and have no line count.
+
+use {
+    std::{
+        collections::HashMap,
+        fs::File,
+        io::{self, Read},
+        path::{Path, PathBuf},
+        sync::Mutex,
+    },
+};
+
+lazy_static! {
+    static ref LINE_COUNT_CACHE_MUTEX: Mutex<HashMap<PathBuf, Option<usize>>> =
+        Mutex::new(HashMap::new());
+}
+
+pub fn clear_cache() {
+    let mut line_count_cache = LINE_COUNT_CACHE_MUTEX.lock().unwrap();
+    line_count_cache.clear();
+}
+
+/// forget the counts of the changed path and of its descendants
+pub fn forget_path(changed: &Path) {
+    let mut line_count_cache = LINE_COUNT_CACHE_MUTEX.lock().unwrap();
+    line_count_cache.retain(|path, _| !path.starts_with(changed));
+}
+
+/// return the number of lines of the file, or None if it's not
+/// a readable text file
+pub fn line_count(path: &Path) -> Option<usize> {
+    let mut line_count_cache = LINE_COUNT_CACHE_MUTEX.lock().unwrap();
+    if let Some(count) = line_count_cache.get(path) {
+        return *count;
+    }
+    let count = File::open(path)
+        .and_then(count_lines)
+        .unwrap_or(None);
+    line_count_cache.insert(PathBuf::from(path), count);
+    count
+}
+
+/// count the lines of a text, the last one being counted even
+/// when it doesn't end with a newline. Return None when the
+/// content looks binary.
+fn count_lines(mut reader: impl Read) -> io::Result<Option<usize>> {
+    let mut buf = [0u8; 16 * 1024];
+    let mut count = 0;
+    let mut last_byte = b'\n';
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        for &b in &buf[..n] {
+            match b {
+                0 => return Ok(None),
+                b'\n' => count += 1,
+                _ => {}
+            }
+        }
+        last_byte = buf[n - 1];
+    }
+    if last_byte != b'\n' {
+        count += 1;
+    }
+    Ok(Some(count))
+}
+
+#[cfg(test)]
+mod line_counts_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_count_lines() {
+        let check = |content: &[u8], expected: Option<usize>| {
+            assert_eq!(count_lines(content).unwrap(), expected);
+        };
+        check(b"", Some(0));
+        check(b"one line", Some(1));
+        check(b"one line\n", Some(1));
+        check(b"two\nlines", Some(2));
+        check(b"\n\n\n", Some(3));
+        check(b"not\0text\n", None);
+    }
+}
@@ -36,16 +36,30 @@ impl<'a> MatchedString<'a> {
     {
         if self.pattern.is_some() {
             if let Some(m) = self.pattern.find(self.string) {
-                let mut pos_idx: usize = 0;
                 let mut combined_style = self.base_style.clone();
                 combined_style.overwrite_with(self.match_style);
-                for (cand_idx, cand_char) in self.string.chars().enumerate() {
-                    if pos_idx < m.pos.len() && m.pos[pos_idx] == cand_idx {
-                        combined_style.queue(w, cand_char)?;
+                // consecutive chars with the same style are written as
+                // one slice of the string
+                let mut pos_idx: usize = 0;
+                let mut run_start: usize = 0; // byte index of the current run
+                let mut run_matched = false;
+                for (cand_idx, (byte_idx, _)) in self.string.char_indices().enumerate() {
+                    let matched = pos_idx < m.pos.len() && m.pos[pos_idx] == cand_idx;
+                    if matched {
                         pos_idx += 1;
-                    } else {
-                        self.base_style.queue(w, cand_char)?;
                     }
+                    if matched != run_matched {
+                        if byte_idx > run_start {
+                            let style = if run_matched { &combined_style } else { self.base_style };
+                            style.queue_str(w, &self.string[run_start..byte_idx])?;
+                        }
+                        run_start = byte_idx;
+                        run_matched = matched;
+                    }
+                }
+                if run_start < self.string.len() {
+                    let style = if run_matched { &combined_style } else { self.base_style };
+                    style.queue_str(w, &self.string[run_start..])?;
                 }
                 return Ok(());
             }
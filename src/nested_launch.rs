@@ -0,0 +1,197 @@
+//! the handling of a broot launched inside another broot, which
+//! usually happens by accident, from a verb running a shell or a
+//! script calling broot.
+//!
+//! An interactive broot listens on a control socket whose path is
+//! given to the programs it launches in the `BROOT_CONTROL_SOCKET`
+//! environment variable. A broot finding this variable may, instead
+//! of starting a second interface, send the path it was launched on
+//! to the outer broot, which opens it when it gets back the terminal.
+//! The `nested_launch` conf entry tells whether to "ask", "send" or
+//! "start".
+
+use {
+    crossbeam::channel::Receiver,
+    std::{
+        io::{self, BufRead, Write},
+        path::{Path, PathBuf},
+    },
+};
+
+#[cfg(unix)]
+use {
+    crate::conf,
+    crossbeam::channel::unbounded,
+    std::{
+        env, fs,
+        io::BufReader,
+        os::unix::net::{UnixListener, UnixStream},
+        process, thread,
+    },
+};
+
+/// the environment variable through which the outer broot
+/// gives the path of its control socket
+pub const ENV_VAR: &str = "BROOT_CONTROL_SOCKET";
+
+/// what a broot launched inside another broot does
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NestedLaunch {
+    Ask,   // ask the user whether to send the path or to start
+    Send,  // send the path to the outer broot and quit
+    Start, // start a second broot, ignoring the outer one
+}
+
+impl Default for NestedLaunch {
+    fn default() -> Self {
+        NestedLaunch::Ask
+    }
+}
+
+impl NestedLaunch {
+    pub fn from_name(name: &str) -> Option<NestedLaunch> {
+        match name {
+            "ask" => Some(NestedLaunch::Ask),
+            "send" => Some(NestedLaunch::Send),
+            "start" => Some(NestedLaunch::Start),
+            _ => None,
+        }
+    }
+}
+
+/// return the control socket of the outer broot, if this
+/// broot was launched by another one which still listens
+#[cfg(unix)]
+pub fn outer_socket() -> Option<PathBuf> {
+    let path = PathBuf::from(env::var_os(ENV_VAR)?);
+    if path.exists() {
+        Some(path)
+    } else {
+        None
+    }
+}
+#[cfg(not(unix))]
+pub fn outer_socket() -> Option<PathBuf> {
+    None
+}
+
+/// ask the user, before any interface is drawn, whether the
+/// path should be sent to the outer broot
+pub fn ask_send(path: &Path) -> io::Result<bool> {
+    let mut stderr = io::stderr();
+    write!(
+        stderr,
+        "broot is already running and launched this one.\n\
+        Open {:?} in the running broot instead of starting another one? [Y/n] ",
+        path,
+    )?;
+    stderr.flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(!answer.trim().eq_ignore_ascii_case("n"))
+}
+
+/// send the path to the outer broot
+#[cfg(unix)]
+pub fn send_path(socket_path: &Path, path: &Path) -> io::Result<()> {
+    let mut stream = UnixStream::connect(socket_path)?;
+    writeln!(stream, "{}", path.to_string_lossy())?;
+    Ok(())
+}
+#[cfg(not(unix))]
+pub fn send_path(_socket_path: &Path, _path: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        "nested launches are only detected on unix systems",
+    ))
+}
+
+/// the socket on which an interactive broot receives the
+/// paths sent by the broots it launched
+#[cfg(unix)]
+pub struct ControlSocket {
+    socket_path: PathBuf,
+    pub paths: Receiver<PathBuf>,
+}
+
+#[cfg(unix)]
+impl ControlSocket {
+    /// create the socket, accept the nested broots in a background
+    /// thread, and tell the programs launched from now on about it
+    pub fn bind() -> io::Result<Self> {
+        let dirs = conf::app_dirs();
+        let dir = dirs.runtime_dir().unwrap_or_else(|| dirs.cache_dir());
+        fs::create_dir_all(dir)?;
+        let socket_path = dir.join(format!("control-{}.sock", process::id()));
+        if socket_path.exists() {
+            // left by a broot which had the same pid and didn't end properly
+            fs::remove_file(&socket_path)?;
+        }
+        let listener = UnixListener::bind(&socket_path)?;
+        let (sender, paths) = unbounded();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        warn!("failed to accept a nested broot: {:?}", e);
+                        continue;
+                    }
+                };
+                for line in BufReader::new(stream).lines() {
+                    match line {
+                        Ok(line) if !line.trim().is_empty() => {
+                            info!("path received from a nested broot: {:?}", &line);
+                            if sender.send(PathBuf::from(line.trim())).is_err() {
+                                return; // broot is quitting
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            warn!("failed to read from a nested broot: {:?}", e);
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+        env::set_var(ENV_VAR, &socket_path);
+        Ok(Self { socket_path, paths })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        env::remove_var(ENV_VAR);
+        let _ = fs::remove_file(&self.socket_path);
+    }
+}
+
+/// outside unix, there's no socket to listen on
+#[cfg(not(unix))]
+pub struct ControlSocket {
+    pub paths: Receiver<PathBuf>,
+}
+
+#[cfg(not(unix))]
+impl ControlSocket {
+    pub fn bind() -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "nested launches are only detected on unix systems",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod nested_launch_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_from_name() {
+        assert_eq!(NestedLaunch::from_name("send"), Some(NestedLaunch::Send));
+        assert_eq!(NestedLaunch::from_name("Start"), None);
+    }
+}
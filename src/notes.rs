@@ -0,0 +1,94 @@
+//! short notes attached to paths, like "delete later" or "belongs to
+//! project X", kept in broot's data directory so that they survive
+//! between sessions.
+//!
+//! Annotated entries are marked in the tree, the note of the selected
+//! one is written in the status line, and `:filter_by_note` only shows
+//! the entries whose note contains a text.
+
+use {
+    crate::path_store::{PathStore, StoredValue},
+    std::{io, path::Path},
+    toml::Value,
+};
+
+impl StoredValue for String {
+    fn from_toml(value: &Value) -> Option<Self> {
+        value.as_str().map(|note| note.to_string())
+    }
+    fn to_toml(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+lazy_static! {
+    static ref NOTES: PathStore<String> = PathStore::new("notes.toml");
+}
+
+/// return the note attached to the path, if any
+pub fn get(path: &Path) -> Option<String> {
+    NOTES.get(path)
+}
+
+pub fn has_note(path: &Path) -> bool {
+    NOTES.contains(path)
+}
+
+/// attach the note to the path, replacing the previous one
+pub fn set(path: &Path, note: &str) -> io::Result<()> {
+    NOTES.change(|notes| {
+        notes.insert(path.to_path_buf(), note.trim().to_string());
+    })
+}
+
+/// remove the note of the path. Return false if there was none.
+pub fn remove(path: &Path) -> io::Result<bool> {
+    NOTES.change(|notes| notes.remove(path).is_some())
+}
+
+/// tell whether the note contains the filter, case ignored. The
+/// `*` filter is matched by all notes.
+fn note_matches(note: &str, filter: &str) -> bool {
+    filter == "*" || note.to_lowercase().contains(&filter.to_lowercase())
+}
+
+/// tell whether the path has a note matched by the filter
+pub fn is_matched(path: &Path, filter: &str) -> bool {
+    NOTES.with_map(|notes| {
+        notes
+            .get(path)
+            .map_or(false, |note| note_matches(note, filter))
+    })
+}
+
+/// move the notes of a file renamed outside of broot, and of the
+/// files inside it, to its new path
+pub fn move_path(from: &Path, to: &Path) -> io::Result<bool> {
+    NOTES.move_path(from, to)
+}
+
+#[cfg(test)]
+mod notes_tests {
+
+    use {
+        super::*,
+        crate::path_store::{from_toml, to_toml, PathMap},
+        std::path::PathBuf,
+    };
+
+    #[test]
+    fn check_notes_file() {
+        let mut notes: PathMap<String> = PathMap::new();
+        notes.insert(PathBuf::from("/home/me/big.iso"), "delete later".to_string());
+        notes.insert(PathBuf::from("/home/me/\"quoted\""), "project X".to_string());
+        assert_eq!(from_toml(&to_toml(&notes)), notes);
+        assert!(from_toml::<String>("not toml at all").is_empty());
+    }
+
+    #[test]
+    fn check_note_matching() {
+        assert!(note_matches("Delete later", "delete"));
+        assert!(note_matches("belongs to project X", "*"));
+        assert!(!note_matches("belongs to project X", "later"));
+    }
+}
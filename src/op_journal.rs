@@ -0,0 +1,138 @@
+//! the journal of the file operations done by broot itself, so that
+//! the last one can be reversed with `:undo`.
+//!
+//! The operations done by external commands (eg `:mkdir` or `:rm`)
+//! aren't in the journal, as broot doesn't know their effects.
+//!
+//! As the queue, the journal is global so that it survives the
+//! navigation between states. It isn't persisted.
+
+use {
+    crate::{
+        bulk_rename,
+        errors::{FileOp, FileOpError, ProgramError},
+        trash::{self, TrashCommand},
+    },
+    std::{
+        fs, io,
+        path::{Path, PathBuf},
+        sync::Mutex,
+    },
+};
+
+/// the number of operations which can be undone
+const MAX_LEN: usize = 50;
+
+/// a file operation which can be reversed
+#[derive(Debug, Clone)]
+pub enum Operation {
+    /// renames, as (old path, new path), in the order they were done
+    Renames(Vec<(PathBuf, PathBuf)>),
+    /// a move of the path to the native trash (the items moved by
+    /// a trash command can't be restored by broot)
+    Trash(PathBuf),
+    /// a restoration from the trash to the path
+    Restore(PathBuf),
+    /// a copy to the path
+    Copy(PathBuf),
+}
+
+lazy_static! {
+    static ref JOURNAL: Mutex<Vec<Operation>> = Mutex::new(Vec::new());
+}
+
+impl Operation {
+    /// reverse the operation and return the report of what was
+    /// done. When some renames failed to be reversed, the operation
+    /// keeps only them, so that the undo can be retried.
+    fn undo(&mut self, trash_commands: &[TrashCommand]) -> Result<String, FileOpError> {
+        match self {
+            Self::Renames(renames) => {
+                let count = renames.len();
+                while let Some((old_path, new_path)) = renames.last() {
+                    // a file created at the old path since would be replaced
+                    if bulk_rename::is_taken(new_path, old_path) {
+                        let e = io::Error::new(
+                            io::ErrorKind::AlreadyExists,
+                            format!("{:?} already exists", old_path),
+                        );
+                        return Err(
+                            FileOpError::new(FileOp::Rename, new_path, e).with_dest(old_path)
+                        );
+                    }
+                    bulk_rename::rename_file(new_path, old_path).map_err(|e| {
+                        FileOpError::new(FileOp::Rename, new_path, e).with_dest(old_path)
+                    })?;
+                    renames.pop();
+                }
+                Ok(format!("{} renames undone", count))
+            }
+            Self::Trash(path) => {
+                trash::restore_path(path)
+                    .map_err(|e| FileOpError::new(FileOp::Restore, path, e))?;
+                Ok(format!("{} restored from the trash", path.to_string_lossy()))
+            }
+            Self::Restore(path) => {
+                trash::trash_with_fallback(path, trash_commands)
+                    .map_err(|e| FileOpError::new(FileOp::Trash, path, e))?;
+                Ok(format!("{} moved back to the trash", path.to_string_lossy()))
+            }
+            Self::Copy(path) => {
+                remove(path).map_err(|e| FileOpError::new(FileOp::Remove, path, e))?;
+                Ok(format!("{} removed", path.to_string_lossy()))
+            }
+        }
+    }
+}
+
+/// remove the file or the directory with its content
+fn remove(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+pub fn record(operation: Operation) {
+    let mut journal = JOURNAL.lock().unwrap();
+    journal.push(operation);
+    if journal.len() > MAX_LEN {
+        journal.remove(0);
+    }
+}
+
+/// reverse the last operation, returning the report to display or
+/// the error. An operation whose undo failed stays in the journal.
+pub fn undo_last(trash_commands: &[TrashCommand]) -> Result<String, ProgramError> {
+    let mut journal = JOURNAL.lock().unwrap();
+    let operation = journal.last_mut().ok_or(ProgramError::NothingToUndo)?;
+    let report = operation.undo(trash_commands)?;
+    journal.pop();
+    Ok(report)
+}
+
+#[cfg(test)]
+mod op_journal_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_rename_undo_conflict() {
+        let dir = std::env::temp_dir().join(format!("broot-undo-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (old_path, new_path) = (dir.join("a"), dir.join("b"));
+        fs::write(&old_path, "renamed").unwrap();
+        fs::rename(&old_path, &new_path).unwrap();
+        fs::write(&old_path, "created since").unwrap();
+        let mut operation = Operation::Renames(vec![(old_path.clone(), new_path.clone())]);
+        let err = operation.undo(&[]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(&old_path).unwrap(), "created since");
+        assert!(matches!(&operation, Operation::Renames(renames) if renames.len() == 1));
+        fs::remove_file(&old_path).unwrap();
+        operation.undo(&[]).unwrap();
+        assert_eq!(fs::read_to_string(&old_path).unwrap(), "renamed");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
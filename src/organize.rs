@@ -0,0 +1,139 @@
+//! moving the files of a directory into subfolders named after their
+//! extension or their date, as done by `:organize`.
+//!
+//! The moves are planned as renames (see the bulk_rename module), so
+//! that they're previewed with their conflicts before being done.
+
+use {
+    crate::bulk_rename::{Rename, RenameStatus},
+    chrono::{DateTime, Local},
+    std::{
+        collections::HashSet,
+        fs, io,
+        path::{Component, Path},
+    },
+};
+
+/// a rule of the `[[organize-rules]]` section of the configuration:
+/// the files whose name matches the glob go to the folder, whose
+/// name may contain `{ext}`, `{year}`, `{month}` and `{day}`
+#[derive(Debug, Clone)]
+pub struct OrganizeRule {
+    pattern: glob::Pattern,
+    folder: String,
+}
+
+impl OrganizeRule {
+    pub fn new(glob: &str, folder: &str) -> Result<OrganizeRule, glob::PatternError> {
+        Ok(OrganizeRule {
+            pattern: glob::Pattern::new(glob)?,
+            folder: folder.to_string(),
+        })
+    }
+    /// tell whether the rule applies to the file name, ignoring
+    /// the case as extensions are often uppercase
+    pub fn matches(&self, file_name: &str) -> bool {
+        let options = glob::MatchOptions {
+            case_sensitive: false,
+            ..glob::MatchOptions::new()
+        };
+        self.pattern.matches_with(file_name, options)
+    }
+}
+
+/// the rule used when none is configured: one folder per extension
+pub fn default_rules() -> Vec<OrganizeRule> {
+    vec![OrganizeRule::new("*", "{ext}").unwrap()]
+}
+
+/// build the name of the folder of a file from the template of the
+/// rule. Return None when the template needs an extension and the
+/// file has none.
+fn folder_name(template: &str, file_name: &str, date: &DateTime<Local>) -> Option<String> {
+    let mut folder = template.to_string();
+    if folder.contains("{ext}") {
+        let ext = Path::new(file_name).extension()?.to_string_lossy().to_lowercase();
+        folder = folder.replace("{ext}", &ext);
+    }
+    Some(
+        folder
+            .replace("{year}", &date.format("%Y").to_string())
+            .replace("{month}", &date.format("%m").to_string())
+            .replace("{day}", &date.format("%d").to_string()),
+    )
+}
+
+/// tell whether the folder stays in the organized directory
+fn is_valid_folder(folder: &str) -> bool {
+    !folder.is_empty()
+        && Path::new(folder)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+}
+
+/// plan the moves of the non hidden files of the directory to the
+/// folders of the first rules matching their names. The files which
+/// no rule matches are left in place.
+pub fn plan(dir: &Path, rules: &[OrganizeRule]) -> io::Result<Vec<Rename>> {
+    let mut files: Vec<(String, DateTime<Local>)> = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let metadata = entry.metadata()?;
+        if name.starts_with('.') || !metadata.is_file() {
+            continue;
+        }
+        let date = metadata.modified().map_or_else(|_| Local::now(), DateTime::from);
+        files.push((name, date));
+    }
+    files.sort();
+    let mut renames = Vec::new();
+    let mut new_paths = HashSet::new();
+    for (name, date) in files {
+        let folder = rules
+            .iter()
+            .find(|rule| rule.matches(&name))
+            .and_then(|rule| folder_name(&rule.folder, &name, &date));
+        let folder = match folder {
+            Some(folder) => folder,
+            None => continue,
+        };
+        let old_path = dir.join(&name);
+        let new_path = dir.join(&folder).join(&name);
+        let status = if !is_valid_folder(&folder) {
+            RenameStatus::Conflict("invalid folder")
+        } else if !new_paths.insert(new_path.clone()) {
+            RenameStatus::Conflict("another file goes to the same path")
+        } else if new_path.exists() {
+            RenameStatus::Conflict("the folder already has a file with this name")
+        } else {
+            RenameStatus::Planned
+        };
+        renames.push(Rename {
+            old_path,
+            new_path,
+            status,
+        });
+    }
+    Ok(renames)
+}
+
+#[cfg(test)]
+mod organize_tests {
+
+    use {super::*, chrono::TimeZone};
+
+    #[test]
+    fn check_folder_names() {
+        let date = Local.ymd(2020, 3, 7).and_hms(10, 0, 0);
+        let folder = |template, name| folder_name(template, name, &date);
+        assert_eq!(folder("{ext}", "Report.PDF"), Some("pdf".to_string()));
+        assert_eq!(folder("{ext}", "Makefile"), None);
+        assert_eq!(folder("{year}-{month}", "Makefile"), Some("2020-03".to_string()));
+        assert_eq!(folder("photos/{year}/{day}", "a.jpg"), Some("photos/2020/07".to_string()));
+        assert!(is_valid_folder("photos/2020"));
+        assert!(!is_valid_folder("../elsewhere"));
+        assert!(!is_valid_folder("/tmp"));
+        assert!(!is_valid_folder(""));
+    }
+}
@@ -0,0 +1,153 @@
+//! the outcome channel, opened with `--listen <socket>`, through
+//! which broot tells the programs embedding it (editors, scripts)
+//! what happens: selection changes, executed verbs, paths sent
+//! with `:send_path`, and the final selection when quitting.
+//!
+//! broot listens on a unix socket and writes the events to all
+//! the connected clients, one JSON object per line, for example
+//!
+//! ```json
+//! {"event": "selection", "path": "/home/dys/dev/broot/src"}
+//! {"event": "verb", "verb": "rm", "path": "/home/dys/dev/broot/target"}
+//! {"event": "quit", "path": "/home/dys/dev/broot"}
+//! ```
+
+use {
+    crate::tree_export::json_string,
+    std::{
+        io,
+        path::{Path, PathBuf},
+    },
+};
+
+#[cfg(unix)]
+use std::{
+    fs,
+    io::Write,
+    os::unix::net::{UnixListener, UnixStream},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+/// how long writing to a client may block before it's forgotten,
+/// so that a client which doesn't read doesn't freeze broot
+#[cfg(unix)]
+const WRITE_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// something worth telling to the clients
+#[derive(Debug, Clone, Copy)]
+pub enum Outcome<'a> {
+    Selection(&'a Path),
+    Verb { name: &'a str, path: &'a Path },
+    Path(&'a Path), // a path explicitly sent by the user
+    Quit(Option<&'a Path>), // the selection when broot quits, if any
+}
+
+impl Outcome<'_> {
+    fn to_json(self) -> String {
+        let path = |path: &Path| json_string(&path.to_string_lossy());
+        match self {
+            Outcome::Selection(p) => format!(r#"{{"event": "selection", "path": {}}}"#, path(p)),
+            Outcome::Verb { name, path: p } => format!(
+                r#"{{"event": "verb", "verb": {}, "path": {}}}"#,
+                json_string(name),
+                path(p),
+            ),
+            Outcome::Path(p) => format!(r#"{{"event": "path", "path": {}}}"#, path(p)),
+            Outcome::Quit(p) => format!(
+                r#"{{"event": "quit", "path": {}}}"#,
+                p.map_or_else(|| "null".to_string(), path),
+            ),
+        }
+    }
+}
+
+#[cfg(unix)]
+pub struct OutcomeChannel {
+    socket_path: PathBuf,
+    clients: Arc<Mutex<Vec<UnixStream>>>,
+}
+
+#[cfg(unix)]
+impl OutcomeChannel {
+    /// create the socket and accept, in a background thread,
+    /// the clients connecting to it
+    pub fn bind(socket_path: PathBuf) -> io::Result<Self> {
+        if socket_path.exists() {
+            // a socket file may be left by a broot which didn't end properly
+            if UnixStream::connect(&socket_path).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    format!("{:?} is already used", socket_path),
+                ));
+            }
+            fs::remove_file(&socket_path)?;
+        }
+        let listener = UnixListener::bind(&socket_path)?;
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let accepted_clients = Arc::clone(&clients);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        if let Err(e) = stream.set_write_timeout(Some(WRITE_TIMEOUT)) {
+                            warn!("failed to set the timeout of a client: {:?}", e);
+                        }
+                        accepted_clients.lock().unwrap().push(stream);
+                    }
+                    Err(e) => warn!("failed to accept a client: {:?}", e),
+                }
+            }
+        });
+        Ok(Self {
+            socket_path,
+            clients,
+        })
+    }
+    /// write the outcome to all clients, forgetting the ones
+    /// which disconnected
+    pub fn send(&self, outcome: Outcome<'_>) {
+        let line = format!("{}\n", outcome.to_json());
+        debug!("sending outcome {}", line.trim_end());
+        let mut clients = self.clients.lock().unwrap();
+        clients.retain(|mut client| client.write_all(line.as_bytes()).is_ok());
+    }
+}
+
+#[cfg(unix)]
+impl Drop for OutcomeChannel {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.socket_path);
+    }
+}
+
+/// outside unix, there's no socket to listen on
+#[cfg(not(unix))]
+pub struct OutcomeChannel {}
+
+#[cfg(not(unix))]
+impl OutcomeChannel {
+    pub fn bind(_socket_path: PathBuf) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "--listen is only supported on unix systems",
+        ))
+    }
+    pub fn send(&self, _outcome: Outcome<'_>) {}
+}
+
+#[cfg(test)]
+mod outcome_channel_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_to_json() {
+        assert_eq!(
+            Outcome::Verb { name: "rm", path: Path::new("/tmp/a \"b\"") }.to_json(),
+            r#"{"event": "verb", "verb": "rm", "path": "/tmp/a \"b\""}"#,
+        );
+        assert_eq!(Outcome::Quit(None).to_json(), r#"{"event": "quit", "path": null}"#);
+    }
+}
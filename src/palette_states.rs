@@ -0,0 +1,432 @@
+//! the palette is a searchable list of everything broot can do from
+//! the tree: the verbs, builtin or configured, and the roots which
+//! can be browsed, bookmarked or recent.
+
+use {
+    crate::{
+        app_context::AppContext,
+        app_state::{AppState, AppStateCmdResult},
+        browser_states::BrowserState,
+        commands::{Action, Command},
+        errors::ProgramError,
+        help_content::{ScreenHelp, ScreenKey},
+        help_states::HelpState,
+        patterns::Pattern,
+        recent_roots,
+        screens::Screen,
+        status::Status,
+        task_sync::Dam,
+        verb_store::PrefixSearchResult,
+    },
+    crossterm::{
+        terminal::{Clear, ClearType},
+        QueueableCommand,
+    },
+    directories::UserDirs,
+    std::{io::Write, path::PathBuf},
+};
+
+/// the width of the column telling the kind of the entries
+const KIND_WIDTH: usize = 10;
+
+/// the help of the palette
+pub static HELP: ScreenHelp = ScreenHelp {
+    name: "palette",
+    description: "The palette lists the verbs, builtin or configured, the bookmarks and the \
+        recent roots. Type to search them, the names first then the descriptions.",
+    keys: &[
+        ScreenKey::new("esc", "get back to the tree, or clear the search"),
+        ScreenKey::new("↑ ↓", "move the selection"),
+        ScreenKey::new("enter", "execute the selected verb (or put it in the input when it needs \
+            arguments), or browse the selected root"),
+        ScreenKey::new("?", "display this help"),
+    ],
+    builtins: Some(&[":back", ":help", ":palette", ":quit"]),
+    external_verbs: false,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PaletteKind {
+    Builtin,
+    Command, // a verb launching an external program
+    Bookmark,
+    RecentRoot,
+}
+
+impl PaletteKind {
+    fn name(self) -> &'static str {
+        match self {
+            PaletteKind::Builtin => "builtin",
+            PaletteKind::Command => "command",
+            PaletteKind::Bookmark => "bookmark",
+            PaletteKind::RecentRoot => "recent",
+        }
+    }
+}
+
+/// what's done when an entry is chosen
+enum PaletteTarget {
+    Verb(usize), // index in the verb store
+    Root(PathBuf),
+}
+
+struct PaletteEntry {
+    kind: PaletteKind,
+    label: String,    // the name of the verb or the root, searched first
+    shortcut: String, // searched with the label, may be empty
+    key: String,      // the description of the key triggering the verb, may be empty
+    detail: String,   // the description of the verb, searched when the label doesn't match
+    target: PaletteTarget,
+}
+
+/// an application state where all the actions may be searched
+/// and executed
+pub struct PaletteState {
+    entries: Vec<PaletteEntry>,
+    pattern: Pattern,
+    displayed: Vec<usize>, // indexes of the entries matching the pattern
+    selection: usize,      // index in displayed
+    scroll: usize,
+}
+
+impl PaletteState {
+    pub fn new(con: &AppContext) -> PaletteState {
+        let mut entries: Vec<PaletteEntry> = con
+            .verb_store
+            .verbs
+            .iter()
+            .enumerate()
+            .filter(|(_, verb)| !verb.invocation.is_empty())
+            .map(|(idx, verb)| PaletteEntry {
+                kind: if verb.execution.starts_with(':') {
+                    PaletteKind::Builtin
+                } else {
+                    PaletteKind::Command
+                },
+                label: verb.invocation.to_string_for_name(&verb.invocation.name),
+                shortcut: verb.shortcut.clone().unwrap_or_default(),
+                key: verb.key_desc.clone(),
+                detail: verb.description.clone().unwrap_or_else(|| verb.execution.clone()),
+                target: PaletteTarget::Verb(idx),
+            })
+            .collect();
+        let user_dirs = UserDirs::new();
+        let home = user_dirs.as_ref().map(|ud| ud.home_dir());
+        let bookmarks = con.launch_args.bookmarks.iter().cloned();
+        let recent_roots = recent_roots::read()
+            .into_iter()
+            .filter(|path| !con.launch_args.bookmarks.contains(path));
+        let roots = bookmarks
+            .map(|path| (PaletteKind::Bookmark, path))
+            .chain(recent_roots.map(|path| (PaletteKind::RecentRoot, path)));
+        for (kind, path) in roots {
+            let label = match home.and_then(|home| path.strip_prefix(home).ok()) {
+                Some(sub_path) => format!("~/{}", sub_path.to_string_lossy()),
+                None => path.to_string_lossy().to_string(),
+            };
+            entries.push(PaletteEntry {
+                kind,
+                label,
+                shortcut: String::new(),
+                key: String::new(),
+                detail: String::new(),
+                target: PaletteTarget::Root(path),
+            });
+        }
+        let displayed = (0..entries.len()).collect();
+        PaletteState {
+            entries,
+            pattern: Pattern::None,
+            displayed,
+            selection: 0,
+            scroll: 0,
+        }
+    }
+
+    /// return the number of lines available for the entries
+    fn page_height(screen: &Screen) -> usize {
+        (screen.height as usize).max(3) - 3
+    }
+
+    /// keep only the entries matching the pattern, the best ones first.
+    /// The entries whose label or shortcut match come before the ones
+    /// found only by their description
+    fn set_pattern(&mut self, pattern: Pattern) {
+        let mut scored: Vec<(i32, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, e)| {
+                if !pattern.is_some() {
+                    return Some((0, idx));
+                }
+                [&e.label, &e.shortcut]
+                    .iter()
+                    .filter(|s| !s.is_empty())
+                    .filter_map(|s| pattern.score_of(s))
+                    .max()
+                    .or_else(|| pattern.score_of(&e.detail).map(|score| score - 100_000))
+                    .map(|score| (score, idx))
+            })
+            .collect();
+        // the sort is stable so the verbs stay before the roots on equal scores
+        scored.sort_by(|a, b| b.0.cmp(&a.0));
+        self.displayed = scored.into_iter().map(|(_, idx)| idx).collect();
+        self.pattern = pattern;
+        self.selection = 0;
+        self.scroll = 0;
+    }
+
+    fn move_selection(&mut self, dy: i32, page_height: usize) {
+        let len = self.displayed.len() as i32;
+        if len == 0 {
+            return;
+        }
+        let selection = (self.selection as i32 + dy).max(0).min(len - 1) as usize;
+        self.selection = selection;
+        if selection < self.scroll {
+            self.scroll = selection;
+        } else if selection >= self.scroll + page_height {
+            self.scroll = selection + 1 - page_height;
+        }
+    }
+
+    /// execute the selected verb on the previous state, or put it in the
+    /// input when it needs arguments, or browse the selected root
+    fn execute_selection(
+        &self,
+        cmd: &mut Command,
+        screen: &Screen,
+        con: &AppContext,
+    ) -> AppStateCmdResult {
+        let entry = match self.displayed.get(self.selection) {
+            Some(&idx) => &self.entries[idx],
+            None => {
+                return AppStateCmdResult::DisplayError("nothing selected".to_string());
+            }
+        };
+        match &entry.target {
+            PaletteTarget::Verb(idx) => {
+                let verb = &con.verb_store.verbs[*idx];
+                *cmd = if verb.invocation.args.is_some() {
+                    Command::from_raw(format!(":{} ", verb.invocation.name), false)
+                } else {
+                    Command::from_raw(format!(":{}", verb.invocation.name), true)
+                };
+                AppStateCmdResult::PopStateAndReapply
+            }
+            PaletteTarget::Root(path) => {
+                if let Err(e) = recent_roots::remember(path) {
+                    warn!("failed to remember {:?} as recent root: {:?}", path, e);
+                }
+                match BrowserState::new(
+                    path.clone(),
+                    con.launch_args.tree_options.clone(),
+                    screen,
+                    con,
+                    &Dam::unlimited(),
+                ) {
+                    Ok(Some(state)) => AppStateCmdResult::ReplaceState(
+                        Box::new(state),
+                        Command::new(),
+                    ),
+                    Ok(None) => AppStateCmdResult::Keep,
+                    Err(e) => AppStateCmdResult::DisplayError(e.to_string()),
+                }
+            }
+        }
+    }
+}
+
+impl AppState for PaletteState {
+    fn help(&self) -> &'static ScreenHelp {
+        &HELP
+    }
+
+    fn has_pending_task(&self) -> bool {
+        false
+    }
+
+    fn can_execute(&self, _verb_index: usize, _con: &AppContext) -> bool {
+        true
+    }
+
+    fn apply(
+        &mut self,
+        cmd: &mut Command,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<AppStateCmdResult, ProgramError> {
+        let page_height = PaletteState::page_height(screen);
+        let verb_execution = match &cmd.action {
+            Action::VerbIndex(index) => Some(con.verb_store.verbs[*index].execution.as_str()),
+            Action::VerbInvocate(invocation) => match con.verb_store.search(&invocation.name) {
+                PrefixSearchResult::Match(verb) => Some(verb.execution.as_str()),
+                _ => {
+                    return Ok(AppStateCmdResult::verb_not_found(&invocation.name));
+                }
+            },
+            _ => None,
+        };
+        Ok(match (&cmd.action, verb_execution) {
+            (Action::Back, _) if self.pattern.is_some() => {
+                self.set_pattern(Pattern::None);
+                cmd.raw.clear();
+                AppStateCmdResult::Keep
+            }
+            (Action::Back, _) | (_, Some(":back")) | (_, Some(":palette")) => {
+                AppStateCmdResult::PopState
+            }
+            (Action::Help, _) | (_, Some(":help")) => AppStateCmdResult::NewState(
+                Box::new(HelpState::new(screen, con, &HELP)),
+                Command::new(),
+            ),
+            (_, Some(":quit")) => AppStateCmdResult::Quit,
+            // the other verbs apply to the tree
+            (_, Some(_)) => AppStateCmdResult::PopStateAndReapply,
+            (Action::Click(_, y), _) => {
+                let y = *y as usize;
+                if y > 0 && y <= page_height && self.scroll + y - 1 < self.displayed.len() {
+                    self.selection = self.scroll + y - 1;
+                }
+                AppStateCmdResult::Keep
+            }
+            (Action::DoubleClick(_, y), _) => {
+                if *y > 0 && self.selection + 1 == self.scroll + *y as usize {
+                    self.execute_selection(cmd, screen, con)
+                } else {
+                    AppStateCmdResult::Keep
+                }
+            }
+            (Action::FuzzyPatternEdit(pat), _) => {
+                self.set_pattern(if pat.is_empty() {
+                    Pattern::None
+                } else {
+                    Pattern::fuzzy(pat)
+                });
+                AppStateCmdResult::Keep
+            }
+            (Action::MoveSelection(dy), _) | (Action::Scroll(dy), _) => {
+                self.move_selection(*dy, page_height);
+                AppStateCmdResult::Keep
+            }
+            (Action::OpenSelection, _) => self.execute_selection(cmd, screen, con),
+            (Action::Resize(w, h), _) => {
+                screen.set_terminal_size(*w, *h, con);
+                AppStateCmdResult::Keep
+            }
+            _ => AppStateCmdResult::Keep,
+        })
+    }
+
+    fn refresh(&mut self, _screen: &Screen, _con: &AppContext) -> Command {
+        Command::new()
+    }
+
+    fn do_pending_task(&mut self, _screen: &mut Screen, _con: &AppContext, _dam: &mut Dam) {
+        unreachable!();
+    }
+
+    fn display(
+        &mut self,
+        mut w: &mut dyn Write,
+        screen: &Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        let page_height = PaletteState::page_height(screen);
+        let width = screen.width as usize;
+        screen.goto_clear(&mut w, 0, 0)?;
+        screen.skin.default.queue_bg(&mut w)?;
+        screen.skin.help_headers.queue_str(&mut w, "Verbs, bookmarks and recent roots")?;
+        screen.clear_line(&mut w)?;
+        for y in 0..page_height {
+            screen.goto(&mut w, 0, y as u16 + 1)?;
+            let idx = self.scroll + y;
+            let selected = idx == self.selection;
+            if let Some(entry) = self.displayed.get(idx).map(|&i| &self.entries[i]) {
+                let label_skin = match entry.kind {
+                    PaletteKind::Builtin | PaletteKind::Command => &screen.skin.exe,
+                    PaletteKind::Bookmark | PaletteKind::RecentRoot => &screen.skin.directory,
+                };
+                let char_match = if selected {
+                    &screen.skin.char_match_selected
+                } else {
+                    &screen.skin.char_match
+                };
+                cond_bg!(kind_style, screen, selected, screen.skin.flag_label);
+                cond_bg!(label_style, screen, selected, label_skin);
+                cond_bg!(char_match_style, screen, selected, char_match);
+                cond_bg!(key_style, screen, selected, screen.skin.flag_value);
+                cond_bg!(detail_style, screen, selected, screen.skin.tree);
+                kind_style.queue_str(
+                    &mut w,
+                    &format!("{:<width$}", entry.kind.name(), width = KIND_WIDTH),
+                )?;
+                self.pattern
+                    .style(&entry.label, &label_style, &char_match_style)
+                    .write_on(&mut w)?;
+                let mut x = KIND_WIDTH + entry.label.chars().count();
+                if !entry.shortcut.is_empty() {
+                    let shortcut = format!(" ({})", entry.shortcut);
+                    x += shortcut.chars().count();
+                    detail_style.queue_str(&mut w, &shortcut)?;
+                }
+                if !entry.key.is_empty() {
+                    let key = format!("  {}", entry.key);
+                    x += key.chars().count();
+                    key_style.queue_str(&mut w, &key)?;
+                }
+                if !entry.detail.is_empty() && x + 3 < width {
+                    let detail: String = entry.detail.chars().take(width - x - 3).collect();
+                    detail_style.queue_str(&mut w, &format!("  {}", detail))?;
+                }
+            } else if idx == 0 && self.displayed.is_empty() {
+                screen.skin.tree.queue_str(&mut w, "Nothing matches the search")?;
+            }
+            if selected && idx < self.displayed.len() {
+                screen.skin.selected_line.queue_bg(&mut w)?;
+            } else {
+                screen.skin.default.queue_bg(&mut w)?;
+            }
+            w.queue(Clear(ClearType::UntilNewLine))?;
+        }
+        Ok(())
+    }
+
+    fn write_status(
+        &self,
+        mut w: &mut dyn Write,
+        cmd: &Command,
+        screen: &Screen,
+        con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        match &cmd.action {
+            Action::VerbEdit(invocation) if !invocation.name.is_empty() => {
+                match con.verb_store.search(&invocation.name) {
+                    PrefixSearchResult::Match(_) => Status::from_message(mad_inline!(
+                        "Hit *enter* to execute the verb on the tree"
+                    ))
+                    .display(&mut w, screen),
+                    _ => Status::from_error(mad_inline!("No matching verb"))
+                        .display(&mut w, screen),
+                }
+            }
+            _ => Status::from_message(mad_inline!(
+                "Type to search, hit *enter* to execute the selection or *esc* to get back"
+            ))
+            .display(&mut w, screen),
+        }
+    }
+
+    /// there's no meaningful flags here
+    fn write_flags(
+        &self,
+        mut w: &mut dyn Write,
+        screen: &mut Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        screen.skin.default.queue_bg(&mut w)?;
+        w.queue(Clear(ClearType::UntilNewLine))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+//! completion of the paths typed as verb arguments
+
+use {
+    crate::verbs,
+    std::{fs, path::Path},
+};
+
+/// return the sorted possible completions of a path being typed,
+/// relative to the given directory (unless it's absolute or starts with `~`).
+///
+/// The returned strings are the whole token completed, with a trailing
+/// slash for directories so that the completion may go on deeper.
+pub fn complete_path(directory: &Path, token: &str) -> Vec<String> {
+    let (dir_part, stem) = match token.rfind('/') {
+        Some(idx) => (&token[..=idx], &token[idx + 1..]),
+        None => ("", token),
+    };
+    let dir = if dir_part.is_empty() {
+        directory.to_path_buf()
+    } else {
+        verbs::path_from_directory(directory, dir_part)
+    };
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(_) => {
+            return Vec::new();
+        }
+    };
+    let mut completions: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_str()?.to_string();
+            // hidden files are only proposed when the user typed the dot
+            if !name.starts_with(stem) || (stem.is_empty() && name.starts_with('.')) {
+                return None;
+            }
+            let slash = if e.path().is_dir() { "/" } else { "" };
+            Some(format!("{}{}{}", dir_part, name, slash))
+        })
+        .collect();
+    completions.sort();
+    completions
+}
+
+/// return the longest string all the candidates start with
+pub fn common_prefix(candidates: &[String]) -> String {
+    let mut prefix = match candidates.first() {
+        Some(first) => first.clone(),
+        None => {
+            return String::new();
+        }
+    };
+    for candidate in &candidates[1..] {
+        let len = prefix
+            .char_indices()
+            .zip(candidate.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or_else(|| prefix.len().min(candidate.len()), |((idx, _), _)| idx);
+        prefix.truncate(len);
+    }
+    prefix
+}
+
+#[cfg(test)]
+mod path_completion_tests {
+
+    use super::common_prefix;
+
+    fn check(candidates: &[&str], prefix: &str) {
+        let candidates: Vec<String> = candidates.iter().map(|s| s.to_string()).collect();
+        assert_eq!(common_prefix(&candidates), prefix.to_string());
+    }
+
+    #[test]
+    fn test_common_prefix() {
+        check(&[], "");
+        check(&["../src/"], "../src/");
+        check(&["../src/", "../static/"], "../s");
+        check(&["target", "tar"], "tar");
+        check(&["été/", "éte"], "ét");
+        check(&["a", "b"], "");
+    }
+}
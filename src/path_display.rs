@@ -0,0 +1,163 @@
+//! the way paths are written in the root line and the status: the
+//! home directory may be abbreviated as `~` and, when the path doesn't
+//! fit, its intermediate directories reduced to their first letter
+//! (eg `/u/s/doc/broot`).
+
+use {
+    directories::UserDirs,
+    std::{
+        path::{Path, PathBuf},
+        sync::RwLock,
+    },
+};
+
+/// how paths are written, as set by the `abbreviate_home` and
+/// `shorten_paths` configuration keys
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PathDisplay {
+    pub abbreviate_home: bool,
+    pub shorten: bool, // whether intermediate directories are shortened when needed
+}
+
+lazy_static! {
+    static ref DISPLAY: RwLock<PathDisplay> = RwLock::new(PathDisplay::default());
+    static ref HOME: Option<PathBuf> = UserDirs::new().map(|ud| ud.home_dir().to_path_buf());
+}
+
+/// set how paths are written by the application
+pub fn init(display: PathDisplay) {
+    *DISPLAY.write().unwrap() = display;
+}
+
+/// the text written for an ancestor of a path, that is its
+/// last component with the separator before it
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathPart<'p> {
+    pub ancestor: &'p Path,
+    pub text: String,
+    shortenable: bool,
+}
+
+impl PathPart<'_> {
+    /// reduce the part to its first letter, or to the two first
+    /// ones for a hidden directory
+    fn shorten(&mut self) {
+        let name_start = self.text.find(|c| c != '/' && c != '\\').unwrap_or(self.text.len());
+        let (separator, name) = self.text.split_at(name_start);
+        let kept = if name.starts_with('.') { 2 } else { 1 };
+        let short_name: String = name.chars().take(kept).collect();
+        self.text = format!("{}{}", separator, short_name);
+        self.shortenable = false;
+    }
+}
+
+fn parts_with<'p>(
+    path: &'p Path,
+    home: Option<&Path>,
+    display: PathDisplay,
+    max_width: usize,
+) -> Vec<PathPart<'p>> {
+    let home = home.filter(|home| {
+        display.abbreviate_home && home.parent().is_some() && path.starts_with(home)
+    });
+    let ancestors: Vec<&Path> = path.ancestors().collect();
+    let mut parts: Vec<PathPart<'_>> = Vec::new();
+    let mut written_len = 0;
+    for ancestor in ancestors.into_iter().rev() {
+        let ancestor_str = ancestor.to_string_lossy();
+        let text = ancestor_str.get(written_len..).unwrap_or("").to_string();
+        written_len = ancestor_str.len();
+        match home {
+            Some(home) if ancestor == home => {
+                parts.push(PathPart {
+                    ancestor,
+                    text: "~".to_string(),
+                    shortenable: false,
+                });
+            }
+            Some(home) if home.starts_with(ancestor) => {} // written as part of the ~
+            _ => {
+                let shortenable = ancestor.file_name().is_some() && ancestor != path;
+                parts.push(PathPart {
+                    ancestor,
+                    text,
+                    shortenable,
+                });
+            }
+        }
+    }
+    if display.shorten {
+        let width = |parts: &[PathPart<'_>]| -> usize {
+            parts.iter().map(|part| part.text.chars().count()).sum()
+        };
+        let mut idx = 0;
+        while idx < parts.len() && width(&parts) > max_width {
+            if parts[idx].shortenable {
+                parts[idx].shorten();
+            }
+            idx += 1;
+        }
+    }
+    parts
+}
+
+/// return the parts to write, in order, to display the path
+/// in at most `max_width` chars when possible
+pub fn parts(path: &Path, max_width: usize) -> Vec<PathPart<'_>> {
+    parts_with(path, HOME.as_deref(), *DISPLAY.read().unwrap(), max_width)
+}
+
+/// return the parts of the path, neither abbreviated nor shortened
+pub fn full_parts(path: &Path) -> Vec<PathPart<'_>> {
+    parts_with(path, None, PathDisplay::default(), 0)
+}
+
+/// return the path as it should be displayed, in at most
+/// `max_width` chars when possible
+pub fn abbreviated(path: &Path, max_width: usize) -> String {
+    parts(path, max_width).into_iter().map(|part| part.text).collect()
+}
+
+/// replace the home directory with `~` in the paths of a text
+/// which is only displayed, for example a command
+pub fn abbreviate_homes(text: &str) -> String {
+    match HOME.as_deref() {
+        Some(home) if DISPLAY.read().unwrap().abbreviate_home => {
+            let home = home.to_string_lossy();
+            if home.len() > 1 {
+                text.replace(&format!("{}/", home), "~/")
+            } else {
+                text.to_string()
+            }
+        }
+        _ => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod path_display_tests {
+
+    use super::*;
+
+    fn display(path: &str, abbreviate_home: bool, shorten: bool, max_width: usize) -> String {
+        let display = PathDisplay { abbreviate_home, shorten };
+        parts_with(Path::new(path), Some(Path::new("/home/me")), display, max_width)
+            .into_iter()
+            .map(|part| part.text)
+            .collect()
+    }
+
+    #[test]
+    fn check_path_display() {
+        let path = "/home/me/dev/broot/src";
+        assert_eq!(display(path, false, false, 10), path);
+        assert_eq!(display(path, true, false, 10), "~/dev/broot/src");
+        assert_eq!(display(path, true, true, 100), "~/dev/broot/src");
+        assert_eq!(display(path, true, true, 13), "~/d/broot/src");
+        assert_eq!(display(path, true, true, 5), "~/d/b/src");
+        assert_eq!(display(path, false, true, 20), "/h/me/dev/broot/src");
+        assert_eq!(display("/home/me", true, true, 0), "~");
+        assert_eq!(display("/home/meh/.config/x", true, true, 12), "/h/m/.c/x");
+        assert_eq!(display("/", true, true, 0), "/");
+    }
+}
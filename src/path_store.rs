@@ -0,0 +1,157 @@
+//! values attached to paths, like the notes or the tags, kept in a
+//! TOML file of broot's data directory so that they survive between
+//! sessions.
+//!
+//! The file is read at first use. It's read again before each change,
+//! so that the changes made by other broot instances aren't lost, and
+//! written only when the change did something.
+
+use {
+    crate::conf,
+    std::{
+        collections::BTreeMap,
+        fs, io,
+        path::{Path, PathBuf},
+        sync::RwLock,
+    },
+    toml::Value,
+};
+
+pub type PathMap<V> = BTreeMap<PathBuf, V>;
+
+/// a value which can be stored in a path store
+pub trait StoredValue: Clone + PartialEq {
+    /// read the value, returning None when it's invalid or empty
+    fn from_toml(value: &Value) -> Option<Self>;
+    fn to_toml(&self) -> Value;
+}
+
+pub struct PathStore<V> {
+    file_name: &'static str,
+    map: RwLock<Option<PathMap<V>>>, // None until first read
+}
+
+/// read the map from the content of a store file, ignoring
+/// what can't be understood
+pub fn from_toml<V: StoredValue>(content: &str) -> PathMap<V> {
+    match content.parse::<Value>() {
+        Ok(Value::Table(tbl)) => tbl
+            .into_iter()
+            .filter_map(|(path, value)| V::from_toml(&value).map(|v| (PathBuf::from(path), v)))
+            .collect(),
+        _ => {
+            warn!("invalid path store file");
+            PathMap::new()
+        }
+    }
+}
+
+pub fn to_toml<V: StoredValue>(map: &PathMap<V>) -> String {
+    Value::Table(
+        map.iter()
+            .map(|(path, value)| (path.to_string_lossy().to_string(), value.to_toml()))
+            .collect(),
+    )
+    .to_string()
+}
+
+/// move the entries of a path, and of the paths inside it, under
+/// its new path. Return whether there was any.
+pub fn move_entries<V>(map: &mut PathMap<V>, from: &Path, to: &Path) -> bool {
+    let moved: Vec<PathBuf> = map.keys().filter(|path| path.starts_with(from)).cloned().collect();
+    for path in &moved {
+        if let (Some(value), Ok(rest)) = (map.remove(path), path.strip_prefix(from)) {
+            let new_path = if rest.as_os_str().is_empty() {
+                to.to_path_buf()
+            } else {
+                to.join(rest)
+            };
+            map.insert(new_path, value);
+        }
+    }
+    !moved.is_empty()
+}
+
+impl<V: StoredValue> PathStore<V> {
+    pub fn new(file_name: &'static str) -> Self {
+        Self {
+            file_name,
+            map: RwLock::new(None),
+        }
+    }
+
+    fn file_path(&self) -> PathBuf {
+        conf::app_dirs().data_dir().join(self.file_name)
+    }
+
+    fn read_file(&self) -> PathMap<V> {
+        fs::read_to_string(self.file_path())
+            .map(|content| from_toml(&content))
+            .unwrap_or_default()
+    }
+
+    /// call the function with the map, reading it first if needed
+    pub fn with_map<R, F: FnOnce(&PathMap<V>) -> R>(&self, f: F) -> R {
+        if let Some(map) = self.map.read().unwrap().as_ref() {
+            return f(map);
+        }
+        let mut guard = self.map.write().unwrap();
+        f(guard.get_or_insert_with(|| self.read_file()))
+    }
+
+    /// apply a change to the map of the file, which is read again
+    /// before, then written if it changed
+    pub fn change<R, F: FnOnce(&mut PathMap<V>) -> R>(&self, f: F) -> io::Result<R> {
+        let mut guard = self.map.write().unwrap();
+        let mut map = self.read_file();
+        let before = map.clone();
+        let result = f(&mut map);
+        if map != before {
+            let file_path = self.file_path();
+            if let Some(dir) = file_path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(file_path, to_toml(&map))?;
+        }
+        *guard = Some(map);
+        Ok(result)
+    }
+
+    /// return the value attached to the path, if any
+    pub fn get(&self, path: &Path) -> Option<V> {
+        self.with_map(|map| map.get(path).cloned())
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.with_map(|map| map.contains_key(path))
+    }
+
+    /// move the values of a file renamed outside of broot, and of
+    /// the files inside it, to its new path
+    pub fn move_path(&self, from: &Path, to: &Path) -> io::Result<bool> {
+        if !self.with_map(|map| map.keys().any(|path| path.starts_with(from))) {
+            return Ok(false);
+        }
+        self.change(|map| move_entries(map, from, to))
+    }
+}
+
+#[cfg(test)]
+mod path_store_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_moves() {
+        let mut map = PathMap::new();
+        map.insert(PathBuf::from("/a"), 1);
+        map.insert(PathBuf::from("/a/b"), 2);
+        map.insert(PathBuf::from("/ab"), 3);
+        assert!(move_entries(&mut map, Path::new("/a"), Path::new("/c")));
+        assert_eq!(map[Path::new("/c")], 1);
+        assert_eq!(map[Path::new("/c/b")], 2);
+        assert_eq!(map[Path::new("/ab")], 3);
+        assert_eq!(map.len(), 3);
+        assert!(!move_entries(&mut map, Path::new("/a"), Path::new("/d")));
+    }
+}
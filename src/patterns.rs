@@ -47,10 +47,26 @@ impl Pattern {
     pub fn score_of(&self, candidate: &str) -> Option<i32> {
         match self {
             Pattern::Fuzzy(fp) => fp.score_of(candidate),
-            Pattern::Regex(rp) => rp.find(candidate).map(|m| m.score),
+            Pattern::Regex(rp) => rp.score_of(candidate),
             Pattern::None => None,
         }
     }
+    /// return the text captured by the first group of a regex
+    /// pattern, if any
+    pub fn capture(&self, candidate: &str) -> Option<String> {
+        match self {
+            Pattern::Regex(rp) => rp.capture(candidate),
+            _ => None,
+        }
+    }
+    /// tell whether the pattern is a fuzzy one starting with a dot,
+    /// which means the user is probably looking for a hidden file
+    pub fn starts_with_dot(&self) -> bool {
+        match self {
+            Pattern::Fuzzy(fp) => fp.starts_with_dot(),
+            _ => false,
+        }
+    }
     pub fn is_some(&self) -> bool {
         match self {
             Pattern::None => false,
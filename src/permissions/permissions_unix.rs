@@ -1,4 +1,11 @@
-use std::{collections::HashMap, sync::Mutex};
+use std::{
+    collections::HashMap,
+    ffi::CString,
+    fs, io,
+    os::unix::{ffi::OsStrExt, fs::PermissionsExt},
+    path::Path,
+    sync::Mutex,
+};
 
 #[cfg(unix)]
 use users;
@@ -36,3 +43,135 @@ pub fn group_name(gid: u32) -> String {
         })
         .to_string()
 }
+
+/// the permission bits a class of users (u, g or o) may have
+fn class_bits(class: char) -> Option<u32> {
+    match class {
+        'u' => Some(0o4700),
+        'g' => Some(0o2070),
+        'o' => Some(0o1007),
+        'a' => Some(0o7777),
+        _ => None,
+    }
+}
+
+/// compute the mode given by a chmod like specification, either
+/// octal (eg `644`) or symbolic (eg `u+x,go-w`), from the current one.
+///
+/// Contrary to chmod, a symbolic clause without class (eg `+x`)
+/// applies to all classes, the umask being ignored.
+pub fn parse_mode(spec: &str, current: u32, is_dir: bool) -> Option<u32> {
+    if !spec.is_empty() && spec.len() <= 4 && spec.chars().all(|c| c.is_digit(8)) {
+        return u32::from_str_radix(spec, 8).ok();
+    }
+    let mut mode = current & 0o7777;
+    for clause in spec.split(',') {
+        let op_idx = clause.find(|c| c == '+' || c == '-' || c == '=')?;
+        let (classes, mut actions) = clause.split_at(op_idx);
+        let mut classes_bits = 0;
+        for class in classes.chars() {
+            classes_bits |= class_bits(class)?;
+        }
+        if classes_bits == 0 {
+            classes_bits = 0o7777;
+        }
+        // a clause may hold several actions, eg `u+x-w`
+        while let Some(op) = actions.chars().next() {
+            let perms_end = actions[1..]
+                .find(|c| c == '+' || c == '-' || c == '=')
+                .map_or(actions.len(), |idx| idx + 1);
+            let mut perm_bits = 0;
+            for perm in actions[1..perms_end].chars() {
+                perm_bits |= match perm {
+                    'r' => 0o444,
+                    'w' => 0o222,
+                    'x' => 0o111,
+                    'X' if is_dir || current & 0o111 != 0 => 0o111,
+                    'X' => 0,
+                    's' => 0o6000,
+                    't' => 0o1000,
+                    _ => return None,
+                };
+            }
+            let bits = perm_bits & classes_bits;
+            match op {
+                '+' => mode |= bits,
+                '-' => mode &= !bits,
+                _ => mode = (mode & !classes_bits) | bits,
+            }
+            actions = &actions[perms_end..];
+        }
+    }
+    Some(mode)
+}
+
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// change the mode of the file, as `chmod` would do
+pub fn chmod(path: &Path, spec: &str) -> io::Result<()> {
+    let metadata = fs::metadata(path)?;
+    let current = metadata.permissions().mode();
+    let mode = parse_mode(spec, current, metadata.is_dir())
+        .ok_or_else(|| invalid_input(format!("invalid mode: {:?}", spec)))?;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+/// change the owner and/or the group of the file, the spec being
+/// like the one of `chown`: `user`, `user:group` or `:group`.
+/// Names and numerical ids are accepted.
+pub fn chown(path: &Path, spec: &str) -> io::Result<()> {
+    let (user, group) = match spec.find(':') {
+        Some(idx) => (&spec[..idx], &spec[idx + 1..]),
+        None => (spec, ""),
+    };
+    if user.is_empty() && group.is_empty() {
+        return Err(invalid_input("a user or a group is needed".to_string()));
+    }
+    // -1 (ie the max value) means the id doesn't change
+    let uid = if user.is_empty() {
+        libc::uid_t::max_value()
+    } else {
+        users::get_user_by_name(user)
+            .map(|user| user.uid())
+            .or_else(|| user.parse().ok())
+            .ok_or_else(|| invalid_input(format!("unknown user: {:?}", user)))?
+    };
+    let gid = if group.is_empty() {
+        libc::gid_t::max_value()
+    } else {
+        users::get_group_by_name(group)
+            .map(|group| group.gid())
+            .or_else(|| group.parse().ok())
+            .ok_or_else(|| invalid_input(format!("unknown group: {:?}", group)))?
+    };
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| invalid_input(format!("invalid path: {:?}", path)))?;
+    match unsafe { libc::chown(c_path.as_ptr(), uid, gid) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+#[cfg(test)]
+mod permissions_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_parse_mode() {
+        assert_eq!(parse_mode("755", 0o644, false), Some(0o755));
+        assert_eq!(parse_mode("u+x", 0o644, false), Some(0o744));
+        assert_eq!(parse_mode("go-r", 0o644, false), Some(0o600));
+        assert_eq!(parse_mode("+x", 0o644, false), Some(0o755));
+        assert_eq!(parse_mode("u=rw,g=r,o=", 0o777, false), Some(0o640));
+        assert_eq!(parse_mode("a+X", 0o600, true), Some(0o711));
+        assert_eq!(parse_mode("a+X", 0o600, false), Some(0o600));
+        assert_eq!(parse_mode("u+x-w", 0o644, false), Some(0o544));
+        assert_eq!(parse_mode("g+s", 0o755, true), Some(0o2755));
+        assert_eq!(parse_mode("u+q", 0o644, false), None);
+        assert_eq!(parse_mode("rw", 0o644, false), None);
+        assert_eq!(parse_mode("99", 0o644, false), None);
+    }
+}
@@ -0,0 +1,227 @@
+//! checks of the names against the rules of the Windows file systems
+//! (NTFS, exFAT, FAT), which forbid some characters and names, so that
+//! a copy or a move to such a file system is refused before it fails
+//! in the middle, and the sanitization of the names.
+//!
+//! On other systems, the file system of the destination is found in
+//! the mounts, and only the mounts of those types are checked.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// the characters which can't be in a name, with the control ones
+const FORBIDDEN_CHARS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// the names of devices, which can't be used, even with an extension
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// the max length of a name, in UTF-16 units
+const MAX_NAME_LEN: usize = 255;
+
+/// the max length of a path for most Windows programs
+#[cfg(windows)]
+const MAX_PATH_LEN: usize = 259;
+
+/// the types of the file systems with the Windows rules, as found in the
+/// mounts (ntfs-3g mounts being of type fuseblk)
+#[cfg(not(windows))]
+const WINDOWS_FS_TYPES: &[&str] = &["exfat", "fuseblk", "msdos", "ntfs", "ntfs3", "vfat"];
+
+/// the walk of the source stops after this number of problems
+const MAX_REPORTED_PROBLEMS: usize = 50;
+
+/// a path which can't be written at the destination
+#[derive(Debug, Clone)]
+pub struct NameProblem {
+    pub path: PathBuf, // the path at the destination
+    pub reason: &'static str,
+    pub fixable: bool, // whether a sanitization of the names would fix it
+}
+
+fn is_forbidden(c: char) -> bool {
+    c < ' ' || FORBIDDEN_CHARS.contains(&c)
+}
+
+fn is_reserved(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    RESERVED_NAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(stem))
+}
+
+/// return why the name can't be used on a Windows file system, if it can't
+pub fn name_problem(name: &str) -> Option<&'static str> {
+    if name.chars().any(is_forbidden) {
+        Some("forbidden character")
+    } else if name.ends_with('.') || name.ends_with(' ') {
+        Some("ends with a dot or a space")
+    } else if is_reserved(name) {
+        Some("reserved name")
+    } else if name.encode_utf16().count() > MAX_NAME_LEN {
+        Some("name too long")
+    } else {
+        None
+    }
+}
+
+/// build a name usable on all the usual file systems, replacing the
+/// forbidden characters with `_`. A valid name is returned unchanged.
+pub fn sanitize(name: &str) -> String {
+    let mut sanitized: String = name
+        .chars()
+        .map(|c| if is_forbidden(c) { '_' } else { c })
+        .collect();
+    while sanitized.encode_utf16().count() > MAX_NAME_LEN {
+        sanitized.pop();
+    }
+    while sanitized.ends_with('.') || sanitized.ends_with(' ') {
+        sanitized.pop();
+    }
+    if sanitized.is_empty() || is_reserved(&sanitized) {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
+
+/// tell whether the file system of the path follows the Windows rules
+#[cfg(windows)]
+fn has_windows_rules(_path: &Path) -> bool {
+    true
+}
+
+/// tell whether the file system of the path follows the Windows rules
+#[cfg(not(windows))]
+fn has_windows_rules(path: &Path) -> bool {
+    // the path doesn't exist yet: we look for its closest existing ancestor
+    let path = match path.ancestors().find(|p| p.exists()).map(fs::canonicalize) {
+        Some(Ok(path)) => path,
+        _ => return false,
+    };
+    let mounts = match crate::filesystems::read_mounts() {
+        Ok(mounts) => mounts,
+        Err(e) => {
+            warn!("failed to read the mounts: {:?}", e);
+            return false;
+        }
+    };
+    mounts
+        .iter()
+        .filter(|mount| path.starts_with(&mount.mount_point))
+        .max_by_key(|mount| mount.mount_point.components().count())
+        .map_or(false, |mount| WINDOWS_FS_TYPES.contains(&mount.fs_type.as_str()))
+}
+
+fn check_entry(source: &Path, dest: &Path, problems: &mut Vec<NameProblem>) {
+    if problems.len() >= MAX_REPORTED_PROBLEMS {
+        return;
+    }
+    let name = dest
+        .file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+    if let Some(reason) = name_problem(&name) {
+        problems.push(NameProblem {
+            path: dest.to_path_buf(),
+            reason,
+            fixable: true,
+        });
+    }
+    #[cfg(windows)]
+    {
+        if dest.to_string_lossy().encode_utf16().count() > MAX_PATH_LEN {
+            problems.push(NameProblem {
+                path: dest.to_path_buf(),
+                reason: "path too long",
+                fixable: false,
+            });
+        }
+    }
+    // symlinks aren't followed
+    if fs::symlink_metadata(source).map_or(false, |md| md.is_dir()) {
+        if let Ok(entries) = fs::read_dir(source) {
+            for entry in entries.filter_map(Result::ok) {
+                check_entry(&entry.path(), &dest.join(entry.file_name()), problems);
+            }
+        }
+    }
+}
+
+/// check that the file or directory at `source` can be copied or moved
+/// to `dest` (the path it would have there), returning the problems of
+/// its name and of the names of its descendants when the destination
+/// is on a file system with the Windows rules
+pub fn check_copy(source: &Path, dest: &Path) -> Vec<NameProblem> {
+    let mut problems = Vec::new();
+    if has_windows_rules(dest) {
+        check_entry(source, dest, &mut problems);
+    }
+    problems
+}
+
+/// describe the problems, which are assumed not empty, in one line
+pub fn describe(problems: &[NameProblem]) -> String {
+    let count = if problems.len() >= MAX_REPORTED_PROBLEMS {
+        format!("{} or more", problems.len())
+    } else {
+        problems.len().to_string()
+    };
+    let first = &problems[0];
+    let mut description = format!(
+        "{} paths can't be written at the destination, eg {:?} ({})",
+        count, first.path, first.reason,
+    );
+    if problems.iter().any(|problem| problem.fixable) {
+        description.push_str(". Fix the names with :sanitize_names");
+    }
+    description
+}
+
+/// return the path and the paths of its descendants, parents before
+/// their children (symlinks aren't followed)
+pub fn tree_paths(path: &Path) -> Vec<PathBuf> {
+    let mut paths = vec![path.to_path_buf()];
+    let mut i = 0;
+    while i < paths.len() {
+        let is_dir = fs::symlink_metadata(&paths[i]).map_or(false, |md| md.is_dir());
+        if is_dir {
+            if let Ok(entries) = fs::read_dir(&paths[i]) {
+                paths.extend(entries.filter_map(Result::ok).map(|entry| entry.path()));
+            }
+        }
+        i += 1;
+    }
+    paths
+}
+
+#[cfg(test)]
+mod portable_names_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_names() {
+        assert_eq!(name_problem("notes.txt"), None);
+        assert_eq!(name_problem("Été 2020.jpg"), None);
+        assert!(name_problem("a:b.txt").is_some());
+        assert!(name_problem("what?").is_some());
+        assert!(name_problem("trailing.").is_some());
+        assert!(name_problem("nul.txt").is_some());
+        assert_eq!(name_problem("nullable.txt"), None);
+        assert!(name_problem(&"a".repeat(256)).is_some());
+    }
+
+    #[test]
+    fn check_sanitization() {
+        assert_eq!(sanitize("notes.txt"), "notes.txt");
+        assert_eq!(sanitize("a:b|c.txt"), "a_b_c.txt");
+        assert_eq!(sanitize("the end. "), "the end");
+        assert_eq!(sanitize("CON"), "_CON");
+        assert_eq!(sanitize("..."), "_");
+        assert_eq!(sanitize(&"é".repeat(300)).chars().count(), 255);
+        for name in &["a:b|c.txt", "the end. ", "aux.c", "..."] {
+            assert_eq!(name_problem(&sanitize(name)), None);
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! the temporary copies of files which can't be opened where they
+//! are: entries of archives, versions of a file at a git commit.
+//!
+//! They're written in a directory private to the user, in the runtime
+//! directory or else in the cache directory, and not in the shared
+//! temporary directory where another user could prepare the paths
+//! (for example as symlinks to the victim's files).
+
+use {
+    crate::conf,
+    std::{
+        fs::{self, File, OpenOptions},
+        io,
+        path::{Path, PathBuf},
+    },
+};
+
+#[cfg(unix)]
+use std::os::unix::fs::DirBuilderExt;
+
+/// return the directory, created if needed, where the copies
+/// related to the named source are written
+pub fn dir(source_name: &str) -> io::Result<PathBuf> {
+    let dirs = conf::app_dirs();
+    let root = dirs
+        .runtime_dir()
+        .unwrap_or_else(|| dirs.cache_dir())
+        .join("previews");
+    create_private_dir(&root)?;
+    let dir = root.join(source_name);
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+#[cfg(unix)]
+fn create_private_dir(path: &Path) -> io::Result<()> {
+    fs::DirBuilder::new().recursive(true).mode(0o700).create(path)
+}
+#[cfg(not(unix))]
+fn create_private_dir(path: &Path) -> io::Result<()> {
+    fs::create_dir_all(path)
+}
+
+/// create the copy, replacing the previous one. The file is created
+/// with `create_new`, so that nothing existing is written through
+pub fn create_file(path: &Path) -> io::Result<File> {
+    if fs::symlink_metadata(path).is_ok() {
+        fs::remove_file(path)?;
+    }
+    OpenOptions::new().write(true).create_new(true).open(path)
+}
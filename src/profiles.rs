@@ -0,0 +1,71 @@
+//! named sets of tree options, declared in the `[profiles]` section of
+//! the configuration, applied with `:profile` or when the root enters a
+//! directory whose special path names them.
+
+use {
+    crate::{
+        sort::Sort,
+        tree_options::TreeOptions,
+    },
+    clap::{AppSettings, ArgMatches},
+};
+
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+    flags: String, // launch flags, as in default_flags (eg "gh")
+    sort: Option<Sort>,
+}
+
+impl Profile {
+    /// build a profile, checking its flags are valid launch flags
+    pub fn new(name: &str, flags: &str, sort: Option<Sort>) -> Result<Profile, clap::Error> {
+        let profile = Profile {
+            name: name.to_string(),
+            flags: flags.to_string(),
+            sort,
+        };
+        profile.flag_matches()?;
+        Ok(profile)
+    }
+    fn flag_matches(&self) -> Result<Option<ArgMatches<'static>>, clap::Error> {
+        if self.flags.is_empty() {
+            return Ok(None);
+        }
+        crate::clap::clap_app()
+            .setting(AppSettings::NoBinaryName)
+            .get_matches_from_safe(vec![format!("-{}", self.flags)])
+            .map(Some)
+    }
+    /// change the options as the profile requires, the other
+    /// options being kept
+    pub fn apply(&self, options: &mut TreeOptions) {
+        if let Ok(Some(matches)) = self.flag_matches() {
+            options.apply(&matches);
+        }
+        if let Some(sort) = self.sort {
+            options.sort = sort;
+            if sort == Sort::Size {
+                options.show_sizes = true;
+            }
+        }
+    }
+}
+
+/// return the profile with this name, the first one winning
+pub fn find<'p>(profiles: &'p [Profile], name: &str) -> Option<&'p Profile> {
+    profiles.iter().find(|profile| profile.name == name)
+}
+
+/// the names of the profiles, for error messages
+pub fn names(profiles: &[Profile]) -> String {
+    if profiles.is_empty() {
+        "none (see the profiles section of the configuration)".to_string()
+    } else {
+        profiles
+            .iter()
+            .map(|profile| profile.name.as_str())
+            .collect::<Vec<&str>>()
+            .join(", ")
+    }
+}
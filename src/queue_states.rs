@@ -0,0 +1,259 @@
+use {
+    crate::{
+        app,
+        app_context::AppContext,
+        app_state::{AppState, AppStateCmdResult},
+        commands::{Action, Command},
+        errors::ProgramError,
+        exec_queue,
+        help_content::{ScreenHelp, ScreenKey},
+        help_states::HelpState,
+        screens::Screen,
+        status::Status,
+        task_sync::Dam,
+        verb_store::PrefixSearchResult,
+    },
+    crossterm::{
+        terminal::{Clear, ClearType},
+        QueueableCommand,
+    },
+    minimad::Composite,
+    std::io::Write,
+};
+
+/// the help of the queue screen
+pub static HELP: ScreenHelp = ScreenHelp {
+    name: "queue screen",
+    description: "This screen lists the commands staged with `:queue`, and how each one went \
+        once they're executed.",
+    keys: &[
+        ScreenKey::new("esc", "get back to the tree, keeping the queue"),
+        ScreenKey::new("enter", "execute the queued commands"),
+        ScreenKey::new("?", "display this help"),
+    ],
+    builtins: Some(&[":back", ":clear_queue", ":execute_queue", ":help", ":quit"]),
+    external_verbs: false,
+};
+
+/// a queued command, with the result of its execution
+/// once it's done
+struct QueueReport {
+    description: String,
+    result: Option<Result<(), String>>,
+}
+
+/// an application state where the user reviews the queued
+/// commands, executes them, and sees how each one went
+pub struct QueueState {
+    reports: Vec<QueueReport>,
+    executed: bool,
+    restored: bool, // whether the commands were queued during a previous session
+}
+
+impl QueueState {
+    /// a state proposing to execute the commands restored on launch
+    pub fn restored() -> QueueState {
+        QueueState {
+            restored: true,
+            ..QueueState::new()
+        }
+    }
+
+    pub fn new() -> QueueState {
+        let reports = exec_queue::descriptions()
+            .into_iter()
+            .map(|description| QueueReport {
+                description,
+                result: None,
+            })
+            .collect();
+        QueueState {
+            reports,
+            executed: false,
+            restored: false,
+        }
+    }
+
+    /// run all the queued commands, in order, even when some fail
+    fn execute(&mut self) -> AppStateCmdResult {
+        if self.executed {
+            return AppStateCmdResult::DisplayError("the queue was already executed".to_string());
+        }
+        self.reports = exec_queue::take()
+            .into_iter()
+            .map(|qe| {
+                info!("executing queued {:?}", &qe.exec_token);
+                let result = qe.run();
+                QueueReport {
+                    description: qe.description,
+                    result: Some(result),
+                }
+            })
+            .collect();
+        self.executed = true;
+        app::clear_caches();
+        AppStateCmdResult::Keep
+    }
+
+    fn count_failures(&self) -> usize {
+        self.reports
+            .iter()
+            .filter(|r| match r.result {
+                Some(Err(_)) => true,
+                _ => false,
+            })
+            .count()
+    }
+}
+
+impl AppState for QueueState {
+    fn help(&self) -> &'static ScreenHelp {
+        &HELP
+    }
+
+    fn has_pending_task(&self) -> bool {
+        false
+    }
+
+    fn can_execute(&self, _verb_index: usize, _con: &AppContext) -> bool {
+        true
+    }
+
+    fn apply(
+        &mut self,
+        cmd: &mut Command,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<AppStateCmdResult, ProgramError> {
+        let verb_execution = match &cmd.action {
+            Action::VerbIndex(index) => Some(&con.verb_store.verbs[*index].execution),
+            Action::VerbInvocate(invocation) => match con.verb_store.search(&invocation.name) {
+                PrefixSearchResult::Match(verb) => Some(&verb.execution),
+                _ => {
+                    return Ok(AppStateCmdResult::verb_not_found(&invocation.name));
+                }
+            },
+            _ => None,
+        };
+        Ok(match (&cmd.action, verb_execution.map(String::as_str)) {
+            (Action::Back, _) | (_, Some(":back")) => AppStateCmdResult::PopState,
+            (Action::OpenSelection, _) | (_, Some(":execute_queue")) => self.execute(),
+            (Action::Help, _) | (_, Some(":help")) => AppStateCmdResult::NewState(
+                Box::new(HelpState::new(screen, con, &HELP)),
+                Command::new(),
+            ),
+            (_, Some(":clear_queue")) => {
+                exec_queue::clear();
+                AppStateCmdResult::PopState
+            }
+            (_, Some(":quit")) => AppStateCmdResult::Quit,
+            (Action::Resize(w, h), _) => {
+                screen.set_terminal_size(*w, *h, con);
+                AppStateCmdResult::Keep
+            }
+            _ => AppStateCmdResult::Keep,
+        })
+    }
+
+    fn refresh(&mut self, _screen: &Screen, _con: &AppContext) -> Command {
+        Command::new()
+    }
+
+    fn do_pending_task(&mut self, _screen: &mut Screen, _con: &AppContext, _dam: &mut Dam) {
+        unreachable!();
+    }
+
+    fn display(
+        &mut self,
+        mut w: &mut dyn Write,
+        screen: &Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        screen.skin.default.queue_bg(&mut w)?;
+        screen.clear(&mut w)?;
+        screen.goto(&mut w, 0, 0)?;
+        screen.skin.help_headers.queue_str(
+            &mut w,
+            if self.restored {
+                "Commands queued in this directory during a previous session"
+            } else {
+                "Queued commands"
+            },
+        )?;
+        let page_height = (screen.height as usize).max(3) - 3;
+        for (y, report) in self.reports.iter().take(page_height).enumerate() {
+            screen.goto(&mut w, 0, y as u16 + 1)?;
+            match &report.result {
+                None => {
+                    screen.skin.tree.queue_str(&mut w, "  · ")?;
+                    screen.skin.file.queue_str(&mut w, &report.description)?;
+                }
+                Some(Ok(())) => {
+                    screen.skin.git_status_new.queue_str(&mut w, "  ✓ ")?;
+                    screen.skin.file.queue_str(&mut w, &report.description)?;
+                }
+                Some(Err(e)) => {
+                    screen.skin.file_error.queue_str(&mut w, "  ✗ ")?;
+                    screen.skin.file.queue_str(&mut w, &report.description)?;
+                    screen.skin.file_error.queue_str(&mut w, &format!("  {}", e))?;
+                }
+            }
+        }
+        if self.reports.len() > page_height {
+            screen.goto(&mut w, 0, page_height as u16)?;
+            screen.skin.pruning.queue_str(
+                &mut w,
+                &format!("  … and {} more", self.reports.len() + 1 - page_height),
+            )?;
+            screen.clear_line(&mut w)?;
+        }
+        Ok(())
+    }
+
+    fn write_status(
+        &self,
+        mut w: &mut dyn Write,
+        _cmd: &Command,
+        screen: &Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        let failures = self.count_failures();
+        if !self.executed && self.restored {
+            let md = format!(
+                "Hit *enter* to execute the {} restored commands, *esc* to keep them queued, or `:clear_queue` to forget them",
+                self.reports.len(),
+            );
+            Status::from_message(Composite::from_inline(&md)).display(&mut w, screen)
+        } else if !self.executed {
+            let md = format!(
+                "Hit *enter* to execute the {} queued commands, *esc* to get back to the tree (the queue is kept)",
+                self.reports.len(),
+            );
+            Status::from_message(Composite::from_inline(&md)).display(&mut w, screen)
+        } else if failures > 0 {
+            let md = format!(
+                "{} command(s) failed out of {}. Hit *esc* to get back to the tree",
+                failures,
+                self.reports.len(),
+            );
+            Status::from_error(Composite::from_inline(&md)).display(&mut w, screen)
+        } else {
+            Status::from_message(mad_inline!(
+                "All commands were successfully executed. Hit *esc* to get back to the tree"
+            ))
+            .display(&mut w, screen)
+        }
+    }
+
+    /// there's no meaningful flags here
+    fn write_flags(
+        &self,
+        mut w: &mut dyn Write,
+        screen: &mut Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        screen.skin.default.queue_bg(&mut w)?;
+        w.queue(Clear(ClearType::UntilNewLine))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+//! the list of the roots broot was recently launched on, kept
+//! in a file so that they can be proposed on the start screen
+
+use {
+    crate::conf,
+    std::{
+        fs, io,
+        path::{Path, PathBuf},
+    },
+};
+
+const MAX_RECENT_ROOTS: usize = 30;
+
+fn file_path() -> PathBuf {
+    conf::app_dirs().data_dir().join("recent_roots")
+}
+
+/// read the recent roots, the most recent first.
+/// Directories which don't exist anymore are skipped.
+pub fn read() -> Vec<PathBuf> {
+    fs::read_to_string(file_path())
+        .map(|content| {
+            content
+                .lines()
+                .map(PathBuf::from)
+                .filter(|path| path.is_dir())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// put the path at the head of the recent roots
+pub fn remember(path: &Path) -> io::Result<()> {
+    let mut roots = read();
+    roots.retain(|root| root != path);
+    roots.insert(0, path.to_path_buf());
+    roots.truncate(MAX_RECENT_ROOTS);
+    let file_path = file_path();
+    if let Some(dir) = file_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let lines: Vec<String> = roots
+        .iter()
+        .map(|root| root.to_string_lossy().to_string())
+        .collect();
+    fs::write(file_path, lines.join("\n"))
+}
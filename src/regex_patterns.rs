@@ -1,6 +1,6 @@
 //! a filename filtering pattern using a regular expression
 
-use std::fmt;
+use std::{fmt, ops::Range};
 
 use regex;
 
@@ -39,21 +39,42 @@ impl RegexPattern {
             flags: flags.to_string(),
         })
     }
-    // return a match if the pattern can be found in the candidate string
+    // return a match if the pattern can be found in the candidate string.
+    // When the regex has groups, only the captured chars are highlighted.
     pub fn find(&self, candidate: &str) -> Option<patterns::Match> {
-        // note that there's no significative cost related to using
-        //  find over is_match
-        match self.rex.find(candidate) {
-            Some(rm) => {
-                let mut pos = Vec::with_capacity(rm.end() - rm.start());
-                for i in rm.start()..rm.end() {
-                    pos.push(i);
-                }
-                Some(patterns::Match { score: 1, pos })
-            }
-            None => None,
+        let ranges: Vec<Range<usize>> = if self.rex.captures_len() > 1 {
+            let caps = self.rex.captures(candidate)?;
+            caps.iter().skip(1).flatten().map(|m| m.range()).collect()
+        } else {
+            // note that there's no significative cost related to using
+            //  find over is_match
+            vec![self.rex.find(candidate)?.range()]
+        };
+        // the ranges are in bytes while we need char positions
+        let pos = candidate
+            .char_indices()
+            .enumerate()
+            .filter(|(_, (byte_idx, _))| ranges.iter().any(|r| r.contains(byte_idx)))
+            .map(|(char_idx, _)| char_idx)
+            .collect();
+        Some(patterns::Match { score: 1, pos })
+    }
+    // return the score of the candidate, always 1 for a regex, when
+    //  the candidate matches
+    pub fn score_of(&self, candidate: &str) -> Option<i32> {
+        if self.rex.is_match(candidate) {
+            Some(1)
+        } else {
+            None
         }
     }
+    // return the text captured by the first group of the regex, if any
+    pub fn capture(&self, candidate: &str) -> Option<String> {
+        self.rex
+            .captures(candidate)?
+            .get(1)
+            .map(|m| m.as_str().to_string())
+    }
     // return the number of results we should find before starting to
     //  sort them (unless time is runing out).
     // In the case of regexes, there's no need to find more results, as
@@ -62,3 +83,23 @@ impl RegexPattern {
         targeted_size
     }
 }
+
+#[cfg(test)]
+mod regex_patterns_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_capture_positions() {
+        let rp = RegexPattern::from(r"(\w+)_(\d+)\.rs", "").unwrap();
+        assert_eq!(rp.find("mod_12.rs").unwrap().pos, vec![0, 1, 2, 4, 5]);
+        assert_eq!(rp.capture("mod_12.rs"), Some("mod".to_string()));
+        assert!(rp.find("mod.rs").is_none());
+        // positions are in chars, not in bytes
+        let rp = RegexPattern::from("é(t)", "").unwrap();
+        assert_eq!(rp.find("un été").unwrap().pos, vec![4]);
+        let rp = RegexPattern::from("ét", "").unwrap();
+        assert_eq!(rp.find("un été").unwrap().pos, vec![3, 4]);
+        assert_eq!(rp.capture("un été"), None);
+    }
+}
@@ -0,0 +1,163 @@
+use {
+    super::{RemoteEntry, Server},
+    directories::UserDirs,
+    ssh2::{CheckResult, FileStat, KnownHostFileKind, Session, Sftp},
+    std::{
+        collections::HashMap,
+        io,
+        net::TcpStream,
+        path::{Path, PathBuf},
+        time::{Duration, UNIX_EPOCH},
+    },
+};
+
+/// how long a request may wait for the server before failing
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// the private keys tried, in order, when the ssh agent can't authenticate
+const KEY_FILES: &[&str] = &["id_ed25519", "id_ecdsa", "id_rsa"];
+
+fn error(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, message)
+}
+
+fn ssh_dir() -> Option<PathBuf> {
+    UserDirs::new().map(|dirs| dirs.home_dir().join(".ssh"))
+}
+
+impl RemoteEntry {
+    fn new(name: String, stat: &FileStat) -> Self {
+        let is_dir = stat.is_dir();
+        Self {
+            name,
+            is_dir,
+            size: if is_dir { 0 } else { stat.size.unwrap_or(0) },
+            mode: stat.perm.unwrap_or(0),
+            uid: stat.uid,
+            gid: stat.gid,
+            modified: stat.mtime.map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+        }
+    }
+}
+
+/// an opened SFTP session, with the listings already received
+pub struct Connection {
+    pub server: Server,
+    sftp: Sftp,
+    listings: HashMap<String, Vec<RemoteEntry>>, // by path of the directory
+    stats: HashMap<String, RemoteEntry>,         // entries which weren't listed
+}
+
+impl Connection {
+    pub fn new(server: &Server) -> io::Result<Self> {
+        let tcp = TcpStream::connect((server.host.as_str(), server.port))?;
+        let mut session = Session::new()?;
+        session.set_timeout(TIMEOUT.as_millis() as u32);
+        session.set_tcp_stream(tcp);
+        session.handshake()?;
+        check_host_key(&session, server)?;
+        authenticate(&session, server)?;
+        let sftp = session.sftp()?;
+        Ok(Self {
+            server: server.clone(),
+            sftp,
+            listings: HashMap::new(),
+            stats: HashMap::new(),
+        })
+    }
+
+    pub fn read_dir(&mut self, path: &str) -> io::Result<Vec<RemoteEntry>> {
+        if let Some(entries) = self.listings.get(path) {
+            return Ok(entries.clone());
+        }
+        let listed = time!(Debug, "sftp readdir", path, self.sftp.readdir(Path::new(path)))?;
+        let entries: Vec<RemoteEntry> = listed
+            .iter()
+            .filter_map(|(child, stat)| {
+                let name = child.file_name()?.to_str()?.to_string();
+                Some(RemoteEntry::new(name, stat))
+            })
+            .collect();
+        self.listings.insert(path.to_string(), entries.clone());
+        Ok(entries)
+    }
+
+    pub fn entry(&mut self, path: &str) -> io::Result<RemoteEntry> {
+        let path = Path::new(path);
+        // the entry was probably listed with its directory
+        let dir = path.parent().and_then(|dir| dir.to_str());
+        let name = path.file_name().and_then(|name| name.to_str());
+        if let (Some(dir), Some(name)) = (dir, name) {
+            let listed = self.listings.get(dir).and_then(|e| e.iter().find(|e| e.name == name));
+            if let Some(entry) = listed {
+                return Ok(entry.clone());
+            }
+        }
+        let key = path.to_string_lossy().to_string();
+        if let Some(entry) = self.stats.get(&key) {
+            return Ok(entry.clone());
+        }
+        let name = name.map_or_else(|| key.clone(), |name| name.to_string());
+        let entry = RemoteEntry::new(name, &self.sftp.stat(path)?);
+        self.stats.insert(key, entry.clone());
+        Ok(entry)
+    }
+
+    pub fn clear_cache(&mut self) {
+        self.listings.clear();
+        self.stats.clear();
+    }
+}
+
+/// check the key of the server is the one in the user's known_hosts
+/// file, as ssh would do, so that we don't send anything to an impostor
+fn check_host_key(session: &Session, server: &Server) -> io::Result<()> {
+    let (key, _) = match session.host_key() {
+        Some(host_key) => host_key,
+        None => {
+            return Err(error("the server gave no host key".to_string()));
+        }
+    };
+    let mut known_hosts = session.known_hosts()?;
+    if let Some(ssh_dir) = ssh_dir() {
+        known_hosts.read_file(&ssh_dir.join("known_hosts"), KnownHostFileKind::OpenSSH)?;
+    }
+    match known_hosts.check_port(&server.host, server.port, key) {
+        CheckResult::Match => Ok(()),
+        CheckResult::NotFound => Err(error(format!(
+            "{} isn't a known host: connect once with ssh to check its key",
+            server.host,
+        ))),
+        CheckResult::Mismatch => Err(error(format!(
+            "the key of {} doesn't match the known one",
+            server.host,
+        ))),
+        CheckResult::Failure => Err(error("failed to check the host key".to_string())),
+    }
+}
+
+/// authenticate with the ssh agent or, if it fails, with a
+/// private key without passphrase (there's no prompt)
+fn authenticate(session: &Session, server: &Server) -> io::Result<()> {
+    if session.userauth_agent(&server.user).is_ok() && session.authenticated() {
+        return Ok(());
+    }
+    if let Some(ssh_dir) = ssh_dir() {
+        for key_file in KEY_FILES {
+            let private_key = ssh_dir.join(key_file);
+            if private_key.exists()
+                && session.userauth_pubkey_file(&server.user, None, &private_key, None).is_ok()
+                && session.authenticated()
+            {
+                return Ok(());
+            }
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        format!(
+            "authentication of {} failed: an ssh agent or a key without passphrase is needed",
+            server.user,
+        ),
+    ))
+}
@@ -0,0 +1,170 @@
+//! browsing of remote trees, through SFTP.
+//!
+//! The paths of remote files are URLs like `sftp://dys@example.com/home/dys`.
+//! They're given as is to verbs, as `{file}`, and are understood by
+//! tools like `sftp` or `curl`.
+//!
+//! Only one server is connected at a time, and the listings of its
+//! directories are kept until the next refresh, as every request pays
+//! the latency of the network.
+
+mod connection;
+
+use {
+    connection::Connection,
+    std::{
+        env,
+        io,
+        path::Path,
+        sync::Mutex,
+        time::SystemTime,
+    },
+};
+
+static SCHEME: &str = "sftp://";
+
+const DEFAULT_PORT: u16 = 22;
+
+/// the server and the account used to connect to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Server {
+    pub user: String,
+    pub host: String,
+    pub port: u16,
+}
+
+/// a remote path, split into the server and the
+/// absolute path on the server
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemotePath {
+    pub server: Server,
+    pub path: String,
+}
+
+/// a file or directory of the remote server, as listed
+#[derive(Debug, Clone)]
+pub struct RemoteEntry {
+    pub name: String,
+    pub is_dir: bool, // symlinks are never followed, so they're files here
+    pub size: u64,
+    pub mode: u32,
+    pub uid: Option<u32>, // ids on the server, which can't be resolved into names
+    pub gid: Option<u32>,
+    pub modified: Option<SystemTime>,
+}
+
+lazy_static! {
+    static ref CONNECTION: Mutex<Option<Connection>> = Mutex::new(None);
+}
+
+/// tell whether the path is the URL of a remote file
+pub fn is_remote(path: &Path) -> bool {
+    path.to_str().map_or(false, |s| s.starts_with(SCHEME))
+}
+
+/// parse a URL like `sftp://user@host:port/path`, where only the host is mandatory
+fn parse_url(url: &str, default_user: &str) -> Option<RemotePath> {
+    if !url.starts_with(SCHEME) {
+        return None;
+    }
+    let url = &url[SCHEME.len()..];
+    let (authority, path) = match url.find('/') {
+        Some(idx) => (&url[..idx], &url[idx..]),
+        None => (url, "/"),
+    };
+    let (user, host_port) = match authority.rfind('@') {
+        Some(idx) => (&authority[..idx], &authority[idx + 1..]),
+        None => (default_user, authority),
+    };
+    let (host, port) = match host_port.rfind(':') {
+        Some(idx) => (&host_port[..idx], host_port[idx + 1..].parse().ok()?),
+        None => (host_port, DEFAULT_PORT),
+    };
+    if host.is_empty() || user.is_empty() {
+        return None;
+    }
+    let path = path.trim_end_matches('/');
+    Some(RemotePath {
+        server: Server {
+            user: user.to_string(),
+            host: host.to_string(),
+            port,
+        },
+        path: if path.is_empty() { "/".to_string() } else { path.to_string() },
+    })
+}
+
+impl RemotePath {
+    /// parse the path of a remote file. The user, when not given,
+    /// is the local one, as with ssh.
+    pub fn from(path: &Path) -> Option<Self> {
+        let default_user = env::var("USER").or_else(|_| env::var("USERNAME")).unwrap_or_default();
+        parse_url(path.to_str()?, &default_user)
+    }
+}
+
+fn with_connection<R>(
+    server: &Server,
+    f: impl FnOnce(&mut Connection) -> io::Result<R>,
+) -> io::Result<R> {
+    let mut connection = CONNECTION.lock().unwrap();
+    match &mut *connection {
+        Some(c) if c.server == *server => {}
+        _ => {
+            *connection = Some(time!(Info, "connection", &server.host, Connection::new(server)?));
+        }
+    }
+    match &mut *connection {
+        Some(c) => f(c),
+        None => unreachable!(),
+    }
+}
+
+/// return the entries of the remote directory
+pub fn read_dir(path: &Path) -> io::Result<Vec<RemoteEntry>> {
+    let remote = RemotePath::from(path).ok_or_else(|| invalid_url(path))?;
+    with_connection(&remote.server, |c| c.read_dir(&remote.path))
+}
+
+/// return the description of the remote file, usually
+/// without request as it was listed with its directory
+pub fn entry(path: &Path) -> io::Result<RemoteEntry> {
+    let remote = RemotePath::from(path).ok_or_else(|| invalid_url(path))?;
+    with_connection(&remote.server, |c| c.entry(&remote.path))
+}
+
+/// forget the listings, so that the directories are read again
+pub fn clear_cache() {
+    if let Some(connection) = &mut *CONNECTION.lock().unwrap() {
+        connection.clear_cache();
+    }
+}
+
+fn invalid_url(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("invalid remote path: {:?} (expected sftp://user@host/path)", path),
+    )
+}
+
+#[cfg(test)]
+mod remote_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_parse_url() {
+        let remote = parse_url("sftp://dys@example.com:2222/home/dys/", "me").unwrap();
+        assert_eq!(remote.server.user, "dys");
+        assert_eq!(remote.server.host, "example.com");
+        assert_eq!(remote.server.port, 2222);
+        assert_eq!(remote.path, "/home/dys");
+        let remote = parse_url("sftp://example.com", "me").unwrap();
+        assert_eq!(remote.server.user, "me");
+        assert_eq!(remote.server.port, DEFAULT_PORT);
+        assert_eq!(remote.path, "/");
+        assert!(parse_url("sftp://dys@/home", "me").is_none());
+        assert!(parse_url("sftp://example.com:ssh/", "me").is_none());
+        assert!(parse_url("/home/dys", "me").is_none());
+    }
+}
@@ -0,0 +1,296 @@
+use {
+    crate::{
+        app_context::AppContext,
+        app_state::{AppState, AppStateCmdResult},
+        bulk_rename::{self, Rename, RenameStatus},
+        commands::{Action, Command},
+        errors::ProgramError,
+        help_content::ScreenHelp,
+        help_states::HelpState,
+        op_journal::{self, Operation},
+        rename_verbs,
+        screens::Screen,
+        status::Status,
+        task_sync::Dam,
+        verb_store::PrefixSearchResult,
+        verbs::VerbExecutor,
+    },
+    crossterm::{
+        terminal::{Clear, ClearType},
+        QueueableCommand,
+    },
+    std::{
+        io::Write,
+        path::{Path, PathBuf},
+    },
+};
+
+/// an application state previewing the renames of `:rename_regex`,
+/// `:sanitize_names` or `:organize`, which are applied when the user
+/// hits enter
+pub struct RenameState {
+    root: PathBuf, // the paths are displayed relative to it
+    renames: Vec<Rename>,
+    applied: bool,
+    pub scroll: usize,
+}
+
+impl RenameState {
+    /// build the preview of planned renames (see the bulk_rename module)
+    pub fn new(root: &Path, renames: Vec<Rename>) -> RenameState {
+        RenameState {
+            root: root.to_path_buf(),
+            renames,
+            applied: false,
+            scroll: 0,
+        }
+    }
+
+    /// return the number of lines available for the renames
+    pub fn page_height(screen: &Screen) -> usize {
+        (screen.height as usize).max(3) - 3
+    }
+
+    pub fn renames_count(&self) -> usize {
+        self.renames.len()
+    }
+
+    fn planned_count(&self) -> usize {
+        self.renames
+            .iter()
+            .filter(|rename| rename.status.is_planned())
+            .count()
+    }
+
+    pub fn try_scroll(&mut self, dy: i32, page_height: usize) {
+        let max_scroll = self.renames.len().max(page_height) - page_height;
+        self.scroll = (self.scroll as i32 + dy).max(0).min(max_scroll as i32) as usize;
+    }
+
+    fn failed_count(&self) -> usize {
+        self.renames
+            .iter()
+            .filter(|rename| matches!(rename.status, RenameStatus::Failed(_)))
+            .count()
+    }
+
+    /// do the planned renames. The preview is closed unless some failed,
+    /// so that the user sees why, and may retry them (for example after
+    /// having closed the program which was using a file).
+    pub fn apply_renames(&mut self) -> AppStateCmdResult {
+        if self.applied {
+            if self.failed_count() == 0 {
+                return AppStateCmdResult::PopState;
+            }
+            for rename in &mut self.renames {
+                if let RenameStatus::Failed(_) = rename.status {
+                    rename.status = RenameStatus::Planned;
+                }
+            }
+        }
+        let planned = self.planned_count();
+        if planned == 0 {
+            return AppStateCmdResult::DisplayError("nothing to rename".to_string());
+        }
+        self.applied = true;
+        let was_planned: Vec<bool> = self
+            .renames
+            .iter()
+            .map(|rename| rename.status.is_planned())
+            .collect();
+        let failures = bulk_rename::apply(&mut self.renames);
+        // the renames are journaled in the order they were done, so that
+        // they can be undone
+        let done: Vec<(PathBuf, PathBuf)> = self
+            .renames
+            .iter()
+            .zip(was_planned)
+            .rev()
+            .filter(|(rename, was_planned)| *was_planned && rename.status.is_done())
+            .map(|(rename, _)| (rename.old_path.clone(), rename.new_path.clone()))
+            .collect();
+        if !done.is_empty() {
+            op_journal::record(Operation::Renames(done));
+        }
+        match failures {
+            0 => AppStateCmdResult::PopState,
+            failures => AppStateCmdResult::DisplayError(format!(
+                "{} of the {} renames failed",
+                failures, planned,
+            )),
+        }
+    }
+
+    fn displayed_path(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+impl AppState for RenameState {
+    fn help(&self) -> &'static ScreenHelp {
+        &rename_verbs::HELP
+    }
+
+    fn has_pending_task(&self) -> bool {
+        false
+    }
+
+    fn can_execute(&self, _verb_index: usize, _con: &AppContext) -> bool {
+        true
+    }
+
+    fn apply(
+        &mut self,
+        cmd: &mut Command,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<AppStateCmdResult, ProgramError> {
+        let page_height = RenameState::page_height(screen);
+        Ok(match &cmd.action {
+            Action::Back => AppStateCmdResult::PopState,
+            Action::Help => {
+                AppStateCmdResult::NewState(
+                    Box::new(HelpState::new(screen, con, &rename_verbs::HELP)),
+                    Command::new(),
+                )
+            }
+            Action::MoveSelection(dy) | Action::Scroll(dy) => {
+                self.try_scroll(*dy, page_height);
+                AppStateCmdResult::Keep
+            }
+            Action::OpenSelection => self.apply_renames(),
+            Action::Resize(w, h) => {
+                screen.set_terminal_size(*w, *h, con);
+                AppStateCmdResult::Keep
+            }
+            Action::VerbIndex(index) => {
+                let verb = &con.verb_store.verbs[*index];
+                self.execute_verb(verb, &verb.invocation, screen, con)?
+            }
+            Action::VerbInvocate(invocation) => match con.verb_store.search(&invocation.name) {
+                PrefixSearchResult::Match(verb) => {
+                    self.execute_verb(verb, &invocation, screen, con)?
+                }
+                _ => AppStateCmdResult::verb_not_found(&invocation.name),
+            },
+            _ => AppStateCmdResult::Keep,
+        })
+    }
+
+    /// the renames aren't planned again, as it would hide their outcome
+    fn refresh(&mut self, _screen: &Screen, _con: &AppContext) -> Command {
+        Command::new()
+    }
+
+    fn do_pending_task(&mut self, _screen: &mut Screen, _con: &AppContext, _dam: &mut Dam) {
+        unreachable!();
+    }
+
+    fn display(
+        &mut self,
+        mut w: &mut dyn Write,
+        screen: &Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        let page_height = RenameState::page_height(screen);
+        screen.goto_clear(&mut w, 0, 0)?;
+        screen.skin.default.queue_bg(&mut w)?;
+        screen
+            .skin
+            .help_headers
+            .queue_str(&mut w, "current path → new name")?;
+        screen.clear_line(&mut w)?;
+        for y in 0..page_height {
+            screen.goto(&mut w, 0, y as u16 + 1)?;
+            let idx = self.scroll + y;
+            if let Some(rename) = self.renames.get(idx) {
+                screen
+                    .skin
+                    .file
+                    .queue(&mut w, self.displayed_path(&rename.old_path))?;
+                screen.skin.tree.queue_str(&mut w, " → ")?;
+                let new_name_style = match rename.status {
+                    RenameStatus::Planned | RenameStatus::Done => &screen.skin.file,
+                    _ => &screen.skin.file_error,
+                };
+                // a file moved to another directory is shown with its new path
+                let new_name = if rename.new_path.parent() == rename.old_path.parent() {
+                    rename.new_name()
+                } else {
+                    self.displayed_path(&rename.new_path)
+                };
+                new_name_style.queue(&mut w, new_name)?;
+                if !rename.status.is_planned() {
+                    screen
+                        .skin
+                        .pruning
+                        .queue(&mut w, format!("  {}", rename.status))?;
+                }
+            } else if idx == 0 && self.renames.is_empty() {
+                screen.skin.tree.queue_str(&mut w, "No visible file name is matched")?;
+            }
+            screen.skin.default.queue_bg(&mut w)?;
+            w.queue(Clear(ClearType::UntilNewLine))?;
+        }
+        Ok(())
+    }
+
+    fn write_status(
+        &self,
+        mut w: &mut dyn Write,
+        cmd: &Command,
+        screen: &Screen,
+        con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        match &cmd.action {
+            Action::VerbEdit(invocation) if !invocation.name.is_empty() => {
+                match con.verb_store.search(&invocation.name) {
+                    PrefixSearchResult::Match(verb) => Status::from_message(mad_inline!(
+                        "Hit *enter* to **$0**",
+                        &verb.invocation.name,
+                    ))
+                    .display(&mut w, screen),
+                    _ => Status::from_error(mad_inline!("No matching verb"))
+                        .display(&mut w, screen),
+                }
+            }
+            _ if self.applied && self.failed_count() > 0 => {
+                let failed = self.failed_count().to_string();
+                Status::from_message(mad_inline!(
+                    "Hit *enter* to retry the $0 failed renames, *esc* to get back to the tree",
+                    &failed,
+                ))
+                .display(&mut w, screen)
+            }
+            _ if self.applied => {
+                Status::from_message(mad_inline!("Hit *esc* to get back to the tree"))
+                    .display(&mut w, screen)
+            }
+            _ => {
+                let planned = self.planned_count().to_string();
+                let skipped = (self.renames.len() - self.planned_count()).to_string();
+                Status::from_message(mad_inline!(
+                    "Hit *enter* to do the $0 renames ($1 skipped), *esc* to cancel",
+                    &planned,
+                    &skipped,
+                ))
+                .display(&mut w, screen)
+            }
+        }
+    }
+
+    /// there's no meaningful flags here
+    fn write_flags(
+        &self,
+        mut w: &mut dyn Write,
+        screen: &mut Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        screen.skin.default.queue_bg(&mut w)?;
+        w.queue(Clear(ClearType::UntilNewLine))?;
+        Ok(())
+    }
+}
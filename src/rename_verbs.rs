@@ -0,0 +1,86 @@
+//! this modules defines the execution of verbs on the rename preview screen
+
+use crate::{
+    app_context::AppContext,
+    app_state::AppStateCmdResult,
+    commands::Command,
+    errors::ProgramError,
+    help_content::{ScreenHelp, ScreenKey},
+    help_states::HelpState,
+    rename_states::RenameState,
+    screens::Screen,
+    verb_invocation::VerbInvocation,
+    verbs::{Verb, VerbExecutor},
+};
+
+/// the help of the rename preview screen
+pub static HELP: ScreenHelp = ScreenHelp {
+    name: "rename preview",
+    description: "This screen lists the renames planned by `:rename_regex`, `:sanitize_names` \
+        or `:organize`, before they're done.",
+    keys: &[
+        ScreenKey::new("esc", "cancel the renames and get back to the tree"),
+        ScreenKey::new("↑ ↓", "scroll"),
+        ScreenKey::new("enter", "do the renames, or retry the failed ones"),
+        ScreenKey::new("?", "display this help"),
+    ],
+    builtins: Some(&[
+        ":back", ":help", ":line_down", ":line_up", ":page_down", ":page_up", ":quit",
+        ":select_first", ":select_last",
+    ]),
+    external_verbs: false,
+};
+
+impl VerbExecutor for RenameState {
+    fn execute_verb(
+        &mut self,
+        verb: &Verb,
+        invocation: &VerbInvocation,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<AppStateCmdResult, ProgramError> {
+        if let Some(err) = verb.match_error(invocation) {
+            return Ok(AppStateCmdResult::DisplayError(err));
+        }
+        if let Some(err) = HELP.unlisted_error(verb) {
+            return Ok(AppStateCmdResult::DisplayError(err));
+        }
+        let page_height = RenameState::page_height(screen);
+        Ok(match verb.execution.as_ref() {
+            ":back" => AppStateCmdResult::PopState,
+            ":help" => AppStateCmdResult::NewState(
+                Box::new(HelpState::new(screen, con, &HELP)),
+                Command::new(),
+            ),
+            ":line_down" => {
+                self.try_scroll(1, page_height);
+                AppStateCmdResult::Keep
+            }
+            ":line_up" => {
+                self.try_scroll(-1, page_height);
+                AppStateCmdResult::Keep
+            }
+            ":page_down" => {
+                self.try_scroll(page_height as i32, page_height);
+                AppStateCmdResult::Keep
+            }
+            ":page_up" => {
+                self.try_scroll(-(page_height as i32), page_height);
+                AppStateCmdResult::Keep
+            }
+            ":quit" => AppStateCmdResult::Quit,
+            ":select_first" => {
+                self.scroll = 0;
+                AppStateCmdResult::Keep
+            }
+            ":select_last" => {
+                self.try_scroll(self.renames_count() as i32, page_height);
+                AppStateCmdResult::Keep
+            }
+            _ if verb.execution.starts_with(':') => AppStateCmdResult::Keep,
+            _ => AppStateCmdResult::DisplayError(
+                "hit enter to do the renames, or esc to cancel them".to_string(),
+            ),
+        })
+    }
+}
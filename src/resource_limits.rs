@@ -0,0 +1,111 @@
+//! awareness of the limit on open files (see `ulimit -n`): when it's
+//! reached while directories are read in parallel, fewer threads are
+//! used from then on and the user is warned, instead of parts of the
+//! tree silently missing.
+
+use std::{
+    fs, io,
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::Duration,
+};
+
+/// number of threads reading directories when there's no problem
+pub const MAX_PARALLELISM: usize = 8;
+
+/// how many times a directory is read again after a lack of file descriptors
+const MAX_RETRIES: u32 = 5;
+
+/// the pause before the first retry, the next ones being longer
+const RETRY_PAUSE: Duration = Duration::from_millis(20);
+
+static PARALLELISM: AtomicUsize = AtomicUsize::new(MAX_PARALLELISM);
+
+// the parallelism the user was last told about
+static WARNED_PARALLELISM: AtomicUsize = AtomicUsize::new(MAX_PARALLELISM);
+
+/// the number of threads which may read directories at the same time
+pub fn parallelism() -> usize {
+    PARALLELISM.load(Ordering::Relaxed)
+}
+
+/// tell whether the error comes from the lack of file descriptors,
+/// either for the process (EMFILE) or the whole system (ENFILE)
+#[cfg(unix)]
+pub fn is_exhaustion(err: &io::Error) -> bool {
+    matches!(err.raw_os_error(), Some(libc::EMFILE) | Some(libc::ENFILE))
+}
+
+#[cfg(windows)]
+pub fn is_exhaustion(err: &io::Error) -> bool {
+    err.raw_os_error() == Some(winapi::shared::winerror::ERROR_TOO_MANY_OPEN_FILES as i32)
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn is_exhaustion(_err: &io::Error) -> bool {
+    false
+}
+
+/// halve the parallelism, down to one thread
+fn back_off() {
+    let parallelism = parallelism();
+    if parallelism > 1 {
+        // when another thread backed off at the same time, once is enough
+        let _ = PARALLELISM.compare_exchange(
+            parallelism,
+            parallelism / 2,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        );
+    }
+}
+
+/// read a directory, backing off the parallelism and retrying a few
+/// times when the process lacks file descriptors
+pub fn read_dir(path: &Path) -> io::Result<fs::ReadDir> {
+    let mut retries = 0;
+    loop {
+        match fs::read_dir(path) {
+            Err(e) if is_exhaustion(&e) && retries < MAX_RETRIES => {
+                back_off();
+                retries += 1;
+                warn!("no file descriptor to read {:?}, parallelism now {}", path, parallelism());
+                thread::sleep(RETRY_PAUSE * retries);
+            }
+            result => {
+                return result;
+            }
+        }
+    }
+}
+
+/// return the warning to display when the parallelism was reduced
+/// since the last call
+pub fn take_warning() -> Option<String> {
+    let parallelism = parallelism();
+    let warned = WARNED_PARALLELISM.swap(parallelism, Ordering::Relaxed);
+    if parallelism < warned {
+        Some(format!(
+            "Too many open files: directories are now read by {} thread{} (see `ulimit -n`)",
+            parallelism,
+            if parallelism > 1 { "s" } else { "" },
+        ))
+    } else {
+        None
+    }
+}
+
+#[cfg(all(test, unix))]
+mod resource_limits_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_exhaustion_errors() {
+        assert!(is_exhaustion(&io::Error::from_raw_os_error(libc::EMFILE)));
+        assert!(is_exhaustion(&io::Error::from_raw_os_error(libc::ENFILE)));
+        assert!(!is_exhaustion(&io::Error::from_raw_os_error(libc::ENOENT)));
+        assert!(!is_exhaustion(&io::Error::from(io::ErrorKind::NotFound)));
+    }
+}
@@ -0,0 +1,75 @@
+//! helpers for the multi-root view, where several directories
+//! are displayed as branches of their closest common ancestor
+
+use {
+    std::path::{Path, PathBuf},
+};
+
+/// return the deepest directory containing all the given paths
+pub fn common_ancestor(paths: &[PathBuf]) -> PathBuf {
+    let mut ancestor: Option<&Path> = None;
+    for path in paths {
+        ancestor = Some(match ancestor {
+            None => path,
+            Some(mut a) => {
+                while !path.starts_with(a) {
+                    a = match a.parent() {
+                        Some(p) => p,
+                        None => break,
+                    };
+                }
+                a
+            }
+        });
+    }
+    ancestor.map_or_else(|| PathBuf::from("/"), Path::to_path_buf)
+}
+
+/// remove the roots which are inside another one (or repeated): the
+/// whole content of a root is displayed, so they'd restrict it
+pub fn dedupe(roots: &mut Vec<PathBuf>) {
+    roots.sort();
+    roots.dedup();
+    let all = roots.clone();
+    roots.retain(|r| !all.iter().any(|a| a != r && r.starts_with(a)));
+}
+
+/// tell whether the path must be kept when only the given roots
+/// should be displayed: it must be either one of their ancestors
+/// or inside one of them
+pub fn is_on_roots_path(path: &Path, roots: &[PathBuf]) -> bool {
+    roots.is_empty() || roots.iter().any(|r| path.starts_with(r) || r.starts_with(path))
+}
+
+#[cfg(test)]
+mod roots_tests {
+
+    use {
+        super::*,
+        std::path::PathBuf,
+    };
+
+    fn check(paths: &[&str], ancestor: &str) {
+        let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+        assert_eq!(common_ancestor(&paths), PathBuf::from(ancestor));
+    }
+
+    #[test]
+    fn test_common_ancestor() {
+        check(&["/home/dys/dev"], "/home/dys/dev");
+        check(&["/home/dys/dev/broot", "/home/dys/dev/termimad"], "/home/dys/dev");
+        check(&["/home/dys/dev/broot", "/home/dys"], "/home/dys");
+        check(&["/home/dys/dev", "/home/dysto/dev"], "/home");
+        check(&["/home/dys", "/var/log"], "/");
+    }
+
+    #[test]
+    fn test_dedupe() {
+        let mut roots: Vec<PathBuf> = ["/a/b", "/a", "/c/d", "/a/b/c", "/c/d"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        dedupe(&mut roots);
+        assert_eq!(roots, vec![PathBuf::from("/a"), PathBuf::from("/c/d")]);
+    }
+}
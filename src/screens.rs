@@ -1,13 +1,17 @@
 use {
     crate::{
         app_context::AppContext,
+        commands::Command,
         errors::ProgramError,
+        io::WriteCleanup,
         mad_skin::{self, StatusMadSkinSet},
         skin::Skin,
+        theme_mode::ThemeMode,
     },
     crossterm::{
-        cursor,
-        terminal::{Clear, ClearType},
+        self, cursor,
+        event::{DisableMouseCapture, EnableMouseCapture},
+        terminal::{Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
         QueueableCommand,
     },
     std::io::Write,
@@ -16,6 +20,9 @@ use {
 
 pub static FLAGS_AREA_WIDTH: u16 = 10;
 
+/// what's written, dimmed, in the input when it's empty
+static INPUT_PLACEHOLDER: &str = "type to search, space for verbs";
+
 pub struct Screen {
     pub width: u16,
     pub height: u16,
@@ -23,6 +30,8 @@ pub struct Screen {
     pub input_field: InputField,
     pub status_skin: StatusMadSkinSet,
     pub help_skin: MadSkin,
+    pub theme_mode: ThemeMode, // the mode whose skin preset is used, unless set with :set_skin
+    pub breadcrumb: Option<String>, // the stacked states, written at the end of the status line
 }
 
 impl Screen {
@@ -38,10 +47,20 @@ impl Screen {
             input_field,
             status_skin,
             help_skin,
+            theme_mode: con.launch_args.theme_mode,
+            breadcrumb: None,
         };
         screen.read_size(con)?;
         Ok(screen)
     }
+    /// replace the skin, and the skins which are derived from it
+    pub fn set_skin(&mut self, skin: Skin) {
+        self.input_field
+            .set_normal_style(CompoundStyle::from(skin.input.clone()));
+        self.status_skin = StatusMadSkinSet::from_skin(&skin);
+        self.help_skin = mad_skin::make_help_mad_skin(&skin);
+        self.skin = skin;
+    }
     pub fn set_terminal_size(&mut self, w: u16, h: u16, con: &AppContext) {
         self.width = w;
         self.height = h;
@@ -50,11 +69,51 @@ impl Screen {
         }
         self.input_field.change_area(0, h - 1, w - FLAGS_AREA_WIDTH);
     }
+    /// give the input field the width left by the flags written at
+    /// the end of its line, when they need more than the default area
+    pub fn set_flags_width(&mut self, flags_width: u16) {
+        let flags_width = flags_width.max(FLAGS_AREA_WIDTH).min(self.width);
+        self.input_field.area.width = self.width - flags_width;
+    }
     pub fn read_size(&mut self, con: &AppContext) -> Result<(), ProgramError> {
         let (w, h) = termimad::terminal_size();
         self.set_terminal_size(w, h, con);
         Ok(())
     }
+    /// display the input field and, unless disabled, a dimmed hint after
+    /// its content: a placeholder when it's empty, or the end of the
+    /// name of the verb being typed, which tab completes
+    pub fn display_input(
+        &self,
+        w: &mut impl Write,
+        cmd: &Command,
+        con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        self.input_field.display_on(w)?;
+        if !con.launch_args.input_hints || cmd.prompt.is_some() {
+            return Ok(());
+        }
+        let hint = if cmd.raw.is_empty() {
+            INPUT_PLACEHOLDER.to_string()
+        } else {
+            match cmd.verb_name_completion(&con.verb_store) {
+                Some(completion) => completion,
+                None => {
+                    return Ok(());
+                }
+            }
+        };
+        // the hint starts after the cursor, which is at the end of the input
+        let area = &self.input_field.area;
+        let x = cmd.raw.chars().count() + 1;
+        if x >= area.width as usize {
+            return Ok(());
+        }
+        let hint: String = hint.chars().take(area.width as usize - x).collect();
+        self.goto(w, area.left + x as u16, area.top)?;
+        self.skin.input_hint.queue(w, hint)?;
+        Ok(())
+    }
     /// move the cursor to x,y and clears the line.
     pub fn goto_clear(&self, w: &mut impl Write, x: u16, y: u16) -> Result<(), ProgramError> {
         self.goto(w, x, y)?;
@@ -75,4 +134,184 @@ impl Screen {
         w.queue(Clear(ClearType::UntilNewLine))?;
         Ok(())
     }
+    /// write, in the flags area, which lines of a scrolled content are
+    /// displayed, or nothing when the whole content fits
+    pub fn write_scroll_position(
+        &self,
+        w: &mut impl Write,
+        scroll: usize,
+        page_height: usize,
+        total: usize,
+    ) -> Result<(), ProgramError> {
+        self.skin.default.queue_bg(w)?;
+        match scroll_position(scroll, page_height, total) {
+            Some(position) => {
+                let width = position.len() as u16 + 1;
+                self.goto_clear(w, self.width.saturating_sub(width), self.height - 1)?;
+                self.skin.flag_value.queue_str(w, &position)?;
+            }
+            None => {
+                self.clear_line(w)?;
+            }
+        }
+        Ok(())
+    }
+    /// set the breadcrumb of the stacked states, which is kept only
+    /// when it wouldn't hide too much of the status
+    pub fn set_breadcrumb(&mut self, breadcrumb: Option<String>) {
+        let max_width = self.width as usize / 3;
+        self.breadcrumb = breadcrumb.filter(|b| b.chars().count() + 2 <= max_width);
+    }
+    /// the width taken by the breadcrumb at the end of the status line,
+    /// which the status text must leave
+    pub fn breadcrumb_width(&self) -> u16 {
+        self.breadcrumb
+            .as_ref()
+            .map_or(0, |breadcrumb| breadcrumb.chars().count() as u16 + 2)
+    }
+    /// write the breadcrumb, if any, at the end of the status line
+    pub fn write_breadcrumb(&self, w: &mut impl Write) -> Result<(), ProgramError> {
+        if let Some(breadcrumb) = &self.breadcrumb {
+            self.goto(w, self.width - self.breadcrumb_width(), self.height - 2)?;
+            self.skin.status_breadcrumb.queue(w, format!(" {} ", breadcrumb))?;
+        }
+        Ok(())
+    }
+}
+
+/// describe the displayed lines of a content as `first-last/total`,
+/// the numbers starting at 1, unless they're all displayed
+pub fn scroll_position(scroll: usize, page_height: usize, total: usize) -> Option<String> {
+    if total <= page_height {
+        None
+    } else {
+        let last = (scroll + page_height).min(total);
+        Some(format!("{}-{}/{}", scroll + 1, last, total))
+    }
+}
+
+/// Helper function for type inference: queue a Command but return Result<()>
+#[inline]
+fn just_queue(
+    mut writer: impl Write,
+    command: impl crossterm::Command,
+) -> crossterm::Result<()> {
+    writer.queue(command).map(move |_| ())
+}
+
+/// the height broot draws on, which is the terminal's one unless
+/// specified at launch
+fn display_height(launch_height: Option<u16>) -> u16 {
+    launch_height.unwrap_or_else(|| termimad::terminal_size().1)
+}
+
+/// without alternate screen, scroll the previous content of the
+/// terminal out of the area broot draws on
+fn make_room(w: &mut impl Write, launch_height: Option<u16>) -> crossterm::Result<()> {
+    for _ in 0..display_height(launch_height) {
+        write!(w, "\r\n")?;
+    }
+    Ok(())
+}
+
+/// without alternate screen, move the cursor below what broot drew,
+/// so that it's not overwritten by the shell on exit
+fn leave_display(w: &mut impl Write, launch_height: Option<u16>) -> crossterm::Result<()> {
+    let height = display_height(launch_height);
+    w.queue(cursor::MoveTo(0, height.max(1) - 1))?;
+    write!(w, "\r\n")?;
+    Ok(())
+}
+
+/// prepare the terminal for the application (alternate screen unless
+/// disabled, hidden cursor, mouse capture, saved title) and return a
+/// writer restoring its state when dropped
+pub fn terminal_writer(
+    writer: impl Write,
+    con: &AppContext,
+) -> Result<impl Write, ProgramError> {
+    let alt_screen = con.launch_args.alt_screen;
+    let set_title = con.launch_args.set_terminal_title;
+    let mouse_capture = con.launch_args.mouse_capture;
+    let launch_height = con.launch_args.height;
+
+    // Ensure the buffer is flushed before we return
+    let writer = WriteCleanup::new(writer, |w| w.flush());
+
+    // Push some terminal state changes, ensuring they're reverted when we
+    // end the program.
+    let writer = WriteCleanup::build(
+        writer,
+        move |w| {
+            if alt_screen {
+                just_queue(w, EnterAlternateScreen)
+            } else {
+                make_room(w, launch_height)
+            }
+        },
+        move |w| {
+            if alt_screen {
+                just_queue(w, LeaveAlternateScreen)
+            } else {
+                leave_display(w, launch_height)
+            }
+        },
+    )?;
+    let writer = WriteCleanup::build(
+        writer,
+        |w| just_queue(w, cursor::Hide),
+        |w| just_queue(w, cursor::Show),
+    )?;
+    let writer = WriteCleanup::build(
+        writer,
+        move |w| -> crossterm::Result<()> {
+            if mouse_capture {
+                just_queue(w, EnableMouseCapture)?;
+            }
+            Ok(())
+        },
+        move |w| -> crossterm::Result<()> {
+            if mouse_capture {
+                just_queue(w, DisableMouseCapture)?;
+            }
+            Ok(())
+        },
+    )?;
+    // the title is saved on the title stack of the terminal (xterm
+    // extension, ignored by other terminals) to be restored on exit
+    let writer = WriteCleanup::build(
+        writer,
+        move |w| -> crossterm::Result<()> {
+            if set_title {
+                write!(w, "\x1b[22;0t")?;
+            }
+            Ok(())
+        },
+        move |w| -> crossterm::Result<()> {
+            if set_title {
+                write!(w, "\x1b[23;0t")?;
+            }
+            Ok(())
+        },
+    )?;
+    Ok(writer)
+}
+
+/// set the title of the terminal
+pub fn write_title(w: &mut impl Write, title: &str) -> Result<(), ProgramError> {
+    write!(w, "\x1b]0;{}\x07", title)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod screens_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_scroll_position() {
+        assert_eq!(scroll_position(0, 30, 30), None);
+        assert_eq!(scroll_position(0, 30, 100), Some("1-30/100".to_string()));
+        assert_eq!(scroll_position(80, 30, 100), Some("81-100/100".to_string()));
+    }
 }
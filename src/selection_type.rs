@@ -4,4 +4,5 @@ pub enum SelectionType {
     File,
     Directory,
     Any,
+    None, // the verb doesn't apply to the selection, eg a change of the display
 }
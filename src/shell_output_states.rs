@@ -0,0 +1,458 @@
+//! the screen displaying the output of a command typed after a `!`
+//! in the input, for example `!ls -l {file}`, which is run by the
+//! shell in the directory of the selection.
+//!
+//! The command runs in the background, in its own process group, and
+//! its output is displayed as it comes. *esc* kills it, with the
+//! programs it started, and so does leaving the screen.
+//!
+//! Only the visible lines are drawn, so that a long output scrolls
+//! as fast as a short one. Typing a pattern jumps to the first
+//! matching line, and *enter* to the next one.
+
+use {
+    crate::{
+        app_context::AppContext,
+        app_state::{AppState, AppStateCmdResult},
+        commands::{Action, Command},
+        errors::ProgramError,
+        external,
+        help_content::{ScreenHelp, ScreenKey},
+        help_states::HelpState,
+        patterns::Pattern,
+        screens::Screen,
+        status::Status,
+        task_sync::Dam,
+        verb_store::PrefixSearchResult,
+    },
+    crossbeam::channel::{unbounded, Receiver, RecvTimeoutError, Sender},
+    minimad::Composite,
+    std::{
+        io::{self, BufRead, BufReader, Read, Write},
+        path::Path,
+        process::{self, Stdio},
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+/// how long the output of a running command is read before the
+/// screen is drawn again
+const OUTPUT_READ_DURATION: Duration = Duration::from_millis(50);
+
+/// how long a line is waited for, before checking the events
+const LINE_WAIT: Duration = Duration::from_millis(10);
+
+/// the help of the shell output screen
+pub static HELP: ScreenHelp = ScreenHelp {
+    name: "shell output screen",
+    description: "This screen shows what was written by a command typed after a `!`, \
+        which the shell ran in the directory of the selection.",
+    keys: &[
+        ScreenKey::new(
+            "esc",
+            "stop the running command, clear the search, or get back to the tree",
+        ),
+        ScreenKey::new("↑ ↓", "scroll"),
+        ScreenKey::new("a letter", "search the lines, jumping to the first matching one"),
+        ScreenKey::new("enter", "jump to the next matching line"),
+        ScreenKey::new("?", "display this help"),
+    ],
+    builtins: Some(&[
+        ":back", ":help", ":kill_task", ":line_down", ":line_up", ":page_down", ":page_up", ":quit",
+    ]),
+    external_verbs: false,
+};
+
+/// a command started in the background, whose output lines
+/// are sent by the threads reading its stdout and stderr
+struct RunningCommand {
+    child: process::Child,
+    lines: Receiver<String>,
+}
+
+enum CommandStatus {
+    Running(RunningCommand),
+    Finished(process::ExitStatus),
+    Interrupted,
+    Failed(String), // the command couldn't be started
+}
+
+/// an application state showing the output of a shell command
+pub struct ShellOutputState {
+    command: String,
+    lines: Vec<String>, // stdout and stderr, as they come
+    status: CommandStatus,
+    scroll: usize,
+    pattern: Pattern,
+    matching_lines: Vec<usize>, // indexes of the lines matching the pattern
+}
+
+/// start the command with the shell of the system, in its own
+/// process group so that it can be killed with the programs it
+/// starts. It gets no input
+fn spawn(command: &str, directory: &Path) -> io::Result<RunningCommand> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = process::Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = process::Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell
+        .arg(command)
+        .current_dir(directory)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut child = external::spawn_in_own_group(&mut shell)?;
+    let (sender, lines) = unbounded();
+    if let Some(stdout) = child.stdout.take() {
+        send_lines(stdout, sender.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        send_lines(stderr, sender);
+    }
+    Ok(RunningCommand { child, lines })
+}
+
+/// read the stream in a thread sending its lines, until it's closed
+fn send_lines(stream: impl Read + Send + 'static, sender: Sender<String>) {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        let mut buf = Vec::new();
+        while let Ok(n) = reader.read_until(b'\n', &mut buf) {
+            if n == 0 {
+                break;
+            }
+            let line = String::from_utf8_lossy(&buf);
+            let line = line.trim_end_matches(&['\r', '\n'][..]).replace('\t', "    ");
+            if sender.send(line).is_err() {
+                break;
+            }
+            buf.clear();
+        }
+    });
+}
+
+impl ShellOutputState {
+    /// start the command, whose output is then read as a pending task
+    pub fn new(command: String, directory: &Path) -> ShellOutputState {
+        info!("running {:?} in {:?}", &command, directory);
+        let status = match spawn(&command, directory) {
+            Ok(running) => CommandStatus::Running(running),
+            Err(e) => CommandStatus::Failed(e.to_string()),
+        };
+        ShellOutputState {
+            command,
+            lines: Vec::new(),
+            status,
+            scroll: 0,
+            pattern: Pattern::None,
+            matching_lines: Vec::new(),
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        matches!(self.status, CommandStatus::Running(_))
+    }
+
+    /// add a line of the output, the page following the end of the
+    /// output when it was displayed
+    fn add_line(&mut self, line: String, screen: &Screen) {
+        let page_height = ShellOutputState::page_height(screen);
+        let at_end = self.scroll + page_height >= self.lines.len();
+        if self.pattern.is_some() && self.pattern.find(&line).is_some() {
+            self.matching_lines.push(self.lines.len());
+        }
+        self.lines.push(line);
+        if at_end {
+            self.scroll = self.lines.len().saturating_sub(page_height);
+        }
+    }
+
+    /// get the exit status of the command, whose output is closed
+    fn finish(&mut self) {
+        if let CommandStatus::Running(running) = &mut self.status {
+            let status = match running.child.wait() {
+                Ok(status) => CommandStatus::Finished(status),
+                Err(e) => CommandStatus::Failed(e.to_string()),
+            };
+            self.status = status;
+        }
+    }
+
+    /// kill the running command and the programs it started, what
+    /// they wrote being kept. Return false when nothing was running
+    fn kill(&mut self) -> bool {
+        if let CommandStatus::Running(running) = &mut self.status {
+            info!("killing {:?}", &self.command);
+            if let Err(e) = external::kill_group(&mut running.child) {
+                warn!("failed to kill {:?}: {:?}", &self.command, e);
+            }
+            let _ = running.child.wait();
+            self.status = CommandStatus::Interrupted;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// search the lines matching the pattern, and jump to the first
+    /// one which isn't above the page
+    fn set_pattern(&mut self, pattern: Pattern, screen: &Screen) {
+        self.matching_lines = if pattern.is_some() {
+            (0..self.lines.len())
+                .filter(|&idx| pattern.find(&self.lines[idx]).is_some())
+                .collect()
+        } else {
+            Vec::new()
+        };
+        self.pattern = pattern;
+        let scroll = self.scroll;
+        self.jump_to_match(|idx| idx >= scroll, screen);
+    }
+
+    /// scroll to put at the top of the page the first matching line
+    /// accepted by the filter, or else the first matching line
+    fn jump_to_match(&mut self, filter: impl Fn(usize) -> bool, screen: &Screen) {
+        let target = self
+            .matching_lines
+            .iter()
+            .copied()
+            .find(|&idx| filter(idx))
+            .or_else(|| self.matching_lines.first().copied());
+        if let Some(idx) = target {
+            self.scroll = 0;
+            self.try_scroll(idx as i32, screen);
+        }
+    }
+
+    fn page_height(screen: &Screen) -> usize {
+        (screen.height as usize).max(3) - 3
+    }
+
+    fn try_scroll(&mut self, dy: i32, screen: &Screen) {
+        let max_scroll = self.lines.len().saturating_sub(ShellOutputState::page_height(screen));
+        let scroll = (self.scroll as i32 + dy).max(0) as usize;
+        self.scroll = scroll.min(max_scroll);
+    }
+}
+
+impl AppState for ShellOutputState {
+    fn help(&self) -> &'static ScreenHelp {
+        &HELP
+    }
+
+    fn has_pending_task(&self) -> bool {
+        self.is_running()
+    }
+
+    fn can_execute(&self, _verb_index: usize, _con: &AppContext) -> bool {
+        true
+    }
+
+    fn apply(
+        &mut self,
+        cmd: &mut Command,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<AppStateCmdResult, ProgramError> {
+        let verb_execution = match &cmd.action {
+            Action::VerbIndex(index) => Some(con.verb_store.verbs[*index].execution.as_str()),
+            Action::VerbInvocate(invocation) => match con.verb_store.search(&invocation.name) {
+                PrefixSearchResult::Match(verb) => Some(verb.execution.as_str()),
+                _ => {
+                    return Ok(AppStateCmdResult::verb_not_found(&invocation.name));
+                }
+            },
+            _ => None,
+        };
+        let page_height = ShellOutputState::page_height(screen) as i32;
+        Ok(match (&cmd.action, verb_execution) {
+            (Action::Back, _) if self.kill() => AppStateCmdResult::Keep,
+            (Action::Back, _) if self.pattern.is_some() => {
+                self.set_pattern(Pattern::None, screen);
+                cmd.raw.clear();
+                AppStateCmdResult::Keep
+            }
+            (Action::Back, _) | (_, Some(":back")) => AppStateCmdResult::PopState,
+            (Action::Help, _) | (_, Some(":help")) => AppStateCmdResult::NewState(
+                Box::new(HelpState::new(screen, con, &HELP)),
+                Command::new(),
+            ),
+            (_, Some(":kill_task")) => {
+                if self.kill() {
+                    AppStateCmdResult::Keep
+                } else {
+                    AppStateCmdResult::DisplayError("the command isn't running".to_string())
+                }
+            }
+            (_, Some(":line_down")) => {
+                self.try_scroll(1, screen);
+                AppStateCmdResult::Keep
+            }
+            (_, Some(":line_up")) => {
+                self.try_scroll(-1, screen);
+                AppStateCmdResult::Keep
+            }
+            (_, Some(":page_down")) => {
+                self.try_scroll(page_height, screen);
+                AppStateCmdResult::Keep
+            }
+            (_, Some(":page_up")) => {
+                self.try_scroll(-page_height, screen);
+                AppStateCmdResult::Keep
+            }
+            (_, Some(":quit")) => AppStateCmdResult::Quit,
+            // the other verbs, and the next shell command, apply to the tree
+            (_, Some(_)) | (Action::ShellExec(_), _) => AppStateCmdResult::PopStateAndReapply,
+            (Action::FuzzyPatternEdit(pat), _) => {
+                let pattern = if pat.is_empty() {
+                    Pattern::None
+                } else {
+                    Pattern::fuzzy(pat)
+                };
+                self.set_pattern(pattern, screen);
+                AppStateCmdResult::Keep
+            }
+            (Action::RegexEdit(pat, flags), _) => match Pattern::regex(pat, flags) {
+                Ok(pattern) => {
+                    self.set_pattern(pattern, screen);
+                    AppStateCmdResult::Keep
+                }
+                Err(e) => AppStateCmdResult::DisplayError(e.to_string()),
+            },
+            (Action::OpenSelection, _) => {
+                let scroll = self.scroll;
+                self.jump_to_match(|idx| idx > scroll, screen);
+                AppStateCmdResult::Keep
+            }
+            (Action::MoveSelection(dy), _) | (Action::Scroll(dy), _) => {
+                self.try_scroll(*dy, screen);
+                AppStateCmdResult::Keep
+            }
+            (Action::Resize(w, h), _) => {
+                screen.set_terminal_size(*w, *h, con);
+                AppStateCmdResult::Keep
+            }
+            _ => AppStateCmdResult::Keep,
+        })
+    }
+
+    fn refresh(&mut self, _screen: &Screen, _con: &AppContext) -> Command {
+        Command::new()
+    }
+
+    /// read the output of the running command for a short time,
+    /// or until there's an event to handle
+    fn do_pending_task(&mut self, screen: &mut Screen, _con: &AppContext, dam: &mut Dam) {
+        let start = Instant::now();
+        while start.elapsed() < OUTPUT_READ_DURATION && !dam.has_event() {
+            let received = match &self.status {
+                CommandStatus::Running(running) => running.lines.recv_timeout(LINE_WAIT),
+                _ => return,
+            };
+            match received {
+                Ok(line) => self.add_line(line, screen),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => self.finish(),
+            }
+        }
+    }
+
+    fn display(
+        &mut self,
+        mut w: &mut dyn Write,
+        screen: &Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        screen.skin.default.queue_bg(&mut w)?;
+        screen.clear(&mut w)?;
+        screen.goto(&mut w, 0, 0)?;
+        screen.skin.help_headers.queue_str(&mut w, &format!("$ {}", &self.command))?;
+        let width = screen.width as usize;
+        let page_height = ShellOutputState::page_height(screen);
+        for (y, line) in self.lines.iter().skip(self.scroll).take(page_height).enumerate() {
+            screen.goto(&mut w, 0, y as u16 + 1)?;
+            let line: String = line.chars().take(width).collect();
+            if self.pattern.is_some() {
+                self.pattern
+                    .style(&line, &screen.skin.default, &screen.skin.char_match)
+                    .write_on(&mut w)?;
+            } else {
+                screen.skin.default.queue_str(&mut w, &line)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_status(
+        &self,
+        mut w: &mut dyn Write,
+        _cmd: &Command,
+        screen: &Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        let lines_count = self.lines.len().to_string();
+        let matching_count = self.matching_lines.len().to_string();
+        match &self.status {
+            CommandStatus::Running(_) => Status::from_message(mad_inline!(
+                "The command is running ($0 lines). Hit *esc* to stop it",
+                &lines_count,
+            ))
+            .display(&mut w, screen),
+            _ if self.pattern.is_some() && self.matching_lines.is_empty() => {
+                Status::from_error(mad_inline!("No line matches the pattern"))
+                    .display(&mut w, screen)
+            }
+            _ if self.pattern.is_some() => Status::from_message(mad_inline!(
+                "$0 matching lines. Hit *enter* to jump to the next one, *esc* to clear the search",
+                &matching_count,
+            ))
+            .display(&mut w, screen),
+            CommandStatus::Finished(status) if status.success() => Status::from_message(
+                mad_inline!("The command succeeded. Hit *esc* to get back to the tree"),
+            )
+            .display(&mut w, screen),
+            CommandStatus::Finished(status) => Status::from_error(Composite::from_inline(&format!(
+                "The command failed ({}). Hit *esc* to get back to the tree",
+                status,
+            )))
+            .display(&mut w, screen),
+            CommandStatus::Interrupted => Status::from_error(mad_inline!(
+                "The command was stopped. Hit *esc* to get back to the tree"
+            ))
+            .display(&mut w, screen),
+            CommandStatus::Failed(e) => Status::from_error(Composite::from_inline(&format!(
+                "The command couldn't be run: {}",
+                e,
+            )))
+            .display(&mut w, screen),
+        }
+    }
+
+    /// the flags area tells which part of a long output is displayed
+    fn write_flags(
+        &self,
+        mut w: &mut dyn Write,
+        screen: &mut Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        screen.write_scroll_position(
+            &mut w,
+            self.scroll,
+            ShellOutputState::page_height(screen),
+            self.lines.len(),
+        )
+    }
+}
+
+/// the command is killed when its output isn't displayed anymore
+impl Drop for ShellOutputState {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
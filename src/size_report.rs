@@ -0,0 +1,55 @@
+//! a report of the sizes of the directories of a tree, made of
+//! the already computed sizes and sorted like the output
+//! of `du | sort -h`, the biggest directories being last.
+//!
+//! It's meant to be shared, for example to discuss what could be
+//! cleaned, so the lines are simply `size<TAB>path`.
+
+use {
+    crate::file_sizes::{self, FileSize},
+    std::{
+        fs, io,
+        path::{Path, PathBuf},
+    },
+};
+
+/// make the content of the report, sorted by increasing size
+fn format(mut sizes: Vec<(PathBuf, FileSize)>) -> String {
+    sizes.sort_by_key(|(path, size)| (Into::<u64>::into(*size), path.clone()));
+    let mut report = String::new();
+    for (path, size) in sizes {
+        report.push_str(&format!("{}\t{}\n", size, path.to_string_lossy()));
+    }
+    report
+}
+
+/// write the known sizes of the root and of its descendant
+/// directories in the file. Return the number of written lines.
+pub fn write(root: &Path, report_path: &Path) -> io::Result<usize> {
+    let sizes = file_sizes::known_dir_sizes(root);
+    if sizes.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no size is known yet: display them with :toggle_sizes",
+        ));
+    }
+    let count = sizes.len();
+    fs::write(report_path, format(sizes))?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod size_report_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_format() {
+        let sizes = vec![
+            (PathBuf::from("/a"), FileSize::new(12000, false)),
+            (PathBuf::from("/a/c"), FileSize::new(9000, false)),
+            (PathBuf::from("/a/b"), FileSize::new(3000, false)),
+        ];
+        assert_eq!(format(sizes), "3000\t/a/b\n9K\t/a/c\n12K\t/a\n");
+    }
+}
@@ -32,6 +32,7 @@ macro_rules! Skin {
             }
             /// build a skin with some entry overloaded by configuration
             pub fn create(mut skin_conf: HashMap<String, CompoundStyle>) -> Skin {
+                complete_conf(&mut skin_conf);
                 let mut skin = Skin {
                     $($name: skin_conf.remove(stringify!($name)).unwrap_or(CompoundStyle::new(
                         $fg,
@@ -40,9 +41,11 @@ macro_rules! Skin {
                     )),)*
                 };
                 $(
-                    let mut base = skin.default.clone();
-                    base.overwrite_with(&skin.$name);
-                    skin.$name = base;
+                    if !OVERLAY_ENTRIES.contains(&stringify!($name)) {
+                        let mut base = skin.default.clone();
+                        base.overwrite_with(&skin.$name);
+                        skin.$name = base;
+                    }
                 )*
                 skin
             }
@@ -57,6 +60,22 @@ macro_rules! Skin {
     }
 }
 
+/// the entries which are applied over the style of the text they're
+/// part of, rather than over the default style, so that the colors
+/// they don't set are the ones of that text (eg a char_match of
+/// "none none underlined" only underlines the matched characters)
+const OVERLAY_ENTRIES: &[&str] = &["char_match", "char_match_selected"];
+
+/// fill the entries which, when not configured, derive from another
+/// configured one
+fn complete_conf(skin_conf: &mut HashMap<String, CompoundStyle>) {
+    if !skin_conf.contains_key("char_match_selected") {
+        if let Some(style) = skin_conf.get("char_match").cloned() {
+            skin_conf.insert("char_match_selected".to_string(), style);
+        }
+    }
+}
+
 pub fn gray(level: u8) -> Option<Color> {
     Some(AnsiValue(0xE8 + level))
 }
@@ -76,6 +95,7 @@ Skin! {
     directory: ansi(110), None; {Bold}
     exe: Some(Cyan), None;
     link: Some(Magenta), None;
+    broken_link: Some(Red), None; {Italic}
     pruning: gray(12), None; {Italic}
     perm__: gray(5), None;
     perm_r: ansi(94), None;
@@ -84,24 +104,41 @@ Skin! {
     owner: ansi(138), None;
     group: ansi(131), None;
     dates: ansi(66), None;
+    freshness_hour: ansi(196), None;
+    freshness_day: ansi(208), None;
+    freshness_week: ansi(214), None;
+    freshness_month: ansi(179), None;
+    count: ansi(136), None;
     sparse: ansi(214), None;
+    note: ansi(179), None; {Italic}
+    tag_red: ansi(160), None;
+    tag_orange: ansi(208), None;
+    tag_yellow: ansi(220), None;
+    tag_green: ansi(34), None;
+    tag_blue: ansi(33), None;
+    tag_purple: ansi(135), None;
+    tag_gray: gray(12), None;
     git_branch: ansi(178), None;
     git_insertions: ansi(28), None;
     git_deletions: ansi(160), None;
     git_status_current: gray(5), None;
     git_status_modified: ansi(28), None;
     git_status_new: ansi(94), None; {Bold}
+    git_status_staged: ansi(31), None;
     git_status_ignored: gray(17), None;
     git_status_conflicted: ansi(88), None;
     git_status_other: ansi(88), None;
     selected_line: None, gray(4);
     char_match: Some(Green), None;
+    char_match_selected: Some(Green), None;
     file_error: Some(Red), None;
     flag_label: gray(15), None;
     flag_value: ansi(178), None; {Bold}
     input: Some(White), None;
+    input_hint: gray(10), None; {Italic}
     status_error: gray(22), ansi(124);
     status_job: ansi(220), gray(5);
+    status_breadcrumb: gray(13), gray(3);
     status_normal: gray(20), gray(3);
     status_italic: ansi(178), gray(3);
     status_bold: ansi(178), gray(3); {Bold}
@@ -0,0 +1,137 @@
+//! skins compiled in broot. One of them may be chosen with the
+//! `skin_preset` conf entry or applied while running with
+//! `:set_skin {name}`.
+//!
+//! The entries of the `[skin]` conf section are applied over
+//! the preset, whatever the preset.
+
+use {
+    crate::skin_conf,
+    std::collections::HashMap,
+    termimad::CompoundStyle,
+};
+
+pub struct SkinPreset {
+    pub name: &'static str,
+    entries: &'static [(&'static str, &'static str)], // overloads of the default skin
+}
+
+pub static PRESETS: &[SkinPreset] = &[
+    SkinPreset {
+        name: "default",
+        entries: &[],
+    },
+    SkinPreset {
+        name: "light",
+        entries: &[
+            ("default", "gray(2) gray(23)"),
+            ("tree", "gray(17) none"),
+            ("file", "gray(1) none"),
+            ("directory", "ansi(20) none bold"),
+            ("exe", "ansi(178) none"),
+            ("link", "Magenta none"),
+            ("broken_link", "Red none italic"),
+            ("pruning", "gray(5) none Italic"),
+            ("perm__", "gray(19) None"),
+            ("perm_r", "ansi(92) None"),
+            ("perm_w", "ansi(192) None"),
+            ("perm_x", "ansi(69) None"),
+            ("selected_line", "none gray(20)"),
+            ("char_match", "ansi(28) none"),
+            ("file_error", "Red none"),
+            ("flag_label", "gray(16) none"),
+            ("flag_value", "ansi(202) none bold"),
+            ("input", "ansi(0) none"),
+            ("input_hint", "gray(14) none italic"),
+            ("status_error", "ansi(196) gray(22)"),
+            ("status_job", "ansi(220) gray(18)"),
+            ("status_breadcrumb", "gray(10) gray(22)"),
+            ("status_normal", "gray(2) gray(22)"),
+            ("status_italic", "ansi(202) gray(22)"),
+            ("status_bold", "ansi(202) gray(22) bold"),
+            ("status_code", "ansi(17) gray(22)"),
+            ("status_ellipsis", "gray(1) white"),
+            ("scrollbar_track", "gray(20) none"),
+            ("scrollbar_thumb", "ansi(238) none"),
+            ("help_paragraph", "gray(2) none"),
+            ("help_bold", "ansi(202) none bold"),
+            ("help_italic", "ansi(202) none italic"),
+            ("help_code", "gray(5) gray(22)"),
+            ("help_headers", "ansi(202) none"),
+        ],
+    },
+    SkinPreset {
+        name: "solarized-dark",
+        entries: &[
+            ("default", "rgb(131, 148, 150) rgb(0, 43, 54)"),
+            ("tree", "rgb(88, 110, 117) none"),
+            ("file", "none none"),
+            ("directory", "rgb(38, 139, 210) none bold"),
+            ("exe", "rgb(211, 1, 2) none"),
+            ("link", "rgb(211, 54, 130) none"),
+            ("broken_link", "rgb(220, 50, 47) none italic"),
+            ("pruning", "rgb(88, 110, 117) none italic"),
+            ("perm__", "rgb(88, 110, 117) none"),
+            ("perm_r", "none none"),
+            ("perm_w", "none none"),
+            ("perm_x", "none none"),
+            ("owner", "rgb(88, 110, 117) none"),
+            ("group", "rgb(88, 110, 117) none"),
+            ("selected_line", "none rgb(7, 54, 66)"),
+            ("char_match", "rgb(133, 153, 0) none underlined"),
+            ("file_error", "rgb(203, 75, 22) none italic"),
+            ("flag_label", "none none"),
+            ("flag_value", "rgb(181, 137, 0) none bold"),
+            ("input", "none none"),
+            ("input_hint", "rgb(88, 110, 117) none italic"),
+            ("status_error", "rgb(203, 75, 22) rgb(7, 54, 66)"),
+            ("status_job", "rgb(108, 113, 196) rgb(7, 54, 66) bold"),
+            ("status_breadcrumb", "rgb(88, 110, 117) rgb(7, 54, 66)"),
+            ("status_normal", "none rgb(7, 54, 66)"),
+            ("status_italic", "rgb(181, 137, 0) rgb(7, 54, 66)"),
+            ("status_bold", "rgb(147, 161, 161) rgb(7, 54, 66) bold"),
+            ("status_code", "rgb(108, 113, 196) rgb(7, 54, 66)"),
+            ("status_ellipsis", "none rgb(7, 54, 66)"),
+            ("scrollbar_track", "rgb(7, 54, 66) none"),
+            ("scrollbar_thumb", "none none"),
+            ("help_paragraph", "none none"),
+            ("help_bold", "rgb(147, 161, 161) none bold"),
+            ("help_italic", "rgb(147, 161, 161) none italic"),
+            ("help_code", "rgb(147, 161, 161) rgb(7, 54, 66)"),
+            ("help_headers", "rgb(181, 137, 0) none"),
+        ],
+    },
+];
+
+/// return the names of the presets, comma separated, for messages
+pub fn names() -> String {
+    PRESETS
+        .iter()
+        .map(|p| p.name)
+        .collect::<Vec<&str>>()
+        .join(", ")
+}
+
+/// return the entries of the skin built from the preset and the
+/// entries of the conf, or None if there's no preset with this name
+pub fn entries(
+    name: &str,
+    conf_entries: &HashMap<String, CompoundStyle>,
+) -> Option<HashMap<String, CompoundStyle>> {
+    let preset = PRESETS.iter().find(|p| p.name == name)?;
+    let mut entries = HashMap::new();
+    for (key, value) in preset.entries {
+        match skin_conf::parse_object_style(value) {
+            Ok(style) => {
+                entries.insert(key.to_string(), style);
+            }
+            Err(e) => {
+                warn!("invalid entry {:?} in skin preset {:?}: {}", key, name, e);
+            }
+        }
+    }
+    for (key, style) in conf_entries {
+        entries.insert(key.clone(), style.clone());
+    }
+    Some(entries)
+}
@@ -0,0 +1,52 @@
+//! the sort modes of the tree.
+//!
+//! When the tree is sorted by anything else than the name,
+//! only the first level is displayed, as it wouldn't make sense
+//! (and would be hard to read) to mix depths.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sort {
+    None,
+    Count,
+    Date,
+    Size,
+}
+
+impl Sort {
+    /// parse a sort mode, as given in configuration or in launch arguments
+    pub fn from_name(name: &str) -> Option<Sort> {
+        match name.to_lowercase().as_ref() {
+            "none" | "name" => Some(Sort::None),
+            "count" => Some(Sort::Count),
+            "date" => Some(Sort::Date),
+            "size" => Some(Sort::Size),
+            _ => None,
+        }
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            Sort::None => "none",
+            Sort::Count => "count",
+            Sort::Date => "date",
+            Sort::Size => "size",
+        }
+    }
+    pub fn is_some(self) -> bool {
+        self != Sort::None
+    }
+    /// return the sort mode to set when the user asks for this one:
+    /// asking again for the current mode removes the sort
+    pub fn toggled(self, asked: Sort) -> Sort {
+        if self == asked {
+            Sort::None
+        } else {
+            asked
+        }
+    }
+}
+
+impl Default for Sort {
+    fn default() -> Self {
+        Sort::None
+    }
+}
@@ -0,0 +1,168 @@
+//! paths which get a special handling in the tree, as declared in
+//! the `[special-paths]` section of the configuration, for example
+//! to never enter a huge directory or to always show a hidden file,
+//! or to sort the tree, or apply a profile, when its root is a given
+//! directory.
+
+use {
+    crate::sort::Sort,
+    glob,
+    std::path::Path,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpecialHandling {
+    None,
+    Hide,    // never shown
+    NoEnter, // shown but its content is never listed
+    Show,    // shown even when hidden or gitignored
+}
+
+impl SpecialHandling {
+    pub fn from_name(name: &str) -> Option<SpecialHandling> {
+        match name.to_lowercase().as_ref() {
+            "none" => Some(SpecialHandling::None),
+            "hide" => Some(SpecialHandling::Hide),
+            "no-enter" | "noenter" => Some(SpecialHandling::NoEnter),
+            "show" => Some(SpecialHandling::Show),
+            _ => None,
+        }
+    }
+}
+
+/// a glob with the handling of the paths it matches, and the
+/// sort and profile of the tree when its root is one of them.
+///
+/// A glob without separator is matched against the file name, other
+/// ones against the whole path.
+#[derive(Debug, Clone)]
+pub struct SpecialPath {
+    pattern: glob::Pattern,
+    filename: bool,
+    pub handling: Option<SpecialHandling>,
+    pub sort: Option<Sort>,
+    pub profile: Option<String>, // the name of a profile of the conf
+}
+
+impl SpecialPath {
+    pub fn new(
+        glob: &str,
+        handling: Option<SpecialHandling>,
+        sort: Option<Sort>,
+        profile: Option<String>,
+    ) -> Result<SpecialPath, glob::PatternError> {
+        Ok(SpecialPath {
+            pattern: glob::Pattern::new(glob)?,
+            filename: !glob.contains('/'),
+            handling,
+            sort,
+            profile,
+        })
+    }
+    pub fn matches(&self, path: &Path) -> bool {
+        if self.filename {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, |name| self.pattern.matches(name))
+        } else {
+            self.pattern.matches_path(path)
+        }
+    }
+}
+
+/// make the special paths of the directories a walk mustn't enter,
+/// from the prune globs (eg `.git` or `node_modules`)
+pub fn prune_paths<S: AsRef<str>>(globs: &[S]) -> Vec<SpecialPath> {
+    globs
+        .iter()
+        .filter_map(|glob| {
+            let glob = glob.as_ref().trim();
+            match SpecialPath::new(glob, Some(SpecialHandling::NoEnter), None, None) {
+                Ok(sp) => Some(sp),
+                Err(e) => {
+                    warn!("invalid prune glob {:?}: {}", glob, e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// return the handling of the path, the first matching
+/// special path with a handling winning
+pub fn handling(special_paths: &[SpecialPath], path: &Path) -> SpecialHandling {
+    special_paths
+        .iter()
+        .filter(|sp| sp.handling.is_some())
+        .find(|sp| sp.matches(path))
+        .and_then(|sp| sp.handling)
+        .unwrap_or(SpecialHandling::None)
+}
+
+/// return the sort to apply when the root of the tree is the path,
+/// the first matching special path with a sort winning
+pub fn sort(special_paths: &[SpecialPath], path: &Path) -> Option<Sort> {
+    special_paths
+        .iter()
+        .filter(|sp| sp.sort.is_some())
+        .find(|sp| sp.matches(path))
+        .and_then(|sp| sp.sort)
+}
+
+/// return the name of the profile to apply when the root of the tree
+/// is the path, the first matching special path with a profile winning
+pub fn profile<'s>(special_paths: &'s [SpecialPath], path: &Path) -> Option<&'s str> {
+    special_paths
+        .iter()
+        .filter(|sp| sp.profile.is_some())
+        .find(|sp| sp.matches(path))
+        .and_then(|sp| sp.profile.as_deref())
+}
+
+#[cfg(test)]
+mod special_paths_tests {
+
+    use {
+        super::*,
+        std::path::Path,
+    };
+
+    #[test]
+    fn check_handling() {
+        let special_paths = vec![
+            SpecialPath::new("node_modules", Some(SpecialHandling::Hide), None, None).unwrap(),
+            SpecialPath::new(
+                "/home/*/.config/broot/backups",
+                Some(SpecialHandling::NoEnter),
+                None,
+                None,
+            )
+            .unwrap(),
+            SpecialPath::new(".git*", Some(SpecialHandling::Show), None, None).unwrap(),
+            SpecialPath::new("/home/*/Downloads", None, Some(Sort::Date), None).unwrap(),
+            SpecialPath::new(
+                "/home/*/dev",
+                Some(SpecialHandling::Hide),
+                Some(Sort::None),
+                Some("code".to_string()),
+            )
+            .unwrap(),
+        ];
+        let check = |path: &str, expected: SpecialHandling| {
+            assert_eq!(handling(&special_paths, Path::new(path)), expected);
+        };
+        check("/dev/app/node_modules", SpecialHandling::Hide);
+        check("/dev/app/node_modules.txt", SpecialHandling::None);
+        check("/home/dys/.config/broot/backups", SpecialHandling::NoEnter);
+        check("/home/dys/.config/broot", SpecialHandling::None);
+        check("/dev/app/.gitignore", SpecialHandling::Show);
+        check("/dev/app/src", SpecialHandling::None);
+        check("/home/dys/Downloads", SpecialHandling::None);
+        check("/home/dys/dev", SpecialHandling::Hide);
+        assert_eq!(sort(&special_paths, Path::new("/home/dys/Downloads")), Some(Sort::Date));
+        assert_eq!(sort(&special_paths, Path::new("/home/dys/dev")), Some(Sort::None));
+        assert_eq!(sort(&special_paths, Path::new("/dev/app")), None);
+        assert_eq!(profile(&special_paths, Path::new("/home/dys/dev")), Some("code"));
+        assert_eq!(profile(&special_paths, Path::new("/home/dys/Downloads")), None);
+    }
+}
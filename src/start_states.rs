@@ -0,0 +1,338 @@
+use {
+    crate::{
+        app_context::AppContext,
+        app_state::{AppState, AppStateCmdResult},
+        browser_states::BrowserState,
+        commands::{Action, Command},
+        errors::ProgramError,
+        help_content::ScreenHelp,
+        help_states::HelpState,
+        patterns::Pattern,
+        recent_roots,
+        screens::Screen,
+        start_verbs,
+        status::Status,
+        task_sync::Dam,
+        verb_store::PrefixSearchResult,
+        verbs::VerbExecutor,
+    },
+    crossterm::{
+        terminal::{Clear, ClearType},
+        QueueableCommand,
+    },
+    directories::UserDirs,
+    std::{
+        io::Write,
+        path::{Path, PathBuf},
+    },
+};
+
+/// a root proposed on the start screen
+pub struct StartEntry {
+    pub path: PathBuf,
+    pub name: String, // the path as displayed, with the home dir replaced by ~
+    pub bookmarked: bool,
+}
+
+impl StartEntry {
+    fn new(path: PathBuf, bookmarked: bool, home: Option<&Path>) -> StartEntry {
+        let name = match home.and_then(|home| path.strip_prefix(home).ok()) {
+            Some(sub_path) => format!("~/{}", sub_path.to_string_lossy()),
+            None => path.to_string_lossy().to_string(),
+        };
+        StartEntry {
+            path,
+            name,
+            bookmarked,
+        }
+    }
+}
+
+/// an application state listing the bookmarked and the recent
+/// roots, so that the user may choose the one to browse
+pub struct StartState {
+    entries: Vec<StartEntry>,
+    pattern: Pattern,
+    displayed: Vec<usize>, // indexes of the entries matching the pattern
+    pub selection: usize,  // index in displayed
+    pub scroll: usize,
+}
+
+impl StartState {
+    pub fn new(con: &AppContext) -> StartState {
+        let user_dirs = UserDirs::new();
+        let home = user_dirs.as_ref().map(|ud| ud.home_dir());
+        let mut entries: Vec<StartEntry> = con
+            .launch_args
+            .bookmarks
+            .iter()
+            .map(|path| StartEntry::new(path.clone(), true, home))
+            .collect();
+        for path in recent_roots::read() {
+            if !entries.iter().any(|e| e.path == path) {
+                entries.push(StartEntry::new(path, false, home));
+            }
+        }
+        let displayed = (0..entries.len()).collect();
+        StartState {
+            entries,
+            pattern: Pattern::None,
+            displayed,
+            selection: 0,
+            scroll: 0,
+        }
+    }
+
+    /// return the number of lines available for the entries
+    pub fn page_height(screen: &Screen) -> usize {
+        (screen.height as usize).max(3) - 3
+    }
+
+    /// return the number of entries matching the pattern
+    pub fn displayed_count(&self) -> usize {
+        self.displayed.len()
+    }
+
+    pub fn selected_path(&self) -> Option<&Path> {
+        self.displayed
+            .get(self.selection)
+            .map(|&idx| self.entries[idx].path.as_path())
+    }
+
+    /// keep only the entries matching the pattern, the best ones first
+    fn set_pattern(&mut self, pattern: Pattern) {
+        let mut scored: Vec<(i32, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, e)| {
+                if pattern.is_some() {
+                    pattern.score_of(&e.name).map(|score| (score, idx))
+                } else {
+                    Some((0, idx))
+                }
+            })
+            .collect();
+        // the sort is stable so bookmarks and recent roots stay first on equal scores
+        scored.sort_by(|a, b| b.0.cmp(&a.0));
+        self.displayed = scored.into_iter().map(|(_, idx)| idx).collect();
+        self.pattern = pattern;
+        self.selection = 0;
+        self.scroll = 0;
+    }
+
+    pub fn move_selection(&mut self, dy: i32, page_height: usize) {
+        let len = self.displayed.len() as i32;
+        if len == 0 {
+            return;
+        }
+        let selection = (self.selection as i32 + dy).max(0).min(len - 1) as usize;
+        self.selection = selection;
+        if selection < self.scroll {
+            self.scroll = selection;
+        } else if selection >= self.scroll + page_height {
+            self.scroll = selection + 1 - page_height;
+        }
+    }
+
+    /// open a browser state on the selected root, which becomes the most
+    /// recent one
+    pub fn open_selection(&self, screen: &Screen, con: &AppContext) -> AppStateCmdResult {
+        match self.selected_path() {
+            Some(path) => {
+                if let Err(e) = recent_roots::remember(path) {
+                    warn!("failed to remember {:?} as recent root: {:?}", path, e);
+                }
+                AppStateCmdResult::from_optional_state(
+                    BrowserState::new(
+                        path.to_path_buf(),
+                        con.launch_args.tree_options.clone(),
+                        screen,
+                        con,
+                        &Dam::unlimited(),
+                    ),
+                    Command::new(),
+                )
+            }
+            None => AppStateCmdResult::DisplayError("no root selected".to_string()),
+        }
+    }
+}
+
+impl AppState for StartState {
+    fn help(&self) -> &'static ScreenHelp {
+        &start_verbs::HELP
+    }
+
+    fn has_pending_task(&self) -> bool {
+        false
+    }
+
+    fn can_execute(&self, _verb_index: usize, _con: &AppContext) -> bool {
+        true
+    }
+
+    fn apply(
+        &mut self,
+        cmd: &mut Command,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<AppStateCmdResult, ProgramError> {
+        let page_height = StartState::page_height(screen);
+        Ok(match &cmd.action {
+            Action::Back => {
+                if self.pattern.is_some() {
+                    self.set_pattern(Pattern::None);
+                    cmd.raw.clear();
+                    AppStateCmdResult::Keep
+                } else {
+                    AppStateCmdResult::PopState
+                }
+            }
+            Action::Click(_, y) => {
+                let y = *y as usize;
+                if y > 0 && y <= page_height && self.scroll + y - 1 < self.displayed.len() {
+                    self.selection = self.scroll + y - 1;
+                }
+                AppStateCmdResult::Keep
+            }
+            Action::DoubleClick(_, y) => {
+                if *y > 0 && self.selection + 1 == self.scroll + *y as usize {
+                    self.open_selection(screen, con)
+                } else {
+                    AppStateCmdResult::Keep
+                }
+            }
+            Action::FuzzyPatternEdit(pat) => {
+                self.set_pattern(if pat.is_empty() {
+                    Pattern::None
+                } else {
+                    Pattern::fuzzy(pat)
+                });
+                AppStateCmdResult::Keep
+            }
+            Action::Help => {
+                AppStateCmdResult::NewState(
+                    Box::new(HelpState::new(screen, con, &start_verbs::HELP)),
+                    Command::new(),
+                )
+            }
+            Action::MoveSelection(dy) | Action::Scroll(dy) => {
+                self.move_selection(*dy, page_height);
+                AppStateCmdResult::Keep
+            }
+            Action::OpenSelection => self.open_selection(screen, con),
+            Action::Resize(w, h) => {
+                screen.set_terminal_size(*w, *h, con);
+                AppStateCmdResult::Keep
+            }
+            Action::VerbIndex(index) => {
+                let verb = &con.verb_store.verbs[*index];
+                self.execute_verb(verb, &verb.invocation, screen, con)?
+            }
+            Action::VerbInvocate(invocation) => match con.verb_store.search(&invocation.name) {
+                PrefixSearchResult::Match(verb) => {
+                    self.execute_verb(verb, &invocation, screen, con)?
+                }
+                _ => AppStateCmdResult::verb_not_found(&invocation.name),
+            },
+            _ => AppStateCmdResult::Keep,
+        })
+    }
+
+    /// reload the entries, as the recent roots may have changed
+    /// while browsing
+    fn refresh(&mut self, _screen: &Screen, con: &AppContext) -> Command {
+        *self = StartState::new(con);
+        Command::new()
+    }
+
+    fn do_pending_task(&mut self, _screen: &mut Screen, _con: &AppContext, _dam: &mut Dam) {
+        unreachable!();
+    }
+
+    fn display(
+        &mut self,
+        mut w: &mut dyn Write,
+        screen: &Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        let page_height = StartState::page_height(screen);
+        screen.goto_clear(&mut w, 0, 0)?;
+        screen.skin.default.queue_bg(&mut w)?;
+        screen.skin.help_headers.queue_str(&mut w, "Bookmarked and recent roots")?;
+        screen.clear_line(&mut w)?;
+        for y in 0..page_height {
+            screen.goto(&mut w, 0, y as u16 + 1)?;
+            let idx = self.scroll + y;
+            let selected = idx == self.selection;
+            if let Some(entry) = self.displayed.get(idx).map(|&i| &self.entries[i]) {
+                cond_bg!(mark_style, screen, selected, screen.skin.flag_value);
+                cond_bg!(name_style, screen, selected, screen.skin.directory);
+                let char_match = if selected {
+                    &screen.skin.char_match_selected
+                } else {
+                    &screen.skin.char_match
+                };
+                cond_bg!(char_match_style, screen, selected, char_match);
+                mark_style.queue_str(&mut w, if entry.bookmarked { "★ " } else { "  " })?;
+                self.pattern
+                    .style(&entry.name, &name_style, &char_match_style)
+                    .write_on(&mut w)?;
+            } else if idx == 0 && self.entries.is_empty() {
+                screen.skin.tree.queue_str(
+                    &mut w,
+                    "No root yet: bookmarks are defined in the configuration file",
+                )?;
+            }
+            if selected && idx < self.displayed.len() {
+                screen.skin.selected_line.queue_bg(&mut w)?;
+            } else {
+                screen.skin.default.queue_bg(&mut w)?;
+            }
+            w.queue(Clear(ClearType::UntilNewLine))?;
+        }
+        Ok(())
+    }
+
+    fn write_status(
+        &self,
+        mut w: &mut dyn Write,
+        cmd: &Command,
+        screen: &Screen,
+        con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        match &cmd.action {
+            Action::VerbEdit(invocation) if !invocation.name.is_empty() => {
+                match con.verb_store.search(&invocation.name) {
+                    PrefixSearchResult::Match(verb) => verb.write_status(
+                        &mut w,
+                        None,
+                        self.selected_path().map_or_else(PathBuf::new, Path::to_path_buf),
+                        None,
+                        invocation,
+                        screen,
+                    ),
+                    _ => Status::from_error(mad_inline!("No matching verb"))
+                        .display(&mut w, screen),
+                }
+            }
+            _ => Status::from_message(mad_inline!(
+                "Type to filter, hit *enter* to browse the selected root or *esc* to quit"
+            ))
+            .display(&mut w, screen),
+        }
+    }
+
+    /// there's no meaningful flags here
+    fn write_flags(
+        &self,
+        mut w: &mut dyn Write,
+        screen: &mut Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        screen.skin.default.queue_bg(&mut w)?;
+        w.queue(Clear(ClearType::UntilNewLine))?;
+        Ok(())
+    }
+}
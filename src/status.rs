@@ -1,6 +1,9 @@
 use crate::{errors::ProgramError, screens::Screen};
-use minimad::{Alignment, Composite};
-use std::io::Write;
+use minimad::{Alignment, Composite, Compound};
+use std::{io::Write, ops::Range};
+
+/// the length under which a path isn't shortened
+const MIN_SHORTENED_PATH: usize = 12;
 
 /// the status contains information written on the grey line
 ///  near the bottom of the screen
@@ -10,6 +13,117 @@ pub struct Status<'a> {
     error: bool, // is the current message an error?
 }
 
+/// a char of a message, with the index of its compound
+type MessageChar = (usize, char);
+
+fn is_path(word: &[MessageChar]) -> bool {
+    word.iter().any(|&(_, c)| c == '/' || c == '\\')
+}
+
+/// return the ranges of the words (runs of chars which aren't spaces)
+fn words(chars: &[MessageChar]) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, &(_, c)) in chars.iter().enumerate() {
+        match (c.is_whitespace(), start) {
+            (true, Some(s)) => {
+                words.push(s..i);
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        words.push(s..chars.len());
+    }
+    words
+}
+
+/// replace the middle of the longest paths with an ellipsis until
+/// the message fits or the paths can't be shortened anymore
+fn shorten_paths(chars: &mut Vec<MessageChar>, width: usize) {
+    while chars.len() > width {
+        let longest = words(chars)
+            .into_iter()
+            .filter(|word| word.len() > MIN_SHORTENED_PATH + 1 && is_path(&chars[word.clone()]))
+            .max_by_key(|word| word.len());
+        let word = match longest {
+            Some(word) => word,
+            None => return,
+        };
+        // one more char is removed for the ellipsis
+        let removed = (chars.len() - width + 1).min(word.len() - MIN_SHORTENED_PATH);
+        let start = word.start + (word.len() - removed) / 2;
+        let compound_idx = chars[start].0;
+        chars.splice(start..start + removed, Some((compound_idx, '…')));
+    }
+}
+
+/// return the ranges of the clauses of the message, which are separated
+/// by commas outside of code, each one with its separator
+fn clauses(chars: &[MessageChar], codes: &[bool]) -> Vec<Range<usize>> {
+    let mut clauses = Vec::new();
+    let mut start = 0;
+    for i in 1..chars.len() {
+        let (compound_idx, c) = chars[i - 1];
+        if c == ',' && chars[i].1 == ' ' && !codes[compound_idx] {
+            clauses.push(start..i + 1);
+            start = i + 1;
+        }
+    }
+    clauses.push(start..chars.len());
+    clauses
+}
+
+/// drop the clauses until the message fits: first the ones in the
+/// middle, from the end, then the first one. The last clause, which
+/// usually tells how to call a verb, is kept.
+fn drop_clauses(chars: &mut Vec<MessageChar>, codes: &[bool], width: usize) {
+    let clauses = clauses(chars, codes);
+    if clauses.len() < 2 {
+        return;
+    }
+    let mut len = chars.len();
+    let mut dropped = Vec::new();
+    let droppable = (1..clauses.len() - 1).rev().chain(std::iter::once(0));
+    for idx in droppable {
+        if len <= width {
+            break;
+        }
+        len -= clauses[idx].len();
+        dropped.push(clauses[idx].clone());
+    }
+    let mut i = 0;
+    chars.retain(|_| {
+        i += 1;
+        !dropped.iter().any(|range| range.contains(&(i - 1)))
+    });
+}
+
+/// make the texts of the compounds of a message fit the width, when
+/// possible, by removing the middle of the paths and then the less
+/// important clauses, instead of cutting the end of the message.
+/// `codes` tells which compounds are code.
+fn fit_texts(texts: &[&str], codes: &[bool], width: usize) -> Vec<String> {
+    let mut chars: Vec<MessageChar> = texts
+        .iter()
+        .enumerate()
+        .flat_map(|(idx, text)| text.chars().map(move |c| (idx, c)))
+        .collect();
+    if chars.len() > width {
+        shorten_paths(&mut chars, width);
+    }
+    if chars.len() > width {
+        drop_clauses(&mut chars, codes, width);
+    }
+    let mut fitted = vec![String::new(); texts.len()];
+    for (idx, c) in chars {
+        fitted[idx].push(c);
+    }
+    fitted
+}
+
 impl<'a> Status<'a> {
 
     pub fn new(
@@ -56,8 +170,64 @@ impl<'a> Status<'a> {
             &screen.status_skin.normal
         };
         skin.write_inline_on(w, " ")?;
-        let remaining_width = screen.width as usize - x - 1;
-        skin.write_composite_fill(w, self.message, remaining_width, Alignment::Left)?;
-        screen.clear_line(w)
+        // the end of the line is left to the breadcrumb
+        let remaining_width = (screen.width - screen.breadcrumb_width()) as usize - x - 1;
+        let compounds = &self.message.compounds;
+        let texts: Vec<&str> = compounds.iter().map(|compound| compound.src).collect();
+        let codes: Vec<bool> = compounds.iter().map(|compound| compound.code).collect();
+        let fitted = fit_texts(&texts, &codes, remaining_width);
+        let message = Composite {
+            style: self.message.style,
+            compounds: compounds
+                .iter()
+                .zip(fitted.iter())
+                .filter(|(_, text)| !text.is_empty())
+                .map(|(compound, text)| Compound {
+                    src: text,
+                    ..compound.clone()
+                })
+                .collect(),
+        };
+        skin.write_composite_fill(w, message, remaining_width, Alignment::Left)?;
+        screen.clear_line(w)?;
+        screen.write_breadcrumb(w)
+    }
+}
+
+#[cfg(test)]
+mod status_tests {
+
+    use super::*;
+
+    fn fit(texts: &[&str], codes: &[bool], width: usize) -> String {
+        fit_texts(texts, codes, width).concat()
+    }
+
+    #[test]
+    fn check_clauses_dropping() {
+        let texts = &[
+            "Hit ", "enter", " to focus, ", "alt-enter", " to cd, or a space then a verb",
+        ];
+        let codes = &[false; 5];
+        assert_eq!(
+            fit(texts, codes, 80),
+            "Hit enter to focus, alt-enter to cd, or a space then a verb",
+        );
+        assert_eq!(fit(texts, codes, 50), "Hit enter to focus, or a space then a verb");
+        assert_eq!(fit(texts, codes, 30), "or a space then a verb");
+    }
+
+    #[test]
+    fn check_paths_shortening() {
+        let texts = &[
+            "Hit ", "enter", " to ", "mv", ": ", "mv /home/me/dev/project/src/a.rs ../b.rs",
+        ];
+        let codes = &[false, false, false, false, false, true];
+        assert_eq!(fit(texts, codes, 42), "Hit enter to mv: mv /home/…rc/a.rs ../b.rs");
+        // the paths aren't shortened below a minimal length
+        assert_eq!(fit(texts, codes, 30), "Hit enter to mv: mv /home/…c/a.rs ../b.rs");
+        // commas in code don't separate clauses
+        let texts = &["Hit ", "enter", " to ", "echo", ": ", "echo a, b"];
+        assert_eq!(fit(texts, codes, 10), "Hit enter to echo: echo a, b");
     }
 }
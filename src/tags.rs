@@ -0,0 +1,156 @@
+//! color tags, as in Finder, attached to paths and kept in broot's
+//! data directory so that they survive between sessions.
+//!
+//! The tags of an entry are drawn as colored dots after its name, and
+//! `:filter_by_tag` (or typing eg `tag:red` as pattern) only shows the
+//! entries having a tag.
+
+use {
+    crate::path_store::{PathStore, StoredValue},
+    std::{io, path::Path},
+    toml::Value,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TagColor {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+    Gray,
+}
+
+impl TagColor {
+    pub const ALL: [TagColor; 7] = [
+        TagColor::Red,
+        TagColor::Orange,
+        TagColor::Yellow,
+        TagColor::Green,
+        TagColor::Blue,
+        TagColor::Purple,
+        TagColor::Gray,
+    ];
+    pub fn name(self) -> &'static str {
+        match self {
+            TagColor::Red => "red",
+            TagColor::Orange => "orange",
+            TagColor::Yellow => "yellow",
+            TagColor::Green => "green",
+            TagColor::Blue => "blue",
+            TagColor::Purple => "purple",
+            TagColor::Gray => "gray",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<TagColor> {
+        let name = name.trim().to_lowercase();
+        TagColor::ALL.iter().copied().find(|color| color.name() == name)
+    }
+    /// read the tag filter typed as a pattern, eg `tag:red`
+    pub fn from_pattern(pattern: &str) -> Option<TagColor> {
+        TagColor::from_name(pattern.strip_prefix("tag:")?)
+    }
+}
+
+/// the tags of a path, sorted, never empty in the store
+impl StoredValue for Vec<TagColor> {
+    fn from_toml(value: &Value) -> Option<Self> {
+        let mut colors: Vec<TagColor> = value
+            .as_array()?
+            .iter()
+            .filter_map(|color| TagColor::from_name(color.as_str()?))
+            .collect();
+        colors.sort();
+        colors.dedup();
+        if colors.is_empty() {
+            None
+        } else {
+            Some(colors)
+        }
+    }
+    fn to_toml(&self) -> Value {
+        Value::Array(self.iter().map(|color| Value::String(color.name().to_string())).collect())
+    }
+}
+
+lazy_static! {
+    static ref TAGS: PathStore<Vec<TagColor>> = PathStore::new("tags.toml");
+}
+
+/// return the tags of the path, in the order of `TagColor::ALL`
+pub fn get(path: &Path) -> Vec<TagColor> {
+    TAGS.get(path).unwrap_or_default()
+}
+
+pub fn has_tag(path: &Path, color: TagColor) -> bool {
+    TAGS.with_map(|tags| tags.get(path).map_or(false, |colors| colors.contains(&color)))
+}
+
+/// add the tag to the path, or remove it if the path already has it.
+/// Return whether the path has the tag after the change.
+pub fn toggle(path: &Path, color: TagColor) -> io::Result<bool> {
+    TAGS.change(|tags| {
+        let colors = tags.entry(path.to_path_buf()).or_default();
+        let tagged = match colors.iter().position(|&c| c == color) {
+            Some(idx) => {
+                colors.remove(idx);
+                false
+            }
+            None => {
+                colors.push(color);
+                colors.sort();
+                true
+            }
+        };
+        if colors.is_empty() {
+            tags.remove(path);
+        }
+        tagged
+    })
+}
+
+/// remove all the tags of the path. Return false if there was none.
+pub fn clear(path: &Path) -> io::Result<bool> {
+    TAGS.change(|tags| tags.remove(path).is_some())
+}
+
+/// move the tags of a file renamed outside of broot, and of the
+/// files inside it, to its new path
+pub fn move_path(from: &Path, to: &Path) -> io::Result<bool> {
+    TAGS.move_path(from, to)
+}
+
+#[cfg(test)]
+mod tags_tests {
+
+    use {
+        super::*,
+        crate::path_store::{from_toml, to_toml, PathMap},
+        std::path::PathBuf,
+    };
+
+    #[test]
+    fn check_tags_file() {
+        let mut tags: PathMap<Vec<TagColor>> = PathMap::new();
+        tags.insert(PathBuf::from("/home/me/todo.md"), vec![TagColor::Red, TagColor::Blue]);
+        assert_eq!(from_toml(&to_toml(&tags)), tags);
+        let tags: PathMap<Vec<TagColor>> = from_toml(
+            r#"
+            "/a" = ["blue", "Red", "pink", "red"]
+            "/b" = ["pink"]
+            "/c" = "red"
+        "#,
+        );
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[Path::new("/a")], vec![TagColor::Red, TagColor::Blue]);
+    }
+
+    #[test]
+    fn check_tag_patterns() {
+        assert_eq!(TagColor::from_pattern("tag:red"), Some(TagColor::Red));
+        assert_eq!(TagColor::from_pattern("tag:Gray"), Some(TagColor::Gray));
+        assert_eq!(TagColor::from_pattern("tag:re"), None);
+        assert_eq!(TagColor::from_pattern("red"), None);
+    }
+}
@@ -7,6 +7,7 @@ use {
     },
     std::{
         thread,
+        time::Duration,
     },
     termimad::Event,
 };
@@ -38,6 +39,15 @@ impl<V> ComputationResult<V> {
     }
 }
 
+/// what ended a wait on the dam
+pub enum DamWake<T, C> {
+    Event(Event), // a user event, to be handled
+    Other(T),     // a message which came on the other channel
+    Control(C),   // a message which came on the control channel
+    Timeout,      // nothing came before the end of the given timeout
+    Dead,         // the event source is closed: broot must quit
+}
+
 /// The dam controls the flow of events.
 /// A dam is used in broot to manage long computations and,
 /// when the user presses a key, either tell the computation
@@ -138,6 +148,53 @@ impl Dam {
             }
         }
     }
+
+    /// block until next event, until a message comes on the
+    /// other or the control channel, or until the timeout, if
+    /// any, is reached.
+    /// There's no other wakeup, so that an idle broot doesn't
+    /// use the CPU
+    pub fn next_wake<T, C>(
+        &mut self,
+        other: &Receiver<T>,
+        control: &Receiver<C>,
+        timeout: Option<Duration>,
+    ) -> DamWake<T, C> {
+        if let Some(event) = self.in_dam.take() {
+            return DamWake::Event(event);
+        }
+        let timeout = timeout.map_or_else(channel::never, channel::after);
+        select! {
+            recv(self.receiver) -> event => match event {
+                Ok(event) => DamWake::Event(event),
+                Err(_) => {
+                    debug!("dead dam");
+                    DamWake::Dead
+                }
+            },
+            recv(other) -> message => match message {
+                Ok(message) => DamWake::Other(message),
+                Err(_) => {
+                    // the other channel is closed, only events matter now
+                    match self.next_event() {
+                        Some(event) => DamWake::Event(event),
+                        None => DamWake::Dead,
+                    }
+                }
+            },
+            recv(control) -> message => match message {
+                Ok(message) => DamWake::Control(message),
+                Err(_) => {
+                    // the control channel is closed, only events matter now
+                    match self.next_event() {
+                        Some(event) => DamWake::Event(event),
+                        None => DamWake::Dead,
+                    }
+                }
+            },
+            recv(timeout) -> _ => DamWake::Timeout,
+        }
+    }
 }
 
 pub struct DamObserver {
@@ -0,0 +1,177 @@
+//! whether the terminal has a dark or a light background, so that the
+//! matching skin preset is used (see the `theme_mode`, `skin_preset`
+//! and `light_skin_preset` conf entries).
+//!
+//! The background is guessed from the `COLORFGBG` environment variable
+//! when it's set, or else by asking its color to the terminal with an
+//! OSC 11 query. This query is followed by a device attributes one,
+//! which all terminals answer, so that broot knows when the answers
+//! are all read and doesn't leave one in the input, where it would
+//! be taken for keystrokes.
+
+use std::env;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThemeMode {
+    Dark,
+    Light,
+}
+
+impl ThemeMode {
+    pub fn from_name(name: &str) -> Option<ThemeMode> {
+        match name {
+            "dark" => Some(ThemeMode::Dark),
+            "light" => Some(ThemeMode::Light),
+            _ => None,
+        }
+    }
+    pub fn toggled(self) -> ThemeMode {
+        match self {
+            ThemeMode::Dark => ThemeMode::Light,
+            ThemeMode::Light => ThemeMode::Dark,
+        }
+    }
+}
+
+/// guess the mode from a `COLORFGBG` value, eg "15;0", whose last
+/// field is the ansi code of the background
+fn from_colorfgbg(value: &str) -> Option<ThemeMode> {
+    match value.rsplit(';').next()?.trim().parse::<u8>().ok()? {
+        0..=6 | 8 => Some(ThemeMode::Dark),
+        7 | 9..=15 => Some(ThemeMode::Light),
+        _ => None,
+    }
+}
+
+/// guess the mode from the answer of the terminal to an OSC 11
+/// query, eg "\x1b]11;rgb:ffff/ffff/dddd\x07"
+fn from_osc_11_answer(answer: &str) -> Option<ThemeMode> {
+    let rgb = answer.split("rgb:").nth(1)?;
+    let rgb = rgb.split(&['\x07', '\x1b'][..]).next()?;
+    let mut channels = rgb.split('/').map(|hex| {
+        let max = 16f64.powi(hex.len() as i32) - 1.0;
+        u32::from_str_radix(hex, 16).ok().map(|v| f64::from(v) / max)
+    });
+    let r = channels.next()??;
+    let g = channels.next()??;
+    let b = channels.next()??;
+    let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    Some(if luminance > 0.5 {
+        ThemeMode::Light
+    } else {
+        ThemeMode::Dark
+    })
+}
+
+/// tell whether the answers end with the one to the device
+/// attributes query, eg "\x1b[?62;22c"
+fn ends_with_device_attributes(answers: &[u8]) -> bool {
+    answers.ends_with(b"c") && answers.windows(3).any(|w| w == b"\x1b[?")
+}
+
+/// ask the terminal for its background color, waiting a little for
+/// the answers. On timeout, the input is flushed so that a partial
+/// answer isn't read as keystrokes
+#[cfg(unix)]
+fn query_background() -> Option<String> {
+    use std::{
+        fs::OpenOptions,
+        io::{Read, Write},
+        mem,
+        os::unix::io::AsRawFd,
+        time::{Duration, Instant},
+    };
+    let mut tty = OpenOptions::new().read(true).write(true).open("/dev/tty").ok()?;
+    let fd = tty.as_raw_fd();
+    // the answer must neither be echoed nor wait for a newline
+    let mut termios: libc::termios = unsafe { mem::zeroed() };
+    if unsafe { libc::tcgetattr(fd, &mut termios) } != 0 {
+        return None;
+    }
+    let original = termios;
+    termios.c_lflag &= !(libc::ICANON | libc::ECHO);
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &termios) } != 0 {
+        return None;
+    }
+    let mut answer = Vec::new();
+    let deadline = Instant::now() + Duration::from_millis(200);
+    let mut complete = false;
+    if tty.write_all(b"\x1b]11;?\x07\x1b[c").and_then(|_| tty.flush()).is_ok() {
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            let mut pollfd = libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let timeout = remaining.as_millis() as libc::c_int;
+            if unsafe { libc::poll(&mut pollfd, 1, timeout) } <= 0 {
+                break;
+            }
+            let mut buf = [0u8; 64];
+            match tty.read(&mut buf) {
+                Ok(n) if n > 0 => answer.extend_from_slice(&buf[..n]),
+                _ => break,
+            }
+            if ends_with_device_attributes(&answer) {
+                complete = true;
+                break;
+            }
+            if answer.len() > 256 {
+                break;
+            }
+        }
+    }
+    unsafe {
+        if !complete {
+            libc::tcflush(fd, libc::TCIFLUSH);
+        }
+        libc::tcsetattr(fd, libc::TCSANOW, &original);
+    }
+    Some(String::from_utf8_lossy(&answer).to_string())
+}
+
+#[cfg(not(unix))]
+fn query_background() -> Option<String> {
+    None
+}
+
+/// guess whether the terminal has a dark or light background
+pub fn detect() -> Option<ThemeMode> {
+    if let Ok(value) = env::var("COLORFGBG") {
+        if let Some(mode) = from_colorfgbg(&value) {
+            debug!("theme mode from COLORFGBG: {:?}", mode);
+            return Some(mode);
+        }
+    }
+    let mode = query_background().and_then(|answer| from_osc_11_answer(&answer));
+    debug!("theme mode from the terminal: {:?}", mode);
+    mode
+}
+
+#[cfg(test)]
+mod theme_mode_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_background_guesses() {
+        assert_eq!(from_colorfgbg("15;0"), Some(ThemeMode::Dark));
+        assert_eq!(from_colorfgbg("0;default;15"), Some(ThemeMode::Light));
+        assert_eq!(from_colorfgbg("default"), None);
+        assert_eq!(from_osc_11_answer("\x1b]11;rgb:ffff/ffff/dddd\x07"), Some(ThemeMode::Light));
+        assert_eq!(from_osc_11_answer("\x1b]11;rgb:1e/1e/2e\x1b\\"), Some(ThemeMode::Dark));
+        assert_eq!(from_osc_11_answer("nothing"), None);
+        assert_eq!(
+            from_osc_11_answer("\x1b]11;rgb:0000/0000/0000\x07\x1b[?62;22c"),
+            Some(ThemeMode::Dark),
+        );
+    }
+
+    #[test]
+    fn check_answers_end() {
+        assert!(ends_with_device_attributes(b"\x1b]11;rgb:ffff/ffff/ffff\x07\x1b[?62;22c"));
+        assert!(ends_with_device_attributes(b"\x1b[?1;2c"));
+        assert!(!ends_with_device_attributes(b"\x1b]11;rgb:ffff/ffff/ffff\x07"));
+        assert!(!ends_with_device_attributes(b"\x1b]11;rgb:cccc/cccc/cccc\x07\x1b[?6"));
+    }
+}
@@ -0,0 +1,48 @@
+//! the choice, among the lines having the best score of a search,
+//! of the one which is selected (see the `tie_breaker` conf entry).
+//!
+//! Lines which are still equal stay in the order of the tree, so
+//! that the selection doesn't jump between them while typing.
+
+use {
+    crate::flat_tree::TreeLine,
+    std::cmp::Ordering,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TieBreaker {
+    Shallower,   // the least deep line
+    ShorterName, // the line with the shortest name, then the least deep
+    MoreRecent,  // the most recently modified line, then the least deep
+}
+
+impl Default for TieBreaker {
+    fn default() -> Self {
+        TieBreaker::Shallower
+    }
+}
+
+impl TieBreaker {
+    pub fn from_name(name: &str) -> Option<TieBreaker> {
+        match name {
+            "shallower" => Some(TieBreaker::Shallower),
+            "shorter_name" => Some(TieBreaker::ShorterName),
+            "more_recent" => Some(TieBreaker::MoreRecent),
+            _ => None,
+        }
+    }
+
+    /// compare two lines of equal scores, `Less` meaning the
+    /// first one is preferred
+    pub fn compare(self, a: &TreeLine, b: &TreeLine) -> Ordering {
+        let depth = a.depth.cmp(&b.depth);
+        match self {
+            TieBreaker::Shallower => depth,
+            TieBreaker::ShorterName => {
+                a.name.chars().count().cmp(&b.name.chars().count()).then(depth)
+            }
+            // a line without date comes after the dated ones
+            TieBreaker::MoreRecent => b.modified().cmp(&a.modified()).then(depth),
+        }
+    }
+}
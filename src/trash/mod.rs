@@ -0,0 +1,131 @@
+//! moving files to the trash of the desktop, listing the trashed
+//! files, restoring or purging them.
+//!
+//! Only the freedesktop.org trash of the user's home (used on Linux)
+//! is currently supported. Where it can't be used, for example on
+//! some mounts, the user may configure commands to run instead.
+
+#[cfg(target_os = "linux")]
+mod trash_linux;
+
+#[cfg(target_os = "linux")]
+pub use trash_linux::{list, purge, restore, trash};
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// a file or directory which is in the trash
+#[derive(Debug, Clone)]
+pub struct TrashedItem {
+    pub name: String, // name of the file in the trash
+    pub original_path: PathBuf,
+    pub deletion_date: String, // as written in the trash info, eg "2020-04-01T12:18:00"
+}
+
+/// a command trashing the paths matching a glob when the native
+/// trash fails, as declared in the `[trash-commands]` section of
+/// the configuration, for example `"/mnt/nas/**" = "gio trash {file}"`
+#[derive(Debug, Clone)]
+pub struct TrashCommand {
+    pattern: glob::Pattern,
+    execution: String,
+}
+
+impl TrashCommand {
+    pub fn new(glob: &str, execution: &str) -> Result<TrashCommand, glob::PatternError> {
+        Ok(TrashCommand {
+            pattern: glob::Pattern::new(glob)?,
+            execution: execution.to_string(),
+        })
+    }
+    pub fn matches(&self, path: &Path) -> bool {
+        self.pattern.matches_path(path)
+    }
+    /// run the command, waiting for its end, and return
+    /// its error output if it failed
+    fn run(&self, path: &Path) -> io::Result<()> {
+        let file = path.to_string_lossy();
+        let mut parts = self
+            .execution
+            .split_whitespace()
+            .map(|token| token.replace("{file}", &file));
+        let exe = match parts.next() {
+            Some(exe) => exe,
+            None => {
+                return Err(io::Error::new(io::ErrorKind::Other, "empty trash command"));
+            }
+        };
+        let output = Command::new(&exe).args(parts).output()?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("{} failed: {}", exe, String::from_utf8_lossy(&output.stderr).trim()),
+            ))
+        }
+    }
+}
+
+/// move the file or directory to the trash, with the native trash
+/// or, if it fails, with the first configured command matching the path.
+/// Return whether the native trash was used, as only its items can
+/// be restored by broot
+pub fn trash_with_fallback(path: &Path, commands: &[TrashCommand]) -> io::Result<bool> {
+    match (trash(path), commands.iter().find(|c| c.matches(path))) {
+        (Err(e), Some(command)) => {
+            info!("native trash failed ({}), running {:?}", e, &command.execution);
+            command.run(path).map(|_| false)
+        }
+        (result, _) => result.map(|_| true),
+    }
+}
+
+/// restore the item most recently trashed from this path
+pub fn restore_path(path: &Path) -> io::Result<()> {
+    let item = list()?
+        .into_iter()
+        .filter(|item| item.original_path == path)
+        .max_by(|a, b| a.deletion_date.cmp(&b.deletion_date))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not found in the trash"))?;
+    restore(&item)
+}
+
+#[cfg(target_os = "linux")]
+pub fn supported() -> bool {
+    true
+}
+
+#[cfg(not(target_os = "linux"))]
+mod trash_unsupported {
+    use {
+        super::TrashedItem,
+        std::{io, path::Path},
+    };
+    fn unsupported() -> io::Error {
+        io::Error::new(io::ErrorKind::Other, "no supported trash on this system")
+    }
+    pub fn trash(_path: &Path) -> io::Result<()> {
+        Err(unsupported())
+    }
+    pub fn list() -> io::Result<Vec<TrashedItem>> {
+        Ok(Vec::new())
+    }
+    pub fn restore(_item: &TrashedItem) -> io::Result<()> {
+        Err(unsupported())
+    }
+    pub fn purge(_item: &TrashedItem) -> io::Result<()> {
+        Err(unsupported())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub use trash_unsupported::{list, purge, restore, trash};
+
+#[cfg(not(target_os = "linux"))]
+pub fn supported() -> bool {
+    false
+}
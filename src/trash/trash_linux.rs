@@ -0,0 +1,224 @@
+//! implementation of the trash described by the freedesktop.org
+//! specification: trashed files are moved in `$XDG_DATA_HOME/Trash/files`
+//! and described by a `.trashinfo` file in `$XDG_DATA_HOME/Trash/info`.
+
+use {
+    super::TrashedItem,
+    chrono::offset::Local,
+    directories::BaseDirs,
+    std::{
+        env,
+        ffi::OsStr,
+        fs::{self, OpenOptions},
+        io::{self, Write},
+        os::unix::ffi::OsStrExt,
+        path::{Path, PathBuf},
+    },
+};
+
+const INFO_EXTENSION: &str = ".trashinfo";
+
+fn trash_dir() -> io::Result<PathBuf> {
+    match BaseDirs::new() {
+        Some(base_dirs) => Ok(base_dirs.data_dir().join("Trash")),
+        None => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no home directory found for the trash",
+        )),
+    }
+}
+
+fn info_path(trash_dir: &Path, name: &str) -> PathBuf {
+    trash_dir.join("info").join(format!("{}{}", name, INFO_EXTENSION))
+}
+
+/// move the file or directory to the trash
+///
+/// Files which aren't on the same file system than the trash
+/// can't be moved to it.
+pub fn trash(path: &Path) -> io::Result<()> {
+    let os_name = match path.file_name() {
+        Some(name) => name,
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "this path can't be trashed",
+            ));
+        }
+    };
+    // only the parent is canonicalized, so that a symlink is
+    // trashed instead of its target
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.canonicalize()?,
+        _ => env::current_dir()?,
+    };
+    let path = parent.join(os_name);
+    let file_name = os_name.to_string_lossy().to_string();
+    let trash_dir = trash_dir()?;
+    fs::create_dir_all(trash_dir.join("files"))?;
+    fs::create_dir_all(trash_dir.join("info"))?;
+    // the info file is created first, with create_new, so that
+    // two concurrent trashings can't take the same name
+    let mut idx = 1;
+    let (name, mut info_file) = loop {
+        let name = if idx == 1 {
+            file_name.clone()
+        } else {
+            format!("{}.{}", file_name, idx)
+        };
+        idx += 1;
+        if fs::symlink_metadata(trash_dir.join("files").join(&name)).is_ok() {
+            continue;
+        }
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(info_path(&trash_dir, &name))
+        {
+            Ok(file) => break (name, file),
+            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    };
+    let info = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(&path),
+        Local::now().format("%Y-%m-%dT%H:%M:%S"),
+    );
+    let moved = info_file
+        .write_all(info.as_bytes())
+        .and_then(|_| fs::rename(&path, trash_dir.join("files").join(&name)));
+    if let Err(e) = moved {
+        let _ = fs::remove_file(info_path(&trash_dir, &name));
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// read a trash info file
+fn read_info(path: &Path, name: String) -> Option<TrashedItem> {
+    let content = fs::read_to_string(path).ok()?;
+    let mut original_path = None;
+    let mut deletion_date = String::new();
+    for line in content.lines() {
+        if line.starts_with("Path=") {
+            original_path = Some(decode_path(&line[5..]));
+        } else if line.starts_with("DeletionDate=") {
+            deletion_date = line[13..].to_string();
+        }
+    }
+    Some(TrashedItem {
+        name,
+        original_path: original_path?,
+        deletion_date,
+    })
+}
+
+/// list the items of the trash, the most recently trashed first
+pub fn list() -> io::Result<Vec<TrashedItem>> {
+    let trash_dir = trash_dir()?;
+    let mut items = Vec::new();
+    let entries = match fs::read_dir(trash_dir.join("info")) {
+        Ok(entries) => entries,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+            return Ok(items); // the trash was never used
+        }
+        Err(e) => return Err(e),
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if !file_name.ends_with(INFO_EXTENSION) {
+            continue;
+        }
+        let name = file_name[..file_name.len() - INFO_EXTENSION.len()].to_string();
+        match read_info(&entry.path(), name) {
+            Some(item) => items.push(item),
+            None => warn!("invalid trash info file: {:?}", entry.path()),
+        }
+    }
+    items.sort_by(|a, b| b.deletion_date.cmp(&a.deletion_date));
+    Ok(items)
+}
+
+/// move back the item to its original location, unless
+/// something is already there
+pub fn restore(item: &TrashedItem) -> io::Result<()> {
+    let trash_dir = trash_dir()?;
+    if fs::symlink_metadata(&item.original_path).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{:?} already exists", &item.original_path),
+        ));
+    }
+    if let Some(parent) = item.original_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(trash_dir.join("files").join(&item.name), &item.original_path)?;
+    fs::remove_file(info_path(&trash_dir, &item.name))
+}
+
+/// definitely remove the item
+pub fn purge(item: &TrashedItem) -> io::Result<()> {
+    let trash_dir = trash_dir()?;
+    let path = trash_dir.join("files").join(&item.name);
+    match fs::symlink_metadata(&path) {
+        Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(&path)?,
+        Ok(_) => fs::remove_file(&path)?,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => {} // only the info remains
+        Err(e) => return Err(e),
+    }
+    fs::remove_file(info_path(&trash_dir, &item.name))
+}
+
+/// percent-encode the path as required in trash info files
+fn encode_path(path: &Path) -> String {
+    let mut encoded = String::new();
+    for &b in path.as_os_str().as_bytes() {
+        match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(b as char);
+            }
+            _ => {
+                encoded.push_str(&format!("%{:02X}", b));
+            }
+        }
+    }
+    encoded
+}
+
+fn decode_path(encoded: &str) -> PathBuf {
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(b) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    PathBuf::from(OsStr::from_bytes(&decoded))
+}
+
+#[cfg(test)]
+mod trash_linux_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_path_encoding() {
+        let check = |path: &str, encoded: &str| {
+            assert_eq!(encode_path(Path::new(path)), encoded);
+            assert_eq!(decode_path(encoded), PathBuf::from(path));
+        };
+        check("/home/dys/dev/broot", "/home/dys/dev/broot");
+        check("/home/dys/my file.txt", "/home/dys/my%20file.txt");
+        check("/tmp/100%", "/tmp/100%25");
+        check("/tmp/été", "/tmp/%C3%A9t%C3%A9");
+    }
+}
@@ -0,0 +1,248 @@
+use {
+    crate::{
+        app_context::AppContext,
+        app_state::{AppState, AppStateCmdResult},
+        commands::{Action, Command},
+        errors::{FileOp, FileOpError, ProgramError},
+        help_content::ScreenHelp,
+        help_states::HelpState,
+        op_journal::{self, Operation},
+        screens::Screen,
+        status::Status,
+        task_sync::Dam,
+        trash::{self, TrashedItem},
+        trash_verbs,
+        verb_store::PrefixSearchResult,
+        verbs::VerbExecutor,
+    },
+    crossterm::{
+        terminal::{Clear, ClearType},
+        QueueableCommand,
+    },
+    std::io::Write,
+};
+
+/// an application state listing the items of the trash, so
+/// that the user may restore or purge them
+pub struct TrashState {
+    items: Vec<TrashedItem>,
+    pub selection: usize,
+    pub scroll: usize,
+}
+
+impl TrashState {
+    pub fn new() -> Result<TrashState, ProgramError> {
+        let items = trash::list()?;
+        Ok(TrashState {
+            items,
+            selection: 0,
+            scroll: 0,
+        })
+    }
+
+    /// return the number of lines available for the items
+    pub fn page_height(screen: &Screen) -> usize {
+        (screen.height as usize).max(3) - 3
+    }
+
+    pub fn items_count(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn selected_item(&self) -> Option<&TrashedItem> {
+        self.items.get(self.selection)
+    }
+
+    pub fn move_selection(&mut self, dy: i32, page_height: usize) {
+        let len = self.items.len() as i32;
+        if len == 0 {
+            return;
+        }
+        let selection = (self.selection as i32 + dy).max(0).min(len - 1) as usize;
+        self.selection = selection;
+        if selection < self.scroll {
+            self.scroll = selection;
+        } else if selection >= self.scroll + page_height {
+            self.scroll = selection + 1 - page_height;
+        }
+    }
+
+    /// move the selected item back to where it was trashed from
+    pub fn restore_selection(&self) -> AppStateCmdResult {
+        match self.selected_item() {
+            Some(item) => match trash::restore(item) {
+                Ok(()) => {
+                    op_journal::record(Operation::Restore(item.original_path.clone()));
+                    AppStateCmdResult::RefreshState { clear_cache: true }
+                }
+                Err(e) => FileOpError::new(FileOp::Restore, &item.original_path, e).into(),
+            },
+            None => AppStateCmdResult::DisplayError("no trashed item selected".to_string()),
+        }
+    }
+
+    /// definitely remove the selected item
+    pub fn purge_selection(&self) -> AppStateCmdResult {
+        match self.selected_item() {
+            Some(item) => match trash::purge(item) {
+                Ok(()) => AppStateCmdResult::RefreshState { clear_cache: false },
+                Err(e) => FileOpError::new(FileOp::Purge, &item.original_path, e).into(),
+            },
+            None => AppStateCmdResult::DisplayError("no trashed item selected".to_string()),
+        }
+    }
+}
+
+impl AppState for TrashState {
+    fn help(&self) -> &'static ScreenHelp {
+        &trash_verbs::HELP
+    }
+
+    fn has_pending_task(&self) -> bool {
+        false
+    }
+
+    fn can_execute(&self, _verb_index: usize, _con: &AppContext) -> bool {
+        true
+    }
+
+    fn apply(
+        &mut self,
+        cmd: &mut Command,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<AppStateCmdResult, ProgramError> {
+        let page_height = TrashState::page_height(screen);
+        Ok(match &cmd.action {
+            Action::Back => AppStateCmdResult::PopState,
+            Action::Click(_, y) => {
+                let y = *y as usize;
+                if y > 0 && y <= page_height && self.scroll + y - 1 < self.items.len() {
+                    self.selection = self.scroll + y - 1;
+                }
+                AppStateCmdResult::Keep
+            }
+            Action::Help => {
+                AppStateCmdResult::NewState(
+                    Box::new(HelpState::new(screen, con, &trash_verbs::HELP)),
+                    Command::new(),
+                )
+            }
+            Action::MoveSelection(dy) | Action::Scroll(dy) => {
+                self.move_selection(*dy, page_height);
+                AppStateCmdResult::Keep
+            }
+            Action::Resize(w, h) => {
+                screen.set_terminal_size(*w, *h, con);
+                AppStateCmdResult::Keep
+            }
+            Action::VerbIndex(index) => {
+                let verb = &con.verb_store.verbs[*index];
+                self.execute_verb(verb, &verb.invocation, screen, con)?
+            }
+            Action::VerbInvocate(invocation) => match con.verb_store.search(&invocation.name) {
+                PrefixSearchResult::Match(verb) => {
+                    self.execute_verb(verb, &invocation, screen, con)?
+                }
+                _ => AppStateCmdResult::verb_not_found(&invocation.name),
+            },
+            _ => AppStateCmdResult::Keep,
+        })
+    }
+
+    /// read the trash again
+    fn refresh(&mut self, screen: &Screen, _con: &AppContext) -> Command {
+        match trash::list() {
+            Ok(items) => {
+                self.items = items;
+                let page_height = TrashState::page_height(screen);
+                self.move_selection(0, page_height);
+            }
+            Err(e) => {
+                warn!("failed to read the trash: {:?}", e);
+            }
+        }
+        Command::new()
+    }
+
+    fn do_pending_task(&mut self, _screen: &mut Screen, _con: &AppContext, _dam: &mut Dam) {
+        unreachable!();
+    }
+
+    fn display(
+        &mut self,
+        mut w: &mut dyn Write,
+        screen: &Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        let page_height = TrashState::page_height(screen);
+        screen.goto_clear(&mut w, 0, 0)?;
+        screen.skin.default.queue_bg(&mut w)?;
+        screen
+            .skin
+            .help_headers
+            .queue_str(&mut w, "deletion date       original path")?;
+        screen.clear_line(&mut w)?;
+        for y in 0..page_height {
+            screen.goto(&mut w, 0, y as u16 + 1)?;
+            let idx = self.scroll + y;
+            let selected = idx == self.selection;
+            if let Some(item) = self.items.get(idx) {
+                cond_bg!(date_style, screen, selected, screen.skin.dates);
+                cond_bg!(path_style, screen, selected, screen.skin.file);
+                date_style.queue(
+                    &mut w,
+                    format!("{:19} ", item.deletion_date.replace('T', " ")),
+                )?;
+                path_style.queue(&mut w, item.original_path.to_string_lossy())?;
+            } else if idx == 0 && self.items.is_empty() {
+                screen.skin.tree.queue_str(&mut w, "The trash is empty")?;
+            }
+            if selected && idx < self.items.len() {
+                screen.skin.selected_line.queue_bg(&mut w)?;
+            } else {
+                screen.skin.default.queue_bg(&mut w)?;
+            }
+            w.queue(Clear(ClearType::UntilNewLine))?;
+        }
+        Ok(())
+    }
+
+    fn write_status(
+        &self,
+        mut w: &mut dyn Write,
+        cmd: &Command,
+        screen: &Screen,
+        con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        match &cmd.action {
+            Action::VerbEdit(invocation) if !invocation.name.is_empty() => {
+                match con.verb_store.search(&invocation.name) {
+                    PrefixSearchResult::Match(verb) => Status::from_message(mad_inline!(
+                        "Hit *enter* to **$0**",
+                        &verb.invocation.name,
+                    ))
+                    .display(&mut w, screen),
+                    _ => Status::from_error(mad_inline!("No matching verb"))
+                        .display(&mut w, screen),
+                }
+            }
+            _ => Status::from_message(mad_inline!(
+                "Type *:restore* or *:purge* to act on the selected item, *esc* to get back"
+            ))
+            .display(&mut w, screen),
+        }
+    }
+
+    /// there's no meaningful flags here
+    fn write_flags(
+        &self,
+        mut w: &mut dyn Write,
+        screen: &mut Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        screen.skin.default.queue_bg(&mut w)?;
+        w.queue(Clear(ClearType::UntilNewLine))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+//! this modules defines the execution of verbs on the trash screen
+
+use crate::{
+    app_context::AppContext,
+    app_state::AppStateCmdResult,
+    commands::Command,
+    errors::ProgramError,
+    help_content::{ScreenHelp, ScreenKey},
+    help_states::HelpState,
+    op_journal,
+    screens::Screen,
+    trash_states::TrashState,
+    verb_invocation::VerbInvocation,
+    verbs::{Verb, VerbExecutor},
+};
+
+/// the help of the trash screen
+pub static HELP: ScreenHelp = ScreenHelp {
+    name: "trash screen",
+    description: "This screen lists the items of the trash, the most recently deleted first.",
+    keys: &[
+        ScreenKey::new("esc", "get back to the tree"),
+        ScreenKey::new("↑ ↓", "move the selection"),
+        ScreenKey::new("?", "display this help"),
+    ],
+    builtins: Some(&[
+        ":back", ":help", ":line_down", ":line_up", ":open_trash", ":refresh", ":page_down",
+        ":page_up", ":purge", ":quit", ":restore", ":select_first", ":select_last", ":undo",
+    ]),
+    external_verbs: false,
+};
+
+impl VerbExecutor for TrashState {
+    fn execute_verb(
+        &mut self,
+        verb: &Verb,
+        invocation: &VerbInvocation,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<AppStateCmdResult, ProgramError> {
+        if let Some(err) = verb.match_error(invocation) {
+            return Ok(AppStateCmdResult::DisplayError(err));
+        }
+        if let Some(err) = HELP.unlisted_error(verb) {
+            return Ok(AppStateCmdResult::DisplayError(err));
+        }
+        let page_height = TrashState::page_height(screen);
+        Ok(match verb.execution.as_ref() {
+            ":back" => AppStateCmdResult::PopState,
+            ":help" => AppStateCmdResult::NewState(
+                Box::new(HelpState::new(screen, con, &HELP)),
+                Command::new(),
+            ),
+            ":line_down" => {
+                self.move_selection(1, page_height);
+                AppStateCmdResult::Keep
+            }
+            ":line_up" => {
+                self.move_selection(-1, page_height);
+                AppStateCmdResult::Keep
+            }
+            ":open_trash" | ":refresh" => AppStateCmdResult::RefreshState { clear_cache: false },
+            ":page_down" => {
+                self.move_selection(page_height as i32, page_height);
+                AppStateCmdResult::Keep
+            }
+            ":page_up" => {
+                self.move_selection(-(page_height as i32), page_height);
+                AppStateCmdResult::Keep
+            }
+            ":purge" => self.purge_selection(),
+            ":quit" => AppStateCmdResult::Quit,
+            ":restore" => self.restore_selection(),
+            ":select_first" => {
+                self.move_selection(-(self.selection as i32), page_height);
+                AppStateCmdResult::Keep
+            }
+            ":select_last" => {
+                let len = self.items_count() as i32;
+                self.move_selection(len, page_height);
+                AppStateCmdResult::Keep
+            }
+            ":undo" => match op_journal::undo_last(&con.trash_commands) {
+                Ok(report) => AppStateCmdResult::RefreshWithMessage(report),
+                Err(e) => AppStateCmdResult::DisplayFailure(e),
+            },
+            _ if verb.execution.starts_with(':') => AppStateCmdResult::Keep,
+            _ => AppStateCmdResult::DisplayError(
+                "only :restore and :purge apply to trashed items".to_string(),
+            ),
+        })
+    }
+}
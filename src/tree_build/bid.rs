@@ -8,7 +8,8 @@ pub type BId = Id<BLine>;
 
 // a structure making it possible to keep bline references
 //  sorted in a binary heap with the line with the smallest
-//  score at the top
+//  score at the top. Among equal scores, the last gathered
+//  line is at the top, so that trimming is stable
 pub struct SortableBId {
     pub id: BId,
     pub score: i32,
@@ -16,13 +17,13 @@ pub struct SortableBId {
 impl Eq for SortableBId {}
 impl PartialEq for SortableBId {
     fn eq(&self, other: &SortableBId) -> bool {
-        self.score == other.score // unused but required by spec of Ord
+        self.score == other.score && self.id == other.id // unused but required by spec of Ord
     }
 }
 impl Ord for SortableBId {
     fn cmp(&self, other: &SortableBId) -> Ordering {
         if self.score == other.score {
-            Ordering::Equal
+            self.id.index().cmp(&other.id.index())
         } else if self.score < other.score {
             Ordering::Greater
         } else {
@@ -1,9 +1,14 @@
 use {
     crate::{
+        archives::Archive,
         errors::TreeBuildError,
-        flat_tree::{LineType, TreeLine},
+        file_sizes::FileSize,
+        flat_tree::{LineMetadata, LineType, TreeLine},
+        remote,
+        task_sync::ComputationResult,
         git_ignore::{
             GitIgnoreChain,
+            IgnoringRule,
         },
     },
     id_arena::{
@@ -11,22 +16,62 @@ use {
     },
     std::{
         fs,
-        path::PathBuf,
+        path::{Path, PathBuf},
+        rc::Rc,
         result::Result,
+        sync::Arc,
     },
     super::{
         bid::BId,
     },
 };
 
+/// the kind of a line, as known during the build
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BLineKind {
+    Dir,
+    File, // or any other file which isn't a directory nor a link
+    SymLink,
+    ArchivedDir, // a directory in an archive, or the archive itself when it's the root
+    ArchivedFile,
+    RemoteDir,
+    RemoteFile, // symlinks aren't followed on a remote server
+}
+
+impl BLineKind {
+    pub fn is_dir(self) -> bool {
+        match self {
+            BLineKind::Dir | BLineKind::ArchivedDir | BLineKind::RemoteDir => true,
+            _ => false,
+        }
+    }
+}
+
+impl From<fs::FileType> for BLineKind {
+    fn from(file_type: fs::FileType) -> Self {
+        if file_type.is_dir() {
+            BLineKind::Dir
+        } else if file_type.is_symlink() {
+            BLineKind::SymLink
+        } else {
+            BLineKind::File
+        }
+    }
+}
+
 /// like a tree line, but with the info needed during the build
-/// This structure isn't usable independantly from the tree builder
+/// This structure isn't usable independantly from the tree builder.
+///
+/// To save memory on big trees, a bline doesn't store its path, which
+/// is made from the names of its ancestors when needed.
 pub struct BLine {
     pub parent_id: Option<BId>,
-    pub path: PathBuf,
     pub depth: u16,
-    pub name: String,
-    pub file_type: fs::FileType,
+    pub name: Rc<str>, // interned, as many lines share the same name
+    pub kind: BLineKind,
+    pub links_to_dir: bool, // true for a symlink to a directory that the search may enter
+    pub dir_id: Option<(u64, u64)>, // (device, inode), known once the children are loaded
+    pub is_loop: bool, // true when the directory is one of its ancestors
     pub children: Option<Vec<BId>>, // sorted and filtered
     pub next_child_idx: usize,      // index for iteration, among the children
     pub has_error: bool,
@@ -34,34 +79,55 @@ pub struct BLine {
     pub score: i32,
     pub nb_kept_children: i32, // used during the trimming step
     pub git_ignore_chain: GitIgnoreChain,
+    pub ignored_by: Option<Arc<IgnoringRule>>, // only known when auditing the ignore rules
 }
 
 impl BLine {
     /// a special constructor, checking nothing
     pub fn from_root(
         blines: &mut Arena<BLine>,
-        path: PathBuf,
+        path: &Path,
         git_ignore_chain: GitIgnoreChain,
+        archive: Option<&Archive>,
     ) -> Result<BId, TreeBuildError> {
-        let name = match path.file_name() {
-            Some(name) => name.to_string_lossy().to_string(),
-            None => String::from("???"), // should not happen
+        let name: Rc<str> = match path.file_name() {
+            Some(name) => Rc::from(name.to_string_lossy().as_ref()),
+            None => Rc::from("???"), // should not happen
         };
-        if let Ok(md) = fs::metadata(&path) {
-            let file_type = md.file_type();
+        let kind = match archive {
+            Some(archive) => archive.is_dir(path).map(|is_dir| {
+                if is_dir {
+                    BLineKind::ArchivedDir
+                } else {
+                    BLineKind::ArchivedFile
+                }
+            }),
+            None if remote::is_remote(path) => remote::entry(path).ok().map(|e| {
+                if e.is_dir {
+                    BLineKind::RemoteDir
+                } else {
+                    BLineKind::RemoteFile
+                }
+            }),
+            None => fs::metadata(path).ok().map(|md| BLineKind::from(md.file_type())),
+        };
+        if let Some(kind) = kind {
             Ok(blines.alloc(BLine {
                 parent_id: None,
-                path,
                 depth: 0,
                 name,
                 children: None,
                 next_child_idx: 0,
-                file_type,
+                kind,
+                links_to_dir: false,
+                dir_id: None,
+                is_loop: false,
                 has_error: false,
                 has_match: true,
                 score: 0,
                 nb_kept_children: 0,
                 git_ignore_chain,
+                ignored_by: None,
             }))
         } else {
             Err(TreeBuildError::FileNotFound {
@@ -69,33 +135,43 @@ impl BLine {
             })
         }
     }
-    pub fn to_tree_line(&self) -> std::io::Result<TreeLine> {
+    /// tell whether the children of this line should be read
+    pub fn can_enter(&self) -> bool {
+        self.kind.is_dir() || self.links_to_dir
+    }
+    /// build the tree line, at the given path. The archive must be
+    /// given when the line comes from an archive
+    pub fn to_tree_line(
+        &self,
+        path: PathBuf,
+        archive: Option<&Archive>,
+    ) -> std::io::Result<TreeLine> {
         let mut has_error = self.has_error;
-        let line_type = if self.file_type.is_dir() {
-            LineType::Dir
-        } else if self.file_type.is_symlink() {
-            if let Ok(target) = fs::read_link(&self.path) {
-                let target = target.to_string_lossy().into_owned();
-                let mut target_path = PathBuf::from(&target);
-                if target_path.is_relative() {
-                    target_path = self.path.parent().unwrap().join(target_path)
-                }
-                if let Ok(target_metadata) = fs::symlink_metadata(&target_path) {
-                    if target_metadata.file_type().is_dir() {
-                        LineType::SymLinkToDir(target)
+        let line_type = match self.kind {
+            BLineKind::Dir | BLineKind::ArchivedDir | BLineKind::RemoteDir => LineType::Dir,
+            BLineKind::File | BLineKind::ArchivedFile | BLineKind::RemoteFile => LineType::File,
+            BLineKind::SymLink => {
+                if let Ok(target) = fs::read_link(&path) {
+                    let target = target.to_string_lossy().into_owned();
+                    let mut target_path = PathBuf::from(&target);
+                    if target_path.is_relative() {
+                        target_path = path.parent().unwrap().join(target_path)
+                    }
+                    if let Ok(target_metadata) = fs::symlink_metadata(&target_path) {
+                        if target_metadata.file_type().is_dir() {
+                            LineType::SymLinkToDir(target)
+                        } else {
+                            LineType::SymLinkToFile(target)
+                        }
                     } else {
+                        has_error = true;
                         LineType::SymLinkToFile(target)
                     }
                 } else {
                     has_error = true;
-                    LineType::SymLinkToFile(target)
+                    LineType::SymLinkToFile(String::from("????"))
                 }
-            } else {
-                has_error = true;
-                LineType::SymLinkToFile(String::from("????"))
             }
-        } else {
-            LineType::File
         };
         let unlisted = if let Some(children) = &self.children {
             // number of not listed children
@@ -103,20 +179,44 @@ impl BLine {
         } else {
             0
         };
-        let metadata = fs::symlink_metadata(&self.path)?;
+        let children_count = self.children.as_ref().map(Vec::len);
+        // the lines of an archive get the metadata of the archive file
+        // (or of the directory of a snapshot), and the sizes found in
+        // the archive. The remote entries were received with their
+        // sizes when listing their directory
+        let (metadata, size) = match (self.kind, archive) {
+            (BLineKind::RemoteDir, _) | (BLineKind::RemoteFile, _) => {
+                let entry = remote::entry(&path)?;
+                let size = Some(FileSize::new(entry.size, false)).filter(|_| !entry.is_dir);
+                (LineMetadata::Remote(entry), size)
+            }
+            (_, Some(archive)) => (
+                LineMetadata::Local(fs::symlink_metadata(&archive.source)?),
+                archive.size_of(&path).map(|s| FileSize::new(s, false)),
+            ),
+            (_, None) => (LineMetadata::Local(fs::symlink_metadata(&path)?), None),
+        };
         Ok(TreeLine {
             left_branchs: vec![false; self.depth as usize].into_boxed_slice(),
             depth: self.depth,
             name: self.name.to_string(),
-            path: self.path.clone(),
+            path,
             line_type,
             has_error,
             nb_kept_children: self.nb_kept_children as usize,
             unlisted,
+            children_count,
             score: self.score,
-            size: None,
+            size,
+            size_is_stale: false,
+            deep_date: ComputationResult::NotComputed,
+            is_loop: self.is_loop,
+            in_archive: archive.is_some(),
+            line_count: ComputationResult::NotComputed,
             metadata,
             git_status: None,
+            ignore_audit: None,
+            change: None,
         })
     }
 }
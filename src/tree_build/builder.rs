@@ -1,9 +1,15 @@
 use {
     crate::{
+        app_context::AppContext,
+        archives::{self, Archive},
+        collation,
         errors::TreeBuildError,
         flat_tree::{Tree, TreeLine},
+        notes,
+        git_ignore::IgnoreAudit,
         git_status::{
             LineStatusComputer,
+            TrackedPaths,
         },
         task_sync::{
             ComputationResult,
@@ -12,6 +18,10 @@ use {
             GitIgnorer,
             GitIgnoreChain,
         },
+        remote,
+        roots,
+        special_paths::{self, SpecialHandling},
+        tags,
         task_sync::Dam,
         tree_options::{
             TreeOptions,
@@ -22,15 +32,20 @@ use {
     },
     id_arena::Arena,
     std::{
-        collections::{BinaryHeap, VecDeque},
-        fs,
-        path::PathBuf,
+        collections::{BinaryHeap, HashSet, VecDeque},
+        fs, io,
+        path::{Path, PathBuf},
         result::Result,
-        time::{Duration, Instant},
+        sync::Arc,
+        time::{Duration, Instant, SystemTime},
     },
     super::{
-        bline::BLine,
+        bline::{BLine, BLineKind},
         bid::{BId, SortableBId},
+        dir_entry::DirEntry,
+        dir_reader::DirReader,
+        interner::NameInterner,
+        walk_cache::WalkCache,
     },
 };
 
@@ -39,12 +54,28 @@ use {
 /// but not after the NOT_LONG duration.
 static NOT_LONG: Duration = Duration::from_millis(900);
 
+/// return the device and inode of the directory, the link
+/// being followed if the path is a symlink.
+/// Those ids are used to detect loops, which may be made by
+/// symlinks or by bind mounts
+#[cfg(unix)]
+fn dir_id(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).ok().map(|md| (md.dev(), md.ino()))
+}
+
+#[cfg(not(unix))]
+fn dir_id(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
+
 /// the result of trying to build a bline
 enum BLineResult {
     Some(BId), // the only positive result
     FilteredOutAsHidden,
     FilteredOutByPattern,
     FilteredOutAsNonFolder,
+    FilteredOutOfRoots,
     GitIgnored,
     Invalid,
 }
@@ -54,26 +85,68 @@ enum BLineResult {
 /// All BLines used during build are stored in the blines arena and kept until the end.
 /// Most operations and temporary data structures just deal with the ids of lines
 ///  the blines arena.
-pub struct TreeBuilder {
+pub struct TreeBuilder<'c> {
     pub options: TreeOptions,
     targeted_size: usize, // the number of lines we should fill (height of the screen)
     nb_gitignored: u32,   // number of times a gitignore pattern excluded a file
+    nb_unreadable: u32,   // number of directories whose reading failed
     blines: Arena<BLine>,
     root_id: BId,
+    root_path: PathBuf, // the paths of the other lines are built from it
+    names: NameInterner,
     total_search: bool,
     git_ignorer: GitIgnorer,
     line_status_computer: Option<LineStatusComputer>,
+    tracked_paths: Option<TrackedPaths>, // only when auditing the ignore rules
+    show_hidden: bool, // true when asked in options or when the pattern looks for dotfiles
+    follow_links: bool, // whether the search enters symlinked directories
+    visited_dirs: HashSet<(u64, u64)>, // (device, inode) of the loaded dirs
+    archive: Option<Arc<Archive>>, // when the tree is the content of an archive
+    remote: bool, // when the tree is on a remote server
+    walk_cache: Option<Arc<WalkCache>>, // directories read by a previous search
+    walk_recorder: Option<WalkCache>, // directories read by this search, for the next one
+    build_time: SystemTime, // when the directories started to be read
+    con: &'c AppContext,
 }
-impl TreeBuilder {
+impl<'c> TreeBuilder<'c> {
     pub fn from(
         path: PathBuf,
-        options: TreeOptions,
+        mut options: TreeOptions,
         targeted_size: usize,
-    ) -> Result<TreeBuilder, TreeBuildError> {
+        con: &'c AppContext,
+    ) -> Result<TreeBuilder<'c>, TreeBuildError> {
+        // the roots restriction only makes sense while they're
+        // below the displayed directory, and not when it's one of them
+        if options.roots.contains(&path) {
+            options.roots.clear();
+        }
+        options.roots.retain(|r| r.starts_with(&path));
+        // the min depth can't be reached when only the first level is
+        // displayed (sizes or sort) or when it's beyond the max depth:
+        // it's ignored instead of producing an empty tree
+        if let Some(min_depth) = options.min_depth {
+            let first_level_only = options.show_sizes || options.sort.is_some();
+            if first_level_only || options.max_depth.map_or(false, |max| max < min_depth) {
+                warn!("min depth {} ignored", min_depth);
+                options.min_depth = None;
+            }
+        }
         let mut blines = Arena::new();
         let mut git_ignorer = time!(Debug, "GitIgnorer::new", GitIgnorer::new());
         let root_ignore_chain = git_ignorer.root_chain(&path);
-        let line_status_computer = if options.filter_by_git_status || options.show_git_file_info {
+        let remote = remote::is_remote(&path);
+        if remote {
+            // we check the server is reachable, so that the user knows why it isn't
+            remote::entry(&path).map_err(|e| TreeBuildError::UnreachableRemote {
+                path: format!("{:?}", path),
+                details: e.to_string(),
+            })?;
+        }
+        // when the statuses are only displayed, they're computed after the
+        // first display of the tree, as it may take long in a big repository
+        let line_status_computer = if remote {
+            None
+        } else if options.filter_by_git_status {
             time!(
                 Debug,
                 "init line_status_computer",
@@ -82,37 +155,100 @@ impl TreeBuilder {
         } else {
             None
         };
+        let archive = match archives::archive_containing(&path) {
+            Some(archive_path) if !remote => Some(archives::open(&archive_path).map_err(|e| {
+                TreeBuildError::UnreadableArchive {
+                    path: format!("{:?}", archive_path),
+                    details: e.to_string(),
+                }
+            })?),
+            _ => None,
+        };
+        let tracked_paths = if options.show_ignore_audit && !remote && archive.is_none() {
+            Repository::discover(&path).ok().and_then(|repo| TrackedPaths::from(&repo))
+        } else {
+            None
+        };
         let root_id = BLine::from_root(
             &mut blines,
-            path,
+            &path,
             root_ignore_chain,
+            archive.as_deref(),
         )?;
+        let show_hidden = options.show_hidden
+            || (con.launch_args.dot_pattern_shows_hidden && options.pattern.starts_with_dot());
+        // links are only followed when searching, and when we can
+        // detect loops with the inodes
+        let follow_links = cfg!(unix)
+            && options.follow_links
+            && options.pattern.is_some();
+        let walk_recorder = if archive.is_none() && !remote {
+            WalkCache::recorder(&options)
+        } else {
+            None
+        };
         Ok(TreeBuilder {
             options,
             targeted_size,
             nb_gitignored: 0,
+            nb_unreadable: 0,
             blines,
             root_id,
+            root_path: path,
+            names: NameInterner::default(),
             total_search: true, // we'll set it to false if we don't look at all children
             git_ignorer,
             line_status_computer,
+            tracked_paths,
+            show_hidden,
+            follow_links,
+            visited_dirs: HashSet::new(),
+            archive,
+            remote,
+            walk_cache: None,
+            walk_recorder,
+            build_time: SystemTime::now(),
+            con,
         })
     }
+
+    /// read the directories from the cache of a previous search
+    /// instead of the disk, when that search had a pattern this
+    /// one extends
+    pub fn reuse_walk(&mut self, walk_cache: Option<Arc<WalkCache>>) {
+        self.walk_cache = walk_cache.filter(|wc| self.is_local() && wc.can_serve(&self.options));
+    }
     /// return a bline if the dir_entry directly matches the options and there's no error
     fn make_line(
         &mut self,
         parent_id: BId,
-        e: fs::DirEntry,
+        parent_path: &Path,
+        e: DirEntry,
         depth: u16,
     ) -> BLineResult {
-        let name = e.file_name();
-        let name = match name.to_str() {
-            Some(name) => name,
-            None => {
-                return BLineResult::Invalid;
+        let (name, path) = match &e {
+            DirEntry::Fs(e) => match e.file_name().into_string() {
+                Ok(name) => (name, e.path()),
+                Err(_) => {
+                    return BLineResult::Invalid;
+                }
+            },
+            DirEntry::Archived(e) => {
+                let name = e.name().to_string();
+                let path = parent_path.join(&name);
+                (name, path)
             }
+            DirEntry::Remote(e) => (e.name.clone(), parent_path.join(&e.name)),
+            DirEntry::Walked(e) => (e.name.clone(), parent_path.join(&e.name)),
         };
-        if !self.options.show_hidden && name.starts_with('.') {
+        let special_handling = special_paths::handling(&self.con.special_paths, &path);
+        if special_handling == SpecialHandling::Hide {
+            return BLineResult::FilteredOutAsHidden;
+        }
+        if !self.show_hidden
+            && name.starts_with('.')
+            && special_handling != SpecialHandling::Show
+        {
             return BLineResult::FilteredOutAsHidden;
         }
         let mut has_match = true;
@@ -124,7 +260,9 @@ impl TreeBuilder {
                 has_match = false;
             }
         }
-        let path = e.path();
+        if !roots::is_on_roots_path(&path, &self.options.roots) {
+            return BLineResult::FilteredOutOfRoots;
+        }
         if has_match && self.options.filter_by_git_status {
             if let Some(line_status_computer) = &self.line_status_computer {
                 if !line_status_computer.is_interesting(&path) {
@@ -132,13 +270,58 @@ impl TreeBuilder {
                 }
             }
         }
-        let file_type = match e.file_type() {
-            Ok(ft) => ft,
-            Err(_) => {
-                return BLineResult::Invalid;
+        let (kind, links_to_dir) = match &e {
+            DirEntry::Fs(e) => match e.file_type() {
+                Ok(file_type) => self.local_kind(file_type, &path),
+                Err(_) => {
+                    return BLineResult::Invalid;
+                }
+            },
+            DirEntry::Walked(e) => self.local_kind(e.file_type, &path),
+            DirEntry::Archived(e) => {
+                let kind = if e.is_dir {
+                    BLineKind::ArchivedDir
+                } else {
+                    BLineKind::ArchivedFile
+                };
+                (kind, false)
+            }
+            DirEntry::Remote(e) => {
+                let kind = if e.is_dir {
+                    BLineKind::RemoteDir
+                } else {
+                    BLineKind::RemoteFile
+                };
+                (kind, false)
             }
         };
-        if file_type.is_file() || file_type.is_symlink() {
+        let is_dir = kind.is_dir() || links_to_dir;
+        if has_match && self.options.ext_filter.is_some() {
+            // directories are only kept when they contain matching files
+            has_match = !is_dir && self.options.ext_filter.accepts(&name);
+        }
+        if has_match && self.options.min_depth.map_or(false, |min| depth < min) {
+            // directories are only kept when they contain deep enough entries
+            has_match = false;
+        }
+        if has_match && self.options.date_filter.is_some() {
+            // directories are only kept when they contain matching files
+            has_match = !is_dir
+                && e.modified()
+                    .map_or(false, |date| self.options.date_filter.accepts(date));
+        }
+        if has_match {
+            if let Some(note_filter) = &self.options.note_filter {
+                // an annotated directory is a match too
+                has_match = notes::is_matched(&path, note_filter);
+            }
+        }
+        if has_match {
+            if let Some(tag_filter) = self.options.tag_filter {
+                has_match = tags::has_tag(&path, tag_filter);
+            }
+        }
+        if !is_dir {
             if !has_match {
                 return BLineResult::FilteredOutByPattern;
             }
@@ -146,12 +329,22 @@ impl TreeBuilder {
                 return BLineResult::FilteredOutAsNonFolder;
             }
         }
-        let git_ignore_chain = if self.options.respect_git_ignore {
-            let parent_chain = &self.blines[parent_id].git_ignore_chain;
-            if !self.git_ignorer.accepts(parent_chain, &path, &name, file_type.is_dir()) {
-                return BLineResult::GitIgnored;
+        // the .gitignore files of archives and remote trees aren't read
+        let audit = self.options.show_ignore_audit;
+        let mut ignored_by = None;
+        let git_ignore_chain = if (self.options.respect_git_ignore || audit) && self.is_local() {
+            let parent = &self.blines[parent_id];
+            let parent_chain = &parent.git_ignore_chain;
+            if special_handling != SpecialHandling::Show {
+                // the entries of an ignored directory are ignored too
+                ignored_by = parent.ignored_by.clone().or_else(|| {
+                    self.git_ignorer.ignoring_rule(parent_chain, &path, &name, is_dir)
+                });
+                if ignored_by.is_some() && !audit {
+                    return BLineResult::GitIgnored;
+                }
             }
-            if file_type.is_dir() {
+            if is_dir {
                 self.git_ignorer.deeper_chain(parent_chain, &path)
             } else {
                 parent_chain.clone()
@@ -159,12 +352,15 @@ impl TreeBuilder {
         } else {
             GitIgnoreChain::default()
         };
+        let name = self.names.intern(&name);
         BLineResult::Some(self.blines.alloc(BLine {
             parent_id: Some(parent_id),
-            path,
             depth,
-            name: name.to_string(),
-            file_type,
+            name,
+            kind,
+            links_to_dir,
+            dir_id: None,
+            is_loop: false,
             children: None,
             next_child_idx: 0,
             has_error: false,
@@ -172,46 +368,128 @@ impl TreeBuilder {
             score,
             nb_kept_children: 0,
             git_ignore_chain,
+            ignored_by,
         }))
     }
 
-    /// returns true when there are direct matches among children
-    fn load_children(&mut self, bid: BId) -> bool {
+    /// return the kind of a line on the local file system, and whether
+    /// it's a symlink to a directory the search must enter
+    fn local_kind(&self, file_type: fs::FileType, path: &Path) -> (BLineKind, bool) {
+        let links_to_dir = self.follow_links
+            && file_type.is_symlink()
+            && fs::metadata(path).map_or(false, |md| md.is_dir());
+        (BLineKind::from(file_type), links_to_dir)
+    }
+
+    /// tell whether the tree is on the local file system, and
+    /// not in an archive or on a remote server
+    fn is_local(&self) -> bool {
+        self.archive.is_none() && !self.remote
+    }
+
+    /// build the path of the line from the names of its ancestors
+    fn path_of(&self, bid: BId) -> PathBuf {
+        let mut names = Vec::new();
+        let mut id = bid;
+        while let Some(parent_id) = self.blines[id].parent_id {
+            names.push(&*self.blines[id].name);
+            id = parent_id;
+        }
+        let mut path = self.root_path.clone();
+        for name in names.iter().rev() {
+            path.push(name);
+        }
+        path
+    }
+
+    /// tell whether one of the ancestors of the line is the
+    /// directory with this id
+    fn is_ancestor_dir(&self, bid: BId, dir_id: (u64, u64)) -> bool {
+        let mut parent_id = self.blines[bid].parent_id;
+        while let Some(id) = parent_id {
+            if self.blines[id].dir_id == Some(dir_id) {
+                return true;
+            }
+            parent_id = self.blines[id].parent_id;
+        }
+        false
+    }
+
+    /// check whether the children of the directory must be read and, if
+    /// not, set them as empty
+    fn must_read_children(&mut self, bid: BId) -> bool {
+        if !self.is_local() {
+            return true; // there's no special path nor loop in an archive or remote tree
+        }
+        let path = self.path_of(bid);
+        if bid != self.root_id {
+            let no_enter = special_paths::handling(&self.con.special_paths, &path)
+                == SpecialHandling::NoEnter
+                || special_paths::handling(&self.options.prune, &path) == SpecialHandling::NoEnter;
+            if no_enter {
+                debug!("not entering {:?}", path);
+                self.blines[bid].children = Some(Vec::new());
+                return false;
+            }
+        }
+        if let Some(dir_id) = dir_id(&path) {
+            if self.is_ancestor_dir(bid, dir_id) {
+                debug!("loop on {:?}", &path);
+                self.blines[bid].is_loop = true;
+                self.blines[bid].children = Some(Vec::new());
+                return false;
+            }
+            self.blines[bid].dir_id = Some(dir_id);
+            // a symlinked directory is entered only if its target wasn't
+            // already read, so that the same files aren't searched twice
+            if !self.visited_dirs.insert(dir_id) && self.blines[bid].links_to_dir {
+                debug!("not entering {:?} again", &path);
+                self.blines[bid].children = Some(Vec::new());
+                return false;
+            }
+        }
+        true
+    }
+
+    /// make the children lines from the entries of the directory.
+    /// Returns true when there are direct matches among children
+    fn set_children(&mut self, bid: BId, entries: io::Result<Vec<DirEntry>>) -> bool {
         let mut has_child_match = false;
-        match fs::read_dir(&self.blines[bid].path) {
+        match entries {
             Ok(entries) => {
                 let mut children: Vec<BId> = Vec::new();
                 let child_depth = self.blines[bid].depth + 1;
+                let path = self.path_of(bid);
+                if let Some(walk_recorder) = &mut self.walk_recorder {
+                    walk_recorder.record(path.clone(), &entries);
+                }
                 for e in entries {
-                    if let Ok(e) = e {
-                        let bl = self.make_line(bid, e, child_depth);
-                        match bl {
-                            BLineResult::Some(child_id) => {
-                                if self.blines[child_id].has_match {
-                                    // direct match
-                                    self.blines[bid].has_match = true;
-                                    has_child_match = true;
-                                }
-                                children.push(child_id);
-                            }
-                            BLineResult::GitIgnored => {
-                                self.nb_gitignored += 1;
-                            }
-                            _ => {
-                                // other reason, we don't care
+                    let bl = self.make_line(bid, &path, e, child_depth);
+                    match bl {
+                        BLineResult::Some(child_id) => {
+                            if self.blines[child_id].has_match {
+                                // direct match
+                                self.blines[bid].has_match = true;
+                                has_child_match = true;
                             }
+                            children.push(child_id);
+                        }
+                        BLineResult::GitIgnored => {
+                            self.nb_gitignored += 1;
+                        }
+                        _ => {
+                            // other reason, we don't care
                         }
                     }
                 }
                 children.sort_by(|&a, &b| {
-                    self.blines[a]
-                        .name
-                        .to_lowercase()
-                        .cmp(&self.blines[b].name.to_lowercase())
+                    collation::compare_names(&self.blines[a].name, &self.blines[b].name)
                 });
                 self.blines[bid].children = Some(children);
             }
-            Err(_err) => {
+            Err(err) => {
+                debug!("can't read {:?}: {:?}", self.path_of(bid), err);
+                self.nb_unreadable += 1;
                 self.blines[bid].has_error = true;
                 self.blines[bid].children = Some(Vec::new());
             }
@@ -219,12 +497,48 @@ impl TreeBuilder {
         has_child_match
     }
 
-    /// return the next child.
+    /// return the entries of the directory if they're in the cache of a previous search
+    fn walked_children(&self, bid: BId) -> Option<Vec<DirEntry>> {
+        self.walk_cache.as_ref()?.entries(&self.path_of(bid))
+    }
+
+    /// read the entries of the directory, on disk, in the archive, or on the server
+    fn read_children(&self, bid: BId) -> io::Result<Vec<DirEntry>> {
+        if let Some(entries) = self.walked_children(bid) {
+            return Ok(entries);
+        }
+        let path = self.path_of(bid);
+        if self.remote {
+            return remote::read_dir(&path)
+                .map(|entries| entries.into_iter().map(DirEntry::Remote).collect());
+        }
+        match &self.archive {
+            Some(archive) => archive
+                .children(&path)
+                .map(|entries| entries.into_iter().map(DirEntry::Archived).collect()),
+            None => fs::read_dir(&path)
+                .map(|entries| entries.filter_map(Result::ok).map(DirEntry::Fs).collect()),
+        }
+    }
+
+    /// read the directory in the current thread.
+    /// Returns true when there are direct matches among children
+    fn load_children(&mut self, bid: BId) -> bool {
+        if !self.must_read_children(bid) {
+            return false;
+        }
+        let entries = self.read_children(bid);
+        self.set_children(bid, entries)
+    }
+
+    /// return the next child, unless the max number of children
+    /// of a directory was reached.
     /// load_children must have been called before on parent_id
     fn next_child(&mut self, parent_id: BId) -> Option<BId> {
+        let max_children = self.options.max_children.unwrap_or(usize::max_value());
         let bline = &mut self.blines[parent_id];
         if let Some(children) = &bline.children {
-            if bline.next_child_idx < children.len() {
+            if bline.next_child_idx < children.len() && bline.next_child_idx < max_children {
                 let next_child = children[bline.next_child_idx];
                 bline.next_child_idx += 1;
                 Some(next_child)
@@ -254,6 +568,7 @@ impl TreeBuilder {
         let mut nb_lines_ok = 1; // in out_blines
         let mut open_dirs: VecDeque<BId> = VecDeque::new();
         let mut next_level_dirs: Vec<BId> = Vec::new();
+        let mut dir_reader = DirReader::new(dam);
         self.load_children(self.root_id);
         open_dirs.push_back(self.root_id);
         loop {
@@ -274,29 +589,56 @@ impl TreeBuilder {
                     if child.has_match {
                         nb_lines_ok += 1;
                     }
-                    if child.file_type.is_dir() {
+                    let too_deep = self.options.max_depth.map_or(false, |max| child.depth >= max);
+                    if child.can_enter() && !too_deep {
                         next_level_dirs.push(child_id);
                     }
                     out_blines.push(child_id);
                 }
             } else {
                 // this depth is finished, we must go deeper
-                if self.options.show_sizes {
+                if self.options.show_sizes || self.options.sort.is_some() {
                     // both for technical reasons (bad sort) and ergonomics
                     //  ones (it proved to be hard to read), we don't want
-                    //  a deep tree when looking at sizes.
+                    //  a deep tree when looking at sizes or when sorting.
                     break;
                 }
                 if next_level_dirs.is_empty() {
                     // except there's nothing deeper
                     break;
                 }
+                // the directories of the level are read in parallel, then
+                // their entries are handled in order. There's no need for
+                // threads when reading in an archive, as it's in memory, nor
+                // on a remote server, as there's only one connection
+                for next_level_dir_id in &next_level_dirs {
+                    if self.is_local() && self.must_read_children(*next_level_dir_id) {
+                        let path = self.path_of(*next_level_dir_id);
+                        if !self.walk_cache.as_ref().map_or(false, |wc| wc.contains(&path)) {
+                            dir_reader.read(*next_level_dir_id, path);
+                        }
+                    }
+                }
                 for next_level_dir_id in &next_level_dirs {
                     if dam.has_event() {
                         info!("task expired (core build - inner loop)");
                         return None;
                     }
-                    let has_child_match = self.load_children(*next_level_dir_id);
+                    let has_child_match = if !self.is_local() {
+                        self.load_children(*next_level_dir_id)
+                    } else if self.blines[*next_level_dir_id].children.is_some() {
+                        false // not to be read
+                    } else if let Some(entries) = self.walked_children(*next_level_dir_id) {
+                        self.set_children(*next_level_dir_id, Ok(entries))
+                    } else {
+                        match dir_reader.take(*next_level_dir_id, dam) {
+                            Some(entries) => self.set_children(*next_level_dir_id, entries),
+                            None => {
+                                info!("task expired (core build - directory reading)");
+                                return None;
+                            }
+                        }
+                    };
                     if has_child_match {
                         // we must ensure the ancestors are made Ok
                         let mut id = *next_level_dir_id;
@@ -318,7 +660,7 @@ impl TreeBuilder {
                 next_level_dirs.clear();
             }
         }
-        if self.options.show_sizes || !self.options.trim_root {
+        if self.options.show_sizes || self.options.sort.is_some() || !self.options.trim_root {
             // if the root directory isn't totally read, we finished it even
             // it it goes past the bottom of the screen
             while let Some(child_id) = self.next_child(self.root_id) {
@@ -335,7 +677,9 @@ impl TreeBuilder {
     ///  removing a parent before its children.
     fn trim_excess(&mut self, out_blines: &[BId]) {
         let mut count = 1;
-        let trim_root = self.options.trim_root && !self.options.show_sizes;
+        let trim_root = self.options.trim_root
+            && !self.options.show_sizes
+            && !self.options.sort.is_some();
         for id in out_blines[1..].iter() {
             if self.blines[*id].has_match {
                 //debug!("bline before trimming: {:?}", &self.blines[*idx].path);
@@ -390,17 +734,30 @@ impl TreeBuilder {
         for id in out_blines.iter() {
             if self.blines[*id].has_match {
                 // we need to count the children, so we load them
-                if self.blines[*id].file_type.is_dir() && self.blines[*id].children.is_none() {
+                if self.blines[*id].can_enter() && self.blines[*id].children.is_none() {
                     self.load_children(*id);
                 }
-                if let Ok(tree_line) = self.blines[*id].to_tree_line() {
-                    lines.push(tree_line);
-                } else {
-                    // I guess the file went missing during tree computation
-                    warn!(
-                        "Error while builind treeline for {:?}",
-                        self.blines[*id].path,
-                    );
+                let path = self.path_of(*id);
+                match self.blines[*id].to_tree_line(path, self.archive.as_deref()) {
+                    Ok(mut tree_line) => {
+                        if let Some(tracked_paths) = &self.tracked_paths {
+                            // the index comes first: git keeps tracking the
+                            // files added before an ignore rule matched them
+                            let ignored_by = &self.blines[*id].ignored_by;
+                            tree_line.ignore_audit = Some(match ignored_by {
+                                _ if tracked_paths.contains(&tree_line.path) => {
+                                    IgnoreAudit::Tracked(ignored_by.clone())
+                                }
+                                Some(rule) => IgnoreAudit::Ignored(Arc::clone(rule)),
+                                None => IgnoreAudit::Untracked,
+                            });
+                        }
+                        lines.push(tree_line);
+                    }
+                    Err(e) => {
+                        // I guess the file went missing during tree computation
+                        warn!("Error while building treeline for {:?}: {:?}", self.path_of(*id), e);
+                    }
                 }
             }
         }
@@ -410,12 +767,25 @@ impl TreeBuilder {
             options: self.options.clone(),
             scroll: 0,
             nb_gitignored: self.nb_gitignored,
+            nb_unreadable: self.nb_unreadable,
+            sizes_interrupted: false,
+            deep_dates_interrupted: false,
+            ancestor_sizes: Vec::new(),
             total_search: self.total_search,
             git_status: ComputationResult::None,
+            walk: self.walk_recorder.take().map(Arc::new),
+            build_time: self.build_time,
+            removed: Vec::new(),
+            line_git_statuses_pending: false,
         };
         tree.after_lines_changed();
         if self.options.show_sizes {
-            time!(Debug, "fetch_file_sizes", tree.fetch_file_sizes()); // not the dirs, only simple files
+            // the sizes of the files are fetched after the first display
+            tree.fetch_ancestor_sizes();
+        }
+        if self.line_status_computer.is_none() && self.options.show_git_file_info && !self.remote {
+            tree.git_status = ComputationResult::NotComputed;
+            tree.line_git_statuses_pending = true;
         }
         if let Some(computer) = self.line_status_computer {
             // tree git status is slow to compute, we just mark it should be
@@ -0,0 +1,29 @@
+use {
+    crate::{archives::ArchivedEntry, remote::RemoteEntry},
+    std::{fs, time::SystemTime},
+    super::walk_cache::WalkedEntry,
+};
+
+/// an entry of a directory, which is either on disk, in an
+/// archive, on a remote server, or remembered from a previous search.
+///
+/// The builder makes lines from those entries, so that it builds the
+/// tree of an archive the same way it builds the tree of a directory.
+pub enum DirEntry {
+    Fs(fs::DirEntry),
+    Archived(ArchivedEntry),
+    Remote(RemoteEntry),
+    Walked(WalkedEntry),
+}
+
+impl DirEntry {
+    /// return the last modification date, if it's known
+    pub fn modified(&self) -> Option<SystemTime> {
+        match self {
+            DirEntry::Fs(e) => e.metadata().and_then(|m| m.modified()).ok(),
+            DirEntry::Archived(e) => e.modified,
+            DirEntry::Remote(e) => e.modified,
+            DirEntry::Walked(_) => None,
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use {
+    crate::{
+        resource_limits,
+        task_sync::Dam,
+    },
+    crossbeam::channel::{unbounded, Receiver, RecvTimeoutError, Sender},
+    std::{
+        collections::HashMap,
+        io,
+        path::PathBuf,
+        thread,
+        time::Duration,
+    },
+    super::{
+        bid::BId,
+        dir_entry::DirEntry,
+    },
+};
+
+/// how long the waiting for a directory lasts before the dam is checked again
+const DAM_CHECK_PERIOD: Duration = Duration::from_millis(10);
+
+type DirContent = io::Result<Vec<DirEntry>>;
+
+/// A pool of threads reading directories in parallel, so that the
+/// tree builder doesn't wait for the disk one directory at a time.
+///
+/// Directories are given in batches (usually a whole level of the tree)
+/// and their entries are then taken in the order the builder wants.
+/// The threads stop when the reader is dropped, or when the lack of
+/// file descriptors reduced the parallelism (see resource_limits).
+pub struct DirReader {
+    jobs: Sender<(BId, PathBuf)>,
+    results: Receiver<(BId, DirContent)>,
+    received: HashMap<BId, DirContent>, // results which came before being asked
+}
+
+impl DirReader {
+    pub fn new(dam: &Dam) -> Self {
+        let (jobs, jobs_receiver) = unbounded::<(BId, PathBuf)>();
+        let (results_sender, results) = unbounded();
+        for worker_idx in 0..resource_limits::parallelism() {
+            let jobs_receiver = jobs_receiver.clone();
+            let results_sender = results_sender.clone();
+            let observer = dam.observer();
+            thread::spawn(move || {
+                // the loop ends when the DirReader is dropped
+                for (bid, path) in jobs_receiver.iter() {
+                    if observer.has_event() {
+                        break; // the builder won't wait for the result
+                    }
+                    let content = resource_limits::read_dir(&path)
+                        .map(|entries| entries.filter_map(Result::ok).map(DirEntry::Fs).collect());
+                    if results_sender.send((bid, content)).is_err() {
+                        break;
+                    }
+                    if worker_idx >= resource_limits::parallelism() {
+                        break; // the other workers take the remaining jobs
+                    }
+                }
+            });
+        }
+        Self {
+            jobs,
+            results,
+            received: HashMap::new(),
+        }
+    }
+
+    /// ask for the reading of a directory
+    pub fn read(&self, bid: BId, path: PathBuf) {
+        if self.jobs.send((bid, path)).is_err() {
+            warn!("no thread to read directories");
+        }
+    }
+
+    /// wait for the entries of a directory which was given to `read`.
+    ///
+    /// Return None if the dam received an event before the end.
+    pub fn take(&mut self, bid: BId, dam: &Dam) -> Option<DirContent> {
+        loop {
+            if let Some(content) = self.received.remove(&bid) {
+                return Some(content);
+            }
+            if dam.has_event() {
+                return None;
+            }
+            match self.results.recv_timeout(DAM_CHECK_PERIOD) {
+                Ok((received_bid, content)) => {
+                    self.received.insert(received_bid, content);
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    return None;
+                }
+            }
+        }
+    }
+}
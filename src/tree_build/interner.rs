@@ -0,0 +1,21 @@
+use std::{collections::HashSet, rc::Rc};
+
+/// a set of the names of the lines built, so that a name found many
+/// times in a big tree (eg `src`, `mod.rs`, `node_modules`) is only
+/// stored once
+#[derive(Default)]
+pub struct NameInterner {
+    names: HashSet<Rc<str>>,
+}
+
+impl NameInterner {
+    /// return the shared copy of the name
+    pub fn intern(&mut self, name: &str) -> Rc<str> {
+        if let Some(interned) = self.names.get(name) {
+            return Rc::clone(interned);
+        }
+        let interned: Rc<str> = Rc::from(name);
+        self.names.insert(Rc::clone(&interned));
+        interned
+    }
+}
@@ -3,5 +3,12 @@
 mod bline;
 mod bid;
 mod builder;
+mod dir_entry;
+mod dir_reader;
+mod interner;
+mod walk_cache;
 
-pub use builder::TreeBuilder;
+pub use {
+    builder::TreeBuilder,
+    walk_cache::WalkCache,
+};
@@ -0,0 +1,85 @@
+use {
+    crate::{fuzzy_patterns::FuzzyPattern, patterns::Pattern, tree_options::TreeOptions},
+    std::{
+        collections::HashMap,
+        fs,
+        path::{Path, PathBuf},
+    },
+    super::dir_entry::DirEntry,
+};
+
+/// an entry of a directory, as read during a previous search
+#[derive(Debug, Clone)]
+pub struct WalkedEntry {
+    pub name: String,
+    pub file_type: fs::FileType,
+}
+
+/// the entries of the directories read during a fuzzy search, so
+/// that a search with a longer pattern doesn't read them again.
+///
+/// Only the directories and the entries which may be matched by
+/// a pattern extending the one of the search are kept, before any
+/// other filtering, so the cache doesn't depend on the other options.
+/// A directory which isn't in the cache is read from the disk.
+#[derive(Debug)]
+pub struct WalkCache {
+    pattern: FuzzyPattern,
+    dirs: HashMap<PathBuf, Vec<WalkedEntry>>,
+}
+
+/// return the fuzzy pattern of the options when a walk with those
+/// options may be recorded or replayed
+fn walkable_pattern(options: &TreeOptions) -> Option<&FuzzyPattern> {
+    match &options.pattern {
+        // the walked entries don't have their dates
+        Pattern::Fuzzy(fp) if !options.date_filter.is_some() => Some(fp),
+        _ => None,
+    }
+}
+
+impl WalkCache {
+    /// create an empty cache, to be filled during a search, if
+    /// a search with a longer pattern could use it
+    pub fn recorder(options: &TreeOptions) -> Option<Self> {
+        walkable_pattern(options).map(|pattern| Self {
+            pattern: pattern.clone(),
+            dirs: HashMap::new(),
+        })
+    }
+
+    /// tell whether a search with those options may read
+    /// the directories from this cache
+    pub fn can_serve(&self, options: &TreeOptions) -> bool {
+        walkable_pattern(options).map_or(false, |fp| self.pattern.is_extended_by(fp))
+    }
+
+    /// remember the useful entries of a directory
+    pub fn record(&mut self, dir: PathBuf, entries: &[DirEntry]) {
+        let pattern = &self.pattern;
+        let kept = entries
+            .iter()
+            .filter_map(|e| match e {
+                DirEntry::Fs(e) => Some(WalkedEntry {
+                    name: e.file_name().into_string().ok()?,
+                    file_type: e.file_type().ok()?,
+                }),
+                DirEntry::Walked(e) => Some(e.clone()),
+                _ => None,
+            })
+            .filter(|e| !e.file_type.is_file() || pattern.may_match(&e.name))
+            .collect();
+        self.dirs.insert(dir, kept);
+    }
+
+    pub fn contains(&self, dir: &Path) -> bool {
+        self.dirs.contains_key(dir)
+    }
+
+    /// return the entries of the directory, if it was read
+    pub fn entries(&self, dir: &Path) -> Option<Vec<DirEntry>> {
+        self.dirs
+            .get(dir)
+            .map(|entries| entries.iter().cloned().map(DirEntry::Walked).collect())
+    }
+}
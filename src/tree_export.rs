@@ -0,0 +1,131 @@
+//! export of a tree, in a format meant for scripts rather than humans,
+//! when broot is run without interface (see the --out-format launch argument)
+
+use {
+    crate::flat_tree::{LineType, Tree, TreeLine},
+    std::{fmt::Write, time::UNIX_EPOCH},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutFormat {
+    Json,
+    Paths,
+}
+
+impl OutFormat {
+    pub fn from_name(name: &str) -> Option<OutFormat> {
+        match name {
+            "json" => Some(OutFormat::Json),
+            "paths" => Some(OutFormat::Paths),
+            _ => None,
+        }
+    }
+}
+
+/// write the tree in the required format.
+///
+/// With `Paths` there's one path per line, the selected one first,
+/// then all the listed files and directories in tree order.
+pub fn export(tree: &Tree, format: OutFormat) -> String {
+    match format {
+        OutFormat::Paths => paths(tree),
+        OutFormat::Json => json(tree),
+    }
+}
+
+fn paths(tree: &Tree) -> String {
+    let mut s = String::new();
+    s.push_str(&tree.selected_line().path.to_string_lossy());
+    for line in tree.lines.iter().filter(|l| l.is_selectable()) {
+        s.push('\n');
+        s.push_str(&line.path.to_string_lossy());
+    }
+    s
+}
+
+fn line_type_name(line: &TreeLine) -> &'static str {
+    match &line.line_type {
+        LineType::File => "file",
+        LineType::Dir => "dir",
+        LineType::SymLinkToDir(_) => "link_to_dir",
+        LineType::SymLinkToFile(_) => "link_to_file",
+        LineType::Pruning => "pruning",
+    }
+}
+
+fn json(tree: &Tree) -> String {
+    let mut s = String::new();
+    s.push_str("{\n");
+    let _ = writeln!(s, "  \"root\": {},", json_string(&tree.root().to_string_lossy()));
+    let _ = writeln!(
+        s,
+        "  \"selection\": {},",
+        json_string(&tree.selected_line().path.to_string_lossy()),
+    );
+    s.push_str("  \"lines\": [");
+    for (idx, line) in tree.lines.iter().filter(|l| l.is_selectable()).enumerate() {
+        if idx > 0 {
+            s.push(',');
+        }
+        let _ = write!(
+            s,
+            "\n    {{\"path\": {}, \"name\": {}, \"depth\": {}, \"type\": \"{}\"",
+            json_string(&line.path.to_string_lossy()),
+            json_string(&line.name),
+            line.depth,
+            line_type_name(line),
+        );
+        if let Some(size) = line.size {
+            let size: u64 = size.into();
+            let _ = write!(s, ", \"size\": {}", size);
+        }
+        if let Some(seconds) = line
+            .modified()
+            .and_then(|date| date.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+        {
+            let _ = write!(s, ", \"modified\": {}", seconds);
+        }
+        if line.unlisted > 0 {
+            let _ = write!(s, ", \"unlisted\": {}", line.unlisted);
+        }
+        s.push('}');
+    }
+    s.push_str("\n  ]\n}");
+    s
+}
+
+/// quote and escape a string so that it's a valid JSON string
+pub fn json_string(raw: &str) -> String {
+    let mut s = String::with_capacity(raw.len() + 2);
+    s.push('"');
+    for c in raw.chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            '\n' => s.push_str("\\n"),
+            '\r' => s.push_str("\\r"),
+            '\t' => s.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(s, "\\u{:04x}", c as u32);
+            }
+            c => s.push(c),
+        }
+    }
+    s.push('"');
+    s
+}
+
+#[cfg(test)]
+mod tree_export_tests {
+
+    use super::json_string;
+
+    #[test]
+    fn check_json_string() {
+        assert_eq!(json_string("src/main.rs"), r#""src/main.rs""#);
+        assert_eq!(json_string(r#"a "b" c\d"#), r#""a \"b\" c\\d""#);
+        assert_eq!(json_string("a\nb\u{1}"), r#""a\nb\u0001""#);
+        assert_eq!(json_string("été"), "\"été\"");
+    }
+}
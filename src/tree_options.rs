@@ -3,8 +3,17 @@ use {
         ArgMatches,
     },
     crate::{
+        columns::{self, Col},
+        date_filter::DateFilter,
+        date_source::DateSource,
+        ext_filter::ExtFilter,
         patterns::Pattern,
+        sort::Sort,
+        special_paths::{self, SpecialPath},
+        tags::TagColor,
+        tie_breaker::TieBreaker,
     },
+    std::path::PathBuf,
 };
 
 #[derive(Debug, Clone)]
@@ -13,12 +22,36 @@ pub struct TreeOptions {
     pub only_folders: bool, // whether to hide normal files and links
     pub show_sizes: bool,  // whether to compute and show sizes of files and dirs
     pub show_dates: bool,  // whether to show the last modified date
+    pub show_line_counts: bool, // whether to show the number of lines of text files
+    pub show_freshness: bool, // whether names of recently modified files are tinted
+    pub show_icons: bool, // whether an icon is written before the names
+    pub date_source: DateSource, // which date is displayed and used for sorting
     pub show_git_file_info: bool,
+    pub show_git_dir_stats: bool, // whether directories tell how many descendants have each git status
     pub trim_root: bool,   // whether to cut out direct children of root
     pub show_permissions: bool, // show classic rwx unix permissions
     pub respect_git_ignore: bool, // hide files as requested by .gitignore ?
+    pub show_ignore_audit: bool, // show all entries, telling whether git tracks or ignores them
+    pub follow_links: bool, // whether searches enter symlinked directories
     pub filter_by_git_status: bool, // only show files whose git status is not nul
+    pub date_filter: DateFilter, // only show files whose last modification date matches
+    pub ext_filter: ExtFilter, // only show files having one of those extensions
+    pub note_filter: Option<String>, // only show entries whose note contains it (* for any)
+    pub tag_filter: Option<TagColor>, // only show entries having this tag
     pub pattern: Pattern,  // an optional filtering/scoring pattern
+    pub roots: Vec<PathBuf>, // when not empty, only those paths (and their ancestors) are displayed
+    pub max_depth: Option<u16>, // directories deeper than this aren't entered
+    pub min_depth: Option<u16>, // entries less deep than this are only shown as ancestors
+    pub prune: Vec<SpecialPath>, // directories which are shown but never entered
+    pub max_children: Option<usize>, // the other children of a directory are left unlisted
+    pub sort: Sort,
+    pub tie_breaker: TieBreaker, // which line is selected among the best matches of a search
+    pub special_sort: bool, // whether the sort comes from a special path, and ends when leaving it
+    pub special_profile: Option<String>, // the profile applied by a special path, until leaving it
+    pub cols_order: Vec<Col>, // the displayed columns, in display order, the name being the last one
+    pub cols_priority: Vec<Col>, // the most important columns first, the last ones are hidden first
+    pub align_size_units: bool, // whether units of sizes are in their own column
+    pub watch: bool, // whether the tree is refreshed when files are created, removed or renamed
 }
 
 impl TreeOptions {
@@ -28,12 +61,36 @@ impl TreeOptions {
             only_folders: self.only_folders,
             show_sizes: self.show_sizes,
             show_dates: self.show_dates,
+            show_line_counts: self.show_line_counts,
+            show_freshness: self.show_freshness,
+            show_icons: self.show_icons,
+            date_source: self.date_source,
             show_permissions: self.show_permissions,
             respect_git_ignore: self.respect_git_ignore,
+            show_ignore_audit: self.show_ignore_audit,
+            follow_links: self.follow_links,
             filter_by_git_status: self.filter_by_git_status,
+            date_filter: self.date_filter,
+            ext_filter: self.ext_filter.clone(),
+            note_filter: self.note_filter.clone(),
+            tag_filter: self.tag_filter,
             show_git_file_info: self.show_git_file_info,
+            show_git_dir_stats: self.show_git_dir_stats,
             trim_root: self.trim_root,
             pattern: Pattern::None,
+            roots: self.roots.clone(),
+            max_depth: self.max_depth,
+            min_depth: self.min_depth,
+            prune: self.prune.clone(),
+            max_children: self.max_children,
+            sort: self.sort,
+            tie_breaker: self.tie_breaker,
+            special_sort: self.special_sort,
+            special_profile: self.special_profile.clone(),
+            cols_order: self.cols_order.clone(),
+            cols_priority: self.cols_priority.clone(),
+            align_size_units: self.align_size_units,
+            watch: self.watch,
         }
     }
     pub fn apply(&mut self, cli_args: &ArgMatches<'_>) {
@@ -61,6 +118,17 @@ impl TreeOptions {
         } else if cli_args.is_present("no-dates") {
             self.show_dates = false;
         }
+        if cli_args.is_present("freshness") {
+            self.show_freshness = true;
+        }
+        if cli_args.is_present("icons") {
+            self.show_icons = true;
+        } else if cli_args.is_present("no-icons") {
+            self.show_icons = false;
+        }
+        if cli_args.is_present("line-counts") {
+            self.show_line_counts = true;
+        }
         if cli_args.is_present("permissions") {
             self.show_permissions = true;
         } else if cli_args.is_present("no-permissions") {
@@ -71,11 +139,49 @@ impl TreeOptions {
         } else if cli_args.is_present("no-show-gitignored") {
             self.respect_git_ignore = true;
         }
+        if cli_args.is_present("follow-links") {
+            self.follow_links = true;
+        } else if cli_args.is_present("no-follow-links") {
+            self.follow_links = false;
+        }
         if cli_args.is_present("show-git-info") {
             self.show_git_file_info = true;
         } else if cli_args.is_present("no-show-git-info") {
             self.show_git_file_info = false;
         }
+        if cli_args.is_present("git-dir-stats") {
+            self.show_git_dir_stats = true;
+            self.show_git_file_info = true;
+        }
+        if cli_args.is_present("git-status") {
+            self.filter_by_git_status = true;
+            self.show_hidden = true;
+        }
+        if let Some(sort) = cli_args.value_of("sort-by").and_then(Sort::from_name) {
+            self.sort = sort;
+            if sort == Sort::Size {
+                self.show_sizes = true;
+            }
+        }
+        if let Some(date_source) = cli_args.value_of("date-source").and_then(DateSource::from_name) {
+            self.date_source = date_source;
+        }
+        if let Some(max_depth) = cli_args.value_of("max-depth").and_then(|s| s.parse().ok()) {
+            self.max_depth = Some(max_depth).filter(|&d| d > 0);
+        }
+        if let Some(min_depth) = cli_args.value_of("min-depth").and_then(|s| s.parse().ok()) {
+            self.min_depth = Some(min_depth).filter(|&d| d > 1);
+        }
+        if let Some(globs) = cli_args.value_of("prune") {
+            let globs: Vec<&str> = globs.split(';').filter(|g| !g.trim().is_empty()).collect();
+            self.prune.extend(special_paths::prune_paths(&globs));
+        }
+        if let Some(max_children) = cli_args.value_of("max-children").and_then(|s| s.parse().ok()) {
+            self.max_children = Some(max_children).filter(|&c| c > 0);
+        }
+        if cli_args.is_present("watch") {
+            self.watch = true;
+        }
         if cli_args.is_present("trim-root") {
             self.trim_root = true;
         } else if cli_args.is_present("no-trim-root") {
@@ -91,12 +197,36 @@ impl Default for TreeOptions {
             only_folders: false,
             show_sizes: false,
             show_dates: false,
+            show_line_counts: false,
+            show_freshness: false,
+            show_icons: false,
+            date_source: DateSource::default(),
             show_git_file_info: false,
+            show_git_dir_stats: false,
             trim_root: true,
             show_permissions: false,
             respect_git_ignore: true,
+            show_ignore_audit: false,
+            follow_links: false,
             filter_by_git_status: false,
+            date_filter: DateFilter::default(),
+            ext_filter: ExtFilter::default(),
+            note_filter: None,
+            tag_filter: None,
             pattern: Pattern::None,
+            roots: Vec::new(),
+            max_depth: None,
+            min_depth: None,
+            prune: Vec::new(),
+            max_children: None,
+            sort: Sort::None,
+            tie_breaker: TieBreaker::default(),
+            special_sort: false,
+            special_profile: None,
+            cols_order: columns::DEFAULT_COLS_ORDER.to_vec(),
+            cols_priority: columns::DEFAULT_COLS_PRIORITY.to_vec(),
+            align_size_units: true,
+            watch: false,
         }
     }
 }
@@ -0,0 +1,294 @@
+use {
+    crate::{
+        app_context::AppContext,
+        app_state::{AppState, AppStateCmdResult},
+        commands::{Action, Command},
+        errors::ProgramError,
+        help_content::{ScreenHelp, ScreenKey},
+        help_states::HelpState,
+        screens::Screen,
+        status::Status,
+        task_sync::Dam,
+        usage_stats::{self, UsageStats},
+        verb_store::PrefixSearchResult,
+        verbs::Verb,
+    },
+    crossterm::{
+        terminal::{Clear, ClearType},
+        QueueableCommand,
+    },
+    minimad::{Composite, TextTemplate},
+    std::io::Write,
+    termimad::{Area, FmtText, TextView},
+};
+
+static MD: &str = r#"
+# Usage statistics
+${disabled
+**The statistics are disabled**: set `usage_stats = true` in your configuration to count the uses of the verbs and of the pattern modes.
+}
+They're kept on this computer only, in `${path}`, and never sent anywhere.
+## Verbs
+|:-|:-|:-|:-:|-:
+|**verb**|**origin**|**key**|**shortcut**|**uses**
+|:-|:-|:-|:-:|-:
+${verb-rows
+|${name}|${origin}|${key}|${shortcut}|${uses}
+}
+|-
+## Patterns
+|:-|-:
+|**mode**|**uses**
+|:-|-:
+${pattern-rows
+|${mode}|${uses}
+}
+|-
+"#;
+
+/// the help of the usage statistics screen
+pub static HELP: ScreenHelp = ScreenHelp {
+    name: "usage statistics screen",
+    description: "This screen tells how many times each verb and each pattern mode was used, \
+        the least used verbs last.",
+    keys: &[
+        ScreenKey::hinted("esc", "get back to the previous screen", "to get back"),
+        ScreenKey::new("↑ ↓", "scroll"),
+        ScreenKey::hinted("?", "display this help", "for the help"),
+    ],
+    builtins: Some(&[
+        ":back", ":help", ":line_down", ":line_up", ":page_down", ":page_up", ":quit", ":usage",
+    ]),
+    external_verbs: false,
+};
+
+/// a verb with its number of uses
+struct VerbRow {
+    name: String,
+    origin: &'static str,
+    key: String,
+    shortcut: String,
+    uses: String,
+}
+
+/// an application state listing the uses of the verbs, the least
+/// used last, and of the pattern modes
+pub struct UsageState {
+    enabled: bool,
+    verb_rows: Vec<VerbRow>,
+    pattern_rows: Vec<(String, String)>,
+    path: String,
+    scroll: i32,
+    area: Area,
+    dirty: bool, // background must be cleared
+}
+
+impl UsageState {
+    pub fn new(con: &AppContext) -> UsageState {
+        let stats = usage_stats::totals();
+        UsageState {
+            enabled: usage_stats::is_enabled(),
+            verb_rows: verb_rows(&stats, con),
+            pattern_rows: stats
+                .patterns
+                .iter()
+                .map(|(mode, uses)| (mode.to_string(), uses.to_string()))
+                .collect(),
+            path: usage_stats::file_path().to_string_lossy().to_string(),
+            scroll: 0,
+            area: Area::uninitialized(), // will be fixed at drawing time
+            dirty: true,
+        }
+    }
+}
+
+/// build the rows of the verbs which can be called by name, the most
+/// used first. When several verbs have the same name, only the one
+/// which is called is listed.
+fn verb_rows(stats: &UsageStats, con: &AppContext) -> Vec<VerbRow> {
+    let mut verbs: Vec<(&Verb, u64)> = Vec::new();
+    for verb in &con.verb_store.verbs {
+        let name = &verb.invocation.name;
+        if name.is_empty() || verbs.iter().any(|(v, _)| &v.invocation.name == name) {
+            continue;
+        }
+        verbs.push((verb, stats.verb_uses(name)));
+    }
+    verbs.sort_by(|(_, a), (_, b)| b.cmp(a));
+    verbs
+        .into_iter()
+        .map(|(verb, uses)| VerbRow {
+            name: verb.invocation.name.to_string(),
+            origin: if verb.from_project {
+                "project"
+            } else if verb.execution.starts_with(':') {
+                "builtin"
+            } else {
+                "command"
+            },
+            key: verb.key_desc.to_string(),
+            shortcut: verb.shortcut.clone().unwrap_or_default(),
+            uses: uses.to_string(),
+        })
+        .collect()
+}
+
+impl AppState for UsageState {
+    fn help(&self) -> &'static ScreenHelp {
+        &HELP
+    }
+
+    fn has_pending_task(&self) -> bool {
+        false
+    }
+
+    fn can_execute(&self, _verb_index: usize, _con: &AppContext) -> bool {
+        true
+    }
+
+    fn apply(
+        &mut self,
+        cmd: &mut Command,
+        screen: &mut Screen,
+        con: &AppContext,
+    ) -> Result<AppStateCmdResult, ProgramError> {
+        let verb_execution = match &cmd.action {
+            Action::VerbIndex(index) => Some(con.verb_store.verbs[*index].execution.as_str()),
+            Action::VerbInvocate(invocation) => match con.verb_store.search(&invocation.name) {
+                PrefixSearchResult::Match(verb) => Some(verb.execution.as_str()),
+                _ => {
+                    return Ok(AppStateCmdResult::verb_not_found(&invocation.name));
+                }
+            },
+            _ => None,
+        };
+        let page_height = self.area.height as i32;
+        Ok(match (&cmd.action, verb_execution) {
+            (Action::Back, _) | (_, Some(":back")) => AppStateCmdResult::PopState,
+            (Action::Help, _) | (_, Some(":help")) => AppStateCmdResult::NewState(
+                Box::new(HelpState::new(screen, con, &HELP)),
+                Command::new(),
+            ),
+            (_, Some(":usage")) => AppStateCmdResult::Keep,
+            (_, Some(":line_down")) => {
+                self.scroll += 1;
+                AppStateCmdResult::Keep
+            }
+            (_, Some(":line_up")) => {
+                self.scroll -= 1;
+                AppStateCmdResult::Keep
+            }
+            (_, Some(":page_down")) => {
+                self.scroll += page_height;
+                AppStateCmdResult::Keep
+            }
+            (_, Some(":page_up")) => {
+                self.scroll -= page_height;
+                AppStateCmdResult::Keep
+            }
+            (_, Some(":quit")) => AppStateCmdResult::Quit,
+            // the other verbs apply to the previous screen
+            (_, Some(_)) => AppStateCmdResult::PopStateAndReapply,
+            (Action::MoveSelection(dy), _) | (Action::Scroll(dy), _) => {
+                self.scroll += *dy;
+                AppStateCmdResult::Keep
+            }
+            (Action::Resize(w, h), _) => {
+                screen.set_terminal_size(*w, *h, con);
+                self.dirty = true;
+                AppStateCmdResult::Keep
+            }
+            _ => AppStateCmdResult::Keep,
+        })
+    }
+
+    fn refresh(&mut self, _screen: &Screen, con: &AppContext) -> Command {
+        *self = UsageState::new(con);
+        Command::new()
+    }
+
+    fn do_pending_task(&mut self, _screen: &mut Screen, _con: &AppContext, _dam: &mut Dam) {
+        unreachable!();
+    }
+
+    fn display(
+        &mut self,
+        mut w: &mut dyn Write,
+        screen: &Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        if self.dirty {
+            screen.skin.default.queue_bg(&mut w)?;
+            screen.clear(&mut w)?;
+            self.area = Area::new(0, 0, screen.width, screen.height - 2);
+            self.area.pad_for_max_width(110);
+            self.dirty = false;
+        }
+        lazy_static! {
+            static ref TEMPLATE: TextTemplate<'static> = TextTemplate::from(MD);
+        }
+        let mut expander = TEMPLATE.expander();
+        expander.set("path", &self.path);
+        if !self.enabled {
+            expander.sub("disabled");
+        }
+        for row in &self.verb_rows {
+            expander
+                .sub("verb-rows")
+                .set("name", &row.name)
+                .set("origin", row.origin)
+                .set("key", &row.key)
+                .set("shortcut", &row.shortcut)
+                .set("uses", &row.uses);
+        }
+        for (mode, uses) in &self.pattern_rows {
+            expander.sub("pattern-rows").set("mode", mode).set("uses", uses);
+        }
+        let text = expander.expand();
+        let fmt_text = FmtText::from_text(
+            &screen.help_skin,
+            text,
+            Some((self.area.width - 1) as usize),
+        );
+        let mut text_view = TextView::from(&self.area, &fmt_text);
+        self.scroll = text_view.set_scroll(self.scroll);
+        Ok(text_view.write_on(&mut w)?)
+    }
+
+    fn write_status(
+        &self,
+        mut w: &mut dyn Write,
+        cmd: &Command,
+        screen: &Screen,
+        con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        match &cmd.action {
+            Action::VerbEdit(invocation) if !invocation.name.is_empty() => {
+                match con.verb_store.search(&invocation.name) {
+                    PrefixSearchResult::Match(_) => Status::from_message(mad_inline!(
+                        "Hit *enter* to execute the verb"
+                    ))
+                    .display(&mut w, screen),
+                    _ => Status::from_error(mad_inline!("No matching verb"))
+                        .display(&mut w, screen),
+                }
+            }
+            _ => {
+                let hint = format!("{}, or a space to start a verb", HELP.keys_hint());
+                Status::from_message(Composite::from_inline(&hint)).display(&mut w, screen)
+            }
+        }
+    }
+
+    /// there's no meaningful flags here
+    fn write_flags(
+        &self,
+        mut w: &mut dyn Write,
+        screen: &mut Screen,
+        _con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        screen.skin.default.queue_bg(&mut w)?;
+        w.queue(Clear(ClearType::UntilNewLine))?;
+        Ok(())
+    }
+}
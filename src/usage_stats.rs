@@ -0,0 +1,172 @@
+//! local statistics of the uses of the verbs and of the pattern modes,
+//! helping to find the verbs which are never used and the ones which
+//! deserve a better key.
+//!
+//! They're only counted when `usage_stats` is set, written in broot's
+//! data directory when it quits, and never sent anywhere. The counts of
+//! the session are added to the ones of the file when it's written, so
+//! that several broot instances don't lose their counts.
+
+use {
+    crate::conf,
+    std::{collections::BTreeMap, fs, io, path::PathBuf, sync::Mutex},
+    toml::{value::Table, Value},
+};
+
+/// the uses of the verbs, by name, and of the pattern modes
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct UsageStats {
+    pub verbs: BTreeMap<String, u64>,
+    pub patterns: BTreeMap<String, u64>,
+}
+
+fn counts_from_toml(value: Option<&Value>) -> BTreeMap<String, u64> {
+    let mut counts = BTreeMap::new();
+    if let Some(Value::Table(tbl)) = value {
+        for (name, count) in tbl {
+            if let Some(count) = count.as_integer().filter(|&count| count > 0) {
+                counts.insert(name.to_string(), count as u64);
+            }
+        }
+    }
+    counts
+}
+
+fn counts_to_toml(counts: &BTreeMap<String, u64>) -> Value {
+    Value::Table(
+        counts
+            .iter()
+            .map(|(name, &count)| (name.to_string(), Value::Integer(count as i64)))
+            .collect(),
+    )
+}
+
+impl UsageStats {
+    /// read the stats from the content of a stats file, ignoring
+    /// what can't be understood
+    fn from_toml(content: &str) -> UsageStats {
+        match content.parse::<Value>() {
+            Ok(root) => UsageStats {
+                verbs: counts_from_toml(root.get("verbs")),
+                patterns: counts_from_toml(root.get("patterns")),
+            },
+            Err(e) => {
+                warn!("invalid usage stats file: {:?}", e);
+                UsageStats::default()
+            }
+        }
+    }
+    fn to_toml(&self) -> String {
+        let mut root = Table::new();
+        root.insert("verbs".to_string(), counts_to_toml(&self.verbs));
+        root.insert("patterns".to_string(), counts_to_toml(&self.patterns));
+        Value::Table(root).to_string()
+    }
+    fn add(&mut self, other: &UsageStats) {
+        for (name, count) in &other.verbs {
+            *self.verbs.entry(name.to_string()).or_insert(0) += count;
+        }
+        for (mode, count) in &other.patterns {
+            *self.patterns.entry(mode.to_string()).or_insert(0) += count;
+        }
+    }
+    pub fn verb_uses(&self, name: &str) -> u64 {
+        self.verbs.get(name).copied().unwrap_or(0)
+    }
+}
+
+lazy_static! {
+    // the counts of the session, none when the stats are disabled
+    static ref SESSION: Mutex<Option<UsageStats>> = Mutex::new(None);
+}
+
+/// the file where the counts of all the sessions are kept
+pub fn file_path() -> PathBuf {
+    conf::app_dirs().data_dir().join("usage_stats.toml")
+}
+
+fn read_file() -> UsageStats {
+    fs::read_to_string(file_path())
+        .map(|content| UsageStats::from_toml(&content))
+        .unwrap_or_default()
+}
+
+/// start counting, when the stats are enabled
+pub fn init(enabled: bool) {
+    *SESSION.lock().unwrap() = if enabled {
+        Some(UsageStats::default())
+    } else {
+        None
+    };
+}
+
+pub fn is_enabled() -> bool {
+    SESSION.lock().unwrap().is_some()
+}
+
+/// count a use of the verb with this name
+pub fn count_verb(name: &str) {
+    if let Some(session) = SESSION.lock().unwrap().as_mut() {
+        *session.verbs.entry(name.to_string()).or_insert(0) += 1;
+    }
+}
+
+/// count a use of a pattern mode (eg "fuzzy")
+pub fn count_pattern(mode: &str) {
+    if let Some(session) = SESSION.lock().unwrap().as_mut() {
+        *session.patterns.entry(mode.to_string()).or_insert(0) += 1;
+    }
+}
+
+/// return the counts of all the sessions, the current one included
+pub fn totals() -> UsageStats {
+    let mut totals = read_file();
+    if let Some(session) = SESSION.lock().unwrap().as_ref() {
+        totals.add(session);
+    }
+    totals
+}
+
+/// add the counts of the session to the file, if the stats are enabled
+pub fn save() -> io::Result<()> {
+    let mut session = SESSION.lock().unwrap();
+    let session = match session.as_mut() {
+        Some(session) => session,
+        None => {
+            return Ok(());
+        }
+    };
+    let mut totals = read_file();
+    totals.add(session);
+    let file_path = file_path();
+    if let Some(dir) = file_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(file_path, totals.to_toml())?;
+    *session = UsageStats::default();
+    Ok(())
+}
+
+#[cfg(test)]
+mod usage_stats_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_stats_persistence() {
+        let mut stats = UsageStats::default();
+        stats.verbs.insert("edit".to_string(), 3);
+        stats.verbs.insert("git diff".to_string(), 1);
+        stats.patterns.insert("fuzzy".to_string(), 7);
+        assert_eq!(UsageStats::from_toml(&stats.to_toml()), stats);
+        let mut session = UsageStats::default();
+        session.verbs.insert("edit".to_string(), 2);
+        session.patterns.insert("regex".to_string(), 1);
+        stats.add(&session);
+        assert_eq!(stats.verb_uses("edit"), 5);
+        assert_eq!(stats.verb_uses("rm"), 0);
+        assert_eq!(stats.patterns.len(), 2);
+        // an invalid file is ignored
+        assert_eq!(UsageStats::from_toml("verbs = ["), UsageStats::default());
+    }
+}
@@ -1,4 +1,8 @@
-use crossterm::event::KeyEvent;
+use {
+    crate::verbs::VerbArg,
+    crossterm::event::KeyEvent,
+    std::time::Duration,
+};
 
 /// what's needed to handle a verb
 #[derive(Debug)]
@@ -11,5 +15,8 @@ pub struct VerbConf {
     pub from_shell: Option<bool>,
     pub leave_broot: Option<bool>,
     pub confirm: Option<bool>,
+    pub timeout: Option<Duration>, // after which a program not leaving broot is killed
+    pub from_project: bool, // whether it comes from a .broot project file
+    pub prompts: Vec<VerbArg>, // the arguments asked, in order, when not given
 }
 
@@ -0,0 +1,152 @@
+//! export of the verbs, once the configuration is applied, for
+//! dotfile managers or docs generators (see the --print-verbs
+//! launch argument)
+
+use {
+    crate::{
+        selection_type::SelectionType,
+        tree_export::json_string,
+        verbs::Verb,
+    },
+    std::fmt::Write,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VerbFormat {
+    Json,
+    Md,
+    Tsv,
+}
+
+impl VerbFormat {
+    pub fn from_name(name: &str) -> Option<VerbFormat> {
+        match name {
+            "json" => Some(VerbFormat::Json),
+            "md" => Some(VerbFormat::Md),
+            "tsv" => Some(VerbFormat::Tsv),
+            _ => None,
+        }
+    }
+}
+
+/// write the verbs in the required format, one verb per
+/// line (or per array element) in the order of the verb store
+pub fn export(verbs: &[Verb], format: VerbFormat) -> String {
+    match format {
+        VerbFormat::Json => json(verbs),
+        VerbFormat::Md => md(verbs),
+        VerbFormat::Tsv => tsv(verbs),
+    }
+}
+
+fn selection_name(verb: &Verb) -> &'static str {
+    match verb.selection_condition {
+        SelectionType::File => "file",
+        SelectionType::Directory => "directory",
+        SelectionType::Any => "any",
+        SelectionType::None => "none",
+    }
+}
+
+fn invocation(verb: &Verb) -> String {
+    verb.invocation.to_string_for_name(&verb.invocation.name)
+}
+
+fn json(verbs: &[Verb]) -> String {
+    let mut s = String::new();
+    s.push('[');
+    for (idx, verb) in verbs.iter().enumerate() {
+        if idx > 0 {
+            s.push(',');
+        }
+        let _ = write!(
+            s,
+            "\n  {{\"name\": {}, \"invocation\": {}, \"execution\": {}, \"applies_to\": \"{}\"",
+            json_string(&verb.invocation.name),
+            json_string(&invocation(verb)),
+            json_string(&verb.execution),
+            selection_name(verb),
+        );
+        if let Some(shortcut) = &verb.shortcut {
+            let _ = write!(s, ", \"shortcut\": {}", json_string(shortcut));
+        }
+        if !verb.key_desc.is_empty() {
+            let _ = write!(s, ", \"key\": {}", json_string(&verb.key_desc));
+        }
+        if let Some(description) = &verb.description {
+            let _ = write!(s, ", \"description\": {}", json_string(description));
+        }
+        let _ = write!(
+            s,
+            ", \"from_shell\": {}, \"leave_broot\": {}, \"from_project\": {}}}",
+            verb.from_shell,
+            verb.leave_broot,
+            verb.from_project,
+        );
+    }
+    s.push_str("\n]");
+    s
+}
+
+/// make the text fit in a cell of a markdown table
+fn md_cell(raw: &str) -> String {
+    raw.replace('|', "\\|").replace('\n', " ")
+}
+
+fn md(verbs: &[Verb]) -> String {
+    let mut s = String::new();
+    s.push_str("invocation | shortcut | key | applies to | description\n");
+    s.push_str("-|-|-|-|-");
+    for verb in verbs {
+        let description = match &verb.description {
+            Some(description) => md_cell(description),
+            None => format!("`{}`", md_cell(&verb.execution)),
+        };
+        let _ = write!(
+            s,
+            "\n{} | {} | {} | {} | {}",
+            md_cell(&invocation(verb)),
+            md_cell(verb.shortcut.as_deref().unwrap_or("-")),
+            if verb.key_desc.is_empty() { "-" } else { &verb.key_desc },
+            selection_name(verb),
+            description,
+        );
+    }
+    s
+}
+
+/// make the text fit in a field of a tab separated line
+fn tsv_field(raw: &str) -> String {
+    raw.replace(&['\t', '\n', '\r'][..], " ")
+}
+
+fn tsv(verbs: &[Verb]) -> String {
+    let mut s = String::new();
+    s.push_str("name\tinvocation\tshortcut\tkey\tapplies_to\texecution\tdescription");
+    for verb in verbs {
+        let _ = write!(
+            s,
+            "\n{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            tsv_field(&verb.invocation.name),
+            tsv_field(&invocation(verb)),
+            tsv_field(verb.shortcut.as_deref().unwrap_or("")),
+            tsv_field(&verb.key_desc),
+            selection_name(verb),
+            tsv_field(&verb.execution),
+            tsv_field(verb.description.as_deref().unwrap_or("")),
+        );
+    }
+    s
+}
+
+#[cfg(test)]
+mod verb_export_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_cell_escaping() {
+        assert_eq!(md_cell("a|b\nc"), "a\\|b c");
+        assert_eq!(tsv_field("a\tb\r\nc"), "a b  c");
+    }
+}
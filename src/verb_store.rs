@@ -3,7 +3,12 @@ use {
     crate::{
         conf::Conf,
         keys,
+        filesystems,
+        path_completion,
         permissions,
+        selection_type::SelectionType,
+        tags::TagColor,
+        trash,
         verbs::Verb,
     },
     crossterm::event::{
@@ -13,6 +18,33 @@ use {
     },
 };
 
+/// return the path to the current broot executable, so that it can
+/// be launched with sudo even when it's not in root's PATH
+#[cfg(unix)]
+fn broot_exe() -> String {
+    std::env::current_exe()
+        .ok()
+        .and_then(|path| path.to_str().map(|s| s.to_string()))
+        .filter(|path| !path.contains(' ')) // it would be split
+        .unwrap_or_else(|| "broot".to_string())
+}
+
+// The executions of the default file management verbs. There's
+// no POSIX shell on Windows, so the commands of cmd are used.
+#[cfg(not(windows))]
+const MKDIR_EXECUTION: &str = "/bin/mkdir -p {subpath:path-from-directory}";
+#[cfg(not(windows))]
+const MV_EXECUTION: &str = "/bin/mv {file} {newpath:path-from-parent}";
+#[cfg(not(windows))]
+const RM_EXECUTION: &str = "/bin/rm -rf {file}";
+#[cfg(windows)]
+const MKDIR_EXECUTION: &str = "cmd /c mkdir {subpath:path-from-directory}";
+#[cfg(windows)]
+const MV_EXECUTION: &str = "cmd /c move /y {file} {newpath:path-from-parent}";
+// rmdir fails on files, which are then removed with del
+#[cfg(windows)]
+const RM_EXECUTION: &str = "cmd /c rmdir /s /q {file} 2>nul || del /f /q {file}";
+
 /// Provide access to the verbs:
 /// - the built-in ones
 /// - the user defined ones
@@ -22,6 +54,7 @@ use {
 /// - if only one verb name starts with the input
 pub struct VerbStore {
     pub verbs: Vec<Verb>,
+    key_bindings: Vec<(KeyEvent, usize)>, // keys of the [keys] conf section with their verb index
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -33,7 +66,10 @@ pub enum PrefixSearchResult<'v, T> {
 
 impl VerbStore {
     pub fn new() -> VerbStore {
-        VerbStore { verbs: Vec::new() }
+        VerbStore {
+            verbs: Vec::new(),
+            key_bindings: Vec::new(),
+        }
     }
     fn add_builtin(
         &mut self,
@@ -41,13 +77,14 @@ impl VerbStore {
         key: Option<KeyEvent>,
         shortcut: Option<String>,
         description: &str,
-    ) {
+    ) -> &mut Verb {
         self.verbs.push(Verb::create_builtin(name, key, shortcut, description));
+        self.verbs.last_mut().unwrap()
     }
     pub fn init(&mut self, conf: &Conf) {
         // we first add the verbs coming from configuration, as
         // we'll search in order. This way, a user can overload a
-        // standard verb.
+        // standard verb (a project can't, see reject_project_overrides).
         for verb_conf in &conf.verbs {
             match Verb::create_external(
                 &verb_conf.invocation,
@@ -59,20 +96,45 @@ impl VerbStore {
                 verb_conf.leave_broot.unwrap_or(true),
                 verb_conf.confirm.unwrap_or(false),
             ) {
-                Ok(v) => {
-                    self.verbs.push(v);
+                Ok(mut v) => {
+                    v.from_project = verb_conf.from_project;
+                    v.timeout = verb_conf.timeout;
+                    match v.set_prompts(verb_conf.prompts.clone()) {
+                        Ok(()) => self.verbs.push(v),
+                        Err(e) => eprintln!("Verb error: {:?}", e),
+                    }
                 }
                 Err(e) => {
                     eprintln!("Verb error: {:?}", e);
                 }
             }
         }
+        self.add_builtin(
+            "add_root {path}",
+            None,
+            None,
+            "display another directory alongside the current root(s)",
+        );
+        self.add_builtin(
+            "at {ref}",
+            None,
+            None,
+            "display the selected directory as it was at a git commit, branch or tag",
+        );
+        self.add_builtin(
+            "await {glob}",
+            None,
+            None,
+            "wait for a file matching the glob to appear below the root, then select it",
+        )
+        .without_selection();
         self.add_builtin(
             "back",
             None, // esc is mapped in commands.rs
             None,
             "revert to the previous state (mapped to *esc*)",
-        );
+        )
+        .without_selection();
         self.verbs.push(
             Verb::create_external(
                 "cd",
@@ -87,32 +149,112 @@ impl VerbStore {
             .unwrap(),
         );
         #[cfg(unix)]
-        self.verbs.push(
-            Verb::create_external(
-                "chmod {args}",
-                None,
-                None,
-                "chmod {args} {file}".to_string(),
-                None,
-                false,
-                false, // doesn't leave broot
-                false,
-            )
-            .unwrap(),
+        self.add_builtin(
+            "chmod {mode}",
+            None,
+            None,
+            "change the mode of the selection, eg `:chmod u+x` or `:chmod 644`",
+        )
+        .destructive();
+        #[cfg(unix)]
+        self.add_builtin(
+            "chown {owner}",
+            None,
+            None,
+            "change the owner and/or group of the selection, eg `:chown dys:staff`",
+        )
+        .destructive();
+        self.add_builtin(
+            "clear_queue",
+            None,
+            None,
+            "remove all the commands staged with :queue",
+        )
+        .without_selection();
+        self.add_builtin(
+            "code_stats",
+            None,
+            Some("cs".to_string()),
+            "count the lines of code, comments and blanks of the selection",
         );
-        self.verbs.push(
-            Verb::create_external(
-                "cp {newpath}",
-                None,
-                None,
-                "/bin/cp -r {file} {newpath:path-from-parent}".to_string(),
+        self.add_builtin(
+            "config_doc",
+            None,
+            None,
+            "list the configuration keys, their defaults and the values given in your files",
+        )
+        .without_selection();
+        self.add_builtin(
+            "cp {newpath}",
+            None,
+            None,
+            "copy the selection to the path, relative to its parent, showing the progress",
+        );
+        #[cfg(windows)]
+        self.add_builtin(
+            "drives",
+            None,
+            Some("dr".to_string()),
+            "list the drives, to browse one of them",
+        )
+        .without_selection();
+        self.add_builtin(
+            "execute_queue",
+            None,
+            Some("eq".to_string()),
+            "review then execute the commands staged with :queue",
+        )
+        .without_selection();
+        self.add_builtin(
+            "ext {extensions}",
+            None,
+            None,
+            "only show files with those extensions (eg `rs,toml`), or stop filtering them",
+        )
+        .without_selection();
+        self.add_builtin(
+            "extract",
+            None,
+            None,
+            "extract the selected entry of an archive next to the archive",
+        );
+        self.add_builtin(
+            "file_log",
+            None,
+            None,
+            "list the recent commits which changed the selected file, to open its versions",
+        );
+        // on Windows, the file systems are listed with :drives
+        if filesystems::supported() && !cfg!(windows) {
+            self.add_builtin(
+                "filesystems",
                 None,
-                false,
-                false,
-                false,
+                Some("fs".to_string()),
+                "list the mounted file systems",
             )
-            .unwrap(),
-        );
+            .without_selection();
+        }
+        self.add_builtin(
+            "filter_by_date {filter}",
+            None,
+            Some("fd".to_string()),
+            "only show files modified in a date range (eg `>2020-03-01` or `newer_than:2w`)",
+        )
+        .without_selection();
+        self.add_builtin(
+            "filter_by_note {text}",
+            None,
+            Some("fn".to_string()),
+            "only show the entries whose note contains the text (`*` for all the annotated ones)",
+        )
+        .without_selection();
+        self.add_builtin(
+            "filter_by_tag {color}",
+            None,
+            Some("ft".to_string()),
+            "only show the entries having the tag (eg `red`)",
+        )
+        .without_selection();
         self.add_builtin(
             "focus",
             None, // enter
@@ -124,31 +266,54 @@ impl VerbStore {
             None,
             None,
             "focus `/`",
+        )
+        .without_selection();
+        self.add_builtin(
+            "gitignore_add",
+            None,
+            None,
+            "add a pattern ignoring the selection to the nearest .gitignore",
         );
         self.add_builtin(
             "help",
             Some(KeyEvent::from(KeyCode::F(1))),
             Some("?".to_string()),
             "display broot's help",
-        );
+        )
+        .without_selection();
+        self.add_builtin(
+            "kill_task",
+            None,
+            None,
+            "kill the running shell command, with the programs it started",
+        )
+        .without_selection();
         self.add_builtin(
             "line_down",
             Some(KeyEvent::from(KeyCode::Down)),
             None,
             "move one line down",
-        );
+        )
+        .without_selection();
         self.add_builtin(
             "line_up",
             Some(KeyEvent::from(KeyCode::Up)),
             None,
             "move one line up",
+        )
+        .without_selection();
+        self.add_builtin(
+            "log",
+            None,
+            None,
+            "list the recent commits which changed the selection, with their files",
         );
         self.verbs.push(
             Verb::create_external(
                 "mkdir {subpath}",
                 None,
                 Some("md".to_string()),
-                "/bin/mkdir -p {subpath:path-from-directory}".to_string(),
+                MKDIR_EXECUTION.to_string(),
                 None,
                 false,
                 false, // doesn't leave broot
@@ -161,14 +326,62 @@ impl VerbStore {
                 "mv {newpath}",
                 None,
                 None,
-                "/bin/mv {file} {newpath:path-from-parent}".to_string(),
+                MV_EXECUTION.to_string(),
                 None,
                 false,
                 false, // doesn't leave broot
-                false,
+                true,  // confirmed on protected paths
             )
             .unwrap(),
         );
+        self.add_builtin(
+            "no_date_filter",
+            None,
+            Some("nd".to_string()),
+            "remove the date filter",
+        )
+        .without_selection();
+        self.add_builtin(
+            "no_ext_filter",
+            None,
+            None,
+            "remove the extension filter",
+        )
+        .without_selection();
+        self.add_builtin(
+            "no_note_filter",
+            None,
+            None,
+            "remove the note filter",
+        )
+        .without_selection();
+        self.add_builtin(
+            "no_sort",
+            None,
+            Some("ns".to_string()),
+            "don't sort",
+        )
+        .without_selection();
+        self.add_builtin(
+            "no_tag_filter",
+            None,
+            None,
+            "remove the tag filter",
+        )
+        .without_selection();
+        self.add_builtin(
+            "note {text}",
+            None,
+            None,
+            "attach a short note to the selected file or directory (eg `delete later`)",
+        );
+        self.add_builtin(
+            "older_than {age}",
+            None,
+            None,
+            "only show files not modified for an age (eg `180d`, `6w`, `3m` or `1y`)",
+        )
+        .without_selection();
         self.add_builtin(
             "open_stay",
             None, // default mapping directly handled in commands#add_event
@@ -182,18 +395,36 @@ impl VerbStore {
             Some("ol".to_string()),
             "open file or directory according to OS settings (quit broot)",
         );
+        if trash::supported() {
+            self.add_builtin(
+                "open_trash",
+                None,
+                Some("ot".to_string()),
+                "list the trashed files, to restore or purge them",
+            )
+            .without_selection();
+        }
+        self.add_builtin(
+            "organize",
+            None,
+            None,
+            "move the files of the selected directory to folders by extension or date",
+        )
+        .destructive();
         self.add_builtin(
             "page_down",
             Some(KeyEvent::from(KeyCode::PageDown)),
             None,
             "scroll one page down",
-        );
+        )
+        .without_selection();
         self.add_builtin(
             "page_up",
             Some(KeyEvent::from(KeyCode::PageUp)),
             None,
             "scroll one page up",
-        );
+        )
+        .without_selection();
         self.add_builtin(
             "parent",
             None,
@@ -217,100 +448,435 @@ impl VerbStore {
             None,
             Some("pt".to_string()),
             "print tree and leaves broot",
+        )
+        .without_selection();
+        self.add_builtin(
+            "profile {name}",
+            None,
+            None,
+            "apply the flags and sort of a profile of the configuration",
+        )
+        .without_selection();
+        if trash::supported() {
+            self.add_builtin(
+                "purge",
+                None,
+                None,
+                "definitely remove the selected trashed file",
+            );
+        }
+        self.add_builtin(
+            "queue {verb}",
+            None,
+            None,
+            "stage a command on the selection, to be executed later with :execute_queue",
         );
+        self.add_builtin(
+            "palette",
+            Some(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL)),
+            None,
+            "search all the verbs, the bookmarks and the recent roots",
+        )
+        .without_selection();
         self.add_builtin(
             "quit",
             Some(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL)),
             Some("q".to_string()),
             "quit the application",
-        );
+        )
+        .without_selection();
         self.add_builtin(
             "refresh",
             Some(KeyEvent::from(KeyCode::F(5))),
             None,
             "refresh tree and clear size cache",
+        )
+        .without_selection();
+        self.add_builtin(
+            "refresh_sizes",
+            None,
+            None,
+            "compute again the sizes of the directories, forgetting the persisted ones",
+        )
+        .without_selection();
+        self.add_builtin(
+            "remove_note",
+            None,
+            None,
+            "remove the note of the selected file or directory",
         );
+        self.add_builtin(
+            "rename_regex {pattern} {replacement}",
+            None,
+            None,
+            "rename the visible files whose name is matched by the regex, after a preview",
+        )
+        .without_selection()
+        .destructive();
+        if trash::supported() {
+            self.add_builtin(
+                "restore",
+                None,
+                None,
+                "move the selected trashed file back to where it was",
+            );
+        }
         self.verbs.push(
             Verb::create_external(
                 "rm",
                 None, // the delete key is used in the input
                 None,
-                "/bin/rm -rf {file}".to_string(),
+                RM_EXECUTION.to_string(),
                 None,
                 false,
                 false, // doesn't leave broot
+                true,  // confirmed on protected paths
+            )
+            .unwrap(),
+        );
+        self.add_builtin(
+            "sanitize_names",
+            None,
+            None,
+            "rename the selection and its descendants whose names are invalid on Windows",
+        )
+        .destructive();
+        self.add_builtin(
+            "send_path",
+            None,
+            Some("sp".to_string()),
+            "send the selected path to the programs listening with --listen, and stay in broot",
+        );
+        self.add_builtin(
+            "set_max_children {count}",
+            None,
+            None,
+            "list at most this number of children per directory (0 for no limit)",
+        )
+        .without_selection();
+        self.add_builtin(
+            "set_max_depth {depth}",
+            None,
+            None,
+            "don't display the directories deeper than this (0 for no limit)",
+        )
+        .without_selection();
+        self.add_builtin(
+            "set_skin {name}",
+            None,
+            None,
+            "apply one of the skins shipped in broot (default, light or solarized-dark)",
+        )
+        .without_selection();
+        self.add_builtin(
+            "size_report {path}",
+            None,
+            None,
+            "write the known sizes of the root's directories in a file, sorted like `du | sort -h`",
+        );
+        self.add_builtin(
+            "sort_by_count",
+            None,
+            Some("sc".to_string()),
+            "sort by count",
+        )
+        .without_selection();
+        self.add_builtin(
+            "sort_by_date",
+            None,
+            Some("sd".to_string()),
+            "sort by date",
+        )
+        .without_selection();
+        self.add_builtin(
+            "sort_by_size",
+            None,
+            Some("ss".to_string()),
+            "sort by size",
+        )
+        .without_selection();
+        #[cfg(unix)]
+        self.verbs.push(
+            Verb::create_external(
+                "sudo_browse",
+                None,
+                None,
+                format!("sudo {} {{directory}}", broot_exe()),
+                Some("quit and browse the directory as root".to_string()),
+                false,
+                true, // leaves broot
                 false,
             )
             .unwrap(),
         );
+        for color in &TagColor::ALL {
+            self.add_builtin(
+                &format!("tag_{}", color.name()),
+                None,
+                None,
+                &format!("toggle the {} tag of the selected file or directory", color.name()),
+            );
+        }
         self.add_builtin(
             "toggle_dates",
             None,
             Some("dates".to_string()),
             "toggle showing last modified dates",
-        );
+        )
+        .without_selection();
+        self.add_builtin(
+            "toggle_deep_dates",
+            None,
+            Some("dd".to_string()),
+            "toggle using the most recent modification in directories as their date",
+        )
+        .without_selection();
         self.add_builtin(
             "toggle_files",
             None,
             Some("files".to_string()),
             "toggle showing files (or just folders)",
-        );
+        )
+        .without_selection();
+        self.add_builtin(
+            "toggle_freshness",
+            None,
+            Some("fresh".to_string()),
+            "toggle coloring names according to how recently they were modified",
+        )
+        .without_selection();
         self.add_builtin(
             "toggle_git_ignore",
             None,
             Some("gi".to_string()),
             "toggle use of .gitignore",
-        );
+        )
+        .without_selection();
+        self.add_builtin(
+            "toggle_git_dir_stats",
+            None,
+            Some("gd".to_string()),
+            "toggle display of the git statuses counts of directories",
+        )
+        .without_selection();
         self.add_builtin(
             "toggle_git_file_info",
             None,
             Some("gf".to_string()),
             "toggle display of git file information",
-        );
+        )
+        .without_selection();
         self.add_builtin(
             "toggle_git_status",
             None,
             Some("gs".to_string()),
             "toggle showing only files relevant for git status",
-        );
+        )
+        .without_selection();
         self.add_builtin(
             "toggle_hidden",
             None,
             Some("h".to_string()),
             "toggle showing hidden files",
-        );
+        )
+        .without_selection();
+        self.add_builtin(
+            "toggle_icons",
+            None,
+            Some("icons".to_string()),
+            "toggle writing an icon before the names",
+        )
+        .without_selection();
+        self.add_builtin(
+            "toggle_ignore_audit",
+            None,
+            Some("ia".to_string()),
+            "toggle telling why entries are tracked, ignored or untracked by git",
+        )
+        .without_selection();
+        self.add_builtin(
+            "toggle_line_counts",
+            None,
+            Some("lc".to_string()),
+            "toggle showing the number of lines of text files",
+        )
+        .without_selection();
+        self.add_builtin(
+            "toggle_links",
+            None,
+            Some("links".to_string()),
+            "toggle searching in symlinked directories",
+        )
+        .without_selection();
         if permissions::supported() {
             self.add_builtin(
                 "toggle_perm",
                 None,
                 Some("perm".to_string()),
                 "toggle showing file permissions",
-            );
+            )
+            .without_selection();
         }
         self.add_builtin(
             "toggle_sizes",
             None,
             Some("sizes".to_string()),
             "toggle showing sizes",
-        );
+        )
+        .without_selection();
+        self.add_builtin(
+            "toggle_theme_mode",
+            None,
+            Some("theme".to_string()),
+            "switch between the skins of dark and light backgrounds",
+        )
+        .without_selection();
         self.add_builtin(
             "toggle_trim_root",
             None,
             Some("t".to_string()),
             "toggle removing nodes at first level too",
-        );
+        )
+        .without_selection();
+        self.add_builtin(
+            "toggle_watch",
+            None,
+            Some("watch".to_string()),
+            "toggle refreshing the tree when files are created, removed or renamed",
+        )
+        .without_selection();
         self.add_builtin(
             "total_search",
             Some(keys::CTRL_S),
             None,
             "search again but on all children",
+        )
+        .without_selection();
+        if trash::supported() || !conf.trash_commands.is_empty() {
+            self.add_builtin(
+                "trash",
+                None,
+                None,
+                "move the selection to the trash",
+            )
+            .destructive();
+        }
+        self.add_builtin(
+            "undo",
+            None,
+            None,
+            "reverse the last renames, copy, trashing or restoring done by broot",
+        )
+        .without_selection();
+        self.add_builtin(
+            "untag",
+            None,
+            None,
+            "remove all the tags of the selected file or directory",
         );
         self.add_builtin(
             "up_tree",
             None,
             Some("up".to_string()),
             "focus the parent of the current root",
-        );
+        )
+        .without_selection();
+        self.add_builtin(
+            "usage",
+            None,
+            None,
+            "tell how many times each verb and pattern mode was used (needs `usage_stats`)",
+        )
+        .without_selection();
+        self.reject_project_overrides(conf);
+        self.inherit_builtin_flags();
+        self.bind_keys(conf);
+    }
+    /// remove the verbs of a project file which would change what an
+    /// existing name, shortcut or key does, as the user couldn't
+    /// expect a project to change the verbs they know
+    fn reject_project_overrides(&mut self, conf: &Conf) {
+        let (project_verbs, verbs): (Vec<Verb>, Vec<Verb>) =
+            self.verbs.drain(..).partition(|verb| verb.from_project);
+        let is_taken = |name: &str| {
+            verbs
+                .iter()
+                .any(|verb| verb.invocation.name == name || verb.shortcut.as_deref() == Some(name))
+        };
+        for verb in project_verbs {
+            let name = &verb.invocation.name;
+            let key_taken = verb.key.map_or(false, |key| {
+                verbs.iter().any(|other| other.key == Some(key))
+                    || conf.keys.iter().any(|(k, _)| *k == key)
+            });
+            if is_taken(name) || verb.shortcut.as_deref().map_or(false, is_taken) || key_taken {
+                eprintln!("Project verb {:?} ignored: it would replace another verb", name);
+                continue;
+            }
+            self.verbs.push(verb);
+        }
+        self.verbs.extend(verbs);
+    }
+    /// give the verbs calling a built-in, eg `{ key = "ctrl-t", execution = ":toggle_hidden" }`,
+    /// the flags of this built-in
+    fn inherit_builtin_flags(&mut self) {
+        for idx in 0..self.verbs.len() {
+            let execution = &self.verbs[idx].execution;
+            if !execution.starts_with(':') {
+                continue;
+            }
+            // the built-ins are after the verbs of the configuration
+            let builtin = self.verbs.iter().rev().find(|verb| &verb.execution == execution);
+            if let Some(builtin) = builtin {
+                let (selection_condition, confirm) = (builtin.selection_condition, builtin.confirm);
+                if selection_condition == SelectionType::None {
+                    self.verbs[idx].selection_condition = selection_condition;
+                }
+                self.verbs[idx].confirm |= confirm;
+            }
+        }
+    }
+    /// apply the [keys] section of the configuration. A bound key
+    /// is removed from the verb it was triggering before.
+    fn bind_keys(&mut self, conf: &Conf) {
+        for (key, verb_name) in &conf.keys {
+            let verb_name = verb_name.trim_start_matches(':');
+            let index = self.verbs.iter().position(|v| {
+                v.invocation.name == verb_name || v.shortcut.as_deref() == Some(verb_name)
+            });
+            match index {
+                Some(index) => {
+                    self.key_bindings.push((*key, index));
+                }
+                None => {
+                    eprintln!("Invalid [keys] entry in configuration");
+                    eprintln!("No verb named {:?}", verb_name);
+                }
+            }
+        }
+        if self.key_bindings.is_empty() {
+            return;
+        }
+        for (index, verb) in self.verbs.iter_mut().enumerate() {
+            let bindings = &self.key_bindings;
+            if let Some(key) = verb.key {
+                if bindings.iter().any(|(k, _)| *k == key) {
+                    verb.key = None;
+                }
+            }
+            verb.key_desc = verb
+                .key
+                .into_iter()
+                .chain(
+                    bindings
+                        .iter()
+                        .filter(|(_, i)| *i == index)
+                        .map(|(k, _)| *k),
+                )
+                .map(keys::key_event_desc)
+                .collect::<Vec<String>>()
+                .join(", ");
+        }
     }
     pub fn search<'v>(&'v self, prefix: &str) -> PrefixSearchResult<'v, &Verb> {
         let mut found_index = 0;
@@ -343,6 +909,32 @@ impl VerbStore {
             _ => PrefixSearchResult::TooManyMatches(completions),
         }
     }
+    /// return what must be added to the prefix to get the name (or
+    /// the shortcut) of the only verb it matches, or the common
+    /// start of the names of the verbs it matches
+    pub fn name_completion(&self, prefix: &str) -> Option<String> {
+        let completed = match self.search(prefix) {
+            PrefixSearchResult::NoMatch => {
+                return None;
+            }
+            PrefixSearchResult::Match(verb) => {
+                if verb.invocation.name.starts_with(prefix) {
+                    verb.invocation.name.clone()
+                } else {
+                    verb.shortcut.clone()?
+                }
+            }
+            PrefixSearchResult::TooManyMatches(names) => {
+                let names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+                path_completion::common_prefix(&names)
+            }
+        };
+        if completed.len() > prefix.len() && completed.starts_with(prefix) {
+            Some(completed[prefix.len()..].to_string())
+        } else {
+            None
+        }
+    }
     /// return the index of the verb having the long name. This function is meant
     /// for internal access when it's sure it can't fail (i.e. for a builtin)
     /// It looks for verbs by name, starting from the builtins, to
@@ -357,6 +949,11 @@ impl VerbStore {
     }
     /// return the index of the verb which is triggered by the given keyboard key, if any
     pub fn index_of_key(&self, key: KeyEvent) -> Option<usize> {
+        for (bound_key, index) in &self.key_bindings {
+            if *bound_key == key {
+                return Some(*index);
+            }
+        }
         for i in 0..self.verbs.len() {
             if let Some(verb_key) = self.verbs[i].key {
                 if verb_key == key {
@@ -1,12 +1,13 @@
 /// Verbs are the engines of broot commands, and apply
 /// - to the selected file (if user-defined, then must contain {file}, {parent} or {directory})
+///   with {capture} being the text captured by the first group of a regex pattern
 /// - to the current app state
 use {
     crate::{
         app_context::AppContext,
         app_state::AppStateCmdResult,
         errors::{ConfError, ProgramError},
-        external, keys,
+        external, file_locks, keys, path_display, portable_names,
         screens::Screen,
         selection_type::SelectionType,
         status::Status,
@@ -24,6 +25,7 @@ use {
         fs::OpenOptions,
         io::Write,
         path::{Path, PathBuf},
+        time::Duration,
     },
 };
 
@@ -44,12 +46,26 @@ pub struct Verb {
     pub description: Option<String>, // a description for the user
     pub from_shell: bool, // whether it must be launched from the parent shell (eg because it's a shell function)
     pub leave_broot: bool, // only defined for external
-    pub confirm: bool, // not yet used...
+    pub confirm: bool, // whether the verb is destructive, needing a confirmation on protected paths
     pub selection_condition: SelectionType,
+    pub from_project: bool, // whether it was defined in a .broot project file
+    pub prompts: Vec<VerbArg>, // arguments asked in this order when not given
+    pub timeout: Option<Duration>, // after which a program not leaving broot is killed
 }
 
 lazy_static! {
     static ref GROUP: Regex = Regex::new(r"\{([^{}:]+)(?::([^{}:]+))?\}").unwrap();
+    // an argument in an invocation, with an optional default value (eg "{newpath=backup}")
+    static ref INVOCATION_ARG: Regex = Regex::new(r"\{([^{}:=]+)(?::[^{}:=]+)?(?:=([^{}]*))?\}")
+        .unwrap();
+}
+
+/// an argument declared in the invocation of a verb
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerbArg {
+    pub name: String,
+    pub label: Option<String>, // how the argument is called when prompted, the name by default
+    pub default: Option<String>, // proposed when the argument is prompted
 }
 
 pub trait VerbExecutor {
@@ -63,21 +79,13 @@ pub trait VerbExecutor {
 }
 
 fn make_invocation_args_regex(spec: &str) -> Result<Regex, ConfError> {
-    let spec = GROUP.replace_all(spec, r"(?P<$1>.+)");
+    let spec = INVOCATION_ARG.replace_all(spec, r"(?P<$1>.+)");
     let spec = format!("^{}$", spec);
     info!("spec = {:?}", &spec);
     Regex::new(&spec.to_string())
         .or_else(|_| Err(ConfError::InvalidVerbInvocation { invocation: spec }))
 }
 
-fn path_to_string(path: &Path, for_shell: bool) -> String {
-    if for_shell {
-        external::escape_for_shell(path)
-    } else {
-        path.to_string_lossy().to_string()
-    }
-}
-
 impl Verb {
     /// build a verb using standard configurable behavior.
     /// "external" means not "built-in".
@@ -115,33 +123,141 @@ impl Verb {
             leave_broot,
             confirm,
             selection_condition,
+            from_project: false,
+            prompts: Vec::new(),
+            timeout: None,
         })
     }
 
     /// built-ins are verbs offering a logic other than the execution
-    ///  based on exec_pattern. They mostly modify the appstate
+    ///  based on exec_pattern. They mostly modify the appstate.
+    ///
+    /// The invocation may declare arguments (eg "add_root {path}"), which
+    /// are then found in the invocation given to the executor.
     pub fn create_builtin(
-        name: &str,
+        invocation_str: &str,
         key: Option<KeyEvent>,
         shortcut: Option<String>,
         description: &str,
     ) -> Verb {
+        let invocation = VerbInvocation::from(invocation_str);
+        let args_parser = invocation
+            .args
+            .as_ref()
+            .map(|args| make_invocation_args_regex(&args))
+            .transpose()
+            .expect("invalid built-in invocation");
         Verb {
-            invocation: VerbInvocation {
-                name: name.to_string(),
-                args: None,
-            },
+            execution: format!(":{}", &invocation.name),
+            invocation,
             key_desc: key.map_or("".to_string(), keys::key_event_desc),
             key,
-            args_parser: None,
+            args_parser,
             shortcut,
-            execution: format!(":{}", name),
             description: Some(description.to_string()),
             from_shell: false,
             leave_broot: true, // ignored
-            confirm: false,    // ignored
+            confirm: false,    // set with destructive()
             selection_condition: SelectionType::Any,
+            from_project: false,
+            prompts: Vec::new(),
+            timeout: None,
+        }
+    }
+
+    /// return the arguments declared in the invocation, in order
+    pub fn args(&self) -> Vec<VerbArg> {
+        match &self.invocation.args {
+            Some(args) => INVOCATION_ARG
+                .captures_iter(args)
+                .map(|c| VerbArg {
+                    name: c[1].to_string(),
+                    label: None,
+                    default: c.get(2).map(|m| m.as_str().to_string()),
+                })
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// declare the arguments which are asked, in this order, when the
+    /// verb is called without them. They must be in the invocation.
+    pub fn set_prompts(&mut self, prompts: Vec<VerbArg>) -> Result<(), ConfError> {
+        let args = self.args();
+        for prompt in &prompts {
+            if !args.iter().any(|arg| arg.name == prompt.name) {
+                return Err(ConfError::InvalidVerbPrompt {
+                    arg: prompt.name.clone(),
+                });
+            }
+        }
+        self.prompts = prompts;
+        Ok(())
+    }
+
+    /// declare that the built-in changes or moves files, and must be
+    /// confirmed when applied to a protected path
+    pub fn destructive(&mut self) -> &mut Verb {
+        self.confirm = true;
+        self
+    }
+
+    /// tell whether the verb must be confirmed when applied to a protected path
+    pub fn is_destructive(&self) -> bool {
+        self.confirm
+    }
+
+    /// declare that the built-in doesn't apply to the selection,
+    /// as it changes the display or the state
+    pub fn without_selection(&mut self) -> &mut Verb {
+        self.selection_condition = SelectionType::None;
+        self
+    }
+
+    /// tell whether the verb applies to the selection, which must then exist
+    pub fn uses_selection(&self) -> bool {
+        self.selection_condition != SelectionType::None
+    }
+
+    /// return the arguments in the order they're asked: the declared
+    /// prompts first, then the other arguments of the invocation
+    pub fn prompt_args(&self) -> Vec<VerbArg> {
+        let mut prompt_args = Vec::new();
+        let args = self.args();
+        for prompt in &self.prompts {
+            let mut prompt = prompt.clone();
+            if prompt.default.is_none() {
+                prompt.default = args
+                    .iter()
+                    .find(|arg| arg.name == prompt.name)
+                    .and_then(|arg| arg.default.clone());
+            }
+            prompt_args.push(prompt);
+        }
+        for arg in args {
+            if !self.prompts.iter().any(|prompt| prompt.name == arg.name) {
+                prompt_args.push(arg);
+            }
         }
+        prompt_args
+    }
+
+    /// build the arguments part of an invocation by replacing the
+    /// declared arguments with the values given for them
+    pub fn fill_args(&self, args: &[VerbArg], values: &[String]) -> String {
+        let invocation_args = match &self.invocation.args {
+            Some(invocation_args) => invocation_args,
+            None => return String::new(),
+        };
+        INVOCATION_ARG
+            .replace_all(invocation_args, |c: &Captures<'_>| {
+                args.iter()
+                    .position(|arg| arg.name == c[1])
+                    .and_then(|idx| values.get(idx))
+                    .map_or("", |value| value.as_str())
+                    .to_string()
+            })
+            .to_string()
     }
 
     /// Assuming the verb has been matched, check whether the arguments
@@ -169,22 +285,15 @@ impl Verb {
     }
 
     /// build the map which will be used to replace braced parts (i.e. like {part}) in
-    /// the execution pattern
+    /// the execution pattern. The values aren't escaped, this is done on replacement.
     fn replacement_map(
         &self,
         file: &Path,
+        capture: Option<&str>,
         args: &Option<String>,
-        for_shell: bool,
     ) -> HashMap<String, String> {
-        let mut map = HashMap::new();
         // first we add the replacements computed from the given path
-        let parent = file.parent().unwrap_or(file); // when there's no parent... we take file
-        let file_str = path_to_string(file, for_shell);
-        let parent_str = path_to_string(parent, for_shell);
-        map.insert("file".to_string(), file_str.to_string());
-        map.insert("parent".to_string(), parent_str.to_string());
-        let dir_str = if file.is_dir() { file_str } else { parent_str };
-        map.insert("directory".to_string(), dir_str.to_string());
+        let mut map = selection_replacement_map(file, capture);
         // then the ones computed from the user input
         debug!("building repmap, args_parser={:?}", &self.args_parser);
         let default_args;
@@ -214,6 +323,7 @@ impl Verb {
         w: &mut impl Write,
         task: Option<&'static str>,
         path: PathBuf,
+        capture: Option<&str>,
         invocation: &VerbInvocation,
         screen: &Screen,
     ) -> Result<(), ProgramError> {
@@ -230,7 +340,9 @@ impl Verb {
                 );
                 Composite::from_inline(&markdown)
             } else {
-                verb_description = self.shell_exec_string(&path, &invocation.args);
+                verb_description = path_display::abbreviate_homes(
+                    &self.shell_exec_string(&path, capture, &invocation.args),
+                );
                 mad_inline!(
                     "Hit *enter* to **$0**: `$1`",
                     &self.invocation.name,
@@ -245,21 +357,49 @@ impl Verb {
         }
     }
 
+    /// return the path the file would have after the execution of
+    /// the verb, when its invocation has a `{newpath}` argument (the
+    /// path being relative to the parent, as for `:cp` and `:mv`)
+    pub fn destination(
+        &self,
+        file: &Path,
+        capture: Option<&str>,
+        args: &Option<String>,
+    ) -> Option<PathBuf> {
+        let map = self.replacement_map(file, capture, args);
+        let input = map.get("newpath")?;
+        let dest = PathBuf::from(path_from(PathSource::Parent, input, &map));
+        Some(if dest.is_dir() {
+            dest.join(file.file_name()?)
+        } else {
+            dest
+        })
+    }
+
     /// build the cmd result for a verb defined with an exec pattern.
     /// Calling this function on a built-in doesn't make sense
     pub fn to_cmd_result(
         &self,
         file: &Path,
+        capture: Option<&str>,
         args: &Option<String>,
         _screen: &mut Screen,
         con: &AppContext,
     ) -> Result<AppStateCmdResult, ProgramError> {
+        // a copy or move to a file system which can't take some of
+        // the names would fail in the middle
+        if let Some(dest) = self.destination(file, capture, args) {
+            let problems = portable_names::check_copy(file, &dest);
+            if !problems.is_empty() {
+                return Ok(AppStateCmdResult::DisplayError(portable_names::describe(&problems)));
+            }
+        }
         Ok(if self.from_shell {
             if let Some(ref export_path) = con.launch_args.cmd_export_path {
                 // Broot was probably launched as br.
                 // the whole command is exported in the passed file
                 let f = OpenOptions::new().append(true).open(export_path)?;
-                writeln!(&f, "{}", self.shell_exec_string(file, args))?;
+                writeln!(&f, "{}", self.shell_exec_string(file, capture, args))?;
                 AppStateCmdResult::Quit
             } else if let Some(ref export_path) = con.launch_args.file_export_path {
                 // old version of the br function: only the file is exported
@@ -273,12 +413,15 @@ impl Verb {
                 )
             }
         } else {
-            let launchable = external::Launchable::program(self.exec_token(file, args))?;
+            let launchable = external::Launchable::program(self.exec_token(file, capture, args))?;
             if self.leave_broot {
                 AppStateCmdResult::from(launchable)
             } else {
                 info!("Executing not leaving, launchable {:?}", launchable);
-                let execution = launchable.execute();
+                let execution = match self.timeout {
+                    Some(timeout) => launchable.execute_within(timeout),
+                    None => launchable.execute(),
+                };
                 match execution {
                     Ok(()) => {
                         debug!("ok");
@@ -286,7 +429,18 @@ impl Verb {
                     }
                     Err(e) => {
                         warn!("launchable failed : {:?}", e);
-                        AppStateCmdResult::DisplayError(e.to_string())
+                        // on Windows, a file used by another program can't
+                        // be moved or removed (eg by :mv or :rm)
+                        let lockers = match e {
+                            ProgramError::ProgramFailed { .. } => {
+                                file_locks::describe_lockers(file)
+                            }
+                            _ => None,
+                        };
+                        AppStateCmdResult::DisplayError(match lockers {
+                            Some(lockers) => format!("{} - {}", e, lockers),
+                            None => e.to_string(),
+                        })
                     }
                 }
             }
@@ -295,25 +449,35 @@ impl Verb {
 
     /// build the token which can be used to launch en executable.
     /// This doesn't make sense for a built-in.
-    pub fn exec_token(&self, file: &Path, args: &Option<String>) -> Vec<String> {
-        let map = self.replacement_map(file, args, false);
+    pub fn exec_token(
+        &self,
+        file: &Path,
+        capture: Option<&str>,
+        args: &Option<String>,
+    ) -> Vec<String> {
+        let map = self.replacement_map(file, capture, args);
         self.execution
             .split_whitespace()
             .map(|token| {
                 GROUP
-                    .replace_all(token, |ec: &Captures<'_>| do_exec_replacement(ec, &map))
+                    .replace_all(token, |ec: &Captures<'_>| do_exec_replacement(ec, &map, false))
                     .to_string()
             })
             .collect()
     }
 
     /// build a shell compatible command, with escapings
-    pub fn shell_exec_string(&self, file: &Path, args: &Option<String>) -> String {
+    pub fn shell_exec_string(
+        &self,
+        file: &Path,
+        capture: Option<&str>,
+        args: &Option<String>,
+    ) -> String {
         debug!("shell_exec_string args={:?}", args);
-        let map = self.replacement_map(file, args, true);
+        let map = self.replacement_map(file, capture, args);
         GROUP
             .replace_all(&self.execution, |ec: &Captures<'_>| {
-                do_exec_replacement(ec, &map)
+                do_exec_replacement(ec, &map, true)
             })
             .to_string()
             .split_whitespace()
@@ -332,6 +496,32 @@ impl Verb {
     }
 }
 
+/// build the map of the replacements computed from the selected path
+fn selection_replacement_map(file: &Path, capture: Option<&str>) -> HashMap<String, String> {
+    let mut map = HashMap::new();
+    let parent = file.parent().unwrap_or(file); // when there's no parent... we take file
+    let file_str = file.to_string_lossy();
+    let parent_str = parent.to_string_lossy();
+    map.insert("file".to_string(), file_str.to_string());
+    map.insert("parent".to_string(), parent_str.to_string());
+    let dir_str = if file.is_dir() { file_str } else { parent_str };
+    map.insert("directory".to_string(), dir_str.to_string());
+    // the part of the file name captured by the pattern
+    if let Some(capture) = capture {
+        map.insert("capture".to_string(), capture.to_string());
+    }
+    map
+}
+
+/// replace the arguments computed from the selection, like `{file}`
+/// or `{directory}`, in a command typed for the shell after a `!`
+pub fn shell_command_string(command: &str, file: &Path, capture: Option<&str>) -> String {
+    let map = selection_replacement_map(file, capture);
+    GROUP
+        .replace_all(command, |ec: &Captures<'_>| do_exec_replacement(ec, &map, true))
+        .to_string()
+}
+
 #[derive(Debug, Clone, Copy)]
 enum PathSource {
     Directory,
@@ -353,9 +543,9 @@ fn path_from(
     replacement_map: &HashMap<String, String>,
 ) -> String {
     let tilde = regex!(r"^~(/|$)");
-    if input.starts_with('/') {
-        // if the input starts with a `/`, we use it as is, we don't
-        // use the replacement_map
+    if input.starts_with('/') || Path::new(input).is_absolute() {
+        // if the input starts with a `/` (or a drive on Windows), we
+        // use it as is, we don't use the replacement_map
         input.to_string()
     } else if tilde.is_match(input) {
         // if the input starts with `~` as first token, we replace
@@ -377,32 +567,125 @@ fn path_from(
         // we put the input behind the source (the selected directory
         // or its parent) and we normalize so that the user can type
         // paths with `../`
-        normalize_path(format!(
+        let path = normalize_path(format!(
             "{}/{}",
             replacement_map.get(source.replacement_map_key()).unwrap(),
             input
-        ))
+        ));
+        if cfg!(windows) {
+            // cmd would take the slashes for the start of options
+            path.replace('/', "\\")
+        } else {
+            path
+        }
+    }
+}
+
+/// build a path from a user input, for a verb execution. For a
+/// shell, the part coming from the selection is escaped, while the
+/// typed input is left as is
+fn exec_path_from(
+    source: PathSource,
+    input: &str,
+    replacement_map: &HashMap<String, String>,
+    for_shell: bool,
+) -> String {
+    if !for_shell {
+        return path_from(source, input, replacement_map);
+    }
+    let key = source.replacement_map_key();
+    let escaped_map: HashMap<String, String> = replacement_map
+        .get(key)
+        .map(|base| (key.to_string(), external::escape_for_shell(Path::new(base))))
+        .into_iter()
+        .collect();
+    path_from(source, input, &escaped_map)
+}
+
+/// build a path from a user input given as argument of a
+/// built-in verb: it's relative to the given directory unless
+/// it's absolute or starts with `~`
+pub fn path_from_directory(directory: &Path, input: &str) -> PathBuf {
+    let mut map = HashMap::new();
+    map.insert(
+        PathSource::Directory.replacement_map_key().to_string(),
+        directory.to_string_lossy().to_string(),
+    );
+    PathBuf::from(path_from(PathSource::Directory, input, &map))
+}
+
+/// the groups computed from the selection, which are escaped when the
+/// execution string is given to a shell. The arguments typed by the user
+/// are left as is.
+const SELECTION_GROUPS: &[&str] = &["file", "parent", "directory", "capture"];
+
+/// build the file:// URI of a path, percent-encoding what's not
+/// allowed in an URI path
+fn file_uri(path: &Path) -> String {
+    let mut path = path.to_string_lossy().to_string();
+    if cfg!(windows) {
+        path = path.replace('\\', "/");
+    }
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/'); // a windows path, like C:/some/dir
+    }
+    for b in path.bytes() {
+        if b.is_ascii_alphanumeric() || b"/-._~:".contains(&b) {
+            uri.push(b as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", b));
+        }
+    }
+    uri
+}
+
+/// make the path relative to the `{directory}` of the selection
+fn relative_to_directory(path: &str, replacement_map: &HashMap<String, String>) -> String {
+    let directory = replacement_map.get("directory").map(Path::new);
+    match directory.and_then(|directory| pathdiff::diff_paths(Path::new(path), directory)) {
+        Some(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+        Some(relative) => relative.to_string_lossy().to_string(),
+        None => path.to_string(),
     }
 }
 
 /// replace a group in the execution string, using
-///  data from the user input and from the selected line
+///  data from the user input and from the selected line.
+///
+/// When the execution is for a shell, the paths of the selection
+///  are escaped, unless a format says otherwise.
 fn do_exec_replacement(
     ec: &Captures<'_>,
     replacement_map: &HashMap<String, String>,
+    for_shell: bool,
 ) -> String {
     let name = ec.get(1).unwrap().as_str();
     if let Some(cap) = replacement_map.get(name) {
         let cap = cap.as_str();
         debug!("do_exec_replacement cap={:?} with {:?}", &cap, ec.get(2));
+        let escape = |s: String| {
+            if for_shell && SELECTION_GROUPS.contains(&name) {
+                external::escape_for_shell(Path::new(&s))
+            } else {
+                s
+            }
+        };
         if let Some(fmt) = ec.get(2) {
             match fmt.as_str() {
-                "path-from-directory" => path_from(PathSource::Directory, cap, replacement_map),
-                "path-from-parent" => path_from(PathSource::Parent, cap, replacement_map),
+                "path-from-directory" => {
+                    exec_path_from(PathSource::Directory, cap, replacement_map, for_shell)
+                }
+                "path-from-parent" => {
+                    exec_path_from(PathSource::Parent, cap, replacement_map, for_shell)
+                }
+                "quoted" => external::quote_for_shell(Path::new(cap)),
+                "uri" => file_uri(Path::new(cap)),
+                "relative" => escape(relative_to_directory(cap, replacement_map)),
                 _ => format!("invalid format: {:?}", fmt.as_str()),
             }
         } else {
-            cap.to_string()
+            escape(cap.to_string())
         }
     } else {
         format!("{{{}}}", name)
@@ -451,3 +734,53 @@ mod path_normalize_tests {
         );
     }
 }
+
+#[cfg(all(test, unix))]
+mod exec_replacement_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_formats() {
+        assert_eq!(file_uri(Path::new("/home/me/a b#1.txt")), "file:///home/me/a%20b%231.txt");
+        let mut map = HashMap::new();
+        map.insert("directory".to_string(), "/home/me".to_string());
+        assert_eq!(relative_to_directory("/home/me/dev/a.rs", &map), "dev/a.rs");
+        assert_eq!(relative_to_directory("/home/me", &map), ".");
+        assert_eq!(relative_to_directory("/home/other", &map), "../other");
+    }
+}
+
+#[cfg(test)]
+mod verb_args_tests {
+
+    use crate::verbs::{Verb, VerbArg};
+
+    #[test]
+    fn check_args() {
+        let mut verb = Verb::create_builtin("cp {newpath=backup} {mode}", None, None, "copy");
+        let newpath = VerbArg {
+            name: "newpath".to_string(),
+            label: None,
+            default: Some("backup".to_string()),
+        };
+        let mode = VerbArg {
+            name: "mode".to_string(),
+            label: Some("copy mode".to_string()),
+            default: None,
+        };
+        assert_eq!(verb.args(), vec![newpath.clone(), VerbArg { label: None, ..mode.clone() }]);
+        let values = vec!["a.rs".to_string(), "fast".to_string()];
+        assert_eq!(verb.fill_args(&verb.args(), &values), "a.rs fast");
+        // the prompts change the order in which the arguments are asked
+        verb.set_prompts(vec![mode.clone()]).unwrap();
+        let prompt_args = verb.prompt_args();
+        assert_eq!(prompt_args, vec![mode.clone(), newpath]);
+        let values = vec!["fast".to_string(), "a.rs".to_string()];
+        assert_eq!(verb.fill_args(&prompt_args, &values), "a.rs fast");
+        let unknown = VerbArg { name: "target".to_string(), ..mode };
+        assert!(verb.set_prompts(vec![unknown]).is_err());
+        let verb = Verb::create_builtin("quit", None, None, "quit");
+        assert!(verb.args().is_empty());
+    }
+}